@@ -0,0 +1,210 @@
+'use strict';
+
+const SESSION_PATH = '/api/admin/session';
+const statusLine = document.getElementById('status');
+
+// The session cookie is HttpOnly; scripts only see the CSRF token
+let csrfToken = '';
+
+async function api(path, options = {}) {
+  const headers = { ...(options.headers || {}), 'X-CSRF-Token': csrfToken };
+  const response = await fetch(path, { ...options, headers, credentials: 'same-origin' });
+  if (!response.ok) {
+    const body = await response.json().catch(() => ({}));
+    throw new Error(`${path}: ${body.error || response.status}`);
+  }
+  return response.status === 204 ? null : response.json();
+}
+
+function showSession(active) {
+  document.getElementById('token-form').hidden = active;
+  document.getElementById('logout').hidden = !active;
+}
+
+function fillTable(id, rows) {
+  const tbody = document.querySelector(`#${id} tbody`);
+  tbody.replaceChildren(
+    ...rows.map(({ cells, onClick }) => {
+      const tr = document.createElement('tr');
+      for (const cell of cells) {
+        const td = document.createElement('td');
+        td.textContent = cell ?? '';
+        tr.appendChild(td);
+      }
+      if (onClick) {
+        tr.addEventListener('click', () => onClick(tr));
+      }
+      return tr;
+    }),
+  );
+}
+
+function report(error) {
+  statusLine.textContent = error ? error.message : '';
+}
+
+async function loadParticipants(event, row) {
+  document.querySelectorAll('#events tr.selected').forEach((tr) => tr.classList.remove('selected'));
+  row.classList.add('selected');
+  document.getElementById('participants-for').textContent = `for ${event.title}`;
+
+  const participants = await api(`/api/events/${event.id}/participants`);
+  fillTable(
+    'participants',
+    participants.map((p) => ({ cells: [p.name, p.email, p.status, p.registered_at] })),
+  );
+}
+
+async function loadEvents() {
+  const events = await api('/api/events');
+  fillTable(
+    'events',
+    events.map((event) => ({
+      cells: [event.title, event.start_time, event.max_participants ?? '∞', event.frozen ? 'yes' : ''],
+      onClick: (row) => loadParticipants(event, row).catch(report),
+    })),
+  );
+}
+
+async function loadCache() {
+  const stats = await api('/api/admin/cache');
+  fillTable(
+    'cache',
+    Object.entries(stats).map(([name, entries]) => ({ cells: [name, entries] })),
+  );
+}
+
+async function loadBackups() {
+  const backups = await api('/api/admin/backups');
+  fillTable(
+    'backups',
+    backups.map((b) => ({ cells: [b.file_name, `${(b.size_bytes / 1024).toFixed(1)} KiB`, b.created_at] })),
+  );
+}
+
+function formatBytes(bytes) {
+  return bytes == null ? '' : `${(bytes / 1024 / 1024).toFixed(1)} MiB`;
+}
+
+async function loadStorage() {
+  const usage = await api('/api/admin/storage');
+  const rows = [
+    ['Database', formatBytes(usage.database_bytes)],
+    ['Backups', formatBytes(usage.backups_bytes)],
+    ['Exports', formatBytes(usage.exports_bytes)],
+    ['Other', formatBytes(usage.other_bytes)],
+    ['Total', formatBytes(usage.total_bytes)],
+  ];
+  if (usage.volume_bytes != null) {
+    const warning = usage.near_capacity ? ' (near capacity)' : '';
+    rows.push(['Volume', `${formatBytes(usage.volume_bytes)}, ${usage.used_percent}% used${warning}`]);
+  }
+  fillTable('storage', rows.map((cells) => ({ cells })));
+}
+
+async function loadSystem() {
+  const system = await api('/api/admin/system');
+  const rows = [
+    ['Version', system.version],
+    ['Instance', `${system.instance_id} (${system.phase})`],
+    ['Uptime', `${Math.floor(system.uptime_secs / 60)} min`],
+    ['Schema', `v${system.schema_version} of v${system.latest_schema_version}`],
+    ...Object.entries(system.participants_by_status).map(([status, count]) => [`Participants ${status}`, count]),
+    ...Object.entries(system.tables).map(([table, count]) => [`Rows in ${table}`, count]),
+  ];
+  fillTable('system', rows.map((cells) => ({ cells })));
+}
+
+async function loadDevices() {
+  const devices = await api('/api/admin/devices');
+  fillTable(
+    'devices',
+    devices.map((d) => ({
+      cells: [d.name, d.event_id, d.last_seen_at ?? 'never', d.pending_sync, d.online ? 'yes' : 'no'],
+    })),
+  );
+}
+
+async function loadMailFailures() {
+  const failures = await api('/api/admin/mail/failures');
+  fillTable(
+    'mail-failures',
+    failures.map((f) => ({ cells: [f.to_address, f.subject, f.error, f.attempts, f.last_failed_at] })),
+  );
+}
+
+async function refresh() {
+  report(null);
+  try {
+    await Promise.all([loadEvents(), loadCache(), loadBackups(), loadSystem(), loadStorage(), loadDevices(), loadMailFailures()]);
+  } catch (error) {
+    report(error);
+  }
+}
+
+document.getElementById('token-form').addEventListener('submit', async (e) => {
+  e.preventDefault();
+  const input = document.getElementById('token');
+  try {
+    const session = await api(SESSION_PATH, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ token: input.value }),
+    });
+    input.value = '';
+    csrfToken = session.csrf_token;
+    showSession(true);
+    refresh();
+  } catch (error) {
+    report(error);
+  }
+});
+
+document.getElementById('logout').addEventListener('click', async () => {
+  try {
+    await api(SESSION_PATH, { method: 'DELETE' });
+  } catch (error) {
+    report(error);
+  }
+  csrfToken = '';
+  showSession(false);
+});
+
+document.getElementById('backup').addEventListener('click', async () => {
+  try {
+    await api('/api/admin/backups', { method: 'POST' });
+    await Promise.all([loadBackups(), loadStorage()]);
+  } catch (error) {
+    report(error);
+  }
+});
+
+document.getElementById('purge').addEventListener('click', async () => {
+  try {
+    const purged = await api('/api/admin/purge', { method: 'POST' });
+    const total = Object.values(purged).reduce((sum, count) => sum + count, 0);
+    statusLine.textContent = `Purged ${total} expired rows`;
+    await loadSystem();
+  } catch (error) {
+    report(error);
+  }
+});
+
+document.getElementById('retry-mail').addEventListener('click', async () => {
+  try {
+    const result = await api('/api/admin/mail/failures/retry', { method: 'POST' });
+    statusLine.textContent = `Resent ${result.sent} of ${result.retried} mails`;
+    await loadMailFailures();
+  } catch (error) {
+    report(error);
+  }
+});
+
+// Resume an existing session after a reload
+api(SESSION_PATH)
+  .then((session) => {
+    csrfToken = session.csrf_token;
+    showSession(true);
+    refresh();
+  })
+  .catch(() => showSession(false));
@@ -0,0 +1,138 @@
+//! Benchmarks for the hottest endpoints against a seeded database.
+//!
+//! Run with `cargo bench --bench hot_endpoints`. The database is seeded once
+//! with 10k events and 100k participants so pagination, cache and index
+//! changes can be compared against a realistic data volume.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+use backend::auth::IsAdmin;
+use backend::json::JsonBody;
+use backend::models::CreateParticipant;
+use backend::routes::{events, participants};
+use backend::{testing, AppState};
+
+const EVENT_COUNT: usize = 10_000;
+const PARTICIPANTS_PER_EVENT: usize = 10;
+
+/// Seed the database with events and participants in a single transaction
+async fn seed(state: &AppState) -> Vec<Uuid> {
+    let mut tx = state.db_pool.begin().await.unwrap();
+    let now = chrono::Utc::now();
+    let mut event_ids = Vec::with_capacity(EVENT_COUNT);
+
+    for i in 0..EVENT_COUNT {
+        let id = Uuid::new_v4();
+        let start = now + chrono::Duration::hours(i as i64);
+        sqlx::query(
+            "INSERT INTO events (id, title, description, start_time, end_time, location, max_participants, created_at, updated_at)
+             VALUES (?, ?, NULL, ?, ?, NULL, NULL, ?, ?)",
+        )
+        .bind(id)
+        .bind(format!("Event {}", i))
+        .bind(start)
+        .bind(start + chrono::Duration::hours(2))
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+        for j in 0..PARTICIPANTS_PER_EVENT {
+            sqlx::query(
+                "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at)
+                 VALUES (?, ?, ?, ?, 'registered', ?, ?)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(id)
+            .bind(format!("Participant {}", j))
+            .bind(format!("p{}-{}@example.com", i, j))
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+
+        event_ids.push(id);
+    }
+
+    tx.commit().await.unwrap();
+    event_ids
+}
+
+fn hot_endpoints(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (state, event_ids, _temp_dir) = rt.block_on(async {
+        let (state, temp_dir) = testing::create_test_state().await;
+        let event_ids = seed(&state).await;
+        (state, event_ids, temp_dir)
+    });
+
+    let mut group = c.benchmark_group("hot_endpoints");
+    group.sample_size(20);
+
+    group.bench_function("list_events/uncached", |b| {
+        b.to_async(&rt).iter(|| async {
+            state.cache.events_list.invalidate_all();
+            events::list_events(State(state.clone()), Query(Default::default()), None, HeaderMap::new()).await.unwrap()
+        })
+    });
+
+    group.bench_function("list_events/cached", |b| {
+        b.to_async(&rt).iter(|| async {
+            events::list_events(State(state.clone()), Query(Default::default()), None, HeaderMap::new()).await.unwrap()
+        })
+    });
+
+    let target = event_ids[EVENT_COUNT / 2];
+
+    group.bench_function("get_event/uncached", |b| {
+        b.to_async(&rt).iter(|| async {
+            state.cache.event.invalidate_all();
+            events::get_event(State(state.clone()), Path(target)).await.unwrap()
+        })
+    });
+
+    group.bench_function("get_event/cached", |b| {
+        b.to_async(&rt).iter(|| async {
+            events::get_event(State(state.clone()), Path(target)).await.unwrap()
+        })
+    });
+
+    let counter = AtomicU64::new(0);
+    group.bench_function("create_participant", |b| {
+        b.to_async(&rt).iter(|| async {
+            let n = counter.fetch_add(1, Ordering::Relaxed);
+            let payload = CreateParticipant {
+                event_id: target,
+                name: format!("Bench {}", n),
+                email: format!("bench-{}@example.com", n),
+                privacy_policy_version: "2024-01".to_string(),
+                marketing_opt_in: false,
+                source: None,
+                referrer_code: None,
+                invited_by: None,
+                metadata: Default::default(),
+                utm_campaign: None,
+                date_of_birth: None,
+                reminders: None,
+                answers: Default::default(),
+            };
+            participants::create_participant(State(state.clone()), IsAdmin(false), JsonBody(payload))
+                .await
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, hot_endpoints);
+criterion_main!(benches);
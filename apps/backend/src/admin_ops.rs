@@ -0,0 +1,209 @@
+//! Operator tasks that otherwise need sqlite3 on the volume.
+//!
+//! Admins can search participants across all events, purge data that is
+//! only kept until it expires, and inspect the state of the database and
+//! of the instance answering. All of it lives under `/api/admin` and needs
+//! the admin token.
+//!
+//! Nothing in the schema is soft-deleted: deleting an event or participant
+//! removes the rows. What a purge removes is the expired leftovers that the
+//! background jobs and request handlers otherwise clean up lazily.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::db::{self, DbPool};
+use crate::encryption::PiiText;
+use crate::instance::Phase;
+use crate::models::ParticipantStatus;
+use crate::routes::participants::{MAX_PAGE_SIZE, MAX_SEARCH_LEN};
+use crate::{audit, confirmation, export, export_jobs, migrations, session, settings};
+
+/// Participants per page when no limit is given
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Tables not in exports whose size is still worth watching
+const TRANSIENT_TABLES: &[&str] = &[
+    "admin_sessions",
+    "confirmation_tokens",
+    "change_notifications",
+    "export_jobs",
+    "instances",
+    "devices",
+];
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ParticipantSearch {
+    pub event_id: Option<Uuid>,
+    pub status: Option<ParticipantStatus>,
+    /// Case-insensitive substring of the name or email
+    #[validate(length(max = MAX_SEARCH_LEN, code = "too_long", message = "must be at most 100 characters"))]
+    pub search: Option<String>,
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "out_of_range", message = "must be between 1 and 200"))]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+/// A participant with the event they registered for
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AdminParticipant {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub event_title: String,
+    #[sqlx(try_from = "PiiText")]
+    pub name: String,
+    #[sqlx(try_from = "PiiText")]
+    pub email: String,
+    pub status: ParticipantStatus,
+    pub registered_at: DateTime<Utc>,
+    pub checked_in_at: Option<DateTime<Utc>>,
+}
+
+/// Rows removed by a purge, per kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    pub admin_sessions: u64,
+    pub confirmation_tokens: u64,
+    pub export_jobs: u64,
+    /// Past the retention policy
+    pub change_notifications: u64,
+    /// Past the retention policy
+    pub audit_entries: u64,
+}
+
+/// What an operator would otherwise look up on the volume
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemState {
+    /// Version of this build
+    pub version: &'static str,
+    pub instance_id: String,
+    pub phase: Phase,
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: i64,
+    pub schema_version: i64,
+    /// Newest migration this build knows
+    pub latest_schema_version: i64,
+    /// Rows per table
+    pub tables: BTreeMap<&'static str, i64>,
+    /// Participants of all events per status
+    pub participants_by_status: BTreeMap<String, i64>,
+}
+
+/// Participants of all events matching `query`, most recently registered
+/// first, and the number of matches across pages. Names and emails may be
+/// sealed (see `encryption`), so a search filters the rows after reading
+/// them.
+pub async fn search_participants(
+    pool: &DbPool,
+    query: &ParticipantSearch,
+) -> Result<(Vec<AdminParticipant>, i64), sqlx::Error> {
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase);
+    let filter = "WHERE (?1 IS NULL OR p.event_id = ?1)
+           AND (?2 IS NULL OR p.status = ?2)";
+    let select = format!(
+        "SELECT p.id, p.event_id, e.title AS event_title, p.name, p.email, p.status,
+                p.registered_at, p.checked_in_at
+         FROM participants p JOIN events e ON e.id = p.event_id
+         {}
+         ORDER BY p.registered_at DESC, p.id",
+        filter
+    );
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    if let Some(search) = search {
+        let matches: Vec<AdminParticipant> = sqlx::query_as::<_, AdminParticipant>(&select)
+            .bind(query.event_id)
+            .bind(query.status.as_ref())
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .filter(|p| p.name.to_lowercase().contains(&search) || p.email.to_lowercase().contains(&search))
+            .collect();
+
+        let total = matches.len() as i64;
+        let participants = matches
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(limit as usize)
+            .collect();
+        return Ok((participants, total));
+    }
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM participants p {}", filter))
+        .bind(query.event_id)
+        .bind(query.status.as_ref())
+        .fetch_one(pool)
+        .await?;
+
+    let participants = sqlx::query_as::<_, AdminParticipant>(&format!("{} LIMIT ?3 OFFSET ?4", select))
+        .bind(query.event_id)
+        .bind(query.status.as_ref())
+        .bind(limit)
+        .bind(query.offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok((participants, total))
+}
+
+/// Delete expired sessions, tokens and export jobs, and what the retention
+/// policy no longer keeps
+pub async fn purge(pool: &DbPool, data_dir: &str, now: DateTime<Utc>) -> Result<PurgeReport, std::io::Error> {
+    let policy = settings::retention(pool).await.map_err(std::io::Error::other)?;
+
+    Ok(PurgeReport {
+        admin_sessions: session::prune(pool, now).await.map_err(std::io::Error::other)?,
+        confirmation_tokens: confirmation::prune(pool, now).await.map_err(std::io::Error::other)?,
+        export_jobs: export_jobs::remove_expired(pool, data_dir, now).await?,
+        change_notifications: db::cleanup_notifications(pool, now - policy.notification_retention())
+            .await
+            .map_err(std::io::Error::other)?,
+        audit_entries: audit::cleanup(pool, now - policy.audit_retention())
+            .await
+            .map_err(std::io::Error::other)?,
+    })
+}
+
+/// Row counts and schema version of the database, and the identity of the
+/// instance answering
+pub async fn system_state(state: &crate::AppState) -> Result<SystemState, sqlx::Error> {
+    let pool = &state.db_pool;
+    let mut tables = BTreeMap::new();
+    for table in export::TABLES.iter().chain(TRANSIENT_TABLES) {
+        let count = sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM {}", table))
+            .fetch_one(pool)
+            .await?;
+        tables.insert(*table, count);
+    }
+
+    let participants_by_status = sqlx::query_as::<_, (String, i64)>(
+        "SELECT status, count(*) FROM participants GROUP BY status"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let started_at = state.instance.started_at();
+    Ok(SystemState {
+        version: env!("CARGO_PKG_VERSION"),
+        instance_id: state.instance.id().to_string(),
+        phase: state.instance.phase(),
+        started_at,
+        uptime_secs: (state.clock.now() - started_at).num_seconds(),
+        schema_version: migrations::current_version(pool).await?,
+        latest_schema_version: migrations::MIGRATIONS.last().map_or(0, |m| m.version),
+        tables,
+        participants_by_status,
+    })
+}
@@ -0,0 +1,38 @@
+//! Minimal operator panel compiled into the binary.
+//!
+//! The files under `admin/` are embedded at build time and served at
+//! `/admin`. The panel is static; it exchanges the operator's admin token
+//! for a session cookie (see `session`) and then talks to the regular JSON
+//! API, so serving the assets themselves needs no authentication.
+
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "admin/"]
+struct Assets;
+
+fn asset(path: &str) -> Response {
+    match Assets::get(path) {
+        Some(file) => (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data.into_owned(),
+        )
+            .into_response(),
+        None => crate::error::api_error(StatusCode::NOT_FOUND, "Route not found").into_response(),
+    }
+}
+
+/// The panel's entry page
+pub async fn index() -> Response {
+    asset("index.html")
+}
+
+/// Scripts and styles referenced by the entry page
+pub async fn static_asset(Path(path): Path<String>) -> Response {
+    asset(&path)
+}
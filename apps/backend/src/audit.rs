@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Sqlite};
+
+/// A recorded administrative or destructive action
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub actor: String,
+    pub details: sqlx::types::Json<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Who performed an audited action
+pub fn actor_label(is_admin: bool) -> &'static str {
+    if is_admin {
+        "admin"
+    } else {
+        "anonymous"
+    }
+}
+
+/// Append an entry to the audit log. Accepts a pool or a transaction so
+/// the entry can be written atomically with the change it describes.
+pub async fn record<'e, E>(
+    executor: E,
+    action: &str,
+    entity_type: &str,
+    entity_id: &str,
+    actor: &str,
+    details: &serde_json::Value,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO audit_log (action, entity_type, entity_id, actor, details, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(action)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(actor)
+    .bind(details.to_string())
+    .bind(now)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Delete audit entries created before `cutoff`
+pub async fn cleanup(pool: &crate::db::DbPool, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM audit_log WHERE created_at < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Entries about an event, or carrying its id in their details, most
+/// recent first
+pub async fn for_event(pool: &crate::db::DbPool, event_id: &str, limit: i64) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, action, entity_type, entity_id, actor, details, created_at
+         FROM audit_log
+         WHERE (entity_type = 'event' AND entity_id = ?1) OR json_extract(details, '$.event_id') = ?1
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?2"
+    )
+    .bind(event_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,84 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap, StatusCode},
+};
+use std::convert::Infallible;
+
+use crate::error::{api_error, ApiError};
+use crate::impersonation::Impersonation;
+use crate::session::AdminSession;
+use crate::AppState;
+
+/// Header carrying the admin secret
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Compare two secrets without short-circuiting on the first mismatch
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Whether the headers carry the configured admin token
+pub fn has_admin_token(headers: &HeaderMap, state: &AppState) -> bool {
+    let Some(expected) = state.config.admin_token.as_deref() else {
+        return false;
+    };
+
+    headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|provided| constant_time_eq(provided.as_bytes(), expected.as_bytes()))
+}
+
+/// Whether the request acts with admin privileges, by admin token or
+/// admin panel session. Impersonated requests carry the admin token but
+/// act as the organizer, so they do not.
+pub fn is_admin(parts: &Parts, state: &AppState) -> bool {
+    parts.extensions.get::<Impersonation>().is_none()
+        && (parts.extensions.get::<AdminSession>().is_some() || has_admin_token(&parts.headers, state))
+}
+
+/// Extractor reporting whether the caller is an admin; never rejects
+#[derive(Debug, Clone, Copy)]
+pub struct IsAdmin(pub bool);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IsAdmin
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+        Ok(IsAdmin(is_admin(parts, &state)))
+    }
+}
+
+/// Extractor for admin-only handlers; rejects non-admins with 403
+#[derive(Debug, Clone, Copy)]
+pub struct RequireAdmin;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireAdmin
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+        if is_admin(parts, &state) {
+            Ok(RequireAdmin)
+        } else if parts.extensions.get::<Impersonation>().is_some() {
+            Err(api_error(StatusCode::FORBIDDEN, "Admin endpoints are unavailable while impersonating"))
+        } else {
+            Err(api_error(StatusCode::FORBIDDEN, "Admin token required"))
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Point-in-time copies of the SQLite database.
+//!
+//! Backups are written with `VACUUM INTO`, which produces a consistent,
+//! compacted copy without blocking writers for long, into
+//! `<DATA_DIR>/backups/` on the same volume.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::db::DbPool;
+
+/// A backup file on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+fn backup_dir(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join("backups")
+}
+
+/// Write a new backup and describe it
+pub async fn create(pool: &DbPool, data_dir: &str, now: DateTime<Utc>) -> Result<BackupInfo, std::io::Error> {
+    let dir = backup_dir(data_dir);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let file_name = format!("backup-{}.db", now.format("%Y%m%dT%H%M%S%.3fZ"));
+    let path = dir.join(&file_name);
+    let target = path
+        .to_str()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "non UTF-8 backup path"))?;
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(target)
+        .execute(pool)
+        .await
+        .map_err(std::io::Error::other)?;
+
+    let metadata = tokio::fs::metadata(&path).await?;
+    Ok(BackupInfo {
+        file_name,
+        size_bytes: metadata.len(),
+        created_at: Some(now),
+    })
+}
+
+/// Existing backups, newest first
+pub async fn list(data_dir: &str) -> Result<Vec<BackupInfo>, std::io::Error> {
+    let mut entries = match tokio::fs::read_dir(backup_dir(data_dir)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut backups = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !file_name.ends_with(".db") {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        backups.push(BackupInfo {
+            file_name,
+            size_bytes: metadata.len(),
+            created_at: metadata.modified().ok().map(DateTime::<Utc>::from),
+        });
+    }
+
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
@@ -0,0 +1,58 @@
+//! Request body size limit.
+//!
+//! Requests declaring a `Content-Length` above MAX_BODY_KB are answered
+//! with a structured 413 before anything reads the body. Bodies sent
+//! without a length are cut off at the same size when they are buffered.
+//! Uploads listed in `UPLOAD_LIMITS` have larger limits of their own.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::export;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Routes accepting bodies above the configured limit, and their limits
+const UPLOAD_LIMITS: &[(&str, usize)] = &[("/api/admin/import/full", export::MAX_IMPORT_BYTES)];
+
+/// Largest body accepted for `path`
+pub fn limit_for(config: &Config, path: &str) -> usize {
+    UPLOAD_LIMITS
+        .iter()
+        .find(|(upload, _)| *upload == path)
+        .map_or(config.max_body_bytes, |(_, limit)| *limit)
+}
+
+/// 413 response naming the limit
+pub fn too_large(limit: usize) -> ApiError {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(json!({ "error": "Request body too large", "limit_bytes": limit })),
+    )
+}
+
+/// Middleware rejecting requests whose declared length exceeds the limit
+pub async fn limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let limit = limit_for(&state.config, request.uri().path());
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+
+    if declared.is_some_and(|length| length > limit as u64) {
+        tracing::debug!("Rejecting {} byte body for {}", declared.unwrap_or_default(), request.uri().path());
+        return too_large(limit).into_response();
+    }
+
+    next.run(request).await
+}
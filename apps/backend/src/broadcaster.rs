@@ -2,13 +2,105 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::debug;
+use uuid::Uuid;
+
+/// Notification channel, named in `change_notifications.channel` and as the
+/// SSE event type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Channel {
+    EventChanges,
+    ParticipantChanges,
+    System,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::EventChanges, Channel::ParticipantChanges, Channel::System];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::EventChanges => "event_changes",
+            Self::ParticipantChanges => "participant_changes",
+            Self::System => "system",
+        }
+    }
+
+    /// Position in `ALL`, for per-channel counters
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::str::FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|channel| channel.as_str() == s)
+            .ok_or_else(|| format!("unknown notification channel '{}'", s))
+    }
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerEvent {
-    pub channel: String,
+    /// Id of the `change_notifications` row, sent as the SSE event id
+    pub id: i64,
+    pub channel: Channel,
     pub payload: String,
 }
 
+/// The fields of a change payload naming what changed
+#[derive(Debug, Deserialize)]
+struct Subject {
+    table: Option<String>,
+    id: Option<Uuid>,
+    event_id: Option<Uuid>,
+}
+
+impl ServerEvent {
+    /// The event a change concerns: the event itself, or the event of the
+    /// participant. None for series changes and system notices.
+    pub fn event_id(&self) -> Option<Uuid> {
+        let subject: Subject = serde_json::from_str(&self.payload).ok()?;
+        match (self.channel, subject.table.as_deref()) {
+            (Channel::EventChanges, Some("events")) => subject.id,
+            (Channel::ParticipantChanges, _) => subject.event_id,
+            _ => None,
+        }
+    }
+}
+
+/// What an SSE client asked to be sent, from the stream's query string;
+/// the default lets everything through
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Topic {
+    /// Only notifications on this channel
+    pub channel: Option<Channel>,
+    /// Only changes to this event and its participants. System notices
+    /// concern every page and are sent regardless.
+    pub event_id: Option<Uuid>,
+}
+
+impl Topic {
+    pub fn matches(&self, event: &ServerEvent) -> bool {
+        if self.channel.is_some_and(|channel| channel != event.channel) {
+            return false;
+        }
+        match self.event_id {
+            Some(event_id) if event.channel != Channel::System => event.event_id() == Some(event_id),
+            _ => true,
+        }
+    }
+}
+
 /// Broadcaster for Server-Sent Events
 #[derive(Clone)]
 pub struct Broadcaster {
@@ -0,0 +1,297 @@
+//! Organizer mails to the participants of an event.
+//!
+//! `POST /api/events/:id/participants/email` renders the subject and body,
+//! which may use the email template placeholders, once per participant
+//! whose status matches the filter and queues the results. The sender mails
+//! the queue in order, at most MAIL_BULK_PER_MINUTE mails a minute so a
+//! large event does not trip the relay's rate limits, and records the
+//! outcome of every send for the report at
+//! `GET /api/events/:id/participants/email/:bulk_id`. Mails that fail are
+//! also stored as mail failures and can be retried from there.
+//!
+//! A recipient is claimed before it is mailed, so instances sharing the
+//! database never send the same mail twice.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection};
+use std::time::Duration;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::email_templates::{self, Values};
+use crate::encryption::{self, PiiText};
+use crate::mail_failures;
+use crate::mailer::OutgoingMail;
+use crate::models::{Event, Participant, ParticipantStatus};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// How long the sender waits before looking at an empty queue again
+const IDLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where a queued mail is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SendState {
+    Queued,
+    Sending,
+    Sent,
+    Failed,
+}
+
+/// Outcome of the mail to one participant
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RecipientOutcome {
+    pub participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    pub to_address: String,
+    pub state: SendState,
+    /// Error of the failed send
+    pub error: Option<String>,
+    pub attempted_at: Option<DateTime<Utc>>,
+}
+
+/// A bulk mail with the outcome of every send
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkMailReport {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub subject: String,
+    /// Participant statuses the mail went to
+    pub statuses: Vec<ParticipantStatus>,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    /// Set once every recipient was attempted
+    pub completed_at: Option<DateTime<Utc>>,
+    pub queued: usize,
+    pub sent: usize,
+    pub failed: usize,
+    pub recipients: Vec<RecipientOutcome>,
+}
+
+#[derive(FromRow)]
+struct BulkMailRow {
+    id: Uuid,
+    event_id: Uuid,
+    subject: String,
+    #[sqlx(json)]
+    statuses: Vec<ParticipantStatus>,
+    requested_by: String,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+/// Statuses mailed when the request names none
+pub fn default_statuses() -> Vec<ParticipantStatus> {
+    vec![ParticipantStatus::Registered, ParticipantStatus::Confirmed, ParticipantStatus::CheckedIn]
+}
+
+/// A request for a bulk mail
+pub struct BulkMail<'a> {
+    pub id: Uuid,
+    pub event: &'a Event,
+    pub subject: &'a str,
+    pub body: &'a str,
+    pub statuses: &'a [ParticipantStatus],
+    pub requested_by: &'a str,
+}
+
+/// Queue one rendered mail per participant whose status is in
+/// `mail.statuses`; returns how many were queued. Run it in a transaction
+/// so the mail and its recipients appear together.
+pub async fn enqueue(
+    conn: &mut SqliteConnection,
+    mail: &BulkMail<'_>,
+    participants: &[Participant],
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO bulk_mails (id, event_id, subject, body, statuses, requested_by, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(mail.id)
+    .bind(mail.event.id)
+    .bind(mail.subject)
+    .bind(mail.body)
+    .bind(serde_json::to_string(mail.statuses).expect("statuses serialize"))
+    .bind(mail.requested_by)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    let mut queued = 0;
+    for participant in participants.iter().filter(|p| mail.statuses.contains(&p.status)) {
+        let values = Values::new(mail.event, participant);
+        sqlx::query(
+            "INSERT INTO bulk_mail_recipients (bulk_mail_id, participant_id, to_name, to_address, to_address_index, subject, text)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(mail.id)
+        .bind(participant.id)
+        .bind(PiiText(participant.name.clone()))
+        .bind(PiiText(participant.email.clone()))
+        .bind(encryption::blind_index(&participant.email))
+        .bind(email_templates::render(mail.subject, &values))
+        .bind(email_templates::render(mail.body, &values))
+        .execute(&mut *conn)
+        .await?;
+        queued += 1;
+    }
+
+    if queued == 0 {
+        sqlx::query("UPDATE bulk_mails SET completed_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(mail.id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(queued)
+}
+
+/// A bulk mail of `event_id` and the outcome of its sends so far
+pub async fn report(pool: &DbPool, event_id: Uuid, id: Uuid) -> Result<Option<BulkMailReport>, sqlx::Error> {
+    let Some(row) = sqlx::query_as::<_, BulkMailRow>(
+        "SELECT id, event_id, subject, statuses, requested_by, created_at, completed_at
+         FROM bulk_mails
+         WHERE id = ? AND event_id = ?"
+    )
+    .bind(id)
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let recipients = sqlx::query_as::<_, RecipientOutcome>(
+        "SELECT participant_id, to_address, state, error, attempted_at
+         FROM bulk_mail_recipients
+         WHERE bulk_mail_id = ?
+         ORDER BY rowid"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    let count = |state: SendState| recipients.iter().filter(|r| r.state == state).count();
+    Ok(Some(BulkMailReport {
+        id: row.id,
+        event_id: row.event_id,
+        subject: row.subject,
+        statuses: row.statuses,
+        requested_by: row.requested_by,
+        created_at: row.created_at,
+        completed_at: row.completed_at,
+        queued: count(SendState::Queued) + count(SendState::Sending),
+        sent: count(SendState::Sent),
+        failed: count(SendState::Failed),
+        recipients,
+    }))
+}
+
+#[derive(FromRow)]
+struct Claimed {
+    bulk_mail_id: Uuid,
+    participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    to_name: String,
+    #[sqlx(try_from = "PiiText")]
+    to_address: String,
+    subject: String,
+    text: String,
+}
+
+/// Claim the oldest queued mail; None when the queue is empty or another
+/// instance got there first
+async fn claim(pool: &DbPool, now: DateTime<Utc>) -> Result<Option<Claimed>, sqlx::Error> {
+    sqlx::query_as::<_, Claimed>(
+        "UPDATE bulk_mail_recipients
+         SET state = 'sending', attempted_at = ?
+         WHERE rowid = (SELECT rowid FROM bulk_mail_recipients WHERE state = 'queued' ORDER BY rowid LIMIT 1)
+           AND state = 'queued'
+         RETURNING bulk_mail_id, participant_id, to_name, to_address, subject, text"
+    )
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record the outcome of a send and complete its bulk mail after the last
+async fn finish(pool: &DbPool, claimed: &Claimed, error: Option<String>, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    let state = if error.is_some() { SendState::Failed } else { SendState::Sent };
+    sqlx::query(
+        "UPDATE bulk_mail_recipients SET state = ?, error = ?, attempted_at = ?
+         WHERE bulk_mail_id = ? AND participant_id = ?"
+    )
+    .bind(state)
+    .bind(error)
+    .bind(now)
+    .bind(claimed.bulk_mail_id)
+    .bind(claimed.participant_id)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "UPDATE bulk_mails SET completed_at = ?
+         WHERE id = ? AND completed_at IS NULL
+           AND NOT EXISTS (
+               SELECT 1 FROM bulk_mail_recipients
+               WHERE bulk_mail_id = bulk_mails.id AND state IN ('queued', 'sending')
+           )"
+    )
+    .bind(now)
+    .bind(claimed.bulk_mail_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Mail up to `limit` queued mails without pausing; returns how many were
+/// attempted
+pub async fn send_queued(state: &AppState, limit: usize) -> Result<usize, sqlx::Error> {
+    let mut attempted = 0;
+
+    while attempted < limit {
+        let Some(claimed) = claim(&state.db_pool, state.clock.now()).await? else {
+            break;
+        };
+        let mail = OutgoingMail {
+            to_name: claimed.to_name.clone(),
+            to_address: claimed.to_address.clone(),
+            subject: claimed.subject.clone(),
+            text: claimed.text.clone(),
+            attachments: Vec::new(),
+        };
+        let error = mail_failures::deliver(state, &mail).await.err().map(|e| {
+            error!("Failed to send bulk mail to {}: {}", mail.to_address, e);
+            e.0
+        });
+        finish(&state.db_pool, &claimed, error, state.clock.now()).await?;
+        attempted += 1;
+    }
+
+    Ok(attempted)
+}
+
+/// Drain the queue one mail at a time, spaced to MAIL_BULK_PER_MINUTE;
+/// runs on instances with background jobs (see `config::Role`)
+pub async fn start_sender(state: AppState) {
+    let spacing = Duration::from_secs(60) / state.config.mail.bulk_per_minute.max(1);
+
+    loop {
+        match send_queued(&state, 1).await {
+            Ok(0) => tokio::time::sleep(IDLE_INTERVAL).await,
+            Ok(_) => tokio::time::sleep(spacing).await,
+            Err(e) => {
+                error!("Failed to send bulk mail: {}", e);
+                tokio::time::sleep(IDLE_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -1,15 +1,133 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
 use moka::future::Cache;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use uuid::Uuid;
 
-use crate::models::{Event, Participant};
+use crate::broadcaster::Channel;
+use crate::maintenance::MaintenanceNotice;
+use crate::models::{Drafts, Event, EventScope, Participant, ParticipantPage, ParticipantStatus};
+use crate::recurrence::SeriesSnapshot;
+use crate::settings::EventListSettings;
+
+/// A JSON response body serialized once and served as is on every hit.
+/// Cloning shares the bytes rather than copying them.
+#[derive(Debug, Clone)]
+pub struct CachedJson {
+    pub body: Bytes,
+    /// Weak, since field naming negotiation may rewrite the body on its
+    /// way out while the content stays the same; `naming` gives the
+    /// camelCase body a tag of its own
+    pub etag: HeaderValue,
+}
+
+impl CachedJson {
+    pub fn new<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        let body = Bytes::from(serde_json::to_vec(value)?);
+        let etag = HeaderValue::from_str(&format!("W/\"{:016x}\"", fnv1a(&body))).expect("hex is a valid header value");
+        Ok(Self { body, etag })
+    }
+
+    /// Whether an `If-None-Match` header in `headers` names this body
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        let tag = |t: &str| t.trim().trim_start_matches("W/").to_string();
+        let ours = tag(self.etag.to_str().unwrap_or_default());
+        headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|t| t.trim() == "*" || tag(t) == ours)
+    }
+
+    /// The cached body, or 304 Not Modified when the client already has it
+    pub fn respond(&self, headers: &HeaderMap) -> Response {
+        if self.matches(headers) {
+            return (StatusCode::NOT_MODIFIED, [(header::ETAG, self.etag.clone())]).into_response();
+        }
+        (
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                (header::ETAG, self.etag.clone()),
+            ],
+            Body::from(self.body.clone()),
+        )
+            .into_response()
+    }
+}
+
+/// 64-bit FNV-1a; only has to tell bodies apart, not resist tampering
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+const TAG_KEY_SEPARATOR: &str = ":tag=";
+
+/// Key of a filtered or paginated participant list of `event_id`. Only
+/// organizers' searches match emails, so that is part of the key; the
+/// search text goes last as it may contain any character.
+pub fn participant_page_key(
+    event_id: Uuid,
+    status: Option<&ParticipantStatus>,
+    search: Option<&str>,
+    searches_emails: bool,
+    offset: u32,
+    limit: Option<u32>,
+) -> String {
+    format!(
+        "{}:status={:?}:emails={}:offset={}:limit={:?}:search={:?}",
+        event_id, status, searches_emails, offset, limit, search
+    )
+}
+
+/// Key of a cached event list, by audience, scope and the tag it is
+/// filtered by
+pub fn events_list_key(drafts: Drafts, scope: EventScope, tag: Option<&str>) -> String {
+    let audience = match drafts {
+        Drafts::None => "all".to_string(),
+        Drafts::All => "with_drafts".to_string(),
+        Drafts::OwnedBy(organizer_id) => format!("drafts_of={}", organizer_id),
+    };
+    match tag {
+        Some(tag) => format!("{}:{}{}{}", audience, scope.as_str(), TAG_KEY_SEPARATOR, tag),
+        None => format!("{}:{}", audience, scope.as_str()),
+    }
+}
+
+/// Invalidations per cached channel, and those forced by reconciliation
+#[derive(Debug, Default)]
+struct Invalidations {
+    per_channel: [AtomicU64; Channel::ALL.len()],
+    forced: AtomicU64,
+}
 
 /// In-memory cache with TTL for events and participants
 #[derive(Clone)]
 pub struct AppCache {
-    pub events_list: Cache<String, Vec<Event>>,
+    /// The serialized event lists, the hottest responses; keyed by
+    /// `events_list_key`
+    pub events_list: Cache<String, CachedJson>,
     pub event: Cache<String, Event>,
+    /// Full participant lists, keyed by event id
     pub participants: Cache<String, Vec<Participant>>,
+    /// Filtered or paginated participant lists, keyed by
+    /// `participant_page_key` so they never answer for the full list
+    pub participant_pages: Cache<String, ParticipantPage>,
     pub participant: Cache<String, Participant>,
+    /// Recurring series with their exclusions and occurrence events
+    pub series: Cache<String, SeriesSnapshot>,
+    pub maintenance: Cache<String, MaintenanceNotice>,
+    pub event_list_settings: Cache<String, EventListSettings>,
+    invalidations: Arc<Invalidations>,
 }
 
 impl AppCache {
@@ -18,7 +136,8 @@ impl AppCache {
         Self {
             events_list: Cache::builder()
                 .time_to_live(ttl)
-                .max_capacity(10)
+                .max_capacity(200)
+                .support_invalidation_closures()
                 .build(),
             event: Cache::builder()
                 .time_to_live(ttl)
@@ -28,10 +147,27 @@ impl AppCache {
                 .time_to_live(ttl)
                 .max_capacity(1000)
                 .build(),
+            participant_pages: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(2000)
+                .build(),
             participant: Cache::builder()
                 .time_to_live(ttl)
                 .max_capacity(5000)
                 .build(),
+            series: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(1000)
+                .build(),
+            maintenance: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(1)
+                .build(),
+            event_list_settings: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(1)
+                .build(),
+            invalidations: Arc::default(),
         }
     }
 
@@ -39,26 +175,86 @@ impl AppCache {
     pub async fn invalidate_events(&self) {
         self.events_list.invalidate_all();
         self.event.invalidate_all();
+        self.series.invalidate_all();
     }
 
-    /// Invalidate caches for a specific event
-    pub async fn invalidate_event(&self, event_id: &str) {
-        self.events_list.invalidate_all();
+    /// Invalidate caches for a specific event tagged `tags`. Series are
+    /// dropped as well, since the event may be one of their occurrences.
+    pub async fn invalidate_event(&self, event_id: &str, tags: &[String]) {
+        self.invalidate_event_lists(tags);
         self.event.remove(event_id).await;
+        self.series.invalidate_all();
+    }
+
+    /// Invalidate the event lists an event tagged `tags` may appear in: the
+    /// unfiltered ones and those filtered by one of its tags
+    pub fn invalidate_event_lists(&self, tags: &[String]) {
+        let tags = tags.to_vec();
+        self.events_list
+            .invalidate_entries_if(move |key, _| match key.split_once(TAG_KEY_SEPARATOR) {
+                Some((_, tag)) => tags.iter().any(|t| t == tag),
+                None => true,
+            })
+            .expect("event list cache supports invalidation closures");
     }
 
     /// Invalidate all participant-related caches
     pub async fn invalidate_participants(&self) {
         self.participants.invalidate_all();
+        self.participant_pages.invalidate_all();
         self.participant.invalidate_all();
     }
 
+    /// Entry count per cache, after flushing pending evictions
+    pub async fn stats(&self) -> BTreeMap<&'static str, u64> {
+        self.events_list.run_pending_tasks().await;
+        self.event.run_pending_tasks().await;
+        self.participants.run_pending_tasks().await;
+        self.participant_pages.run_pending_tasks().await;
+        self.participant.run_pending_tasks().await;
+        self.series.run_pending_tasks().await;
+        self.maintenance.run_pending_tasks().await;
+        self.event_list_settings.run_pending_tasks().await;
+
+        BTreeMap::from([
+            ("events_list", self.events_list.entry_count()),
+            ("event", self.event.entry_count()),
+            ("participants", self.participants.entry_count()),
+            ("participant_pages", self.participant_pages.entry_count()),
+            ("participant", self.participant.entry_count()),
+            ("series", self.series.entry_count()),
+            ("maintenance", self.maintenance.entry_count()),
+            ("event_list_settings", self.event_list_settings.entry_count()),
+        ])
+    }
+
     /// Invalidate caches based on notification channel
-    pub async fn invalidate_for_channel(&self, channel: &str) {
+    pub async fn invalidate_for_channel(&self, channel: Channel) {
         match channel {
-            "event_changes" => self.invalidate_events().await,
-            "participant_changes" => self.invalidate_participants().await,
-            _ => {}
+            Channel::EventChanges => self.invalidate_events().await,
+            Channel::ParticipantChanges => self.invalidate_participants().await,
+            Channel::System => {
+                self.maintenance.invalidate_all();
+                self.event_list_settings.invalidate_all();
+            }
         }
+        self.invalidations.per_channel[channel.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Times the caches of `channel` were invalidated
+    pub fn invalidations(&self, channel: Channel) -> u64 {
+        self.invalidations.per_channel[channel.index()].load(Ordering::Relaxed)
+    }
+
+    /// Invalidate the caches of `channel` for a change no notification
+    /// announced (see `reconcile`)
+    pub async fn force_invalidate(&self, channel: Channel) {
+        self.invalidate_for_channel(channel).await;
+        self.invalidations.forced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Invalidations forced by reconciliation
+    pub fn forced_invalidations(&self) -> u64 {
+        self.invalidations.forced.load(Ordering::Relaxed)
     }
 }
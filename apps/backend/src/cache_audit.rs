@@ -0,0 +1,130 @@
+//! Debug aid for stale-read reports: with `CACHE_AUDIT=true`, every
+//! successful cached GET is followed by a direct database read of the same
+//! resource, and any divergence is logged with the affected entity ids.
+//!
+//! This doubles database load on read paths and can report false positives
+//! when a write lands between the two reads, so it is meant for short
+//! debugging sessions, not production traffic.
+
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::models::Drafts;
+use crate::organizers::EventAuthor;
+use crate::routes::{events, participants};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Largest response body the audit will buffer
+const MAX_AUDITED_BODY: usize = 16 * 1024 * 1024;
+
+/// The `:id` segment of routes shaped like `/api/<resource>/:id[/...]`
+fn path_id(path: &str) -> Option<Uuid> {
+    path.split('/').nth(3)?.parse().ok()
+}
+
+/// Read the resource behind `route` directly from the database
+async fn direct_read(
+    state: &AppState,
+    route: &str,
+    path: &str,
+    drafts: Drafts,
+) -> Result<Option<Value>, sqlx::Error> {
+    let pool = &state.db_pool;
+    let value = match (route, path_id(path)) {
+        ("/api/events", _) => {
+            let scope = events::default_scope(state).await;
+            serde_json::to_value(events::fetch_events(pool, drafts, scope, None, state.clock.now()).await?)
+        }
+        ("/api/events/:id", Some(id)) => serde_json::to_value(events::fetch_event(pool, id).await?),
+        ("/api/events/:id/participants", Some(id)) => {
+            serde_json::to_value(participants::fetch_participants(pool, id).await?)
+        }
+        ("/api/participants/:id", Some(id)) => {
+            serde_json::to_value(participants::fetch_participant(pool, id).await?)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(value.ok())
+}
+
+/// Ids of list entries that are missing, extra or different in `served`
+fn diverging_ids(served: &Value, direct: &Value) -> BTreeSet<String> {
+    let index = |value: &Value| -> BTreeMap<String, Value> {
+        value
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| (item["id"].as_str().unwrap_or_default().to_string(), item.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let (served, direct) = (index(served), index(direct));
+    served
+        .keys()
+        .chain(direct.keys())
+        .filter(|id| served.get(*id) != direct.get(*id))
+        .cloned()
+        .collect()
+}
+
+/// Middleware comparing cached GET responses with a direct database read
+pub async fn cache_audit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !state.config.cache_audit || request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let Some(route) = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string()) else {
+        return next.run(request).await;
+    };
+    // Filtered reads such as `?near=` or `?tag=` are not audited
+    if request.uri().query().is_some() {
+        return next.run(request).await;
+    }
+    let path = request.uri().path().to_string();
+    // Drafts are listed by who is asking, as `list_events` does
+    let (mut parts, body) = request.into_parts();
+    let author = EventAuthor::from_request_parts(&mut parts, &state).await.ok();
+    let drafts = Drafts::visible_to(author.as_ref());
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_AUDITED_BODY).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Cache audit could not buffer {}: {}", path, e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    match (serde_json::from_slice::<Value>(&bytes), direct_read(&state, &route, &path, drafts).await) {
+        (Ok(served), Ok(Some(direct))) if served != direct => {
+            if served.is_array() {
+                warn!(route = %route, path = %path, ids = ?diverging_ids(&served, &direct), "Cache divergence");
+            } else {
+                warn!(route = %route, path = %path, id = %served["id"], "Cache divergence");
+            }
+        }
+        (_, Err(e)) => error!("Cache audit direct read failed for {}: {}", path, e),
+        _ => {}
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,160 @@
+//! Busy and free time of an organizer.
+//!
+//! `GET /api/organizers/:id/availability?from=&to=` lays the events an
+//! organizer owns over a window and answers with busy and free blocks that
+//! cover it end to end, so the scheduling assistant can suggest slots that
+//! clash with nothing when a new event is created. Overlapping and
+//! back-to-back events merge into one busy block. Drafts hold their time,
+//! as they are planned; cancelled events free it.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+
+/// Window covered when `to` is omitted
+pub const DEFAULT_WINDOW_DAYS: i64 = 28;
+/// Longest window one request may cover
+pub const MAX_WINDOW_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    /// Start of the window; now when omitted
+    pub from: Option<DateTime<Utc>>,
+    /// End of the window; DEFAULT_WINDOW_DAYS after `from` when omitted
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Busy,
+    Free,
+}
+
+/// A stretch of the window, clipped to it
+#[derive(Debug, Clone, Serialize)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Events taking up a busy block, by start; empty for free ones
+    pub event_ids: Vec<Uuid>,
+}
+
+/// An organizer's window, as busy and free blocks in order
+#[derive(Debug, Clone, Serialize)]
+pub struct Calendar {
+    pub organizer_id: Uuid,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub blocks: Vec<Block>,
+}
+
+/// The time an event takes up
+#[derive(Debug, Clone, FromRow)]
+pub struct Booking {
+    pub id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// The window asked for, or why it cannot be served
+pub fn window(query: &CalendarQuery, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), &'static str> {
+    let from = query.from.unwrap_or(now);
+    let to = query.to.unwrap_or(from + Duration::days(DEFAULT_WINDOW_DAYS));
+    if to <= from {
+        return Err("to must be after from");
+    }
+    if to - from > Duration::days(MAX_WINDOW_DAYS) {
+        return Err("window must be at most 366 days");
+    }
+    Ok((from, to))
+}
+
+/// Events of `organizer_id` overlapping `from..to` that hold their time,
+/// by start
+pub async fn bookings(
+    pool: &DbPool,
+    organizer_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<Booking>, sqlx::Error> {
+    sqlx::query_as::<_, Booking>(
+        "SELECT id, start_time, end_time FROM events
+         WHERE organizer_id = ?1 AND status != 'cancelled'
+           AND start_time < ?3 AND end_time > ?2
+         ORDER BY start_time, id"
+    )
+    .bind(organizer_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+/// Cover `from..to` with busy blocks for `bookings`, sorted by start, and
+/// free blocks between them
+pub fn blocks(bookings: &[Booking], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Block> {
+    let mut busy: Vec<Block> = Vec::new();
+    for booking in bookings {
+        let start = booking.start_time.max(from);
+        let end = booking.end_time.min(to);
+        if start >= end {
+            continue;
+        }
+        match busy.last_mut() {
+            Some(last) if start <= last.end => {
+                last.end = last.end.max(end);
+                last.event_ids.push(booking.id);
+            }
+            _ => busy.push(Block {
+                kind: BlockKind::Busy,
+                start,
+                end,
+                event_ids: vec![booking.id],
+            }),
+        }
+    }
+
+    let mut blocks = Vec::with_capacity(busy.len() * 2 + 1);
+    let mut cursor = from;
+    for block in busy {
+        if cursor < block.start {
+            blocks.push(free(cursor, block.start));
+        }
+        cursor = block.end;
+        blocks.push(block);
+    }
+    if cursor < to {
+        blocks.push(free(cursor, to));
+    }
+    blocks
+}
+
+fn free(start: DateTime<Utc>, end: DateTime<Utc>) -> Block {
+    Block {
+        kind: BlockKind::Free,
+        start,
+        end,
+        event_ids: Vec::new(),
+    }
+}
+
+/// The calendar of `organizer_id` over `from..to`
+pub async fn build(
+    pool: &DbPool,
+    organizer_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Calendar, sqlx::Error> {
+    let bookings = bookings(pool, organizer_id, from, to).await?;
+    Ok(Calendar {
+        organizer_id,
+        from,
+        to,
+        blocks: blocks(&bookings, from, to),
+    })
+}
@@ -0,0 +1,97 @@
+//! Self-service cancellation links.
+//!
+//! Every registration gets a cancellation token of the form
+//! `<nonce>.<signature>`. The nonce is random and stored with the
+//! participant in `cancellation_tokens`; the signature is an HMAC of the
+//! nonce. `POST /api/registrations/cancel?token=` checks the signature
+//! before looking the nonce up, so forged or mangled tokens never reach the
+//! database, and changing the key revokes every link at once.
+//!
+//! The key is CANCELLATION_SIGNING_KEY. When unset, a random key is created
+//! on first use and kept in `settings`, where every instance finds it and
+//! full exports carry it along. As the signature only depends on the nonce,
+//! tokens can be rebuilt for mails sent after the registration.
+
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use sqlx::Sqlite;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::export::decode_hex;
+use crate::settings;
+
+/// Settings key of the generated signing key
+const KEY_SETTING: &str = "cancellation_signing_key";
+
+/// The signing key: `configured` if set, else the one kept in `settings`,
+/// created by whichever instance needs it first
+pub async fn signing_key(pool: &DbPool, configured: Option<&str>, now: DateTime<Utc>) -> Result<String, sqlx::Error> {
+    if let Some(key) = configured {
+        return Ok(key.to_string());
+    }
+
+    settings::shared_secret(pool, KEY_SETTING, now).await
+}
+
+fn hmac_key(key: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes())
+}
+
+/// The token for `nonce`
+pub fn token(key: &str, nonce: &str) -> String {
+    let signature: String = hmac::sign(&hmac_key(key), nonce.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}.{}", nonce, signature)
+}
+
+/// The nonce of `token` if its signature is valid
+fn verified_nonce<'t>(key: &str, token: &'t str) -> Option<&'t str> {
+    let (nonce, signature) = token.split_once('.')?;
+    let tag = decode_hex(signature)?;
+    hmac::verify(&hmac_key(key), nonce.as_bytes(), &tag).ok()?;
+    Some(nonce)
+}
+
+/// Create the token of a new registration. Accepts a pool or a transaction
+/// so it can be written atomically with the participant.
+pub async fn issue<'e, E>(executor: E, key: &str, participant_id: Uuid, now: DateTime<Utc>) -> Result<String, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let nonce = Uuid::new_v4().simple().to_string();
+    sqlx::query("INSERT INTO cancellation_tokens (nonce, participant_id, created_at) VALUES (?, ?, ?)")
+        .bind(&nonce)
+        .bind(participant_id)
+        .bind(now)
+        .execute(executor)
+        .await?;
+
+    Ok(token(key, &nonce))
+}
+
+/// The token of an existing participant, e.g. for a mail
+pub async fn token_for(pool: &DbPool, key: &str, participant_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+    let nonce = sqlx::query_scalar::<_, String>("SELECT nonce FROM cancellation_tokens WHERE participant_id = ?")
+        .bind(participant_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(nonce.map(|nonce| token(key, &nonce)))
+}
+
+/// The participant `token` cancels; None if it is forged, signed with
+/// another key or belongs to a deleted participant
+pub async fn participant_for(pool: &DbPool, key: &str, token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    let Some(nonce) = verified_nonce(key, token) else {
+        return Ok(None);
+    };
+
+    sqlx::query_scalar::<_, Uuid>("SELECT participant_id FROM cancellation_tokens WHERE nonce = ?")
+        .bind(nonce)
+        .fetch_optional(pool)
+        .await
+}
@@ -0,0 +1,148 @@
+//! Places held back from registration.
+//!
+//! Organizers reserve places of a limited event for sponsors, speakers or
+//! VIPs with a capacity hold of some number of seats. Held places count as
+//! taken: registration, status changes and waitlist promotion all compare
+//! the places held by participants against `max_participants` minus the
+//! seats of the event's holds, and availability reports them separately.
+//!
+//! A hold only reserves places that are free when it is created. Releasing
+//! it returns its places to registration, promoting waitlisted participants
+//! into them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite, SqliteConnection};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::db::DbPool;
+use crate::validation;
+
+/// Longest hold label
+pub const MAX_LABEL_LEN: u64 = 100;
+
+/// Places of an event reserved for invited guests
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CapacityHold {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    /// Who the places are for, e.g. "Sponsors"
+    pub label: String,
+    pub seats: i64,
+    /// Audit actor who created the hold
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateHold {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_LABEL_LEN, code = "out_of_range")
+    )]
+    pub label: String,
+    #[validate(range(min = 1, code = "out_of_range", message = "must be greater than 0"))]
+    pub seats: u32,
+}
+
+/// Outcome of trying to create a hold
+#[derive(Debug)]
+pub enum Created {
+    Hold(CapacityHold),
+    /// The event has no participant limit to hold places of
+    Unlimited,
+    /// Fewer places are free than the hold asks for
+    TooFew { available: i64 },
+}
+
+/// Places reserved across an event's holds
+pub async fn held_seats<'e, E>(executor: E, event_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, i64>("SELECT COALESCE(SUM(seats), 0) FROM capacity_holds WHERE event_id = ?")
+        .bind(event_id)
+        .fetch_one(executor)
+        .await
+}
+
+/// An event's holds, oldest first
+pub async fn list(pool: &DbPool, event_id: Uuid) -> Result<Vec<CapacityHold>, sqlx::Error> {
+    sqlx::query_as::<_, CapacityHold>(
+        "SELECT id, event_id, label, seats, created_by, created_at
+         FROM capacity_holds WHERE event_id = ? ORDER BY created_at, rowid"
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Hold `seats` of the event's free places. The check and the insert are a
+/// single statement, so concurrent registrations cannot take the places in
+/// between.
+pub async fn create(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    event_id: Uuid,
+    hold: &CreateHold,
+    actor: &str,
+    now: DateTime<Utc>,
+) -> Result<Created, sqlx::Error> {
+    let created = sqlx::query_as::<_, CapacityHold>(
+        "INSERT INTO capacity_holds (id, event_id, label, seats, created_by, created_at)
+         SELECT ?1, e.id, ?3, ?4, ?5, ?6
+         FROM events e
+         WHERE e.id = ?2
+           AND e.max_participants
+               - (SELECT count(*) FROM participants p
+                  WHERE p.event_id = e.id AND p.status IN ('registered', 'confirmed', 'checked_in'))
+               - (SELECT COALESCE(SUM(h.seats), 0) FROM capacity_holds h WHERE h.event_id = e.id)
+               >= ?4
+         RETURNING id, event_id, label, seats, created_by, created_at"
+    )
+    .bind(id)
+    .bind(event_id)
+    .bind(hold.label.trim())
+    .bind(hold.seats)
+    .bind(actor)
+    .bind(now)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(hold) = created {
+        return Ok(Created::Hold(hold));
+    }
+
+    let available = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT e.max_participants
+                - (SELECT count(*) FROM participants p
+                   WHERE p.event_id = e.id AND p.status IN ('registered', 'confirmed', 'checked_in'))
+                - (SELECT COALESCE(SUM(h.seats), 0) FROM capacity_holds h WHERE h.event_id = e.id)
+         FROM events e WHERE e.id = ?"
+    )
+    .bind(event_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(match available {
+        None => Created::Unlimited,
+        Some(available) => Created::TooFew { available: available.max(0) },
+    })
+}
+
+/// Delete a hold of `event_id`, returning it if it existed
+pub async fn release(
+    conn: &mut SqliteConnection,
+    event_id: Uuid,
+    id: Uuid,
+) -> Result<Option<CapacityHold>, sqlx::Error> {
+    sqlx::query_as::<_, CapacityHold>(
+        "DELETE FROM capacity_holds WHERE id = ? AND event_id = ?
+         RETURNING id, event_id, label, seats, created_by, created_at"
+    )
+    .bind(id)
+    .bind(event_id)
+    .fetch_optional(&mut *conn)
+    .await
+}
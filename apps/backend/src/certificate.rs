@@ -0,0 +1,194 @@
+//! Attendance certificates as single-page PDFs.
+//!
+//! The PDF is written by hand: one A4 landscape page using the standard
+//! Helvetica fonts, which every viewer provides, so nothing is embedded.
+//! Text is encoded as WinAnsi; characters outside Latin-1 become '?'.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{Event, Participant};
+
+/// A4 landscape in points
+const PAGE_WIDTH: f64 = 842.0;
+const PAGE_HEIGHT: f64 = 595.0;
+
+/// Body lines are wrapped at this many characters
+const WRAP_COLUMNS: usize = 70;
+
+/// Certificate wording; `{name}`, `{event}` and `{date}` are replaced per
+/// participant
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct Template {
+    #[validate(length(min = 1, max = 200, code = "out_of_range"))]
+    pub title: String,
+    #[validate(length(min = 1, max = 2000, code = "out_of_range"))]
+    pub body: String,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            title: "Certificate of Attendance".to_string(),
+            body: "This certifies that {name} attended {event} on {date}.".to_string(),
+        }
+    }
+}
+
+/// Date as printed on certificates, e.g. "March 1, 2026"
+pub fn format_date(time: DateTime<Utc>) -> String {
+    time.format("%B %-d, %Y").to_string()
+}
+
+impl Template {
+    /// Fill in the placeholders for one participant
+    pub fn fill(&self, text: &str, event: &Event, participant: &Participant) -> String {
+        text.replace("{name}", &participant.name)
+            .replace("{event}", &event.title)
+            .replace("{date}", &format_date(event.start_time))
+    }
+}
+
+/// Render the certificate for `participant`
+pub fn render(
+    template: &Template,
+    event: &Event,
+    participant: &Participant,
+    certificate_id: Uuid,
+    issued_at: DateTime<Utc>,
+) -> Vec<u8> {
+    let title = template.fill(&template.title, event, participant);
+    let body = template.fill(&template.body, event, participant);
+
+    let mut content = String::new();
+    // Double frame
+    let _ = writeln!(content, "2 w 30 30 {} {} re S", PAGE_WIDTH - 60.0, PAGE_HEIGHT - 60.0);
+    let _ = writeln!(content, "0.5 w 40 40 {} {} re S", PAGE_WIDTH - 80.0, PAGE_HEIGHT - 80.0);
+
+    centered_text(&mut content, "F1", 32.0, 430.0, &title);
+
+    let mut y = 350.0;
+    for paragraph in body.lines() {
+        for line in wrap(paragraph, WRAP_COLUMNS) {
+            centered_text(&mut content, "F2", 16.0, y, &line);
+            y -= 24.0;
+        }
+    }
+
+    let footer = format!("Issued {} - Certificate {}", format_date(issued_at), certificate_id);
+    centered_text(&mut content, "F2", 9.0, 60.0, &footer);
+
+    document(&encode(&content))
+}
+
+/// Approximate width of Helvetica text; glyphs average about half an em
+fn approximate_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * size * 0.5
+}
+
+fn centered_text(content: &mut String, font: &str, size: f64, y: f64, text: &str) {
+    let x = ((PAGE_WIDTH - approximate_width(text, size)) / 2.0).max(50.0);
+    let _ = writeln!(
+        content,
+        "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET",
+        font,
+        size,
+        x,
+        y,
+        escape(text)
+    );
+}
+
+/// Greedy word wrap; words longer than a line are left intact
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Escape a PDF string literal
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// WinAnsi bytes for the content stream; Latin-1 maps one to one
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ (0x0A | 0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Assemble the PDF objects, cross-reference table and trailer
+fn document(content: &[u8]) -> Vec<u8> {
+    let objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        [
+            format!("<< /Length {} >>\nstream\n", content.len()).as_bytes(),
+            content,
+            b"\nendstream",
+        ]
+        .concat(),
+    ];
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    pdf
+}
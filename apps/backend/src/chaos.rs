@@ -0,0 +1,168 @@
+//! Fault injection for staging, so clients can exercise their retry and
+//! reconnection logic against realistic failures.
+//!
+//! CHAOS_RULES lists faults per route pattern, separated by `;`:
+//!
+//! ```text
+//! /api/events=error:0.1,latency:0.5:800;/api/events/stream=drop:0.2;*=latency:0.05:2000
+//! ```
+//!
+//! `error:<rate>` answers with a 500, `latency:<rate>:<ms>` delays the
+//! request and `drop:<rate>` skips SSE messages. Rates are between 0 and 1;
+//! `*` stands for every `/api` route without a rule of its own. Affected
+//! responses carry `X-Chaos`. The rules are ignored when NODE_ENV or
+//! RAILWAY_ENVIRONMENT_NAME is `production`.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+
+use crate::error::internal_error;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Response header naming the injected fault
+pub const CHAOS_HEADER: &str = "x-chaos";
+
+/// Route pattern matching every `/api` route without its own rule
+const ANY_ROUTE: &str = "*";
+
+/// Faults injected into one route
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChaosRule {
+    pub route: String,
+    pub error_rate: f64,
+    pub latency_rate: f64,
+    pub latency: Duration,
+    /// Share of SSE messages skipped
+    pub drop_rate: f64,
+}
+
+/// Fault injection rules (CHAOS_RULES); empty injects nothing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChaosConfig {
+    pub rules: Vec<ChaosRule>,
+}
+
+fn rate(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|r| (0.0..=1.0).contains(r))
+        .ok_or_else(|| format!("invalid rate '{}' (expected 0 to 1)", value))
+}
+
+impl ChaosConfig {
+    /// Parse a CHAOS_RULES value
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for rule in spec.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+            let (route, faults) = rule
+                .split_once('=')
+                .ok_or_else(|| format!("rule '{}' has no faults (expected <route>=<fault>,...)", rule))?;
+            let mut parsed = ChaosRule {
+                route: route.trim().to_string(),
+                ..ChaosRule::default()
+            };
+
+            for fault in faults.split(',').map(str::trim) {
+                let parts: Vec<&str> = fault.split(':').collect();
+                match parts.as_slice() {
+                    ["error", r] => parsed.error_rate = rate(r)?,
+                    ["drop", r] => parsed.drop_rate = rate(r)?,
+                    ["latency", r, ms] => {
+                        parsed.latency_rate = rate(r)?;
+                        parsed.latency = Duration::from_millis(
+                            ms.parse().map_err(|_| format!("invalid latency '{}' (expected milliseconds)", ms))?,
+                        );
+                    }
+                    _ => return Err(format!("unknown fault '{}' (expected error:<rate>, latency:<rate>:<ms> or drop:<rate>)", fault)),
+                }
+            }
+            rules.push(parsed);
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Read CHAOS_RULES unless this is a production deployment
+    pub fn from_env() -> Self {
+        let Some(spec) = std::env::var("CHAOS_RULES").ok().filter(|v| !v.is_empty()) else {
+            return Self::default();
+        };
+
+        let production = ["NODE_ENV", "RAILWAY_ENVIRONMENT_NAME"]
+            .iter()
+            .any(|name| std::env::var(name).is_ok_and(|v| v.eq_ignore_ascii_case("production")));
+        if production {
+            tracing::warn!("CHAOS_RULES is set in production and ignored");
+            return Self::default();
+        }
+
+        let config = Self::parse(&spec).expect("Invalid CHAOS_RULES");
+        tracing::warn!("Fault injection enabled: {}", spec);
+        config
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// The rule for a matched route pattern
+    pub fn rule(&self, route: &str) -> Option<&ChaosRule> {
+        self.rules.iter().find(|r| r.route == route).or_else(|| {
+            route
+                .starts_with("/api/")
+                .then(|| self.rules.iter().find(|r| r.route == ANY_ROUTE))
+                .flatten()
+        })
+    }
+
+    /// Whether to skip the next SSE message sent on `route`
+    pub fn drop_message(&self, route: &str) -> bool {
+        self.rule(route).is_some_and(|r| happens(r.drop_rate))
+    }
+}
+
+fn happens(rate: f64) -> bool {
+    rate > 0.0 && fastrand::f64() < rate
+}
+
+/// Middleware injecting the configured latency and errors
+pub async fn inject(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let chaos = &state.config.chaos;
+    if !chaos.is_enabled() {
+        return next.run(request).await;
+    }
+    let Some(rule) = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| chaos.rule(path.as_str()))
+        .cloned()
+    else {
+        return next.run(request).await;
+    };
+
+    let delayed = happens(rule.latency_rate);
+    if delayed {
+        tokio::time::sleep(rule.latency).await;
+    }
+
+    let mut response = if happens(rule.error_rate) {
+        tracing::debug!("Injecting error into {}", rule.route);
+        let mut response = internal_error().into_response();
+        response.headers_mut().append(CHAOS_HEADER, HeaderValue::from_static("error"));
+        response
+    } else {
+        next.run(request).await
+    };
+    if delayed {
+        response.headers_mut().append(CHAOS_HEADER, HeaderValue::from_static("latency"));
+    }
+    response
+}
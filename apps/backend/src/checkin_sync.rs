@@ -0,0 +1,189 @@
+//! Check-ins recorded by kiosks while offline.
+//!
+//! A kiosk that lost its connection keeps checking people in and later
+//! submits the queue with `POST /api/checkins/batch`, each check-in with
+//! the time the device recorded it. Records are applied in order and
+//! reported one by one, so a device can drop what was accepted and keep
+//! only what needs a person to look at it.
+//!
+//! The same participant may be checked in twice: at another door, by an
+//! organizer, or when a batch is retried after a lost response. Those are
+//! not conflicts; the earliest time wins, and resubmitting a batch changes
+//! nothing. While the event is frozen every record is rejected, to be
+//! submitted again once it thaws.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{EventStatus, Participant, ParticipantStatus};
+
+/// Most check-ins accepted in one batch
+pub const MAX_BATCH: u64 = 500;
+
+/// How far ahead of the server a device clock may run
+pub const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CheckInBatch {
+    /// The submitting device; taken from the device token when absent
+    pub device_id: Option<Uuid>,
+    #[validate(length(min = 1, max = MAX_BATCH, code = "out_of_range", message = "must list 1 to 500 check-ins"))]
+    pub checkins: Vec<OfflineCheckIn>,
+}
+
+/// One check-in as the device recorded it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineCheckIn {
+    pub participant_id: Uuid,
+    /// Device time of the check-in
+    pub checked_in_at: DateTime<Utc>,
+}
+
+/// What became of a submitted check-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Recorded as submitted
+    CheckedIn,
+    /// Already checked in later; the earlier time was kept
+    Corrected,
+    /// Already checked in at or before this time
+    Duplicate,
+    NotFound,
+    /// The participant belongs to another event than the device
+    WrongEvent,
+    /// The participant holds no place, e.g. cancelled or waitlisted
+    NoPlace,
+    EventCompleted,
+    /// The device's event is frozen; only admins may change it
+    EventFrozen,
+    /// The time lies ahead of the server clock beyond the allowed skew
+    FutureTimestamp,
+}
+
+impl Outcome {
+    /// Whether the check-in is on record afterwards; the device may drop it
+    pub fn accepted(self) -> bool {
+        matches!(self, Outcome::CheckedIn | Outcome::Corrected | Outcome::Duplicate)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResult {
+    pub participant_id: Uuid,
+    pub outcome: Outcome,
+    pub accepted: bool,
+    /// The check-in time on record afterwards
+    pub checked_in_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub device_id: Uuid,
+    /// In the order submitted
+    pub results: Vec<SyncResult>,
+}
+
+/// A check-in applied to the database
+pub struct Applied {
+    pub result: SyncResult,
+    /// The participant, when they were newly checked in or their check-in
+    /// time was corrected
+    pub participant: Option<Participant>,
+}
+
+/// Apply one offline check-in for a device of `event_id`
+pub async fn apply(
+    conn: &mut SqliteConnection,
+    event_id: Uuid,
+    record: &OfflineCheckIn,
+    now: DateTime<Utc>,
+) -> Result<Applied, sqlx::Error> {
+    let result = |outcome: Outcome, checked_in_at: Option<DateTime<Utc>>| SyncResult {
+        participant_id: record.participant_id,
+        outcome,
+        accepted: outcome.accepted(),
+        checked_in_at,
+    };
+
+    if record.checked_in_at > now + Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+        return Ok(Applied {
+            result: result(Outcome::FutureTimestamp, None),
+            participant: None,
+        });
+    }
+
+    let frozen = sqlx::query_scalar::<_, bool>("SELECT frozen FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if frozen == Some(true) {
+        return Ok(Applied {
+            result: result(Outcome::EventFrozen, None),
+            participant: None,
+        });
+    }
+
+    let participant = sqlx::query_as::<_, Participant>(
+        "UPDATE participants
+         SET status = 'checked_in', checked_in_at = ?1, updated_at = ?2
+         WHERE id = ?3 AND event_id = ?4 AND status IN ('registered', 'confirmed')
+           AND (SELECT e.status FROM events e WHERE e.id = participants.event_id) != 'completed'
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(record.checked_in_at)
+    .bind(now)
+    .bind(record.participant_id)
+    .bind(event_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(participant) = participant {
+        return Ok(Applied {
+            result: result(Outcome::CheckedIn, Some(record.checked_in_at)),
+            participant: Some(participant),
+        });
+    }
+
+    let current = sqlx::query_as::<_, (Uuid, ParticipantStatus, Option<DateTime<Utc>>, EventStatus)>(
+        "SELECT p.event_id, p.status, p.checked_in_at, e.status
+         FROM participants p JOIN events e ON e.id = p.event_id
+         WHERE p.id = ?"
+    )
+    .bind(record.participant_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let outcome = match current {
+        None => result(Outcome::NotFound, None),
+        Some((participant_event, ..)) if participant_event != event_id => result(Outcome::WrongEvent, None),
+        Some((_, ParticipantStatus::CheckedIn, Some(recorded), _)) if recorded > record.checked_in_at => {
+            let participant = sqlx::query_as::<_, Participant>(
+                "UPDATE participants SET checked_in_at = ?, updated_at = ? WHERE id = ?
+                 RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+            )
+            .bind(record.checked_in_at)
+            .bind(now)
+            .bind(record.participant_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            return Ok(Applied {
+                result: result(Outcome::Corrected, Some(record.checked_in_at)),
+                participant: Some(participant),
+            });
+        }
+        Some((_, ParticipantStatus::CheckedIn, recorded, _)) => result(Outcome::Duplicate, recorded),
+        Some((_, ParticipantStatus::Registered | ParticipantStatus::Confirmed, _, EventStatus::Completed)) => {
+            result(Outcome::EventCompleted, None)
+        }
+        Some(_) => result(Outcome::NoPlace, None),
+    };
+
+    Ok(Applied {
+        result: outcome,
+        participant: None,
+    })
+}
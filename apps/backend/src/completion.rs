@@ -0,0 +1,81 @@
+//! Automatic completion of events that have ended.
+//!
+//! Every EVENT_COMPLETION_INTERVAL_SECS the scheduler moves published
+//! events whose end lies more than EVENT_COMPLETION_GRACE_MINUTES in the
+//! past to `completed`. Completed events no longer accept registrations,
+//! status changes, cancellations, removals or check-ins, except from
+//! admins correcting the record afterwards.
+//!
+//! Each completion is audited and published as `EventCompleted`, which
+//! reaches SSE clients as a `COMPLETE` operation on `event_changes` so
+//! live dashboards can switch to their post-event view. The update is a
+//! single statement, so instances running jobs side by side never complete
+//! an event twice.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::audit;
+use crate::domain_events::{self, DomainEvent};
+use crate::models::{Event, EVENT_COLUMNS};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Actor recorded in the audit log for completions
+const ACTOR: &str = "scheduler";
+
+/// Complete the published events that ended before `now` minus the grace
+/// period, and publish the change; returns the completed events
+pub async fn complete_ended(state: &AppState, now: DateTime<Utc>) -> Result<Vec<Event>, sqlx::Error> {
+    let cutoff = now - state.config.event_completion_grace;
+    let mut tx = state.db_pool.begin().await?;
+
+    let events = sqlx::query_as::<_, Event>(&format!(
+        "UPDATE events SET status = 'completed', updated_at = ?
+         WHERE status = 'published' AND end_time <= ?
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(now)
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for event in &events {
+        audit::record(
+            &mut *tx,
+            "event.complete",
+            "event",
+            &event.id.to_string(),
+            ACTOR,
+            &json!({ "end_time": event.end_time }),
+            now,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    for event in &events {
+        domain_events::publish(state, DomainEvent::EventCompleted { event: event.clone() }).await;
+    }
+
+    Ok(events)
+}
+
+/// Complete ended events once per `interval`; runs on instances with
+/// background jobs (see `config::Role`)
+pub async fn start_scheduler(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match complete_ended(&state, state.clock.now()).await {
+            Ok(events) if !events.is_empty() => info!("Completed {} ended events", events.len()),
+            Ok(_) => {}
+            Err(e) => error!("Failed to complete ended events: {}", e),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Bounded request concurrency.
+//!
+//! Handler work runs under tower's `GlobalConcurrencyLimitLayer`; requests
+//! beyond the limit wait in a queue whose depth is exported as a metric,
+//! and are shed with 503 once the queue is full. NDJSON exports hold a
+//! SQLite connection for as long as their body streams, so they take a
+//! separate permit that lives until the stream ends instead of only until
+//! the handler returns.
+//!
+//! The time from enqueueing to the response is recorded in a latency window
+//! for the autoscaling metrics.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tracing::warn;
+
+use crate::latency::LatencyWindow;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Request concurrency limits
+#[derive(Debug, Clone)]
+pub struct ConcurrencyConfig {
+    /// Requests handled at once (CONCURRENCY_LIMIT)
+    pub max_concurrent: usize,
+    /// Requests allowed to wait for a slot before new ones are shed (CONCURRENCY_QUEUE_LIMIT)
+    pub max_queued: usize,
+    /// NDJSON exports streaming at once (EXPORT_CONCURRENCY_LIMIT)
+    pub max_exports: usize,
+}
+
+impl ConcurrencyConfig {
+    /// Read limits from the environment; zero values fall back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &usize| *v > 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_concurrent: read("CONCURRENCY_LIMIT", defaults.max_concurrent),
+            max_queued: read("CONCURRENCY_QUEUE_LIMIT", defaults.max_queued),
+            max_exports: read("EXPORT_CONCURRENCY_LIMIT", defaults.max_exports),
+        }
+    }
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 64,
+            max_queued: 256,
+            max_exports: 2,
+        }
+    }
+}
+
+/// Shared semaphores and counters backing the limits
+#[derive(Clone)]
+pub struct ConcurrencyLimits {
+    config: Arc<ConcurrencyConfig>,
+    requests: Arc<Semaphore>,
+    exports: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    shed_queue_full: Arc<AtomicU64>,
+    shed_exports: Arc<AtomicU64>,
+    latency: LatencyWindow,
+}
+
+impl ConcurrencyLimits {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            requests: Arc::new(Semaphore::new(config.max_concurrent)),
+            exports: Arc::new(Semaphore::new(config.max_exports)),
+            config: Arc::new(config),
+            queued: Arc::new(AtomicUsize::new(0)),
+            shed_queue_full: Arc::new(AtomicU64::new(0)),
+            shed_exports: Arc::new(AtomicU64::new(0)),
+            latency: LatencyWindow::default(),
+        }
+    }
+
+    pub fn config(&self) -> &ConcurrencyConfig {
+        &self.config
+    }
+
+    /// tower layer enforcing `max_concurrent` across all wrapped routes
+    pub fn layer(&self) -> GlobalConcurrencyLimitLayer {
+        GlobalConcurrencyLimitLayer::with_semaphore(self.requests.clone())
+    }
+
+    /// Requests waiting for a handler slot
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Handler slots currently taken
+    pub fn active(&self) -> usize {
+        self.config.max_concurrent - self.requests.available_permits()
+    }
+
+    /// NDJSON exports currently streaming
+    pub fn active_exports(&self) -> usize {
+        self.config.max_exports - self.exports.available_permits()
+    }
+
+    /// Requests shed because the queue was full
+    pub fn shed_queue_full(&self) -> u64 {
+        self.shed_queue_full.load(Ordering::Relaxed)
+    }
+
+    /// Exports refused because all export slots were taken
+    pub fn shed_exports(&self) -> u64 {
+        self.shed_exports.load(Ordering::Relaxed)
+    }
+
+    /// Latencies of recently served requests, queue wait included
+    pub fn latency(&self) -> &LatencyWindow {
+        &self.latency
+    }
+
+    /// Claim an export slot; hold the permit until the body is fully streamed
+    pub fn try_export_permit(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.exports.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            self.shed_exports.fetch_add(1, Ordering::Relaxed);
+            warn!("Refusing export: {} exports already streaming", self.config.max_exports);
+        }
+        permit
+    }
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self::new(ConcurrencyConfig::default())
+    }
+}
+
+/// Marks a request as queued until it is admitted or dropped
+#[derive(Clone)]
+struct QueueTicket {
+    _slot: Arc<QueueSlot>,
+}
+
+struct QueueSlot(Arc<AtomicUsize>);
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Middleware outside the concurrency limit: counts the request as queued,
+/// or sheds it when the queue is already full
+pub async fn enqueue(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let limits = &state.concurrency;
+    let depth = limits.queued.fetch_add(1, Ordering::Relaxed) + 1;
+    let ticket = QueueTicket {
+        _slot: Arc::new(QueueSlot(limits.queued.clone())),
+    };
+
+    if depth > limits.config.max_queued {
+        drop(ticket);
+        limits.shed_queue_full.fetch_add(1, Ordering::Relaxed);
+        warn!("Shedding request: {} requests queued", depth - 1);
+        return state.load_shedder.shed_response();
+    }
+
+    request.extensions_mut().insert(ticket);
+    let response = next.run(request).await;
+    limits.latency.record(started.elapsed());
+    response
+}
+
+/// Middleware inside the concurrency limit: the request got a slot, so it
+/// no longer counts as queued
+pub async fn admit(mut request: Request, next: Next) -> Response {
+    request.extensions_mut().remove::<QueueTicket>();
+    next.run(request).await
+}
@@ -0,0 +1,460 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use crate::chaos::ChaosConfig;
+use crate::concurrency::ConcurrencyConfig;
+use crate::cors::CorsConfig;
+use crate::encryption::PiiKey;
+use crate::geo::GeoConfig;
+use crate::ids::IdVersion;
+use crate::load_shed::LoadShedConfig;
+use crate::mailer::MailConfig;
+use crate::naming::FieldNaming;
+use crate::reconnect::ReconnectConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::tls::TlsConfig;
+use crate::validation::{DateBounds, DurationLimits, FieldLimits};
+
+/// Where the server accepts connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindTarget {
+    Tcp(SocketAddr),
+    /// Unix domain socket, e.g. behind a sidecar proxy
+    Unix(PathBuf),
+}
+
+impl BindTarget {
+    /// Parse BIND_ADDR: `unix:<path>`, `<ip>:<port>` or a bare `<ip>`
+    /// listening on `port`
+    pub fn parse(value: &str, port: u16) -> Result<Self, String> {
+        let value = value.trim();
+        if let Some(path) = value.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("unix socket path must not be empty".to_string());
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        if let Ok(addr) = value.parse::<SocketAddr>() {
+            return Ok(Self::Tcp(addr));
+        }
+        value
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .map(|ip| Self::Tcp(SocketAddr::new(ip, port)))
+            .map_err(|_| format!("invalid bind address '{}'", value))
+    }
+}
+
+impl std::fmt::Display for BindTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// What a process runs (INSTANCE_ROLE, or `--role` on the command line)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    /// HTTP server and background jobs in one process
+    #[default]
+    All,
+    /// HTTP server only, for latency-focused instances
+    Web,
+    /// Background jobs only: retention cleanup, digests and telemetry
+    Worker,
+}
+
+impl Role {
+    pub fn serves_http(self) -> bool {
+        self != Self::Worker
+    }
+
+    pub fn runs_jobs(self) -> bool {
+        self != Self::Web
+    }
+
+    /// The role given as `--role <role>` or `--role=<role>` in `args`, if any
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(value) = arg.strip_prefix("--role=") {
+                return value.parse().map(Some);
+            }
+            if arg == "--role" {
+                return args.next().ok_or("--role needs a value")?.parse().map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "web" => Ok(Self::Web),
+            "worker" => Ok(Self::Worker),
+            _ => Err(format!("unknown role '{}' (expected all, web or worker)", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::All => "all",
+            Self::Web => "web",
+            Self::Worker => "worker",
+        })
+    }
+}
+
+/// Application configuration read from the environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Writable directory holding the SQLite database
+    pub data_dir: String,
+    /// Size of the volume mounted at `data_dir` (VOLUME_SIZE_MB), for
+    /// storage reports; the server cannot detect it
+    pub volume_size_bytes: Option<u64>,
+    pub port: u16,
+    /// Listen address (BIND_ADDR); defaults to all interfaces on `port`
+    pub bind: BindTarget,
+    /// TLS termination and HTTP/2 via ALPN; None serves plain HTTP
+    pub tls: Option<TlsConfig>,
+    pub cache_ttl_secs: u64,
+    /// Public and admin CORS policies
+    pub cors: CorsConfig,
+    pub load_shed: LoadShedConfig,
+    /// Reconnect delays advised to SSE clients
+    pub sse_reconnect: ReconnectConfig,
+    pub concurrency: ConcurrencyConfig,
+    /// UUID version for new ids (ID_VERSION=v4|v7)
+    pub id_version: IdVersion,
+    /// Largest accepted request body (MAX_BODY_KB)
+    pub max_body_bytes: usize,
+    /// Maximum lengths of free-text fields
+    pub field_limits: FieldLimits,
+    /// Sanity bounds on event start times
+    pub date_bounds: DateBounds,
+    /// Minimum and maximum event duration
+    pub duration_limits: DurationLimits,
+    /// Secret for admin-only operations (ADMIN_TOKEN); admin access is disabled when unset
+    pub admin_token: Option<String>,
+    /// Lifetime of confirmation tokens for destructive operations
+    pub confirmation_ttl: chrono::Duration,
+    /// Lifetime of admin panel sessions (ADMIN_SESSION_TTL_HOURS)
+    pub admin_session_ttl: chrono::Duration,
+    /// Secret signing organizer tokens (JWT_SECRET); generated once and
+    /// kept in the database when unset
+    pub jwt_secret: Option<String>,
+    /// Lifetime of organizer tokens (ORGANIZER_TOKEN_TTL_HOURS)
+    pub organizer_token_ttl: chrono::Duration,
+    /// Time without a heartbeat after which a kiosk device is reported
+    /// offline (DEVICE_OFFLINE_AFTER_SECS)
+    pub device_offline_after: chrono::Duration,
+    /// Opt-in anonymous usage reporting
+    pub telemetry: TelemetryConfig,
+    /// Time without a successful notification poll after which the
+    /// instance reports not ready (POLLER_STALL_THRESHOLD_SECS)
+    pub poller_stall_threshold: chrono::Duration,
+    /// How often organizer digests are generated (DIGEST_INTERVAL_HOURS)
+    pub digest_interval: std::time::Duration,
+    /// How often due event reminders are sent (REMINDER_INTERVAL_SECS)
+    pub reminder_interval: std::time::Duration,
+    /// How often buffered event views are written (POPULARITY_FLUSH_INTERVAL_SECS)
+    pub popularity_flush_interval: std::time::Duration,
+    /// How often ended events are marked completed
+    /// (EVENT_COMPLETION_INTERVAL_SECS); None when set to 0
+    pub event_completion_interval: Option<std::time::Duration>,
+    /// How long after its end an event is completed (EVENT_COMPLETION_GRACE_MINUTES)
+    pub event_completion_grace: chrono::Duration,
+    /// Country allow/deny lists for registrations
+    pub geo: GeoConfig,
+    /// Double-read cached GETs and log divergence (CACHE_AUDIT, debug only)
+    pub cache_audit: bool,
+    /// Reject registrations from domains without mail exchangers
+    /// (EMAIL_MX_CHECK; needs the `mx-check` feature)
+    pub email_mx_check: bool,
+    /// Apply pending migrations on startup (AUTO_MIGRATE, default true);
+    /// when false they wait for review, see `migrations::plan`
+    pub auto_migrate: bool,
+    /// Injected latency, errors and dropped SSE messages (CHAOS_RULES, staging only)
+    pub chaos: ChaosConfig,
+    /// Identity of this process (INSTANCE_ID, else RAILWAY_REPLICA_ID, else random)
+    pub instance_id: String,
+    /// Whether this process serves HTTP, runs background jobs, or both
+    pub role: Role,
+    /// How often the instance heartbeat row is refreshed (HEARTBEAT_INTERVAL_SECS)
+    pub heartbeat_interval: std::time::Duration,
+    /// How often caches are checked against the database for changes whose
+    /// notification went missing (RECONCILE_INTERVAL_SECS)
+    pub reconcile_interval: std::time::Duration,
+    /// Sender and SMTP transport for participant mails
+    pub mail: MailConfig,
+    /// Privacy policy version registrations must accept (PRIVACY_POLICY_VERSION);
+    /// None accepts whatever version the client reports
+    pub privacy_policy_version: Option<String>,
+    /// Accept request bodies with keys the payload does not define
+    /// (ALLOW_UNKNOWN_FIELDS), for clients not yet fixed to send only known ones
+    pub allow_unknown_fields: bool,
+    /// Key naming of JSON bodies for clients not asking for one through an
+    /// `Accept` profile (JSON_FIELD_NAMING)
+    pub json_field_naming: FieldNaming,
+    /// Secret signing export download URLs (EXPORT_SIGNING_KEY); generated
+    /// once and kept in the database when unset, so every instance accepts
+    /// URLs signed by another
+    pub export_signing_key: Option<String>,
+    /// Secret signing self-service cancellation tokens
+    /// (CANCELLATION_SIGNING_KEY); generated once and kept in the database
+    /// when unset
+    pub cancellation_signing_key: Option<String>,
+    /// How long export files and their download URLs last (EXPORT_URL_TTL_SECS)
+    pub export_url_ttl: chrono::Duration,
+    /// Key encrypting dates of birth, names and emails (PII_ENCRYPTION_KEY);
+    /// without it dates of birth are checked but not stored, and names and
+    /// emails are stored in plain text
+    pub pii_key: Option<PiiKey>,
+}
+
+impl Config {
+    /// Build the configuration from environment variables
+    pub fn from_env() -> Self {
+        let data_dir = std::env::var("DATA_DIR")
+            .unwrap_or_else(|_| "/run/media".to_string());
+
+        let port = std::env::var("PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(3000);
+
+        let bind = std::env::var("BIND_ADDR")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|v| BindTarget::parse(&v, port).expect("Invalid BIND_ADDR"))
+            .unwrap_or(BindTarget::Tcp(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port)));
+
+        let cache_ttl_secs = std::env::var("CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+
+        let id_version = std::env::var("ID_VERSION")
+            .ok()
+            .map(|v| v.parse().expect("Invalid ID_VERSION"))
+            .unwrap_or_default();
+
+        let json_field_naming = std::env::var("JSON_FIELD_NAMING")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().expect("Invalid JSON_FIELD_NAMING"))
+            .unwrap_or_default();
+
+        let confirmation_ttl_secs = std::env::var("CONFIRMATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+
+        let admin_session_ttl_hours = std::env::var("ADMIN_SESSION_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(12);
+
+        let organizer_token_ttl_hours = std::env::var("ORGANIZER_TOKEN_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+
+        let device_offline_after_secs = std::env::var("DEVICE_OFFLINE_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(120);
+
+        let poller_stall_threshold_secs = std::env::var("POLLER_STALL_THRESHOLD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30);
+
+        let digest_interval_hours: u64 = std::env::var("DIGEST_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+
+        let reminder_interval_secs: u64 = std::env::var("REMINDER_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(300);
+
+        let popularity_flush_interval_secs: u64 = std::env::var("POPULARITY_FLUSH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30);
+
+        let event_completion_interval_secs: u64 = std::env::var("EVENT_COMPLETION_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(60);
+
+        let event_completion_grace_minutes: i64 = std::env::var("EVENT_COMPLETION_GRACE_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|m| *m >= 0)
+            .unwrap_or(0);
+
+        let instance_id = std::env::var("INSTANCE_ID")
+            .or_else(|_| std::env::var("RAILWAY_REPLICA_ID"))
+            .ok()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let export_url_ttl_secs = std::env::var("EXPORT_URL_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3600);
+
+        let role = std::env::var("INSTANCE_ROLE")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().expect("Invalid INSTANCE_ROLE"))
+            .unwrap_or_default();
+
+        let heartbeat_interval_secs: u64 = std::env::var("HEARTBEAT_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(10);
+
+        let reconcile_interval_secs: u64 = std::env::var("RECONCILE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30);
+
+        Self {
+            data_dir,
+            volume_size_bytes: std::env::var("VOLUME_SIZE_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|mb| *mb > 0)
+                .map(|mb| mb * 1024 * 1024),
+            port,
+            bind,
+            tls: TlsConfig::from_env(),
+            cache_ttl_secs,
+            cors: CorsConfig::from_env(),
+            load_shed: LoadShedConfig::from_env(),
+            sse_reconnect: ReconnectConfig::from_env(),
+            concurrency: ConcurrencyConfig::from_env(),
+            id_version,
+            max_body_bytes: std::env::var("MAX_BODY_KB")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|kb| *kb > 0)
+                .unwrap_or(1024)
+                * 1024,
+            field_limits: FieldLimits::from_env(),
+            date_bounds: DateBounds::from_env(),
+            duration_limits: DurationLimits::from_env(),
+            admin_token: std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty()),
+            confirmation_ttl: chrono::Duration::seconds(confirmation_ttl_secs),
+            admin_session_ttl: chrono::Duration::hours(admin_session_ttl_hours),
+            jwt_secret: std::env::var("JWT_SECRET").ok().filter(|k| !k.is_empty()),
+            organizer_token_ttl: chrono::Duration::hours(organizer_token_ttl_hours),
+            device_offline_after: chrono::Duration::seconds(device_offline_after_secs),
+            telemetry: TelemetryConfig::from_env(),
+            poller_stall_threshold: chrono::Duration::seconds(poller_stall_threshold_secs),
+            digest_interval: std::time::Duration::from_secs(digest_interval_hours * 3600),
+            reminder_interval: std::time::Duration::from_secs(reminder_interval_secs),
+            popularity_flush_interval: std::time::Duration::from_secs(popularity_flush_interval_secs),
+            event_completion_interval: (event_completion_interval_secs > 0)
+                .then(|| std::time::Duration::from_secs(event_completion_interval_secs)),
+            event_completion_grace: chrono::Duration::minutes(event_completion_grace_minutes),
+            geo: GeoConfig::from_env(),
+            cache_audit: std::env::var("CACHE_AUDIT").is_ok_and(|v| v == "true" || v == "1"),
+            email_mx_check: std::env::var("EMAIL_MX_CHECK").is_ok_and(|v| v == "true" || v == "1"),
+            auto_migrate: std::env::var("AUTO_MIGRATE").map_or(true, |v| v != "false" && v != "0"),
+            chaos: ChaosConfig::from_env(),
+            instance_id,
+            role,
+            heartbeat_interval: std::time::Duration::from_secs(heartbeat_interval_secs),
+            reconcile_interval: std::time::Duration::from_secs(reconcile_interval_secs),
+            mail: MailConfig::from_env(),
+            privacy_policy_version: std::env::var("PRIVACY_POLICY_VERSION").ok().filter(|v| !v.is_empty()),
+            allow_unknown_fields: std::env::var("ALLOW_UNKNOWN_FIELDS").is_ok_and(|v| v == "true" || v == "1"),
+            json_field_naming,
+            export_signing_key: std::env::var("EXPORT_SIGNING_KEY").ok().filter(|k| !k.is_empty()),
+            cancellation_signing_key: std::env::var("CANCELLATION_SIGNING_KEY").ok().filter(|k| !k.is_empty()),
+            export_url_ttl: chrono::Duration::seconds(export_url_ttl_secs),
+            pii_key: std::env::var("PII_ENCRYPTION_KEY")
+                .ok()
+                .filter(|k| !k.is_empty())
+                .map(|k| k.parse().expect("Invalid PII_ENCRYPTION_KEY")),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_dir: "/run/media".to_string(),
+            volume_size_bytes: None,
+            port: 3000,
+            bind: BindTarget::Tcp(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 3000)),
+            tls: None,
+            cache_ttl_secs: 60,
+            cors: CorsConfig::default(),
+            load_shed: LoadShedConfig::default(),
+            sse_reconnect: ReconnectConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+            id_version: IdVersion::default(),
+            max_body_bytes: 1024 * 1024,
+            field_limits: FieldLimits::default(),
+            date_bounds: DateBounds::default(),
+            duration_limits: DurationLimits::default(),
+            admin_token: None,
+            confirmation_ttl: chrono::Duration::seconds(60),
+            admin_session_ttl: chrono::Duration::hours(12),
+            jwt_secret: None,
+            organizer_token_ttl: chrono::Duration::hours(24),
+            device_offline_after: chrono::Duration::seconds(120),
+            telemetry: TelemetryConfig::default(),
+            poller_stall_threshold: chrono::Duration::seconds(30),
+            digest_interval: std::time::Duration::from_secs(24 * 3600),
+            reminder_interval: std::time::Duration::from_secs(300),
+            popularity_flush_interval: std::time::Duration::from_secs(30),
+            event_completion_interval: Some(std::time::Duration::from_secs(60)),
+            event_completion_grace: chrono::Duration::zero(),
+            geo: GeoConfig::default(),
+            cache_audit: false,
+            email_mx_check: false,
+            auto_migrate: true,
+            chaos: ChaosConfig::default(),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            role: Role::default(),
+            heartbeat_interval: std::time::Duration::from_secs(10),
+            reconcile_interval: std::time::Duration::from_secs(30),
+            mail: MailConfig::default(),
+            privacy_policy_version: None,
+            allow_unknown_fields: false,
+            json_field_naming: FieldNaming::default(),
+            export_signing_key: None,
+            cancellation_signing_key: None,
+            export_url_ttl: chrono::Duration::hours(1),
+            pii_key: None,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Two-phase confirmation for destructive operations.
+//!
+//! A client first calls `POST /api/confirmations` describing the request it
+//! is about to make and receives a short-lived, single-use token. The
+//! destructive request must then carry that token in `X-Confirmation-Token`.
+//! Tokens live in SQLite so a token prepared on one instance can be
+//! redeemed on another.
+
+use axum::{
+    extract::{MatchedPath, Query, Request, State},
+    http::{Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::db::DbPool;
+use crate::routes::events::DeleteEventQuery;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Header carrying the confirmation token
+pub const CONFIRMATION_HEADER: &str = "x-confirmation-token";
+
+/// Whether a request needs a confirmation token. The query is decoded as
+/// the handler decodes it, so an encoded `force` cannot slip past; one the
+/// handler would reject is not destructive either.
+fn is_destructive(method: &Method, path: &str, uri: &Uri) -> bool {
+    match (method, path) {
+        (&Method::DELETE, "/api/events/:id") => {
+            Query::<DeleteEventQuery>::try_from_uri(uri).is_ok_and(|Query(query)| query.force)
+        }
+        (&Method::DELETE, "/api/participants/by-email/:email") => true,
+        _ => false,
+    }
+}
+
+/// The request target a token is bound to: path plus query string
+pub fn target_of(path: &str, query: Option<&str>) -> String {
+    match query {
+        Some(q) if !q.is_empty() => format!("{}?{}", path, q),
+        _ => path.to_string(),
+    }
+}
+
+/// Store a new token for `method target`, valid until `expires_at`
+pub async fn issue(
+    pool: &DbPool,
+    token: &str,
+    method: &str,
+    target: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO confirmation_tokens (token, method, target, expires_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(token)
+    .bind(method)
+    .bind(target)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Delete tokens that expired without being redeemed
+pub async fn prune(pool: &DbPool, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM confirmation_tokens WHERE expires_at <= ?")
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Consume a token; returns false if it is unknown, expired, already used
+/// or bound to a different request
+pub async fn redeem(
+    pool: &DbPool,
+    token: &str,
+    method: &str,
+    target: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM confirmation_tokens
+         WHERE token = ? AND method = ? AND target = ? AND expires_at > ?"
+    )
+    .bind(token)
+    .bind(method)
+    .bind(target)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Middleware rejecting destructive requests that lack a valid token
+pub async fn require_confirmation(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(path) = request.extensions().get::<MatchedPath>() else {
+        return next.run(request).await;
+    };
+
+    if !is_destructive(request.method(), path.as_str(), request.uri()) {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(CONFIRMATION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let target = target_of(request.uri().path(), request.uri().query());
+
+    let confirmed = match token {
+        Some(token) => {
+            match redeem(&state.db_pool, &token, request.method().as_str(), &target, state.clock.now()).await {
+                Ok(confirmed) => confirmed,
+                Err(e) => {
+                    tracing::error!("Failed to redeem confirmation token: {}", e);
+                    return crate::error::internal_error().into_response();
+                }
+            }
+        }
+        None => false,
+    };
+
+    if !confirmed {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({
+                "error": "Confirmation required; obtain a token from POST /api/confirmations",
+                "method": request.method().as_str(),
+                "target": target,
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,95 @@
+//! Privacy policy and marketing consent recorded with each registration.
+//!
+//! Registrants must report the privacy policy version they accepted; the
+//! marketing opt-in is optional. Each consent row carries a random
+//! withdrawal token so a participant can revoke marketing consent from a
+//! link without an account.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Sqlite};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+
+/// Consent as recorded for one participant
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Consent {
+    pub participant_id: Uuid,
+    pub privacy_policy_version: String,
+    pub privacy_accepted_at: DateTime<Utc>,
+    pub marketing_opt_in: bool,
+    pub marketing_updated_at: DateTime<Utc>,
+    /// Only handed to the registrant; never part of exports
+    #[serde(skip_serializing)]
+    pub withdrawal_token: String,
+}
+
+/// A fresh withdrawal token
+pub fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Store consent for a new registration. Accepts a pool or a transaction so
+/// it can be written atomically with the participant.
+pub async fn record<'e, E>(
+    executor: E,
+    participant_id: Uuid,
+    privacy_policy_version: &str,
+    marketing_opt_in: bool,
+    withdrawal_token: &str,
+    now: DateTime<Utc>,
+) -> Result<Consent, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Consent>(
+        "INSERT INTO participant_consents
+             (participant_id, privacy_policy_version, privacy_accepted_at, marketing_opt_in, marketing_updated_at, withdrawal_token)
+         VALUES (?, ?, ?, ?, ?, ?)
+         RETURNING participant_id, privacy_policy_version, privacy_accepted_at, marketing_opt_in, marketing_updated_at, withdrawal_token"
+    )
+    .bind(participant_id)
+    .bind(privacy_policy_version)
+    .bind(now)
+    .bind(marketing_opt_in)
+    .bind(now)
+    .bind(withdrawal_token)
+    .fetch_one(executor)
+    .await
+}
+
+/// Consent of one participant, if recorded
+pub async fn fetch(pool: &DbPool, participant_id: Uuid) -> Result<Option<Consent>, sqlx::Error> {
+    sqlx::query_as::<_, Consent>(
+        "SELECT participant_id, privacy_policy_version, privacy_accepted_at, marketing_opt_in, marketing_updated_at, withdrawal_token
+         FROM participant_consents
+         WHERE participant_id = ?"
+    )
+    .bind(participant_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Turn marketing consent off for the holder of `token`. Withdrawing twice
+/// keeps the original withdrawal time. None if the token is unknown.
+pub async fn withdraw_marketing<'e, E>(
+    executor: E,
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<Consent>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Consent>(
+        "UPDATE participant_consents
+         SET marketing_updated_at = CASE WHEN marketing_opt_in THEN ? ELSE marketing_updated_at END,
+             marketing_opt_in = 0
+         WHERE withdrawal_token = ?
+         RETURNING participant_id, privacy_policy_version, privacy_accepted_at, marketing_opt_in, marketing_updated_at, withdrawal_token"
+    )
+    .bind(now)
+    .bind(token)
+    .fetch_optional(executor)
+    .await
+}
@@ -0,0 +1,231 @@
+//! Cross-origin policies.
+//!
+//! Requests are answered under one of two policies, chosen by path. Public
+//! endpoints follow CORS_ORIGIN: a comma-separated list of frontend
+//! origins, or any origin when it is `*` (or unset in debug builds). An
+//! entry like `https://*.example.com` admits every subdomain of
+//! example.com over https, for preview deployments. Public endpoints
+//! authenticate by header tokens, never by cookie, so they do not allow
+//! credentials.
+//!
+//! The admin API and the embedded panel (`/api/admin/*`, `/admin/*`) are
+//! locked to CORS_ADMIN_ORIGIN, a list of the same form, which defaults to
+//! CORS_ORIGIN. With CORS_ALLOW_CREDENTIALS the admin policy lets those
+//! origins send the session cookie (see `session`). When no admin origins
+//! are known the admin routes stay same-origin, except for debug builds
+//! without any CORS_ORIGIN, which keep allowing every origin.
+//!
+//! Both policies let browsers cache preflight results for CORS_MAX_AGE_SECS.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::{Layer, ServiceExt};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::{auth, confirmation, devices, impersonation, maintenance, session, visibility};
+
+/// Path prefixes answered under the admin policy
+const ADMIN_PREFIXES: &[&str] = &["/api/admin", "/admin"];
+
+/// Methods cross-origin callers may use
+const METHODS: [Method; 5] = [Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE];
+
+/// Cross-origin settings
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Comma-separated frontend origins (CORS_ORIGIN); `*` allows all,
+    /// empty allows all in debug only
+    pub origin: String,
+    /// Origins of the admin panel (CORS_ADMIN_ORIGIN); empty falls back to `origin`
+    pub admin_origin: String,
+    /// Let the admin origin send cookies (CORS_ALLOW_CREDENTIALS)
+    pub allow_credentials: bool,
+    /// How long browsers may cache preflight results; zero disables caching
+    pub max_age: Duration,
+}
+
+impl CorsConfig {
+    /// Read the policies from CORS_* environment variables
+    pub fn from_env() -> Self {
+        let max_age_secs = std::env::var("CORS_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(600);
+
+        Self {
+            origin: std::env::var("CORS_ORIGIN").unwrap_or_default(),
+            admin_origin: std::env::var("CORS_ADMIN_ORIGIN").unwrap_or_default(),
+            allow_credentials: std::env::var("CORS_ALLOW_CREDENTIALS").is_ok_and(|v| v == "true" || v == "1"),
+            max_age: Duration::from_secs(max_age_secs),
+        }
+    }
+
+    /// The origins the admin policy admits, if any
+    fn admin_origin(&self) -> Option<&str> {
+        [self.admin_origin.as_str(), self.origin.as_str()]
+            .into_iter()
+            .find(|origins| !origins.trim().is_empty())
+            .filter(|origins| !allows_any(origins))
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origin: String::new(),
+            admin_origin: String::new(),
+            allow_credentials: false,
+            max_age: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Whether `path` is answered under the admin policy
+fn is_admin_path(path: &str) -> bool {
+    ADMIN_PREFIXES
+        .iter()
+        .any(|prefix| path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
+}
+
+/// Whether an origin list admits every origin
+fn allows_any(origins: &str) -> bool {
+    origins.trim().is_empty() || entries(origins).any(|entry| entry == "*")
+}
+
+fn entries(origins: &str) -> impl Iterator<Item = &str> {
+    origins.split(',').map(str::trim).filter(|entry| !entry.is_empty())
+}
+
+/// One entry of an origin list
+#[derive(Debug, Clone)]
+enum OriginPattern {
+    Exact(HeaderValue),
+    /// `scheme://*.domain`: any subdomain of the domain, with the scheme
+    /// and port of the pattern
+    Subdomains { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    fn parse(entry: &str, var: &str) -> Self {
+        let invalid = || -> ! { panic!("Invalid {} entry '{}'", var, entry) };
+
+        if !entry.contains('*') {
+            return OriginPattern::Exact(entry.parse().unwrap_or_else(|_| invalid()));
+        }
+
+        let Some((scheme, host)) = entry.split_once("://") else { invalid() };
+        match host.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') && suffix.len() > 1 && !suffix.contains(['*', '/']) => {
+                OriginPattern::Subdomains {
+                    scheme: format!("{}://", scheme.to_ascii_lowercase()),
+                    suffix: suffix.to_ascii_lowercase(),
+                }
+            }
+            _ => invalid(),
+        }
+    }
+
+    fn matches(&self, origin: &HeaderValue) -> bool {
+        match self {
+            OriginPattern::Exact(exact) => exact == origin,
+            OriginPattern::Subdomains { scheme, suffix } => {
+                let Ok(origin) = origin.to_str() else { return false };
+                let origin = origin.to_ascii_lowercase();
+                origin
+                    .strip_prefix(scheme.as_str())
+                    .and_then(|host| host.strip_suffix(suffix.as_str()))
+                    .is_some_and(|subdomain| {
+                        !subdomain.is_empty()
+                            && subdomain.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                    })
+            }
+        }
+    }
+}
+
+/// The origins of a comma-separated list, as a `CorsLayer` origin setting.
+/// A single origin is always sent back, so browsers report the mismatch.
+fn allow_origins(origins: &str, var: &str) -> AllowOrigin {
+    let patterns: Vec<OriginPattern> = entries(origins).map(|entry| OriginPattern::parse(entry, var)).collect();
+
+    if let [OriginPattern::Exact(origin)] = patterns.as_slice() {
+        return AllowOrigin::exact(origin.clone());
+    }
+    AllowOrigin::predicate(move |origin, _| patterns.iter().any(|pattern| pattern.matches(origin)))
+}
+
+/// Layer with the settings both policies share
+fn base(config: &CorsConfig) -> CorsLayer {
+    let layer = CorsLayer::new()
+        .allow_methods(METHODS)
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            header::AUTHORIZATION,
+            HeaderName::from_static(auth::ADMIN_TOKEN_HEADER),
+            HeaderName::from_static(session::CSRF_HEADER),
+            HeaderName::from_static(confirmation::CONFIRMATION_HEADER),
+            HeaderName::from_static(impersonation::IMPERSONATE_HEADER),
+            HeaderName::from_static(visibility::PARTICIPANT_TOKEN_HEADER),
+            HeaderName::from_static(devices::DEVICE_TOKEN_HEADER),
+        ])
+        .expose_headers([
+            HeaderName::from_static(maintenance::MAINTENANCE_HEADER),
+            HeaderName::from_static(crate::routes::participants::TOTAL_COUNT_HEADER),
+        ]);
+
+    if config.max_age.is_zero() {
+        layer
+    } else {
+        layer.max_age(config.max_age)
+    }
+}
+
+/// The public and admin policies
+#[derive(Clone)]
+pub struct Policies {
+    public: CorsLayer,
+    admin: CorsLayer,
+}
+
+impl Policies {
+    pub fn new(config: &CorsConfig) -> Self {
+        let public = if allows_any(&config.origin) {
+            base(config).allow_origin(Any)
+        } else {
+            base(config).allow_origin(allow_origins(&config.origin, "CORS_ORIGIN"))
+        };
+
+        let admin = match config.admin_origin() {
+            Some(origins) => base(config)
+                .allow_origin(allow_origins(origins, "CORS_ADMIN_ORIGIN"))
+                .allow_credentials(config.allow_credentials),
+            None if config.origin.is_empty() => base(config).allow_origin(Any),
+            // No origin matches, so browsers keep the admin API same-origin
+            None => base(config).allow_origin(AllowOrigin::list([])),
+        };
+
+        Self { public, admin }
+    }
+}
+
+/// Middleware answering preflights and decorating responses under the
+/// policy of the request's path
+pub async fn apply(State(policies): State<Arc<Policies>>, request: Request, next: Next) -> Response {
+    let policy = if is_admin_path(request.uri().path()) {
+        &policies.admin
+    } else {
+        &policies.public
+    };
+
+    match policy.layer(next).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(never) => match never {},
+    }
+}
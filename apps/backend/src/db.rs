@@ -1,10 +1,15 @@
+use chrono::{DateTime, Utc};
 use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
-use crate::broadcaster::{Broadcaster, ServerEvent};
+use crate::broadcaster::{Broadcaster, Channel, ServerEvent};
 use crate::cache::AppCache;
+use crate::clock::SharedClock;
+use crate::watchdog::TaskHealth;
 
 pub type DbPool = SqlitePool;
 
@@ -30,8 +35,14 @@ pub async fn create_pool(db_path: &str) -> Result<DbPool, sqlx::Error> {
     Ok(pool)
 }
 
-/// Initialize database tables
+/// Initialize database tables and apply pending migrations
 pub async fn initialize_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
+    initialize_schema(pool, true).await
+}
+
+/// Initialize database tables; with `migrate` false, pending migrations
+/// are left for an operator to review (see `migrations::plan`)
+pub async fn initialize_schema(pool: &DbPool, migrate: bool) -> Result<(), sqlx::Error> {
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS events (
             id TEXT PRIMARY KEY NOT NULL,
@@ -68,6 +79,10 @@ pub async fn initialize_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_start_time ON events(start_time)")
         .execute(pool)
         .await?;
+    // Creation-order scans; with v7 ids the primary key follows the same order
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_created_at ON events(created_at)")
+        .execute(pool)
+        .await?;
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_participants_event_id ON participants(event_id)")
         .execute(pool)
         .await?;
@@ -91,6 +106,39 @@ pub async fn initialize_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
         .execute(pool)
         .await?;
 
+    if !migrate {
+        let pending = crate::migrations::pending(pool).await?;
+        if !pending.is_empty() {
+            warn!(
+                "{} migrations pending; this instance must not serve traffic until they are applied. Review them at POST /api/admin/migrations/plan and restart with AUTO_MIGRATE=true",
+                pending.len()
+            );
+            return Ok(());
+        }
+    }
+    crate::migrations::run(pool).await?;
+
+    let mut tx = pool.begin().await?;
+    let generated = crate::slug::backfill(&mut tx).await?;
+    tx.commit().await?;
+    if generated > 0 {
+        info!("Generated slugs for {} events", generated);
+    }
+
+    let mut tx = pool.begin().await?;
+    let sealed = crate::encryption::seal_participants(&mut tx).await?;
+    tx.commit().await?;
+    if sealed > 0 {
+        info!("Sealed or indexed the names and emails of {} participants", sealed);
+    }
+
+    let mut tx = pool.begin().await?;
+    let sealed = crate::encryption::seal_deliveries(&mut tx).await?;
+    tx.commit().await?;
+    if sealed > 0 {
+        info!("Sealed or indexed {} queued or failed mails and webhook deliveries", sealed);
+    }
+
     info!("Database tables initialized");
     Ok(())
 }
@@ -98,14 +146,14 @@ pub async fn initialize_tables(pool: &DbPool) -> Result<(), sqlx::Error> {
 /// Insert a change notification for cross-instance sync
 pub async fn insert_notification(
     pool: &DbPool,
-    channel: &str,
+    channel: Channel,
     payload: &str,
+    created_at: DateTime<Utc>,
 ) -> Result<(), sqlx::Error> {
-    let now = chrono::Utc::now().to_rfc3339();
     sqlx::query("INSERT INTO change_notifications (channel, payload, created_at) VALUES (?, ?, ?)")
         .bind(channel)
         .bind(payload)
-        .bind(&now)
+        .bind(created_at.to_rfc3339())
         .execute(pool)
         .await?;
 
@@ -121,62 +169,271 @@ pub async fn get_max_notification_id(pool: &DbPool) -> i64 {
         .unwrap_or(0)
 }
 
-/// Poll for new notifications and broadcast them (cross-instance sync)
+/// Ids of the oldest and newest notification kept. With none kept, the
+/// range is empty and starts after the last id ever assigned; notifications
+/// before the start were deleted by retention.
+pub async fn notification_history(pool: &DbPool) -> Result<(i64, i64), sqlx::Error> {
+    sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COALESCE(min(id), (SELECT seq + 1 FROM sqlite_sequence WHERE name = 'change_notifications'), 1),
+                COALESCE(max(id), (SELECT seq FROM sqlite_sequence WHERE name = 'change_notifications'), 0)
+         FROM change_notifications"
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Up to `limit` notifications after `after_id`, oldest first, as they were
+/// broadcast
+pub async fn notifications_after(pool: &DbPool, after_id: i64, limit: i64) -> Result<Vec<ServerEvent>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, String)>(
+        "SELECT id, channel, payload FROM change_notifications WHERE id > ? ORDER BY id ASC LIMIT ?",
+    )
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, channel, payload)| match channel.parse() {
+            Ok(channel) => Some(ServerEvent { id, channel, payload }),
+            Err(e) => {
+                warn!("Skipping notification {}: {}", id, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Delete change notifications created before `cutoff`
+pub async fn cleanup_notifications(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM change_notifications WHERE created_at < ?")
+        .bind(cutoff.to_rfc3339())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete notifications and audit entries older than the retention policy,
+/// returning the number of notifications deleted. The policy is re-read on
+/// every run so admin changes apply without a restart.
+pub async fn run_retention(pool: &DbPool, now: DateTime<Utc>) -> u64 {
+    let policy = match crate::settings::retention(pool).await {
+        Ok(policy) => policy,
+        Err(e) => {
+            error!("Failed to load retention policy: {}", e);
+            crate::settings::RetentionPolicy::default()
+        }
+    };
+
+    let deleted = cleanup_notifications(pool, now - policy.notification_retention())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to cleanup notifications: {}", e);
+            0
+        });
+    if let Err(e) = crate::audit::cleanup(pool, now - policy.audit_retention()).await {
+        error!("Failed to cleanup audit log: {}", e);
+    }
+    deleted
+}
+
+/// Time between notification polls
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Timings and volumes of the notification poller, read by `/metrics`.
+///
+/// Notifications are deleted by the retention job, not the poller, so those
+/// deletions are counted here too: together with the rows fetched they show
+/// whether the table grows faster than it is cleaned up.
+#[derive(Debug, Clone, Default)]
+pub struct PollerMetrics {
+    iterations: Arc<AtomicU64>,
+    /// Iterations that took longer than `POLL_INTERVAL`
+    overruns: Arc<AtomicU64>,
+    rows_fetched: Arc<AtomicU64>,
+    /// Totals and most recent values, in microseconds
+    poll_us: Arc<AtomicU64>,
+    last_poll_us: Arc<AtomicU64>,
+    fanout_us: Arc<AtomicU64>,
+    last_fanout_us: Arc<AtomicU64>,
+    cleanup_deleted: Arc<AtomicU64>,
+}
+
+impl PollerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record_iteration(&self, poll: Duration, fanout: Duration, rows: u64) {
+        let poll_us = poll.as_micros() as u64;
+        let fanout_us = fanout.as_micros() as u64;
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        self.rows_fetched.fetch_add(rows, Ordering::Relaxed);
+        self.poll_us.fetch_add(poll_us, Ordering::Relaxed);
+        self.last_poll_us.store(poll_us, Ordering::Relaxed);
+        self.fanout_us.fetch_add(fanout_us, Ordering::Relaxed);
+        self.last_fanout_us.store(fanout_us, Ordering::Relaxed);
+    }
+
+    fn record_overrun(&self) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count notifications removed by a retention run
+    pub fn record_cleanup(&self, deleted: u64) {
+        self.cleanup_deleted.fetch_add(deleted, Ordering::Relaxed);
+    }
+
+    /// Completed poll iterations, failed queries included
+    pub fn iterations(&self) -> u64 {
+        self.iterations.load(Ordering::Relaxed)
+    }
+
+    pub fn overruns(&self) -> u64 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+
+    pub fn rows_fetched(&self) -> u64 {
+        self.rows_fetched.load(Ordering::Relaxed)
+    }
+
+    /// Time spent querying, across all iterations
+    pub fn poll_time(&self) -> Duration {
+        Duration::from_micros(self.poll_us.load(Ordering::Relaxed))
+    }
+
+    pub fn last_poll_time(&self) -> Duration {
+        Duration::from_micros(self.last_poll_us.load(Ordering::Relaxed))
+    }
+
+    /// Time spent invalidating caches and broadcasting, across all iterations
+    pub fn fanout_time(&self) -> Duration {
+        Duration::from_micros(self.fanout_us.load(Ordering::Relaxed))
+    }
+
+    pub fn last_fanout_time(&self) -> Duration {
+        Duration::from_micros(self.last_fanout_us.load(Ordering::Relaxed))
+    }
+
+    pub fn cleanup_deleted(&self) -> u64 {
+        self.cleanup_deleted.load(Ordering::Relaxed)
+    }
+}
+
+/// Consecutive failed polls after which the poller gives up so its
+/// supervisor can restart it
+const MAX_CONSECUTIVE_POLL_FAILURES: u32 = 10;
+
+/// Poll for new notifications and broadcast them (cross-instance sync).
+/// Returns after repeated query failures; run it under `watchdog::supervise`.
 pub async fn start_notification_poller(
     pool: DbPool,
     broadcaster: Broadcaster,
     cache: AppCache,
+    clock: SharedClock,
     last_id: Arc<Mutex<i64>>,
+    health: TaskHealth,
+    metrics: PollerMetrics,
 ) {
-    let mut poll_count: u64 = 0;
+    let mut consecutive_failures: u32 = 0;
 
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        tokio::time::sleep(POLL_INTERVAL).await;
 
         let current_last_id = {
             let guard = last_id.lock().await;
             *guard
         };
 
-        match sqlx::query_as::<_, (i64, String, String)>(
+        let started = Instant::now();
+        let polled = sqlx::query_as::<_, (i64, String, String)>(
             "SELECT id, channel, payload FROM change_notifications WHERE id > ? ORDER BY id ASC",
         )
         .bind(current_last_id)
         .fetch_all(&pool)
-        .await
-        {
+        .await;
+        let poll_time = started.elapsed();
+
+        let fanout_started = Instant::now();
+        let rows = match &polled {
             Ok(notifications) => {
                 if !notifications.is_empty() {
                     let mut guard = last_id.lock().await;
-                    for (id, channel, payload) in &notifications {
+                    for (id, channel, payload) in notifications {
+                        *guard = *id;
+                        // Written by an instance that knows a channel this one does not
+                        let channel: Channel = match channel.parse() {
+                            Ok(channel) => channel,
+                            Err(e) => {
+                                warn!("Skipping notification {}: {}", id, e);
+                                continue;
+                            }
+                        };
+
                         // Invalidate cache based on notification channel
                         cache.invalidate_for_channel(channel).await;
 
                         let event = ServerEvent {
-                            channel: channel.clone(),
+                            id: *id,
+                            channel,
                             payload: payload.clone(),
                         };
                         broadcaster.broadcast(event);
-                        *guard = *id;
                     }
                 }
+                notifications.len() as u64
             }
-            Err(e) => {
-                error!("Failed to poll notifications: {}", e);
-            }
+            Err(_) => 0,
+        };
+        let fanout_time = fanout_started.elapsed();
+
+        metrics.record_iteration(poll_time, fanout_time, rows);
+        // The next poll waits for this one, so a slow iteration delays
+        // every change from other instances by the excess
+        let elapsed = poll_time + fanout_time;
+        if elapsed > POLL_INTERVAL {
+            metrics.record_overrun();
+            warn!(
+                "Notification poll took {:?} ({:?} querying, {:?} broadcasting {} rows), longer than the {:?} poll interval; cross-instance sync is lagging",
+                elapsed, poll_time, fanout_time, rows, POLL_INTERVAL
+            );
         }
 
-        poll_count = poll_count.saturating_add(1);
-        if poll_count % 60 == 0 {
-            // Clean up old notifications (keep last hour)
-            let cutoff = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
-            if let Err(e) = sqlx::query("DELETE FROM change_notifications WHERE created_at < ?")
-                .bind(&cutoff)
-                .execute(&pool)
-                .await
-            {
-                error!("Failed to cleanup notifications: {}", e);
+        if let Err(e) = polled {
+            error!("Failed to poll notifications: {}", e);
+            consecutive_failures += 1;
+            if consecutive_failures >= MAX_CONSECUTIVE_POLL_FAILURES {
+                error!("Notification poller giving up after {} failed polls", consecutive_failures);
+                return;
             }
+            continue;
+        }
+
+        consecutive_failures = 0;
+        health.record_success(clock.now());
+    }
+}
+
+/// Apply the retention policy and remove expired exports from `data_dir`
+/// once per `interval`; runs on instances with background jobs (see
+/// `config::Role`)
+pub async fn start_retention(
+    pool: DbPool,
+    clock: SharedClock,
+    data_dir: String,
+    interval: Duration,
+    metrics: PollerMetrics,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+        metrics.record_cleanup(run_retention(&pool, clock.now()).await);
+        if let Err(e) = crate::export_jobs::remove_expired(&pool, &data_dir, clock.now()).await {
+            warn!("Failed to remove expired exports: {}", e);
         }
     }
 }
@@ -0,0 +1,261 @@
+//! Kiosk devices: check-in tablets at the door.
+//!
+//! An organizer registers a tablet for one event with `POST /api/devices`
+//! and gets back its token, once. The tablet sends it in `X-Device-Token`;
+//! only a SHA-256 hash of it is stored. The token is scoped: it checks in
+//! participants of the device's event and lists its check-ins, and grants
+//! nothing else.
+//!
+//! Devices send `POST /api/devices/heartbeat` every so often, reporting
+//! how many check-ins they still hold for syncing. `GET /api/admin/devices`
+//! lists them with when they were last seen, so a door scanner that went
+//! quiet is noticed before the queue forms.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::convert::Infallible;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::db::DbPool;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::export::encode_hex;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Header carrying a device token
+pub const DEVICE_TOKEN_HEADER: &str = "x-device-token";
+/// Longest device name
+pub const MAX_NAME_LEN: u64 = 100;
+/// Longest app version a heartbeat may report
+pub const MAX_APP_VERSION_LEN: u64 = 50;
+
+/// A registered kiosk device
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Device {
+    pub id: Uuid,
+    pub name: String,
+    /// The only event the device may check participants in to
+    pub event_id: Uuid,
+    #[serde(skip)]
+    pub token_hash: String,
+    /// Check-ins the device reported still waiting to be synced
+    pub pending_sync: i64,
+    pub app_version: Option<String>,
+    /// Time of the last heartbeat; None before the first
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A device as listed for admins
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStatus {
+    #[serde(flatten)]
+    pub device: Device,
+    /// Sent a heartbeat within DEVICE_OFFLINE_AFTER_SECS
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RegisterDevice {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_NAME_LEN, code = "out_of_range")
+    )]
+    pub name: String,
+    pub event_id: Uuid,
+}
+
+/// A newly registered device and its token, which is not shown again
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredDevice {
+    #[serde(flatten)]
+    pub device: Device,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct Heartbeat {
+    /// Check-ins queued on the device, not yet synced
+    #[serde(default)]
+    pub pending_sync: u32,
+    #[validate(length(max = MAX_APP_VERSION_LEN, code = "out_of_range"))]
+    pub app_version: Option<String>,
+}
+
+/// A fresh device token
+pub fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// What is stored of a token
+pub fn hash_token(token: &str) -> String {
+    encode_hex(digest::digest(&digest::SHA256, token.as_bytes()).as_ref())
+}
+
+const COLUMNS: &str = "id, name, event_id, token_hash, pending_sync, app_version, last_seen_at, created_at";
+
+pub async fn create(
+    pool: &DbPool,
+    id: Uuid,
+    name: &str,
+    event_id: Uuid,
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<Device, sqlx::Error> {
+    sqlx::query_as::<_, Device>(&format!(
+        "INSERT INTO devices (id, name, event_id, token_hash, created_at) VALUES (?, ?, ?, ?, ?)
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(name)
+    .bind(event_id)
+    .bind(hash_token(token))
+    .bind(now)
+    .fetch_one(pool)
+    .await
+}
+
+/// The device a token belongs to
+pub async fn find_by_token(pool: &DbPool, token: &str) -> Result<Option<Device>, sqlx::Error> {
+    sqlx::query_as::<_, Device>(&format!("SELECT {} FROM devices WHERE token_hash = ?", COLUMNS))
+        .bind(hash_token(token))
+        .fetch_optional(pool)
+        .await
+}
+
+/// Record a heartbeat of `id`
+pub async fn beat(pool: &DbPool, id: Uuid, heartbeat: &Heartbeat, now: DateTime<Utc>) -> Result<Device, sqlx::Error> {
+    sqlx::query_as::<_, Device>(&format!(
+        "UPDATE devices
+         SET last_seen_at = ?, pending_sync = ?, app_version = COALESCE(?, app_version)
+         WHERE id = ?
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(now)
+    .bind(heartbeat.pending_sync)
+    .bind(heartbeat.app_version.as_deref())
+    .bind(id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Note that a device was heard from other than by a heartbeat
+pub async fn touch(pool: &DbPool, id: Uuid, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE devices SET last_seen_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// All devices, those silent the longest first, marking the ones heard
+/// from since `since` as online
+pub async fn list(pool: &DbPool, since: DateTime<Utc>) -> Result<Vec<DeviceStatus>, sqlx::Error> {
+    let devices = sqlx::query_as::<_, Device>(&format!(
+        "SELECT {} FROM devices ORDER BY last_seen_at IS NOT NULL, last_seen_at, created_at",
+        COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(devices
+        .into_iter()
+        .map(|device| DeviceStatus {
+            online: device.last_seen_at.is_some_and(|seen| seen >= since),
+            device,
+        })
+        .collect())
+}
+
+/// Revoke a device's token; returns whether it existed
+pub async fn delete(pool: &DbPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM devices WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Middleware resolving `X-Device-Token` into a `Device` extension.
+/// Requests without one pass; an unknown or revoked token is rejected.
+pub async fn authenticate(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(token) = request
+        .headers()
+        .get(DEVICE_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+    else {
+        return next.run(request).await;
+    };
+
+    match find_by_token(&state.db_pool, &token).await {
+        Ok(Some(device)) => {
+            request.extensions_mut().insert(device);
+            next.run(request).await
+        }
+        Ok(None) => api_error(StatusCode::UNAUTHORIZED, "Unknown or revoked device token").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load device: {}", e);
+            internal_error().into_response()
+        }
+    }
+}
+
+/// Extractor yielding the calling device, if any; never rejects
+#[derive(Debug, Clone)]
+pub struct KioskDevice(pub Option<Device>);
+
+impl KioskDevice {
+    /// Whether the caller is a device registered for `event_id`
+    pub fn serves(&self, event_id: Uuid) -> bool {
+        self.0.as_ref().is_some_and(|device| device.event_id == event_id)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for KioskDevice
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(KioskDevice(parts.extensions.get::<Device>().cloned()))
+    }
+}
+
+/// Extractor for device-only handlers; rejects other callers with 401
+#[derive(Debug, Clone)]
+pub struct CurrentDevice(pub Device);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentDevice
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Device>()
+            .cloned()
+            .map(CurrentDevice)
+            .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Device token required"))
+    }
+}
@@ -0,0 +1,225 @@
+//! Organizer digests: what changed on an event over a recent window.
+//!
+//! Status moves come from `participant_status_history`, which a trigger
+//! fills whenever a participant's status changes. The daily scheduler
+//! publishes `DomainEvent::DigestReady` for each event with activity so a
+//! mailer can send them; `GET /api/events/:id/digest` serves the same data
+//! on demand.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::encryption::PiiText;
+use crate::domain_events::{self, DomainEvent};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// A participant mentioned in a digest
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestEntry {
+    pub participant_id: Uuid,
+    /// None if the participant has since been deleted
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Activity on one event between `period_start` and `period_end`
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub event_id: Uuid,
+    pub event_title: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub new_registrations: Vec<DigestEntry>,
+    pub cancellations: Vec<DigestEntry>,
+    pub waitlist_joined: Vec<DigestEntry>,
+    /// Left the waitlist for a registered or confirmed seat
+    pub waitlist_promoted: Vec<DigestEntry>,
+    /// Current participant count per status
+    pub totals: BTreeMap<String, i64>,
+}
+
+impl Digest {
+    /// Whether anything happened in the period
+    pub fn has_activity(&self) -> bool {
+        !(self.new_registrations.is_empty()
+            && self.cancellations.is_empty()
+            && self.waitlist_joined.is_empty()
+            && self.waitlist_promoted.is_empty())
+    }
+
+    /// Plain-text rendering suitable for an email body
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Digest for \"{}\"", self.event_title);
+        let _ = writeln!(
+            out,
+            "{} to {}",
+            self.period_start.format("%Y-%m-%d %H:%M UTC"),
+            self.period_end.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        for (heading, entries) in [
+            ("New registrations", &self.new_registrations),
+            ("Cancellations", &self.cancellations),
+            ("Joined waitlist", &self.waitlist_joined),
+            ("Promoted from waitlist", &self.waitlist_promoted),
+        ] {
+            let _ = writeln!(out, "\n{} ({})", heading, entries.len());
+            for entry in entries {
+                let name = entry.name.as_deref().unwrap_or("(deleted participant)");
+                match &entry.email {
+                    Some(email) => { let _ = writeln!(out, "  - {} <{}>", name, email); }
+                    None => { let _ = writeln!(out, "  - {}", name); }
+                }
+            }
+        }
+
+        let _ = writeln!(out, "\nCurrent totals");
+        if self.totals.is_empty() {
+            let _ = writeln!(out, "  no participants");
+        }
+        for (status, count) in &self.totals {
+            let _ = writeln!(out, "  {}: {}", status, count);
+        }
+
+        out
+    }
+}
+
+/// Build the digest for one event; None if the event does not exist
+pub async fn build(
+    pool: &DbPool,
+    event_id: Uuid,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<Option<Digest>, sqlx::Error> {
+    let Some(event_title) = sqlx::query_scalar::<_, String>("SELECT title FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let new_registrations = sqlx::query_as::<_, (Uuid, PiiText, PiiText, DateTime<Utc>)>(
+        "SELECT id, name, email, registered_at FROM participants
+         WHERE event_id = ? AND registered_at >= ? AND registered_at < ?
+         ORDER BY registered_at ASC"
+    )
+    .bind(event_id)
+    .bind(period_start)
+    .bind(period_end)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(participant_id, name, email, at)| DigestEntry {
+        participant_id,
+        name: Some(name.into()),
+        email: Some(email.into()),
+        at,
+    })
+    .collect();
+
+    let moves = sqlx::query_as::<_, (Uuid, Option<PiiText>, Option<PiiText>, String, String, DateTime<Utc>)>(
+        "SELECT h.participant_id, p.name, p.email, h.from_status, h.to_status, h.changed_at
+         FROM participant_status_history h
+         LEFT JOIN participants p ON p.id = h.participant_id
+         WHERE h.event_id = ? AND h.changed_at >= ? AND h.changed_at < ?
+         ORDER BY h.changed_at ASC, h.id ASC"
+    )
+    .bind(event_id)
+    .bind(period_start)
+    .bind(period_end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut cancellations = Vec::new();
+    let mut waitlist_joined = Vec::new();
+    let mut waitlist_promoted = Vec::new();
+    for (participant_id, name, email, from, to, at) in moves {
+        let entry = DigestEntry {
+            participant_id,
+            name: name.map(String::from),
+            email: email.map(String::from),
+            at,
+        };
+        match (from.as_str(), to.as_str()) {
+            (_, "cancelled") => cancellations.push(entry),
+            (_, "waitlisted") => waitlist_joined.push(entry),
+            ("waitlisted", "registered" | "confirmed") => waitlist_promoted.push(entry),
+            _ => {}
+        }
+    }
+
+    let totals = sqlx::query_as::<_, (String, i64)>(
+        "SELECT status, count(*) FROM participants WHERE event_id = ? GROUP BY status"
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    Ok(Some(Digest {
+        event_id,
+        event_title,
+        period_start,
+        period_end,
+        new_registrations,
+        cancellations,
+        waitlist_joined,
+        waitlist_promoted,
+        totals,
+    }))
+}
+
+/// Events with registrations or status changes since `since`
+async fn active_events(pool: &DbPool, since: DateTime<Utc>) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT event_id FROM participants WHERE registered_at >= ?
+         UNION
+         SELECT event_id FROM participant_status_history WHERE changed_at >= ?"
+    )
+    .bind(since)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Publish a digest for every active event once per `interval`
+pub async fn start_scheduler(state: AppState, interval: Duration) {
+    let window = chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::days(1));
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let now = state.clock.now();
+        let since = now - window;
+        let event_ids = match active_events(&state.db_pool, since).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("Failed to find events for digests: {}", e);
+                continue;
+            }
+        };
+
+        for event_id in event_ids {
+            match build(&state.db_pool, event_id, since, now).await {
+                Ok(Some(digest)) if digest.has_activity() => {
+                    domain_events::publish(&state, DomainEvent::DigestReady { digest }).await;
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to build digest for event {}: {}", event_id, e),
+            }
+        }
+    }
+}
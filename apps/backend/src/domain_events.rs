@@ -0,0 +1,210 @@
+//! Typed domain events for in-process consumers.
+//!
+//! Mutation handlers describe what happened with a single
+//! `domain_events::publish` call. That call performs the side-effects every
+//! mutation shares: cache invalidation and the cross-instance SSE
+//! notification. It then hands the event to the `DomainEventBus`, where
+//! subsystems such as mailers, webhooks and stats subscribe without
+//! handlers having to know about them.
+//!
+//! The bus is local to this instance: subscribers see mutations handled
+//! here, not those of other instances, which arrive only through the
+//! notification poller.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::broadcaster::Channel;
+use crate::db;
+use crate::digest::Digest;
+use crate::models::{Event, Participant, ParticipantCounts, ParticipantStatus};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Something that happened to an event or participant
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    EventCreated { event: Event },
+    EventUpdated { event: Event },
+    /// `participants` are the registrations removed along with the event
+    EventDeleted { event: Event, participants: Vec<Participant> },
+    EventFreezeChanged { event: Event },
+    /// A draft event was published
+    EventPublished { event: Event },
+    /// An ended event was closed by the scheduler
+    EventCompleted { event: Event },
+    /// A recurring series was created, changed or deleted; its occurrence
+    /// events publish their own changes
+    SeriesChanged { series_id: Uuid },
+    /// `counts` are the event's registrations right after the change,
+    /// read in the transaction that made it
+    ParticipantRegistered { participant: Participant, counts: ParticipantCounts },
+    /// `previous` is the status the participant held before the change
+    ParticipantStatusChanged {
+        participant: Participant,
+        previous: ParticipantStatus,
+        counts: ParticipantCounts,
+    },
+    ParticipantRemoved { participant: Participant, counts: ParticipantCounts },
+    /// A waitlisted participant took a place freed by someone else
+    ParticipantPromoted { participant: Participant, counts: ParticipantCounts },
+    /// A participant arrived at the event
+    ParticipantCheckedIn { participant: Participant, counts: ParticipantCounts },
+    /// A participant's details changed, not their registration
+    ParticipantDetailsChanged { participant: Participant, counts: ParticipantCounts },
+    /// A scheduled organizer digest; local only, nothing to invalidate
+    DigestReady { digest: Digest },
+}
+
+impl DomainEvent {
+    /// SSE channel and payload announcing this change to all instances
+    fn notification(&self, now: DateTime<Utc>) -> Option<(Channel, serde_json::Value)> {
+        let notification = match self {
+            DomainEvent::EventCreated { event } => (Channel::EventChanges, event_payload("INSERT", event.id, now)),
+            DomainEvent::EventUpdated { event } => (Channel::EventChanges, event_payload("UPDATE", event.id, now)),
+            DomainEvent::EventDeleted { event, .. } => (Channel::EventChanges, event_payload("DELETE", event.id, now)),
+            DomainEvent::EventFreezeChanged { event } => {
+                let operation = if event.frozen { "FREEZE" } else { "UNFREEZE" };
+                (Channel::EventChanges, event_payload(operation, event.id, now))
+            }
+            DomainEvent::EventPublished { event } => (Channel::EventChanges, event_payload("PUBLISH", event.id, now)),
+            DomainEvent::EventCompleted { event } => (Channel::EventChanges, event_payload("COMPLETE", event.id, now)),
+            DomainEvent::SeriesChanged { series_id } => (
+                Channel::EventChanges,
+                json!({
+                    "operation": "UPDATE",
+                    "table": "event_series",
+                    "id": series_id,
+                    "timestamp": now
+                }),
+            ),
+            DomainEvent::ParticipantRegistered { participant, counts } => (
+                Channel::ParticipantChanges,
+                participant_payload("INSERT", participant, counts, now),
+            ),
+            DomainEvent::ParticipantStatusChanged { participant, counts, .. } => (
+                Channel::ParticipantChanges,
+                participant_payload("UPDATE", participant, counts, now),
+            ),
+            DomainEvent::ParticipantRemoved { participant, counts } => (
+                Channel::ParticipantChanges,
+                participant_payload("DELETE", participant, counts, now),
+            ),
+            DomainEvent::ParticipantPromoted { participant, counts } => (
+                Channel::ParticipantChanges,
+                participant_payload("PROMOTE", participant, counts, now),
+            ),
+            DomainEvent::ParticipantCheckedIn { participant, counts } => (
+                Channel::ParticipantChanges,
+                participant_payload("CHECKIN", participant, counts, now),
+            ),
+            DomainEvent::ParticipantDetailsChanged { participant, counts } => (
+                Channel::ParticipantChanges,
+                participant_payload("UPDATE", participant, counts, now),
+            ),
+            DomainEvent::DigestReady { .. } => return None,
+        };
+
+        Some(notification)
+    }
+}
+
+fn event_payload(operation: &str, id: Uuid, now: DateTime<Utc>) -> serde_json::Value {
+    json!({
+        "operation": operation,
+        "table": "events",
+        "id": id,
+        "timestamp": now
+    })
+}
+
+fn participant_payload(
+    operation: &str,
+    participant: &Participant,
+    counts: &ParticipantCounts,
+    now: DateTime<Utc>,
+) -> serde_json::Value {
+    json!({
+        "operation": operation,
+        "table": "participants",
+        "id": participant.id,
+        "event_id": participant.event_id,
+        "counts": counts,
+        "timestamp": now
+    })
+}
+
+/// In-process fan-out of domain events
+#[derive(Clone)]
+pub struct DomainEventBus {
+    sender: Arc<broadcast::Sender<DomainEvent>>,
+}
+
+impl DomainEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    /// Receive every event published after this call. Slow subscribers
+    /// miss events (`RecvError::Lagged`) rather than block handlers.
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.sender.subscribe()
+    }
+
+    fn send(&self, event: DomainEvent) {
+        // No subscribers is fine
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for DomainEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Record a committed change: invalidate caches, notify other instances
+/// and hand the event to local subscribers
+pub async fn publish(state: &AppState, event: DomainEvent) {
+    match &event {
+        DomainEvent::EventCreated { event }
+        | DomainEvent::EventUpdated { event }
+        | DomainEvent::EventFreezeChanged { event }
+        | DomainEvent::EventPublished { event }
+        | DomainEvent::EventCompleted { event } => {
+            state.cache.invalidate_event(&event.id.to_string(), &event.tags).await
+        }
+        DomainEvent::EventDeleted { event, .. } => {
+            state.cache.invalidate_event(&event.id.to_string(), &event.tags).await;
+            state.cache.invalidate_participants().await;
+        }
+        DomainEvent::SeriesChanged { series_id } => {
+            state.cache.series.remove(&series_id.to_string()).await;
+        }
+        DomainEvent::ParticipantRegistered { .. }
+        | DomainEvent::ParticipantStatusChanged { .. }
+        | DomainEvent::ParticipantRemoved { .. }
+        | DomainEvent::ParticipantPromoted { .. }
+        | DomainEvent::ParticipantCheckedIn { .. }
+        | DomainEvent::ParticipantDetailsChanged { .. } => state.cache.invalidate_participants().await,
+        DomainEvent::DigestReady { .. } => {}
+    }
+
+    let now = state.clock.now();
+    if let Some((channel, payload)) = event.notification(now) {
+        if let Err(e) = db::insert_notification(&state.db_pool, channel, &payload.to_string(), now).await {
+            tracing::error!("Failed to insert {} notification: {}", channel, e);
+        }
+    }
+
+    state.domain_events.send(event);
+}
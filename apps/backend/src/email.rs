@@ -0,0 +1,156 @@
+//! Email address validation and normalization for participants.
+//!
+//! Every participant write stores the address as `normalize` returns it:
+//! trimmed and lowercased. Duplicate detection (the `dedupe_key` index, see
+//! `models::EmailPolicy`) compares stored addresses byte for byte, so
+//! `John@Example.com ` and `john@example.com` only count as the same
+//! registrant once both are normalized. Mail providers treat the local part
+//! case-insensitively in practice, so lowercasing it loses nothing.
+//!
+//! `syntax` accepts the dot-atom addresses people actually type, including
+//! internationalized ones; quoted local parts, comments and IP literals are
+//! rejected. Built with the `mx-check` feature, `MxRecords` additionally
+//! rejects domains that cannot receive mail (see `EMAIL_MX_CHECK`).
+
+use std::borrow::Cow;
+use validator::ValidationError;
+
+/// Longest local part (before the `@`)
+pub const MAX_LOCAL_LEN: usize = 64;
+/// Longest domain
+pub const MAX_DOMAIN_LEN: usize = 253;
+/// Longest domain label
+pub const MAX_LABEL_LEN: usize = 63;
+
+/// Characters a local part may contain besides letters and digits
+const LOCAL_SPECIALS: &str = "!#$%&'*+/=?^_`{|}~-.";
+
+/// The form addresses are stored and compared in
+pub fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn invalid(message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new("invalid_email").with_message(message.into())
+}
+
+fn dot_separated(value: &str) -> bool {
+    !value.starts_with('.') && !value.ends_with('.') && !value.contains("..")
+}
+
+/// Check the syntax of a (normalized) address
+pub fn syntax(value: &str) -> Result<(), ValidationError> {
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return Err(invalid("must be a valid email address"));
+    };
+
+    if local.is_empty() || local.chars().count() > MAX_LOCAL_LEN {
+        return Err(invalid(format!(
+            "the part before '@' must be 1 to {} characters",
+            MAX_LOCAL_LEN
+        )));
+    }
+    let local_chars = local
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || LOCAL_SPECIALS.contains(c) || (!c.is_ascii() && !c.is_whitespace() && !c.is_control()));
+    if !local_chars || !dot_separated(local) {
+        return Err(invalid("must be a valid email address"));
+    }
+
+    if domain.is_empty() || domain.chars().count() > MAX_DOMAIN_LEN || !dot_separated(domain) {
+        return Err(invalid("must have a valid domain"));
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return Err(invalid("must have a valid domain"));
+    }
+    for label in &labels {
+        let valid = label.chars().count() <= MAX_LABEL_LEN
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-');
+        if !valid {
+            return Err(invalid("must have a valid domain"));
+        }
+    }
+    // Top-level domains are never numeric; this also rejects bare IPs
+    if labels.last().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit())) {
+        return Err(invalid("must have a valid domain"));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "mx-check")]
+pub use mx::MxRecords;
+
+#[cfg(feature = "mx-check")]
+mod mx {
+    use axum::async_trait;
+    use hickory_resolver::error::ResolveErrorKind;
+    use hickory_resolver::TokioAsyncResolver;
+
+    use crate::reputation::{EmailReputation, Verdict};
+
+    /// Rejects addresses whose domain has no mail exchanger: neither an MX
+    /// record nor, as the fallback RFC 5321 allows, an address record, or
+    /// only a null MX (RFC 7505). Lookup failures other than "no such
+    /// records" accept the address, so a DNS outage does not stop
+    /// registrations.
+    pub struct MxRecords {
+        resolver: TokioAsyncResolver,
+    }
+
+    impl MxRecords {
+        /// Resolve with the system's DNS configuration
+        pub fn from_system_conf() -> Result<Self, hickory_resolver::error::ResolveError> {
+            Ok(Self {
+                resolver: TokioAsyncResolver::tokio_from_system_conf()?,
+            })
+        }
+
+        fn reject() -> Verdict {
+            Verdict::Reject {
+                code: "undeliverable_email",
+                message: "Email domain does not accept mail".to_string(),
+            }
+        }
+    }
+
+    fn no_records(e: &hickory_resolver::error::ResolveError) -> bool {
+        matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. })
+    }
+
+    #[async_trait]
+    impl EmailReputation for MxRecords {
+        async fn check(&self, email: &str) -> Verdict {
+            let Some((_, domain)) = email.rsplit_once('@') else {
+                return Verdict::Accept;
+            };
+            // Fully qualified, so resolv.conf search domains are not tried
+            let fqdn = format!("{}.", domain);
+
+            match self.resolver.mx_lookup(fqdn.as_str()).await {
+                Ok(records) => {
+                    if records.iter().all(|mx| mx.exchange().is_root()) {
+                        Self::reject()
+                    } else {
+                        Verdict::Accept
+                    }
+                }
+                Err(e) if no_records(&e) => match self.resolver.lookup_ip(fqdn.as_str()).await {
+                    Ok(_) => Verdict::Accept,
+                    Err(e) if no_records(&e) => Self::reject(),
+                    Err(e) => {
+                        tracing::warn!("Address lookup for {} failed: {}", domain, e);
+                        Verdict::Accept
+                    }
+                },
+                Err(e) => {
+                    tracing::warn!("MX lookup for {} failed: {}", domain, e);
+                    Verdict::Accept
+                }
+            }
+        }
+    }
+}
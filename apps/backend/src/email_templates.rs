@@ -0,0 +1,310 @@
+//! Organizer-defined wording for participant mails.
+//!
+//! Templates carry a subject and a plain-text body with handlebars-style
+//! placeholders such as `{{name}}` or `{{ event.title }}`. Only the
+//! placeholders in `PLACEHOLDERS` are accepted, so a typo is rejected when
+//! the template is saved instead of reaching participants verbatim.
+//!
+//! Confirmation and waitlist templates replace the built-in wording of
+//! registration mails; a template for the event wins over one for all
+//! events. Announcement templates are sent on demand to everyone holding a
+//! place.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::borrow::Cow;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::db::DbPool;
+use crate::models::{Event, Participant};
+use crate::validation;
+
+/// Placeholders a template may use
+pub const PLACEHOLDERS: &[&str] = &[
+    "name",
+    "email",
+    "event.title",
+    "event.description",
+    "event.location",
+    "event.start",
+    "event.end",
+];
+
+/// Longest accepted template subject
+pub const MAX_SUBJECT_LEN: u64 = 200;
+/// Longest accepted template body
+pub const MAX_BODY_LEN: u64 = 10_000;
+
+/// Which mail a template words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TemplateKind {
+    /// Registration that holds a place
+    Confirmation,
+    /// Registration put on the waitlist
+    Waitlisted,
+    /// Message sent on demand to everyone holding a place
+    Announcement,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EmailTemplate {
+    pub id: Uuid,
+    /// The event the template applies to; all events when empty
+    pub event_id: Option<Uuid>,
+    pub kind: TemplateKind,
+    pub name: String,
+    pub subject: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailTemplateInput {
+    pub event_id: Option<Uuid>,
+    pub kind: TemplateKind,
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = 100, code = "too_long", message = "must be at most 100 characters")
+    )]
+    pub name: String,
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_SUBJECT_LEN, code = "too_long", message = "must be at most 200 characters"),
+        custom(function = "known_placeholders")
+    )]
+    pub subject: String,
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_BODY_LEN, code = "too_long", message = "must be at most 10000 characters"),
+        custom(function = "known_placeholders")
+    )]
+    pub body: String,
+}
+
+/// A piece of template text
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split `text` into literal text and placeholders; an unclosed `{{` is an
+/// error
+fn segments(text: &str) -> Result<Vec<Segment<'_>>, ValidationError> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else {
+            return Err(ValidationError::new("unclosed_placeholder")
+                .with_message(Cow::Borrowed("has a '{{' without a matching '}}'")));
+        };
+        segments.push(Segment::Text(&rest[..open]));
+        segments.push(Segment::Placeholder(rest[open + 2..open + 2 + close].trim()));
+        rest = &rest[open + 2 + close + 2..];
+    }
+    segments.push(Segment::Text(rest));
+    Ok(segments)
+}
+
+/// Reject placeholders outside `PLACEHOLDERS`
+pub fn known_placeholders(text: &str) -> Result<(), ValidationError> {
+    placeholders_allowed(text, |name| PLACEHOLDERS.contains(&name), &PLACEHOLDERS.join(", "))
+}
+
+/// Reject placeholders `allowed` refuses; `hint` lists the accepted ones in
+/// the error message
+pub fn placeholders_allowed(text: &str, allowed: impl Fn(&str) -> bool, hint: &str) -> Result<(), ValidationError> {
+    for segment in segments(text)? {
+        if let Segment::Placeholder(name) = segment {
+            if !allowed(name) {
+                let mut err = ValidationError::new("unknown_placeholder").with_message(Cow::Owned(format!(
+                    "unknown placeholder '{{{{{}}}}}' (allowed: {})",
+                    name, hint
+                )));
+                err.add_param(Cow::Borrowed("placeholder"), &name);
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Values substituted for the placeholders
+#[derive(Debug, Clone)]
+pub struct Values<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub event: &'a Event,
+}
+
+impl<'a> Values<'a> {
+    pub fn new(event: &'a Event, participant: &'a Participant) -> Self {
+        Self {
+            name: &participant.name,
+            email: &participant.email,
+            event,
+        }
+    }
+
+    pub fn get(&self, placeholder: &str) -> Option<String> {
+        let time = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M UTC").to_string();
+        Some(match placeholder {
+            "name" => self.name.to_string(),
+            "email" => self.email.to_string(),
+            "event.title" => self.event.title.clone(),
+            "event.description" => self.event.description.clone().unwrap_or_default(),
+            "event.location" => self.event.location.clone().unwrap_or_else(|| "TBA".to_string()),
+            "event.start" => time(self.event.start_time),
+            "event.end" => time(self.event.end_time),
+            _ => return None,
+        })
+    }
+}
+
+/// Fill in the placeholders of `text`. Text that does not parse, which
+/// validation keeps out of the database, is returned unchanged.
+pub fn render(text: &str, values: &Values) -> String {
+    render_with(text, |name| values.get(name))
+}
+
+/// Fill in the placeholders of `text` with what `lookup` returns for them;
+/// placeholders it has no value for are kept as they are
+pub fn render_with(text: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let Ok(segments) = segments(text) else {
+        return text.to_string();
+    };
+    let mut rendered = String::with_capacity(text.len());
+    for segment in segments {
+        match segment {
+            Segment::Text(t) => rendered.push_str(t),
+            Segment::Placeholder(name) => match lookup(name) {
+                Some(value) => rendered.push_str(&value),
+                None => {
+                    rendered.push_str("{{");
+                    rendered.push_str(name);
+                    rendered.push_str("}}");
+                }
+            },
+        }
+    }
+    rendered
+}
+
+/// A rendered subject and body
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedMail {
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailTemplate {
+    pub fn render(&self, values: &Values) -> RenderedMail {
+        RenderedMail {
+            subject: render(&self.subject, values),
+            body: render(&self.body, values),
+        }
+    }
+}
+
+const COLUMNS: &str = "id, event_id, kind, name, subject, body, created_at, updated_at";
+
+pub async fn find(pool: &DbPool, id: Uuid) -> Result<Option<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!("SELECT {} FROM email_templates WHERE id = ?", COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Templates of one event and those for all events; all templates when
+/// `event_id` is empty. With `owner`, only templates of that organizer's
+/// events and those for all events.
+pub async fn list(pool: &DbPool, event_id: Option<Uuid>, owner: Option<Uuid>) -> Result<Vec<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!(
+        "SELECT {} FROM email_templates
+         WHERE (?1 IS NULL OR event_id = ?1 OR event_id IS NULL)
+           AND (?2 IS NULL OR event_id IS NULL OR event_id IN (SELECT id FROM events WHERE organizer_id = ?2))
+         ORDER BY kind, event_id IS NULL, name",
+        COLUMNS
+    ))
+    .bind(event_id)
+    .bind(owner)
+    .fetch_all(pool)
+    .await
+}
+
+/// The template used for `kind` mails about `event_id`: the event's own,
+/// else the one for all events
+pub async fn for_event(pool: &DbPool, kind: TemplateKind, event_id: Uuid) -> Result<Option<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!(
+        "SELECT {} FROM email_templates
+         WHERE kind = ? AND (event_id = ? OR event_id IS NULL)
+         ORDER BY event_id IS NULL
+         LIMIT 1",
+        COLUMNS
+    ))
+    .bind(kind)
+    .bind(event_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn insert(
+    pool: &DbPool,
+    id: Uuid,
+    input: &EmailTemplateInput,
+    now: DateTime<Utc>,
+) -> Result<EmailTemplate, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!(
+        "INSERT INTO email_templates (id, event_id, kind, name, subject, body, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(input.event_id)
+    .bind(input.kind)
+    .bind(input.name.trim())
+    .bind(&input.subject)
+    .bind(&input.body)
+    .bind(now)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn update(
+    pool: &DbPool,
+    id: Uuid,
+    input: &EmailTemplateInput,
+    now: DateTime<Utc>,
+) -> Result<Option<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, EmailTemplate>(&format!(
+        "UPDATE email_templates
+         SET event_id = ?, kind = ?, name = ?, subject = ?, body = ?, updated_at = ?
+         WHERE id = ?
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(input.event_id)
+    .bind(input.kind)
+    .bind(input.name.trim())
+    .bind(&input.subject)
+    .bind(&input.body)
+    .bind(now)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete(pool: &DbPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM email_templates WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,283 @@
+//! Encryption of personal data at rest.
+//!
+//! Values are sealed with AES-256-GCM under PII_ENCRYPTION_KEY (64 hex
+//! digits) and stored as the random nonce followed by the ciphertext, so
+//! the same value never encrypts the same way twice. Without a key dates
+//! of birth are not stored at all.
+//!
+//! Participant names and emails are stored either way, so they are sealed
+//! by the column type itself: bind and read them as `PiiText`, and they are
+//! sealed under the key `install`ed at startup and opened again, with rows
+//! written before the key was set read as the plain text they are. The
+//! key is process-wide because decoding a row has no access to the app
+//! state. Lookups that used to compare plain text go through
+//! `blind_index`, a keyed hash kept in `participants.email_index` and in
+//! the dedupe keys, and searches filter opened rows in memory.
+//!
+//! Mails and webhook calls waiting to go out, or kept after failing, carry
+//! the same details: the addresses and names of queued and failed mails
+//! and webhook payloads are `PiiText` as well, with addresses indexed in
+//! `to_address_index`.
+//!
+//! `seal_participants` and `seal_deliveries` run after the migrations and
+//! seal rows still in plain text, so setting the key on an existing
+//! database encrypts it on the next start. Every instance must run a build with this module before
+//! the key is set: older builds cannot read sealed rows.
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Sqlite, SqliteConnection, Type, TypeInfo, ValueRef};
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+use crate::export::{decode_hex, encode_hex};
+use crate::models::EmailPolicy;
+
+/// Key sealing personal data
+#[derive(Clone, PartialEq, Eq)]
+pub struct PiiKey([u8; 32]);
+
+impl std::fmt::Debug for PiiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PiiKey(..)")
+    }
+}
+
+impl std::str::FromStr for PiiKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_hex(s.trim())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(Self)
+            .ok_or_else(|| "expected 64 hex digits".to_string())
+    }
+}
+
+impl PiiKey {
+    fn key(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.0).expect("32 bytes is an AES-256 key"))
+    }
+
+    /// Nonce and ciphertext of `plaintext`
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).expect("system randomness available");
+
+        let mut sealed = plaintext.to_vec();
+        self.key()
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
+            .expect("plaintext fits AES-GCM limits");
+        [nonce.as_slice(), &sealed].concat()
+    }
+
+    /// The plaintext of `encrypt`'s output; None if it was not sealed with
+    /// this key or has been tampered with
+    pub fn decrypt(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+
+        let mut opened = ciphertext.to_vec();
+        let plaintext = self.key().open_in_place(nonce, Aad::empty(), &mut opened).ok()?;
+        Some(plaintext.to_vec())
+    }
+
+    /// Hex HMAC of `value` under a key derived from this one, so equal
+    /// values can be found without storing them
+    pub fn blind_index(&self, value: &str) -> String {
+        let derived = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &self.0), b"participant blind index");
+        let key = hmac::Key::new(hmac::HMAC_SHA256, derived.as_ref());
+        encode_hex(hmac::sign(&key, value.as_bytes()).as_ref())
+    }
+}
+
+/// Key for personal data columns, set once per process
+static STORAGE_KEY: OnceLock<PiiKey> = OnceLock::new();
+
+/// Seal personal data columns under `key` from now on. Installing the key
+/// already installed is a no-op; another one is an error.
+pub fn install(key: &PiiKey) -> Result<(), String> {
+    if STORAGE_KEY.get_or_init(|| key.clone()) == key {
+        Ok(())
+    } else {
+        Err("a different PII key is already installed".to_string())
+    }
+}
+
+/// The installed key, if any
+pub fn storage_key() -> Option<&'static PiiKey> {
+    STORAGE_KEY.get()
+}
+
+/// How `value` is looked up: its blind index under the installed key, or
+/// the value itself without one
+pub fn blind_index(value: &str) -> String {
+    match storage_key() {
+        Some(key) => key.blind_index(value),
+        None => value.to_string(),
+    }
+}
+
+/// A personal data column: sealed under the installed key when bound,
+/// opened when read. Plain text is written without a key and read as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiText(pub String);
+
+impl From<PiiText> for String {
+    fn from(text: PiiText) -> Self {
+        text.0
+    }
+}
+
+impl Type<Sqlite> for PiiText {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <[u8] as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for PiiText {
+    fn encode_by_ref(&self, args: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        match storage_key() {
+            Some(key) => <Vec<u8> as Encode<Sqlite>>::encode(key.encrypt(self.0.as_bytes()), args),
+            None => <String as Encode<Sqlite>>::encode_by_ref(&self.0, args),
+        }
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for PiiText {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.type_info().name() != "BLOB" {
+            return <String as Decode<Sqlite>>::decode(value).map(Self);
+        }
+        let sealed = <&[u8] as Decode<Sqlite>>::decode(value)?;
+        let key = storage_key().ok_or("personal data is encrypted but no PII key is installed")?;
+        let plain = key.decrypt(sealed).ok_or("personal data was not sealed with the installed PII key")?;
+        Ok(Self(String::from_utf8(plain)?))
+    }
+}
+
+/// Seal participants still stored in plain text and derive their email
+/// index and dedupe key under the installed key; rows without an email
+/// index, such as imported ones, get one too. Without a key nothing is
+/// sealed, and sealed rows are an error since they cannot be read.
+pub async fn seal_participants(conn: &mut SqliteConnection) -> Result<u64, sqlx::Error> {
+    let key = storage_key();
+    if key.is_none() {
+        let sealed = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM participants WHERE typeof(name) = 'blob' OR typeof(email) = 'blob'"
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if sealed > 0 {
+            return Err(sqlx::Error::Configuration(
+                "participant names and emails are encrypted; set PII_ENCRYPTION_KEY".into(),
+            ));
+        }
+    }
+
+    let rows = sqlx::query_as::<_, (Uuid, PiiText, PiiText, EmailPolicy)>(
+        "SELECT p.id, p.name, p.email, e.email_policy
+         FROM participants p JOIN events e ON e.id = p.event_id
+         WHERE p.email_index IS NULL OR (?1 AND (typeof(p.name) = 'text' OR typeof(p.email) = 'text'))"
+    )
+    .bind(key.is_some())
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (id, name, email, policy) in &rows {
+        sqlx::query("UPDATE participants SET name = ?, email = ?, email_index = ?, dedupe_key = ? WHERE id = ?")
+            .bind(name)
+            .bind(email)
+            .bind(blind_index(&email.0))
+            .bind(policy.dedupe_key(&email.0, &name.0))
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(rows.len() as u64)
+}
+
+/// Seal the addresses, names and payloads of queued and failed mails and
+/// webhook deliveries still stored in plain text, indexing the addresses
+/// under the installed key; rows without an address index, such as
+/// imported ones, get one too. Like `seal_participants`, sealed rows
+/// without a key are an error.
+pub async fn seal_deliveries(conn: &mut SqliteConnection) -> Result<u64, sqlx::Error> {
+    let key = storage_key();
+    if key.is_none() {
+        let sealed = sqlx::query_scalar::<_, i64>(
+            "SELECT (SELECT count(*) FROM mail_failures WHERE typeof(to_address) = 'blob' OR typeof(mail) = 'blob')
+                  + (SELECT count(*) FROM bulk_mail_recipients WHERE typeof(to_address) = 'blob' OR typeof(to_name) = 'blob')
+                  + (SELECT count(*) FROM webhook_deliveries WHERE typeof(payload) = 'blob')"
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if sealed > 0 {
+            return Err(sqlx::Error::Configuration(
+                "mail addresses and webhook payloads are encrypted; set PII_ENCRYPTION_KEY".into(),
+            ));
+        }
+    }
+
+    let failures = sqlx::query_as::<_, (i64, PiiText, PiiText)>(
+        "SELECT id, to_address, mail FROM mail_failures
+         WHERE to_address_index IS NULL OR (?1 AND (typeof(to_address) = 'text' OR typeof(mail) = 'text'))"
+    )
+    .bind(key.is_some())
+    .fetch_all(&mut *conn)
+    .await?;
+    for (id, to_address, mail) in &failures {
+        sqlx::query("UPDATE mail_failures SET to_address = ?, to_address_index = ?, mail = ? WHERE id = ?")
+            .bind(to_address)
+            .bind(blind_index(&to_address.0))
+            .bind(mail)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let recipients = sqlx::query_as::<_, (i64, PiiText, PiiText)>(
+        "SELECT rowid, to_name, to_address FROM bulk_mail_recipients
+         WHERE to_address_index IS NULL OR (?1 AND (typeof(to_name) = 'text' OR typeof(to_address) = 'text'))"
+    )
+    .bind(key.is_some())
+    .fetch_all(&mut *conn)
+    .await?;
+    for (rowid, to_name, to_address) in &recipients {
+        sqlx::query("UPDATE bulk_mail_recipients SET to_name = ?, to_address = ?, to_address_index = ? WHERE rowid = ?")
+            .bind(to_name)
+            .bind(to_address)
+            .bind(blind_index(&to_address.0))
+            .bind(rowid)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let deliveries = sqlx::query_as::<_, (Uuid, PiiText)>(
+        "SELECT id, payload FROM webhook_deliveries WHERE ?1 AND typeof(payload) = 'text'"
+    )
+    .bind(key.is_some())
+    .fetch_all(&mut *conn)
+    .await?;
+    for (id, payload) in &deliveries {
+        sqlx::query("UPDATE webhook_deliveries SET payload = ? WHERE id = ?")
+            .bind(payload)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok((failures.len() + recipients.len() + deliveries.len()) as u64)
+}
@@ -0,0 +1,16 @@
+use axum::{http::StatusCode, Json};
+use serde_json::json;
+
+/// Error response returned by handlers: a status code and a JSON body of
+/// the form `{ "error": "..." }`, optionally with extra detail fields
+pub type ApiError = (StatusCode, Json<serde_json::Value>);
+
+/// Build an error response with a plain message
+pub fn api_error(status: StatusCode, message: &str) -> ApiError {
+    (status, Json(json!({ "error": message })))
+}
+
+/// Generic 500 response; the cause should already have been logged
+pub fn internal_error() -> ApiError {
+    api_error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+}
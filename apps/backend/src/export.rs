@@ -0,0 +1,422 @@
+//! Full database export and import as JSON Lines.
+//!
+//! Unlike a backup, the export does not depend on the SQLite file format or
+//! the schema it was taken with. The first line is a header naming the
+//! schema version; every further line is one table row, keyed by column
+//! name. Values keep their SQLite storage class; BLOBs, such as ids, are
+//! written as `{"$blob": "<hex>"}`. Rows are read in a single transaction,
+//! so the export is a consistent snapshot.
+//!
+//! Importing restores an export into an empty database of the same or a
+//! newer schema: columns the schema no longer has are dropped, columns it
+//! gained take their defaults. Events and participants pass the same
+//! validation as when created through the API, and everything is written
+//! in one transaction, so a rejected import leaves nothing behind.
+
+use axum::{body::Bytes, http::StatusCode};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column as _, Row, Sqlite, SqliteConnection, TypeInfo, ValueRef};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::mpsc;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::db::DbPool;
+use crate::encryption;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::migrations;
+use crate::models::{CreateEvent, EmailPolicy};
+use crate::routes::participants::rekey_participants;
+use crate::validation::{self, FieldLimits};
+
+/// Version of the line format itself, independent of the schema
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Exported tables, parents before the rows referring to them. Sessions,
+/// instance heartbeats, confirmation tokens, kiosk devices and change
+/// notifications are transient and left out.
+pub const TABLES: &[&str] = &[
+    "organizers",
+    "event_series",
+    "event_series_exclusions",
+    "events",
+    "tags",
+    "event_tags",
+    "participants",
+    "participant_consents",
+    "reminder_preferences",
+    "cancellation_tokens",
+    "event_form_fields",
+    "participant_answers",
+    "event_seat_maps",
+    "seat_assignments",
+    "capacity_holds",
+    "referrals",
+    "event_view_counts",
+    "reminders_sent",
+    "participant_status_history",
+    "certificates",
+    "settings",
+    "audit_log",
+    "mail_failures",
+    "bulk_mails",
+    "bulk_mail_recipients",
+    "webhooks",
+    "webhook_deliveries",
+];
+
+/// Largest export accepted for import
+pub const MAX_IMPORT_BYTES: usize = 512 * 1024 * 1024;
+
+/// Tables an import may find rows in; existing settings are replaced
+const MERGED_TABLES: &[&str] = &["settings"];
+
+/// One line of an export
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    Header {
+        format_version: u32,
+        schema_version: i64,
+        exported_at: DateTime<Utc>,
+        tables: Vec<String>,
+    },
+    Row {
+        table: String,
+        row: Map<String, Value>,
+    },
+}
+
+/// Rows restored per table
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    /// Schema version the export was taken with
+    pub schema_version: i64,
+    pub rows: BTreeMap<String, u64>,
+    /// Rows of tables this schema no longer has
+    pub skipped: u64,
+}
+
+/// Key marking a hex-encoded BLOB value
+pub(crate) const BLOB_KEY: &str = "$blob";
+
+/// Column names of a table in the current schema
+async fn columns(conn: &mut SqliteConnection, table: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A row as a JSON object, by the storage class of each value
+pub(crate) fn row_to_json(row: &SqliteRow) -> Result<Map<String, Value>, sqlx::Error> {
+    let mut object = Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(i)?),
+                "REAL" => Value::from(row.try_get::<f64, _>(i)?),
+                "BLOB" => {
+                    let bytes = row.try_get::<Vec<u8>, _>(i)?;
+                    serde_json::json!({ BLOB_KEY: encode_hex(&bytes) })
+                }
+                _ => Value::from(row.try_get::<String, _>(i)?),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(object)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn line(record: &Record) -> Bytes {
+    let mut line = serde_json::to_vec(record).expect("records serialize");
+    line.push(b'\n');
+    Bytes::from(line)
+}
+
+/// Write the export to `tx`, one line per chunk, until done or the client
+/// disconnects; a failure is passed on to abort the response body
+pub async fn write(pool: DbPool, tx: mpsc::Sender<Result<Bytes, std::io::Error>>, now: DateTime<Utc>) {
+    if let Err(e) = write_records(&pool, &tx, now).await {
+        tracing::error!("Full export failed: {}", e);
+        let _ = tx.send(Err(std::io::Error::other(e))).await;
+    }
+}
+
+async fn write_records(
+    pool: &DbPool,
+    tx: &mpsc::Sender<Result<Bytes, std::io::Error>>,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let schema_version = migrations::current_version(pool).await?;
+    let mut snapshot = pool.begin().await?;
+
+    let header = Record::Header {
+        format_version: FORMAT_VERSION,
+        schema_version,
+        exported_at: now,
+        tables: TABLES.iter().map(|t| t.to_string()).collect(),
+    };
+    if tx.send(Ok(line(&header))).await.is_err() {
+        return Ok(());
+    }
+
+    for table in TABLES {
+        let sql = format!("SELECT * FROM {} ORDER BY rowid", quote(table));
+        let mut rows = sqlx::query(&sql).fetch(&mut *snapshot);
+        while let Some(row) = rows.next().await {
+            let record = Record::Row {
+                table: table.to_string(),
+                row: row_to_json(&row?)?,
+            };
+            if tx.send(Ok(line(&record))).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fields of an imported participant checked like a registration
+#[derive(Debug, Deserialize, Validate)]
+#[validate(context = FieldLimits)]
+struct ImportedParticipant {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        custom(function = "validation::name_len", use_context)
+    )]
+    name: String,
+    #[validate(
+        email(code = "invalid_email", message = "must be a valid email address"),
+        custom(function = "validation::email_len", use_context)
+    )]
+    email: String,
+}
+
+/// `error` with the line of the export it refers to
+fn at_line(mut error: ApiError, line: usize) -> ApiError {
+    if let Some(body) = error.1 .0.as_object_mut() {
+        body.insert("line".to_string(), Value::from(line));
+    }
+    error
+}
+
+fn invalid(line: usize, message: impl std::fmt::Display) -> ApiError {
+    at_line(api_error(StatusCode::UNPROCESSABLE_ENTITY, &message.to_string()), line)
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    tracing::error!("Import failed: {}", e);
+    internal_error()
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Check a row the API would have validated
+fn validate_row(table: &str, row: &Map<String, Value>, config: &Config, now: DateTime<Utc>) -> Result<(), ApiError> {
+    let value = Value::Object(row.clone());
+    match table {
+        "events" => {
+            let event: CreateEvent =
+                serde_json::from_value(value).map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, &e.to_string()))?;
+            // Past events are restored as they were, so the date bounds do not apply
+            validation::validate_event(&event, config, now, true, None)
+        }
+        "participants" => {
+            let mut value = value;
+            // Sealed names and emails are checked as the text they hold
+            for column in ["name", "email"] {
+                let Some(hex) = row.get(column).and_then(|v| v.get(BLOB_KEY)).and_then(Value::as_str) else {
+                    continue;
+                };
+                let text = decode_hex(hex)
+                    .zip(encryption::storage_key())
+                    .and_then(|(sealed, key)| key.decrypt(&sealed))
+                    .and_then(|plain| String::from_utf8(plain).ok())
+                    .ok_or_else(|| {
+                        api_error(
+                            StatusCode::UNPROCESSABLE_ENTITY,
+                            &format!("Participant {} is not sealed with this server's PII key", column),
+                        )
+                    })?;
+                value[column] = Value::String(text);
+            }
+            let participant: ImportedParticipant =
+                serde_json::from_value(value).map_err(|e| api_error(StatusCode::UNPROCESSABLE_ENTITY, &e.to_string()))?;
+            validation::validate_with_limits(&participant, &config.field_limits)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Insert one row, keeping only columns the table has
+async fn insert_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    columns: &[String],
+    row: &Map<String, Value>,
+) -> Result<Result<(), String>, sqlx::Error> {
+    let present: Vec<&String> = columns.iter().filter(|c| row.contains_key(*c)).collect();
+    if present.is_empty() {
+        return Ok(Err("row has none of the table's columns".to_string()));
+    }
+
+    let sql = format!(
+        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+        quote(table),
+        present.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", "),
+        vec!["?"; present.len()].join(", ")
+    );
+
+    let mut query = sqlx::query::<Sqlite>(&sql);
+    for column in present {
+        query = match &row[column.as_str()] {
+            Value::Null => query.bind(None::<String>),
+            Value::Bool(b) => query.bind(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64()),
+            },
+            Value::String(s) => query.bind(s.clone()),
+            Value::Object(blob) if blob.len() == 1 && blob.contains_key(BLOB_KEY) => {
+                match blob[BLOB_KEY].as_str().and_then(decode_hex) {
+                    Some(bytes) => query.bind(bytes),
+                    None => return Ok(Err(format!("column {} is not valid hex", column))),
+                }
+            }
+            other => query.bind(other.to_string()),
+        };
+    }
+
+    match query.execute(&mut *conn).await {
+        Ok(_) => Ok(Ok(())),
+        // Constraint violations are the export's fault, not the server's
+        Err(sqlx::Error::Database(e)) => Ok(Err(e.message().to_string())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Restore an export into this database
+pub async fn import(pool: &DbPool, config: &Config, body: &[u8], now: DateTime<Utc>) -> Result<ImportReport, ApiError> {
+    let mut lines = body
+        .split(|b| *b == b'\n')
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace));
+
+    let schema_version = match lines.next().map(|(n, line)| (n, serde_json::from_slice::<Record>(line))) {
+        Some((_, Ok(Record::Header { format_version, schema_version, .. }))) => {
+            if format_version > FORMAT_VERSION {
+                return Err(invalid(1, format!("Unsupported export format version {}", format_version)));
+            }
+            schema_version
+        }
+        Some((n, Ok(_))) => return Err(invalid(n, "Export must start with a header record")),
+        Some((n, Err(e))) => return Err(invalid(n, format!("Invalid record: {}", e))),
+        None => return Err(api_error(StatusCode::UNPROCESSABLE_ENTITY, "Export is empty")),
+    };
+
+    let current = migrations::current_version(pool).await.map_err(db_error)?;
+    if schema_version > current {
+        return Err(api_error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &format!("Export is from a newer schema (v{}) than this server (v{})", schema_version, current),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let mut schema = HashMap::new();
+    for table in TABLES {
+        let table_columns = columns(&mut tx, table).await.map_err(db_error)?;
+        if !MERGED_TABLES.contains(table) {
+            let rows = sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM {}", quote(table)))
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            if rows > 0 {
+                return Err(api_error(
+                    StatusCode::CONFLICT,
+                    &format!("Import needs an empty database, but {} has rows", table),
+                ));
+            }
+        }
+        schema.insert(*table, table_columns);
+    }
+
+    let mut report = ImportReport {
+        schema_version,
+        ..ImportReport::default()
+    };
+    for (n, line) in lines {
+        let (table, row) = match serde_json::from_slice::<Record>(line) {
+            Ok(Record::Row { table, row }) => (table, row),
+            Ok(Record::Header { .. }) => return Err(invalid(n, "Unexpected second header record")),
+            Err(e) => return Err(invalid(n, format!("Invalid record: {}", e))),
+        };
+
+        let Some(table_columns) = schema.get(table.as_str()) else {
+            report.skipped += 1;
+            continue;
+        };
+
+        validate_row(&table, &row, config, now).map_err(|e| at_line(e, n))?;
+        if let Err(message) = insert_row(&mut tx, &table, table_columns, &row).await.map_err(db_error)? {
+            return Err(invalid(n, format!("Row rejected by {}: {}", table, message)));
+        }
+        *report.rows.entry(table).or_default() += 1;
+    }
+
+    // Plain-text names and emails are sealed as if registered here, and
+    // exports from before email indexes existed get them
+    encryption::seal_participants(&mut tx).await.map_err(db_error)?;
+    encryption::seal_deliveries(&mut tx).await.map_err(db_error)?;
+
+    // Exports from before dedupe keys existed have none; derive them again
+    let events = sqlx::query_as::<_, (uuid::Uuid, EmailPolicy)>("SELECT id, email_policy FROM events")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    for (event_id, policy) in events {
+        if let Err(e) = rekey_participants(&mut tx, event_id, policy).await {
+            return Err(api_error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                &format!("Participants of event {} violate its email policy: {}", event_id, e),
+            ));
+        }
+    }
+
+    // Likewise for slugs
+    crate::slug::backfill(&mut tx).await.map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+    if report.skipped > 0 {
+        tracing::warn!("Import skipped {} rows of tables this schema no longer has", report.skipped);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,214 @@
+//! Participant exports written in the background.
+//!
+//! Streaming a large export through a request can outlast proxy timeouts,
+//! so `POST /api/events/:id/export/start` only creates a job and answers
+//! with its id. The job writes the event's participants as NDJSON to
+//! `<DATA_DIR>/exports/` and, once ready, the job status carries a download
+//! URL signed with EXPORT_SIGNING_KEY, or without it with a key generated
+//! once and kept in the database, so any instance accepts it. The URL needs
+//! no other credentials and stops working, like the file itself, after
+//! EXPORT_URL_TTL_SECS.
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use ring::hmac;
+use serde::Serialize;
+use sqlx::FromRow;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::OwnedSemaphorePermit;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::export::decode_hex;
+use crate::routes::ndjson;
+use crate::settings;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+/// A background export
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the file and its download URL go away
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn export_dir(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join("exports")
+}
+
+/// Where a job's file is written
+pub fn file_path(data_dir: &str, id: Uuid) -> PathBuf {
+    export_dir(data_dir).join(format!("{}.ndjson", id))
+}
+
+/// Settings key of the generated signing key
+const KEY_SETTING: &str = "export_signing_key";
+
+/// The URL signing key: `configured` if set, else the one kept in
+/// `settings`, created by whichever instance needs it first
+pub async fn signing_key(pool: &DbPool, configured: Option<&str>, now: DateTime<Utc>) -> Result<String, sqlx::Error> {
+    if let Some(key) = configured {
+        return Ok(key.to_string());
+    }
+
+    settings::shared_secret(pool, KEY_SETTING, now).await
+}
+
+fn hmac_key(secret: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())
+}
+
+fn signed_message(id: Uuid, expires: i64) -> String {
+    format!("{}:{}", id, expires)
+}
+
+/// Hex signature of a download of `id` valid until `expires` (Unix seconds)
+pub fn sign(secret: &str, id: Uuid, expires: i64) -> String {
+    hmac::sign(&hmac_key(secret), signed_message(id, expires).as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Whether `signature` was made by `sign` for `id` and `expires`
+pub fn verify(secret: &str, id: Uuid, expires: i64, signature: &str) -> bool {
+    let Some(tag) = decode_hex(signature) else {
+        return false;
+    };
+    hmac::verify(&hmac_key(secret), signed_message(id, expires).as_bytes(), &tag).is_ok()
+}
+
+/// Signed URL downloading a ready job's file
+pub fn download_url(secret: &str, job: &ExportJob) -> Option<String> {
+    let expires = job.expires_at.filter(|_| job.status == JobStatus::Ready)?.timestamp();
+    Some(format!(
+        "/api/exports/{}/download?expires={}&signature={}",
+        job.id,
+        expires,
+        sign(secret, job.id, expires)
+    ))
+}
+
+pub async fn find(pool: &DbPool, id: Uuid) -> Result<Option<ExportJob>, sqlx::Error> {
+    sqlx::query_as::<_, ExportJob>(
+        "SELECT id, event_id, status, error, size_bytes, created_at, completed_at, expires_at
+         FROM export_jobs WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Create a job for `event_id` and write its file in the background,
+/// holding `permit` until done
+pub async fn start(state: &AppState, event_id: Uuid, permit: OwnedSemaphorePermit) -> Result<ExportJob, sqlx::Error> {
+    let now = state.clock.now();
+    let job = sqlx::query_as::<_, ExportJob>(
+        "INSERT INTO export_jobs (id, event_id, created_at) VALUES (?, ?, ?)
+         RETURNING id, event_id, status, error, size_bytes, created_at, completed_at, expires_at"
+    )
+    .bind(state.ids.generate(now))
+    .bind(event_id)
+    .bind(now)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    let state = state.clone();
+    let (id, data_dir) = (job.id, state.config.data_dir.clone());
+    tokio::spawn(async move {
+        let _permit = permit;
+        let result = write(&state.db_pool, &data_dir, id, event_id).await;
+
+        let now = state.clock.now();
+        let update = match result {
+            Ok(size) => sqlx::query(
+                "UPDATE export_jobs SET status = 'ready', size_bytes = ?, completed_at = ?, expires_at = ? WHERE id = ?"
+            )
+            .bind(size as i64)
+            .bind(now)
+            .bind(now + state.config.export_url_ttl)
+            .bind(id),
+            Err(e) => {
+                tracing::error!("Export job {} failed: {}", id, e);
+                sqlx::query("UPDATE export_jobs SET status = 'failed', error = ?, completed_at = ? WHERE id = ?")
+                    .bind(e.to_string())
+                    .bind(now)
+                    .bind(id)
+            }
+        };
+        if let Err(e) = update.execute(&state.db_pool).await {
+            tracing::error!("Failed to record outcome of export job {}: {}", id, e);
+        }
+    });
+
+    Ok(job)
+}
+
+/// Write the participants of `event_id` to the job's file; returns its size
+async fn write(pool: &DbPool, data_dir: &str, id: Uuid, event_id: Uuid) -> Result<u64, std::io::Error> {
+    tokio::fs::create_dir_all(export_dir(data_dir)).await?;
+    let path = file_path(data_dir, id);
+    // Written under a temporary name so a half-written file is never served
+    let partial = path.with_extension("ndjson.part");
+
+    let mut file = tokio::io::BufWriter::new(tokio::fs::File::create(&partial).await?);
+    let mut rows = ndjson::participant_rows(pool, Some(event_id), None, None);
+    while let Some(row) = rows.next().await {
+        let mut line = serde_json::to_vec(&row.map_err(std::io::Error::other)?)?;
+        line.push(b'\n');
+        file.write_all(&line).await?;
+    }
+    file.flush().await?;
+    drop(file);
+
+    tokio::fs::rename(&partial, &path).await?;
+    Ok(tokio::fs::metadata(&path).await?.len())
+}
+
+/// Delete expired jobs and any export file without a live job; returns
+/// the number of jobs deleted
+pub async fn remove_expired(pool: &DbPool, data_dir: &str, now: DateTime<Utc>) -> Result<u64, std::io::Error> {
+    let removed = sqlx::query("DELETE FROM export_jobs WHERE expires_at < ?")
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(std::io::Error::other)?
+        .rows_affected();
+
+    let mut entries = match tokio::fs::read_dir(export_dir(data_dir)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(removed),
+        Err(e) => return Err(e),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(id) = file_name.split('.').next().and_then(|id| id.parse::<Uuid>().ok()) else {
+            continue;
+        };
+        let live = find(pool, id).await.map_err(std::io::Error::other)?.is_some();
+        if !live {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,156 @@
+//! Recorded response fixtures shared with the frontend.
+//!
+//! `tests/fixture_tests.rs` calls every endpoint the frontend uses and
+//! compares each response with its fixture under `fixtures/`, so a change
+//! to a response shape fails the build until the fixture is re-recorded
+//! with `UPDATE_FIXTURES=1 cargo test`. The frontend's mock server reads
+//! the same files, from disk or from `GET /api/debug/fixtures/:name` in
+//! debug builds.
+//!
+//! Responses are canonicalized before comparison: UUIDs and random tokens
+//! are renumbered in order of appearance, everything else comes from a
+//! fixed clock.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One recorded response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    /// Route pattern as registered, e.g. `/api/events/:id`
+    pub route: String,
+    /// Query string the response was recorded with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    pub status: u16,
+    /// Null for empty bodies
+    pub body: Value,
+}
+
+impl Fixture {
+    /// Pretty-printed with a trailing newline, as stored on disk
+    pub fn to_file(&self) -> String {
+        let mut text = serde_json::to_string_pretty(self).expect("fixtures serialize");
+        text.push('\n');
+        text
+    }
+}
+
+/// Random tokens are simple UUIDs, 32 lowercase hex digits; signatures
+/// are 64 of them and invite codes 12
+fn is_token(s: &str) -> bool {
+    matches!(s.len(), 12 | 32 | 64) && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// A hyphenated UUID, as serialized by `uuid`
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// Byte offset of the first UUID in `s`, such as the id in a URL
+fn find_uuid(s: &str) -> Option<usize> {
+    (0..s.len().saturating_sub(35)).find(|&i| s.get(i..i + 36).is_some_and(is_uuid))
+}
+
+#[derive(Default)]
+struct Renumbering {
+    uuids: HashMap<String, String>,
+    tokens: HashMap<String, String>,
+}
+
+impl Renumbering {
+    fn uuid(&mut self, uuid: &str) -> String {
+        let next = self.uuids.len() + 1;
+        self.uuids
+            .entry(uuid.to_string())
+            .or_insert_with(|| format!("00000000-0000-4000-8000-{:012}", next))
+            .clone()
+    }
+
+    fn token(&mut self, token: &str) -> String {
+        let next = self.tokens.len() + 1;
+        self.tokens
+            .entry(token.to_string())
+            .or_insert_with(|| format!("{:0width$}", next, width = token.len()))
+            .clone()
+    }
+
+    fn replace_str(&mut self, s: &str) -> String {
+        if is_token(s) {
+            return self.token(s);
+        }
+        // Signed tokens: `<nonce>.<signature>`
+        if let Some((nonce, signature)) = s.split_once('.').filter(|(n, s)| is_token(n) && is_token(s)) {
+            return format!("{}.{}", self.token(nonce), self.token(signature));
+        }
+
+        let mut replaced = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(start) = find_uuid(rest) {
+            replaced.push_str(&rest[..start]);
+            replaced.push_str(&self.uuid(&rest[start..start + 36]));
+            rest = &rest[start + 36..];
+        }
+        replaced.push_str(rest);
+        replaced
+    }
+
+    fn replace(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.replace_str(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.replace(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.replace(field)),
+            _ => {}
+        }
+    }
+}
+
+/// Replace UUIDs, also inside strings such as URLs, and tokens in `value`
+/// with stable placeholders of the same shape; the same input value always
+/// gets the same placeholder
+pub fn canonicalize(value: &mut Value) {
+    Renumbering::default().replace(value);
+}
+
+#[cfg(debug_assertions)]
+mod serve {
+    use axum::{
+        extract::Path,
+        http::{header, StatusCode},
+        response::{IntoResponse, Response},
+        Json,
+    };
+    use rust_embed::RustEmbed;
+
+    use crate::error::api_error;
+
+    #[derive(RustEmbed)]
+    #[folder = "fixtures/"]
+    struct Files;
+
+    /// Names of the recorded fixtures
+    pub async fn list_fixtures() -> Json<Vec<String>> {
+        let mut names: Vec<String> = Files::iter()
+            .filter_map(|file| file.strip_suffix(".json").map(str::to_string))
+            .collect();
+        names.sort();
+        Json(names)
+    }
+
+    /// One recorded fixture
+    pub async fn get_fixture(Path(name): Path<String>) -> Response {
+        match Files::get(&format!("{}.json", name)) {
+            Some(file) => ([(header::CONTENT_TYPE, "application/json")], file.data.into_owned()).into_response(),
+            None => api_error(StatusCode::NOT_FOUND, "Fixture not found").into_response(),
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+pub use serve::{get_fixture, list_fixtures};
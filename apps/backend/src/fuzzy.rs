@@ -0,0 +1,96 @@
+//! Typo-tolerant matching for participant search at the door.
+//!
+//! A query is compared with every word of a participant's name, the full
+//! name and, for organizers, the email and its local part. Each comparison
+//! scores the better of trigram similarity and edit distance (with adjacent
+//! transpositions, so "jonh" is one edit from "john"); substrings score
+//! fully. The participant's score is its best comparison.
+
+use std::collections::HashSet;
+
+/// Scores below this are not a match
+pub const THRESHOLD: f64 = 0.6;
+
+/// Longest accepted query, in characters
+pub const MAX_QUERY_LEN: usize = 100;
+
+fn normalize(text: &str) -> Vec<char> {
+    text.trim().to_lowercase().chars().collect()
+}
+
+/// Edit distance counting insertions, deletions, substitutions and swaps
+/// of adjacent characters (optimal string alignment)
+pub fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    let mut rows = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[n][m]
+}
+
+fn trigrams(text: &[char]) -> HashSet<[char; 3]> {
+    let padded: Vec<char> = [' ', ' '].into_iter().chain(text.iter().copied()).chain([' ']).collect();
+    padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Share of trigrams the two strings have in common (Jaccard index)
+pub fn trigram_similarity(a: &[char], b: &[char]) -> f64 {
+    let (a, b) = (trigrams(a), trigrams(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Similarity of `query` to one candidate string, from 0 to 1
+fn similarity(query: &[char], candidate: &[char]) -> f64 {
+    if candidate.is_empty() {
+        return 0.0;
+    }
+    if candidate.windows(query.len()).any(|w| w == query) {
+        return 1.0;
+    }
+    let longest = query.len().max(candidate.len()) as f64;
+    let edits = 1.0 - edit_distance(query, candidate) as f64 / longest;
+    edits.max(trigram_similarity(query, candidate))
+}
+
+/// Best similarity of `query` to the name and, when given, the email
+pub fn score(query: &str, name: &str, email: Option<&str>) -> f64 {
+    let query = normalize(query);
+    if query.is_empty() {
+        return 0.0;
+    }
+
+    let mut candidates: Vec<String> = name.split_whitespace().map(str::to_string).collect();
+    candidates.push(name.to_string());
+    if let Some(email) = email {
+        candidates.push(email.to_string());
+        if let Some((local, _)) = email.split_once('@') {
+            candidates.push(local.to_string());
+            candidates.extend(local.split(['.', '_', '-', '+']).map(str::to_string));
+        }
+    }
+
+    candidates
+        .iter()
+        .map(|candidate| similarity(&query, &normalize(candidate)))
+        .fold(0.0, f64::max)
+}
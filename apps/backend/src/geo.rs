@@ -0,0 +1,181 @@
+//! Optional country allow/deny lists for registrations.
+//!
+//! With `GEO_DB_PATH` pointing at a MaxMind country database and
+//! `GEO_ALLOW_COUNTRIES` and/or `GEO_DENY_COUNTRIES` set (comma-separated
+//! ISO codes), the `gate` middleware on the registration route resolves
+//! the client's country and rejects registrations that the lists exclude.
+//! Every decision is written to the audit log.
+//!
+//! Addresses the database cannot place (private ranges, lookup misses)
+//! pass unless an allow list is configured.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::audit;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Resolves an IP address to an ISO country code
+pub trait CountryLookup: Send + Sync {
+    fn country(&self, ip: IpAddr) -> Option<String>;
+}
+
+/// Lookup backed by a MaxMind GeoIP2/GeoLite2 country (or city) database
+pub struct MaxMindLookup {
+    reader: maxminddb::Reader<Vec<u8>>,
+}
+
+impl MaxMindLookup {
+    pub fn open(path: &str) -> Result<Self, maxminddb::MaxMindDBError> {
+        Ok(Self {
+            reader: maxminddb::Reader::open_readfile(path)?,
+        })
+    }
+}
+
+impl CountryLookup for MaxMindLookup {
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: maxminddb::geoip2::Country = self.reader.lookup(ip).ok()?;
+        record.country?.iso_code.map(str::to_string)
+    }
+}
+
+/// Geo gating settings read from the environment
+#[derive(Debug, Clone, Default)]
+pub struct GeoConfig {
+    /// MaxMind database file (GEO_DB_PATH); gating is off when unset
+    pub db_path: Option<String>,
+    /// Only these countries may register (GEO_ALLOW_COUNTRIES)
+    pub allow: Vec<String>,
+    /// These countries may not register (GEO_DENY_COUNTRIES)
+    pub deny: Vec<String>,
+}
+
+impl GeoConfig {
+    pub fn from_env() -> Self {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|c| c.trim().to_ascii_uppercase())
+                .filter(|c| !c.is_empty())
+                .collect()
+        };
+
+        Self {
+            db_path: std::env::var("GEO_DB_PATH").ok().filter(|p| !p.is_empty()),
+            allow: list("GEO_ALLOW_COUNTRIES"),
+            deny: list("GEO_DENY_COUNTRIES"),
+        }
+    }
+}
+
+/// Country lookup plus the allow/deny policy
+#[derive(Clone)]
+pub struct GeoGate {
+    lookup: Arc<dyn CountryLookup>,
+    allow: HashSet<String>,
+    deny: HashSet<String>,
+}
+
+impl GeoGate {
+    pub fn new(lookup: Arc<dyn CountryLookup>, allow: &[String], deny: &[String]) -> Self {
+        let normalize = |codes: &[String]| codes.iter().map(|c| c.to_ascii_uppercase()).collect();
+        Self {
+            lookup,
+            allow: normalize(allow),
+            deny: normalize(deny),
+        }
+    }
+
+    /// Build the gate from config; None when gating is not configured
+    pub fn from_config(config: &GeoConfig) -> Option<Self> {
+        let path = config.db_path.as_deref()?;
+        if config.allow.is_empty() && config.deny.is_empty() {
+            return None;
+        }
+
+        let lookup = MaxMindLookup::open(path)
+            .unwrap_or_else(|e| panic!("Failed to open GEO_DB_PATH {}: {}", path, e));
+        Some(Self::new(Arc::new(lookup), &config.allow, &config.deny))
+    }
+
+    /// The resolved country and whether it may register
+    pub fn decide(&self, ip: IpAddr) -> (Option<String>, bool) {
+        let country = self.lookup.country(ip);
+        let allowed = match &country {
+            Some(code) => !self.deny.contains(code) && (self.allow.is_empty() || self.allow.contains(code)),
+            None => self.allow.is_empty(),
+        };
+        (country, allowed)
+    }
+}
+
+/// The client address: the last `X-Forwarded-For` hop (appended by the
+/// platform proxy in front of us), else the socket peer
+pub fn client_ip(request: &Request) -> Option<IpAddr> {
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+
+    forwarded.or_else(|| {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+    })
+}
+
+/// Middleware rejecting registrations from excluded countries
+pub async fn gate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(geo) = state.geo.as_ref() else {
+        return next.run(request).await;
+    };
+    let Some(ip) = client_ip(&request) else {
+        tracing::warn!("Geo gate could not determine client address");
+        return next.run(request).await;
+    };
+
+    let (country, allowed) = geo.decide(ip);
+    let action = if allowed { "registration.geo_allowed" } else { "registration.geo_denied" };
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        action,
+        "client_ip",
+        &ip.to_string(),
+        audit::actor_label(false),
+        &json!({ "country": country }),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to record geo decision: {}", e);
+    }
+
+    if !allowed {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Registrations are not available in your region",
+                "code": "geo_blocked"
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
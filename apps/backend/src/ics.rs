@@ -0,0 +1,177 @@
+//! iCalendar (RFC 5545) invites for individual registrations.
+//!
+//! Each registration gets its own VEVENT with a stable UID so calendar
+//! clients update or remove the entry when a later invite for the same UID
+//! arrives (iTIP, RFC 5546). SEQUENCE grows with every change to the event
+//! or the registration, as iTIP requires for updates to be applied.
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+use crate::models::{Event, Participant, ParticipantStatus};
+
+const PRODID: &str = "-//railway-test//events//EN";
+
+/// Content lines longer than this many octets are folded
+const MAX_LINE_OCTETS: usize = 75;
+
+/// iTIP method of an invite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Create or update the attendee's calendar entry
+    Request,
+    /// Remove the attendee's calendar entry
+    Cancel,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Request => "REQUEST",
+            Method::Cancel => "CANCEL",
+        }
+    }
+}
+
+/// Sender shown as ORGANIZER
+#[derive(Debug, Clone)]
+pub struct Organizer {
+    pub name: String,
+    pub email: String,
+}
+
+/// A VEVENT invite for one participant's registration
+#[derive(Debug, Clone)]
+pub struct Invite<'a> {
+    pub method: Method,
+    pub event: &'a Event,
+    pub participant: &'a Participant,
+    pub organizer: &'a Organizer,
+    /// Creation time of this invite (DTSTAMP)
+    pub stamp: DateTime<Utc>,
+}
+
+impl Invite<'_> {
+    /// Stable per registration, so re-sent invites replace earlier ones
+    pub fn uid(&self) -> String {
+        let domain = self
+            .organizer
+            .email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or("localhost");
+        format!("registration-{}@{}", self.participant.id, domain)
+    }
+
+    /// Seconds from registration to the latest change of the event or the
+    /// registration; cancellations use the invite time so they always win
+    pub fn sequence(&self) -> i64 {
+        let latest = match self.method {
+            Method::Request => self.event.updated_at.max(self.participant.updated_at),
+            Method::Cancel => self.stamp,
+        };
+        (latest - self.participant.registered_at).num_seconds().max(0)
+    }
+
+    /// Render the VCALENDAR object with CRLF line endings
+    pub fn render(&self) -> String {
+        let cancelled = self.method == Method::Cancel;
+        let (status, partstat) = match (&self.participant.status, cancelled) {
+            (_, true) | (ParticipantStatus::Cancelled, _) => ("CANCELLED", "DECLINED"),
+            (ParticipantStatus::Declined, _) => ("CANCELLED", "DECLINED"),
+            (ParticipantStatus::Waitlisted | ParticipantStatus::Maybe, _) => ("TENTATIVE", "TENTATIVE"),
+            (ParticipantStatus::Registered | ParticipantStatus::Confirmed | ParticipantStatus::CheckedIn, _) => ("CONFIRMED", "ACCEPTED"),
+        };
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            format!("PRODID:{}", PRODID),
+            "VERSION:2.0".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!("METHOD:{}", self.method.as_str()),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid()),
+            format!("SEQUENCE:{}", self.sequence()),
+            format!("DTSTAMP:{}", format_time(self.stamp)),
+            format!("DTSTART:{}", format_time(self.event.start_time)),
+            format!("DTEND:{}", format_time(self.event.end_time)),
+            format!("SUMMARY:{}", escape_text(&self.event.title)),
+        ];
+        if let Some(description) = self.event.description.as_deref().filter(|d| !d.is_empty()) {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(location) = self.event.location.as_deref().filter(|l| !l.is_empty()) {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        lines.push(format!(
+            "ORGANIZER;CN={}:mailto:{}",
+            quote_param(&self.organizer.name),
+            self.organizer.email
+        ));
+        lines.push(format!(
+            "ATTENDEE;CN={};ROLE=REQ-PARTICIPANT;PARTSTAT={};RSVP=FALSE:mailto:{}",
+            quote_param(&self.participant.name),
+            partstat,
+            self.participant.email
+        ));
+        lines.push(format!("STATUS:{}", status));
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut out = String::new();
+        for line in lines {
+            let _ = write!(out, "{}\r\n", fold(&line));
+        }
+        out
+    }
+}
+
+/// UTC date-time in the basic format, e.g. 20260301T100000Z
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+pub fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parameter values containing separators must be quoted; DQUOTE itself
+/// and control characters are not allowed at all
+fn quote_param(value: &str) -> String {
+    let cleaned: String = value.chars().filter(|c| *c != '"' && !c.is_control()).collect();
+    if cleaned.contains([':', ';', ',']) {
+        format!("\"{}\"", cleaned)
+    } else {
+        cleaned
+    }
+}
+
+/// Fold a content line at 75 octets without splitting a UTF-8 sequence;
+/// continuation lines start with a single space
+pub fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts toward the continuation line
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+    out
+}
@@ -0,0 +1,131 @@
+//! Admin impersonation of organizers for support.
+//!
+//! An admin sends the admin token together with `X-Impersonate-Org`, the id
+//! of an organizer account, to act as that organizer. The request then runs
+//! as the organizer: it is not treated as admin, so frozen events, events
+//! owned by others and admin-only endpoints behave as they would for them.
+//! Every impersonated request is written to the audit log with its outcome,
+//! and the response carries `X-Impersonating` so clients can show a banner.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::convert::Infallible;
+
+use crate::audit;
+use crate::auth;
+use crate::error::{api_error, internal_error};
+use crate::organizers::{self, Organizer};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Request header naming the organizer to act as
+pub const IMPERSONATE_HEADER: &str = "x-impersonate-org";
+
+/// Response header echoing the impersonated organizer
+pub const IMPERSONATING_HEADER: &str = "x-impersonating";
+
+/// Marker extension on impersonated requests
+#[derive(Debug, Clone)]
+pub struct Impersonation {
+    pub org: String,
+    /// The account `org` names
+    pub organizer: Organizer,
+}
+
+/// Audit actor for changes made while impersonating `org`
+pub fn actor_label(org: &str) -> String {
+    format!("admin:impersonating:{}", org)
+}
+
+/// Extractor yielding the impersonated organizer, if any; never rejects
+#[derive(Debug, Clone)]
+pub struct Impersonating(pub Option<String>);
+
+impl Impersonating {
+    /// Audit actor for this request: the impersonation, else admin or anonymous
+    pub fn actor(&self, is_admin: bool) -> String {
+        match &self.0 {
+            Some(org) => actor_label(org),
+            None => audit::actor_label(is_admin).to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Impersonating
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Impersonating(
+            parts.extensions.get::<Impersonation>().map(|i| i.org.clone()),
+        ))
+    }
+}
+
+/// Middleware turning `X-Impersonate-Org` into an `Impersonation` and
+/// auditing the request. Only admins may impersonate, and only organizers
+/// that exist. The organizer is also inserted as if signed in, so
+/// extractors such as `CurrentOrganizer` see the request as theirs.
+pub async fn impersonate(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(value) = request.headers().get(IMPERSONATE_HEADER) else {
+        return next.run(request).await;
+    };
+
+    if !auth::has_admin_token(request.headers(), &state) {
+        return api_error(StatusCode::FORBIDDEN, "Impersonation requires the admin token").into_response();
+    }
+
+    let Some(id) = value.to_str().ok().and_then(|v| v.trim().parse::<uuid::Uuid>().ok()) else {
+        return api_error(StatusCode::BAD_REQUEST, "X-Impersonate-Org must be an organizer id").into_response();
+    };
+    let organizer = match organizers::find(&state.db_pool, id).await {
+        Ok(Some(organizer)) => organizer,
+        Ok(None) => return api_error(StatusCode::NOT_FOUND, "Organizer not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load impersonated organizer: {}", e);
+            return internal_error().into_response();
+        }
+    };
+    let org = organizer.id.to_string();
+
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    tracing::warn!("Admin impersonating organizer {}: {} {}", org, method, path);
+
+    request.extensions_mut().insert(organizer.clone());
+    request.extensions_mut().insert(Impersonation { org: org.clone(), organizer });
+    let mut response = next.run(request).await;
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "impersonation.request",
+        "organization",
+        &org,
+        audit::actor_label(true),
+        &json!({
+            "method": method,
+            "path": path,
+            "status": response.status().as_u16(),
+        }),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to record impersonated request: {}", e);
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&org) {
+        response.headers_mut().insert(IMPERSONATING_HEADER, value);
+    }
+    response
+}
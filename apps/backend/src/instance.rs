@@ -0,0 +1,213 @@
+//! Instance identity and lifecycle for overlapping deploys.
+//!
+//! During a rolling deploy the old and new instance share the database for
+//! a while. Each instance writes a heartbeat row to `instances` and moves
+//! through three phases: `Starting` until schema migrations ran and the
+//! notification poller has caught up with `change_notifications`, `Ready`
+//! while serving, and `Draining` once shutdown begins. `/ready` only
+//! reports ready in the `Ready` phase so the platform routes traffic to an
+//! instance only after it can serve fresh state.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::clock::SharedClock;
+use crate::db::DbPool;
+use crate::watchdog::TaskHealth;
+
+/// Heartbeats older than this many intervals mark an instance as gone
+const STALE_AFTER_INTERVALS: i32 = 6;
+
+/// Lifecycle phase of this instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Starting,
+    Ready,
+    Draining,
+}
+
+impl Phase {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Phase::Ready,
+            2 => Phase::Draining,
+            _ => Phase::Starting,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Starting => "starting",
+            Phase::Ready => "ready",
+            Phase::Draining => "draining",
+        }
+    }
+}
+
+/// This process's identity, shared with the readiness check
+#[derive(Debug, Clone)]
+pub struct Instance {
+    id: Arc<str>,
+    started_at: DateTime<Utc>,
+    phase: Arc<AtomicU8>,
+    /// Flipped once on shutdown, for long-lived responses to wind down
+    draining: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl Instance {
+    pub fn new(id: &str, started_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Arc::from(id),
+            started_at,
+            phase: Arc::new(AtomicU8::new(Phase::Starting as u8)),
+            draining: Arc::new(tokio::sync::watch::Sender::new(false)),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn phase(&self) -> Phase {
+        Phase::from_u8(self.phase.load(Ordering::Relaxed))
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.phase() == Phase::Ready
+    }
+
+    /// Move from `Starting` to `Ready`; a draining instance stays draining
+    pub fn mark_ready(&self) -> bool {
+        self.phase
+            .compare_exchange(Phase::Starting as u8, Phase::Ready as u8, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Stop reporting ready so the platform drains traffic away
+    pub fn mark_draining(&self) {
+        self.phase.store(Phase::Draining as u8, Ordering::Relaxed);
+        self.draining.send_replace(true);
+    }
+
+    /// Resolve once the instance is draining, immediately if it already is
+    pub async fn drained(&self) {
+        let mut receiver = self.draining.subscribe();
+        // The sender lives as long as `self`, so this cannot fail
+        let _ = receiver.wait_for(|draining| *draining).await;
+    }
+}
+
+/// A heartbeat row as seen by any instance
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct InstanceInfo {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    pub last_heartbeat: DateTime<Utc>,
+    pub phase: String,
+}
+
+/// Record that this instance is alive, and in which phase
+pub async fn heartbeat(pool: &DbPool, instance: &Instance, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO instances (id, started_at, last_heartbeat, phase) VALUES (?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET last_heartbeat = excluded.last_heartbeat, phase = excluded.phase"
+    )
+    .bind(instance.id())
+    .bind(instance.started_at())
+    .bind(now)
+    .bind(instance.phase().as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove this instance's row on shutdown
+pub async fn deregister(pool: &DbPool, instance: &Instance) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM instances WHERE id = ?")
+        .bind(instance.id())
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Instances that sent a heartbeat since `since`, oldest first
+pub async fn list(pool: &DbPool, since: DateTime<Utc>) -> Result<Vec<InstanceInfo>, sqlx::Error> {
+    sqlx::query_as::<_, InstanceInfo>(
+        "SELECT id, started_at, last_heartbeat, phase FROM instances
+         WHERE last_heartbeat >= ? ORDER BY started_at ASC"
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete rows of instances that stopped without deregistering
+pub async fn prune(pool: &DbPool, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM instances WHERE last_heartbeat < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Oldest heartbeat still considered alive
+pub fn liveness_cutoff(now: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+    let interval = chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::seconds(10));
+    now - interval * STALE_AFTER_INTERVALS
+}
+
+/// Write heartbeats every `interval`. The instance becomes ready once the
+/// notification poller has completed its first poll, i.e. has caught up
+/// with every notification written before this instance started serving.
+pub async fn start_heartbeat(
+    pool: DbPool,
+    instance: Instance,
+    poller_health: TaskHealth,
+    clock: SharedClock,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval.min(Duration::from_secs(1)));
+    let mut last_heartbeat: Option<DateTime<Utc>> = None;
+
+    loop {
+        ticker.tick().await;
+        let now = clock.now();
+
+        let caught_up = poller_health.last_success().is_some();
+        let became_ready = caught_up && instance.mark_ready();
+        if became_ready {
+            info!("Instance {} caught up and ready", instance.id());
+        }
+
+        // Write on phase changes immediately, otherwise once per interval
+        let due = last_heartbeat.is_none_or(|last| {
+            (now - last).to_std().unwrap_or_default() >= interval
+        });
+        if !(due || became_ready) {
+            continue;
+        }
+
+        if let Err(e) = heartbeat(&pool, &instance, now).await {
+            error!("Failed to write instance heartbeat: {}", e);
+            continue;
+        }
+        last_heartbeat = Some(now);
+
+        if let Err(e) = prune(&pool, liveness_cutoff(now, interval)).await {
+            error!("Failed to prune stale instances: {}", e);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! JSON request body extractor.
+//!
+//! Like `axum::Json`, but rejects payloads with keys the target type does
+//! not know, so a misspelled field (`max_participents`) is reported instead
+//! of silently dropped. ALLOW_UNKNOWN_FIELDS restores the lenient behavior
+//! for older clients.
+//!
+//! Malformed bodies are answered with a structured 400 naming the offending
+//! field and, for type mismatches, the expected type, instead of axum's
+//! plain-text rejection.
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRef, FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use crate::body_limit;
+use crate::error::{api_error, ApiError};
+use crate::AppState;
+
+/// Extractor deserializing a JSON request body into `T`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonBody<T>(pub T);
+
+/// Whether the request declares a JSON body (`application/json` or `+json`)
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// A body that could not be deserialized, with the path to the offending
+/// value ("." for the document itself)
+pub struct ParseError {
+    pub path: String,
+    pub error: serde_json::Error,
+}
+
+/// Deserialize `bytes`, collecting the paths of keys `T` ignored
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<(T, Vec<String>), ParseError> {
+    let mut unknown = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let mut record = |path: serde_ignored::Path| unknown.push(path.to_string());
+    let ignored = serde_ignored::Deserializer::new(&mut deserializer, &mut record);
+    let value = serde_path_to_error::deserialize(ignored).map_err(|e| ParseError {
+        path: e.path().to_string(),
+        error: e.into_inner(),
+    })?;
+    deserializer.end().map_err(|error| ParseError {
+        path: ".".to_string(),
+        error,
+    })?;
+    Ok((value, unknown))
+}
+
+/// 400 response describing why the body could not be deserialized.
+///
+/// `field` is the dotted path to the offending value (for a missing field,
+/// the field itself) and is absent for errors of the document as a whole,
+/// like a syntax error; `expected` is present for type and value mismatches.
+pub fn parse_error(ParseError { path, error }: ParseError) -> ApiError {
+    let position = format!(" at line {} column {}", error.line(), error.column());
+    let text = error.to_string();
+    let message = text.strip_suffix(&position).unwrap_or(&text);
+
+    let mut field = (path != ".").then_some(path);
+    if let Some(missing) = message.strip_prefix("missing field `").and_then(|m| m.strip_suffix('`')) {
+        field = Some(match field {
+            Some(parent) => format!("{}.{}", parent, missing),
+            None => missing.to_string(),
+        });
+    }
+
+    let mut body = Map::new();
+    let summary = if error.is_data() { "Invalid request body" } else { "Malformed JSON" };
+    body.insert("error".into(), Value::from(summary));
+    if let Some(field) = field {
+        body.insert("field".into(), Value::from(field));
+    }
+    if let Some((_, expected)) = message.rsplit_once(", expected ") {
+        body.insert("expected".into(), Value::from(expected));
+    }
+    body.insert("message".into(), Value::from(message));
+    if error.line() > 0 {
+        body.insert("line".into(), Value::from(error.line()));
+        body.insert("column".into(), Value::from(error.column()));
+    }
+
+    (StatusCode::BAD_REQUEST, Json(Value::Object(body)))
+}
+
+/// 422 response listing the unknown keys
+pub fn unknown_fields_error(fields: Vec<String>) -> ApiError {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": "Unknown fields", "unknown_fields": fields })),
+    )
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_json_content_type(request.headers()) {
+            return Err(api_error(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Expected request with `Content-Type: application/json`",
+            ));
+        }
+
+        let limit = body_limit::limit_for(&AppState::from_ref(state).config, request.uri().path());
+        let bytes = Bytes::from_request(request, state).await.map_err(|rejection| match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => body_limit::too_large(limit),
+            status => api_error(status, &rejection.body_text()),
+        })?;
+
+        let (value, unknown) = parse::<T>(&bytes).map_err(parse_error)?;
+
+        if !unknown.is_empty() {
+            if !AppState::from_ref(state).config.allow_unknown_fields {
+                return Err(unknown_fields_error(unknown));
+            }
+            tracing::debug!("Ignoring unknown request fields: {}", unknown.join(", "));
+        }
+
+        Ok(JsonBody(value))
+    }
+}
@@ -0,0 +1,53 @@
+//! Sliding window of recent request latencies.
+//!
+//! Keeps the latest samples of the last minute, bounded in number, so a
+//! percentile can be computed at scrape time without histogram buckets.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Samples older than this no longer count
+pub const WINDOW: Duration = Duration::from_secs(60);
+
+/// At most this many samples are kept; older ones are dropped first
+pub const MAX_SAMPLES: usize = 4096;
+
+#[derive(Clone, Default)]
+pub struct LatencyWindow {
+    samples: Arc<Mutex<VecDeque<(Instant, Duration)>>>,
+}
+
+impl LatencyWindow {
+    pub fn record(&self, latency: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((Instant::now(), latency));
+    }
+
+    /// Latency below which `quantile` of the recent requests finished;
+    /// None without samples in the window
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let now = Instant::now();
+        let mut samples = self.samples.lock().unwrap();
+        while samples
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > WINDOW)
+        {
+            samples.pop_front();
+        }
+        let mut latencies: Vec<Duration> = samples.iter().map(|(_, latency)| *latency).collect();
+        drop(samples);
+
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort_unstable();
+        // Nearest-rank percentile
+        let rank = (quantile.clamp(0.0, 1.0) * latencies.len() as f64).ceil() as usize;
+        Some(latencies[rank.saturating_sub(1)])
+    }
+}
+
@@ -1,32 +1,420 @@
+pub mod admin_ops;
+pub mod admin_ui;
+pub mod audit;
+pub mod auth;
+pub mod backup;
+pub mod body_limit;
 pub mod broadcaster;
+pub mod bulk_mail;
 pub mod cache;
+pub mod calendar;
+pub mod cancellation;
+pub mod capacity_holds;
+pub mod cache_audit;
+pub mod certificate;
+pub mod checkin_sync;
+pub mod chaos;
+pub mod clock;
+pub mod completion;
+pub mod concurrency;
+pub mod config;
+pub mod confirmation;
+pub mod consent;
+pub mod cors;
 pub mod db;
+pub mod devices;
+pub mod digest;
+pub mod domain_events;
+pub mod email;
+pub mod email_templates;
+pub mod encryption;
+pub mod error;
+pub mod export;
+pub mod export_jobs;
+pub mod fixtures;
+pub mod fuzzy;
+pub mod geo;
+pub mod ics;
+pub mod ids;
+pub mod impersonation;
+pub mod instance;
+pub mod json;
+pub mod latency;
+pub mod load_shed;
+pub mod log_level;
+pub mod mail_failures;
+pub mod mailer;
+pub mod maintenance;
+pub mod masking;
+pub mod metadata;
+pub mod metrics;
+pub mod migrations;
 pub mod models;
+pub mod naming;
+pub mod nearby;
+pub mod organizers;
+pub mod participant_diff;
+pub mod personal_data;
+pub mod popularity;
+pub mod reconcile;
+pub mod reconnect;
+pub mod referrals;
+pub mod registration_form;
+pub mod recurrence;
+pub mod registration_mail;
+pub mod reminders;
+pub mod reputation;
 pub mod routes;
+pub mod seating;
+pub mod session;
+pub mod settings;
+pub mod slug;
+pub mod storage;
+pub mod tags;
+pub mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tls;
+pub mod validation;
+pub mod visibility;
+pub mod watchdog;
+pub mod webhooks;
 
-use axum::Json;
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get, patch, post, put},
+    Json, Router,
+};
 use serde::Serialize;
+use std::sync::Arc;
+use tower::Layer;
+use tower_http::normalize_path::NormalizePathLayer;
 
 use cache::AppCache;
+use clock::SharedClock;
+use config::Config;
 use db::DbPool;
+use domain_events::DomainEventBus;
+use ids::IdGenerator;
 use broadcaster::Broadcaster;
+use load_shed::LoadShedder;
+use watchdog::TaskHealth;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: DbPool,
     pub broadcaster: Broadcaster,
+    /// Typed mutation events for in-process subscribers
+    pub domain_events: DomainEventBus,
     pub cache: AppCache,
+    pub load_shedder: LoadShedder,
+    /// Request and export concurrency limits
+    pub concurrency: concurrency::ConcurrencyLimits,
+    pub clock: SharedClock,
+    pub ids: IdGenerator,
+    pub config: Arc<Config>,
+    /// Liveness of the notification poller, reported by `/ready`
+    pub poller_health: TaskHealth,
+    /// Timings and volumes of the notification poller, for `/metrics`
+    pub poller_metrics: db::PollerMetrics,
+    /// Country gating for registrations; None when not configured
+    pub geo: Option<geo::GeoGate>,
+    /// Email check applied to every registration
+    pub email_reputation: Arc<dyn reputation::EmailReputation>,
+    /// Outgoing mail transport
+    pub mailer: Arc<dyn mailer::Mailer>,
+    /// Recent send outcomes on this instance, for failure alerts
+    pub mail_outcomes: mail_failures::MailOutcomes,
+    /// Identity and lifecycle phase of this process, reported by `/ready`
+    pub instance: instance::Instance,
+    /// Runtime log filter of this process, changed by admins
+    pub log_level: log_level::LogControl,
+    /// Event views not yet written to the database
+    pub views: popularity::ViewCounter,
 }
 
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Operator notice for frontends, present only while set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintenance_message: Option<String>,
 }
 
-pub async fn health_check() -> Json<HealthResponse> {
+pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "healthy".to_string(),
-        timestamp: chrono::Utc::now(),
+        timestamp: state.clock.now(),
+        maintenance_message: maintenance::current_message(&state).await,
     })
 }
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub status: String,
+    pub instance_id: String,
+    pub phase: instance::Phase,
+    pub poller_last_success: Option<chrono::DateTime<chrono::Utc>>,
+    pub poller_restarts: u64,
+}
+
+/// Ready only once startup catch-up finished, before shutdown begins, and
+/// while the notification poller keeps succeeding; otherwise this instance
+/// would serve stale caches and miss cross-instance events
+pub async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let health = &state.poller_health;
+    let stalled = health.is_stalled(state.clock.now(), state.config.poller_stall_threshold);
+
+    let (status, label) = if stalled || !state.instance.is_ready() {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    } else {
+        (StatusCode::OK, "ready")
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            status: label.to_string(),
+            instance_id: state.instance.id().to_string(),
+            phase: state.instance.phase(),
+            poller_last_success: health.last_success(),
+            poller_restarts: health.restarts(),
+        }),
+    )
+}
+
+/// Build the application router with all routes and layers.
+///
+/// Used by both main.rs and the tests so they exercise the same stack.
+pub fn build_router(state: AppState, config: &Config) -> Router {
+    let router = Router::new()
+        // NDJSON streaming exports (static routes, matched before :id params)
+        .route("/api/events/ndjson", get(routes::ndjson::stream_events))
+        .route("/api/participants/ndjson", get(routes::ndjson::stream_participants))
+
+        // Embedded admin panel
+        .route("/admin", get(admin_ui::index))
+        .route("/admin/*path", get(admin_ui::static_asset))
+
+        // Admin operations
+        .route("/api/admin/system", get(routes::admin::system_state))
+        .route("/api/admin/participants", get(routes::admin::search_participants))
+        .route("/api/admin/purge", post(routes::admin::purge))
+        .route("/api/admin/cache", get(routes::admin::cache_stats))
+        .route("/api/admin/instances", get(routes::admin::list_instances))
+        .route("/api/admin/migrations/plan", post(routes::admin::plan_migrations))
+        .route(
+            "/api/admin/log-level",
+            get(routes::admin::get_log_level)
+                .put(routes::admin::update_log_level)
+                .delete(routes::admin::reset_log_level),
+        )
+        .route("/api/admin/mail/failures", get(routes::admin::list_mail_failures))
+        .route("/api/admin/mail/failures/retry", post(routes::admin::retry_mail_failures))
+        .route("/api/admin/backups", get(routes::admin::list_backups).post(routes::admin::create_backup))
+        .route("/api/admin/storage", get(routes::admin::storage_usage))
+        .route("/api/admin/devices", get(routes::admin::list_devices))
+        .route("/api/admin/devices/:id", delete(routes::admin::revoke_device))
+        .route("/api/admin/export/full", get(routes::admin::export_full))
+        .route(
+            "/api/admin/import/full",
+            post(routes::admin::import_full).layer(DefaultBodyLimit::max(export::MAX_IMPORT_BYTES)),
+        )
+        .route(
+            session::SESSION_PATH,
+            get(routes::sessions::get_session)
+                .post(routes::sessions::create_session)
+                .delete(routes::sessions::delete_session),
+        )
+
+        // Admin settings
+        .route("/api/admin/settings/retention", get(routes::admin::get_retention).put(routes::admin::update_retention))
+        .route("/api/admin/settings/maintenance", get(routes::admin::get_maintenance).put(routes::admin::update_maintenance))
+        .route(
+            "/api/admin/settings/event-list",
+            get(routes::admin::get_event_list_settings).put(routes::admin::update_event_list_settings),
+        )
+
+        // Organizer accounts
+        .route("/api/organizers/signup", post(routes::organizers::signup))
+        .route("/api/organizers/login", post(routes::organizers::login))
+        .route("/api/organizers/me", get(routes::organizers::me))
+        .route("/api/organizers/:id/availability", get(routes::organizers::get_availability))
+        .route("/api/organizers/me/webhooks", get(routes::webhooks::list_webhooks).post(routes::webhooks::create_webhook))
+        .route(
+            "/api/organizers/me/webhooks/:id",
+            put(routes::webhooks::update_webhook).delete(routes::webhooks::delete_webhook),
+        )
+
+        // Registration analytics for organizers
+        .route("/api/analytics/sources", get(routes::analytics::source_conversion))
+
+        // Confirmation tokens for destructive operations
+        .route("/api/confirmations", post(routes::confirmations::prepare_confirmation))
+
+        // Consent withdrawal links from registration mails
+        .route("/api/registrations/cancel", post(routes::participants::cancel_registration))
+        .route("/api/consents/:token/withdraw", post(routes::consents::withdraw_marketing_consent))
+
+        // Reminder preference links from registrations and reminder mails
+        .route(
+            "/api/reminders/:token",
+            get(routes::reminders::get_preferences).put(routes::reminders::update_preferences),
+        )
+
+        // Organizer-defined mail wording
+        .route(
+            "/api/email-templates",
+            get(routes::email_templates::list_templates).post(routes::email_templates::create_template),
+        )
+        .route("/api/email-templates/preview", post(routes::email_templates::preview_template))
+        .route(
+            "/api/email-templates/:id",
+            get(routes::email_templates::get_template)
+                .put(routes::email_templates::update_template)
+                .delete(routes::email_templates::delete_template),
+        )
+
+        // Event routes
+        .route("/api/events", get(routes::events::list_events).post(routes::events::create_event))
+        .route("/api/events/batch", post(routes::events::batch_get_events))
+        .route("/api/events/trending", get(routes::events::trending_events))
+        .route("/api/events/by-slug/:slug", get(routes::events::get_event_by_slug))
+        .route("/api/events/:id", get(routes::events::get_event).put(routes::events::update_event).delete(routes::events::delete_event))
+        .route("/api/events/:id/announcements", post(routes::email_templates::send_announcement))
+        .route("/api/events/:id/audit", get(routes::audit::list_event_audit))
+        .route("/api/events/:id/digest", get(routes::digest::get_event_digest))
+        .route("/api/events/:id/export/start", post(routes::exports::start_export))
+        .route("/api/events/:id/publish", post(routes::events::publish_event))
+        .route("/api/events/:id/freeze", post(routes::events::freeze_event))
+        .route("/api/events/:id/unfreeze", post(routes::events::unfreeze_event))
+        .route(
+            "/api/events/:id/certificates",
+            get(routes::certificates::list_certificates).post(routes::certificates::issue_certificates),
+        )
+        .route("/api/certificates/:id", get(routes::certificates::download_certificate))
+        .route("/api/exports/:id", get(routes::exports::get_export))
+        .route("/api/exports/:id/download", get(routes::exports::download_export))
+
+        // Recurring event series
+        .route("/api/series", post(routes::series::create_series))
+        .route("/api/series/:id", get(routes::series::get_series).delete(routes::series::delete_series))
+        .route("/api/series/:id/occurrences", get(routes::series::list_occurrences))
+        .route(
+            "/api/series/:id/occurrences/:start",
+            put(routes::series::update_occurrence).delete(routes::series::delete_occurrence),
+        )
+        .route("/api/series/:id/occurrences/:start/event", post(routes::series::open_occurrence))
+
+        // Participant routes
+        .route("/api/events/:id/participants", get(routes::participants::list_participants))
+        .route("/api/events/:id/participants/diff", get(routes::participants::get_participant_diff))
+        .route("/api/events/:id/participants/email", post(routes::email_templates::send_bulk_mail))
+        .route("/api/events/:id/participants/email/:bulk_id", get(routes::email_templates::get_bulk_mail))
+        .route("/api/events/:id/availability", get(routes::participants::get_availability))
+        .route(
+            "/api/events/:id/registration-form",
+            get(routes::registration_forms::get_registration_form)
+                .put(routes::registration_forms::update_registration_form),
+        )
+        .route(
+            "/api/events/:id/seat-map",
+            get(routes::seating::get_seat_map).put(routes::seating::update_seat_map),
+        )
+        .route("/api/events/:id/seats", get(routes::seating::get_occupancy))
+        .route(
+            "/api/events/:id/holds",
+            get(routes::capacity_holds::list_holds).post(routes::capacity_holds::create_hold),
+        )
+        .route("/api/events/:id/holds/:hold_id", delete(routes::capacity_holds::release_hold))
+        .route("/api/events/:id/checkins", get(routes::checkins::list_checkins))
+        .route("/api/checkins/batch", post(routes::checkins::sync_checkins))
+        .route("/api/devices", post(routes::devices::register_device))
+        .route("/api/devices/heartbeat", post(routes::devices::heartbeat))
+        .route("/api/events/:id/referrals", get(routes::referrals::get_referrals))
+        .route(
+            "/api/participants",
+            post(routes::participants::create_participant)
+                .route_layer(middleware::from_fn_with_state(state.clone(), geo::gate)),
+        )
+        .route("/api/participants/:id", get(routes::participants::get_participant).put(routes::participants::update_participant_status).delete(routes::participants::delete_participant))
+        .route("/api/participants/:id/metadata", patch(routes::participants::update_participant_metadata))
+        .route("/api/participants/:id/checkin", post(routes::checkins::check_in))
+        .route("/api/participants/:id/seat", post(routes::seating::assign_seat).delete(routes::seating::release_seat))
+        .route("/api/participants/:id/webhook-deliveries", get(routes::webhooks::list_participant_deliveries))
+        .route("/api/participants/by-email/:email", delete(routes::personal_data::erase_personal_data))
+        .route("/api/participants/by-email/:email/export", get(routes::personal_data::export_personal_data))
+
+        // Structured JSON 404 for unknown routes
+        .fallback(routes::fallback::not_found)
+
+        // Bound concurrent handler work; requests beyond the limit queue,
+        // and are shed once the queue is full
+        .layer(middleware::from_fn(concurrency::admit))
+        .layer(state.concurrency.layer())
+        .layer(middleware::from_fn_with_state(state.clone(), concurrency::enqueue))
+
+        // Probes, metrics and the long-lived SSE stream bypass the concurrency limit
+        .route("/health", get(health_check))
+        .route("/ready", get(readiness_check))
+        .route("/metrics", get(metrics::metrics_handler))
+        .route("/metrics/autoscale", get(metrics::autoscale_handler))
+        .route("/api/events/stream", get(routes::sse::event_stream));
+
+    // Recorded response fixtures for the frontend's mock server
+    #[cfg(debug_assertions)]
+    let router = router
+        .route("/api/debug/fixtures", get(fixtures::list_fixtures))
+        .route("/api/debug/fixtures/:name", get(fixtures::get_fixture));
+
+    let router = router
+        // Debug-only comparison of cached reads against the database
+        .layer(middleware::from_fn_with_state(state.clone(), cache_audit::cache_audit))
+
+        // Staging-only latency and errors for exercising client retries
+        .layer(middleware::from_fn_with_state(state.clone(), chaos::inject))
+
+        // JSON body for 405 responses (Allow header preserved)
+        .layer(middleware::from_fn(routes::fallback::method_not_allowed))
+
+        // Admin panel sessions from the session cookie, with CSRF checks
+        .layer(middleware::from_fn_with_state(state.clone(), session::load_session))
+
+        // Destructive operations need a prepared confirmation token
+        .layer(middleware::from_fn_with_state(state.clone(), confirmation::require_confirmation))
+
+        // Signed-in organizers from `Authorization: Bearer`
+        .layer(middleware::from_fn_with_state(state.clone(), organizers::authenticate))
+
+        // Kiosk devices from `X-Device-Token`
+        .layer(middleware::from_fn_with_state(state.clone(), devices::authenticate))
+
+        // Admins acting as an organizer; audited per request
+        .layer(middleware::from_fn_with_state(state.clone(), impersonation::impersonate))
+
+        // Announce the operator maintenance notice on every response
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance::maintenance_header))
+
+        // Shed low-priority requests under DB pressure
+        .layer(middleware::from_fn_with_state(state.clone(), load_shed::load_shed))
+
+        // snake_case or camelCase JSON keys, as negotiated per request
+        .layer(middleware::from_fn_with_state(state.clone(), naming::negotiate))
+
+        // Refuse oversized bodies before anything buffers them; the limit
+        // also caps bodies sent without a length
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(state.clone(), body_limit::limit))
+
+        // Public and admin CORS policies, chosen by path
+        .layer(middleware::from_fn_with_state(Arc::new(cors::Policies::new(&config.cors)), cors::apply))
+
+        // Add state
+        .with_state(state);
+
+    // Trailing slashes are trimmed before routing so `/api/events/` matches `/api/events`
+    Router::new().fallback_service(NormalizePathLayer::trim_trailing_slash().layer(router))
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Routes that may be shed under pressure (GET only). Writes and health
+/// checks are never shed so capacity is preserved for them.
+const LOW_PRIORITY_ROUTES: &[&str] = &[
+    "/api/events",
+    "/api/events/:id/participants",
+    "/api/events/ndjson",
+    "/api/participants/ndjson",
+];
+
+/// Thresholds for load shedding
+#[derive(Debug, Clone)]
+pub struct LoadShedConfig {
+    /// In-flight request count above which low-priority requests are shed
+    pub max_in_flight: usize,
+    /// Pool acquire time above which low-priority requests are shed
+    pub acquire_threshold: Duration,
+    /// Value of the Retry-After header on shed responses
+    pub retry_after_secs: u64,
+}
+
+impl LoadShedConfig {
+    /// Read thresholds from LOAD_SHED_* environment variables
+    pub fn from_env() -> Self {
+        let max_in_flight = std::env::var("LOAD_SHED_MAX_IN_FLIGHT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(256);
+        let acquire_threshold_ms = std::env::var("LOAD_SHED_ACQUIRE_THRESHOLD_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(250);
+        let retry_after_secs = std::env::var("LOAD_SHED_RETRY_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+
+        Self {
+            max_in_flight,
+            acquire_threshold: Duration::from_millis(acquire_threshold_ms),
+            retry_after_secs,
+        }
+    }
+}
+
+impl Default for LoadShedConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 256,
+            acquire_threshold: Duration::from_millis(250),
+            retry_after_secs: 5,
+        }
+    }
+}
+
+/// Tracks in-flight requests and decides when to shed load
+#[derive(Clone)]
+pub struct LoadShedder {
+    config: Arc<LoadShedConfig>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadShedConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Number of requests currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Whether the in-flight limit is exceeded; the SSE stream checks this
+    /// itself so it can advise clients when to come back
+    pub fn is_overloaded(&self) -> bool {
+        self.in_flight() > self.config.max_in_flight
+    }
+
+    /// 503 with Retry-After, shared by every shedding path
+    pub(crate) fn shed_response(&self) -> Response {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, self.config.retry_after_secs.to_string())],
+            Json(json!({ "error": "Service temporarily overloaded" })),
+        )
+            .into_response()
+    }
+}
+
+impl Default for LoadShedder {
+    fn default() -> Self {
+        Self::new(LoadShedConfig::default())
+    }
+}
+
+/// Decrements the in-flight counter when the request finishes
+struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn is_low_priority(method: &Method, path: Option<&MatchedPath>) -> bool {
+    method == Method::GET
+        && path.is_some_and(|p| LOW_PRIORITY_ROUTES.contains(&p.as_str()))
+}
+
+/// Middleware returning 503 with Retry-After for low-priority requests
+/// when the in-flight limit is exceeded or the DB pool is saturated
+pub async fn load_shed(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let shedder = &state.load_shedder;
+    let in_flight = shedder.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+    let _guard = InFlightGuard(shedder.in_flight.clone());
+
+    if is_low_priority(request.method(), request.extensions().get::<MatchedPath>()) {
+        if in_flight > shedder.config.max_in_flight {
+            warn!("Shedding request: {} requests in flight", in_flight);
+            return shedder.shed_response();
+        }
+
+        // Probe the pool; a slow acquire means SQLite connections are saturated
+        let probe = tokio::time::timeout(shedder.config.acquire_threshold, state.db_pool.acquire()).await;
+        match probe {
+            Ok(Ok(_conn)) => {}
+            Ok(Err(e)) => {
+                warn!("Shedding request: pool acquire failed: {}", e);
+                return shedder.shed_response();
+            }
+            Err(_) => {
+                warn!(
+                    "Shedding request: pool acquire exceeded {:?}",
+                    shedder.config.acquire_threshold
+                );
+                return shedder.shed_response();
+            }
+        }
+    }
+
+    next.run(request).await
+}
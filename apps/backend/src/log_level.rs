@@ -0,0 +1,172 @@
+//! Changing the log filter of a running instance.
+//!
+//! `PUT /api/admin/log-level` swaps the tracing `EnvFilter` through the
+//! reload layer installed at startup, for a limited time only: once the
+//! change expires, the configured default (RUST_LOG, or `FALLBACK_FILTER`)
+//! comes back, so debug logging turned on during an incident cannot be
+//! forgotten. A change applies to the instance that handled the request.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+use validator::Validate;
+
+/// Filter used when RUST_LOG is unset or invalid
+pub const FALLBACK_FILTER: &str = "backend=debug,tower_http=debug";
+/// How long a change lasts when the request names no duration
+pub const DEFAULT_TTL_SECS: u64 = 600;
+/// Longest a change may last
+pub const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+
+type Apply = Arc<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+/// A requested filter change
+#[derive(Debug, Deserialize, Validate)]
+pub struct LogLevelChange {
+    /// `EnvFilter` directives, e.g. `backend=trace,sqlx=debug`
+    pub filter: String,
+    #[serde(default = "default_ttl_secs")]
+    #[validate(range(min = 1, max = MAX_TTL_SECS, code = "out_of_range", message = "must be between 1 and 86400 seconds"))]
+    pub ttl_secs: u64,
+}
+
+fn default_ttl_secs() -> u64 {
+    DEFAULT_TTL_SECS
+}
+
+/// The filter in effect on this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLevel {
+    pub filter: String,
+    pub default_filter: String,
+    /// When the default comes back; None while it is in effect
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// The filter of the instance that answered, which need not be the one
+/// that answers the next request
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceLogLevel {
+    pub instance: String,
+    #[serde(flatten)]
+    pub level: LogLevel,
+}
+
+struct Current {
+    filter: String,
+    expires_at: Option<DateTime<Utc>>,
+    /// Bumped by every change, so a superseded expiry leaves it alone
+    generation: u64,
+}
+
+/// Handle for changing the log filter at runtime
+#[derive(Clone)]
+pub struct LogControl {
+    default: Arc<str>,
+    apply: Option<Apply>,
+    current: Arc<Mutex<Current>>,
+}
+
+impl LogControl {
+    fn new(default: String, apply: Option<Apply>) -> Self {
+        Self {
+            current: Arc::new(Mutex::new(Current {
+                filter: default.clone(),
+                expires_at: None,
+                generation: 0,
+            })),
+            default: default.into(),
+            apply,
+        }
+    }
+
+    /// A control without a reload layer: changes are tracked but filter
+    /// nothing, as in tests where no subscriber is installed
+    pub fn detached() -> Self {
+        Self::new(FALLBACK_FILTER.to_string(), None)
+    }
+
+    pub fn current(&self) -> LogLevel {
+        let current = self.current.lock().unwrap();
+        LogLevel {
+            filter: current.filter.clone(),
+            default_filter: self.default.to_string(),
+            expires_at: current.expires_at,
+        }
+    }
+
+    fn install(&self, filter: &str) -> Result<(), String> {
+        let parsed = EnvFilter::try_new(filter).map_err(|e| e.to_string())?;
+        match &self.apply {
+            Some(apply) => apply(parsed),
+            None => Ok(()),
+        }
+    }
+
+    /// Switch to `filter` until `ttl` has passed; fails with the parse
+    /// error for invalid directives
+    pub fn set(&self, filter: &str, ttl: Duration, now: DateTime<Utc>) -> Result<LogLevel, String> {
+        self.install(filter)?;
+
+        let generation = {
+            let mut current = self.current.lock().unwrap();
+            current.filter = filter.to_string();
+            current.expires_at = Some(now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX));
+            current.generation += 1;
+            current.generation
+        };
+
+        let control = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(ttl).await;
+            control.revert(Some(generation));
+        });
+
+        Ok(self.current())
+    }
+
+    /// Go back to the default now
+    pub fn reset(&self) -> LogLevel {
+        self.revert(None);
+        self.current()
+    }
+
+    /// Restore the default, unless `generation` was superseded by a later
+    /// change
+    fn revert(&self, generation: Option<u64>) {
+        let mut current = self.current.lock().unwrap();
+        if generation.is_some_and(|g| g != current.generation) {
+            return;
+        }
+        if let Err(e) = self.install(&self.default) {
+            tracing::error!("Failed to restore log filter {}: {}", self.default, e);
+            return;
+        }
+        current.filter = self.default.to_string();
+        current.expires_at = None;
+        current.generation += 1;
+        tracing::info!("Log filter back at {}", self.default);
+    }
+}
+
+impl Default for LogControl {
+    fn default() -> Self {
+        Self::detached()
+    }
+}
+
+/// The filter layer to install in the subscriber at startup, starting at
+/// RUST_LOG, and the control that changes it later
+pub fn reloadable_filter() -> (reload::Layer<EnvFilter, Registry>, LogControl) {
+    let default = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|v| EnvFilter::try_new(v).is_ok())
+        .unwrap_or_else(|| FALLBACK_FILTER.to_string());
+
+    let (layer, handle) = reload::Layer::new(EnvFilter::new(&default));
+    let apply: Apply = Arc::new(move |filter| handle.reload(filter).map_err(|e| e.to_string()));
+
+    (layer, LogControl::new(default, Some(apply)))
+}
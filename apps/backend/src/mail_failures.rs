@@ -0,0 +1,215 @@
+//! Surfacing mails that could not be sent.
+//!
+//! Mail goes out in the background after the request that caused it has
+//! been answered, so an SMTP outage never reaches a client. `deliver`
+//! stores every failed mail whole, for `GET /api/admin/mail/failures` to
+//! list and `POST /api/admin/mail/failures/retry` to resend once the relay
+//! is back. Each instance also keeps the outcomes of its recent sends:
+//! when the failure rate reaches MAIL_FAILURE_ALERT_PERCENT, a
+//! `mail_failures` notification goes out on the `system` channel, and
+//! another once the rate drops below it again.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::FromRow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::broadcaster::Channel;
+use crate::db::{self, DbPool};
+use crate::encryption::{self, PiiText};
+use crate::mailer::{MailConfig, MailError, OutgoingMail};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Fewest sends the failure rate is judged on, so one bounced mail after
+/// a quiet night does not alert
+const MIN_SAMPLES: usize = 5;
+
+/// A stored failed mail, without its content
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MailFailure {
+    pub id: i64,
+    #[sqlx(try_from = "PiiText")]
+    pub to_address: String,
+    pub subject: String,
+    /// Error of the latest attempt
+    pub error: String,
+    pub attempts: i64,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_failed_at: DateTime<Utc>,
+}
+
+/// Outcome of resending stored failures
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RetryReport {
+    pub retried: usize,
+    pub sent: usize,
+    pub failed: usize,
+}
+
+/// Whether this instance currently considers mail delivery broken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailHealth {
+    Failing,
+    Recovered,
+}
+
+#[derive(Default)]
+struct Outcomes {
+    /// Recent sends, true for delivered
+    recent: VecDeque<bool>,
+    failing: bool,
+}
+
+/// Outcomes of this instance's recent sends
+#[derive(Clone, Default)]
+pub struct MailOutcomes {
+    inner: Arc<Mutex<Outcomes>>,
+}
+
+impl MailOutcomes {
+    /// Record a send; returns the new health when the failure rate just
+    /// crossed the alert threshold in either direction
+    pub fn record(&self, delivered: bool, config: &MailConfig) -> Option<(MailHealth, f64)> {
+        let mut outcomes = self.inner.lock().unwrap();
+        while outcomes.recent.len() >= config.failure_window {
+            outcomes.recent.pop_front();
+        }
+        outcomes.recent.push_back(delivered);
+
+        let samples = outcomes.recent.len();
+        if samples < MIN_SAMPLES.min(config.failure_window) {
+            return None;
+        }
+        let failed = outcomes.recent.iter().filter(|ok| !**ok).count();
+        let rate = failed as f64 / samples as f64;
+        let failing = failed * 100 >= config.failure_alert_percent as usize * samples;
+
+        if failing == outcomes.failing {
+            return None;
+        }
+        outcomes.failing = failing;
+        Some((if failing { MailHealth::Failing } else { MailHealth::Recovered }, rate))
+    }
+}
+
+/// Send a mail, storing it for retry if that fails
+pub async fn deliver(state: &AppState, mail: &OutgoingMail) -> Result<(), MailError> {
+    let result = state.mailer.send(mail).await;
+    observe(state, result.is_ok()).await;
+
+    if let Err(e) = &result {
+        if let Err(db_error) = record(&state.db_pool, mail, &e.0, state.clock.now()).await {
+            tracing::error!("Failed to store failed mail to {}: {}", mail.to_address, db_error);
+        }
+    }
+
+    result
+}
+
+/// Count a send towards the failure rate and announce threshold crossings
+async fn observe(state: &AppState, delivered: bool) {
+    let Some((health, rate)) = state.mail_outcomes.record(delivered, &state.config.mail) else {
+        return;
+    };
+
+    let now = state.clock.now();
+    match health {
+        MailHealth::Failing => tracing::error!("Mail failure rate at {:.0}%", rate * 100.0),
+        MailHealth::Recovered => tracing::info!("Mail failure rate back at {:.0}%", rate * 100.0),
+    }
+
+    let payload = json!({
+        "type": "mail_failures",
+        "state": health,
+        "failure_rate": rate,
+        "window": state.config.mail.failure_window,
+        "instance_id": state.instance.id(),
+        "timestamp": now
+    })
+    .to_string();
+    if let Err(e) = db::insert_notification(&state.db_pool, Channel::System, &payload, now).await {
+        tracing::error!("Failed to insert system notification: {}", e);
+    }
+}
+
+/// Store a failed mail
+async fn record(pool: &DbPool, mail: &OutgoingMail, error: &str, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    let content = serde_json::to_string(mail).expect("mails serialize");
+
+    sqlx::query(
+        "INSERT INTO mail_failures (to_address, to_address_index, subject, mail, error, first_failed_at, last_failed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)"
+    )
+    .bind(PiiText(mail.to_address.clone()))
+    .bind(encryption::blind_index(&mail.to_address))
+    .bind(&mail.subject)
+    .bind(PiiText(content))
+    .bind(error)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Stored failures, most recent first
+pub async fn list(pool: &DbPool) -> Result<Vec<MailFailure>, sqlx::Error> {
+    sqlx::query_as::<_, MailFailure>(
+        "SELECT id, to_address, subject, error, attempts, first_failed_at, last_failed_at
+         FROM mail_failures
+         ORDER BY last_failed_at DESC, id DESC"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Resend stored failures, oldest first; delivered ones are removed, the
+/// others keep their place with the new error
+pub async fn retry(state: &AppState) -> Result<RetryReport, sqlx::Error> {
+    let stored = sqlx::query_as::<_, (i64, PiiText)>("SELECT id, mail FROM mail_failures ORDER BY id")
+        .fetch_all(&state.db_pool)
+        .await?;
+
+    let mut report = RetryReport::default();
+    for (id, PiiText(content)) in stored {
+        let mail: OutgoingMail = match serde_json::from_str(&content) {
+            Ok(mail) => mail,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable failed mail {}: {}", id, e);
+                continue;
+            }
+        };
+
+        report.retried += 1;
+        let result = state.mailer.send(&mail).await;
+        observe(state, result.is_ok()).await;
+
+        match result {
+            Ok(()) => {
+                report.sent += 1;
+                sqlx::query("DELETE FROM mail_failures WHERE id = ?")
+                    .bind(id)
+                    .execute(&state.db_pool)
+                    .await?;
+            }
+            Err(e) => {
+                report.failed += 1;
+                sqlx::query(
+                    "UPDATE mail_failures SET error = ?, attempts = attempts + 1, last_failed_at = ? WHERE id = ?"
+                )
+                .bind(&e.0)
+                .bind(state.clock.now())
+                .bind(id)
+                .execute(&state.db_pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,241 @@
+//! Outgoing mail transport.
+//!
+//! `Mailer` is the pluggable sender used by everything that emails people.
+//! `SmtpMailer` delivers through the server configured with SMTP_*; without
+//! SMTP_HOST the `LogMailer` only logs what would have been sent, so
+//! development setups and tests need no mail server.
+
+use mail_send::mail_builder::headers::content_type::ContentType;
+use mail_send::mail_builder::MessageBuilder;
+use mail_send::SmtpClientBuilder;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ics::Organizer;
+
+/// SMTP connection settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// TLS from the first byte (port 465) instead of STARTTLS
+    pub implicit_tls: bool,
+}
+
+/// Sender identity and transport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailConfig {
+    /// None logs mails instead of sending them
+    pub smtp: Option<SmtpConfig>,
+    pub from_name: String,
+    pub from_address: String,
+    /// Failure rate, in percent of recent sends, at which operators are
+    /// alerted (MAIL_FAILURE_ALERT_PERCENT)
+    pub failure_alert_percent: u32,
+    /// Number of recent sends the failure rate is computed over
+    /// (MAIL_FAILURE_WINDOW)
+    pub failure_window: usize,
+    /// Most organizer bulk mails sent a minute (MAIL_BULK_PER_MINUTE)
+    pub bulk_per_minute: u32,
+}
+
+impl MailConfig {
+    /// Read SMTP_* and MAIL_FROM* environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let smtp = std::env::var("SMTP_HOST").ok().filter(|h| !h.is_empty()).map(|host| {
+            let port = std::env::var("SMTP_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(587);
+            SmtpConfig {
+                host,
+                port,
+                username: std::env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+                password: std::env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+                implicit_tls: std::env::var("SMTP_IMPLICIT_TLS")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(port == 465),
+            }
+        });
+
+        Self {
+            smtp,
+            from_name: std::env::var("MAIL_FROM_NAME")
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or(defaults.from_name),
+            from_address: std::env::var("MAIL_FROM")
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or(defaults.from_address),
+            failure_alert_percent: std::env::var("MAIL_FAILURE_ALERT_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|p| (1..=100).contains(p))
+                .unwrap_or(defaults.failure_alert_percent),
+            failure_window: std::env::var("MAIL_FAILURE_WINDOW")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|w| *w > 0)
+                .unwrap_or(defaults.failure_window),
+            bulk_per_minute: std::env::var("MAIL_BULK_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.bulk_per_minute),
+        }
+    }
+
+    /// The sender as it appears in calendar invites
+    pub fn organizer(&self) -> Organizer {
+        Organizer {
+            name: self.from_name.clone(),
+            email: self.from_address.clone(),
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            smtp: None,
+            from_name: "Events".to_string(),
+            from_address: "events@localhost".to_string(),
+            failure_alert_percent: 50,
+            failure_window: 20,
+            bulk_per_minute: 60,
+        }
+    }
+}
+
+/// A file attached to a mail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub filename: String,
+    /// MIME type without parameters, e.g. "text/calendar"
+    pub content_type: String,
+    /// Content-Type parameters, e.g. ("method", "REQUEST")
+    pub parameters: Vec<(String, String)>,
+    pub content: Vec<u8>,
+}
+
+/// A plain-text mail to one recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutgoingMail {
+    pub to_name: String,
+    pub to_address: String,
+    pub subject: String,
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+}
+
+/// Why a mail could not be sent
+#[derive(Debug, Clone)]
+pub struct MailError(pub String);
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// Delivers mails
+#[axum::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), MailError>;
+}
+
+/// Logs mails instead of sending them
+pub struct LogMailer;
+
+#[axum::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), MailError> {
+        tracing::info!(
+            "Mail not sent (SMTP_HOST unset): to={} subject={:?} attachments={}",
+            mail.to_address,
+            mail.subject,
+            mail.attachments.len()
+        );
+        Ok(())
+    }
+}
+
+/// Time allowed for connecting, authenticating and sending one mail
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends through an SMTP relay, one connection per mail
+pub struct SmtpMailer {
+    smtp: SmtpConfig,
+    from_name: String,
+    from_address: String,
+}
+
+impl SmtpMailer {
+    pub fn new(smtp: SmtpConfig, from_name: String, from_address: String) -> Self {
+        Self {
+            smtp,
+            from_name,
+            from_address,
+        }
+    }
+
+    fn build<'a>(&'a self, mail: &'a OutgoingMail) -> MessageBuilder<'a> {
+        let mut message = MessageBuilder::new()
+            .from((self.from_name.as_str(), self.from_address.as_str()))
+            .to((mail.to_name.as_str(), mail.to_address.as_str()))
+            .subject(mail.subject.as_str())
+            .text_body(mail.text.as_str());
+
+        for attachment in &mail.attachments {
+            let content_type = attachment
+                .parameters
+                .iter()
+                .fold(ContentType::new(attachment.content_type.as_str()), |ct, (key, value)| {
+                    ct.attribute(key.as_str(), value.as_str())
+                });
+            message = message.attachment(content_type, attachment.filename.as_str(), attachment.content.as_slice());
+        }
+
+        message
+    }
+}
+
+#[axum::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), MailError> {
+        let mut builder = SmtpClientBuilder::new(self.smtp.host.as_str(), self.smtp.port)
+            .implicit_tls(self.smtp.implicit_tls)
+            .timeout(SMTP_TIMEOUT);
+        if let (Some(username), Some(password)) = (&self.smtp.username, &self.smtp.password) {
+            builder = builder.credentials((username.as_str(), password.as_str()));
+        }
+
+        let mut client = builder
+            .connect()
+            .await
+            .map_err(|e| MailError(format!("SMTP connect to {} failed: {}", self.smtp.host, e)))?;
+        client
+            .send(self.build(mail))
+            .await
+            .map_err(|e| MailError(format!("SMTP send failed: {}", e)))
+    }
+}
+
+/// The mailer for this configuration
+pub fn from_config(config: &MailConfig) -> Arc<dyn Mailer> {
+    match &config.smtp {
+        Some(smtp) => Arc::new(SmtpMailer::new(
+            smtp.clone(),
+            config.from_name.clone(),
+            config.from_address.clone(),
+        )),
+        None => Arc::new(LogMailer),
+    }
+}
@@ -1,36 +1,54 @@
-use axum::{
-    routing::{get, post},
-    Router,
-};
+use axum::Router;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use axum::extract::{ConnectInfo, Request};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::time::Duration;
+use tokio::net::TcpListener;
 use tokio::sync::Mutex;
-use tower_http::cors::CorsLayer;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+#[cfg(unix)]
+use {std::path::PathBuf, tokio::net::UnixListener};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use backend::{AppState, health_check};
-use backend::{broadcaster::Broadcaster, cache::AppCache, db, routes};
+use backend::{AppState, build_router};
+use backend::{broadcaster::Broadcaster, cache::AppCache, db};
+use backend::config::{BindTarget, Config, Role};
+use backend::tls::{self, TlsConfig};
+use backend::clock::{SharedClock, SystemClock};
+use backend::ids::IdGenerator;
+use backend::instance::{self, Instance};
+use backend::concurrency::ConcurrencyLimits;
+use backend::load_shed::LoadShedder;
+use backend::reputation::{AllOf, DisposableDomains, EmailReputation, NoReputationCheck};
+use backend::watchdog::{self, TaskHealth};
+use backend::domain_events::DomainEventBus;
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
+    // Initialize tracing; the filter can be changed at runtime through
+    // PUT /api/admin/log-level
+    let (log_filter, log_level) = backend::log_level::reloadable_filter();
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "backend=debug,tower_http=debug".into()),
-        )
+        .with(log_filter)
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // Data directory from environment (writable filesystem path)
-    let data_dir = std::env::var("DATA_DIR")
-        .unwrap_or_else(|_| "/run/media".to_string());
+    let mut config = Config::from_env();
+    if let Some(role) = Role::from_args(std::env::args().skip(1)).expect("Invalid --role") {
+        config.role = role;
+    }
 
     // Ensure data directory exists
-    std::fs::create_dir_all(&data_dir)
+    std::fs::create_dir_all(&config.data_dir)
         .expect("Failed to create data directory");
 
-    let db_path = std::path::Path::new(&data_dir).join("data.db");
+    let db_path = std::path::Path::new(&config.data_dir).join("data.db");
 
     // Initialize SQLite database pool
     let db_path = db_path
@@ -41,45 +59,192 @@ async fn main() {
         .await
         .expect("Failed to create database pool");
 
+    // Participant names and emails are sealed under the PII key, starting
+    // with the rows still in plain text when the tables are initialized
+    if let Some(key) = &config.pii_key {
+        backend::encryption::install(key).expect("Failed to install PII key");
+    }
+
     // Initialize database tables
-    db::initialize_tables(&db_pool)
+    db::initialize_schema(&db_pool, config.auto_migrate)
         .await
         .expect("Failed to initialize database tables");
 
-    // Create in-memory cache with TTL
-    let cache_ttl: u64 = std::env::var("CACHE_TTL_SECS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(60);
+    // Not ready until the poller has caught up (see `instance`)
+    let instance = Instance::new(&config.instance_id, chrono::Utc::now());
+    tracing::info!("Starting instance {} ({})", instance.id(), config.role);
 
-    let cache = AppCache::new(cache_ttl);
+    // Create in-memory cache with TTL
+    let cache = AppCache::new(config.cache_ttl_secs);
 
     // Create broadcaster for SSE
     let broadcaster = Broadcaster::new();
 
-    // Start notification poller for cross-instance sync
+    // Wall-clock time source shared by handlers and background tasks
+    let clock: SharedClock = Arc::new(SystemClock);
+
+    // Start notification poller for cross-instance sync, restarted with
+    // backoff if it dies and watched for stalls. Every role runs it: web
+    // instances fan changes out to their SSE clients and drop stale cache
+    // entries, and its health decides readiness either way.
+    let poller_health = TaskHealth::new();
+    let poller_metrics = db::PollerMetrics::new();
     let last_id = Arc::new(Mutex::new(
         db::get_max_notification_id(&db_pool).await,
     ));
-    tokio::spawn(db::start_notification_poller(
+    {
+        let (pool, broadcaster, cache, clock, health, metrics) = (
+            db_pool.clone(),
+            broadcaster.clone(),
+            cache.clone(),
+            clock.clone(),
+            poller_health.clone(),
+            poller_metrics.clone(),
+        );
+        tokio::spawn(watchdog::supervise("notification poller", poller_health.clone(), move || {
+            db::start_notification_poller(
+                pool.clone(),
+                broadcaster.clone(),
+                cache.clone(),
+                clock.clone(),
+                last_id.clone(),
+                health.clone(),
+                metrics.clone(),
+            )
+        }));
+    }
+    tokio::spawn(watchdog::watch(
+        "notification poller",
+        poller_health.clone(),
+        clock.clone(),
+        config.poller_stall_threshold,
+    ));
+
+    // Heartbeat row for deploy coordination; flips the instance to ready
+    tokio::spawn(instance::start_heartbeat(
         db_pool.clone(),
-        broadcaster.clone(),
-        cache.clone(),
-        last_id,
+        instance.clone(),
+        poller_health.clone(),
+        clock.clone(),
+        config.heartbeat_interval,
     ));
 
+    if config.role.runs_jobs() {
+        // Drop notifications and audit entries past their retention, and
+        // export files past their download window
+        tokio::spawn(db::start_retention(
+            db_pool.clone(),
+            clock.clone(),
+            config.data_dir.clone(),
+            RETENTION_INTERVAL,
+            poller_metrics.clone(),
+        ));
+
+        // Opt-in anonymous usage reporting (no-op unless TELEMETRY_ENDPOINT is set)
+        tokio::spawn(backend::telemetry::start_reporter(
+            db_pool.clone(),
+            clock.clone(),
+            config.telemetry.clone(),
+        ));
+    }
+
+    if config.role.serves_http() {
+        // Catch changes whose notification never made it to the caches
+        tokio::spawn(backend::reconcile::start(
+            db_pool.clone(),
+            cache.clone(),
+            config.reconcile_interval,
+        ));
+    }
+
+    // Load shedding thresholds for low-priority endpoints
+    let load_shedder = LoadShedder::new(config.load_shed.clone());
+
+    // Disposable-domain blocking when a block list is configured, then
+    // the MX check when enabled
+    let mut email_checks: Vec<Arc<dyn EmailReputation>> = Vec::new();
+    let blocked_domains = DisposableDomains::from_env();
+    if !blocked_domains.is_empty() {
+        email_checks.push(Arc::new(blocked_domains));
+    }
+    if config.email_mx_check {
+        #[cfg(feature = "mx-check")]
+        match backend::email::MxRecords::from_system_conf() {
+            Ok(mx) => email_checks.push(Arc::new(mx)),
+            Err(e) => panic!("EMAIL_MX_CHECK is set but DNS is not configured: {}", e),
+        }
+        #[cfg(not(feature = "mx-check"))]
+        tracing::warn!("EMAIL_MX_CHECK is set but this build lacks the mx-check feature; not checking");
+    }
+    let email_reputation: Arc<dyn EmailReputation> = match email_checks.len() {
+        0 => Arc::new(NoReputationCheck),
+        1 => email_checks.remove(0),
+        _ => Arc::new(AllOf(email_checks)),
+    };
+
     // Create shared application state
     let app_state = AppState {
         db_pool,
         broadcaster,
+        domain_events: DomainEventBus::new(),
         cache,
+        load_shedder,
+        concurrency: ConcurrencyLimits::new(config.concurrency.clone()),
+        clock,
+        ids: IdGenerator::new(config.id_version),
+        config: Arc::new(config.clone()),
+        poller_health,
+        poller_metrics,
+        geo: backend::geo::GeoGate::from_config(&config.geo),
+        email_reputation,
+        mailer: backend::mailer::from_config(&config.mail),
+        mail_outcomes: Default::default(),
+        instance: instance.clone(),
+        log_level,
+        views: Default::default(),
     };
+    let shutdown_pool = app_state.db_pool.clone();
+
+    // Confirmation mails with calendar invites for registration changes.
+    // Domain events stay in the process that published them, so this runs
+    // wherever requests are handled as well as next to the digests.
+    tokio::spawn(backend::registration_mail::start_sender(app_state.clone()));
+    // CRM webhook deliveries for status changes, queued in the database
+    tokio::spawn(backend::webhooks::start_enqueuer(app_state.clone()));
+
+    if config.role.runs_jobs() {
+        // Daily organizer digests, published on the domain event bus
+        tokio::spawn(backend::digest::start_scheduler(
+            app_state.clone(),
+            config.digest_interval,
+        ));
+
+        // Event reminders at the lead times participants chose
+        tokio::spawn(backend::reminders::start_scheduler(
+            app_state.clone(),
+            config.reminder_interval,
+        ));
+
+        // Organizer bulk mails, throttled to MAIL_BULK_PER_MINUTE
+        tokio::spawn(backend::bulk_mail::start_sender(app_state.clone()));
+
+        // Queued CRM webhook deliveries, retried with backoff
+        tokio::spawn(backend::webhooks::start_dispatcher(app_state.clone()));
+
+        // Ended events become completed, closing them to participant changes
+        if let Some(interval) = config.event_completion_interval {
+            tokio::spawn(backend::completion::start_scheduler(app_state.clone(), interval));
+        }
+    }
+
+    if !config.role.serves_http() {
+        tracing::info!("Worker running without HTTP listener");
+        shutdown_signal(shutdown_pool, instance).await;
+        return;
+    }
 
-    // Build application router
-    let cors_origin = std::env::var("CORS_ORIGIN").unwrap_or_else(|_| "".to_string());
-    
     // Check CORS_ORIGIN requirement
-    if cors_origin.is_empty() {
+    if config.cors.origin.is_empty() {
         let rust_log = std::env::var("RUST_LOG").unwrap_or_default();
 
         if rust_log != "debug" {
@@ -88,56 +253,237 @@ async fn main() {
             tracing::warn!("CORS_ORIGIN is not set - allowing all origins in debug mode");
         }
     }
-    
-    let cors_layer = if cors_origin.is_empty() {
-        CorsLayer::new()
-            .allow_origin(tower_http::cors::Any)
-            .allow_methods(tower_http::cors::Any)
-            .allow_headers(tower_http::cors::Any)
-    } else {
-        CorsLayer::new()
-            .allow_origin(cors_origin.parse::<axum::http::HeaderValue>().expect("Invalid CORS_ORIGIN"))
-            .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT, axum::http::Method::DELETE])
-            .allow_headers([axum::http::header::CONTENT_TYPE])
-    };
 
-    let app = Router::new()
-        // Health check
-        .route("/health", get(health_check))
-        
-        // SSE stream endpoint (static route must be before :id param to avoid matchit capture)
-        .route("/api/events/stream", get(routes::sse::event_stream))
-        
-        // Event routes
-        .route("/api/events", get(routes::events::list_events).post(routes::events::create_event))
-        .route("/api/events/:id", get(routes::events::get_event).put(routes::events::update_event).delete(routes::events::delete_event))
-        
-        // Participant routes
-        .route("/api/events/:id/participants", get(routes::participants::list_participants))
-        .route("/api/participants", post(routes::participants::create_participant))
-        .route("/api/participants/:id", get(routes::participants::get_participant).put(routes::participants::update_participant_status).delete(routes::participants::delete_participant))
-        
-        // Add CORS middleware
-        .layer(cors_layer)
-        
-        // Add state
-        .with_state(app_state);
+    // Event views counted by request handlers, written behind in batches
+    let views = app_state.views.clone();
+    tokio::spawn(backend::popularity::start_flusher(
+        app_state.db_pool.clone(),
+        views.clone(),
+        app_state.clock.clone(),
+        config.popularity_flush_interval,
+    ));
+    let flush_pool = app_state.db_pool.clone();
+    let flush_clock = app_state.clock.clone();
+
+    // Build application router
+    let app = build_router(app_state, &config);
 
     // Start server
-    let port = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(3000);
-    
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    
-    tracing::info!("Server listening on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr)
+    let listener = Listener::bind(&config.bind, config.tls.as_ref())
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
+    tracing::info!(
+        "Server listening on {}{}",
+        config.bind,
+        if config.tls.is_some() { " (TLS)" } else { "" }
+    );
+
+    // Plain-HTTP listener sending clients to the TLS port
+    if let (Some(redirect_port), BindTarget::Tcp(addr)) =
+        (config.tls.as_ref().and_then(|tls| tls.redirect_port), &config.bind)
+    {
+        let (redirect_addr, https_port) = (SocketAddr::new(addr.ip(), redirect_port), addr.port());
+        let redirect_listener = TcpListener::bind(redirect_addr)
+            .await
+            .expect("Failed to bind redirect listener");
+        tracing::info!("Redirecting HTTP on {} to HTTPS", redirect_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(redirect_listener, tls::redirect_router(https_port)).await {
+                tracing::error!("Redirect listener failed: {}", e);
+            }
+        });
+    }
+
+    listener
+        .serve(app, shutdown_signal(shutdown_pool, instance))
         .await
         .expect("Server error");
+
+    // Keep the views counted since the last flush
+    if let Err(e) = views.flush(&flush_pool, flush_clock.now()).await {
+        tracing::error!("Failed to flush event views on shutdown: {}", e);
+    }
+}
+
+/// How often the retention policy is applied
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time allowed for a client to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A bound TCP, TLS or unix socket listener
+enum Listener {
+    Tcp(TcpListener),
+    Tls(TcpListener, TlsAcceptor),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    async fn bind(target: &BindTarget, tls: Option<&TlsConfig>) -> std::io::Result<Self> {
+        match (target, tls) {
+            (BindTarget::Tcp(addr), Some(tls)) => {
+                let server_config = tls::server_config(tls)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+                Ok(Self::Tls(TcpListener::bind(addr).await?, TlsAcceptor::from(server_config)))
+            }
+            (BindTarget::Unix(_), Some(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "TLS is not supported on unix sockets; terminate TLS in the proxy",
+            )),
+            (BindTarget::Tcp(addr), None) => Ok(Self::Tcp(TcpListener::bind(addr).await?)),
+            #[cfg(unix)]
+            (BindTarget::Unix(path), None) => {
+                // A socket left behind by a previous run would make bind fail
+                match std::fs::remove_file(path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+                Ok(Self::Unix(UnixListener::bind(path)?, path.clone()))
+            }
+            #[cfg(not(unix))]
+            (BindTarget::Unix(_), None) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "unix sockets are not supported on this platform",
+            )),
+        }
+    }
+
+    /// Serve `app` until `shutdown` resolves, then let open connections finish
+    async fn serve(self, app: Router, shutdown: impl Future<Output = ()> + Send + 'static) -> std::io::Result<()> {
+        match self {
+            // Peer addresses are needed by the geo gate when no proxy header is present
+            Self::Tcp(listener) => {
+                axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(shutdown)
+                    .await
+            }
+            Self::Tls(listener, acceptor) => {
+                serve_tls(listener, acceptor, app, shutdown).await;
+                Ok(())
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, path) => {
+                serve_unix(listener, app, shutdown).await;
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!("Failed to remove socket {}: {}", path.display(), e);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// axum 0.7 only serves plain TCP listeners, so TLS connections are driven
+/// by hyper directly. The protocol negotiated via ALPN picks HTTP/1.1 or
+/// HTTP/2; the peer address is attached as `ConnectInfo` like axum does.
+async fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, app: Router, shutdown: impl Future<Output = ()>) {
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::error!("Failed to accept connection: {}", e);
+                        continue;
+                    }
+                };
+                let (acceptor, watcher) = (acceptor.clone(), graceful.watcher());
+                let service = app.clone().map_request(move |mut request: Request<hyper::body::Incoming>| {
+                    request.extensions_mut().insert(ConnectInfo(peer));
+                    request
+                });
+
+                tokio::spawn(async move {
+                    let stream = match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(e)) => {
+                            tracing::debug!("TLS handshake with {} failed: {}", peer, e);
+                            return;
+                        }
+                        Err(_) => {
+                            tracing::debug!("TLS handshake with {} timed out", peer);
+                            return;
+                        }
+                    };
+
+                    let builder = auto::Builder::new(TokioExecutor::new());
+                    let connection = builder.serve_connection(TokioIo::new(stream), TowerToHyperService::new(service));
+                    if let Err(e) = watcher.watch(connection).await {
+                        tracing::debug!("Connection error from {}: {}", peer, e);
+                    }
+                });
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+
+    graceful.shutdown().await;
+}
+
+/// Unix connections are driven by hyper directly as well. Peers have no IP
+/// address; the geo gate relies on the proxy's X-Forwarded-For header instead.
+#[cfg(unix)]
+async fn serve_unix(listener: UnixListener, app: Router, shutdown: impl Future<Output = ()>) {
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::error!("Failed to accept unix connection: {}", e);
+                        continue;
+                    }
+                };
+                let (service, watcher) = (TowerToHyperService::new(app.clone()), graceful.watcher());
+                tokio::spawn(async move {
+                    let builder = auto::Builder::new(TokioExecutor::new());
+                    let connection = builder.serve_connection(TokioIo::new(stream), service);
+                    if let Err(e) = watcher.watch(connection).await {
+                        tracing::debug!("Unix connection error: {}", e);
+                    }
+                });
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+
+    graceful.shutdown().await;
+}
+
+/// Resolve on SIGTERM or Ctrl-C after marking the instance as draining, so
+/// in-flight requests finish while the new instance takes over
+async fn shutdown_signal(pool: db::DbPool, instance: Instance) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("Failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutting down instance {}", instance.id());
+    instance.mark_draining();
+    if let Err(e) = instance::deregister(&pool, &instance).await {
+        tracing::error!("Failed to deregister instance: {}", e);
+    }
 }
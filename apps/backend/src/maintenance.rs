@@ -0,0 +1,82 @@
+//! Operator-set maintenance notice ("registrations paused until 18:00").
+//!
+//! The notice is stored in the settings table and cached per instance.
+//! Changing it publishes a notification on the `system` channel, which
+//! reaches SSE clients and drops the cached copy on every instance. While
+//! set, every response carries it in `X-Maintenance-Message` and the
+//! health endpoint includes it.
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::settings;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Settings key of the maintenance notice
+pub const MAINTENANCE_KEY: &str = "maintenance";
+
+/// Response header carrying the notice
+pub const MAINTENANCE_HEADER: &str = "x-maintenance-message";
+
+/// Cache key of the single cached notice
+const CACHE_KEY: &str = "current";
+
+/// The maintenance notice; no message means normal operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct MaintenanceNotice {
+    #[validate(length(max = 500, code = "too_long"))]
+    pub message: Option<String>,
+}
+
+/// The current notice message, if any
+pub async fn current_message(state: &AppState) -> Option<String> {
+    if let Some(notice) = state.cache.maintenance.get(CACHE_KEY).await {
+        return notice.message;
+    }
+
+    match settings::get::<MaintenanceNotice>(&state.db_pool, MAINTENANCE_KEY).await {
+        Ok(notice) => {
+            state.cache.maintenance.insert(CACHE_KEY.to_string(), notice.clone()).await;
+            notice.message
+        }
+        Err(e) => {
+            tracing::error!("Failed to load maintenance notice: {}", e);
+            None
+        }
+    }
+}
+
+/// Header-safe form of a message: bytes outside printable ASCII (and `%`)
+/// are percent-encoded, so clients should percent-decode the value
+fn header_value(message: &str) -> HeaderValue {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    HeaderValue::from_str(&encoded).expect("percent-encoded value is valid ASCII")
+}
+
+/// Middleware attaching `X-Maintenance-Message` while a notice is set
+pub async fn maintenance_header(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let message = current_message(&state).await;
+    let mut response = next.run(request).await;
+
+    if let Some(message) = message.filter(|m| !m.is_empty()) {
+        response.headers_mut().insert(MAINTENANCE_HEADER, header_value(&message));
+    }
+
+    response
+}
@@ -0,0 +1,115 @@
+//! Hiding personal data from viewers not entitled to it.
+//!
+//! A `MaskPolicy` says which kinds of personal data a viewer may see.
+//! Typed views ask it field by field (`keep`); free-form JSON such as audit
+//! details is filtered by key through `apply`, or by wrapping the value in
+//! `Masked`, which does so while serializing. Hidden fields are left out
+//! rather than blanked, the way participant views have always omitted the
+//! email.
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::visibility::Viewer;
+
+/// A kind of personal data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pii {
+    Email,
+    Name,
+}
+
+impl Pii {
+    /// The kind of personal data stored under a JSON object key, if any
+    pub fn of_key(key: &str) -> Option<Self> {
+        match key {
+            "email" | "to_address" => Some(Self::Email),
+            "name" => Some(Self::Name),
+            _ if key.ends_with("_email") => Some(Self::Email),
+            _ if key.ends_with("_name") => Some(Self::Name),
+            _ => None,
+        }
+    }
+}
+
+/// Which personal data a viewer may see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskPolicy {
+    pub hide_emails: bool,
+    pub hide_names: bool,
+}
+
+impl MaskPolicy {
+    /// Everything as stored
+    pub const REVEAL: Self = Self { hide_emails: false, hide_names: false };
+    /// Names but no emails
+    pub const HIDE_EMAILS: Self = Self { hide_emails: true, hide_names: false };
+    /// Neither names nor emails
+    pub const HIDE_PII: Self = Self { hide_emails: true, hide_names: true };
+
+    /// Policy for participant lists: only organizers see emails
+    pub fn for_viewer(viewer: Viewer) -> Self {
+        match viewer {
+            Viewer::Organizer => Self::REVEAL,
+            Viewer::Participant | Viewer::Public => Self::HIDE_EMAILS,
+        }
+    }
+
+    /// Policy for audit data: only admins see the personal data in it;
+    /// organizers, including admins impersonating one, do not
+    pub fn for_audit(is_admin: bool) -> Self {
+        if is_admin {
+            Self::REVEAL
+        } else {
+            Self::HIDE_PII
+        }
+    }
+
+    pub fn hides(self, pii: Pii) -> bool {
+        match pii {
+            Pii::Email => self.hide_emails,
+            Pii::Name => self.hide_names,
+        }
+    }
+
+    /// `value` if this policy lets `pii` through
+    pub fn keep<T>(self, pii: Pii, value: T) -> Option<T> {
+        (!self.hides(pii)).then_some(value)
+    }
+
+    /// Remove hidden personal data from every object in `value`
+    pub fn apply(self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                object.retain(|key, _| Pii::of_key(key).is_none_or(|pii| !self.hides(pii)));
+                object.values_mut().for_each(|v| self.apply(v));
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.apply(v)),
+            _ => {}
+        }
+    }
+}
+
+/// A value serialized with its personal data masked by a policy
+#[derive(Debug, Clone)]
+pub struct Masked<T> {
+    value: T,
+    policy: MaskPolicy,
+}
+
+impl<T> Masked<T> {
+    pub fn new(value: T, policy: MaskPolicy) -> Self {
+        Self { value, policy }
+    }
+}
+
+impl<T: Serialize> Serialize for Masked<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.policy == MaskPolicy::REVEAL {
+            return self.value.serialize(serializer);
+        }
+        let mut value = serde_json::to_value(&self.value).map_err(serde::ser::Error::custom)?;
+        self.policy.apply(&mut value);
+        value.serialize(serializer)
+    }
+}
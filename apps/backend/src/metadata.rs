@@ -0,0 +1,80 @@
+//! Free-form participant details.
+//!
+//! Deployments collect details such as dietary requirements, company or
+//! t-shirt size that have no column of their own. They go into a
+//! participant's `metadata`: a flat JSON object of strings, numbers and
+//! booleans, given on registration and changed with
+//! `PATCH /api/participants/:id/metadata`. A patch is a JSON merge patch
+//! (RFC 7396): listed keys are set, keys set to null are removed, others
+//! stay. It is applied by SQLite's `json_patch` in the update itself, so
+//! concurrent patches of different keys never overwrite each other.
+//!
+//! Metadata may hold personal data, so participant lists show it to
+//! organizers only.
+
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use validator::ValidationError;
+
+/// A participant's details, keyed by name
+pub type Metadata = Map<String, Value>;
+
+/// Most keys one participant's metadata may have
+pub const MAX_KEYS: usize = 32;
+/// Longest key
+pub const MAX_KEY_LEN: usize = 64;
+/// Longest string value
+pub const MAX_VALUE_LEN: usize = 500;
+/// Largest metadata as stored JSON; the column's CHECK enforces it too
+pub const MAX_BYTES: usize = 8192;
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+fn check_entries(value: &Metadata, allow_null: bool) -> Result<(), ValidationError> {
+    if value.len() > MAX_KEYS {
+        return Err(error("too_many_keys", format!("must have at most {} keys", MAX_KEYS)));
+    }
+
+    for (key, entry) in value {
+        if key.is_empty() || key.chars().count() > MAX_KEY_LEN {
+            return Err(error("invalid_key", format!("keys must be 1 to {} characters", MAX_KEY_LEN)));
+        }
+        if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(error("invalid_key", "keys may only contain letters, digits, '_' and '-'"));
+        }
+        match entry {
+            Value::String(text) if text.chars().count() > MAX_VALUE_LEN => {
+                return Err(error(
+                    "too_long",
+                    format!("'{}' must be at most {} characters", key, MAX_VALUE_LEN),
+                ));
+            }
+            Value::String(_) | Value::Number(_) | Value::Bool(_) => {}
+            Value::Null if allow_null => {}
+            _ => {
+                return Err(error(
+                    "invalid_type",
+                    format!("'{}' must be a string, number or boolean", key),
+                ));
+            }
+        }
+    }
+
+    if Value::Object(value.clone()).to_string().len() > MAX_BYTES {
+        return Err(error("too_large", format!("must be at most {} bytes as JSON", MAX_BYTES)));
+    }
+
+    Ok(())
+}
+
+/// Metadata given on registration
+pub fn entries(value: &Metadata) -> Result<(), ValidationError> {
+    check_entries(value, false)
+}
+
+/// A merge patch; null removes a key
+pub fn patch(value: &Metadata) -> Result<(), ValidationError> {
+    check_entries(value, true)
+}
@@ -0,0 +1,199 @@
+//! Prometheus text exposition of in-process gauges and counters.
+//!
+//! Values are read from the components that own them at scrape time, so
+//! there is no separate registry to keep in sync.
+//!
+//! `/metrics/autoscale` serves only the three scaling signals, in the
+//! OpenMetrics format, for autoscalers that poll every second.
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::fmt::Write;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Accumulates metric families in the Prometheus text format
+#[derive(Default)]
+pub struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: impl std::fmt::Display) {
+        self.header(name, "gauge", help);
+        let _ = writeln!(self.out, "{} {}", name, value);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: impl std::fmt::Display) {
+        self.header(name, "counter", help);
+        let _ = writeln!(self.out, "{} {}", name, value);
+    }
+
+    /// A counter family with one sample per label value
+    pub fn labeled_counter(&mut self, name: &str, help: &str, label: &str, samples: &[(&str, u64)]) {
+        self.header(name, "counter", help);
+        for (value, count) in samples {
+            let _ = writeln!(self.out, "{}{{{}=\"{}\"}} {}", name, label, value, count);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    /// The text with the `# EOF` terminator OpenMetrics requires
+    pub fn finish_openmetrics(mut self) -> String {
+        self.out.push_str("# EOF\n");
+        self.out
+    }
+}
+
+/// Render every metric of this instance
+pub fn render(state: &AppState) -> String {
+    let mut metrics = MetricsWriter::default();
+    let limits = &state.concurrency;
+
+    metrics.gauge(
+        "http_requests_in_flight",
+        "Requests currently being handled, including queued ones",
+        state.load_shedder.in_flight(),
+    );
+    metrics.gauge(
+        "http_requests_active",
+        "Requests holding a concurrency slot",
+        limits.active(),
+    );
+    metrics.gauge(
+        "http_requests_queued",
+        "Requests waiting for a concurrency slot",
+        limits.queue_depth(),
+    );
+    metrics.gauge(
+        "http_requests_concurrency_limit",
+        "Configured number of concurrency slots",
+        limits.config().max_concurrent,
+    );
+    metrics.labeled_counter(
+        "http_requests_shed_total",
+        "Requests rejected with 503 by the concurrency limits",
+        "reason",
+        &[
+            ("queue_full", limits.shed_queue_full()),
+            ("export_limit", limits.shed_exports()),
+        ],
+    );
+    metrics.gauge(
+        "ndjson_exports_active",
+        "NDJSON exports currently streaming",
+        limits.active_exports(),
+    );
+    metrics.gauge(
+        "db_pool_connections",
+        "Open SQLite connections",
+        state.db_pool.size(),
+    );
+    metrics.gauge(
+        "db_pool_idle_connections",
+        "Idle SQLite connections",
+        state.db_pool.num_idle(),
+    );
+    metrics.counter(
+        "notification_poller_restarts_total",
+        "Times the notification poller was restarted",
+        state.poller_health.restarts(),
+    );
+    let poller = &state.poller_metrics;
+    metrics.counter(
+        "notification_poller_iterations_total",
+        "Notification poll iterations completed",
+        poller.iterations(),
+    );
+    metrics.counter(
+        "notification_poller_overruns_total",
+        "Notification poll iterations that took longer than the poll interval",
+        poller.overruns(),
+    );
+    metrics.counter(
+        "notification_poller_rows_fetched_total",
+        "Notifications fetched by the poller",
+        poller.rows_fetched(),
+    );
+    metrics.counter(
+        "notification_poller_poll_seconds_total",
+        "Time spent querying for notifications",
+        poller.poll_time().as_secs_f64(),
+    );
+    metrics.gauge(
+        "notification_poller_last_poll_seconds",
+        "Query time of the latest poll iteration",
+        poller.last_poll_time().as_secs_f64(),
+    );
+    metrics.counter(
+        "notification_poller_fanout_seconds_total",
+        "Time spent invalidating caches and broadcasting fetched notifications",
+        poller.fanout_time().as_secs_f64(),
+    );
+    metrics.gauge(
+        "notification_poller_last_fanout_seconds",
+        "Broadcast fan-out time of the latest poll iteration",
+        poller.last_fanout_time().as_secs_f64(),
+    );
+    metrics.counter(
+        "notification_cleanup_deleted_total",
+        "Notifications deleted by the retention job on this instance",
+        poller.cleanup_deleted(),
+    );
+    metrics.counter(
+        "cache_reconcile_forced_invalidations_total",
+        "Cache invalidations for changes no notification announced",
+        state.cache.forced_invalidations(),
+    );
+
+    metrics.finish()
+}
+
+/// Prometheus scrape endpoint
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        render(&state),
+    )
+}
+
+/// Render the autoscaling signals: in-flight requests, p95 latency and
+/// queue depth. Reads two atomics and sorts at most one window of samples.
+pub fn render_autoscale(state: &AppState) -> String {
+    let mut metrics = MetricsWriter::default();
+    let limits = &state.concurrency;
+
+    metrics.gauge(
+        "http_requests_in_flight",
+        "Requests currently being handled, including queued ones",
+        state.load_shedder.in_flight(),
+    );
+    metrics.gauge(
+        "http_request_duration_p95_seconds",
+        "95th percentile latency of requests served in the last minute",
+        limits.latency().quantile(0.95).map_or(0.0, |p95| p95.as_secs_f64()),
+    );
+    metrics.gauge(
+        "http_requests_queued",
+        "Requests waiting for a concurrency slot",
+        limits.queue_depth(),
+    );
+
+    metrics.finish_openmetrics()
+}
+
+/// Compact OpenMetrics endpoint for external autoscalers
+pub async fn autoscale_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/openmetrics-text; version=1.0.0; charset=utf-8")],
+        render_autoscale(&state),
+    )
+}
@@ -0,0 +1,923 @@
+//! Versioned schema migrations applied on top of the base tables.
+//!
+//! `db::initialize_tables` creates the original schema with
+//! `CREATE TABLE IF NOT EXISTS`; every later schema change (new tables,
+//! columns, triggers) goes here so it runs exactly once per database.
+//! Applied versions are recorded in `schema_migrations`.
+//!
+//! Migrations run on startup unless AUTO_MIGRATE=false. `plan` shows what
+//! the next run would do, so operators can start a new build with
+//! migrations held back, review `POST /api/admin/migrations/plan`, and
+//! restart it with AUTO_MIGRATE=true once approved.
+
+use serde::Serialize;
+use sqlx::Row;
+use tracing::info;
+
+use crate::db::DbPool;
+
+/// A single schema change
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+/// All migrations in application order. Never edit or reorder an entry
+/// once released; add a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    // SQLite cannot add CHECK constraints to existing tables, so the
+    // length caps (FieldLimits::HARD_CAPS) are enforced with triggers
+    Migration {
+        version: 1,
+        name: "field_length_checks",
+        statements: &[
+            "CREATE TRIGGER IF NOT EXISTS events_length_check_insert
+             BEFORE INSERT ON events
+             WHEN length(NEW.title) > 1000
+               OR length(NEW.description) > 20000
+               OR length(NEW.location) > 1000
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: events field length');
+             END",
+            "CREATE TRIGGER IF NOT EXISTS events_length_check_update
+             BEFORE UPDATE ON events
+             WHEN length(NEW.title) > 1000
+               OR length(NEW.description) > 20000
+               OR length(NEW.location) > 1000
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: events field length');
+             END",
+            "CREATE TRIGGER IF NOT EXISTS participants_length_check_insert
+             BEFORE INSERT ON participants
+             WHEN length(NEW.name) > 1000
+               OR length(NEW.email) > 320
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+            "CREATE TRIGGER IF NOT EXISTS participants_length_check_update
+             BEFORE UPDATE ON participants
+             WHEN length(NEW.name) > 1000
+               OR length(NEW.email) > 320
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "audit_log",
+        statements: &[
+            "CREATE TABLE audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action TEXT NOT NULL,
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                details TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            "CREATE INDEX idx_audit_log_entity ON audit_log(entity_type, entity_id)",
+            "CREATE INDEX idx_audit_log_created_at ON audit_log(created_at)",
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "confirmation_tokens",
+        statements: &[
+            "CREATE TABLE confirmation_tokens (
+                token TEXT PRIMARY KEY NOT NULL,
+                method TEXT NOT NULL,
+                target TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "event_freeze",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN frozen INTEGER NOT NULL DEFAULT 0",
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "settings",
+        statements: &[
+            "CREATE TABLE settings (
+                key TEXT PRIMARY KEY NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "participant_status_history",
+        statements: &[
+            "CREATE TABLE participant_status_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                participant_id TEXT NOT NULL,
+                event_id TEXT NOT NULL,
+                from_status TEXT NOT NULL,
+                to_status TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )",
+            "CREATE INDEX idx_status_history_event ON participant_status_history(event_id, changed_at)",
+            "CREATE TRIGGER participants_status_history
+             AFTER UPDATE OF status ON participants
+             WHEN OLD.status IS NOT NEW.status
+             BEGIN
+                 INSERT INTO participant_status_history
+                     (participant_id, event_id, from_status, to_status, changed_at)
+                 VALUES (NEW.id, NEW.event_id, OLD.status, NEW.status, NEW.updated_at);
+             END",
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "instances",
+        statements: &[
+            "CREATE TABLE instances (
+                id TEXT PRIMARY KEY NOT NULL,
+                started_at TEXT NOT NULL,
+                last_heartbeat TEXT NOT NULL,
+                phase TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "participant_consents",
+        statements: &[
+            "CREATE TABLE participant_consents (
+                participant_id TEXT PRIMARY KEY NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                privacy_policy_version TEXT NOT NULL,
+                privacy_accepted_at TEXT NOT NULL,
+                marketing_opt_in INTEGER NOT NULL,
+                marketing_updated_at TEXT NOT NULL,
+                withdrawal_token TEXT NOT NULL UNIQUE
+            )",
+        ],
+    },
+    // UNIQUE (event_id, email) is part of the original participants table,
+    // so the table is rebuilt without it. The replacement is a partial index
+    // on dedupe_key, which registration derives from the event's
+    // email_policy (NULL when duplicates are unlimited). Dropping the table
+    // drops its triggers and may cascade into participant_consents, so both
+    // are restored afterwards.
+    Migration {
+        version: 9,
+        name: "email_policy",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN email_policy TEXT NOT NULL DEFAULT 'strict'
+                CHECK (email_policy IN ('strict', 'allow_different_name', 'unlimited'))",
+            "CREATE TEMP TABLE participant_consents_backup AS SELECT * FROM participant_consents",
+            "CREATE TABLE participants_rebuild (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'registered',
+                registered_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                dedupe_key TEXT
+            )",
+            "INSERT INTO participants_rebuild (id, event_id, name, email, status, registered_at, updated_at, dedupe_key)
+             SELECT id, event_id, name, email, status, registered_at, updated_at, email FROM participants",
+            "DROP TABLE participants",
+            "ALTER TABLE participants_rebuild RENAME TO participants",
+            "DELETE FROM participant_consents",
+            "INSERT INTO participant_consents SELECT * FROM participant_consents_backup",
+            "DROP TABLE participant_consents_backup",
+            "CREATE INDEX idx_participants_event_id ON participants(event_id)",
+            "CREATE INDEX idx_participants_email ON participants(email)",
+            "CREATE UNIQUE INDEX idx_participants_dedupe ON participants(event_id, dedupe_key)
+             WHERE dedupe_key IS NOT NULL",
+            "CREATE TRIGGER participants_length_check_insert
+             BEFORE INSERT ON participants
+             WHEN length(NEW.name) > 1000
+               OR length(NEW.email) > 320
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+            "CREATE TRIGGER participants_length_check_update
+             BEFORE UPDATE ON participants
+             WHEN length(NEW.name) > 1000
+               OR length(NEW.email) > 320
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+            "CREATE TRIGGER participants_status_history
+             AFTER UPDATE OF status ON participants
+             WHEN OLD.status IS NOT NEW.status
+             BEGIN
+                 INSERT INTO participant_status_history
+                     (participant_id, event_id, from_status, to_status, changed_at)
+                 VALUES (NEW.id, NEW.event_id, OLD.status, NEW.status, NEW.updated_at);
+             END",
+        ],
+    },    // The bundled SQLite has no math functions, so the sines and cosines
+    // used by the near-me search are stored next to the coordinates
+    Migration {
+        version: 10,
+        name: "event_coordinates",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN latitude REAL CHECK (latitude BETWEEN -90 AND 90)",
+            "ALTER TABLE events ADD COLUMN longitude REAL CHECK (longitude BETWEEN -180 AND 180)",
+            "ALTER TABLE events ADD COLUMN geo_sin_lat REAL",
+            "ALTER TABLE events ADD COLUMN geo_cos_lat REAL",
+            "ALTER TABLE events ADD COLUMN geo_sin_lng REAL",
+            "ALTER TABLE events ADD COLUMN geo_cos_lng REAL",
+            "CREATE INDEX idx_events_coordinates ON events(latitude, longitude) WHERE latitude IS NOT NULL",
+        ],
+    },
+    Migration {
+        version: 11,
+        name: "certificates",
+        statements: &[
+            "CREATE TABLE certificates (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                participant_id TEXT NOT NULL UNIQUE REFERENCES participants(id) ON DELETE CASCADE,
+                issued_at TEXT NOT NULL,
+                pdf BLOB NOT NULL
+            )",
+            "CREATE INDEX idx_certificates_event ON certificates(event_id)",
+        ],
+    },
+    Migration {
+        version: 12,
+        name: "admin_sessions",
+        statements: &[
+            "CREATE TABLE admin_sessions (
+                id TEXT PRIMARY KEY NOT NULL,
+                csrf_token TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            )",
+        ],
+    },
+    // Registrants receive an access token proving they take part in an
+    // event, for lists visible only to participants. Older registrations
+    // have none.
+    Migration {
+        version: 13,
+        name: "participant_visibility",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN participant_visibility TEXT NOT NULL DEFAULT 'public'
+                CHECK (participant_visibility IN ('public', 'participants', 'organizers'))",
+            "ALTER TABLE participants ADD COLUMN access_token TEXT",
+            "CREATE UNIQUE INDEX idx_participants_access_token ON participants(access_token)
+             WHERE access_token IS NOT NULL",
+        ],
+    },
+    // Mails that could not be sent, kept whole so they can be retried
+    Migration {
+        version: 14,
+        name: "mail_failures",
+        statements: &[
+            "CREATE TABLE mail_failures (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                to_address TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                mail TEXT NOT NULL,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                first_failed_at TEXT NOT NULL,
+                last_failed_at TEXT NOT NULL
+            )",
+        ],
+    },
+    // Where a registration came from, as reported by the client; NULL for
+    // older registrations and clients not reporting it
+    Migration {
+        version: 15,
+        name: "participant_client_context",
+        statements: &[
+            "ALTER TABLE participants ADD COLUMN source TEXT CHECK (source IN ('web', 'mobile', 'kiosk'))",
+            "ALTER TABLE participants ADD COLUMN referrer_code TEXT",
+            "ALTER TABLE participants ADD COLUMN utm_campaign TEXT",
+            "CREATE INDEX idx_participants_source ON participants(source)",
+        ],
+    },
+    // Participant exports written in the background for signed download
+    Migration {
+        version: 16,
+        name: "export_jobs",
+        statements: &[
+            "CREATE TABLE export_jobs (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'ready', 'failed')),
+                error TEXT,
+                size_bytes INTEGER,
+                created_at TEXT NOT NULL,
+                completed_at TEXT,
+                expires_at TEXT
+            )",
+        ],
+    },
+    // Age-restricted events; the date of birth is kept encrypted and only
+    // when an encryption key is configured
+    Migration {
+        version: 17,
+        name: "age_restriction",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN min_age INTEGER CHECK (min_age BETWEEN 1 AND 120)",
+            "ALTER TABLE participants ADD COLUMN date_of_birth BLOB",
+        ],
+    },
+    // Organizer-defined mail wording; without an event the template applies
+    // to all events. One registration template of each kind per scope.
+    Migration {
+        version: 18,
+        name: "email_templates",
+        statements: &[
+            "CREATE TABLE email_templates (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT REFERENCES events(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL CHECK (kind IN ('confirmation', 'waitlisted', 'announcement')),
+                name TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "CREATE UNIQUE INDEX idx_email_templates_scope ON email_templates (kind, COALESCE(event_id, ''))
+             WHERE kind != 'announcement'",
+            "CREATE INDEX idx_email_templates_event ON email_templates (event_id)",
+        ],
+    },
+    // Recurring events: a series expands into occurrences on read; an
+    // occurrence becomes an event row once edited or opened for registration
+    Migration {
+        version: 19,
+        name: "event_series",
+        statements: &[
+            "CREATE TABLE event_series (
+                id TEXT PRIMARY KEY NOT NULL,
+                title TEXT NOT NULL,
+                description TEXT,
+                location TEXT,
+                max_participants INTEGER,
+                email_policy TEXT NOT NULL DEFAULT 'strict'
+                    CHECK (email_policy IN ('strict', 'allow_different_name', 'unlimited')),
+                participant_visibility TEXT NOT NULL DEFAULT 'public'
+                    CHECK (participant_visibility IN ('public', 'participants', 'organizers')),
+                latitude REAL,
+                longitude REAL,
+                min_age INTEGER CHECK (min_age BETWEEN 1 AND 120),
+                start_time TEXT NOT NULL,
+                end_time TEXT NOT NULL,
+                freq TEXT NOT NULL CHECK (freq IN ('daily', 'weekly', 'monthly')),
+                interval INTEGER NOT NULL DEFAULT 1 CHECK (interval >= 1),
+                until TEXT,
+                count INTEGER CHECK (count >= 1),
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "CREATE TABLE event_series_exclusions (
+                series_id TEXT NOT NULL REFERENCES event_series(id) ON DELETE CASCADE,
+                occurrence_start TEXT NOT NULL,
+                PRIMARY KEY (series_id, occurrence_start)
+            )",
+            "ALTER TABLE events ADD COLUMN series_id TEXT REFERENCES event_series(id) ON DELETE SET NULL",
+            "ALTER TABLE events ADD COLUMN occurrence_start TEXT",
+            "CREATE UNIQUE INDEX idx_events_occurrence ON events (series_id, occurrence_start) WHERE series_id IS NOT NULL",
+        ],
+    },
+    Migration {
+        version: 20,
+        name: "event_status",
+        statements: &[
+            // Existing events were public, so they start out published
+            "ALTER TABLE events ADD COLUMN status TEXT NOT NULL DEFAULT 'published'
+                 CHECK (status IN ('draft', 'published', 'cancelled'))",
+        ],
+    },
+    Migration {
+        version: 21,
+        name: "reminder_preferences",
+        statements: &[
+            "CREATE TABLE reminder_preferences (
+                participant_id TEXT PRIMARY KEY NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                remind_24h INTEGER NOT NULL,
+                remind_1h INTEGER NOT NULL,
+                token TEXT NOT NULL UNIQUE,
+                updated_at TEXT NOT NULL
+            )",
+            // Existing registrations get the default of a reminder a day ahead
+            "INSERT INTO reminder_preferences (participant_id, remind_24h, remind_1h, token, updated_at)
+             SELECT id, 1, 0, lower(hex(randomblob(16))), registered_at FROM participants",
+            // Keyed by the start time announced, so a moved event is reminded again
+            "CREATE TABLE reminders_sent (
+                participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                lead_time TEXT NOT NULL,
+                event_start TEXT NOT NULL,
+                sent_at TEXT NOT NULL,
+                PRIMARY KEY (participant_id, lead_time, event_start)
+            )",
+        ],
+    },
+    Migration {
+        version: 22,
+        name: "event_tags",
+        statements: &[
+            "CREATE TABLE tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL
+            )",
+            "CREATE TABLE event_tags (
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (event_id, tag_id)
+            )",
+            "CREATE INDEX idx_event_tags_tag ON event_tags (tag_id)",
+        ],
+    },
+    Migration {
+        version: 23,
+        name: "event_slugs",
+        statements: &[
+            // Filled in for existing events by `slug::backfill`
+            "ALTER TABLE events ADD COLUMN slug TEXT",
+            "CREATE UNIQUE INDEX idx_events_slug ON events (slug) WHERE slug IS NOT NULL",
+        ],
+    },
+    Migration {
+        version: 24,
+        name: "bulk_mails",
+        statements: &[
+            "CREATE TABLE bulk_mails (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                subject TEXT NOT NULL,
+                body TEXT NOT NULL,
+                statuses TEXT NOT NULL,
+                requested_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                completed_at TEXT
+            )",
+            // One rendered mail per recipient, sent in rowid order
+            "CREATE TABLE bulk_mail_recipients (
+                bulk_mail_id TEXT NOT NULL REFERENCES bulk_mails(id) ON DELETE CASCADE,
+                participant_id TEXT NOT NULL,
+                to_name TEXT NOT NULL,
+                to_address TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                text TEXT NOT NULL,
+                state TEXT NOT NULL DEFAULT 'queued',
+                error TEXT,
+                attempted_at TEXT,
+                PRIMARY KEY (bulk_mail_id, participant_id)
+            )",
+            "CREATE INDEX idx_bulk_mail_recipients_state ON bulk_mail_recipients (state)",
+        ],
+    },
+    Migration {
+        version: 25,
+        name: "participant_checkins",
+        statements: &[
+            "ALTER TABLE participants ADD COLUMN checked_in_at TEXT",
+        ],
+    },
+    // Existing participants get a nonce too, so their links work once mailed
+    Migration {
+        version: 26,
+        name: "cancellation_tokens",
+        statements: &[
+            "CREATE TABLE cancellation_tokens (
+                nonce TEXT PRIMARY KEY NOT NULL,
+                participant_id TEXT NOT NULL UNIQUE REFERENCES participants(id) ON DELETE CASCADE,
+                created_at TEXT NOT NULL
+            )",
+            "INSERT INTO cancellation_tokens (nonce, participant_id, created_at)
+             SELECT lower(hex(randomblob(16))), id, registered_at FROM participants",
+        ],
+    },
+    // Answers are stored as JSON so each field kind keeps its own type
+    Migration {
+        version: 27,
+        name: "registration_forms",
+        statements: &[
+            "CREATE TABLE event_form_fields (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                key TEXT NOT NULL,
+                label TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('text', 'select', 'checkbox')),
+                required INTEGER NOT NULL DEFAULT 0,
+                options TEXT NOT NULL DEFAULT '[]',
+                position INTEGER NOT NULL,
+                UNIQUE (event_id, key)
+            )",
+            "CREATE TABLE participant_answers (
+                participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                field_id TEXT NOT NULL REFERENCES event_form_fields(id) ON DELETE CASCADE,
+                value TEXT NOT NULL,
+                PRIMARY KEY (participant_id, field_id)
+            )",
+        ],
+    },
+    // Views are written behind in hourly buckets; see `popularity`
+    Migration {
+        version: 28,
+        name: "event_view_counts",
+        statements: &[
+            "CREATE TABLE event_view_counts (
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                hour TEXT NOT NULL,
+                views INTEGER NOT NULL,
+                PRIMARY KEY (event_id, hour)
+            )",
+            "CREATE INDEX idx_event_view_counts_hour ON event_view_counts (hour)",
+        ],
+    },
+    // SQLite cannot change a CHECK constraint in place, so the status
+    // column is copied into one that also allows 'completed'. A column's
+    // own CHECK does not keep it from being dropped.
+    Migration {
+        version: 29,
+        name: "event_completed_status",
+        statements: &[
+            "ALTER TABLE events ADD COLUMN status_next TEXT NOT NULL DEFAULT 'published'
+                 CHECK (status_next IN ('draft', 'published', 'cancelled', 'completed'))",
+            "UPDATE events SET status_next = status",
+            "ALTER TABLE events DROP COLUMN status",
+            "ALTER TABLE events RENAME COLUMN status_next TO status",
+        ],
+    },
+    // Registrations now store addresses trimmed and lowercased (see
+    // `email::normalize`). Every dedupe key starts with the email, so its
+    // prefix is swapped for the normalized one. Addresses that would then
+    // collide with another registration of the same event are left as they
+    // are for an organizer to resolve. SQLite's lower() folds ASCII only.
+    Migration {
+        version: 30,
+        name: "normalize_participant_emails",
+        statements: &[
+            "UPDATE participants
+             SET email = lower(trim(email)),
+                 dedupe_key = lower(trim(email)) || substr(dedupe_key, length(email) + 1)
+             WHERE email != lower(trim(email))
+               AND NOT EXISTS (
+                   SELECT 1 FROM participants other
+                   WHERE other.event_id = participants.event_id
+                     AND other.id != participants.id
+                     AND lower(trim(other.email)) || substr(other.dedupe_key, length(other.email) + 1)
+                       = lower(trim(participants.email)) || substr(participants.dedupe_key, length(participants.email) + 1)
+               )",
+        ],
+    },
+    // Existing registrations get an invite code too; see `referrals`
+    Migration {
+        version: 31,
+        name: "referrals",
+        statements: &[
+            "ALTER TABLE participants ADD COLUMN invite_code TEXT",
+            "UPDATE participants SET invite_code = lower(hex(randomblob(6)))",
+            "CREATE UNIQUE INDEX idx_participants_invite_code ON participants(invite_code)
+             WHERE invite_code IS NOT NULL",
+            "CREATE TABLE referrals (
+                participant_id TEXT PRIMARY KEY NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                referrer_id TEXT REFERENCES participants(id) ON DELETE SET NULL,
+                created_at TEXT NOT NULL
+            )",
+            "CREATE INDEX idx_referrals_referrer ON referrals(referrer_id)",
+            "CREATE INDEX idx_referrals_event ON referrals(event_id)",
+        ],
+    },
+    // The size cap matches metadata::MAX_BYTES
+    Migration {
+        version: 32,
+        name: "participant_metadata",
+        statements: &[
+            "ALTER TABLE participants ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}'
+                 CHECK (json_valid(metadata) AND json_type(metadata) = 'object' AND length(metadata) <= 8192)",
+        ],
+    },
+    Migration {
+        version: 33,
+        name: "organizers",
+        statements: &[
+            "CREATE TABLE organizers (
+                id TEXT PRIMARY KEY NOT NULL,
+                email TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                password_hash TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "ALTER TABLE events ADD COLUMN organizer_id TEXT REFERENCES organizers(id) ON DELETE SET NULL",
+            "CREATE INDEX idx_events_organizer ON events(organizer_id)",
+        ],
+    },
+    // Sections are stored as JSON; see `seating`
+    Migration {
+        version: 34,
+        name: "seating",
+        statements: &[
+            "CREATE TABLE event_seat_maps (
+                event_id TEXT PRIMARY KEY NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                sections TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "CREATE TABLE seat_assignments (
+                participant_id TEXT PRIMARY KEY NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                section TEXT NOT NULL,
+                seat INTEGER NOT NULL CHECK (seat > 0),
+                assigned_at TEXT NOT NULL,
+                UNIQUE (event_id, section, seat)
+            )",
+        ],
+    },
+    Migration {
+        version: 35,
+        name: "capacity_holds",
+        statements: &[
+            "CREATE TABLE capacity_holds (
+                id TEXT PRIMARY KEY NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                label TEXT NOT NULL,
+                seats INTEGER NOT NULL CHECK (seats > 0),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            "CREATE INDEX idx_capacity_holds_event ON capacity_holds(event_id)",
+        ],
+    },
+    Migration {
+        version: 36,
+        name: "devices",
+        statements: &[
+            "CREATE TABLE devices (
+                id TEXT PRIMARY KEY NOT NULL,
+                name TEXT NOT NULL,
+                event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+                token_hash TEXT NOT NULL UNIQUE,
+                pending_sync INTEGER NOT NULL DEFAULT 0,
+                app_version TEXT,
+                last_seen_at TEXT,
+                created_at TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 37,
+        name: "webhooks",
+        statements: &[
+            "CREATE TABLE webhooks (
+                id TEXT PRIMARY KEY NOT NULL,
+                organizer_id TEXT NOT NULL REFERENCES organizers(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                url TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                transitions TEXT NOT NULL,
+                fields TEXT NOT NULL,
+                active INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            "CREATE INDEX idx_webhooks_organizer ON webhooks(organizer_id)",
+            "CREATE TABLE webhook_deliveries (
+                id TEXT PRIMARY KEY NOT NULL,
+                webhook_id TEXT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+                participant_id TEXT NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+                event_id TEXT NOT NULL,
+                from_status TEXT,
+                to_status TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                state TEXT NOT NULL DEFAULT 'queued',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                response_status INTEGER,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                delivered_at TEXT
+            )",
+            "CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(state, next_attempt_at)",
+            "CREATE INDEX idx_webhook_deliveries_participant ON webhook_deliveries(participant_id, created_at)",
+        ],
+    },
+    // Names and emails may be sealed (see `encryption`): lookups by email
+    // go through its blind index, and the length caps allow for sealed
+    // values, whose length() is in bytes: at most 4 per character plus 28
+    // for nonce and tag
+    Migration {
+        version: 38,
+        name: "participant_pii_encryption",
+        statements: &[
+            "ALTER TABLE participants ADD COLUMN email_index TEXT",
+            "UPDATE participants SET email_index = email",
+            "CREATE INDEX idx_participants_email_index ON participants(email_index)",
+            "DROP TRIGGER participants_length_check_insert",
+            "DROP TRIGGER participants_length_check_update",
+            "CREATE TRIGGER participants_length_check_insert
+             BEFORE INSERT ON participants
+             WHEN (typeof(NEW.name) = 'text' AND length(NEW.name) > 1000)
+               OR (typeof(NEW.email) = 'text' AND length(NEW.email) > 320)
+               OR length(NEW.name) > 4028
+               OR length(NEW.email) > 1308
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+            "CREATE TRIGGER participants_length_check_update
+             BEFORE UPDATE ON participants
+             WHEN (typeof(NEW.name) = 'text' AND length(NEW.name) > 1000)
+               OR (typeof(NEW.email) = 'text' AND length(NEW.email) > 320)
+               OR length(NEW.name) > 4028
+               OR length(NEW.email) > 1308
+             BEGIN
+                 SELECT RAISE(ABORT, 'CHECK constraint failed: participants field length');
+             END",
+        ],
+    },
+    // Occurrence events take the owner of their series
+    Migration {
+        version: 39,
+        name: "series_owners",
+        statements: &[
+            "ALTER TABLE event_series ADD COLUMN organizer_id TEXT REFERENCES organizers(id) ON DELETE SET NULL",
+        ],
+    },
+    // Addresses of queued and failed mails may be sealed like participant
+    // emails (see `encryption`), so they are looked up by blind index too
+    Migration {
+        version: 40,
+        name: "mail_address_indexes",
+        statements: &[
+            "ALTER TABLE mail_failures ADD COLUMN to_address_index TEXT",
+            "UPDATE mail_failures SET to_address_index = to_address",
+            "CREATE INDEX idx_mail_failures_to_address_index ON mail_failures(to_address_index)",
+            "ALTER TABLE bulk_mail_recipients ADD COLUMN to_address_index TEXT",
+            "UPDATE bulk_mail_recipients SET to_address_index = to_address",
+            "CREATE INDEX idx_bulk_mail_recipients_to_address_index ON bulk_mail_recipients(to_address_index)",
+        ],
+    },
+];
+
+async fn ensure_migrations_table(pool: &DbPool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Highest applied migration version (0 if none)
+pub async fn current_version(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    ensure_migrations_table(pool).await?;
+
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(row.get("version"))
+}
+
+/// Migrations that have not been applied yet
+pub async fn pending(pool: &DbPool) -> Result<Vec<&'static Migration>, sqlx::Error> {
+    let current = current_version(pool).await?;
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+/// Database the statements of a plan are written for
+pub const DIALECT: &str = "sqlite";
+
+/// A statement a pending migration would run
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedStatement {
+    pub sql: String,
+    /// Rows the statement changed in a rehearsal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows_affected: Option<u64>,
+    /// Why the statement failed in a rehearsal; later statements are not
+    /// rehearsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A migration that has not been applied yet
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMigration {
+    pub version: i64,
+    pub name: &'static str,
+    pub statements: Vec<PlannedStatement>,
+}
+
+/// What the next migration run would do
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationPlan {
+    pub dialect: &'static str,
+    pub current_version: i64,
+    /// Version after the pending migrations; the current one when none are
+    pub target_version: i64,
+    /// Whether the statements were run and rolled back
+    pub rehearsed: bool,
+    /// Whether every rehearsed statement succeeded; None when not rehearsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rehearsal_succeeded: Option<bool>,
+    pub pending: Vec<PlannedMigration>,
+}
+
+/// Strip the indentation statements carry from their place in `MIGRATIONS`
+fn dedent(sql: &str) -> String {
+    let mut lines = sql.trim().lines();
+    let first = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut dedented = first.to_string();
+    for line in rest {
+        dedented.push('\n');
+        dedented.push_str(line.get(indent..).unwrap_or(line.trim_start()).trim_end());
+    }
+    dedented
+}
+
+/// The pending migrations and their statements, without applying them.
+/// With `rehearse`, all of them run in one transaction that is rolled back,
+/// reporting the rows each statement changed or the first failure; this
+/// holds the database's write lock for as long as the statements take.
+pub async fn plan(pool: &DbPool, rehearse: bool) -> Result<MigrationPlan, sqlx::Error> {
+    let current_version = current_version(pool).await?;
+    let migrations = pending(pool).await?;
+
+    let mut plan = MigrationPlan {
+        dialect: DIALECT,
+        current_version,
+        target_version: migrations.last().map_or(current_version, |m| m.version),
+        rehearsed: rehearse,
+        rehearsal_succeeded: rehearse.then_some(true),
+        pending: migrations
+            .iter()
+            .map(|m| PlannedMigration {
+                version: m.version,
+                name: m.name,
+                statements: m
+                    .statements
+                    .iter()
+                    .map(|sql| PlannedStatement { sql: dedent(sql), rows_affected: None, error: None })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    if rehearse && !migrations.is_empty() {
+        let mut tx = pool.begin().await?;
+        'rehearsal: for (migration, planned) in migrations.iter().zip(&mut plan.pending) {
+            for (sql, statement) in migration.statements.iter().zip(&mut planned.statements) {
+                match sqlx::query(sql).execute(&mut *tx).await {
+                    Ok(result) => statement.rows_affected = Some(result.rows_affected()),
+                    Err(e) => {
+                        statement.error = Some(e.to_string());
+                        plan.rehearsal_succeeded = Some(false);
+                        break 'rehearsal;
+                    }
+                }
+            }
+        }
+        tx.rollback().await?;
+    }
+
+    Ok(plan)
+}
+
+/// Apply all pending migrations, each in its own transaction
+pub async fn run(pool: &DbPool) -> Result<(), sqlx::Error> {
+    for migration in pending(pool).await? {
+        let mut tx = pool.begin().await?;
+
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        sqlx::query("INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        info!("Applied migration {} ({})", migration.version, migration.name);
+    }
+
+    Ok(())
+}
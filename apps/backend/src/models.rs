@@ -1,33 +1,228 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
 use uuid::Uuid;
+use validator::Validate;
+
+use crate::email;
+use crate::encryption::PiiText;
+use crate::metadata::{self, Metadata};
+use crate::organizers::EventAuthor;
+use crate::reminders::LeadTime;
+use crate::validation::{self, FieldLimits};
+
+/// Columns selected for an `Event`, its tags gathered from `event_tags`
+pub const EVENT_COLUMNS: &str = "id, title, description, start_time, end_time, location, max_participants, frozen, status, email_policy, participant_visibility, latitude, longitude, min_age, series_id, occurrence_start, created_at, updated_at, slug, (SELECT json_group_array(t.name ORDER BY t.name) FROM event_tags et JOIN tags t ON t.id = et.tag_id WHERE et.event_id = events.id) AS tags";
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Event {
     pub id: Uuid,
     pub title: String,
+    /// URL-friendly name from the title at creation, unique across events;
+    /// see `slug`
+    #[serde(default)]
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub location: Option<String>,
     pub max_participants: Option<i32>,
+    /// Frozen events reject registrations and modifications from non-admins
+    #[serde(default)]
+    pub frozen: bool,
+    #[serde(default)]
+    pub status: EventStatus,
+    #[serde(default)]
+    pub email_policy: EmailPolicy,
+    #[serde(default)]
+    pub participant_visibility: ParticipantVisibility,
+    /// Venue coordinates in degrees (WGS 84), used by the near-me search
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// Youngest age allowed to register, on the day the event starts
+    #[serde(default)]
+    pub min_age: Option<i32>,
+    /// The recurring series this event is an occurrence of
+    #[serde(default)]
+    pub series_id: Option<Uuid>,
+    /// Start of the occurrence as the series schedules it, which stays its
+    /// key when the event itself is moved
+    #[serde(default)]
+    pub occurrence_start: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Normalized tag names, sorted; see `tags`
+    #[serde(default)]
+    #[sqlx(json)]
+    pub tags: Vec<String>,
+}
+
+/// Where an event is in its lifecycle. Events created through the API start
+/// as drafts; only published events are listed publicly and accept
+/// registrations. Events from before the lifecycle existed, series
+/// occurrences and imports without a status count as published. Published
+/// events become completed once they have ended; see `completion`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum EventStatus {
+    /// Visible to organizers only, closed for registration
+    Draft,
+    #[default]
+    Published,
+    Cancelled,
+    /// Ended; participants can no longer be changed except by admins
+    Completed,
+}
+
+/// Which events a list covers, relative to now, and in what order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventScope {
+    /// Events that have not ended yet, soonest first
+    #[default]
+    Upcoming,
+    /// Events that have ended, most recent first
+    Past,
+    /// Every event, latest start first
+    All,
+}
+
+impl EventScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upcoming => "upcoming",
+            Self::Past => "past",
+            Self::All => "all",
+        }
+    }
+}
+
+/// Whose drafts an event list shows besides published events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Drafts {
+    #[default]
+    None,
+    /// Every draft, for admins
+    All,
+    /// An organizer's own drafts
+    OwnedBy(Uuid),
+}
+
+impl Drafts {
+    /// Drafts `author` may see listed
+    pub fn visible_to(author: Option<&EventAuthor>) -> Self {
+        match author {
+            None => Self::None,
+            Some(author) if author.is_admin() => Self::All,
+            Some(author) => author.organizer_id().map_or(Self::None, Self::OwnedBy),
+        }
+    }
+
+    pub fn all(&self) -> bool {
+        matches!(self, Self::All)
+    }
+
+    /// The organizer whose drafts are shown, unless all are
+    pub fn owner(&self) -> Option<Uuid> {
+        match self {
+            Self::OwnedBy(organizer_id) => Some(*organizer_id),
+            _ => None,
+        }
+    }
+}
+
+/// How an event treats several registrations with the same email.
+///
+/// Registration stores a dedupe key per participant that a partial unique
+/// index on (event_id, dedupe_key) enforces: the email for `Strict`, email
+/// plus case-folded name for `AllowDifferentName`, and NULL for `Unlimited`,
+/// each as its blind index (see `encryption`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum EmailPolicy {
+    /// One registration per email
+    #[default]
+    Strict,
+    /// The same email may register several people with different names
+    AllowDifferentName,
+    /// No restriction
+    Unlimited,
+}
+
+impl EmailPolicy {
+    /// Dedupe key of a registration with `email`, already normalized, and
+    /// `name`. The name is folded like SQLite's lower(trim()) did, so keys
+    /// stored before encryption still match.
+    pub fn dedupe_key(self, email: &str, name: &str) -> Option<String> {
+        match self {
+            Self::Strict => Some(crate::encryption::blind_index(email)),
+            Self::AllowDifferentName => Some(crate::encryption::blind_index(&format!(
+                "{}\n{}",
+                email,
+                name.trim_matches(' ').to_ascii_lowercase()
+            ))),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+/// Who may list an event's participants. Only organizers see their emails;
+/// see `visibility` for how the viewer is determined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ParticipantVisibility {
+    /// Anyone
+    #[default]
+    Public,
+    /// Registered participants of the same event
+    Participants,
+    /// Organizers only
+    Organizers,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Participant {
     pub id: Uuid,
     pub event_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
     pub name: String,
+    #[sqlx(try_from = "PiiText")]
     pub email: String,
     pub status: ParticipantStatus,
     pub registered_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the participant last checked in at the door
+    #[serde(default)]
+    pub checked_in_at: Option<DateTime<Utc>>,
+    /// Details such as dietary requirements; see `metadata`
+    #[serde(default)]
+    #[sqlx(json)]
+    pub metadata: Metadata,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+/// One page of a filtered participant list
+#[derive(Debug, Clone)]
+pub struct ParticipantPage {
+    pub participants: Vec<Participant>,
+    /// Participants matching the filters across all pages
+    pub total: i64,
+}
+
+/// Registrations of one event by standing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct ParticipantCounts {
+    /// Registered or confirmed, i.e. holding a place
+    pub registered: i64,
+    pub waitlisted: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(rename_all = "lowercase")]
 pub enum ParticipantStatus {
@@ -35,23 +230,168 @@ pub enum ParticipantStatus {
     Confirmed,
     Cancelled,
     Waitlisted,
+    /// Tentative RSVP; does not hold a place
+    Maybe,
+    /// Declined the invitation; does not hold a place
+    Declined,
+    /// Arrived at the event; set by check-in only
+    #[serde(rename = "checked_in")]
+    #[sqlx(rename = "checked_in")]
+    CheckedIn,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ParticipantStatus {
+    /// Whether a participant in this state takes up one of the event's places
+    pub fn holds_place(&self) -> bool {
+        matches!(self, Self::Registered | Self::Confirmed | Self::CheckedIn)
+    }
+
+    /// Whether participants may set this state themselves; confirming and
+    /// waitlisting are up to the organizer
+    pub fn client_settable(&self) -> bool {
+        matches!(self, Self::Registered | Self::Maybe | Self::Declined | Self::Cancelled)
+    }
+}
+
+/// Registrations of one event by status, for availability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StatusCounts {
+    pub registered: i64,
+    pub confirmed: i64,
+    pub maybe: i64,
+    pub declined: i64,
+    pub waitlisted: i64,
+    pub cancelled: i64,
+    pub checked_in: i64,
+}
+
+/// An event's capacity and how its registrations are spread over statuses
+#[derive(Debug, Clone, Serialize)]
+pub struct Availability {
+    pub event_id: Uuid,
+    pub max_participants: Option<i32>,
+    /// Places held by registered or confirmed participants
+    pub taken: i64,
+    /// Places reserved by capacity holds; see `capacity_holds`
+    pub held: i64,
+    /// Places left for registration; None when the event is unlimited
+    pub remaining: Option<i64>,
+    pub counts: StatusCounts,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(context = FieldLimits)]
+#[validate(schema(function = "validation::event_time_range", skip_on_field_errors = false))]
 pub struct CreateEvent {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        custom(function = "validation::title_len", use_context)
+    )]
     pub title: String,
+    #[validate(custom(function = "validation::description_len", use_context))]
     pub description: Option<String>,
+    #[validate(custom(function = "validation::sane_date"))]
     pub start_time: DateTime<Utc>,
+    #[validate(custom(function = "validation::sane_date"))]
     pub end_time: DateTime<Utc>,
+    #[validate(custom(function = "validation::location_len", use_context))]
     pub location: Option<String>,
+    #[validate(range(min = 1, code = "out_of_range", message = "must be greater than 0"))]
     pub max_participants: Option<i32>,
+    #[serde(default)]
+    pub email_policy: EmailPolicy,
+    #[serde(default)]
+    pub participant_visibility: ParticipantVisibility,
+    /// Given together with `longitude` or not at all
+    #[validate(range(min = -90.0, max = 90.0, code = "out_of_range", message = "must be between -90 and 90"))]
+    pub latitude: Option<f64>,
+    #[validate(range(min = -180.0, max = 180.0, code = "out_of_range", message = "must be between -180 and 180"))]
+    pub longitude: Option<f64>,
+    #[serde(default)]
+    #[validate(range(min = 1, max = 120, code = "out_of_range", message = "must be between 1 and 120"))]
+    pub min_age: Option<i32>,
+    /// Categories such as "workshop"; normalized by `validation::normalize_tags`
+    #[serde(default)]
+    #[validate(custom(function = "validation::tags"))]
+    pub tags: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(context = FieldLimits)]
 pub struct CreateParticipant {
     pub event_id: Uuid,
+    #[validate(
+        custom(function = "validation::not_blank"),
+        custom(function = "validation::name_len", use_context)
+    )]
     pub name: String,
+    /// Stored normalized; see `email::normalize`
+    #[validate(
+        custom(function = "email::syntax"),
+        custom(function = "validation::email_len", use_context)
+    )]
     pub email: String,
+    /// Version of the privacy policy the registrant accepted
+    #[validate(
+        custom(function = "validation::not_blank"),
+        custom(function = "validation::policy_version_len")
+    )]
+    pub privacy_policy_version: String,
+    #[serde(default)]
+    pub marketing_opt_in: bool,
+    /// Client the registration was made with
+    #[serde(default)]
+    pub source: Option<RegistrationSource>,
+    #[serde(default)]
+    #[validate(custom(function = "validation::tracking_code"))]
+    pub referrer_code: Option<String>,
+    /// UTM campaign of the link the registrant followed
+    #[serde(default)]
+    #[validate(custom(function = "validation::tracking_code"))]
+    pub utm_campaign: Option<String>,
+    /// Invite code or id of the participant who invited the registrant;
+    /// see `referrals`
+    #[serde(default)]
+    #[validate(custom(function = "validation::tracking_code"))]
+    pub invited_by: Option<String>,
+    /// Required by events with a minimum age; stored encrypted
+    #[serde(default)]
+    pub date_of_birth: Option<NaiveDate>,
+    /// When to be reminded of the event; a day ahead when omitted, none
+    /// when empty
+    #[serde(default)]
+    pub reminders: Option<Vec<LeadTime>>,
+    /// Answers to the event's custom form fields, keyed by field key; see
+    /// `registration_form`
+    #[serde(default)]
+    pub answers: BTreeMap<String, serde_json::Value>,
+    /// Details without a field of their own; see `metadata`
+    #[serde(default)]
+    #[validate(custom(function = "metadata::entries"))]
+    pub metadata: Metadata,
+}
+
+/// Client a registration was made with, as reported by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum RegistrationSource {
+    Web,
+    Mobile,
+    Kiosk,
+}
+
+/// Registrations from one source and how many of them were confirmed
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SourceConversion {
+    /// None for registrations without a reported source
+    pub source: Option<RegistrationSource>,
+    pub registrations: i64,
+    pub confirmed: i64,
+    pub cancelled: i64,
+    /// Share of registrations confirmed, from 0 to 1
+    #[sqlx(skip)]
+    pub conversion_rate: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
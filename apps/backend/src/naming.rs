@@ -0,0 +1,277 @@
+//! JSON field naming negotiation.
+//!
+//! The API is written in snake_case. Clients wanting camelCase ask for it
+//! with a `profile` parameter on `Accept`
+//! (`Accept: application/json; profile="camelCase"`); JSON_FIELD_NAMING
+//! sets what clients get without asking, so a deployment can flip its
+//! default while older clients still request snake_case. For camelCase
+//! clients the middleware rewrites the keys of JSON responses, and of JSON
+//! request bodies back to snake_case before handlers see them. Only keys
+//! naming fields are rewritten: maps keyed by user-chosen names or by
+//! values, such as participant metadata, are listed per route in
+//! `REQUEST_MAPS` and `RESPONSE_MAPS` and pass through as they are. ETags
+//! of camelCase responses carry a suffix of their own, so a cache holding
+//! one representation never validates the other.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+use crate::body_limit;
+use crate::error::internal_error;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Naming convention of JSON object keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldNaming {
+    #[default]
+    SnakeCase,
+    CamelCase,
+}
+
+impl FromStr for FieldNaming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "snakecase" | "snake" => Ok(Self::SnakeCase),
+            "camelcase" | "camel" => Ok(Self::CamelCase),
+            _ => Err(format!("unknown field naming '{}' (expected snake_case or camelCase)", s)),
+        }
+    }
+}
+
+/// The naming requested through an `Accept` profile parameter, if any
+pub fn requested(headers: &HeaderMap) -> Option<FieldNaming> {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .flat_map(|range| range.split(';').skip(1))
+        .filter_map(|param| param.split_once('='))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("profile"))
+        .find_map(|(_, value)| value.trim().trim_matches('"').parse().ok())
+}
+
+/// `max_participants` → `maxParticipants`
+pub fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper = false;
+    for (i, c) in key.chars().enumerate() {
+        if c == '_' && i > 0 {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// `maxParticipants` → `max_participants`
+pub fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Where bodies hold maps keyed by data rather than field names: names
+/// chosen by users (metadata, form answers, webhook field mappings) or
+/// values such as statuses and table names. Each entry is a route, `*`
+/// standing for one path segment, and the snake_case keys leading from the
+/// body's root to the map, arrays passed through; no keys make the whole
+/// body such a map.
+const REQUEST_MAPS: &[(&str, &[&str])] = &[
+    ("/api/participants", &["metadata"]),
+    ("/api/participants", &["answers"]),
+    ("/api/participants/*/metadata", &[]),
+    ("/api/organizers/me/webhooks", &["fields"]),
+    ("/api/organizers/me/webhooks/*", &["fields"]),
+];
+
+/// Like `REQUEST_MAPS`, for response bodies
+const RESPONSE_MAPS: &[(&str, &[&str])] = &[
+    ("/api/participants", &["metadata"]),
+    ("/api/participants/*", &["metadata"]),
+    ("/api/participants/*/metadata", &["metadata"]),
+    ("/api/participants/*/checkin", &["metadata"]),
+    ("/api/participants/by-email/*/export", &["registrations", "related"]),
+    ("/api/registrations/cancel", &["metadata"]),
+    ("/api/events/*/participants", &["metadata"]),
+    ("/api/events/*/digest", &["totals"]),
+    ("/api/admin/participants", &["metadata"]),
+    ("/api/admin/system", &["tables"]),
+    ("/api/admin/system", &["participants_by_status"]),
+    ("/api/admin/import/full", &["rows"]),
+    ("/api/organizers/me/webhooks", &["fields"]),
+    ("/api/organizers/me/webhooks/*", &["fields"]),
+];
+
+/// Whether `path` is `route`, where `*` matches any one segment
+fn matches_route(route: &str, path: &str) -> bool {
+    let (mut route, mut path) = (route.split('/'), path.split('/'));
+    loop {
+        match (route.next(), path.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(segment)) if expected == "*" || expected == segment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// The maps `table` lists for requests to `path`
+fn maps_for(table: &[(&str, &'static [&'static str])], path: &str) -> Vec<&'static [&'static str]> {
+    table
+        .iter()
+        .filter(|(route, _)| matches_route(route, path))
+        .map(|(_, keys)| *keys)
+        .collect()
+}
+
+/// Rename the keys of every object in `value`, leaving the contents of
+/// `maps` alone
+fn rename_keys(value: Value, rename: fn(&str) -> String, maps: &[&[&str]]) -> Value {
+    rename_below(value, rename, maps, &mut Vec::new())
+}
+
+/// `rename_keys` for the value found under `keys`
+fn rename_below(value: Value, rename: fn(&str) -> String, maps: &[&[&str]], keys: &mut Vec<String>) -> Value {
+    if maps.iter().any(|map| map.iter().eq(keys.iter())) {
+        return value;
+    }
+
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    keys.push(to_snake_case(&key));
+                    let value = rename_below(value, rename, maps, keys);
+                    keys.pop();
+                    (rename(&key), value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| rename_below(v, rename, maps, keys)).collect()),
+        other => other,
+    }
+}
+
+/// Marks the ETags of camelCase bodies, inside the quotes
+const CAMEL_CASE_TAG: &str = "-camel";
+
+/// `W/"abc"` → `W/"abc-camel"`
+fn camel_case_etag(etag: &HeaderValue) -> Option<HeaderValue> {
+    let tag = etag.to_str().ok()?.strip_suffix('"')?;
+    HeaderValue::from_str(&format!("{}{}\"", tag, CAMEL_CASE_TAG)).ok()
+}
+
+/// The tags of a camelCase client's `If-None-Match` as the handlers issued
+/// them; tags of snake_case bodies are dropped, as they name another
+/// representation
+fn snake_case_if_none_match(headers: &HeaderMap) -> Option<HeaderValue> {
+    let suffix = format!("{}\"", CAMEL_CASE_TAG);
+    let tags: Vec<String> = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter_map(|tag| match tag.strip_suffix(&suffix) {
+            Some(tag) => Some(format!("{}\"", tag)),
+            None => (tag == "*").then(|| tag.to_string()),
+        })
+        .collect();
+    HeaderValue::from_str(&tags.join(", ")).ok().filter(|_| !tags.is_empty())
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// Middleware applying the negotiated naming to JSON bodies
+pub async fn negotiate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let naming = requested(request.headers()).unwrap_or(state.config.json_field_naming);
+    let path = request.uri().path().to_string();
+
+    let mut request = if naming == FieldNaming::CamelCase && is_json(request.headers()) {
+        let (parts, body) = request.into_parts();
+        let limit = body_limit::limit_for(&state.config, parts.uri.path());
+        let Ok(bytes) = to_bytes(body, limit).await else {
+            return body_limit::too_large(limit).into_response();
+        };
+        // Malformed bodies pass through unchanged for the extractor to report
+        let bytes = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) => {
+                let maps = maps_for(REQUEST_MAPS, &path);
+                serde_json::to_vec(&rename_keys(value, to_snake_case, &maps)).expect("JSON values serialize")
+            }
+            Err(_) => bytes.to_vec(),
+        };
+        Request::from_parts(parts, Body::from(bytes))
+    } else {
+        request
+    };
+    if naming == FieldNaming::CamelCase && request.headers().contains_key(header::IF_NONE_MATCH) {
+        match snake_case_if_none_match(request.headers()) {
+            Some(tags) => request.headers_mut().insert(header::IF_NONE_MATCH, tags),
+            None => request.headers_mut().remove(header::IF_NONE_MATCH),
+        };
+    }
+
+    let mut response = next.run(request).await;
+    let etag = response.headers().get(header::ETAG).cloned();
+    if !is_json(response.headers()) && etag.is_none() {
+        return response;
+    }
+    response.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+    if naming == FieldNaming::SnakeCase {
+        return response;
+    }
+    if let Some(etag) = etag.as_ref().and_then(camel_case_etag) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    if !is_json(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for field renaming: {}", e);
+            return internal_error().into_response();
+        }
+    };
+    let bytes = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => {
+            let maps = maps_for(RESPONSE_MAPS, &path);
+            serde_json::to_vec(&rename_keys(value, to_camel_case, &maps)).expect("JSON values serialize")
+        }
+        Err(_) => bytes.to_vec(),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(bytes))
+}
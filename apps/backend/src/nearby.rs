@@ -0,0 +1,194 @@
+//! "Events near me" search.
+//!
+//! The bundled SQLite is compiled without math functions, so every event
+//! with coordinates also stores the sines and cosines of its latitude and
+//! longitude (`geo_*` columns). With those, the haversine of the central
+//! angle is plain arithmetic:
+//!
+//! hav(c) = hav(Δφ) + cos φ1 · cos φ2 · hav(Δλ), where
+//! hav(Δφ) = (1 − (cos φ1 cos φ2 + sin φ1 sin φ2)) / 2 and
+//! hav(Δλ) = (1 − (cos λ1 cos λ2 + sin λ1 sin λ2)) / 2.
+//!
+//! A latitude/longitude bounding box narrows the candidates through
+//! `idx_events_coordinates` before the exact radius check.
+
+use serde::Serialize;
+use sqlx::{FromRow, Sqlite};
+
+use crate::db::DbPool;
+use crate::models::{Drafts, Event, EVENT_COLUMNS};
+
+/// Mean Earth radius
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Radius used when `radius_km` is not given
+pub const DEFAULT_RADIUS_KM: f64 = 25.0;
+
+/// Largest accepted `radius_km`
+pub const MAX_RADIUS_KM: f64 = 500.0;
+
+/// A position in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl Point {
+    /// Parse "lat,lng"; None if malformed or out of range
+    pub fn parse(value: &str) -> Option<Self> {
+        let (lat, lng) = value.split_once(',')?;
+        let point = Point {
+            lat: lat.trim().parse().ok()?,
+            lng: lng.trim().parse().ok()?,
+        };
+        let valid = point.lat.is_finite()
+            && point.lng.is_finite()
+            && (-90.0..=90.0).contains(&point.lat)
+            && (-180.0..=180.0).contains(&point.lng);
+        valid.then_some(point)
+    }
+
+    /// sin/cos of both coordinates, as stored in the `geo_*` columns
+    pub fn trig(&self) -> Trig {
+        let (sin_lat, cos_lat) = self.lat.to_radians().sin_cos();
+        let (sin_lng, cos_lng) = self.lng.to_radians().sin_cos();
+        Trig {
+            sin_lat,
+            cos_lat,
+            sin_lng,
+            cos_lng,
+        }
+    }
+}
+
+/// Precomputed sines and cosines of a point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trig {
+    pub sin_lat: f64,
+    pub cos_lat: f64,
+    pub sin_lng: f64,
+    pub cos_lng: f64,
+}
+
+/// The `geo_*` column values for an event's coordinates
+pub fn trig_columns(latitude: Option<f64>, longitude: Option<f64>) -> Option<Trig> {
+    Some(Point { lat: latitude?, lng: longitude? }.trig())
+}
+
+/// Degree ranges containing every point within a radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lng: f64,
+    pub max_lng: f64,
+}
+
+impl BoundingBox {
+    /// Boxes reaching a pole or crossing the antimeridian fall back to the
+    /// full longitude range; the radius check still applies
+    pub fn around(center: Point, radius_km: f64) -> Self {
+        let angle = radius_km / EARTH_RADIUS_KM;
+        let min_lat = center.lat - angle.to_degrees();
+        let max_lat = center.lat + angle.to_degrees();
+
+        let (min_lng, max_lng) = if min_lat <= -90.0 || max_lat >= 90.0 {
+            (-180.0, 180.0)
+        } else {
+            let delta = (angle.sin() / center.lat.to_radians().cos()).asin().to_degrees();
+            let (min_lng, max_lng) = (center.lng - delta, center.lng + delta);
+            if min_lng < -180.0 || max_lng > 180.0 {
+                (-180.0, 180.0)
+            } else {
+                (min_lng, max_lng)
+            }
+        };
+
+        Self {
+            min_lat: min_lat.max(-90.0),
+            max_lat: max_lat.min(90.0),
+            min_lng,
+            max_lng,
+        }
+    }
+}
+
+/// Great-circle distance for a haversine value, in km
+pub fn distance_km(haversine: f64) -> f64 {
+    2.0 * EARTH_RADIUS_KM * haversine.clamp(0.0, 1.0).sqrt().asin()
+}
+
+/// Haversine value of a distance, the SQL-side radius bound
+fn haversine_of(distance_km: f64) -> f64 {
+    (distance_km / (2.0 * EARTH_RADIUS_KM)).sin().powi(2)
+}
+
+/// An event with its distance from the search center
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyEvent {
+    #[serde(flatten)]
+    pub event: Event,
+    /// Rounded to metres
+    pub distance_km: f64,
+}
+
+#[derive(FromRow)]
+struct NearbyRow {
+    #[sqlx(flatten)]
+    event: Event,
+    haversine: f64,
+}
+
+/// Events within `radius_km` of `center`, nearest first; drafts only as far
+/// as `drafts` allows, and only those tagged `tag` if given
+pub async fn events_near(
+    pool: &DbPool,
+    center: Point,
+    radius_km: f64,
+    drafts: Drafts,
+    tag: Option<&str>,
+) -> Result<Vec<NearbyEvent>, sqlx::Error> {
+    let trig = center.trig();
+    let bounds = BoundingBox::around(center, radius_km);
+
+    let rows = sqlx::query_as::<Sqlite, NearbyRow>(&format!(
+        "SELECT * FROM (
+             SELECT {},
+                    (1 - (geo_cos_lat * ?2 + geo_sin_lat * ?1)) / 2
+                        + geo_cos_lat * ?2 * (1 - (geo_cos_lng * ?4 + geo_sin_lng * ?3)) / 2 AS haversine
+             FROM events
+             WHERE latitude BETWEEN ?5 AND ?6
+               AND longitude BETWEEN ?7 AND ?8
+               AND (status != 'draft' OR ?10 OR organizer_id = ?11)
+               AND (?12 IS NULL OR EXISTS (
+                   SELECT 1 FROM event_tags et JOIN tags t ON t.id = et.tag_id WHERE et.event_id = events.id AND t.name = ?12
+               ))
+         )
+         WHERE haversine <= ?9
+         ORDER BY haversine ASC, start_time ASC",
+        EVENT_COLUMNS
+    ))
+    .bind(trig.sin_lat)
+    .bind(trig.cos_lat)
+    .bind(trig.sin_lng)
+    .bind(trig.cos_lng)
+    .bind(bounds.min_lat)
+    .bind(bounds.max_lat)
+    .bind(bounds.min_lng)
+    .bind(bounds.max_lng)
+    .bind(haversine_of(radius_km))
+    .bind(drafts.all())
+    .bind(drafts.owner())
+    .bind(tag)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| NearbyEvent {
+            event: row.event,
+            distance_km: (distance_km(row.haversine) * 1000.0).round() / 1000.0,
+        })
+        .collect())
+}
@@ -0,0 +1,458 @@
+//! Organizer accounts and their bearer tokens.
+//!
+//! Organizers sign up at `POST /api/organizers/signup` and log in at
+//! `POST /api/organizers/login`; both answer with a JWT (HS256) to send as
+//! `Authorization: Bearer <token>`. Passwords are stored as salted
+//! PBKDF2-HMAC-SHA256 hashes.
+//!
+//! The token names the organizer in `sub` and expires after
+//! ORGANIZER_TOKEN_TTL_HOURS. It is signed with JWT_SECRET; when unset, a
+//! random secret is created on first use and kept in `settings`, where
+//! every instance finds it. `authenticate` checks the token on every request
+//! and looks the organizer up, so deleting an account revokes its tokens.
+//!
+//! Creating an event needs an `EventAuthor`: a signed-in organizer, who
+//! then owns the event, or an admin. Organizers may only change and delete
+//! the events they own, and an impersonating admin is checked as the
+//! organizer it acts as; only admins may change any event. Reads stay
+//! public.
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{hmac, pbkdf2};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::num::NonZeroU32;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::auth;
+use crate::db::DbPool;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::export::{decode_hex, encode_hex};
+use crate::impersonation::{self, Impersonation};
+use crate::recurrence;
+use crate::settings;
+use crate::validation::FieldLimits;
+use crate::AppState;
+
+/// Settings key of the generated token secret
+const KEY_SETTING: &str = "organizer_jwt_key";
+
+/// PBKDF2 rounds for new password hashes
+const PBKDF2_ITERATIONS: u32 = 100_000;
+/// Scheme prefix of stored password hashes
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// Shortest accepted password
+pub const MIN_PASSWORD_LEN: u64 = 10;
+/// Longest accepted password
+pub const MAX_PASSWORD_LEN: u64 = 128;
+
+/// An organizer account
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Organizer {
+    pub id: Uuid,
+    pub email: String,
+    pub name: String,
+    #[serde(skip)]
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(context = FieldLimits)]
+pub struct Signup {
+    #[validate(
+        custom(function = "crate::validation::not_blank"),
+        custom(function = "crate::validation::name_len", use_context)
+    )]
+    pub name: String,
+    /// Stored normalized; see `email::normalize`
+    #[validate(
+        custom(function = "crate::email::syntax"),
+        custom(function = "crate::validation::email_len", use_context)
+    )]
+    pub email: String,
+    #[validate(length(
+        min = MIN_PASSWORD_LEN,
+        max = MAX_PASSWORD_LEN,
+        code = "out_of_range",
+        message = "must be 10 to 128 characters"
+    ))]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Login {
+    pub email: String,
+    pub password: String,
+}
+
+/// A freshly issued token
+#[derive(Debug, Serialize)]
+pub struct IssuedToken {
+    pub token: String,
+    pub token_type: &'static str,
+    pub expires_at: DateTime<Utc>,
+    pub organizer: Organizer,
+}
+
+/// Salted hash of `password` for storage
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new().fill(&mut salt).expect("system randomness available");
+
+    let mut hash = [0u8; HASH_LEN];
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations are non-zero");
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, password.as_bytes(), &mut hash);
+
+    format!("{}${}${}${}", HASH_SCHEME, PBKDF2_ITERATIONS, encode_hex(&salt), encode_hex(&hash))
+}
+
+/// Whether `password` matches a hash from `hash_password`
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some(HASH_SCHEME), Some(iterations), Some(salt), Some(hash), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let (Some(iterations), Some(salt), Some(hash)) = (
+        iterations.parse::<u32>().ok().and_then(NonZeroU32::new),
+        decode_hex(salt),
+        decode_hex(hash),
+    ) else {
+        return false;
+    };
+
+    pbkdf2::verify(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, password.as_bytes(), &hash).is_ok()
+}
+
+/// The token secret: `configured` if set, else the one kept in `settings`
+pub async fn signing_key(pool: &DbPool, configured: Option<&str>, now: DateTime<Utc>) -> Result<String, sqlx::Error> {
+    if let Some(key) = configured {
+        return Ok(key.to_string());
+    }
+
+    settings::shared_secret(pool, KEY_SETTING, now).await
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    typ: String,
+}
+
+/// Claims of an organizer token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// Organizer id
+    pub sub: Uuid,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+fn hmac_key(key: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes())
+}
+
+fn encode_part<T: Serialize>(value: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).expect("token parts serialize"))
+}
+
+fn decode_part<T: for<'de> Deserialize<'de>>(part: &str) -> Option<T> {
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(part).ok()?).ok()
+}
+
+/// A token naming `organizer_id`, valid from `now` until `expires_at`
+pub fn issue_token(key: &str, organizer_id: Uuid, now: DateTime<Utc>, expires_at: DateTime<Utc>) -> String {
+    let header = encode_part(&JwtHeader {
+        alg: "HS256".to_string(),
+        typ: "JWT".to_string(),
+    });
+    let claims = encode_part(&Claims {
+        sub: organizer_id,
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    });
+
+    let signed = format!("{}.{}", header, claims);
+    let signature = hmac::sign(&hmac_key(key), signed.as_bytes());
+    format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(signature.as_ref()))
+}
+
+/// The claims of `token` if it is signed with `key` and unexpired at `now`
+pub fn verify_token(key: &str, token: &str, now: DateTime<Utc>) -> Option<Claims> {
+    let (signed, signature) = token.rsplit_once('.')?;
+    let (header, claims) = signed.split_once('.')?;
+
+    // Only the algorithm we issue; never `none` or one picked by the caller
+    let header: JwtHeader = decode_part(header)?;
+    if header.alg != "HS256" {
+        return None;
+    }
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    hmac::verify(&hmac_key(key), signed.as_bytes(), &signature).ok()?;
+
+    let claims: Claims = decode_part(claims)?;
+    (claims.exp > now.timestamp()).then_some(claims)
+}
+
+/// Columns selected for an `Organizer`
+const COLUMNS: &str = "id, email, name, password_hash, created_at, updated_at";
+
+pub async fn find(pool: &DbPool, id: Uuid) -> Result<Option<Organizer>, sqlx::Error> {
+    sqlx::query_as::<_, Organizer>(&format!("SELECT {} FROM organizers WHERE id = ?", COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn find_by_email(pool: &DbPool, email: &str) -> Result<Option<Organizer>, sqlx::Error> {
+    sqlx::query_as::<_, Organizer>(&format!("SELECT {} FROM organizers WHERE email = ?", COLUMNS))
+        .bind(email)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Store a new account; fails with a UNIQUE violation if the email is taken
+pub async fn create(
+    pool: &DbPool,
+    id: Uuid,
+    name: &str,
+    email: &str,
+    password_hash: &str,
+    now: DateTime<Utc>,
+) -> Result<Organizer, sqlx::Error> {
+    sqlx::query_as::<_, Organizer>(&format!(
+        "INSERT INTO organizers (id, email, name, password_hash, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?)
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(email)
+    .bind(name)
+    .bind(password_hash)
+    .bind(now)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = api_error(StatusCode::UNAUTHORIZED, message).into_response();
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Middleware resolving `Authorization: Bearer` into an `Organizer`
+/// extension. Requests without a bearer token pass unauthenticated; an
+/// invalid or expired one, or one of a deleted account, is rejected.
+pub async fn authenticate(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(token) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim().to_string())
+    else {
+        return next.run(request).await;
+    };
+
+    let now = state.clock.now();
+    let key = match signing_key(&state.db_pool, state.config.jwt_secret.as_deref(), now).await {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::error!("Failed to load organizer token key: {}", e);
+            return internal_error().into_response();
+        }
+    };
+    let Some(claims) = verify_token(&key, &token, now) else {
+        return unauthorized("Invalid or expired organizer token");
+    };
+
+    match find(&state.db_pool, claims.sub).await {
+        Ok(Some(organizer)) => {
+            request.extensions_mut().insert(organizer);
+            next.run(request).await
+        }
+        Ok(None) => unauthorized("Organizer account no longer exists"),
+        Err(e) => {
+            tracing::error!("Failed to load organizer: {}", e);
+            internal_error().into_response()
+        }
+    }
+}
+
+/// Extractor for the signed-in organizer; rejects other callers with 401
+#[derive(Debug, Clone)]
+pub struct CurrentOrganizer(pub Organizer);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentOrganizer
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Organizer>()
+            .cloned()
+            .map(CurrentOrganizer)
+            .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Organizer token required"))
+    }
+}
+
+/// Who may create and change events
+#[derive(Debug, Clone)]
+pub enum EventAuthor {
+    Admin,
+    /// An admin acting as an organizer
+    Impersonating(Organizer),
+    Organizer(Organizer),
+}
+
+impl EventAuthor {
+    pub fn is_admin(&self) -> bool {
+        matches!(self, EventAuthor::Admin)
+    }
+
+    /// Owner recorded on events this author creates
+    pub fn organizer_id(&self) -> Option<Uuid> {
+        match self {
+            EventAuthor::Admin => None,
+            EventAuthor::Impersonating(organizer) | EventAuthor::Organizer(organizer) => Some(organizer.id),
+        }
+    }
+
+    /// Audit actor for changes made by this author
+    pub fn actor(&self) -> String {
+        match self {
+            EventAuthor::Admin => "admin".to_string(),
+            EventAuthor::Impersonating(organizer) => impersonation::actor_label(&organizer.id.to_string()),
+            EventAuthor::Organizer(organizer) => format!("organizer:{}", organizer.id),
+        }
+    }
+
+    /// Reject changes to an event owned by someone else, checked as the
+    /// impersonated organizer while impersonating. Events created before
+    /// accounts existed have no owner and are left to admins. Missing
+    /// events pass so the caller can report its own 404.
+    pub async fn ensure_owns<'e, E>(&self, executor: E, event_id: Uuid) -> Result<(), ApiError>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        if self.is_admin() {
+            return Ok(());
+        }
+
+        let owner = event_owner(executor, event_id).await?;
+        self.check_owner(owner, "Event belongs to another organizer")
+    }
+
+    /// Whether `ensure_owns` would let this author change the event, for
+    /// reads that show organizers more instead of rejecting others
+    pub async fn owns<'e, E>(&self, executor: E, event_id: Uuid) -> Result<bool, ApiError>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        if self.is_admin() {
+            return Ok(true);
+        }
+
+        let owner = event_owner(executor, event_id).await?;
+        Ok(self.check_owner(owner, "Event belongs to another organizer").is_ok())
+    }
+
+    /// Like `ensure_owns`, for a recurring series
+    pub async fn ensure_owns_series<'e, E>(&self, executor: E, series_id: Uuid) -> Result<(), ApiError>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        if self.is_admin() {
+            return Ok(());
+        }
+
+        let owner = recurrence::owner(executor, series_id).await.map_err(|e| {
+            tracing::error!("Failed to check series owner: {}", e);
+            internal_error()
+        })?;
+
+        self.check_owner(owner, "Series belongs to another organizer")
+    }
+
+    fn check_owner(&self, owner: Option<Option<Uuid>>, message: &str) -> Result<(), ApiError> {
+        match (owner, self.organizer_id()) {
+            (Some(Some(owner)), Some(organizer_id)) if owner != organizer_id => {
+                Err(api_error(StatusCode::FORBIDDEN, message))
+            }
+            (Some(None), _) => Err(api_error(StatusCode::FORBIDDEN, "Only admins can change records without an owner")),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether the caller, if signed in at all, owns the event as
+/// `EventAuthor::owns` decides
+pub async fn is_owner(author: Option<&EventAuthor>, pool: &DbPool, event_id: Uuid) -> Result<bool, ApiError> {
+    match author {
+        Some(author) => author.owns(pool, event_id).await,
+        None => Ok(false),
+    }
+}
+
+/// Owner of an event: None when it does not exist, Some(None) when it has
+/// no owner
+async fn event_owner<'e, E>(executor: E, event_id: Uuid) -> Result<Option<Option<Uuid>>, ApiError>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_scalar::<_, Option<Uuid>>("SELECT organizer_id FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check event owner: {}", e);
+            internal_error()
+        })
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for EventAuthor
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+        if auth::is_admin(parts, &state) {
+            Ok(EventAuthor::Admin)
+        } else if let Some(impersonation) = parts.extensions.get::<Impersonation>() {
+            Ok(EventAuthor::Impersonating(impersonation.organizer.clone()))
+        } else if let Some(organizer) = parts.extensions.get::<Organizer>() {
+            Ok(EventAuthor::Organizer(organizer.clone()))
+        } else {
+            Err(api_error(StatusCode::UNAUTHORIZED, "Organizer token required"))
+        }
+    }
+}
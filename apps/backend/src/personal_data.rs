@@ -0,0 +1,257 @@
+//! Data subject requests: access and erasure.
+//!
+//! A person is known by their email address and may have registered for
+//! any number of events. `GET /api/participants/by-email/:email/export`
+//! returns every registration under the address with the rows of
+//! `REGISTRATION_TABLES` about it, and the mails to the address that could
+//! not be sent. `DELETE /api/participants/by-email/:email` erases all of
+//! it; the places freed go to the waitlists as with any removal. Erasure
+//! needs a confirmation token (see `confirmation`).
+//!
+//! Both are admin only and audited. The audit entries name the person by
+//! `subject_id`, a hash of the address, so the log keeps no trace of it
+//! while a later request for the same address can still be matched.
+
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::{Sqlite, SqliteConnection};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::encryption::{self, PiiKey, PiiText};
+use crate::export::{decode_hex, encode_hex, row_to_json, BLOB_KEY};
+use crate::models::Participant;
+use crate::routes::participants::promote_from_waitlist;
+
+/// Tables with rows about a registration, in `participant_id`
+pub const REGISTRATION_TABLES: &[&str] = &[
+    "participant_consents",
+    "reminder_preferences",
+    "reminders_sent",
+    "participant_answers",
+    "seat_assignments",
+    "referrals",
+    "participant_status_history",
+    "certificates",
+    "bulk_mail_recipients",
+    "webhook_deliveries",
+];
+
+/// Columns left out of exports: tokens that act on the registration, keys
+/// derived from the email, and documents the person already received
+const OMITTED_COLUMNS: &[&str] = &[
+    "access_token",
+    "dedupe_key",
+    "email_index",
+    "to_address_index",
+    "token",
+    "withdrawal_token",
+    "pdf",
+];
+
+/// Everything held about an email address
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonalData {
+    pub email: String,
+    pub exported_at: DateTime<Utc>,
+    pub registrations: Vec<RegistrationData>,
+    /// Mails to the address that could not be sent
+    pub mail_failures: Vec<Map<String, Value>>,
+}
+
+/// One registration and what is stored about it elsewhere
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistrationData {
+    pub event_title: String,
+    pub participant: Map<String, Value>,
+    /// Rows per table; tables without any are left out
+    pub related: BTreeMap<&'static str, Vec<Map<String, Value>>>,
+}
+
+/// Rows removed by an erasure, per kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErasureReport {
+    /// Registrations removed, across all events
+    pub registrations: Vec<Uuid>,
+    pub bulk_mail_recipients: u64,
+    pub mail_failures: u64,
+}
+
+/// An erasure and the registrations it changed
+#[derive(Debug)]
+pub struct Erased {
+    pub report: ErasureReport,
+    /// Registrations removed, as they were
+    pub removed: Vec<Participant>,
+    /// Waitlisted participants who took a freed place
+    pub promoted: Vec<Participant>,
+}
+
+/// How audit entries name the person behind an address
+pub fn subject_id(email: &str) -> String {
+    encode_hex(digest::digest(&digest::SHA256, email.as_bytes()).as_ref())
+}
+
+/// A row for export: omitted columns dropped, ids, sealed and JSON columns
+/// readable
+fn exported(mut row: Map<String, Value>) -> Map<String, Value> {
+    row.retain(|column, _| !OMITTED_COLUMNS.contains(&column.as_str()));
+    for value in row.values_mut() {
+        // Ids are stored as 16-byte blobs, sealed columns (see `encryption`)
+        // as longer ones
+        if let Value::Object(blob) = value {
+            let Some(bytes) = blob.get(BLOB_KEY).and_then(Value::as_str).and_then(decode_hex) else {
+                continue;
+            };
+            if let Ok(id) = Uuid::from_slice(&bytes) {
+                *value = Value::String(id.to_string());
+            } else if let Some(text) = encryption::storage_key()
+                .and_then(|key| key.decrypt(&bytes))
+                .and_then(|plain| String::from_utf8(plain).ok())
+            {
+                *value = Value::String(text);
+            }
+        }
+        if let Value::String(text) = value {
+            if text.starts_with(['{', '[']) {
+                if let Ok(parsed) = serde_json::from_str::<Value>(text) {
+                    *value = parsed;
+                }
+            }
+        }
+    }
+    row
+}
+
+async fn rows<T>(pool: &DbPool, sql: &str, key: T) -> Result<Vec<Map<String, Value>>, sqlx::Error>
+where
+    T: for<'q> sqlx::Encode<'q, Sqlite> + sqlx::Type<Sqlite> + Send,
+{
+    sqlx::query(sql)
+        .bind(key)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| row_to_json(row).map(exported))
+        .collect()
+}
+
+/// Everything held about `email`, which must be normalized. Dates of birth
+/// are decrypted with `key`; without it they cannot be read, and none were
+/// stored.
+pub async fn collect(
+    pool: &DbPool,
+    email: &str,
+    key: Option<&PiiKey>,
+    now: DateTime<Utc>,
+) -> Result<PersonalData, sqlx::Error> {
+    let participants = sqlx::query_as::<_, (Uuid, String, PiiText, PiiText, Option<Vec<u8>>)>(
+        "SELECT p.id, e.title, p.name, p.email, p.date_of_birth
+         FROM participants p JOIN events e ON e.id = p.event_id
+         WHERE p.email_index = ?
+         ORDER BY p.registered_at, p.rowid"
+    )
+    .bind(encryption::blind_index(email))
+    .fetch_all(pool)
+    .await?;
+
+    let mut registrations = Vec::with_capacity(participants.len());
+    for (id, event_title, name, address, date_of_birth) in participants {
+        let Some(mut participant) = rows(pool, "SELECT * FROM participants WHERE id = ?", id).await?.pop() else {
+            continue;
+        };
+        // Stored sealed, like the date of birth below
+        participant.insert("name".to_string(), Value::String(name.0));
+        participant.insert("email".to_string(), Value::String(address.0));
+        let date_of_birth = date_of_birth
+            .zip(key)
+            .and_then(|(sealed, key)| key.decrypt(&sealed))
+            .and_then(|plain| String::from_utf8(plain).ok());
+        participant.insert("date_of_birth".to_string(), date_of_birth.map_or(Value::Null, Value::String));
+
+        let mut related = BTreeMap::new();
+        for table in REGISTRATION_TABLES {
+            let found = rows(
+                pool,
+                &format!("SELECT * FROM {} WHERE participant_id = ? ORDER BY rowid", table),
+                id,
+            )
+            .await?;
+            if !found.is_empty() {
+                related.insert(*table, found);
+            }
+        }
+
+        registrations.push(RegistrationData {
+            event_title,
+            participant,
+            related,
+        });
+    }
+
+    Ok(PersonalData {
+        email: email.to_string(),
+        exported_at: now,
+        registrations,
+        mail_failures: rows(
+            pool,
+            "SELECT * FROM mail_failures WHERE to_address_index = ? ORDER BY id",
+            encryption::blind_index(email),
+        ).await?,
+    })
+}
+
+/// Remove everything held about `email`, which must be normalized, and
+/// fill the places freed from the waitlists. Run it in a transaction.
+pub async fn erase(conn: &mut SqliteConnection, email: &str, now: DateTime<Utc>) -> Result<Erased, sqlx::Error> {
+    // Tables keyed by participant_id without a foreign key are cleared by
+    // hand; the others follow the participants
+    let removed = sqlx::query_as::<_, Participant>(
+        "DELETE FROM participants WHERE email_index = ?
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(encryption::blind_index(email))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut report = ErasureReport::default();
+    for participant in &removed {
+        sqlx::query("DELETE FROM participant_status_history WHERE participant_id = ?")
+            .bind(participant.id)
+            .execute(&mut *conn)
+            .await?;
+        report.bulk_mail_recipients += sqlx::query("DELETE FROM bulk_mail_recipients WHERE participant_id = ?")
+            .bind(participant.id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        report.registrations.push(participant.id);
+    }
+    report.bulk_mail_recipients += sqlx::query("DELETE FROM bulk_mail_recipients WHERE to_address_index = ?")
+        .bind(encryption::blind_index(email))
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    report.mail_failures = sqlx::query("DELETE FROM mail_failures WHERE to_address_index = ?")
+        .bind(encryption::blind_index(email))
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    // Once all registrations are gone, so none of them is promoted
+    let mut promoted = Vec::new();
+    for participant in removed.iter().filter(|p| p.status.holds_place()) {
+        if let Some(next) = promote_from_waitlist(&mut *conn, participant.event_id, None, now).await? {
+            promoted.push(next);
+        }
+    }
+
+    Ok(Erased {
+        report,
+        removed,
+        promoted,
+    })
+}
@@ -0,0 +1,188 @@
+//! Event popularity for the homepage's trending list.
+//!
+//! Every successful `GET /api/events/:id` counts a view. Writing a row per
+//! view would put SQLite's single writer in the path of the busiest read,
+//! so views are counted in memory and written behind: every
+//! POPULARITY_FLUSH_INTERVAL_SECS the counts gathered since the last flush
+//! are added to hourly buckets in `event_view_counts`, all in one
+//! transaction. An idle instance does not write at all, and a failed flush
+//! puts its counts back for the next one.
+//!
+//! `GET /api/events/trending` ranks upcoming published events by views and
+//! registrations over a recent window. A registration weighs as much as
+//! `REGISTRATION_WEIGHT` views. Views still in the buffer are not counted
+//! yet, so the list lags by up to one flush interval.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::clock::SharedClock;
+use crate::db::DbPool;
+use crate::models::{Event, EVENT_COLUMNS};
+
+/// Views one registration is worth in the ranking
+pub const REGISTRATION_WEIGHT: i64 = 20;
+/// How long hourly view buckets are kept; also the longest window
+pub const VIEW_RETENTION_HOURS: i64 = 7 * 24;
+
+/// Views counted since the last flush, shared by all request handlers
+#[derive(Debug, Clone, Default)]
+pub struct ViewCounter {
+    pending: Arc<Mutex<HashMap<Uuid, i64>>>,
+}
+
+impl ViewCounter {
+    /// Count one view of `event_id`
+    pub fn record(&self, event_id: Uuid) {
+        *self.pending.lock().unwrap().entry(event_id).or_insert(0) += 1;
+    }
+
+    /// Views waiting to be written
+    pub fn pending(&self) -> i64 {
+        self.pending.lock().unwrap().values().sum()
+    }
+
+    fn take(&self) -> HashMap<Uuid, i64> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+
+    fn restore(&self, counts: HashMap<Uuid, i64>) {
+        let mut pending = self.pending.lock().unwrap();
+        for (event_id, views) in counts {
+            *pending.entry(event_id).or_insert(0) += views;
+        }
+    }
+
+    /// Add the buffered views to the bucket of the current hour and drop
+    /// buckets past retention. Returns the number of views written.
+    pub async fn flush(&self, pool: &DbPool, now: DateTime<Utc>) -> Result<i64, sqlx::Error> {
+        let counts = self.take();
+        if counts.is_empty() {
+            return Ok(0);
+        }
+
+        let written = counts.values().sum();
+        match write(pool, &counts, now).await {
+            Ok(()) => Ok(written),
+            Err(e) => {
+                self.restore(counts);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn hour_of(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(Duration::hours(1)).unwrap_or(at)
+}
+
+async fn write(pool: &DbPool, counts: &HashMap<Uuid, i64>, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    let hour = hour_of(now);
+    let mut tx = pool.begin().await?;
+
+    for (event_id, views) in counts {
+        // Events deleted since their views were counted are skipped
+        sqlx::query(
+            "INSERT INTO event_view_counts (event_id, hour, views)
+             SELECT id, ?, ? FROM events WHERE id = ?
+             ON CONFLICT (event_id, hour) DO UPDATE SET views = views + excluded.views"
+        )
+        .bind(hour)
+        .bind(views)
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM event_view_counts WHERE hour < ?")
+        .bind(hour - Duration::hours(VIEW_RETENTION_HOURS))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+/// Flush buffered views once per `interval`
+pub async fn start_flusher(pool: DbPool, views: ViewCounter, clock: SharedClock, interval: std::time::Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match views.flush(&pool, clock.now()).await {
+            Ok(0) => {}
+            Ok(written) => tracing::debug!("Flushed {} event views", written),
+            Err(e) => tracing::error!("Failed to flush event views: {}", e),
+        }
+    }
+}
+
+/// An event in the trending list with the numbers it was ranked by
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendingEvent {
+    #[serde(flatten)]
+    pub event: Event,
+    /// Views within the window
+    pub views: i64,
+    /// Registrations within the window
+    pub registrations: i64,
+    /// Registrations per hour over the window
+    pub registration_velocity: f64,
+    pub score: i64,
+}
+
+#[derive(FromRow)]
+struct TrendingRow {
+    #[sqlx(flatten)]
+    event: Event,
+    views: i64,
+    registrations: i64,
+    score: i64,
+}
+
+/// Upcoming published events with views or registrations in the last
+/// `window_hours`, most popular first
+pub async fn trending(
+    pool: &DbPool,
+    now: DateTime<Utc>,
+    window_hours: i64,
+    limit: i64,
+) -> Result<Vec<TrendingEvent>, sqlx::Error> {
+    let since = now - Duration::hours(window_hours);
+
+    let rows = sqlx::query_as::<_, TrendingRow>(&format!(
+        "SELECT *, views + ?1 * registrations AS score FROM (
+             SELECT {},
+                    COALESCE((SELECT SUM(v.views) FROM event_view_counts v
+                              WHERE v.event_id = events.id AND v.hour >= ?2), 0) AS views,
+                    (SELECT count(*) FROM participants p
+                     WHERE p.event_id = events.id AND p.registered_at >= ?3) AS registrations
+             FROM events
+             WHERE status = 'published' AND end_time > ?4
+         )
+         WHERE views > 0 OR registrations > 0
+         ORDER BY score DESC, start_time ASC
+         LIMIT ?5",
+        EVENT_COLUMNS
+    ))
+    .bind(REGISTRATION_WEIGHT)
+    .bind(hour_of(since))
+    .bind(since)
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TrendingEvent {
+            registration_velocity: (row.registrations as f64 / window_hours as f64 * 100.0).round() / 100.0,
+            event: row.event,
+            views: row.views,
+            registrations: row.registrations,
+            score: row.score,
+        })
+        .collect())
+}
@@ -0,0 +1,112 @@
+//! Anti-entropy between the database and this instance's caches.
+//!
+//! Caches are invalidated by change notifications, which are best effort:
+//! a change whose notification insert failed would stay hidden behind the
+//! cache until its TTL ran out. Every RECONCILE_INTERVAL_SECS each cached
+//! table is fingerprinted by its row count and latest `updated_at`. When a
+//! fingerprint moves, the table's caches are invalidated; if no
+//! notification invalidated them since the last pass, the change was
+//! missed, which is logged and counted in
+//! `cache_reconcile_forced_invalidations_total`.
+//!
+//! Changes that were notified are invalidated once more as well, since a
+//! notification for one change says nothing about another, unnotified one
+//! made right after it. That costs one extra cache miss per interval.
+
+use sqlx::FromRow;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, warn};
+
+use crate::broadcaster::Channel;
+use crate::cache::AppCache;
+use crate::db::DbPool;
+
+/// Cached tables and the notification channel invalidating their caches
+const TABLES: &[(&str, Channel)] = &[
+    ("events", Channel::EventChanges),
+    ("event_series", Channel::EventChanges),
+    ("participants", Channel::ParticipantChanges),
+    ("settings", Channel::System),
+];
+
+/// Cheap summary of a table that changes whenever rows are added, removed
+/// or updated
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Fingerprint {
+    pub row_count: i64,
+    pub last_updated: Option<String>,
+}
+
+async fn fingerprint(pool: &DbPool, table: &str) -> Result<Fingerprint, sqlx::Error> {
+    sqlx::query_as::<_, Fingerprint>(&format!(
+        "SELECT count(*) AS row_count, max(updated_at) AS last_updated FROM {}",
+        table
+    ))
+    .fetch_one(pool)
+    .await
+}
+
+/// What a table looked like at the last pass
+#[derive(Debug, Clone)]
+struct Seen {
+    fingerprint: Fingerprint,
+    /// The cache's invalidation count for the table's channel at that time
+    invalidations: u64,
+}
+
+/// Compares table fingerprints with those of the previous pass
+#[derive(Debug, Default)]
+pub struct Reconciler {
+    seen: HashMap<&'static str, Seen>,
+}
+
+impl Reconciler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One reconciliation pass; returns the channels whose changes no
+    /// notification announced. The first pass only takes fingerprints.
+    pub async fn run_once(&mut self, pool: &DbPool, cache: &AppCache) -> Result<Vec<Channel>, sqlx::Error> {
+        let mut missed = Vec::new();
+
+        for &(table, channel) in TABLES {
+            let current = fingerprint(pool, table).await?;
+            let notified = cache.invalidations(channel);
+
+            if let Some(seen) = self.seen.get(table) {
+                if seen.fingerprint != current {
+                    if seen.invalidations == notified {
+                        warn!("{} changed without a notification; invalidating its caches", table);
+                        cache.force_invalidate(channel).await;
+                        missed.push(channel);
+                    } else {
+                        cache.invalidate_for_channel(channel).await;
+                    }
+                }
+            }
+
+            self.seen.insert(
+                table,
+                Seen {
+                    fingerprint: current,
+                    invalidations: cache.invalidations(channel),
+                },
+            );
+        }
+
+        Ok(missed)
+    }
+}
+
+/// Reconcile the caches with the database once per `interval`
+pub async fn start(pool: DbPool, cache: AppCache, interval: Duration) {
+    let mut reconciler = Reconciler::new();
+    loop {
+        if let Err(e) = reconciler.run_once(&pool, &cache).await {
+            error!("Failed to reconcile caches: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
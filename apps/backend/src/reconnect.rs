@@ -0,0 +1,108 @@
+//! Reconnect guidance for SSE clients.
+//!
+//! An EventSource gives up for good on a 503, and a stream that just closes
+//! brings every client back after the browser's default delay, all at the
+//! same moment. SSE clients are therefore told when to come back instead:
+//! every stream opens with a `retry:` field, and a stream refused because
+//! the instance sheds load, or ended because the instance shuts down, closes
+//! with a final `reconnect` event carrying the delay and the reason.
+//!
+//! The delay is SSE_RECONNECT_DELAY_MS plus a random share of
+//! SSE_RECONNECT_JITTER_MS drawn per client, which spreads thousands of
+//! kiosks reconnecting after a deploy over the jitter window.
+
+use axum::http::StatusCode;
+use axum::response::sse::Event;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+/// SSE event type of the final reconnect advice
+pub const RECONNECT_EVENT: &str = "reconnect";
+
+/// Reconnect delays advised to SSE clients
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectConfig {
+    /// Shortest delay advised (SSE_RECONNECT_DELAY_MS)
+    pub delay: Duration,
+    /// Upper bound of the random delay added per client (SSE_RECONNECT_JITTER_MS)
+    pub jitter: Duration,
+}
+
+impl ReconnectConfig {
+    /// Read delays from SSE_RECONNECT_* environment variables
+    pub fn from_env() -> Self {
+        let delay_ms = std::env::var("SSE_RECONNECT_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3000);
+        let jitter_ms = std::env::var("SSE_RECONNECT_JITTER_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000);
+
+        Self {
+            delay: Duration::from_millis(delay_ms),
+            jitter: Duration::from_millis(jitter_ms),
+        }
+    }
+
+    /// A delay between `delay` and `delay + jitter`, drawn anew per call
+    pub fn next_delay(&self) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        self.delay + Duration::from_millis(fastrand::u64(0..=jitter_ms))
+    }
+
+    /// Opening event of a stream, setting the client's reconnect delay
+    pub fn retry_event(&self) -> Event {
+        Event::default().retry(self.next_delay())
+    }
+
+    /// Final event of a stream the server is about to close
+    pub fn reconnect_event(&self, reason: ReconnectReason, now: DateTime<Utc>) -> Event {
+        let delay = self.next_delay();
+        let advice = ReconnectAdvice {
+            kind: RECONNECT_EVENT,
+            reason,
+            status: StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            retry_after_ms: delay.as_millis() as u64,
+            timestamp: now,
+        };
+
+        Event::default()
+            .event(RECONNECT_EVENT)
+            .retry(delay)
+            .data(serde_json::to_string(&advice).expect("advice serializes"))
+    }
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(3000),
+            jitter: Duration::from_millis(10_000),
+        }
+    }
+}
+
+/// Why the server closes a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconnectReason {
+    /// The instance sheds load and refused the stream
+    Overloaded,
+    /// The instance is shutting down; another one takes over
+    ShuttingDown,
+}
+
+/// Payload of the `reconnect` event
+#[derive(Debug, Clone, Serialize)]
+struct ReconnectAdvice {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    reason: ReconnectReason,
+    /// Status a plain request would have been answered with
+    status: u16,
+    retry_after_ms: u64,
+    timestamp: DateTime<Utc>,
+}
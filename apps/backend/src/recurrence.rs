@@ -0,0 +1,425 @@
+//! Recurring events.
+//!
+//! A series stores an event's fields once, together with a rule modelled
+//! on iCalendar's RRULE: every `interval` days, weeks or months, ending at
+//! `until` or after `count` occurrences. Occurrences are expanded from the
+//! rule when read instead of being stored.
+//!
+//! An occurrence becomes an event row of its own, linked through
+//! `series_id` and `occurrence_start`, once it is edited on its own or
+//! opened for registration; from then on the row wins over the rule.
+//! Deleted occurrences are remembered as exclusions. Editing "all future
+//! occurrences" ends the series before the occurrence and continues it as
+//! a new series from there, unless it is the first occurrence.
+
+use chrono::{DateTime, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection};
+use std::borrow::Cow;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::db::DbPool;
+use crate::models::{CreateEvent, EmailPolicy, Event, EVENT_COLUMNS, ParticipantVisibility};
+use crate::nearby;
+use crate::slug;
+
+/// Most occurrences a series may have
+pub const MAX_OCCURRENCES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// When a series repeats; exactly one of `until` and `count` ends it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Validate, FromRow)]
+#[validate(schema(function = "one_end"))]
+pub struct Recurrence {
+    pub freq: Frequency,
+    #[serde(default = "one")]
+    #[validate(range(min = 1, max = 99, code = "out_of_range", message = "must be between 1 and 99"))]
+    pub interval: u32,
+    /// Latest start of an occurrence
+    pub until: Option<DateTime<Utc>>,
+    #[validate(range(min = 1, max = 1000, code = "out_of_range", message = "must be between 1 and 1000"))]
+    pub count: Option<u32>,
+}
+
+fn one_end(recurrence: &Recurrence) -> Result<(), ValidationError> {
+    let message = match (recurrence.until, recurrence.count) {
+        (Some(_), None) | (None, Some(_)) => return Ok(()),
+        (None, None) => "either until or count is required",
+        (Some(_), Some(_)) => "until and count cannot both be given",
+    };
+    let mut err = ValidationError::new("invalid_end").with_message(Cow::Borrowed(message));
+    err.add_param(Cow::Borrowed("field"), &"until");
+    Err(err)
+}
+
+impl Recurrence {
+    /// Start of the occurrence `n` periods after `first`
+    fn nth(&self, first: DateTime<Utc>, n: u32) -> Option<DateTime<Utc>> {
+        let periods = n.checked_mul(self.interval)?;
+        match self.freq {
+            Frequency::Daily => first.checked_add_signed(Duration::days(periods.into())),
+            Frequency::Weekly => first.checked_add_signed(Duration::weeks(periods.into())),
+            // Clamped to the end of shorter months
+            Frequency::Monthly => first.checked_add_months(Months::new(periods)),
+        }
+    }
+
+    /// Occurrence starts from `first` on, at most `MAX_OCCURRENCES`
+    pub fn starts(&self, first: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let limit = self.count.map_or(MAX_OCCURRENCES, |c| (c as usize).min(MAX_OCCURRENCES));
+        (0..limit as u32)
+            .map_while(move |n| self.nth(first, n))
+            .take_while(move |start| self.until.is_none_or(|until| *start <= until))
+    }
+
+    /// Whether the rule yields more than `MAX_OCCURRENCES` occurrences
+    pub fn exceeds_limit(&self, first: DateTime<Utc>) -> bool {
+        self.count.is_none()
+            && self
+                .nth(first, MAX_OCCURRENCES as u32)
+                .is_some_and(|next| self.until.is_some_and(|until| next <= until))
+    }
+}
+
+/// A recurring event
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EventSeries {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub max_participants: Option<i32>,
+    pub email_policy: EmailPolicy,
+    pub participant_visibility: ParticipantVisibility,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub min_age: Option<i32>,
+    /// Times of the first occurrence
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    #[sqlx(flatten)]
+    pub recurrence: Recurrence,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EventSeries {
+    pub fn duration(&self) -> Duration {
+        self.end_time - self.start_time
+    }
+
+    pub fn starts(&self) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        self.recurrence.starts(self.start_time)
+    }
+
+    /// Position of the occurrence starting at `start`, if the rule has one
+    pub fn occurrence_index(&self, start: DateTime<Utc>) -> Option<usize> {
+        self.starts()
+            .take_while(|s| *s <= start)
+            .position(|s| s == start)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSeries {
+    /// Fields and times of the first occurrence
+    pub event: CreateEvent,
+    pub recurrence: Recurrence,
+}
+
+/// One occurrence of a series, expanded from the rule or read from its
+/// event row
+#[derive(Debug, Clone, Serialize)]
+pub struct Occurrence {
+    pub series_id: Uuid,
+    pub occurrence_start: DateTime<Utc>,
+    /// The occurrence's own event, once it has one; registrations go there
+    pub event_id: Option<Uuid>,
+    pub title: String,
+    pub description: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub location: Option<String>,
+    pub max_participants: Option<i32>,
+}
+
+/// A series with everything needed to expand it, as cached
+#[derive(Debug, Clone)]
+pub struct SeriesSnapshot {
+    pub series: EventSeries,
+    /// Starts of deleted occurrences
+    pub exclusions: Vec<DateTime<Utc>>,
+    /// Occurrences that have an event row
+    pub events: Vec<Event>,
+}
+
+impl SeriesSnapshot {
+    /// Occurrences starting (as scheduled) within `from..to`
+    pub fn expand(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<Occurrence> {
+        let series = &self.series;
+        self.series
+            .starts()
+            .skip_while(|start| from.is_some_and(|from| *start < from))
+            .take_while(|start| to.is_none_or(|to| *start < to))
+            .filter(|start| !self.exclusions.contains(start))
+            .map(|start| match self.events.iter().find(|e| e.occurrence_start == Some(start)) {
+                Some(event) => Occurrence {
+                    series_id: series.id,
+                    occurrence_start: start,
+                    event_id: Some(event.id),
+                    title: event.title.clone(),
+                    description: event.description.clone(),
+                    start_time: event.start_time,
+                    end_time: event.end_time,
+                    location: event.location.clone(),
+                    max_participants: event.max_participants,
+                },
+                None => Occurrence {
+                    series_id: series.id,
+                    occurrence_start: start,
+                    event_id: None,
+                    title: series.title.clone(),
+                    description: series.description.clone(),
+                    start_time: start,
+                    end_time: start + series.duration(),
+                    location: series.location.clone(),
+                    max_participants: series.max_participants,
+                },
+            })
+            .collect()
+    }
+
+    /// The event row of the occurrence starting at `start`
+    pub fn event_at(&self, start: DateTime<Utc>) -> Option<&Event> {
+        self.events.iter().find(|e| e.occurrence_start == Some(start))
+    }
+}
+
+pub async fn find<'e, E>(executor: E, id: Uuid) -> Result<Option<EventSeries>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_as::<_, EventSeries>(
+        "SELECT id, title, description, location, max_participants, email_policy, participant_visibility, latitude, longitude, min_age,
+                start_time, end_time, freq, interval, until, count, created_at, updated_at
+         FROM event_series
+         WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// A series with its exclusions and occurrence events, straight from the
+/// database
+pub async fn snapshot(pool: &DbPool, id: Uuid) -> Result<Option<SeriesSnapshot>, sqlx::Error> {
+    let Some(series) = find(pool, id).await? else {
+        return Ok(None);
+    };
+
+    let exclusions = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT occurrence_start FROM event_series_exclusions WHERE series_id = ? ORDER BY occurrence_start"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    let events = sqlx::query_as::<_, Event>(&format!(
+        "SELECT {}
+         FROM events
+         WHERE series_id = ?
+         ORDER BY occurrence_start",
+        EVENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(SeriesSnapshot { series, exclusions, events }))
+}
+
+/// Store a series whose first occurrence is `event`, owned by `organizer_id`
+pub async fn insert<'e, E>(
+    executor: E,
+    id: Uuid,
+    event: &CreateEvent,
+    recurrence: &Recurrence,
+    organizer_id: Option<Uuid>,
+    now: DateTime<Utc>,
+) -> Result<EventSeries, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_as::<_, EventSeries>(
+        "INSERT INTO event_series (id, title, description, location, max_participants, email_policy, participant_visibility, latitude, longitude, min_age,
+                                   start_time, end_time, freq, interval, until, count, organizer_id, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         RETURNING id, title, description, location, max_participants, email_policy, participant_visibility, latitude, longitude, min_age,
+                   start_time, end_time, freq, interval, until, count, created_at, updated_at"
+    )
+    .bind(id)
+    .bind(&event.title)
+    .bind(&event.description)
+    .bind(&event.location)
+    .bind(event.max_participants)
+    .bind(event.email_policy)
+    .bind(event.participant_visibility)
+    .bind(event.latitude)
+    .bind(event.longitude)
+    .bind(event.min_age)
+    .bind(event.start_time)
+    .bind(event.end_time)
+    .bind(recurrence.freq)
+    .bind(recurrence.interval)
+    .bind(recurrence.until)
+    .bind(recurrence.count)
+    .bind(organizer_id)
+    .bind(now)
+    .bind(now)
+    .fetch_one(executor)
+    .await
+}
+
+/// Owner of a series: None when it does not exist, Some(None) when it has
+/// no owner
+pub async fn owner<'e, E>(executor: E, id: Uuid) -> Result<Option<Option<Uuid>>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_scalar::<_, Option<Uuid>>("SELECT organizer_id FROM event_series WHERE id = ?")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+}
+
+/// Overwrite a series' fields and rule
+pub async fn replace<'e, E>(
+    executor: E,
+    id: Uuid,
+    event: &CreateEvent,
+    recurrence: &Recurrence,
+    now: DateTime<Utc>,
+) -> Result<EventSeries, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_as::<_, EventSeries>(
+        "UPDATE event_series
+         SET title = ?, description = ?, location = ?, max_participants = ?, email_policy = ?, participant_visibility = ?,
+             latitude = ?, longitude = ?, min_age = ?, start_time = ?, end_time = ?,
+             freq = ?, interval = ?, until = ?, count = ?, updated_at = ?
+         WHERE id = ?
+         RETURNING id, title, description, location, max_participants, email_policy, participant_visibility, latitude, longitude, min_age,
+                   start_time, end_time, freq, interval, until, count, created_at, updated_at"
+    )
+    .bind(&event.title)
+    .bind(&event.description)
+    .bind(&event.location)
+    .bind(event.max_participants)
+    .bind(event.email_policy)
+    .bind(event.participant_visibility)
+    .bind(event.latitude)
+    .bind(event.longitude)
+    .bind(event.min_age)
+    .bind(event.start_time)
+    .bind(event.end_time)
+    .bind(recurrence.freq)
+    .bind(recurrence.interval)
+    .bind(recurrence.until)
+    .bind(recurrence.count)
+    .bind(now)
+    .bind(id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Give the occurrence starting at `start` an event row of its own, with
+/// the series' fields and owner, and a slug naming its date
+pub async fn materialize(
+    conn: &mut SqliteConnection,
+    series: &EventSeries,
+    id: Uuid,
+    start: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Event, sqlx::Error> {
+    let trig = nearby::trig_columns(series.latitude, series.longitude);
+    let slug = slug::unique_slug(&mut *conn, &format!("{} {}", series.title, start.format("%Y-%m-%d"))).await?;
+
+    sqlx::query_as::<_, Event>(&format!(
+        "INSERT INTO events (id, title, description, start_time, end_time, location, max_participants, email_policy, participant_visibility,
+                             latitude, longitude, geo_sin_lat, geo_cos_lat, geo_sin_lng, geo_cos_lng, min_age, series_id, occurrence_start, slug,
+                             organizer_id, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT organizer_id FROM event_series WHERE id = ?), ?, ?)
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(id)
+    .bind(&series.title)
+    .bind(&series.description)
+    .bind(start)
+    .bind(start + series.duration())
+    .bind(&series.location)
+    .bind(series.max_participants)
+    .bind(series.email_policy)
+    .bind(series.participant_visibility)
+    .bind(series.latitude)
+    .bind(series.longitude)
+    .bind(trig.map(|t| t.sin_lat))
+    .bind(trig.map(|t| t.cos_lat))
+    .bind(trig.map(|t| t.sin_lng))
+    .bind(trig.map(|t| t.cos_lng))
+    .bind(series.min_age)
+    .bind(series.id)
+    .bind(start)
+    .bind(slug)
+    .bind(series.id)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Remember that the occurrence starting at `start` was deleted
+pub async fn exclude<'e, E>(executor: E, series_id: Uuid, start: DateTime<Utc>) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query("INSERT OR IGNORE INTO event_series_exclusions (series_id, occurrence_start) VALUES (?, ?)")
+        .bind(series_id)
+        .bind(start)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// The rule of `series` cut short so that its last occurrence is the one
+/// before `index`
+pub fn ended_before(series: &EventSeries, index: usize, start: DateTime<Utc>) -> Recurrence {
+    let mut recurrence = series.recurrence.clone();
+    match recurrence.count {
+        Some(_) => recurrence.count = Some(index as u32),
+        None => recurrence.until = Some(start - Duration::seconds(1)),
+    }
+    recurrence
+}
+
+/// The rule continuing `series` from its occurrence `index`, moved by `shift`
+pub fn continued_from(series: &EventSeries, index: usize, shift: Duration) -> Recurrence {
+    let mut recurrence = series.recurrence.clone();
+    recurrence.count = recurrence.count.map(|c| c - index as u32);
+    recurrence.until = recurrence.until.map(|u| u + shift);
+    recurrence
+}
@@ -0,0 +1,140 @@
+//! Bring-a-friend referrals.
+//!
+//! Every registration gets a short `invite_code` to share. A registrant
+//! who was invited names the inviter in `invited_by` of
+//! `POST /api/participants`, either by that code or by participant id; the
+//! inviter must be registered for the same event. Each such registration
+//! adds an edge to the event's referral graph in `referrals`.
+//!
+//! `GET /api/events/:id/referrals` ranks the inviters of an event by the
+//! registrations they brought in directly, and also counts the ones their
+//! invitees brought in turn. A referral always points at an earlier
+//! registration, so the graph has no cycles. Removing an inviter keeps the
+//! registrations they referred, which then count as referred by nobody.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Sqlite};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::encryption::PiiText;
+use crate::models::ParticipantStatus;
+
+/// Length of an invite code
+pub const INVITE_CODE_LEN: usize = 12;
+/// Most inviters one summary lists
+pub const MAX_TOP_REFERRERS: i64 = 100;
+
+/// A fresh invite code
+pub fn new_invite_code() -> String {
+    // The first 12 hex digits of a v4 UUID are all random
+    Uuid::new_v4().simple().to_string()[..INVITE_CODE_LEN].to_string()
+}
+
+/// The registration of `event_id` that `invited_by` names, by participant
+/// id or invite code
+pub async fn resolve(pool: &DbPool, event_id: Uuid, invited_by: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    match Uuid::parse_str(invited_by) {
+        Ok(id) => {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM participants WHERE id = ? AND event_id = ?")
+                .bind(id)
+                .bind(event_id)
+                .fetch_optional(pool)
+                .await
+        }
+        Err(_) => {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM participants WHERE invite_code = ? AND event_id = ?")
+                .bind(invited_by.to_ascii_lowercase())
+                .bind(event_id)
+                .fetch_optional(pool)
+                .await
+        }
+    }
+}
+
+/// Record that `referrer_id` invited `participant_id`. Accepts a pool or a
+/// transaction so it can be written atomically with the participant.
+pub async fn record<'e, E>(
+    executor: E,
+    event_id: Uuid,
+    participant_id: Uuid,
+    referrer_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO referrals (participant_id, event_id, referrer_id, created_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(participant_id)
+    .bind(event_id)
+    .bind(referrer_id)
+    .bind(now)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// An inviter and the registrations they brought in
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Referrer {
+    pub participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    pub name: String,
+    #[sqlx(try_from = "PiiText")]
+    pub email: String,
+    pub status: ParticipantStatus,
+    /// Registrations made with this participant's invitation
+    pub referrals: i64,
+    /// Of those, the ones confirmed or checked in
+    pub confirmed: i64,
+    /// Registrations invited by this participant's invitees, at any depth
+    pub indirect: i64,
+}
+
+/// Referral numbers of one event
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferralSummary {
+    pub event_id: Uuid,
+    /// Registrations made with an invitation
+    pub referred: i64,
+    /// Participants who invited anyone, most direct referrals first
+    pub top_referrers: Vec<Referrer>,
+}
+
+/// Summarize the referrals of `event_id`, listing up to `limit` inviters
+pub async fn summary(pool: &DbPool, event_id: Uuid, limit: i64) -> Result<ReferralSummary, sqlx::Error> {
+    let referred = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM referrals WHERE event_id = ?")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await?;
+
+    let top_referrers = sqlx::query_as::<_, Referrer>(
+        "WITH RECURSIVE chain (root, participant_id, depth) AS (
+             SELECT referrer_id, participant_id, 1 FROM referrals
+             WHERE event_id = ?1 AND referrer_id IS NOT NULL
+             UNION ALL
+             SELECT chain.root, r.participant_id, chain.depth + 1
+             FROM chain JOIN referrals r ON r.referrer_id = chain.participant_id
+         )
+         SELECT p.id AS participant_id, p.name, p.email, p.status,
+                sum(chain.depth = 1) AS referrals,
+                sum(chain.depth = 1 AND invitee.status IN ('confirmed', 'checked_in')) AS confirmed,
+                sum(chain.depth > 1) AS indirect
+         FROM chain
+         JOIN participants p ON p.id = chain.root
+         JOIN participants invitee ON invitee.id = chain.participant_id
+         GROUP BY p.id
+         ORDER BY referrals DESC, confirmed DESC, indirect DESC, p.registered_at ASC
+         LIMIT ?2"
+    )
+    .bind(event_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ReferralSummary { event_id, referred, top_referrers })
+}
@@ -0,0 +1,337 @@
+//! Custom registration form fields.
+//!
+//! Organizers add their own questions to an event's registration form:
+//! free text, a choice from a list, or a checkbox. Registrants answer them
+//! in `answers` of `POST /api/participants`, keyed by the field's `key`;
+//! answers are checked against the fields and stored as JSON in
+//! `participant_answers`, together with the participant.
+//!
+//! Replacing the form keeps the answers of fields whose key stays, so a
+//! relabelled question does not lose what was already answered. Removing a
+//! field, or changing its kind, removes its answers.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, Sqlite, SqliteConnection};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::db::DbPool;
+use crate::ids::IdGenerator;
+
+/// Most fields one form may have
+pub const MAX_FIELDS: usize = 50;
+/// Longest field key
+pub const MAX_KEY_LEN: usize = 64;
+/// Longest field label
+pub const MAX_LABEL_LEN: usize = 200;
+/// Most options of a select field
+pub const MAX_OPTIONS: usize = 50;
+/// Longest select option
+pub const MAX_OPTION_LEN: usize = 100;
+/// Longest answer to a text field
+pub const MAX_TEXT_ANSWER_LEN: usize = 2000;
+
+/// What kind of answer a field takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum FieldKind {
+    /// A string
+    Text,
+    /// One of the field's options
+    Select,
+    /// true or false; a required checkbox must be ticked
+    Checkbox,
+}
+
+/// One question of an event's registration form
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct FormField {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    /// Key answers are submitted under
+    pub key: String,
+    pub label: String,
+    pub kind: FieldKind,
+    pub required: bool,
+    /// Choices of a select field; empty for other kinds
+    #[sqlx(json)]
+    pub options: Vec<String>,
+    pub position: i64,
+}
+
+/// The registration form of one event, fields in display order
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistrationForm {
+    pub event_id: Uuid,
+    pub fields: Vec<FormField>,
+}
+
+/// A field as the organizer defines it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldInput {
+    pub key: String,
+    pub label: String,
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// Replacement for an event's form; fields are shown in the given order
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RegistrationFormInput {
+    #[validate(custom(function = "fields"))]
+    pub fields: Vec<FieldInput>,
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// Check field definitions: valid unique keys, labels, and options only on
+/// select fields, which need at least one
+fn fields(value: &[FieldInput]) -> Result<(), ValidationError> {
+    if value.len() > MAX_FIELDS {
+        return Err(error("too_many_fields", format!("must have at most {} fields", MAX_FIELDS)));
+    }
+
+    let mut keys = HashSet::new();
+    for field in value {
+        if field.key.is_empty() || field.key.len() > MAX_KEY_LEN {
+            return Err(error("invalid_key", format!("keys must be 1 to {} characters", MAX_KEY_LEN)));
+        }
+        if !field.key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            return Err(error("invalid_key", "keys may only contain lowercase letters, digits and '_'"));
+        }
+        if !keys.insert(field.key.as_str()) {
+            return Err(error("duplicate_key", format!("key '{}' is used more than once", field.key)));
+        }
+        if field.label.trim().is_empty() || field.label.chars().count() > MAX_LABEL_LEN {
+            return Err(error("invalid_label", format!("labels must be 1 to {} characters", MAX_LABEL_LEN)));
+        }
+
+        match field.kind {
+            FieldKind::Select => {
+                if field.options.is_empty() || field.options.len() > MAX_OPTIONS {
+                    return Err(error(
+                        "invalid_options",
+                        format!("select fields need 1 to {} options", MAX_OPTIONS),
+                    ));
+                }
+                let mut options = HashSet::new();
+                for option in &field.options {
+                    if option.trim().is_empty() || option.chars().count() > MAX_OPTION_LEN {
+                        return Err(error(
+                            "invalid_options",
+                            format!("options must be 1 to {} characters", MAX_OPTION_LEN),
+                        ));
+                    }
+                    if !options.insert(option.as_str()) {
+                        return Err(error("invalid_options", format!("option '{}' is listed twice", option)));
+                    }
+                }
+            }
+            FieldKind::Text | FieldKind::Checkbox => {
+                if !field.options.is_empty() {
+                    return Err(error("invalid_options", "only select fields have options"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fields of an event's form in display order
+pub async fn load<'e, E>(executor: E, event_id: Uuid) -> Result<Vec<FormField>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, FormField>(
+        "SELECT id, event_id, key, label, kind, required, options, position
+         FROM event_form_fields WHERE event_id = ? ORDER BY position"
+    )
+    .bind(event_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Replace an event's fields with `fields`, keeping the ids, and so the
+/// answers, of fields whose key stays; new fields get ids from `ids`
+pub async fn replace(
+    pool: &DbPool,
+    ids: &IdGenerator,
+    event_id: Uuid,
+    fields: &[FieldInput],
+    now: DateTime<Utc>,
+) -> Result<Vec<FormField>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let keys: Vec<&str> = fields.iter().map(|f| f.key.as_str()).collect();
+    let placeholders = vec!["?"; keys.len()].join(", ");
+    let mut delete = String::from("DELETE FROM event_form_fields WHERE event_id = ?");
+    if !keys.is_empty() {
+        delete.push_str(&format!(" AND key NOT IN ({})", placeholders));
+    }
+    let mut query = sqlx::query(&delete).bind(event_id);
+    for key in &keys {
+        query = query.bind(*key);
+    }
+    query.execute(&mut *tx).await?;
+
+    for (position, field) in fields.iter().enumerate() {
+        // Answers of another kind no longer fit the field
+        sqlx::query(
+            "DELETE FROM participant_answers WHERE field_id IN (
+                 SELECT id FROM event_form_fields WHERE event_id = ? AND key = ? AND kind != ?
+             )"
+        )
+        .bind(event_id)
+        .bind(&field.key)
+        .bind(field.kind)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO event_form_fields (id, event_id, key, label, kind, required, options, position)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (event_id, key) DO UPDATE SET
+                 label = excluded.label,
+                 kind = excluded.kind,
+                 required = excluded.required,
+                 options = excluded.options,
+                 position = excluded.position"
+        )
+        .bind(ids.generate(now))
+        .bind(event_id)
+        .bind(&field.key)
+        .bind(field.label.trim())
+        .bind(field.kind)
+        .bind(field.required)
+        .bind(sqlx::types::Json(&field.options))
+        .bind(position as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let fields = load(&mut *tx, event_id).await?;
+    tx.commit().await?;
+    Ok(fields)
+}
+
+fn answer_error(key: &str, code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    let mut err = error(code, message);
+    err.add_param(Cow::Borrowed("field"), &format!("answers.{}", key));
+    err
+}
+
+/// Check answers against an event's fields, reporting every problem under
+/// `answers.<key>`. Returns the answers to store by field id; unanswered
+/// optional fields and unticked optional checkboxes are left out.
+pub fn check_answers(
+    fields: &[FormField],
+    answers: &BTreeMap<String, Value>,
+) -> Result<Vec<(Uuid, Value)>, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    let mut checked = Vec::new();
+
+    for key in answers.keys() {
+        if !fields.iter().any(|f| &f.key == key) {
+            errors.add("__all__", answer_error(key, "unknown_field", "is not a field of this event's form"));
+        }
+    }
+
+    for field in fields {
+        let answer = answers.get(&field.key).filter(|v| !v.is_null());
+        let valid = match (field.kind, answer) {
+            (_, None) => {
+                if field.required {
+                    errors.add("__all__", answer_error(&field.key, "required", "must be answered"));
+                }
+                continue;
+            }
+            (FieldKind::Text, Some(Value::String(text))) => {
+                if field.required && text.trim().is_empty() {
+                    errors.add("__all__", answer_error(&field.key, "required", "must be answered"));
+                    continue;
+                }
+                if text.chars().count() > MAX_TEXT_ANSWER_LEN {
+                    errors.add(
+                        "__all__",
+                        answer_error(
+                            &field.key,
+                            "too_long",
+                            format!("must be at most {} characters", MAX_TEXT_ANSWER_LEN),
+                        ),
+                    );
+                    continue;
+                }
+                !text.trim().is_empty()
+            }
+            (FieldKind::Select, Some(Value::String(choice))) => {
+                if !field.options.contains(choice) {
+                    errors.add(
+                        "__all__",
+                        answer_error(
+                            &field.key,
+                            "invalid_option",
+                            format!("must be one of: {}", field.options.join(", ")),
+                        ),
+                    );
+                    continue;
+                }
+                true
+            }
+            (FieldKind::Checkbox, Some(Value::Bool(ticked))) => {
+                if field.required && !ticked {
+                    errors.add("__all__", answer_error(&field.key, "required", "must be ticked"));
+                    continue;
+                }
+                *ticked
+            }
+            (FieldKind::Text | FieldKind::Select, Some(_)) => {
+                errors.add("__all__", answer_error(&field.key, "invalid_type", "must be a string"));
+                continue;
+            }
+            (FieldKind::Checkbox, Some(_)) => {
+                errors.add("__all__", answer_error(&field.key, "invalid_type", "must be true or false"));
+                continue;
+            }
+        };
+
+        if valid {
+            if let Some(answer) = answer {
+                checked.push((field.id, answer.clone()));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(checked)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Store checked answers of a new registration, inside its transaction
+pub async fn record_answers(
+    conn: &mut SqliteConnection,
+    participant_id: Uuid,
+    answers: &[(Uuid, Value)],
+) -> Result<(), sqlx::Error> {
+    for (field_id, value) in answers {
+        sqlx::query("INSERT INTO participant_answers (participant_id, field_id, value) VALUES (?, ?, ?)")
+            .bind(participant_id)
+            .bind(field_id)
+            .bind(sqlx::types::Json(value))
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
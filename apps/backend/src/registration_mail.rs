@@ -0,0 +1,244 @@
+//! Confirmation mails with calendar invites.
+//!
+//! Subscribes to the domain event bus and mails participants whenever their
+//! registration or its event changes. Active registrations get a
+//! METHOD:REQUEST invite with the participant as ATTENDEE; cancellations,
+//! removals and deleted events send METHOD:CANCEL for the same UID so the
+//! entry disappears from the attendee's calendar. Organizers can reword the
+//! registration and waitlist mails with email templates.
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, warn};
+
+use crate::cancellation;
+use crate::consent;
+use crate::mail_failures;
+use crate::domain_events::DomainEvent;
+use crate::email_templates::{self, TemplateKind, Values};
+use crate::ics::{Invite, Method, Organizer};
+use crate::mailer::{Attachment, OutgoingMail};
+use crate::models::{Event, Participant, ParticipantStatus};
+use crate::routes::events::fetch_event;
+use crate::routes::participants::fetch_participants;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Registrations that hold a place and therefore a calendar entry
+fn holds_place(participant: &Participant) -> bool {
+    participant.status.holds_place()
+}
+
+/// Mail with an invite for `participant` attached as invite.ics
+pub fn invite_mail(
+    method: Method,
+    event: &Event,
+    participant: &Participant,
+    organizer: &Organizer,
+    subject: String,
+    text: String,
+    now: DateTime<Utc>,
+) -> OutgoingMail {
+    let invite = Invite {
+        method,
+        event,
+        participant,
+        organizer,
+        stamp: now,
+    };
+
+    OutgoingMail {
+        to_name: participant.name.clone(),
+        to_address: participant.email.clone(),
+        subject,
+        text,
+        attachments: vec![Attachment {
+            filename: "invite.ics".to_string(),
+            content_type: "text/calendar".to_string(),
+            parameters: vec![
+                ("method".to_string(), method.as_str().to_string()),
+                ("charset".to_string(), "utf-8".to_string()),
+            ],
+            content: invite.render().into_bytes(),
+        }],
+    }
+}
+
+fn when(event: &Event) -> String {
+    format!(
+        "{} – {} (UTC)",
+        event.start_time.format("%Y-%m-%d %H:%M"),
+        event.end_time.format("%Y-%m-%d %H:%M")
+    )
+}
+
+fn request(event: &Event, participant: &Participant, organizer: &Organizer, subject: &str, now: DateTime<Utc>) -> OutgoingMail {
+    let text = format!(
+        "Hello {},\n\nyour registration for \"{}\" is confirmed.\n\nWhen: {}\nWhere: {}\n\nThe attached invite keeps your calendar up to date.\n",
+        participant.name,
+        event.title,
+        when(event),
+        event.location.as_deref().unwrap_or("TBA"),
+    );
+    invite_mail(Method::Request, event, participant, organizer, format!("{}: {}", subject, event.title), text, now)
+}
+
+fn cancel(event: &Event, participant: &Participant, organizer: &Organizer, reason: &str, now: DateTime<Utc>) -> OutgoingMail {
+    let text = format!(
+        "Hello {},\n\n{}\n\nEvent: \"{}\" ({})\n\nThe attached cancellation removes it from your calendar.\n",
+        participant.name,
+        reason,
+        event.title,
+        when(event),
+    );
+    invite_mail(Method::Cancel, event, participant, organizer, format!("Cancelled: {}", event.title), text, now)
+}
+
+/// Mails caused by one domain event
+pub async fn mails_for(state: &AppState, domain_event: &DomainEvent) -> Result<Vec<OutgoingMail>, sqlx::Error> {
+    let organizer = state.config.mail.organizer();
+    let now = state.clock.now();
+
+    let mails = match domain_event {
+        DomainEvent::ParticipantRegistered { participant, .. } => {
+            let Some(event) = fetch_event(&state.db_pool, participant.event_id).await? else {
+                return Ok(Vec::new());
+            };
+            let kind = if holds_place(participant) {
+                TemplateKind::Confirmation
+            } else {
+                TemplateKind::Waitlisted
+            };
+            let template = email_templates::for_event(&state.db_pool, kind, event.id).await?;
+
+            let mut mail = if let Some(template) = template {
+                let rendered = template.render(&Values::new(&event, participant));
+                match kind {
+                    TemplateKind::Confirmation => {
+                        invite_mail(Method::Request, &event, participant, &organizer, rendered.subject, rendered.body, now)
+                    }
+                    _ => OutgoingMail {
+                        to_name: participant.name.clone(),
+                        to_address: participant.email.clone(),
+                        subject: rendered.subject,
+                        text: rendered.body,
+                        attachments: Vec::new(),
+                    },
+                }
+            } else if holds_place(participant) {
+                request(&event, participant, &organizer, "Registration confirmed", now)
+            } else {
+                OutgoingMail {
+                    to_name: participant.name.clone(),
+                    to_address: participant.email.clone(),
+                    subject: format!("Waitlisted: {}", event.title),
+                    text: format!(
+                        "Hello {},\n\n\"{}\" is currently full and you are on the waitlist. \
+                         You will receive a calendar invite if a place becomes available.\n",
+                        participant.name, event.title
+                    ),
+                    attachments: Vec::new(),
+                }
+            };
+            let key = cancellation::signing_key(&state.db_pool, state.config.cancellation_signing_key.as_deref(), now).await?;
+            if let Some(token) = cancellation::token_for(&state.db_pool, &key, participant.id).await? {
+                mail.text.push_str(&format!(
+                    "\nCannot make it? Cancel with POST /api/registrations/cancel?token={}\n",
+                    token
+                ));
+            }
+            if let Some(consent) = consent::fetch(&state.db_pool, participant.id).await? {
+                if consent.marketing_opt_in {
+                    mail.text.push_str(&format!(
+                        "\nYou agreed to receive news about our events. To withdraw, \
+                         send POST /api/consents/{}/withdraw.\n",
+                        consent.withdrawal_token
+                    ));
+                }
+            }
+            vec![mail]
+        }
+        DomainEvent::ParticipantStatusChanged { participant, .. } => {
+            let Some(event) = fetch_event(&state.db_pool, participant.event_id).await? else {
+                return Ok(Vec::new());
+            };
+            match participant.status {
+                ParticipantStatus::Registered | ParticipantStatus::Confirmed => {
+                    vec![request(&event, participant, &organizer, "Registration updated", now)]
+                }
+                // The invite stays, marked tentative
+                ParticipantStatus::Maybe => {
+                    vec![request(&event, participant, &organizer, "Tentative", now)]
+                }
+                ParticipantStatus::Declined => {
+                    vec![cancel(&event, participant, &organizer, "you declined the invitation.", now)]
+                }
+                ParticipantStatus::Cancelled => {
+                    vec![cancel(&event, participant, &organizer, "your registration has been cancelled.", now)]
+                }
+                ParticipantStatus::Waitlisted => {
+                    vec![cancel(&event, participant, &organizer, "you have been moved to the waitlist.", now)]
+                }
+                // Set by check-in, which publishes its own event
+                ParticipantStatus::CheckedIn => Vec::new(),
+            }
+        }
+        DomainEvent::ParticipantPromoted { participant, .. } => {
+            let Some(event) = fetch_event(&state.db_pool, participant.event_id).await? else {
+                return Ok(Vec::new());
+            };
+            vec![request(&event, participant, &organizer, "A place opened up", now)]
+        }
+        DomainEvent::ParticipantRemoved { participant, .. } if holds_place(participant) => {
+            let Some(event) = fetch_event(&state.db_pool, participant.event_id).await? else {
+                return Ok(Vec::new());
+            };
+            vec![cancel(&event, participant, &organizer, "your registration has been removed.", now)]
+        }
+        DomainEvent::EventUpdated { event } => fetch_participants(&state.db_pool, event.id)
+            .await?
+            .iter()
+            .filter(|p| holds_place(p))
+            .map(|p| request(event, p, &organizer, "Event updated", now))
+            .collect(),
+        DomainEvent::EventDeleted { event, participants } => participants
+            .iter()
+            .filter(|p| holds_place(p))
+            .map(|p| cancel(event, p, &organizer, "this event has been cancelled.", now))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(mails)
+}
+
+/// Send confirmation mails for every registration change on this instance
+pub async fn start_sender(state: AppState) {
+    let mut events = state.domain_events.subscribe();
+
+    loop {
+        let domain_event = match events.recv().await {
+            Ok(domain_event) => domain_event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Registration mailer lagged; {} domain events not mailed", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let mails = match mails_for(&state, &domain_event).await {
+            Ok(mails) => mails,
+            Err(e) => {
+                error!("Failed to prepare registration mails: {}", e);
+                continue;
+            }
+        };
+
+        for mail in &mails {
+            if let Err(e) = mail_failures::deliver(&state, mail).await {
+                error!("Failed to send \"{}\" to {}: {}", mail.subject, mail.to_address, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Event reminders mailed ahead of the start.
+//!
+//! Registrants choose their lead times, a day and/or an hour ahead or none,
+//! when registering; the default is a day ahead. Each preference row carries
+//! a random token so the choice can be changed later from a link without an
+//! account. Every REMINDER_INTERVAL_SECS the scheduler mails the reminders
+//! that have come due for published events.
+//!
+//! A reminder is claimed in `reminders_sent` before it is mailed, so
+//! instances sharing the database never send it twice. The claim is keyed
+//! by the start time it announced: moving an event reminds again.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite};
+use std::time::Duration;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::encryption::PiiText;
+use crate::mail_failures;
+use crate::mailer::OutgoingMail;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// How long before the start a reminder goes out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+pub enum LeadTime {
+    #[serde(rename = "24h")]
+    #[sqlx(rename = "24h")]
+    Day,
+    #[serde(rename = "1h")]
+    #[sqlx(rename = "1h")]
+    Hour,
+}
+
+impl LeadTime {
+    /// Longest first
+    pub const ALL: [LeadTime; 2] = [LeadTime::Day, LeadTime::Hour];
+
+    pub fn duration(self) -> chrono::Duration {
+        match self {
+            Self::Day => chrono::Duration::hours(24),
+            Self::Hour => chrono::Duration::hours(1),
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            Self::Day => "remind_24h",
+            Self::Hour => "remind_1h",
+        }
+    }
+
+    /// The next shorter lead time; a reminder is only due while that one
+    /// is not, so a late registration gets the nearest reminder only
+    fn shorter(self) -> Option<LeadTime> {
+        match self {
+            Self::Day => Some(Self::Hour),
+            Self::Hour => None,
+        }
+    }
+
+    fn phrase(self) -> &'static str {
+        match self {
+            Self::Day => "tomorrow",
+            Self::Hour => "in an hour",
+        }
+    }
+}
+
+/// Lead times of a registration that does not choose any
+pub fn default_lead_times() -> Vec<LeadTime> {
+    vec![LeadTime::Day]
+}
+
+/// Reminder lead times chosen by one participant
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderPreferences {
+    pub participant_id: Uuid,
+    /// Empty when the participant wants no reminders
+    pub lead_times: Vec<LeadTime>,
+    pub updated_at: DateTime<Utc>,
+    /// Only handed to the registrant
+    #[serde(skip_serializing)]
+    pub token: String,
+}
+
+#[derive(FromRow)]
+struct PreferenceRow {
+    participant_id: Uuid,
+    remind_24h: bool,
+    remind_1h: bool,
+    token: String,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<PreferenceRow> for ReminderPreferences {
+    fn from(row: PreferenceRow) -> Self {
+        let wanted = |lead_time: LeadTime| match lead_time {
+            LeadTime::Day => row.remind_24h,
+            LeadTime::Hour => row.remind_1h,
+        };
+        Self {
+            participant_id: row.participant_id,
+            lead_times: LeadTime::ALL.into_iter().filter(|l| wanted(*l)).collect(),
+            updated_at: row.updated_at,
+            token: row.token,
+        }
+    }
+}
+
+/// A fresh preference token
+pub fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Store the preferences of a new registration. Accepts a pool or a
+/// transaction so they can be written atomically with the participant.
+pub async fn record<'e, E>(
+    executor: E,
+    participant_id: Uuid,
+    lead_times: &[LeadTime],
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<ReminderPreferences, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, PreferenceRow>(
+        "INSERT INTO reminder_preferences (participant_id, remind_24h, remind_1h, token, updated_at)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING participant_id, remind_24h, remind_1h, token, updated_at"
+    )
+    .bind(participant_id)
+    .bind(lead_times.contains(&LeadTime::Day))
+    .bind(lead_times.contains(&LeadTime::Hour))
+    .bind(token)
+    .bind(now)
+    .fetch_one(executor)
+    .await
+    .map(Into::into)
+}
+
+/// Preferences of the holder of `token`
+pub async fn find(pool: &DbPool, token: &str) -> Result<Option<ReminderPreferences>, sqlx::Error> {
+    sqlx::query_as::<_, PreferenceRow>(
+        "SELECT participant_id, remind_24h, remind_1h, token, updated_at
+         FROM reminder_preferences
+         WHERE token = ?"
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+    .map(|row| row.map(Into::into))
+}
+
+/// Replace the lead times of the holder of `token`. None if the token is
+/// unknown.
+pub async fn update(
+    pool: &DbPool,
+    token: &str,
+    lead_times: &[LeadTime],
+    now: DateTime<Utc>,
+) -> Result<Option<ReminderPreferences>, sqlx::Error> {
+    sqlx::query_as::<_, PreferenceRow>(
+        "UPDATE reminder_preferences
+         SET remind_24h = ?, remind_1h = ?, updated_at = ?
+         WHERE token = ?
+         RETURNING participant_id, remind_24h, remind_1h, token, updated_at"
+    )
+    .bind(lead_times.contains(&LeadTime::Day))
+    .bind(lead_times.contains(&LeadTime::Hour))
+    .bind(now)
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+    .map(|row| row.map(Into::into))
+}
+
+/// A reminder that has come due
+#[derive(Debug, Clone, FromRow)]
+pub struct DueReminder {
+    pub participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    pub name: String,
+    #[sqlx(try_from = "PiiText")]
+    pub email: String,
+    pub event_title: String,
+    pub location: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub token: String,
+}
+
+/// Reminders for `lead_time` due at `now` and not sent yet, for
+/// registrations holding a place at published events
+async fn due(pool: &DbPool, lead_time: LeadTime, now: DateTime<Utc>) -> Result<Vec<DueReminder>, sqlx::Error> {
+    let after = now + lead_time.shorter().map_or(chrono::Duration::zero(), LeadTime::duration);
+
+    sqlx::query_as::<_, DueReminder>(&format!(
+        "SELECT p.id AS participant_id, p.name, p.email, e.title AS event_title, e.location, e.start_time, r.token
+         FROM participants p
+         JOIN events e ON e.id = p.event_id
+         JOIN reminder_preferences r ON r.participant_id = p.id
+         WHERE r.{} = 1
+           AND p.status IN ('registered', 'confirmed', 'checked_in')
+           AND e.status = 'published'
+           AND e.start_time > ? AND e.start_time <= ?
+           AND NOT EXISTS (
+               SELECT 1 FROM reminders_sent s
+               WHERE s.participant_id = p.id AND s.lead_time = ? AND s.event_start = e.start_time
+           )
+         ORDER BY e.start_time ASC",
+        lead_time.column()
+    ))
+    .bind(after)
+    .bind(now + lead_time.duration())
+    .bind(lead_time)
+    .fetch_all(pool)
+    .await
+}
+
+/// Claim a reminder for sending; false if another instance already did
+async fn claim(pool: &DbPool, reminder: &DueReminder, lead_time: LeadTime, now: DateTime<Utc>) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO reminders_sent (participant_id, lead_time, event_start, sent_at)
+         VALUES (?, ?, ?, ?)"
+    )
+    .bind(reminder.participant_id)
+    .bind(lead_time)
+    .bind(reminder.start_time)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub fn reminder_mail(reminder: &DueReminder, lead_time: LeadTime) -> OutgoingMail {
+    let text = format!(
+        "Hello {},\n\n\"{}\" starts {}.\n\nWhen: {} (UTC)\nWhere: {}\n\nTo change your reminders, use this token: {}\n",
+        reminder.name,
+        reminder.event_title,
+        lead_time.phrase(),
+        reminder.start_time.format("%Y-%m-%d %H:%M"),
+        reminder.location.as_deref().unwrap_or("TBA"),
+        reminder.token,
+    );
+
+    OutgoingMail {
+        to_name: reminder.name.clone(),
+        to_address: reminder.email.clone(),
+        subject: format!("Reminder: {}", reminder.event_title),
+        text,
+        attachments: Vec::new(),
+    }
+}
+
+/// Mail every reminder that is due; returns how many were sent or stored
+/// for retry
+pub async fn send_due(state: &AppState) -> Result<usize, sqlx::Error> {
+    let now = state.clock.now();
+    let mut sent = 0;
+
+    for lead_time in LeadTime::ALL {
+        for reminder in due(&state.db_pool, lead_time, now).await? {
+            if !claim(&state.db_pool, &reminder, lead_time, now).await? {
+                continue;
+            }
+            let mail = reminder_mail(&reminder, lead_time);
+            if let Err(e) = mail_failures::deliver(state, &mail).await {
+                error!("Failed to mail reminder to {}: {}", mail.to_address, e);
+            }
+            sent += 1;
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Send due reminders once per `interval`; runs on instances with
+/// background jobs (see `config::Role`)
+pub async fn start_scheduler(state: AppState, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        if let Err(e) = send_due(&state).await {
+            error!("Failed to send reminders: {}", e);
+        }
+    }
+}
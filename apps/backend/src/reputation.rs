@@ -0,0 +1,131 @@
+//! Pluggable email reputation checks for registrations.
+//!
+//! `create_participant` asks the configured `EmailReputation` about every
+//! address before inserting. The default accepts everything; with
+//! `EMAIL_BLOCKED_DOMAINS` and/or `EMAIL_BLOCKED_DOMAINS_FILE` set, the
+//! built-in `DisposableDomains` rejects throwaway-mail domains (and their
+//! subdomains) with the `disposable_email` validation code. Several
+//! checks are combined with `AllOf`.
+
+use axum::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Outcome of a reputation check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    /// Rejected; `code` is reported as the validation code on `email`
+    Reject { code: &'static str, message: String },
+}
+
+/// Decides whether an email address may register
+#[async_trait]
+pub trait EmailReputation: Send + Sync {
+    async fn check(&self, email: &str) -> Verdict;
+}
+
+/// Accepts every address
+#[derive(Debug, Default)]
+pub struct NoReputationCheck;
+
+#[async_trait]
+impl EmailReputation for NoReputationCheck {
+    async fn check(&self, _email: &str) -> Verdict {
+        Verdict::Accept
+    }
+}
+
+/// Runs checks in order; the first rejection wins
+#[derive(Default)]
+pub struct AllOf(pub Vec<Arc<dyn EmailReputation>>);
+
+#[async_trait]
+impl EmailReputation for AllOf {
+    async fn check(&self, email: &str) -> Verdict {
+        for check in &self.0 {
+            if let rejected @ Verdict::Reject { .. } = check.check(email).await {
+                return rejected;
+            }
+        }
+        Verdict::Accept
+    }
+}
+
+/// Rejects addresses at blocked (disposable) domains
+#[derive(Debug, Default)]
+pub struct DisposableDomains {
+    domains: HashSet<String>,
+}
+
+impl DisposableDomains {
+    pub fn new<I, S>(domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            domains: domains
+                .into_iter()
+                .map(|d| d.as_ref().trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|d| !d.is_empty() && !d.starts_with('#'))
+                .collect(),
+        }
+    }
+
+    /// Build from EMAIL_BLOCKED_DOMAINS (comma-separated) and
+    /// EMAIL_BLOCKED_DOMAINS_FILE (one domain per line, `#` comments)
+    pub fn from_env() -> Self {
+        let mut domains: Vec<String> = std::env::var("EMAIL_BLOCKED_DOMAINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::to_string)
+            .collect();
+
+        if let Ok(path) = std::env::var("EMAIL_BLOCKED_DOMAINS_FILE") {
+            if !path.is_empty() {
+                let contents = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("Failed to read EMAIL_BLOCKED_DOMAINS_FILE {}: {}", path, e));
+                domains.extend(contents.lines().map(str::to_string));
+            }
+        }
+
+        Self::new(domains)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    fn is_blocked(&self, domain: &str) -> bool {
+        // Match the domain itself and every parent domain
+        let mut candidate = domain;
+        loop {
+            if self.domains.contains(candidate) {
+                return true;
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) => candidate = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EmailReputation for DisposableDomains {
+    async fn check(&self, email: &str) -> Verdict {
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return Verdict::Accept;
+        };
+
+        if self.is_blocked(&domain.to_ascii_lowercase()) {
+            Verdict::Reject {
+                code: "disposable_email",
+                message: "Disposable email addresses cannot register".to_string(),
+            }
+        } else {
+            Verdict::Accept
+        }
+    }
+}
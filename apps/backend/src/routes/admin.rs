@@ -0,0 +1,594 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use serde_json::json;
+
+use crate::admin_ops::{self, ParticipantSearch, PurgeReport, SystemState};
+use crate::audit;
+use crate::auth::RequireAdmin;
+use crate::backup::{self, BackupInfo};
+use crate::broadcaster::Channel;
+use crate::db;
+use crate::devices::{self, DeviceStatus};
+use crate::export::{self, ImportReport};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::instance::{self, InstanceInfo};
+use crate::json::JsonBody;
+use crate::log_level::{InstanceLogLevel, LogLevel, LogLevelChange};
+use crate::mail_failures::{self, MailFailure, RetryReport};
+use crate::maintenance::{self, MaintenanceNotice};
+use crate::migrations::{self, MigrationPlan};
+use crate::routes::participants::TOTAL_COUNT_HEADER;
+use crate::settings::{self, EventListSettings, RetentionPolicy};
+use crate::storage::{self, StorageUsage};
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Current retention policy
+pub async fn get_retention(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<RetentionPolicy>, ApiError> {
+    let policy = settings::retention(&state.db_pool).await.map_err(|e| {
+        tracing::error!("Failed to load retention policy: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(policy))
+}
+
+/// Replace the retention policy; omitted fields reset to their defaults.
+/// Background cleanup picks the change up on its next run.
+pub async fn update_retention(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    JsonBody(payload): JsonBody<RetentionPolicy>,
+) -> Result<Json<RetentionPolicy>, ApiError> {
+    validation::validate(&payload)?;
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    settings::put(&mut *tx, settings::RETENTION_KEY, &payload, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store retention policy: {}", e);
+            internal_error()
+        })?;
+
+    audit::record(
+        &mut *tx,
+        "settings.update",
+        "settings",
+        settings::RETENTION_KEY,
+        audit::actor_label(true),
+        &json!({ "value": payload }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(payload))
+}
+
+/// Current event list settings
+pub async fn get_event_list_settings(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<EventListSettings>, ApiError> {
+    let settings = settings::event_list(&state.db_pool).await.map_err(|e| {
+        tracing::error!("Failed to load event list settings: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(settings))
+}
+
+/// Replace the event list settings; omitted fields reset to their
+/// defaults. Other instances drop their cached copy on the `system`
+/// notification.
+pub async fn update_event_list_settings(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    JsonBody(payload): JsonBody<EventListSettings>,
+) -> Result<Json<EventListSettings>, ApiError> {
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    settings::put(&mut *tx, settings::EVENT_LIST_KEY, &payload, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store event list settings: {}", e);
+            internal_error()
+        })?;
+
+    audit::record(
+        &mut *tx,
+        "settings.update",
+        "settings",
+        settings::EVENT_LIST_KEY,
+        audit::actor_label(true),
+        &json!({ "value": payload }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    state.cache.event_list_settings.invalidate_all();
+    let notification_payload = json!({
+        "type": "settings",
+        "key": settings::EVENT_LIST_KEY,
+        "timestamp": now
+    }).to_string();
+    if let Err(e) = db::insert_notification(&state.db_pool, Channel::System, &notification_payload, now).await {
+        tracing::error!("Failed to insert system notification: {}", e);
+    }
+
+    Ok(Json(payload))
+}
+
+/// Current maintenance notice
+pub async fn get_maintenance(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<MaintenanceNotice>, ApiError> {
+    let notice = settings::get(&state.db_pool, maintenance::MAINTENANCE_KEY)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load maintenance notice: {}", e);
+            internal_error()
+        })?;
+
+    Ok(Json(notice))
+}
+
+/// Set or clear (`{"message": null}`) the maintenance notice and announce
+/// it to SSE clients on the `system` channel
+pub async fn update_maintenance(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    JsonBody(mut payload): JsonBody<MaintenanceNotice>,
+) -> Result<Json<MaintenanceNotice>, ApiError> {
+    validation::validate(&payload)?;
+    payload.message = payload.message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    settings::put(&mut *tx, maintenance::MAINTENANCE_KEY, &payload, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store maintenance notice: {}", e);
+            internal_error()
+        })?;
+
+    audit::record(
+        &mut *tx,
+        "settings.update",
+        "settings",
+        maintenance::MAINTENANCE_KEY,
+        audit::actor_label(true),
+        &json!({ "value": payload }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    // Refresh this instance now; the notification reaches the others
+    state.cache.maintenance.invalidate_all();
+    let notification_payload = json!({
+        "type": "maintenance",
+        "message": payload.message,
+        "timestamp": now
+    }).to_string();
+    if let Err(e) = db::insert_notification(&state.db_pool, Channel::System, &notification_payload, now).await {
+        tracing::error!("Failed to insert system notification: {}", e);
+    }
+
+    Ok(Json(payload))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MigrationPlanQuery {
+    /// Run the statements in a transaction that is rolled back
+    #[serde(default)]
+    pub rehearse: bool,
+}
+
+/// Migrations this build would apply to the database, with their
+/// statements; nothing is applied
+pub async fn plan_migrations(
+    State(state): State<AppState>,
+    Query(query): Query<MigrationPlanQuery>,
+    _admin: RequireAdmin,
+) -> Result<Json<MigrationPlan>, ApiError> {
+    let plan = migrations::plan(&state.db_pool, query.rehearse).await.map_err(|e| {
+        tracing::error!("Failed to plan migrations: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(plan))
+}
+
+/// Schema version, row counts and the instance answering
+pub async fn system_state(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<SystemState>, ApiError> {
+    let system = admin_ops::system_state(&state).await.map_err(|e| {
+        tracing::error!("Failed to inspect system state: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(system))
+}
+
+/// Participants of all events, most recently registered first, filtered
+/// by `event_id`, `status` and `search` and paged with `limit` and
+/// `offset`; the number of matches is sent in `X-Total-Count`
+pub async fn search_participants(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    Query(query): Query<ParticipantSearch>,
+) -> Result<Response, ApiError> {
+    validation::validate(&query)?;
+
+    let (participants, total) = admin_ops::search_participants(&state.db_pool, &query).await.map_err(|e| {
+        tracing::error!("Failed to search participants: {}", e);
+        internal_error()
+    })?;
+
+    Ok(([(TOTAL_COUNT_HEADER, total.to_string())], Json(participants)).into_response())
+}
+
+/// Delete expired sessions, tokens and exports, and whatever the retention
+/// policy no longer keeps, without waiting for the background jobs
+pub async fn purge(State(state): State<AppState>, _admin: RequireAdmin) -> Result<Json<PurgeReport>, ApiError> {
+    let now = state.clock.now();
+    let report = admin_ops::purge(&state.db_pool, &state.config.data_dir, now).await.map_err(|e| {
+        tracing::error!("Failed to purge expired data: {}", e);
+        internal_error()
+    })?;
+    tracing::info!("Purged expired data: {:?}", report);
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "system.purge",
+        "system",
+        "all",
+        audit::actor_label(true),
+        &json!(report),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok(Json(report))
+}
+
+/// Entry counts of the in-memory caches on this instance
+pub async fn cache_stats(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Json<BTreeMap<&'static str, u64>> {
+    Json(state.cache.stats().await)
+}
+
+/// Instances with a recent heartbeat; during a deploy both the old and the
+/// new instance are listed with their lifecycle phase
+pub async fn list_instances(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<Vec<InstanceInfo>>, ApiError> {
+    let since = instance::liveness_cutoff(state.clock.now(), state.config.heartbeat_interval);
+    let instances = instance::list(&state.db_pool, since).await.map_err(|e| {
+        tracing::error!("Failed to list instances: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(instances))
+}
+
+/// Mails that could not be sent, most recent first
+pub async fn list_mail_failures(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<Vec<MailFailure>>, ApiError> {
+    let failures = mail_failures::list(&state.db_pool).await.map_err(|e| {
+        tracing::error!("Failed to list mail failures: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(failures))
+}
+
+/// Resend every stored failed mail; those still failing stay listed
+pub async fn retry_mail_failures(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<RetryReport>, ApiError> {
+    let report = mail_failures::retry(&state).await.map_err(|e| {
+        tracing::error!("Failed to retry mail failures: {}", e);
+        internal_error()
+    })?;
+    tracing::info!(
+        "Retried {} failed mails: {} sent, {} still failing",
+        report.retried,
+        report.sent,
+        report.failed
+    );
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "mail.retry",
+        "mail_failures",
+        "all",
+        audit::actor_label(true),
+        &json!(report),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok(Json(report))
+}
+
+/// Backups present in the data directory, newest first
+pub async fn list_backups(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<Vec<BackupInfo>>, ApiError> {
+    let backups = backup::list(&state.config.data_dir).await.map_err(|e| {
+        tracing::error!("Failed to list backups: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(backups))
+}
+
+/// Kiosk devices, those silent the longest first
+pub async fn list_devices(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<Vec<DeviceStatus>>, ApiError> {
+    let since = state.clock.now() - state.config.device_offline_after;
+    let devices = devices::list(&state.db_pool, since).await.map_err(|e| {
+        tracing::error!("Failed to list devices: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(devices))
+}
+
+/// Revoke a kiosk device's token
+pub async fn revoke_device(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let existed = devices::delete(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to revoke device: {}", e);
+        internal_error()
+    })?;
+    if !existed {
+        return Err(api_error(StatusCode::NOT_FOUND, "Device not found"));
+    }
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "device.revoke",
+        "device",
+        &id.to_string(),
+        audit::actor_label(true),
+        &json!({}),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to audit device revocation: {}", e);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Disk usage of the data directory against the configured volume size
+pub async fn storage_usage(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<Json<StorageUsage>, ApiError> {
+    let usage = storage::usage(&state.config.data_dir, state.config.volume_size_bytes)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to measure storage: {}", e);
+            internal_error()
+        })?;
+
+    Ok(Json(usage))
+}
+
+/// Write a consistent copy of the database to the data directory
+pub async fn create_backup(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Result<(StatusCode, Json<BackupInfo>), ApiError> {
+    let now = state.clock.now();
+    let info = backup::create(&state.db_pool, &state.config.data_dir, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create backup: {}", e);
+            internal_error()
+        })?;
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "backup.create",
+        "backup",
+        &info.file_name,
+        audit::actor_label(true),
+        &json!({ "size_bytes": info.size_bytes }),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+/// Stream every table as JSON Lines, starting with a header naming the
+/// schema version; holds an export slot while streaming
+pub async fn export_full(State(state): State<AppState>, _admin: RequireAdmin) -> Response {
+    let Some(permit) = state.concurrency.try_export_permit() else {
+        return state.load_shedder.shed_response();
+    };
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+    let pool = state.db_pool.clone();
+    let now = state.clock.now();
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        export::write(pool, tx, now).await;
+    });
+
+    let file_name = format!("export-{}.jsonl", now.format("%Y%m%dT%H%M%SZ"));
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+        ],
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+/// Restore a full export into this (empty) database
+pub async fn import_full(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    body: Bytes,
+) -> Result<Json<ImportReport>, ApiError> {
+    let now = state.clock.now();
+    let report = export::import(&state.db_pool, &state.config, &body, now).await?;
+
+    state.cache.invalidate_events().await;
+    state.cache.invalidate_participants().await;
+    state.cache.maintenance.invalidate_all();
+    state.cache.event_list_settings.invalidate_all();
+    tracing::info!("Imported export of schema v{}: {:?}", report.schema_version, report.rows);
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "import.full",
+        "database",
+        "all",
+        audit::actor_label(true),
+        &json!(report),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok(Json(report))
+}
+
+fn instance_log_level(state: &AppState, level: LogLevel) -> Json<InstanceLogLevel> {
+    Json(InstanceLogLevel { instance: state.instance.id().to_string(), level })
+}
+
+/// Log filter in effect on the instance that answers
+pub async fn get_log_level(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Json<InstanceLogLevel> {
+    instance_log_level(&state, state.log_level.current())
+}
+
+/// Change the log filter of the instance that answers for `ttl_secs`
+/// (ten minutes by default), after which the configured default returns
+pub async fn update_log_level(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+    JsonBody(payload): JsonBody<LogLevelChange>,
+) -> Result<Json<InstanceLogLevel>, ApiError> {
+    validation::validate(&payload)?;
+
+    let now = state.clock.now();
+    let level = state
+        .log_level
+        .set(payload.filter.trim(), Duration::from_secs(payload.ttl_secs), now)
+        .map_err(|e| validation::field_error("filter", "invalid_filter", e))?;
+
+    tracing::warn!("Log filter set to {} until {:?}", level.filter, level.expires_at);
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "log_level.update",
+        "instance",
+        state.instance.id(),
+        audit::actor_label(true),
+        &json!({ "filter": level.filter, "expires_at": level.expires_at }),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok(instance_log_level(&state, level))
+}
+
+/// Go back to the configured default log filter before the change expires
+pub async fn reset_log_level(
+    State(state): State<AppState>,
+    _admin: RequireAdmin,
+) -> Json<InstanceLogLevel> {
+    instance_log_level(&state, state.log_level.reset())
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::{internal_error, ApiError};
+use crate::models::SourceConversion;
+use crate::organizers::EventAuthor;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SourceQuery {
+    /// Limit to one event; all events when omitted
+    pub event_id: Option<Uuid>,
+}
+
+/// Registrations per reported source with their conversion to confirmed
+/// attendance, largest source first. Organizers see their own events.
+pub async fn source_conversion(
+    State(state): State<AppState>,
+    Query(query): Query<SourceQuery>,
+    author: EventAuthor,
+) -> Result<Json<Vec<SourceConversion>>, ApiError> {
+    if let Some(event_id) = query.event_id {
+        author.ensure_owns(&state.db_pool, event_id).await?;
+    }
+
+    let mut sources = sqlx::query_as::<_, SourceConversion>(
+        "SELECT source,
+                count(*) AS registrations,
+                sum(status IN ('confirmed', 'checked_in')) AS confirmed,
+                sum(status = 'cancelled') AS cancelled
+         FROM participants
+         WHERE (?1 IS NULL OR event_id = ?1)
+           AND (?2 IS NULL OR event_id IN (SELECT id FROM events WHERE organizer_id = ?2))
+         GROUP BY source
+         ORDER BY registrations DESC, source"
+    )
+    .bind(query.event_id)
+    .bind(author.organizer_id())
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to aggregate registration sources: {}", e);
+        internal_error()
+    })?;
+
+    for source in &mut sources {
+        source.conversion_rate = source.confirmed as f64 / source.registrations as f64;
+    }
+
+    Ok(Json(sources))
+}
@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEntry};
+use crate::error::{internal_error, ApiError};
+use crate::masking::{MaskPolicy, Masked};
+use crate::organizers::EventAuthor;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Most entries returned for one event
+const MAX_ENTRIES: i64 = 500;
+
+/// Audit trail of an event for its organizers. Personal data in the
+/// entries is only shown to admins; organizers, and admins impersonating
+/// one, get it masked.
+pub async fn list_event_audit(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<Vec<Masked<AuditEntry>>>, ApiError> {
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    let entries = audit::for_event(&state.db_pool, &event_id.to_string(), MAX_ENTRIES)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch audit entries: {}", e);
+            internal_error()
+        })?;
+
+    let policy = MaskPolicy::for_audit(author.is_admin());
+    Ok(Json(entries.into_iter().map(|entry| Masked::new(entry, policy)).collect()))
+}
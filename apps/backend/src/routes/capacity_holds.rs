@@ -0,0 +1,179 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::capacity_holds::{self, CapacityHold, CreateHold, Created};
+use crate::domain_events::{self, DomainEvent};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::organizers::EventAuthor;
+use crate::routes::events::{ensure_not_completed, ensure_not_frozen, fetch_event};
+use crate::routes::participants::{count_participants, promote_from_waitlist};
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+async fn require_event(state: &AppState, id: Uuid) -> Result<(), ApiError> {
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+    Ok(())
+}
+
+/// Places of an event held back from registration
+pub async fn list_holds(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<Vec<CapacityHold>>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    require_event(&state, id).await?;
+
+    let holds = capacity_holds::list(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to list capacity holds: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(holds))
+}
+
+/// Hold free places of a limited event back from registration
+pub async fn create_hold(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<CreateHold>,
+) -> Result<(StatusCode, Json<CapacityHold>), ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    validation::validate(&payload)?;
+    require_event(&state, id).await?;
+    ensure_not_frozen(&state.db_pool, id, author.is_admin()).await?;
+    ensure_not_completed(&state.db_pool, id, author.is_admin()).await?;
+
+    let now = state.clock.now();
+    let actor = author.actor();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let hold = match capacity_holds::create(&mut tx, state.ids.generate(now), id, &payload, &actor, now).await.map_err(|e| {
+        tracing::error!("Failed to create capacity hold: {}", e);
+        internal_error()
+    })? {
+        Created::Hold(hold) => hold,
+        Created::Unlimited => {
+            return Err(api_error(StatusCode::CONFLICT, "Event has no participant limit to hold places of"));
+        }
+        Created::TooFew { available } => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Not enough free places to hold",
+                    "requested": payload.seats,
+                    "available": available
+                })),
+            ));
+        }
+    };
+
+    audit::record(
+        &mut *tx,
+        "capacity_hold.create",
+        "event",
+        &id.to_string(),
+        &actor,
+        &json!({ "hold_id": hold.id, "label": hold.label, "seats": hold.seats }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to audit capacity hold: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit capacity hold: {}", e);
+        internal_error()
+    })?;
+
+    Ok((StatusCode::CREATED, Json(hold)))
+}
+
+/// Release a hold, giving its places to waitlisted participants first
+pub async fn release_hold(
+    State(state): State<AppState>,
+    Path((id, hold_id)): Path<(Uuid, Uuid)>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    require_event(&state, id).await?;
+    ensure_not_frozen(&state.db_pool, id, author.is_admin()).await?;
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let hold = capacity_holds::release(&mut tx, id, hold_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to release capacity hold: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Capacity hold not found"))?;
+
+    let mut promoted = Vec::new();
+    for _ in 0..hold.seats {
+        let next = promote_from_waitlist(&mut tx, id, None, now).await.map_err(|e| {
+            tracing::error!("Failed to promote from waitlist: {}", e);
+            internal_error()
+        })?;
+        match next {
+            Some(participant) => promoted.push(participant),
+            None => break,
+        }
+    }
+
+    audit::record(
+        &mut *tx,
+        "capacity_hold.release",
+        "event",
+        &id.to_string(),
+        &author.actor(),
+        &json!({ "hold_id": hold.id, "label": hold.label, "seats": hold.seats, "promoted": promoted.len() }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to audit capacity hold release: {}", e);
+        internal_error()
+    })?;
+
+    let counts = count_participants(&mut *tx, id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit capacity hold release: {}", e);
+        internal_error()
+    })?;
+
+    for participant in promoted {
+        domain_events::publish(&state, DomainEvent::ParticipantPromoted { participant, counts }).await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,249 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::certificate::{self, Template};
+use crate::encryption::PiiText;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::mail_failures;
+use crate::mailer::{Attachment, OutgoingMail};
+use crate::models::{Event, Participant, ParticipantStatus};
+use crate::organizers::EventAuthor;
+use crate::routes::events::fetch_event;
+use crate::routes::participants::fetch_participants;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct IssueCertificates {
+    /// Wording of the certificates; the default template when omitted
+    #[serde(default)]
+    pub template: Template,
+    /// Also mail each certificate to its participant
+    #[serde(default)]
+    pub email: bool,
+}
+
+/// A stored certificate, without the PDF itself
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CertificateInfo {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    pub participant_name: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CertificateListing {
+    #[serde(flatten)]
+    pub info: CertificateInfo,
+    pub download_url: String,
+}
+
+impl From<CertificateInfo> for CertificateListing {
+    fn from(info: CertificateInfo) -> Self {
+        Self {
+            download_url: format!("/api/certificates/{}", info.id),
+            info,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IssuedCertificates {
+    pub certificates: Vec<CertificateListing>,
+    /// Mails queued for sending
+    pub emailed: usize,
+}
+
+async fn require_event(state: &AppState, id: Uuid) -> Result<Event, ApiError> {
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))
+}
+
+fn certificate_mail(event: &Event, participant: &Participant, pdf: Vec<u8>) -> OutgoingMail {
+    OutgoingMail {
+        to_name: participant.name.clone(),
+        to_address: participant.email.clone(),
+        subject: format!("Your certificate: {}", event.title),
+        text: format!(
+            "Hello {},\n\nthank you for attending \"{}\". Your certificate of attendance is attached.\n",
+            participant.name, event.title
+        ),
+        attachments: vec![Attachment {
+            filename: "certificate.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            parameters: Vec::new(),
+            content: pdf,
+        }],
+    }
+}
+
+/// Issue certificates for every participant checked in at an event, the
+/// only ones known to have attended. Issuing again re-renders them under
+/// the same ids, so earlier download links keep working.
+pub async fn issue_certificates(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<IssueCertificates>,
+) -> Result<(StatusCode, Json<IssuedCertificates>), ApiError> {
+    validation::validate(&payload.template)?;
+    let event = require_event(&state, event_id).await?;
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    let attendees: Vec<Participant> = fetch_participants(&state.db_pool, event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participants: {}", e);
+            internal_error()
+        })?
+        .into_iter()
+        .filter(|p| p.status == ParticipantStatus::CheckedIn)
+        .collect();
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let existing: HashMap<Uuid, Uuid> =
+        sqlx::query_as::<_, (Uuid, Uuid)>("SELECT participant_id, id FROM certificates WHERE event_id = ?")
+            .bind(event_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch certificates: {}", e);
+                internal_error()
+            })?
+            .into_iter()
+            .collect();
+
+    let mut certificates = Vec::with_capacity(attendees.len());
+    let mut mails = Vec::new();
+    for participant in &attendees {
+        let id = existing.get(&participant.id).copied().unwrap_or_else(|| state.ids.generate(now));
+        let pdf = certificate::render(&payload.template, &event, participant, id, now);
+
+        sqlx::query(
+            "INSERT INTO certificates (id, event_id, participant_id, issued_at, pdf)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (participant_id) DO UPDATE SET issued_at = excluded.issued_at, pdf = excluded.pdf"
+        )
+        .bind(id)
+        .bind(event_id)
+        .bind(participant.id)
+        .bind(now)
+        .bind(&pdf)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store certificate: {}", e);
+            internal_error()
+        })?;
+
+        if payload.email {
+            mails.push(certificate_mail(&event, participant, pdf));
+        }
+        certificates.push(CertificateListing::from(CertificateInfo {
+            id,
+            event_id,
+            participant_id: participant.id,
+            participant_name: participant.name.clone(),
+            issued_at: now,
+        }));
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit certificates: {}", e);
+        internal_error()
+    })?;
+
+    let emailed = mails.len();
+    if !mails.is_empty() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            for mail in mails {
+                if let Err(e) = mail_failures::deliver(&state, &mail).await {
+                    tracing::error!("Failed to mail certificate to {}: {}", mail.to_address, e);
+                }
+            }
+        });
+    }
+
+    Ok((StatusCode::CREATED, Json(IssuedCertificates { certificates, emailed })))
+}
+
+/// Certificates issued for an event
+pub async fn list_certificates(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<Vec<CertificateListing>>, ApiError> {
+    require_event(&state, event_id).await?;
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    let mut certificates = sqlx::query_as::<_, CertificateInfo>(
+        "SELECT c.id, c.event_id, c.participant_id, p.name AS participant_name, c.issued_at
+         FROM certificates c
+         JOIN participants p ON p.id = c.participant_id
+         WHERE c.event_id = ?"
+    )
+    .bind(event_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list certificates: {}", e);
+        internal_error()
+    })?;
+    // Sorted here since names may be sealed (see `encryption`)
+    certificates.sort_by(|a, b| a.participant_name.cmp(&b.participant_name));
+
+    Ok(Json(certificates.into_iter().map(CertificateListing::from).collect()))
+}
+
+/// Download one certificate as PDF; participants get theirs by mail
+pub async fn download_certificate(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Response, ApiError> {
+    let (event_id, pdf) = sqlx::query_as::<_, (Uuid, Vec<u8>)>("SELECT event_id, pdf FROM certificates WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch certificate: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Certificate not found"))?;
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"certificate-{}.pdf\"", id)),
+        ],
+        pdf,
+    )
+        .into_response())
+}
@@ -0,0 +1,272 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth::IsAdmin;
+use crate::checkin_sync::{self, CheckInBatch, Outcome, SyncReport};
+use crate::devices::{self, CurrentDevice, KioskDevice};
+use crate::domain_events::{self, DomainEvent};
+use crate::encryption::PiiText;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::models::{EventStatus, Participant, ParticipantStatus};
+use crate::organizers::EventAuthor;
+use crate::routes::events::{ensure_not_frozen, fetch_event};
+use crate::routes::participants::count_participants;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// One participant who checked in
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CheckIn {
+    pub participant_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    pub name: String,
+    pub checked_in_at: DateTime<Utc>,
+}
+
+/// Attendance of an event so far
+#[derive(Debug, Clone, Serialize)]
+pub struct Attendance {
+    pub event_id: Uuid,
+    /// Participants holding a place, checked in or not
+    pub expected: i64,
+    pub checked_in: i64,
+    /// Most recent first
+    pub checkins: Vec<CheckIn>,
+}
+
+const ORGANIZERS_ONLY: &str = "Check-in is available to organizers only";
+
+/// Organizers check in at the events they own; a kiosk device only at its own
+async fn require_door(
+    state: &AppState,
+    author: Option<&EventAuthor>,
+    device: &KioskDevice,
+    event_id: Uuid,
+) -> Result<(), ApiError> {
+    if device.serves(event_id) {
+        return Ok(());
+    }
+    if let Some(author) = author {
+        return author.ensure_owns(&state.db_pool, event_id).await;
+    }
+    if device.0.is_some() {
+        return Err(api_error(StatusCode::FORBIDDEN, "Device is not registered for this event"));
+    }
+    Err(api_error(StatusCode::FORBIDDEN, ORGANIZERS_ONLY))
+}
+
+/// Check a participant holding a place in at the door; while the event is
+/// frozen or once it has been completed, only admins can record arrivals
+pub async fn check_in(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    IsAdmin(is_admin): IsAdmin,
+    author: Option<EventAuthor>,
+    device: KioskDevice,
+) -> Result<Json<Participant>, ApiError> {
+    if !is_admin {
+        if author.is_none() && device.0.is_none() {
+            return Err(api_error(StatusCode::FORBIDDEN, ORGANIZERS_ONLY));
+        }
+        // Both doors are scoped to events, so find out whose door this is
+        let event_id = sqlx::query_scalar::<_, Uuid>("SELECT event_id FROM participants WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch participant: {}", e);
+                internal_error()
+            })?
+            .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Participant not found"))?;
+        require_door(&state, author.as_ref(), &device, event_id).await?;
+        ensure_not_frozen(&state.db_pool, event_id, false).await?;
+    }
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let participant = sqlx::query_as::<_, Participant>(
+        "UPDATE participants
+         SET status = 'checked_in', checked_in_at = ?1, updated_at = ?1
+         WHERE id = ?2 AND status IN ('registered', 'confirmed')
+           AND (?3 OR (SELECT e.status FROM events e WHERE e.id = participants.event_id) != 'completed')
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(now)
+    .bind(id)
+    .bind(is_admin)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check in participant: {}", e);
+        internal_error()
+    })?;
+
+    // Nothing updated: the participant is missing, holds no place to check
+    // in to, or the event has been completed
+    let Some(participant) = participant else {
+        let status = sqlx::query_as::<_, (ParticipantStatus, EventStatus)>(
+            "SELECT p.status, e.status FROM participants p JOIN events e ON e.id = p.event_id WHERE p.id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?;
+        return Err(match status {
+            None => api_error(StatusCode::NOT_FOUND, "Participant not found"),
+            Some((ParticipantStatus::CheckedIn, _)) => api_error(StatusCode::CONFLICT, "Participant already checked in"),
+            Some((ParticipantStatus::Registered | ParticipantStatus::Confirmed, EventStatus::Completed)) => {
+                api_error(StatusCode::CONFLICT, "Event has ended; participants can no longer be changed")
+            }
+            Some(_) => api_error(StatusCode::CONFLICT, "Participant holds no place at this event"),
+        });
+    };
+
+    let counts = count_participants(&mut *tx, participant.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit check-in: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(
+        &state,
+        DomainEvent::ParticipantCheckedIn { participant: participant.clone(), counts },
+    )
+    .await;
+
+    Ok(Json(participant))
+}
+
+/// Check-ins a kiosk recorded while offline, for the device's own event.
+/// Each record gets its own outcome; the earliest time of a check-in wins.
+pub async fn sync_checkins(
+    State(state): State<AppState>,
+    CurrentDevice(device): CurrentDevice,
+    JsonBody(payload): JsonBody<CheckInBatch>,
+) -> Result<Json<SyncReport>, ApiError> {
+    validation::validate(&payload)?;
+    if payload.device_id.is_some_and(|id| id != device.id) {
+        return Err(validation::field_error(
+            "device_id",
+            "mismatch",
+            "does not match the device token".to_string(),
+        ));
+    }
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let mut results = Vec::with_capacity(payload.checkins.len());
+    let mut checked_in = Vec::new();
+    let mut corrected = Vec::new();
+    for record in &payload.checkins {
+        let applied = checkin_sync::apply(&mut tx, device.event_id, record, now).await.map_err(|e| {
+            tracing::error!("Failed to sync check-in: {}", e);
+            internal_error()
+        })?;
+        match applied.result.outcome {
+            Outcome::Corrected => corrected.extend(applied.participant),
+            _ => checked_in.extend(applied.participant),
+        }
+        results.push(applied.result);
+    }
+
+    let counts = count_participants(&mut *tx, device.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit check-in sync: {}", e);
+        internal_error()
+    })?;
+
+    if let Err(e) = devices::touch(&state.db_pool, device.id, now).await {
+        tracing::error!("Failed to record device activity: {}", e);
+    }
+    tracing::info!(
+        "Device {} synced {} check-ins, {} new",
+        device.id,
+        results.len(),
+        checked_in.len()
+    );
+
+    for participant in checked_in {
+        domain_events::publish(&state, DomainEvent::ParticipantCheckedIn { participant, counts }).await;
+    }
+    for participant in corrected {
+        domain_events::publish(&state, DomainEvent::ParticipantDetailsChanged { participant, counts }).await;
+    }
+
+    Ok(Json(SyncReport {
+        device_id: device.id,
+        results,
+    }))
+}
+
+/// Who has checked in at an event so far, for the door and live attendance
+pub async fn list_checkins(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: Option<EventAuthor>,
+    device: KioskDevice,
+) -> Result<Json<Attendance>, ApiError> {
+    require_door(&state, author.as_ref(), &device, event_id).await?;
+
+    fetch_event(&state.db_pool, event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let checkins = sqlx::query_as::<_, CheckIn>(
+        "SELECT id AS participant_id, name, checked_in_at
+         FROM participants
+         WHERE event_id = ? AND status = 'checked_in'
+         ORDER BY checked_in_at DESC"
+    )
+    .bind(event_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list check-ins: {}", e);
+        internal_error()
+    })?;
+
+    let counts = count_participants(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(Attendance {
+        event_id,
+        expected: counts.registered,
+        checked_in: checkins.len() as i64,
+        checkins,
+    }))
+}
@@ -0,0 +1,66 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::confirmation;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct PrepareConfirmation {
+    /// HTTP method of the request to confirm, e.g. "DELETE"
+    pub method: String,
+    /// Path and query of the request to confirm, e.g. "/api/events/<id>?force=true"
+    pub target: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfirmationToken {
+    pub token: String,
+    pub method: String,
+    pub target: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issue a short-lived, single-use token for a destructive request
+pub async fn prepare_confirmation(
+    State(state): State<AppState>,
+    JsonBody(payload): JsonBody<PrepareConfirmation>,
+) -> Result<(StatusCode, Json<ConfirmationToken>), ApiError> {
+    let method = payload.method.trim().to_ascii_uppercase();
+    if !matches!(method.as_str(), "DELETE" | "POST" | "PUT") {
+        return Err(api_error(StatusCode::BAD_REQUEST, "method must be DELETE, POST or PUT"));
+    }
+    if !payload.target.starts_with("/api/") {
+        return Err(api_error(StatusCode::BAD_REQUEST, "target must be an /api/ path"));
+    }
+
+    let now = state.clock.now();
+    let token = Uuid::new_v4().simple().to_string();
+    let expires_at = now + state.config.confirmation_ttl;
+
+    if let Err(e) = confirmation::prune(&state.db_pool, now).await {
+        tracing::warn!("Failed to prune confirmation tokens: {}", e);
+    }
+
+    confirmation::issue(&state.db_pool, &token, &method, &payload.target, expires_at)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue confirmation token: {}", e);
+            internal_error()
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ConfirmationToken {
+            token,
+            method,
+            target: payload.target,
+            expires_at,
+        }),
+    ))
+}
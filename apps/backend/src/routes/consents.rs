@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+
+use crate::audit;
+use crate::consent::{self, Consent};
+use crate::error::{api_error, internal_error, ApiError};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Revoke marketing consent using the token handed out at registration.
+/// Idempotent: withdrawing again returns the already withdrawn consent.
+pub async fn withdraw_marketing_consent(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<Consent>, ApiError> {
+    let now = state.clock.now();
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let consent = consent::withdraw_marketing(&mut *tx, &token, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to withdraw marketing consent: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Consent not found"))?;
+
+    audit::record(
+        &mut *tx,
+        "consent.withdraw_marketing",
+        "participant",
+        &consent.participant_id.to_string(),
+        "participant",
+        &json!({ "withdrawn_at": consent.marketing_updated_at }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit consent withdrawal: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(consent))
+}
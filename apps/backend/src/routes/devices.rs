@@ -0,0 +1,75 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::json;
+
+use crate::audit;
+use crate::devices::{self, CurrentDevice, Device, Heartbeat, RegisterDevice, RegisteredDevice};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::organizers::EventAuthor;
+use crate::routes::events::fetch_event;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Register a check-in tablet for an event; its organizer only. The token
+/// in the response is not shown again.
+pub async fn register_device(
+    State(state): State<AppState>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<RegisterDevice>,
+) -> Result<(StatusCode, Json<RegisteredDevice>), ApiError> {
+    validation::validate(&payload)?;
+    author.ensure_owns(&state.db_pool, payload.event_id).await?;
+
+    fetch_event(&state.db_pool, payload.event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let now = state.clock.now();
+    let token = devices::new_token();
+    let device = devices::create(&state.db_pool, state.ids.generate(now), payload.name.trim(), payload.event_id, &token, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to register device: {}", e);
+            internal_error()
+        })?;
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "device.register",
+        "device",
+        &device.id.to_string(),
+        &author.actor(),
+        &json!({ "name": device.name, "event_id": device.event_id }),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to audit device registration: {}", e);
+    }
+
+    Ok((StatusCode::CREATED, Json(RegisteredDevice { device, token })))
+}
+
+/// Report that the calling device is alive, with its unsynced check-ins
+pub async fn heartbeat(
+    State(state): State<AppState>,
+    CurrentDevice(device): CurrentDevice,
+    JsonBody(payload): JsonBody<Heartbeat>,
+) -> Result<Json<Device>, ApiError> {
+    validation::validate(&payload)?;
+
+    let device = devices::beat(&state.db_pool, device.id, &payload, state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record device heartbeat: {}", e);
+            internal_error()
+        })?;
+
+    Ok(Json(device))
+}
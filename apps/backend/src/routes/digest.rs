@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::digest;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::organizers::EventAuthor;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    /// "json" (default) or "text"
+    pub format: Option<String>,
+}
+
+/// Activity on an event over the last 24 hours. It names everyone who
+/// registered or moved, so only those who may change the event may read it.
+pub async fn get_event_digest(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DigestQuery>,
+    author: EventAuthor,
+) -> Result<Response, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+
+    let as_text = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("text") => true,
+        Some(_) => return Err(api_error(StatusCode::BAD_REQUEST, "format must be json or text")),
+    };
+
+    let now = state.clock.now();
+    let digest = digest::build(&state.db_pool, id, now - chrono::Duration::days(1), now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build digest: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    if as_text {
+        Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], digest.to_text()).into_response())
+    } else {
+        Ok(Json(digest).into_response())
+    }
+}
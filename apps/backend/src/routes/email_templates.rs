@@ -0,0 +1,404 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::audit;
+use crate::bulk_mail::{self, BulkMail, BulkMailReport};
+use crate::email_templates::{self, EmailTemplate, EmailTemplateInput, RenderedMail, TemplateKind, Values, MAX_BODY_LEN, MAX_SUBJECT_LEN};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::mail_failures;
+use crate::mailer::OutgoingMail;
+use crate::models::{Event, Participant, ParticipantStatus};
+use crate::organizers::EventAuthor;
+use crate::routes::events::fetch_event;
+use crate::routes::participants::fetch_participants;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateQuery {
+    /// Templates of this event and those for all events
+    pub event_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PreviewTemplate {
+    pub event_id: Uuid,
+    /// Participant whose details fill in `{{name}}` and `{{email}}`; sample
+    /// values when omitted
+    pub participant_id: Option<Uuid>,
+    #[validate(custom(function = "email_templates::known_placeholders"))]
+    pub subject: String,
+    #[validate(custom(function = "email_templates::known_placeholders"))]
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendAnnouncement {
+    pub template_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnouncementSent {
+    /// Mails queued for sending
+    pub recipients: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SendBulkMail {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_SUBJECT_LEN, code = "too_long", message = "must be at most 200 characters"),
+        custom(function = "email_templates::known_placeholders")
+    )]
+    pub subject: String,
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_BODY_LEN, code = "too_long", message = "must be at most 10000 characters"),
+        custom(function = "email_templates::known_placeholders")
+    )]
+    pub body: String,
+    /// Participants with one of these statuses are mailed; those holding a
+    /// place when omitted
+    #[serde(default = "bulk_mail::default_statuses")]
+    #[validate(length(min = 1, code = "empty", message = "must name at least one status"))]
+    pub statuses: Vec<ParticipantStatus>,
+}
+
+/// Templates of one event belong to its organizer; those for all events
+/// word every organizer's mails, so only admins change them
+async fn ensure_owns_template(state: &AppState, author: &EventAuthor, event_id: Option<Uuid>) -> Result<(), ApiError> {
+    match event_id {
+        Some(event_id) => author.ensure_owns(&state.db_pool, event_id).await,
+        None if author.is_admin() => Ok(()),
+        None => Err(api_error(StatusCode::FORBIDDEN, "Only admins can change templates for all events")),
+    }
+}
+
+async fn require_event(state: &AppState, id: Uuid) -> Result<Event, ApiError> {
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))
+}
+
+async fn require_template(state: &AppState, id: Uuid) -> Result<EmailTemplate, ApiError> {
+    email_templates::find(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch email template: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Email template not found"))
+}
+
+/// Validate a template and check that its event exists
+async fn check_input(state: &AppState, input: &EmailTemplateInput) -> Result<(), ApiError> {
+    validation::validate(input)?;
+    if let Some(event_id) = input.event_id {
+        require_event(state, event_id).await?;
+    }
+    Ok(())
+}
+
+fn save_error(e: sqlx::Error) -> ApiError {
+    if let Some(db_error) = e.as_database_error() {
+        if db_error.message().contains("UNIQUE constraint failed") {
+            return api_error(
+                StatusCode::CONFLICT,
+                "A template of this kind already exists for this event",
+            );
+        }
+    }
+    tracing::error!("Failed to save email template: {}", e);
+    internal_error()
+}
+
+/// Email templates, optionally limited to those used for one event.
+/// Organizers see those of their own events and those for all events.
+pub async fn list_templates(
+    State(state): State<AppState>,
+    Query(query): Query<TemplateQuery>,
+    author: EventAuthor,
+) -> Result<Json<Vec<EmailTemplate>>, ApiError> {
+    if let Some(event_id) = query.event_id {
+        author.ensure_owns(&state.db_pool, event_id).await?;
+    }
+
+    let templates = email_templates::list(&state.db_pool, query.event_id, author.organizer_id()).await.map_err(|e| {
+        tracing::error!("Failed to fetch email templates: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(templates))
+}
+
+pub async fn create_template(
+    State(state): State<AppState>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<EmailTemplateInput>,
+) -> Result<(StatusCode, Json<EmailTemplate>), ApiError> {
+    check_input(&state, &payload).await?;
+    ensure_owns_template(&state, &author, payload.event_id).await?;
+
+    let now = state.clock.now();
+    let template = email_templates::insert(&state.db_pool, state.ids.generate(now), &payload, now)
+        .await
+        .map_err(save_error)?;
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+pub async fn get_template(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<EmailTemplate>, ApiError> {
+    let template = require_template(&state, id).await?;
+    if let Some(event_id) = template.event_id {
+        author.ensure_owns(&state.db_pool, event_id).await?;
+    }
+    Ok(Json(template))
+}
+
+pub async fn update_template(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<EmailTemplateInput>,
+) -> Result<Json<EmailTemplate>, ApiError> {
+    check_input(&state, &payload).await?;
+    let template = require_template(&state, id).await?;
+    ensure_owns_template(&state, &author, template.event_id).await?;
+    ensure_owns_template(&state, &author, payload.event_id).await?;
+
+    email_templates::update(&state.db_pool, id, &payload, state.clock.now())
+        .await
+        .map_err(save_error)?
+        .map(Json)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Email template not found"))
+}
+
+pub async fn delete_template(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    let template = require_template(&state, id).await?;
+    ensure_owns_template(&state, &author, template.event_id).await?;
+
+    let deleted = email_templates::delete(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to delete email template: {}", e);
+        internal_error()
+    })?;
+    if !deleted {
+        return Err(api_error(StatusCode::NOT_FOUND, "Email template not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Render a subject and body, saved or not, for an event
+pub async fn preview_template(
+    State(state): State<AppState>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<PreviewTemplate>,
+) -> Result<Json<RenderedMail>, ApiError> {
+    validation::validate(&payload)?;
+    author.ensure_owns(&state.db_pool, payload.event_id).await?;
+    let event = require_event(&state, payload.event_id).await?;
+
+    let participant = match payload.participant_id {
+        Some(participant_id) => Some(
+            fetch_participants(&state.db_pool, event.id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to fetch participants: {}", e);
+                    internal_error()
+                })?
+                .into_iter()
+                .find(|p| p.id == participant_id)
+                .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Participant not found"))?,
+        ),
+        None => None,
+    };
+    let values = match &participant {
+        Some(participant) => Values::new(&event, participant),
+        None => Values {
+            name: "Jane Doe",
+            email: "jane.doe@example.com",
+            event: &event,
+        },
+    };
+
+    Ok(Json(RenderedMail {
+        subject: email_templates::render(&payload.subject, &values),
+        body: email_templates::render(&payload.body, &values),
+    }))
+}
+
+fn announcement_mail(template: &EmailTemplate, event: &Event, participant: &Participant) -> OutgoingMail {
+    let rendered = template.render(&Values::new(event, participant));
+    OutgoingMail {
+        to_name: participant.name.clone(),
+        to_address: participant.email.clone(),
+        subject: rendered.subject,
+        text: rendered.body,
+        attachments: Vec::new(),
+    }
+}
+
+/// Mail an announcement template to everyone holding a place at an event
+pub async fn send_announcement(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<SendAnnouncement>,
+) -> Result<(StatusCode, Json<AnnouncementSent>), ApiError> {
+    author.ensure_owns(&state.db_pool, event_id).await?;
+    let event = require_event(&state, event_id).await?;
+
+    let template = require_template(&state, payload.template_id).await?;
+    if template.kind != TemplateKind::Announcement {
+        return Err(validation::field_error(
+            "template_id",
+            "wrong_kind",
+            "must be an announcement template".to_string(),
+        ));
+    }
+    if template.event_id.is_some_and(|id| id != event_id) {
+        return Err(validation::field_error(
+            "template_id",
+            "wrong_event",
+            "belongs to another event".to_string(),
+        ));
+    }
+
+    let mails: Vec<OutgoingMail> = fetch_participants(&state.db_pool, event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participants: {}", e);
+            internal_error()
+        })?
+        .iter()
+        .filter(|p| p.status.holds_place())
+        .map(|p| announcement_mail(&template, &event, p))
+        .collect();
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "announcement.send",
+        "event",
+        &event_id.to_string(),
+        &author.actor(),
+        &json!({ "template_id": template.id, "recipients": mails.len() }),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to audit announcement: {}", e);
+    }
+
+    let recipients = mails.len();
+    let state = state.clone();
+    tokio::spawn(async move {
+        for mail in mails {
+            if let Err(e) = mail_failures::deliver(&state, &mail).await {
+                tracing::error!("Failed to mail announcement to {}: {}", mail.to_address, e);
+            }
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(AnnouncementSent { recipients })))
+}
+
+/// Queue a mail to the participants of an event with the given statuses;
+/// the report tracks each send
+pub async fn send_bulk_mail(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<SendBulkMail>,
+) -> Result<(StatusCode, Json<BulkMailReport>), ApiError> {
+    author.ensure_owns(&state.db_pool, event_id).await?;
+    validation::validate(&payload)?;
+    let event = require_event(&state, event_id).await?;
+
+    let participants = fetch_participants(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch participants: {}", e);
+        internal_error()
+    })?;
+
+    let now = state.clock.now();
+    let actor = author.actor();
+    let mail = BulkMail {
+        id: state.ids.generate(now),
+        event: &event,
+        subject: &payload.subject,
+        body: &payload.body,
+        statuses: &payload.statuses,
+        requested_by: &actor,
+    };
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+    let recipients = bulk_mail::enqueue(&mut tx, &mail, &participants, now).await.map_err(|e| {
+        tracing::error!("Failed to queue bulk mail: {}", e);
+        internal_error()
+    })?;
+    audit::record(
+        &mut *tx,
+        "participants.email",
+        "event",
+        &event_id.to_string(),
+        &actor,
+        &json!({ "bulk_mail_id": mail.id, "statuses": payload.statuses, "recipients": recipients }),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    let report = load_bulk_mail(&state, event_id, mail.id).await?;
+    Ok((StatusCode::ACCEPTED, Json(report)))
+}
+
+async fn load_bulk_mail(state: &AppState, event_id: Uuid, id: Uuid) -> Result<BulkMailReport, ApiError> {
+    bulk_mail::report(&state.db_pool, event_id, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch bulk mail: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Bulk mail not found"))
+}
+
+/// Progress of a bulk mail, with the outcome of every send
+pub async fn get_bulk_mail(
+    State(state): State<AppState>,
+    Path((event_id, id)): Path<(Uuid, Uuid)>,
+    author: EventAuthor,
+) -> Result<Json<BulkMailReport>, ApiError> {
+    author.ensure_owns(&state.db_pool, event_id).await?;
+    Ok(Json(load_bulk_mail(&state, event_id, id).await?))
+}
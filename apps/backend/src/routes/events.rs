@@ -1,34 +1,220 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::db;
-use crate::models::{Event, CreateEvent};
+use crate::audit;
+use crate::cache::{self, CachedJson};
+use crate::db::DbPool;
+use crate::auth::RequireAdmin;
+use crate::domain_events::{self, DomainEvent};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::validation;
+use crate::models::{Drafts, Event, EVENT_COLUMNS, CreateEvent, EventScope, EventStatus, Participant};
+use crate::nearby;
+use crate::organizers::EventAuthor;
+use crate::popularity::{self, TrendingEvent};
+use crate::recurrence;
+use crate::routes::participants;
+use crate::settings;
+use crate::slug;
+use crate::tags;
 
 // Type alias for our app state
 type AppState = crate::AppState;
 
-/// List all events
+/// Reject mutations of a frozen event unless the caller is an admin.
+/// Missing events pass so the caller can report its own 404.
+pub(crate) async fn ensure_not_frozen<'e, E>(
+    executor: E,
+    event_id: Uuid,
+    is_admin: bool,
+) -> Result<(), ApiError>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    if is_admin {
+        return Ok(());
+    }
+
+    let frozen = sqlx::query_scalar::<_, bool>("SELECT frozen FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check event freeze: {}", e);
+            internal_error()
+        })?;
+
+    if frozen == Some(true) {
+        return Err(api_error(StatusCode::LOCKED, "Event is frozen"));
+    }
+
+    Ok(())
+}
+
+/// Reject participant changes on a completed event unless the caller is an
+/// admin. Missing events pass so the caller can report its own 404.
+pub(crate) async fn ensure_not_completed<'e, E>(
+    executor: E,
+    event_id: Uuid,
+    is_admin: bool,
+) -> Result<(), ApiError>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    if is_admin {
+        return Ok(());
+    }
+
+    let status = sqlx::query_scalar::<_, EventStatus>("SELECT status FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check event status: {}", e);
+            internal_error()
+        })?;
+
+    if status == Some(EventStatus::Completed) {
+        return Err(api_error(StatusCode::CONFLICT, "Event has ended; participants can no longer be changed"));
+    }
+
+    Ok(())
+}
+
+/// Events of `scope` as of `now`, in its order, straight from the database;
+/// drafts only as far as `drafts` allows, and only those tagged `tag` if given
+pub async fn fetch_events(
+    pool: &DbPool,
+    drafts: Drafts,
+    scope: EventScope,
+    tag: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<Event>, sqlx::Error> {
+    sqlx::query_as::<_, Event>(&format!(
+        "SELECT {}
+         FROM events 
+         WHERE (status != 'draft' OR ?1 OR organizer_id = ?2)
+           AND (?3 IS NULL OR EXISTS (
+               SELECT 1 FROM event_tags et JOIN tags t ON t.id = et.tag_id WHERE et.event_id = events.id AND t.name = ?3
+           ))
+           AND CASE ?4 WHEN 'upcoming' THEN end_time > ?5 WHEN 'past' THEN end_time <= ?5 ELSE 1 END
+         ORDER BY CASE WHEN ?4 = 'upcoming' THEN start_time END ASC, start_time DESC",
+        EVENT_COLUMNS
+    ))
+    .bind(drafts.all())
+    .bind(drafts.owner())
+    .bind(tag)
+    .bind(scope.as_str())
+    .bind(now)
+    .fetch_all(pool)
+    .await
+}
+
+/// One event straight from the database
+pub async fn fetch_event(pool: &DbPool, id: Uuid) -> Result<Option<Event>, sqlx::Error> {
+    sqlx::query_as::<_, Event>(&format!(
+        "SELECT {}
+         FROM events 
+         WHERE id = ?",
+        EVENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListEventsQuery {
+    /// Search center as "lat,lng"
+    pub near: Option<String>,
+    /// Search radius around `near`, default 25 km
+    pub radius_km: Option<f64>,
+    /// Only events with this tag
+    pub tag: Option<String>,
+    /// Defaults to the configured `EventListSettings::default_scope`
+    pub scope: Option<EventScope>,
+}
+
+/// Cache key of the single cached event list settings
+const SETTINGS_CACHE_KEY: &str = "current";
+
+/// Scope of event lists that do not ask for one, as configured
+pub async fn default_scope(state: &AppState) -> EventScope {
+    if let Some(settings) = state.cache.event_list_settings.get(SETTINGS_CACHE_KEY).await {
+        return settings.default_scope;
+    }
+
+    match settings::event_list(&state.db_pool).await {
+        Ok(settings) => {
+            state.cache.event_list_settings.insert(SETTINGS_CACHE_KEY.to_string(), settings).await;
+            settings.default_scope
+        }
+        Err(e) => {
+            tracing::error!("Failed to load event list settings: {}", e);
+            EventScope::default()
+        }
+    }
+}
+
+/// List events by `?scope=upcoming|past|all` (upcoming, soonest first,
+/// unless configured otherwise), or with `?near=lat,lng` all those within
+/// `radius_km`, nearest first and with their `distance_km`; `?tag=`
+/// narrows either to events with that tag. Lists without `near` carry an
+/// ETag and are answered with 304 when `If-None-Match` still matches; they
+/// are cached per scope, so an event crossing into the past may stay listed
+/// as upcoming for up to the cache TTL. Drafts are listed to their
+/// organizers and admins only.
 pub async fn list_events(
     State(state): State<AppState>,
-) -> Result<Json<Vec<Event>>, (StatusCode, Json<serde_json::Value>)> {
+    Query(query): Query<ListEventsQuery>,
+    author: Option<EventAuthor>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let drafts = Drafts::visible_to(author.as_ref());
+    let tag = query.tag.as_deref().map(|tag| tag.trim().to_lowercase());
+
+    if let Some(near) = query.near.as_deref() {
+        let center = nearby::Point::parse(near).ok_or_else(|| {
+            validation::field_error("near", "invalid_point", "must be \"lat,lng\" in degrees".to_string())
+        })?;
+        let radius_km = query.radius_km.unwrap_or(nearby::DEFAULT_RADIUS_KM);
+        if !(radius_km > 0.0 && radius_km <= nearby::MAX_RADIUS_KM) {
+            return Err(validation::field_error(
+                "radius_km",
+                "out_of_range",
+                format!("must be greater than 0 and at most {}", nearby::MAX_RADIUS_KM),
+            ));
+        }
+
+        let events = nearby::events_near(&state.db_pool, center, radius_km, drafts, tag.as_deref()).await.map_err(|e| {
+            tracing::error!("Failed to search events near {}: {}", near, e);
+            internal_error()
+        })?;
+        return Ok(Json(events).into_response());
+    }
+
+    let scope = match query.scope {
+        Some(scope) => scope,
+        None => default_scope(&state).await,
+    };
+
     // Check cache first
-    if let Some(events) = state.cache.events_list.get("all").await {
-        return Ok(Json(events));
+    let key = cache::events_list_key(drafts, scope, tag.as_deref());
+    if let Some(cached) = state.cache.events_list.get(&key).await {
+        return Ok(cached.respond(&headers));
     }
 
-    let events = sqlx::query_as::<_, Event>(
-        "SELECT id, title, description, start_time, end_time, location, max_participants, created_at, updated_at 
-         FROM events 
-         ORDER BY start_time DESC"
-    )
-    .fetch_all(&state.db_pool)
-    .await
-    .map_err(|e| {
+    let events = fetch_events(&state.db_pool, drafts, scope, tag.as_deref(), state.clock.now()).await.map_err(|e| {
         tracing::error!("Failed to fetch events: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -36,33 +222,31 @@ pub async fn list_events(
         )
     })?;
 
+    let cached = CachedJson::new(&events).map_err(|e| {
+        tracing::error!("Failed to serialize events: {}", e);
+        internal_error()
+    })?;
+
     // Populate cache
-    state.cache.events_list.insert("all".to_string(), events.clone()).await;
+    state.cache.events_list.insert(key, cached.clone()).await;
 
-    Ok(Json(events))
+    Ok(cached.respond(&headers))
 }
 
 /// Get a single event by ID
 pub async fn get_event(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Event>, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<Json<Event>, ApiError> {
     let id_str = id.to_string();
 
     // Check cache first
     if let Some(event) = state.cache.event.get(&id_str).await {
+        state.views.record(id);
         return Ok(Json(event));
     }
 
-    let event = sqlx::query_as::<_, Event>(
-        "SELECT id, title, description, start_time, end_time, location, max_participants, created_at, updated_at 
-         FROM events 
-         WHERE id = ?"
-    )
-    .bind(id)
-    .fetch_optional(&state.db_pool)
-    .await
-    .map_err(|e| {
+    let event = fetch_event(&state.db_pool, id).await.map_err(|e| {
         tracing::error!("Database error fetching event: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -78,56 +262,205 @@ pub async fn get_event(
 
     // Populate cache
     state.cache.event.insert(id_str, event.clone()).await;
+    state.views.record(id);
 
     Ok(Json(event))
 }
 
-/// Create a new event
-pub async fn create_event(
+#[derive(Debug, Deserialize, Validate)]
+pub struct TrendingQuery {
+    #[serde(default = "default_trending_limit")]
+    #[validate(range(min = 1, max = 50, code = "out_of_range", message = "must be between 1 and 50"))]
+    pub limit: i64,
+    /// Hours to look back over
+    #[serde(default = "default_trending_window")]
+    #[validate(range(
+        min = 1,
+        max = popularity::VIEW_RETENTION_HOURS,
+        code = "out_of_range",
+        message = "must be between 1 and 168"
+    ))]
+    pub window_hours: i64,
+}
+
+fn default_trending_limit() -> i64 {
+    10
+}
+
+fn default_trending_window() -> i64 {
+    24
+}
+
+/// Upcoming events ranked by recent views and registrations, for the
+/// homepage; see `popularity`
+pub async fn trending_events(
     State(state): State<AppState>,
-    Json(payload): Json<CreateEvent>,
-) -> Result<(StatusCode, Json<Event>), (StatusCode, Json<serde_json::Value>)> {
-    if payload.end_time <= payload.start_time {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "end_time must be after start_time" })),
-        ));
-    }
+    Query(query): Query<TrendingQuery>,
+) -> Result<Json<Vec<TrendingEvent>>, ApiError> {
+    validation::validate(&query)?;
 
-    if let Some(max) = payload.max_participants {
-        if max <= 0 {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "max_participants must be greater than 0" })),
-            ));
+    let events = popularity::trending(&state.db_pool, state.clock.now(), query.window_hours, query.limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to rank trending events: {}", e);
+            internal_error()
+        })?;
+
+    Ok(Json(events))
+}
+
+/// Most events one batch request may ask for
+pub const MAX_BATCH_IDS: u64 = 100;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct BatchEventsRequest {
+    #[validate(length(min = 1, max = MAX_BATCH_IDS, code = "out_of_range", message = "must list 1 to 100 ids"))]
+    pub ids: Vec<Uuid>,
+}
+
+/// Outcome for one requested id
+#[derive(Debug, Serialize)]
+pub struct BatchEventResult {
+    pub id: Uuid,
+    pub found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Event>,
+}
+
+/// One result per distinct requested id, in request order
+#[derive(Debug, Serialize)]
+pub struct BatchEvents {
+    pub results: Vec<BatchEventResult>,
+}
+
+/// Several events in one round-trip, e.g. for a participant's
+/// registrations. Cached events are served from the single-event cache;
+/// the rest are loaded with one query and cached in turn.
+pub async fn batch_get_events(
+    State(state): State<AppState>,
+    JsonBody(payload): JsonBody<BatchEventsRequest>,
+) -> Result<Json<BatchEvents>, ApiError> {
+    validation::validate(&payload)?;
+
+    let mut ids = payload.ids;
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    let mut found = std::collections::HashMap::new();
+    let mut missing = Vec::new();
+    for id in &ids {
+        match state.cache.event.get(&id.to_string()).await {
+            Some(event) => {
+                found.insert(*id, event);
+            }
+            None => missing.push(*id),
         }
     }
 
-    if payload.title.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "title is required" })),
-        ));
+    if !missing.is_empty() {
+        let sql = format!(
+            "SELECT {}
+             FROM events
+             WHERE id IN ({})",
+            EVENT_COLUMNS,
+            vec!["?"; missing.len()].join(", ")
+        );
+        let mut query = sqlx::query_as::<_, Event>(&sql);
+        for id in &missing {
+            query = query.bind(id);
+        }
+        let events = query.fetch_all(&state.db_pool).await.map_err(|e| {
+            tracing::error!("Database error fetching events: {}", e);
+            internal_error()
+        })?;
+
+        for event in events {
+            state.cache.event.insert(event.id.to_string(), event.clone()).await;
+            found.insert(event.id, event);
+        }
     }
 
-    let id = Uuid::new_v4();
-    let now = chrono::Utc::now();
+    let results = ids
+        .into_iter()
+        .map(|id| {
+            let event = found.remove(&id);
+            BatchEventResult { id, found: event.is_some(), event }
+        })
+        .collect();
 
-    let event = sqlx::query_as::<_, Event>(
-        "INSERT INTO events (id, title, description, start_time, end_time, location, max_participants, created_at, updated_at) 
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) 
-         RETURNING id, title, description, start_time, end_time, location, max_participants, created_at, updated_at"
-    )
+    Ok(Json(BatchEvents { results }))
+}
+
+/// Get a single event by its slug, through the same cache as `get_event`
+pub async fn get_event_by_slug(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Event>, ApiError> {
+    let id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM events WHERE slug = ?")
+        .bind(slug.to_lowercase())
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error resolving event slug: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    get_event(State(state), Path(id)).await
+}
+
+/// Create a new event as a draft; see `publish_event`
+pub async fn create_event(
+    State(state): State<AppState>,
+    author: EventAuthor,
+    JsonBody(mut payload): JsonBody<CreateEvent>,
+) -> Result<(StatusCode, Json<Event>), ApiError> {
+    let now = state.clock.now();
+    validation::normalize_event_times(&mut payload, &state.config.date_bounds, now, None);
+    validation::normalize_tags(&mut payload.tags);
+    validation::validate_event(&payload, &state.config, now, author.is_admin(), None)?;
+
+    let id = state.ids.generate(now);
+    let trig = nearby::trig_columns(payload.latitude, payload.longitude);
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    let slug = slug::unique_slug(&mut tx, &payload.title).await.map_err(|e| {
+        tracing::error!("Failed to pick event slug: {}", e);
+        internal_error()
+    })?;
+
+    let mut event = sqlx::query_as::<_, Event>(&format!(
+        "INSERT INTO events (id, title, description, start_time, end_time, location, max_participants, status, email_policy, participant_visibility,
+                             latitude, longitude, geo_sin_lat, geo_cos_lat, geo_sin_lng, geo_cos_lng, min_age, slug, organizer_id, created_at, updated_at) 
+         VALUES (?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) 
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
     .bind(id)
     .bind(&payload.title)
     .bind(&payload.description)
-    .bind(&payload.start_time)
-    .bind(&payload.end_time)
+    .bind(payload.start_time)
+    .bind(payload.end_time)
     .bind(&payload.location)
-    .bind(&payload.max_participants)
+    .bind(payload.max_participants)
+    .bind(payload.email_policy)
+    .bind(payload.participant_visibility)
+    .bind(payload.latitude)
+    .bind(payload.longitude)
+    .bind(trig.map(|t| t.sin_lat))
+    .bind(trig.map(|t| t.cos_lat))
+    .bind(trig.map(|t| t.sin_lng))
+    .bind(trig.map(|t| t.cos_lng))
+    .bind(payload.min_age)
+    .bind(slug)
+    .bind(author.organizer_id())
     .bind(now)
     .bind(now)
-    .fetch_one(&state.db_pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
         if let Some(db_error) = e.as_database_error() {
@@ -137,6 +470,9 @@ pub async fn create_event(
                     Json(json!({ "error": "Invalid event values" })),
                 );
             }
+            if db_error.message().contains("UNIQUE constraint failed") {
+                return api_error(StatusCode::CONFLICT, "An event with this title was created concurrently; retry");
+            }
         }
         tracing::error!("Failed to create event: {}", e);
         (
@@ -145,23 +481,18 @@ pub async fn create_event(
         )
     })?;
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_events().await;
-    let notification_payload = json!({
-        "operation": "INSERT",
-        "table": "events",
-        "id": event.id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "event_changes",
-        &notification_payload,
-    )
-    .await
-    {
-        tracing::error!("Failed to insert event notification: {}", e);
-    }
+    tags::set_event_tags(&mut tx, id, &payload.tags, now).await.map_err(|e| {
+        tracing::error!("Failed to tag event: {}", e);
+        internal_error()
+    })?;
+    event.tags = payload.tags;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(&state, DomainEvent::EventCreated { event: event.clone() }).await;
 
     Ok((StatusCode::CREATED, Json(event)))
 }
@@ -170,48 +501,78 @@ pub async fn create_event(
 pub async fn update_event(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<CreateEvent>,
-) -> Result<Json<Event>, (StatusCode, Json<serde_json::Value>)> {
-    if payload.end_time <= payload.start_time {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "end_time must be after start_time" })),
-        ));
-    }
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<CreateEvent>,
+) -> Result<Json<Event>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    apply_update(&state, id, author.is_admin(), payload).await.map(Json)
+}
 
-    if let Some(max) = payload.max_participants {
-        if max <= 0 {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "max_participants must be greater than 0" })),
-            ));
-        }
-    }
+/// Validate and store new values for an event, for callers that have
+/// already checked who may change it
+pub(crate) async fn apply_update(
+    state: &AppState,
+    id: Uuid,
+    is_admin: bool,
+    mut payload: CreateEvent,
+) -> Result<Event, ApiError> {
+    let now = state.clock.now();
+    let previous_start = if state.config.date_bounds.reject_past {
+        sqlx::query_scalar::<_, DateTime<Utc>>("SELECT start_time FROM events WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch event start: {}", e);
+                internal_error()
+            })?
+    } else {
+        None
+    };
+    validation::normalize_event_times(&mut payload, &state.config.date_bounds, now, previous_start);
+    validation::normalize_tags(&mut payload.tags);
+    validation::validate_event(&payload, &state.config, now, is_admin, previous_start)?;
+    ensure_not_frozen(&state.db_pool, id, is_admin).await?;
 
-    if payload.title.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "title is required" })),
-        ));
-    }
+    let trig = nearby::trig_columns(payload.latitude, payload.longitude);
 
-    let now = chrono::Utc::now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    // Lists filtered by a tag the event loses must drop it too
+    let previous_tags = tags::event_tags(&mut *tx, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch event tags: {}", e);
+        internal_error()
+    })?;
 
-    let event = sqlx::query_as::<_, Event>(
+    let mut event = sqlx::query_as::<_, Event>(&format!(
         "UPDATE events 
-         SET title = ?, description = ?, start_time = ?, end_time = ?, location = ?, max_participants = ?, updated_at = ?
+         SET title = ?, description = ?, start_time = ?, end_time = ?, location = ?, max_participants = ?, email_policy = ?, participant_visibility = ?,
+             latitude = ?, longitude = ?, geo_sin_lat = ?, geo_cos_lat = ?, geo_sin_lng = ?, geo_cos_lng = ?, min_age = ?, updated_at = ?
          WHERE id = ?
-         RETURNING id, title, description, start_time, end_time, location, max_participants, created_at, updated_at"
-    )
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
     .bind(&payload.title)
     .bind(&payload.description)
-    .bind(&payload.start_time)
-    .bind(&payload.end_time)
+    .bind(payload.start_time)
+    .bind(payload.end_time)
     .bind(&payload.location)
-    .bind(&payload.max_participants)
+    .bind(payload.max_participants)
+    .bind(payload.email_policy)
+    .bind(payload.participant_visibility)
+    .bind(payload.latitude)
+    .bind(payload.longitude)
+    .bind(trig.map(|t| t.sin_lat))
+    .bind(trig.map(|t| t.cos_lat))
+    .bind(trig.map(|t| t.sin_lng))
+    .bind(trig.map(|t| t.cos_lng))
+    .bind(payload.min_age)
     .bind(now)
     .bind(id)
-    .fetch_optional(&state.db_pool)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         if let Some(db_error) = e.as_database_error() {
@@ -235,69 +596,287 @@ pub async fn update_event(
         )
     })?;
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_event(&id.to_string()).await;
-    let notification_payload = json!({
-        "operation": "UPDATE",
-        "table": "events",
-        "id": event.id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "event_changes",
-        &notification_payload,
+    tags::set_event_tags(&mut tx, id, &payload.tags, now).await.map_err(|e| {
+        tracing::error!("Failed to tag event: {}", e);
+        internal_error()
+    })?;
+    event.tags = payload.tags;
+
+    // Existing registrations must satisfy the (possibly changed) policy
+    participants::rekey_participants(&mut tx, id, event.email_policy)
+        .await
+        .map_err(|e| {
+            if let Some(db_error) = e.as_database_error() {
+                if db_error.message().contains("UNIQUE constraint failed") {
+                    return api_error(
+                        StatusCode::CONFLICT,
+                        "Existing registrations share an email; they conflict with this email_policy",
+                    );
+                }
+            }
+            tracing::error!("Failed to apply email policy: {}", e);
+            internal_error()
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    state.cache.invalidate_event_lists(&previous_tags);
+    domain_events::publish(state, DomainEvent::EventUpdated { event: event.clone() }).await;
+
+    Ok(event)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteEventQuery {
+    /// Delete even if participants are registered (cascades to them)
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Delete an event. Refuses with 409 while participants exist unless
+/// `?force=true` is given; forced deletes are recorded in the audit log.
+pub async fn delete_event(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeleteEventQuery>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    author.ensure_owns(&mut *tx, id).await?;
+    ensure_not_frozen(&mut *tx, id, author.is_admin()).await?;
+
+    let participants = sqlx::query_as::<_, Participant>(
+        "SELECT id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata
+         FROM participants
+         WHERE event_id = ?"
     )
+    .bind(id)
+    .fetch_all(&mut *tx)
     .await
-    {
-        tracing::error!("Failed to insert event notification: {}", e);
+    .map_err(|e| {
+        tracing::error!("Failed to fetch participants: {}", e);
+        internal_error()
+    })?;
+
+    if !participants.is_empty() && !query.force {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Event has registered participants; use ?force=true to delete anyway",
+                "participant_count": participants.len()
+            })),
+        ));
     }
 
-    Ok(Json(event))
+    // Read before the delete cascades to them
+    let tags = tags::event_tags(&mut *tx, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch event tags: {}", e);
+        internal_error()
+    })?;
+
+    let mut event = sqlx::query_as::<_, Event>(&format!(
+        "DELETE FROM events WHERE id = ?
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete event: {}", e);
+        internal_error()
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Event not found" })),
+        )
+    })?;
+    event.tags = tags;
+
+    // A deleted occurrence must not reappear from its series' rule
+    if let (Some(series_id), Some(start)) = (event.series_id, event.occurrence_start) {
+        recurrence::exclude(&mut *tx, series_id, start).await.map_err(|e| {
+            tracing::error!("Failed to exclude occurrence: {}", e);
+            internal_error()
+        })?;
+    }
+
+    if !participants.is_empty() {
+        let details = json!({
+            "participant_count": participants.len(),
+            "participants": participants,
+        });
+        audit::record(
+            &mut *tx,
+            "event.force_delete",
+            "event",
+            &id.to_string(),
+            &author.actor(),
+            &details,
+            state.clock.now(),
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record audit entry: {}", e);
+            internal_error()
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(&state, DomainEvent::EventDeleted { event, participants }).await;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// Delete an event
-pub async fn delete_event(
+/// Freeze an event so only admins can register or modify while organizers
+/// reconcile the participant list
+pub async fn freeze_event(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _admin: RequireAdmin,
+) -> Result<Json<Event>, ApiError> {
+    set_frozen(&state, id, true).await.map(Json)
+}
+
+/// Lift a freeze placed by `freeze_event`
+pub async fn unfreeze_event(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
-    let result = sqlx::query("DELETE FROM events WHERE id = ?")
-        .bind(id)
-        .execute(&state.db_pool)
+    _admin: RequireAdmin,
+) -> Result<Json<Event>, ApiError> {
+    set_frozen(&state, id, false).await.map(Json)
+}
+
+async fn set_frozen(state: &AppState, id: Uuid, frozen: bool) -> Result<Event, ApiError> {
+    let now = state.clock.now();
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    let event = sqlx::query_as::<_, Event>(&format!(
+        "UPDATE events
+         SET frozen = ?, updated_at = ?
+         WHERE id = ?
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(frozen)
+    .bind(now)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update event freeze: {}", e);
+        internal_error()
+    })?
+    .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let action = if frozen { "event.freeze" } else { "event.unfreeze" };
+    audit::record(&mut *tx, action, "event", &id.to_string(), audit::actor_label(true), &json!({}), now)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to delete event: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Internal server error" })),
-            )
+            tracing::error!("Failed to record audit entry: {}", e);
+            internal_error()
         })?;
 
-    if result.rows_affected() == 0 {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Event not found" })),
-        ));
-    }
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(state, DomainEvent::EventFreezeChanged { event: event.clone() }).await;
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_event(&id.to_string()).await;
-    state.cache.invalidate_participants().await;
-    let notification_payload = json!({
-        "operation": "DELETE",
-        "table": "events",
-        "id": id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "event_changes",
-        &notification_payload,
+    Ok(event)
+}
+
+/// Publish a draft event, listing it publicly and opening registration.
+/// Publishing a published event returns it unchanged; cancelled events
+/// cannot be published.
+pub async fn publish_event(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<Event>, ApiError> {
+    let now = state.clock.now();
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        internal_error()
+    })?;
+
+    author.ensure_owns(&mut *tx, id).await?;
+    ensure_not_frozen(&mut *tx, id, author.is_admin()).await?;
+
+    let published = sqlx::query_as::<_, Event>(&format!(
+        "UPDATE events
+         SET status = 'published', updated_at = ?
+         WHERE id = ? AND status = 'draft'
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(now)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to publish event: {}", e);
+        internal_error()
+    })?;
+
+    // Not a draft, or missing
+    let Some(event) = published else {
+        if let Err(e) = tx.rollback().await {
+            tracing::warn!("Failed to roll back publish: {}", e);
+        }
+        let event = fetch_event(&state.db_pool, id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch event: {}", e);
+                internal_error()
+            })?
+            .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+        return match event.status {
+            EventStatus::Cancelled => Err(api_error(StatusCode::CONFLICT, "Cancelled events cannot be published")),
+            EventStatus::Draft | EventStatus::Published | EventStatus::Completed => Ok(Json(event)),
+        };
+    };
+
+    audit::record(
+        &mut *tx,
+        "event.publish",
+        "event",
+        &id.to_string(),
+        &author.actor(),
+        &json!({}),
+        now,
     )
     .await
-    {
-        tracing::error!("Failed to insert event notification: {}", e);
-    }
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
 
-    Ok(StatusCode::NO_CONTENT)
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(&state, DomainEvent::EventPublished { event: event.clone() }).await;
+
+    Ok(Json(event))
 }
@@ -0,0 +1,161 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::export_jobs::{self, ExportJob, JobStatus};
+use crate::organizers::EventAuthor;
+use crate::routes::events::fetch_event;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// An export job with its download URL once ready
+#[derive(Debug, Serialize)]
+pub struct ExportJobView {
+    #[serde(flatten)]
+    pub job: ExportJob,
+    pub status_url: String,
+    pub download_url: Option<String>,
+}
+
+impl ExportJobView {
+    fn new(job: ExportJob, secret: &str) -> Self {
+        Self {
+            status_url: format!("/api/exports/{}", job.id),
+            download_url: export_jobs::download_url(secret, &job),
+            job,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    /// Unix time the URL stops working
+    pub expires: i64,
+    pub signature: String,
+}
+
+async fn signing_key(state: &AppState) -> Result<String, ApiError> {
+    export_jobs::signing_key(&state.db_pool, state.config.export_signing_key.as_deref(), state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load export signing key: {}", e);
+            internal_error()
+        })
+}
+
+/// Start writing an event's participants to a file in the background
+pub async fn start_export(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Response, ApiError> {
+    let event = fetch_event(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch event: {}", e);
+        internal_error()
+    })?;
+    if event.is_none() {
+        return Err(api_error(StatusCode::NOT_FOUND, "Event not found"));
+    }
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    let now = state.clock.now();
+
+    let Some(permit) = state.concurrency.try_export_permit() else {
+        return Ok(state.load_shedder.shed_response());
+    };
+    let job = export_jobs::start(&state, event_id, permit).await.map_err(|e| {
+        tracing::error!("Failed to create export job: {}", e);
+        internal_error()
+    })?;
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "export.start",
+        "event",
+        &event_id.to_string(),
+        &author.actor(),
+        &json!({ "job_id": job.id }),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to audit export: {}", e);
+    }
+
+    let key = signing_key(&state).await?;
+    Ok((StatusCode::ACCEPTED, Json(ExportJobView::new(job, &key))).into_response())
+}
+
+/// Status of an export job
+pub async fn get_export(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<ExportJobView>, ApiError> {
+    let job = export_jobs::find(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch export job: {}", e);
+            internal_error()
+        })?
+        .filter(|job| job.expires_at.is_none_or(|expires| expires > state.clock.now()))
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Export not found"))?;
+    author.ensure_owns(&state.db_pool, job.event_id).await?;
+
+    let key = signing_key(&state).await?;
+    Ok(Json(ExportJobView::new(job, &key)))
+}
+
+/// Download an export file; the signed URL is the only credential
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, ApiError> {
+    let key = signing_key(&state).await?;
+    if !export_jobs::verify(&key, id, query.expires, &query.signature) {
+        return Err(api_error(StatusCode::FORBIDDEN, "Invalid download signature"));
+    }
+    if query.expires <= state.clock.now().timestamp() {
+        return Err(api_error(StatusCode::GONE, "Download link expired"));
+    }
+
+    let job = export_jobs::find(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch export job: {}", e);
+        internal_error()
+    })?;
+    if !job.is_some_and(|job| job.status == JobStatus::Ready) {
+        return Err(api_error(StatusCode::GONE, "Export no longer available"));
+    }
+
+    let file = match tokio::fs::File::open(export_jobs::file_path(&state.config.data_dir, id)).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(api_error(StatusCode::GONE, "Export no longer available"));
+        }
+        Err(e) => {
+            tracing::error!("Failed to open export {}: {}", id, e);
+            return Err(internal_error());
+        }
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"participants-{}.ndjson\"", id)),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
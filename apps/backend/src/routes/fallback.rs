@@ -0,0 +1,35 @@
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::error::{api_error, ApiError};
+
+/// Fallback for unknown routes
+pub async fn not_found() -> ApiError {
+    api_error(StatusCode::NOT_FOUND, "Route not found")
+}
+
+/// Give axum's empty 405 responses the same JSON shape as other errors,
+/// keeping the Allow header
+pub async fn method_not_allowed(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+        return response;
+    }
+
+    let allow = response.headers().get(header::ALLOW).cloned();
+    let mut json_response = (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(json!({ "error": "Method not allowed" })),
+    )
+        .into_response();
+    if let Some(allow) = allow {
+        json_response.headers_mut().insert(header::ALLOW, allow);
+    }
+    json_response
+}
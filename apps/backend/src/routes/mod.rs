@@ -1,3 +1,26 @@
+pub mod admin;
+pub mod analytics;
+pub mod audit;
+pub mod capacity_holds;
+pub mod certificates;
+pub mod checkins;
+pub mod confirmations;
+pub mod consents;
+pub mod devices;
+pub mod digest;
+pub mod email_templates;
 pub mod events;
+pub mod exports;
+pub mod fallback;
+pub mod ndjson;
+pub mod organizers;
 pub mod participants;
+pub mod personal_data;
+pub mod referrals;
+pub mod registration_forms;
+pub mod reminders;
+pub mod seating;
+pub mod series;
+pub mod sessions;
 pub mod sse;
+pub mod webhooks;
@@ -0,0 +1,226 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::error::ApiError;
+use crate::models::{Event, EVENT_COLUMNS, Participant, RegistrationSource};
+use crate::organizers::EventAuthor;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Rows buffered between the database stream and the client; a slow client
+/// blocks the producer once this fills up
+const BUFFER_ROWS: usize = 64;
+
+#[derive(Debug, Deserialize)]
+pub struct ParticipantStreamQuery {
+    pub event_id: Option<Uuid>,
+    /// Only registrations made with this client
+    pub source: Option<RegistrationSource>,
+}
+
+/// Consent and seat columns joined onto an exported participant; consent
+/// is all NULL for registrations made before it was recorded
+#[derive(Debug, FromRow)]
+struct ParticipantExportRow {
+    #[sqlx(flatten)]
+    participant: Participant,
+    privacy_policy_version: Option<String>,
+    privacy_accepted_at: Option<DateTime<Utc>>,
+    marketing_opt_in: Option<bool>,
+    marketing_updated_at: Option<DateTime<Utc>>,
+    source: Option<RegistrationSource>,
+    referrer_code: Option<String>,
+    utm_campaign: Option<String>,
+    seat_section: Option<String>,
+    seat_number: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedConsent {
+    privacy_policy_version: String,
+    privacy_accepted_at: DateTime<Utc>,
+    marketing_opt_in: bool,
+    marketing_updated_at: DateTime<Utc>,
+}
+
+/// The seat of an exported participant; see `seating`
+#[derive(Debug, Serialize)]
+struct ExportedSeat {
+    section: String,
+    seat: i64,
+}
+
+/// A participant as exported, with consent, client context and seat
+#[derive(Debug, Serialize)]
+pub(crate) struct ParticipantExport {
+    #[serde(flatten)]
+    participant: Participant,
+    consent: Option<ExportedConsent>,
+    source: Option<RegistrationSource>,
+    referrer_code: Option<String>,
+    utm_campaign: Option<String>,
+    seat: Option<ExportedSeat>,
+}
+
+impl From<ParticipantExportRow> for ParticipantExport {
+    fn from(row: ParticipantExportRow) -> Self {
+        let consent = match (
+            row.privacy_policy_version,
+            row.privacy_accepted_at,
+            row.marketing_opt_in,
+            row.marketing_updated_at,
+        ) {
+            (Some(privacy_policy_version), Some(privacy_accepted_at), Some(marketing_opt_in), Some(marketing_updated_at)) => {
+                Some(ExportedConsent {
+                    privacy_policy_version,
+                    privacy_accepted_at,
+                    marketing_opt_in,
+                    marketing_updated_at,
+                })
+            }
+            _ => None,
+        };
+
+        Self {
+            participant: row.participant,
+            consent,
+            source: row.source,
+            referrer_code: row.referrer_code,
+            utm_campaign: row.utm_campaign,
+            seat: row
+                .seat_section
+                .zip(row.seat_number)
+                .map(|(section, seat)| ExportedSeat { section, seat }),
+        }
+    }
+}
+
+/// Forward rows as newline-delimited JSON until the stream ends or the
+/// client disconnects
+async fn forward_rows<T: Serialize>(
+    mut rows: BoxStream<'_, Result<T, sqlx::Error>>,
+    tx: mpsc::Sender<Result<Bytes, std::io::Error>>,
+) {
+    while let Some(row) = rows.next().await {
+        let chunk = match row {
+            Ok(row) => match serde_json::to_vec(&row) {
+                Ok(mut line) => {
+                    line.push(b'\n');
+                    Ok(Bytes::from(line))
+                }
+                Err(e) => {
+                    tracing::error!("Failed to serialize NDJSON row: {}", e);
+                    Err(std::io::Error::other(e))
+                }
+            },
+            Err(e) => {
+                tracing::error!("Failed to stream rows: {}", e);
+                Err(std::io::Error::other(e))
+            }
+        };
+
+        let failed = chunk.is_err();
+        if tx.send(chunk).await.is_err() || failed {
+            // Client went away, or the error has been passed on to abort the body
+            break;
+        }
+    }
+}
+
+fn ndjson_response(rx: mpsc::Receiver<Result<Bytes, std::io::Error>>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+/// Stream all events as NDJSON, ordered by start time. The export slot is
+/// held by the producer task, i.e. for as long as the connection is in use.
+pub async fn stream_events(State(state): State<AppState>) -> Response {
+    let Some(permit) = state.concurrency.try_export_permit() else {
+        return state.load_shedder.shed_response();
+    };
+    let (tx, rx) = mpsc::channel(BUFFER_ROWS);
+    let pool = state.db_pool.clone();
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let sql = format!("SELECT {} FROM events ORDER BY start_time ASC", EVENT_COLUMNS);
+        let rows = sqlx::query_as::<_, Event>(&sql).fetch(&pool);
+        forward_rows(rows, tx).await;
+    });
+
+    ndjson_response(rx)
+}
+
+/// Exported participants in registration order, optionally of one event,
+/// source or organizer's events
+pub(crate) fn participant_rows(
+    pool: &DbPool,
+    event_id: Option<Uuid>,
+    source: Option<RegistrationSource>,
+    owner: Option<Uuid>,
+) -> BoxStream<'_, Result<ParticipantExport, sqlx::Error>> {
+    sqlx::query_as::<_, ParticipantExportRow>(
+        "SELECT p.id, p.event_id, p.name, p.email, p.status, p.registered_at, p.updated_at, p.checked_in_at, p.metadata,
+                c.privacy_policy_version, c.privacy_accepted_at, c.marketing_opt_in, c.marketing_updated_at,
+                p.source, p.referrer_code, p.utm_campaign,
+                s.section AS seat_section, s.seat AS seat_number
+         FROM participants p
+         LEFT JOIN participant_consents c ON c.participant_id = p.id
+         LEFT JOIN seat_assignments s
+           ON s.participant_id = p.id AND p.status IN ('registered', 'confirmed', 'checked_in')
+         WHERE (?1 IS NULL OR p.event_id = ?1)
+           AND (?2 IS NULL OR p.source = ?2)
+           AND (?3 IS NULL OR p.event_id IN (SELECT id FROM events WHERE organizer_id = ?3))
+         ORDER BY p.registered_at ASC"
+    )
+    .bind(event_id)
+    .bind(source)
+    .bind(owner)
+    .fetch(pool)
+    .map(|row| row.map(ParticipantExport::from))
+    .boxed()
+}
+
+/// Stream participants with their consent, client context and seat as NDJSON,
+/// optionally for a single event or source. Emails and consent are in every
+/// row, so like other exports this is for an event's organizer only;
+/// without an event, organizers get the participants of the events they own.
+pub async fn stream_participants(
+    State(state): State<AppState>,
+    Query(query): Query<ParticipantStreamQuery>,
+    author: EventAuthor,
+) -> Result<Response, ApiError> {
+    if let Some(event_id) = query.event_id {
+        author.ensure_owns(&state.db_pool, event_id).await?;
+    }
+
+    let Some(permit) = state.concurrency.try_export_permit() else {
+        return Ok(state.load_shedder.shed_response());
+    };
+    let (tx, rx) = mpsc::channel(BUFFER_ROWS);
+    let (pool, owner) = (state.db_pool.clone(), author.organizer_id());
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let rows = participant_rows(&pool, query.event_id, query.source, owner);
+        forward_rows(rows, tx).await;
+    });
+
+    Ok(ndjson_response(rx))
+}
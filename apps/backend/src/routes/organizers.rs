@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::calendar::{self, Calendar, CalendarQuery};
+use crate::email;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::organizers::{self, CurrentOrganizer, EventAuthor, IssuedToken, Login, Organizer, Signup};
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Sign and return a token for `organizer`
+async fn issue(state: &AppState, organizer: Organizer) -> Result<IssuedToken, ApiError> {
+    let now = state.clock.now();
+    let key = organizers::signing_key(&state.db_pool, state.config.jwt_secret.as_deref(), now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load organizer token key: {}", e);
+            internal_error()
+        })?;
+    let expires_at = now + state.config.organizer_token_ttl;
+
+    Ok(IssuedToken {
+        token: organizers::issue_token(&key, organizer.id, now, expires_at),
+        token_type: "Bearer",
+        expires_at,
+        organizer,
+    })
+}
+
+/// Create an organizer account and sign it in
+pub async fn signup(
+    State(state): State<AppState>,
+    JsonBody(mut payload): JsonBody<Signup>,
+) -> Result<(StatusCode, Json<IssuedToken>), ApiError> {
+    payload.email = email::normalize(&payload.email);
+    payload.name = payload.name.trim().to_string();
+    validation::validate_with_limits(&payload, &state.config.field_limits)?;
+
+    let now = state.clock.now();
+    // Hashing is deliberately slow; keep it off the async workers
+    let password = payload.password.clone();
+    let password_hash = tokio::task::spawn_blocking(move || organizers::hash_password(&password))
+        .await
+        .map_err(|e| {
+            tracing::error!("Password hashing failed: {}", e);
+            internal_error()
+        })?;
+
+    let organizer = organizers::create(
+        &state.db_pool,
+        state.ids.generate(now),
+        &payload.name,
+        &payload.email,
+        &password_hash,
+        now,
+    )
+    .await
+    .map_err(|e| {
+        if let Some(db_error) = e.as_database_error() {
+            if db_error.message().contains("UNIQUE constraint failed") {
+                return api_error(StatusCode::CONFLICT, "An organizer with this email already exists");
+            }
+        }
+        tracing::error!("Failed to create organizer: {}", e);
+        internal_error()
+    })?;
+    tracing::info!("Organizer {} signed up", organizer.id);
+
+    Ok((StatusCode::CREATED, Json(issue(&state, organizer).await?)))
+}
+
+/// Exchange an organizer's email and password for a token
+pub async fn login(
+    State(state): State<AppState>,
+    JsonBody(payload): JsonBody<Login>,
+) -> Result<Json<IssuedToken>, ApiError> {
+    let organizer = organizers::find_by_email(&state.db_pool, &email::normalize(&payload.email))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load organizer: {}", e);
+            internal_error()
+        })?;
+
+    let stored = organizer.as_ref().map(|o| o.password_hash.clone());
+    let password = payload.password;
+    let valid = tokio::task::spawn_blocking(move || match stored {
+        Some(stored) => organizers::verify_password(&password, &stored),
+        // Unknown emails still cost a hash, so timing does not reveal accounts
+        None => {
+            organizers::hash_password(&password);
+            false
+        }
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Password verification failed: {}", e);
+        internal_error()
+    })?;
+
+    match organizer {
+        Some(organizer) if valid => Ok(Json(issue(&state, organizer).await?)),
+        _ => Err(api_error(StatusCode::UNAUTHORIZED, "Invalid email or password")),
+    }
+}
+
+/// The signed-in organizer
+pub async fn me(CurrentOrganizer(organizer): CurrentOrganizer) -> Json<Organizer> {
+    Json(organizer)
+}
+
+/// Busy and free blocks of an organizer over a window, for scheduling a new
+/// event; for the organizer themselves and admins
+pub async fn get_availability(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<CalendarQuery>,
+    author: EventAuthor,
+) -> Result<Json<Calendar>, ApiError> {
+    // Checked as the impersonated organizer while impersonating
+    if author.organizer_id().is_some_and(|organizer_id| organizer_id != id) {
+        return Err(api_error(StatusCode::FORBIDDEN, "Calendar belongs to another organizer"));
+    }
+    let (from, to) = calendar::window(&query, state.clock.now()).map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+
+    organizers::find(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch organizer: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Organizer not found"))?;
+
+    let calendar = calendar::build(&state.db_pool, id, from, to).await.map_err(|e| {
+        tracing::error!("Failed to build organizer calendar: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(calendar))
+}
@@ -1,57 +1,548 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sqlx::{Sqlite, SqliteConnection};
 use uuid::Uuid;
+use validator::{Validate, ValidationErrors};
 
-use crate::db;
-use crate::models::{Participant, CreateParticipant, UpdateParticipantStatus};
+use crate::audit;
+use crate::auth::IsAdmin;
+use crate::cache;
+use crate::cancellation;
+use crate::capacity_holds;
+use crate::consent::{self, Consent};
+use crate::db::DbPool;
+use crate::email;
+use crate::encryption::{self, PiiText};
+use crate::metadata::{self, Metadata};
+use crate::organizers::{self, EventAuthor};
+use crate::domain_events::{self, DomainEvent};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::fuzzy;
+use crate::json::JsonBody;
+use crate::participant_diff::{self, ParticipantDiff};
+use crate::referrals;
+use crate::registration_form;
+use crate::reminders::{self, LeadTime, ReminderPreferences};
+use crate::reputation::Verdict;
+use crate::routes::events::{ensure_not_completed, ensure_not_frozen, fetch_event};
+use crate::validation;
+use crate::visibility::{self, ParticipantView, Viewer};
+use crate::models::{
+    Availability, EmailPolicy, EventStatus, Participant, ParticipantCounts, ParticipantPage, ParticipantStatus, StatusCounts, CreateParticipant,
+    UpdateParticipantStatus,
+};
 
 // Type alias for our app state
 type AppState = crate::AppState;
 
-/// List all participants for an event
-pub async fn list_participants(
-    State(state): State<AppState>,
-    Path(event_id): Path<Uuid>,
-) -> Result<Json<Vec<Participant>>, (StatusCode, Json<serde_json::Value>)> {
-    let key = event_id.to_string();
+/// Most participants one page may hold
+pub const MAX_PAGE_SIZE: u32 = 200;
+/// Longest accepted `search`
+pub const MAX_SEARCH_LEN: u64 = 100;
+/// Response header with the number of participants matching the filters
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
 
-    // Check cache first
-    if let Some(participants) = state.cache.participants.get(&key).await {
-        return Ok(Json(participants));
+#[derive(Debug, Deserialize, Validate)]
+pub struct ParticipantListQuery {
+    /// Typo-tolerant search over names, and emails for organizers;
+    /// matches are returned best first
+    pub fuzzy: Option<String>,
+    /// Only participants with this status
+    pub status: Option<ParticipantStatus>,
+    /// Case-insensitive substring of the name, or of the email for
+    /// organizers
+    #[validate(length(max = MAX_SEARCH_LEN, code = "too_long", message = "must be at most 100 characters"))]
+    pub search: Option<String>,
+    /// Participants per page; all of them when omitted
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "out_of_range", message = "must be between 1 and 200"))]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelRegistrationQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParticipantDiffQuery {
+    pub from: DateTime<Utc>,
+    /// Now when omitted
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Consent as shown to the registrant, including the withdrawal token
+#[derive(Debug, Serialize)]
+pub struct ConsentReceipt {
+    pub privacy_policy_version: String,
+    pub privacy_accepted_at: DateTime<Utc>,
+    pub marketing_opt_in: bool,
+    /// Redeem at `POST /api/consents/:token/withdraw` to revoke marketing consent
+    pub withdrawal_token: String,
+}
+
+impl From<Consent> for ConsentReceipt {
+    fn from(consent: Consent) -> Self {
+        Self {
+            privacy_policy_version: consent.privacy_policy_version,
+            privacy_accepted_at: consent.privacy_accepted_at,
+            marketing_opt_in: consent.marketing_opt_in,
+            withdrawal_token: consent.withdrawal_token,
+        }
+    }
+}
+
+/// Reminder preferences as shown to the registrant, including the token
+#[derive(Debug, Serialize)]
+pub struct ReminderReceipt {
+    pub lead_times: Vec<LeadTime>,
+    /// Use with `/api/reminders/:token` to change the lead times later
+    pub token: String,
+}
+
+impl From<ReminderPreferences> for ReminderReceipt {
+    fn from(preferences: ReminderPreferences) -> Self {
+        Self {
+            lead_times: preferences.lead_times,
+            token: preferences.token,
+        }
+    }
+}
+
+/// Response to a registration: the participant plus the recorded consent
+/// and reminder preferences
+#[derive(Debug, Serialize)]
+pub struct Registration {
+    #[serde(flatten)]
+    pub participant: Participant,
+    pub consent: ConsentReceipt,
+    pub reminders: ReminderReceipt,
+    /// Send as `X-Participant-Token` to see lists visible to participants
+    pub access_token: String,
+    /// Redeem at `POST /api/registrations/cancel?token=` to cancel
+    pub cancellation_token: String,
+    /// Share as `invited_by` to bring friends along
+    pub invite_code: String,
+}
+
+/// Recompute the dedupe keys of an event's participants for `policy`;
+/// fails with a UNIQUE violation if existing registrations break it
+pub async fn rekey_participants(conn: &mut SqliteConnection, event_id: Uuid, policy: EmailPolicy) -> Result<(), sqlx::Error> {
+    let participants = sqlx::query_as::<_, (Uuid, PiiText, PiiText)>(
+        "SELECT id, name, email FROM participants WHERE event_id = ?"
+    )
+    .bind(event_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Cleared first, so keys passing between participants never collide
+    sqlx::query("UPDATE participants SET dedupe_key = NULL WHERE event_id = ?")
+        .bind(event_id)
+        .execute(&mut *conn)
+        .await?;
+    for (id, name, email) in participants {
+        sqlx::query("UPDATE participants SET dedupe_key = ? WHERE id = ?")
+            .bind(policy.dedupe_key(&email.0, &name.0))
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Current registration counts of an event; pass the mutation's
+/// transaction so the counts reflect exactly that change
+pub async fn count_participants<'e, E>(executor: E, event_id: Uuid) -> Result<ParticipantCounts, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, ParticipantCounts>(
+        "SELECT COALESCE(SUM(status IN ('registered', 'confirmed', 'checked_in')), 0) AS registered,
+                COALESCE(SUM(status = 'waitlisted'), 0) AS waitlisted
+         FROM participants
+         WHERE event_id = ?"
+    )
+    .bind(event_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Give the place just freed at `event_id` to its longest-waiting
+/// participant, if the event has a free place and anyone waiting. Run it in
+/// the transaction that frees the place; `freed_by`, the participant who
+/// gave it up, is never promoted.
+pub(crate) async fn promote_from_waitlist(
+    conn: &mut SqliteConnection,
+    event_id: Uuid,
+    freed_by: Option<Uuid>,
+    now: DateTime<Utc>,
+) -> Result<Option<Participant>, sqlx::Error> {
+    sqlx::query_as::<_, Participant>(
+        "UPDATE participants
+         SET status = 'registered', updated_at = ?1
+         WHERE id = (SELECT id FROM participants
+                     WHERE event_id = ?2 AND status = 'waitlisted' AND (?3 IS NULL OR id != ?3)
+                     ORDER BY registered_at, rowid
+                     LIMIT 1)
+           AND ((SELECT max_participants FROM events WHERE id = ?2) IS NULL
+                OR (SELECT count(*) FROM participants
+                    WHERE event_id = ?2 AND status IN ('registered', 'confirmed', 'checked_in'))
+                   < (SELECT max_participants FROM events WHERE id = ?2)
+                     - (SELECT COALESCE(SUM(seats), 0) FROM capacity_holds WHERE event_id = ?2))
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(now)
+    .bind(event_id)
+    .bind(freed_by)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Registrations of an event by status
+pub async fn count_by_status(pool: &DbPool, event_id: Uuid) -> Result<StatusCounts, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (ParticipantStatus, i64)>(
+        "SELECT status, count(*) FROM participants WHERE event_id = ? GROUP BY status"
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut counts = StatusCounts::default();
+    for (status, count) in rows {
+        let slot = match status {
+            ParticipantStatus::Registered => &mut counts.registered,
+            ParticipantStatus::Confirmed => &mut counts.confirmed,
+            ParticipantStatus::Maybe => &mut counts.maybe,
+            ParticipantStatus::Declined => &mut counts.declined,
+            ParticipantStatus::Waitlisted => &mut counts.waitlisted,
+            ParticipantStatus::Cancelled => &mut counts.cancelled,
+            ParticipantStatus::CheckedIn => &mut counts.checked_in,
+        };
+        *slot = count;
     }
+    Ok(counts)
+}
 
-    let participants = sqlx::query_as::<_, Participant>(
-        "SELECT id, event_id, name, email, status, registered_at, updated_at 
+/// An event's participants in registration order, straight from the database
+pub async fn fetch_participants(pool: &DbPool, event_id: Uuid) -> Result<Vec<Participant>, sqlx::Error> {
+    sqlx::query_as::<_, Participant>(
+        "SELECT id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata
          FROM participants 
          WHERE event_id = ? 
          ORDER BY registered_at ASC"
     )
     .bind(event_id)
-    .fetch_all(&state.db_pool)
+    .fetch_all(pool)
     .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch participants: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Internal server error" })),
-        )
+}
+
+/// One participant straight from the database
+pub async fn fetch_participant(pool: &DbPool, id: Uuid) -> Result<Option<Participant>, sqlx::Error> {
+    sqlx::query_as::<_, Participant>(
+        "SELECT id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata
+         FROM participants 
+         WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// A filtered page of an event's participants, oldest registration first.
+/// Names and emails may be sealed (see `encryption`), so a search filters
+/// the event's participants after reading them.
+pub async fn fetch_participant_page(
+    pool: &DbPool,
+    event_id: Uuid,
+    status: Option<&ParticipantStatus>,
+    search: Option<&str>,
+    searches_emails: bool,
+    offset: u32,
+    limit: Option<u32>,
+) -> Result<ParticipantPage, sqlx::Error> {
+    let filter = "WHERE event_id = ?1 AND (?2 IS NULL OR status = ?2)";
+
+    if let Some(search) = search.map(str::to_lowercase) {
+        let matches: Vec<Participant> = sqlx::query_as::<_, Participant>(&format!(
+            "SELECT id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata
+             FROM participants
+             {}
+             ORDER BY registered_at ASC",
+            filter
+        ))
+        .bind(event_id)
+        .bind(status)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter(|p| {
+            p.name.to_lowercase().contains(&search) || (searches_emails && p.email.to_lowercase().contains(&search))
+        })
+        .collect();
+
+        let total = matches.len() as i64;
+        let participants = matches
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect();
+        return Ok(ParticipantPage { participants, total });
+    }
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM participants {}", filter))
+        .bind(event_id)
+        .bind(status)
+        .fetch_one(pool)
+        .await?;
+
+    let participants = sqlx::query_as::<_, Participant>(&format!(
+        "SELECT id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata
+         FROM participants
+         {}
+         ORDER BY registered_at ASC
+         LIMIT ?3 OFFSET ?4",
+        filter
+    ))
+    .bind(event_id)
+    .bind(status)
+    .bind(limit.map_or(-1, i64::from))
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ParticipantPage { participants, total })
+}
+
+/// List participants for an event, as far as the event's participant
+/// visibility allows the caller to see them. Filtered by `status` and
+/// `search` and paged with `limit` and `offset`; the number of matches
+/// across pages is sent in `X-Total-Count`.
+pub async fn list_participants(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    Query(query): Query<ParticipantListQuery>,
+    author: Option<EventAuthor>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    validation::validate(&query)?;
+    let fuzzy = query.fuzzy.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    if fuzzy.is_some_and(|q| q.chars().count() > fuzzy::MAX_QUERY_LEN) {
+        return Err(validation::field_error(
+            "fuzzy",
+            "too_long",
+            format!("must be at most {} characters", fuzzy::MAX_QUERY_LEN),
+        ));
+    }
+    let search = query.search.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    if fuzzy.is_some() && search.is_some() {
+        return Err(validation::field_error(
+            "search",
+            "conflict",
+            "cannot be combined with fuzzy".to_string(),
+        ));
+    }
+
+    let key = event_id.to_string();
+
+    let event = match state.cache.event.get(&key).await {
+        Some(event) => Some(event),
+        None => fetch_event(&state.db_pool, event_id).await.map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?,
+    };
+    let visibility = event.map(|e| e.participant_visibility).unwrap_or_default();
+
+    let is_organizer = organizers::is_owner(author.as_ref(), &state.db_pool, event_id).await?;
+    let viewer = visibility::viewer(&state.db_pool, &headers, event_id, is_organizer)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check participant token: {}", e);
+            internal_error()
+        })?;
+    if !viewer.can_list(visibility) {
+        return Err(api_error(StatusCode::FORBIDDEN, "Participant list is not visible to you"));
+    }
+    // Only organizers see emails, so only they may search by them
+    let searches_emails = viewer == Viewer::Organizer;
+
+    let filtered = query.status.is_some() || search.is_some() || query.limit.is_some() || query.offset > 0;
+    let page = if fuzzy.is_none() && filtered {
+        let page_key = cache::participant_page_key(
+            event_id,
+            query.status.as_ref(),
+            search,
+            searches_emails,
+            query.offset,
+            query.limit,
+        );
+        match state.cache.participant_pages.get(&page_key).await {
+            Some(page) => page,
+            None => {
+                let page = fetch_participant_page(
+                    &state.db_pool,
+                    event_id,
+                    query.status.as_ref(),
+                    search,
+                    searches_emails,
+                    query.offset,
+                    query.limit,
+                )
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to fetch participants: {}", e);
+                    internal_error()
+                })?;
+                state.cache.participant_pages.insert(page_key, page.clone()).await;
+                page
+            }
+        }
+    } else {
+        // Check cache first
+        let participants = match state.cache.participants.get(&key).await {
+            Some(participants) => participants,
+            None => {
+                let participants = fetch_participants(&state.db_pool, event_id).await.map_err(|e| {
+                    tracing::error!("Failed to fetch participants: {}", e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({ "error": "Internal server error" })),
+                    )
+                })?;
+
+                // Populate cache
+                state.cache.participants.insert(key, participants.clone()).await;
+                participants
+            }
+        };
+
+        match fuzzy {
+            Some(fuzzy) => fuzzy_page(participants, fuzzy, searches_emails, &query),
+            None => ParticipantPage { total: participants.len() as i64, participants },
+        }
+    };
+
+    let views: Vec<ParticipantView> = page
+        .participants
+        .into_iter()
+        .map(|participant| ParticipantView::new(participant, viewer))
+        .collect();
+
+    Ok(([(TOTAL_COUNT_HEADER, page.total.to_string())], Json(views)).into_response())
+}
+
+/// Fuzzy matches in `participants`, best first, narrowed to the query's
+/// status and page
+fn fuzzy_page(
+    participants: Vec<Participant>,
+    fuzzy: &str,
+    searches_emails: bool,
+    query: &ParticipantListQuery,
+) -> ParticipantPage {
+    let mut matches: Vec<(f64, Participant)> = participants
+        .into_iter()
+        .filter(|participant| query.status.as_ref().is_none_or(|status| &participant.status == status))
+        .map(|participant| {
+            let email = searches_emails.then_some(participant.email.as_str());
+            (fuzzy::score(fuzzy, &participant.name, email), participant)
+        })
+        .filter(|(score, _)| *score >= fuzzy::THRESHOLD)
+        .collect();
+    // Stable, so equally good matches stay in registration order
+    matches.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    let total = matches.len() as i64;
+    let participants = matches
+        .into_iter()
+        .map(|(_, participant)| participant)
+        .skip(query.offset as usize)
+        .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect();
+    ParticipantPage { participants, total }
+}
+
+/// Places taken and left, with registrations broken down by status.
+/// Only registered and confirmed participants hold a place.
+pub async fn get_availability(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<Availability>, ApiError> {
+    let event = fetch_event(&state.db_pool, event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let counts = count_by_status(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
     })?;
 
-    // Populate cache
-    state.cache.participants.insert(key, participants.clone()).await;
+    let held = capacity_holds::held_seats(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to count held places: {}", e);
+        internal_error()
+    })?;
 
-    Ok(Json(participants))
+    let taken = counts.registered + counts.confirmed + counts.checked_in;
+    Ok(Json(Availability {
+        event_id,
+        max_participants: event.max_participants,
+        taken,
+        held,
+        remaining: event.max_participants.map(|max| (i64::from(max) - taken - held).max(0)),
+        counts,
+    }))
+}
+
+/// Registrations and status changes between `from` (inclusive) and `to`
+/// (exclusive), oldest first, for organizers applying deltas elsewhere
+pub async fn get_participant_diff(
+    State(state): State<AppState>,
+    Path(event_id): Path<Uuid>,
+    Query(query): Query<ParticipantDiffQuery>,
+    author: EventAuthor,
+) -> Result<Json<ParticipantDiff>, ApiError> {
+    author.ensure_owns(&state.db_pool, event_id).await?;
+
+    let to = query.to.unwrap_or_else(|| state.clock.now());
+    if query.from >= to {
+        return Err(validation::field_error("from", "invalid_range", "must be before to".to_string()));
+    }
+
+    fetch_event(&state.db_pool, event_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let diff = participant_diff::build(&state.db_pool, event_id, query.from, to)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build participant diff: {}", e);
+            internal_error()
+        })?;
+
+    Ok(Json(diff))
 }
 
 /// Get a single participant by ID
 pub async fn get_participant(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Participant>, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<Json<Participant>, ApiError> {
     let id_str = id.to_string();
 
     // Check cache first
@@ -59,15 +550,7 @@ pub async fn get_participant(
         return Ok(Json(participant));
     }
 
-    let participant = sqlx::query_as::<_, Participant>(
-        "SELECT id, event_id, name, email, status, registered_at, updated_at 
-         FROM participants 
-         WHERE id = ?"
-    )
-    .bind(id)
-    .fetch_optional(&state.db_pool)
-    .await
-    .map_err(|e| {
+    let participant = fetch_participant(&state.db_pool, id).await.map_err(|e| {
         tracing::error!("Database error fetching participant: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -87,91 +570,132 @@ pub async fn get_participant(
     Ok(Json(participant))
 }
 
-/// Create a new participant
+/// Create a new participant; once the event is full, registrations are
+/// accepted onto the waitlist
 pub async fn create_participant(
     State(state): State<AppState>,
-    Json(payload): Json<CreateParticipant>,
-) -> Result<(StatusCode, Json<Participant>), (StatusCode, Json<serde_json::Value>)> {
-    if payload.name.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "name is required" })),
-        ));
+    IsAdmin(is_admin): IsAdmin,
+    JsonBody(mut payload): JsonBody<CreateParticipant>,
+) -> Result<(StatusCode, Json<Registration>), ApiError> {
+    payload.email = email::normalize(&payload.email);
+    validation::validate_with_limits(&payload, &state.config.field_limits)?;
+
+    if let Some(current) = &state.config.privacy_policy_version {
+        if payload.privacy_policy_version.trim() != current {
+            return Err(validation::field_error(
+                "privacy_policy_version",
+                "outdated_policy",
+                format!("must accept the current privacy policy ({})", current),
+            ));
+        }
     }
 
-    if payload.email.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "email is required" })),
-        ));
+    if let Verdict::Reject { code, message } = state.email_reputation.check(&payload.email).await {
+        return Err(validation::field_error("email", code, message));
     }
 
-    let mut tx = state.db_pool.begin().await.map_err(|e| {
-        tracing::error!("Failed to start transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Internal server error" })),
-        )
+    let now = state.clock.now();
+    let event = match state.cache.event.get(&payload.event_id.to_string()).await {
+        Some(event) => Some(event),
+        None => fetch_event(&state.db_pool, payload.event_id).await.map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?,
+    };
+    // A missing event is reported by the insert below
+    if let Some(event) = &event {
+        validation::check_age(payload.date_of_birth, event.min_age, event.start_time.date_naive(), now.date_naive())?;
+    }
+    let form = registration_form::load(&state.db_pool, payload.event_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch registration form: {}", e);
+        internal_error()
     })?;
+    let answers = registration_form::check_answers(&form, &payload.answers).map_err(validation::validation_error)?;
+    let referrer = match payload.invited_by.as_deref().filter(|c| !c.is_empty()) {
+        Some(invited_by) => Some(
+            referrals::resolve(&state.db_pool, payload.event_id, invited_by)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to resolve referrer: {}", e);
+                    internal_error()
+                })?
+                .ok_or_else(|| {
+                    validation::field_error(
+                        "invited_by",
+                        "unknown_referrer",
+                        "must be the invite code or id of a participant of this event".to_string(),
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    let date_of_birth = payload
+        .date_of_birth
+        .zip(state.config.pii_key.as_ref())
+        .map(|(date_of_birth, key)| key.encrypt(date_of_birth.to_string().as_bytes()));
 
-    let max_participants = sqlx::query_scalar::<_, Option<i32>>(
-        "SELECT max_participants FROM events WHERE id = ?"
+    let id = state.ids.generate(now);
+    let access_token = visibility::new_access_token();
+    let invite_code = referrals::new_invite_code();
+    let cancellation_key = cancellation::signing_key(
+        &state.db_pool,
+        state.config.cancellation_signing_key.as_deref(),
+        now,
     )
-    .bind(&payload.event_id)
-    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
-        tracing::error!("Database error checking event: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Internal server error" })),
-        )
-    })?
-    .ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "Event not found" })),
-        )
+        tracing::error!("Failed to load cancellation signing key: {}", e);
+        internal_error()
     })?;
 
-    if let Some(max) = max_participants {
-        let current_count = sqlx::query_scalar::<_, i64>(
-            "SELECT count(*) FROM participants WHERE event_id = ?"
-        )
-        .bind(&payload.event_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| {
-            tracing::error!("Database error counting participants: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Internal server error" })),
-            )
-        })?;
-
-        if current_count >= max as i64 {
-            return Err((
-                StatusCode::CONFLICT,
-                Json(json!({ "error": "Event is full" })),
-            ));
-        }
-    }
-
-    let id = Uuid::new_v4();
-    let now = chrono::Utc::now();
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
 
+    // The capacity check and the insert are a single statement, so SQLite's
+    // write lock makes them atomic even across instances sharing the file.
+    // Places reserved by capacity holds are not available; see `capacity_holds`
     let participant = sqlx::query_as::<_, Participant>(
-        "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at)
-         VALUES (?, ?, ?, ?, 'registered', ?, ?)
-         RETURNING id, event_id, name, email, status, registered_at, updated_at"
+        "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at, dedupe_key, access_token,
+                                   source, referrer_code, utm_campaign, date_of_birth, invite_code, metadata, email_index)
+         SELECT ?1, e.id, ?2, ?3,
+                CASE WHEN e.max_participants IS NULL
+                          OR (SELECT count(*) FROM participants p
+                              WHERE p.event_id = e.id AND p.status IN ('registered', 'confirmed', 'checked_in'))
+                         < e.max_participants - (SELECT COALESCE(SUM(h.seats), 0) FROM capacity_holds h WHERE h.event_id = e.id)
+                     THEN 'registered'
+                     ELSE 'waitlisted'
+                END,
+                ?4, ?4,
+                CASE e.email_policy
+                    WHEN 'strict' THEN ?14
+                    WHEN 'allow_different_name' THEN ?15
+                END,
+                ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?16
+         FROM events e
+         WHERE e.id = ?5
+           AND (e.status = 'published' OR (e.status = 'completed' AND ?6))
+           AND (e.frozen = 0 OR ?6)
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
     )
     .bind(id)
-    .bind(&payload.event_id)
-    .bind(&payload.name)
-    .bind(&payload.email)
+    .bind(PiiText(payload.name.clone()))
+    .bind(PiiText(payload.email.clone()))
     .bind(now)
-    .bind(now)
-    .fetch_one(&mut *tx)
+    .bind(payload.event_id)
+    .bind(is_admin)
+    .bind(&access_token)
+    .bind(payload.source)
+    .bind(payload.referrer_code.as_deref().filter(|c| !c.is_empty()))
+    .bind(payload.utm_campaign.as_deref().filter(|c| !c.is_empty()))
+    .bind(date_of_birth)
+    .bind(&invite_code)
+    .bind(sqlx::types::Json(&payload.metadata))
+    .bind(EmailPolicy::Strict.dedupe_key(&payload.email, &payload.name))
+    .bind(EmailPolicy::AllowDifferentName.dedupe_key(&payload.email, &payload.name))
+    .bind(encryption::blind_index(&payload.email))
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         if let Some(db_error) = e.as_database_error() {
@@ -181,6 +705,12 @@ pub async fn create_participant(
                     Json(json!({ "error": "Participant already registered" })),
                 );
             }
+            if db_error.message().contains("CHECK constraint failed") {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "Invalid participant values" })),
+                );
+            }
         }
         tracing::error!("Failed to create participant: {}", e);
         (
@@ -189,54 +719,193 @@ pub async fn create_participant(
         )
     })?;
 
-    tx.commit().await.map_err(|e| {
-        tracing::error!("Failed to commit transaction: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Internal server error" })),
-        )
+    // Nothing inserted: the event is missing, unpublished or frozen
+    let Some(participant) = participant else {
+        // Release the write lock before the follow-up reads
+        if let Err(e) = tx.rollback().await {
+            tracing::warn!("Failed to roll back registration: {}", e);
+        }
+
+        let status = sqlx::query_scalar::<_, EventStatus>("SELECT status FROM events WHERE id = ?")
+            .bind(payload.event_id)
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error checking event: {}", e);
+                internal_error()
+            })?;
+
+        return Err(match status {
+            None => api_error(StatusCode::BAD_REQUEST, "Event not found"),
+            Some(EventStatus::Draft) => {
+                api_error(StatusCode::CONFLICT, "Event is not published yet; registration is closed")
+            }
+            Some(EventStatus::Cancelled) => {
+                api_error(StatusCode::CONFLICT, "Event is cancelled; registration is closed")
+            }
+            Some(EventStatus::Completed) => {
+                api_error(StatusCode::CONFLICT, "Event has ended; registration is closed")
+            }
+            Some(EventStatus::Published) => {
+                ensure_not_frozen(&state.db_pool, payload.event_id, is_admin).await?;
+                api_error(StatusCode::CONFLICT, "Registration is closed")
+            }
+        });
+    };
+
+    let consent = consent::record(
+        &mut *tx,
+        participant.id,
+        payload.privacy_policy_version.trim(),
+        payload.marketing_opt_in,
+        &consent::new_token(),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record consent: {}", e);
+        internal_error()
     })?;
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_participants().await;
-    let notification_payload = json!({
-        "operation": "INSERT",
-        "table": "participants",
-        "id": participant.id,
-        "event_id": participant.event_id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "participant_changes",
-        &notification_payload,
+    let reminders = reminders::record(
+        &mut *tx,
+        participant.id,
+        payload.reminders.as_deref().unwrap_or(&reminders::default_lead_times()),
+        &reminders::new_token(),
+        now,
     )
     .await
-    {
-        tracing::error!("Failed to insert participant notification: {}", e);
+    .map_err(|e| {
+        tracing::error!("Failed to record reminder preferences: {}", e);
+        internal_error()
+    })?;
+
+    let cancellation_token = cancellation::issue(&mut *tx, &cancellation_key, participant.id, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to issue cancellation token: {}", e);
+            internal_error()
+        })?;
+
+    registration_form::record_answers(&mut tx, participant.id, &answers)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store form answers: {}", e);
+            internal_error()
+        })?;
+
+    if let Some(referrer_id) = referrer {
+        referrals::record(&mut *tx, participant.event_id, participant.id, referrer_id, now)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to record referral: {}", e);
+                internal_error()
+            })?;
     }
 
-    Ok((StatusCode::CREATED, Json(participant)))
+    let counts = count_participants(&mut *tx, participant.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit registration: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(
+        &state,
+        DomainEvent::ParticipantRegistered { participant: participant.clone(), counts },
+    )
+    .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Registration {
+            participant,
+            consent: consent.into(),
+            reminders: reminders.into(),
+            access_token,
+            cancellation_token,
+            invite_code,
+        }),
+    ))
 }
 
-/// Update participant status
+/// Update participant status.
+///
+/// Participants may register, answer maybe, decline or cancel; confirming
+/// and waitlisting, and moving someone off the waitlist, is up to the
+/// organizer. Moving into a state that holds a place fails with 409 when
+/// the event is full; leaving one promotes the longest-waiting participant.
 pub async fn update_participant_status(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateParticipantStatus>,
-) -> Result<Json<Participant>, (StatusCode, Json<serde_json::Value>)> {
-    let now = chrono::Utc::now();
+    IsAdmin(is_admin): IsAdmin,
+    author: Option<EventAuthor>,
+    JsonBody(payload): JsonBody<UpdateParticipantStatus>,
+) -> Result<Json<Participant>, ApiError> {
+    let current = sqlx::query_as::<_, (Uuid, ParticipantStatus)>("SELECT event_id, status FROM participants WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?;
+    let Some((event_id, from)) = current else {
+        return Err(api_error(StatusCode::NOT_FOUND, "Participant not found"));
+    };
+    ensure_not_frozen(&state.db_pool, event_id, is_admin).await?;
+    ensure_not_completed(&state.db_pool, event_id, is_admin).await?;
+
+    if payload.status == ParticipantStatus::CheckedIn {
+        return Err(validation::field_error(
+            "status",
+            "use_checkin",
+            "is set by POST /api/participants/:id/checkin".to_string(),
+        ));
+    }
+
+    let is_organizer = organizers::is_owner(author.as_ref(), &state.db_pool, event_id).await?;
+    if !is_organizer {
+        let leaves_waitlist = from == ParticipantStatus::Waitlisted && payload.status.holds_place();
+        if !payload.status.client_settable() || leaves_waitlist {
+            return Err(validation::field_error(
+                "status",
+                "organizer_only",
+                "only the organizer can set this status".to_string(),
+            ));
+        }
+    }
+
+    let now = state.clock.now();
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
 
+    // Taking a place checks capacity in the same statement, like
+    // registration; participants already holding one are never full
     let participant = sqlx::query_as::<_, Participant>(
-        "UPDATE participants 
-         SET status = ?, updated_at = ?
-         WHERE id = ?
-         RETURNING id, event_id, name, email, status, registered_at, updated_at"
+        "UPDATE participants
+         SET status = ?1, updated_at = ?2
+         WHERE id = ?3
+           AND (NOT ?4
+                OR status IN ('registered', 'confirmed', 'checked_in')
+                OR (SELECT e.max_participants FROM events e WHERE e.id = participants.event_id) IS NULL
+                OR (SELECT count(*) FROM participants p
+                    WHERE p.event_id = participants.event_id AND p.status IN ('registered', 'confirmed', 'checked_in'))
+                   < (SELECT e.max_participants FROM events e WHERE e.id = participants.event_id)
+                     - (SELECT COALESCE(SUM(h.seats), 0) FROM capacity_holds h WHERE h.event_id = participants.event_id))
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
     )
     .bind(&payload.status)
     .bind(now)
     .bind(id)
-    .fetch_optional(&state.db_pool)
+    .bind(payload.status.holds_place())
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| {
         tracing::error!("Failed to update participant: {}", e);
@@ -246,40 +915,263 @@ pub async fn update_participant_status(
         )
     })?
     .ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Participant not found" })),
+        // The participant existed a moment ago, so the capacity check failed
+        api_error(StatusCode::CONFLICT, "Event is full")
+    })?;
+
+    let promoted = if from.holds_place() && !participant.status.holds_place() {
+        promote_from_waitlist(&mut tx, event_id, Some(id), now).await.map_err(|e| {
+            tracing::error!("Failed to promote from waitlist: {}", e);
+            internal_error()
+        })?
+    } else {
+        None
+    };
+
+    let counts = count_participants(&mut *tx, participant.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit status change: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(
+        &state,
+        DomainEvent::ParticipantStatusChanged { participant: participant.clone(), previous: from, counts },
+    )
+    .await;
+    if let Some(promoted) = promoted {
+        domain_events::publish(&state, DomainEvent::ParticipantPromoted { participant: promoted, counts }).await;
+    }
+
+    Ok(Json(participant))
+}
+
+/// Change a participant's metadata with a JSON merge patch; organizers, or
+/// the participant sending their access token in `X-Participant-Token`
+pub async fn update_participant_metadata(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    IsAdmin(is_admin): IsAdmin,
+    author: Option<EventAuthor>,
+    headers: HeaderMap,
+    JsonBody(patch): JsonBody<Metadata>,
+) -> Result<Json<Participant>, ApiError> {
+    if let Err(e) = metadata::patch(&patch) {
+        let mut errors = ValidationErrors::new();
+        errors.add("metadata", e);
+        return Err(validation::validation_error(errors));
+    }
+
+    let current = sqlx::query_as::<_, (Uuid, Option<String>)>("SELECT event_id, access_token FROM participants WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?;
+    let Some((event_id, access_token)) = current else {
+        return Err(api_error(StatusCode::NOT_FOUND, "Participant not found"));
+    };
+
+    let is_organizer = organizers::is_owner(author.as_ref(), &state.db_pool, event_id).await?;
+    let token = headers.get(visibility::PARTICIPANT_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if !is_organizer && (access_token.is_none() || token != access_token.as_deref()) {
+        return Err(api_error(StatusCode::FORBIDDEN, "Only organizers and the participant may change these details"));
+    }
+    ensure_not_frozen(&state.db_pool, event_id, is_admin).await?;
+    ensure_not_completed(&state.db_pool, event_id, is_admin).await?;
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    // Merged in the statement itself, so concurrent patches all apply
+    let participant = sqlx::query_as::<_, Participant>(
+        "UPDATE participants
+         SET metadata = json_patch(metadata, ?1), updated_at = ?2
+         WHERE id = ?3 AND (SELECT count(*) FROM json_each(json_patch(metadata, ?1))) <= ?4
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(sqlx::types::Json(&patch))
+    .bind(state.clock.now())
+    .bind(id)
+    .bind(metadata::MAX_KEYS as i64)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        if e.as_database_error().is_some_and(|db_error| db_error.message().contains("CHECK constraint failed")) {
+            return validation::field_error(
+                "metadata",
+                "too_large",
+                format!("must be at most {} bytes as JSON", metadata::MAX_BYTES),
+            );
+        }
+        tracing::error!("Failed to update participant metadata: {}", e);
+        internal_error()
+    })?
+    .ok_or_else(|| {
+        validation::field_error(
+            "metadata",
+            "too_many_keys",
+            format!("must have at most {} keys", metadata::MAX_KEYS),
         )
     })?;
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_participants().await;
-    let notification_payload = json!({
-        "operation": "UPDATE",
-        "table": "participants",
-        "id": participant.id,
-        "event_id": participant.event_id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "participant_changes",
-        &notification_payload,
+    let counts = count_participants(&mut *tx, participant.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit metadata change: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(
+        &state,
+        DomainEvent::ParticipantDetailsChanged { participant: participant.clone(), counts },
+    )
+    .await;
+
+    Ok(Json(participant))
+}
+
+/// Cancel the registration the token was issued for, without any other
+/// credentials. Cancelling again returns the cancelled participant.
+pub async fn cancel_registration(
+    State(state): State<AppState>,
+    Query(query): Query<CancelRegistrationQuery>,
+) -> Result<Json<Participant>, ApiError> {
+    let now = state.clock.now();
+    let key = cancellation::signing_key(&state.db_pool, state.config.cancellation_signing_key.as_deref(), now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load cancellation signing key: {}", e);
+            internal_error()
+        })?;
+
+    let id = cancellation::participant_for(&state.db_pool, &key, query.token.trim())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up cancellation token: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Cancellation link is invalid"))?;
+
+    let participant = fetch_participant(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Cancellation link is invalid"))?;
+    if participant.status == ParticipantStatus::Cancelled {
+        return Ok(Json(participant));
+    }
+    ensure_not_frozen(&state.db_pool, participant.event_id, false).await?;
+    ensure_not_completed(&state.db_pool, participant.event_id, false).await?;
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    // The status is read again in the update, as another request may have
+    // cancelled or promoted the participant since
+    let from = sqlx::query_scalar::<_, ParticipantStatus>("SELECT status FROM participants WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?;
+    let cancelled = sqlx::query_as::<_, Participant>(
+        "UPDATE participants SET status = 'cancelled', updated_at = ?1
+         WHERE id = ?2 AND status != 'cancelled'
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(now)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to cancel registration: {}", e);
+        internal_error()
+    })?;
+    let (Some(from), Some(cancelled)) = (from, cancelled) else {
+        // Cancelled or deleted in the meantime
+        drop(tx);
+        return fetch_participant(&state.db_pool, id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch participant: {}", e);
+                internal_error()
+            })?
+            .map(Json)
+            .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Cancellation link is invalid"));
+    };
+
+    let promoted = if from.holds_place() {
+        promote_from_waitlist(&mut tx, cancelled.event_id, Some(id), now).await.map_err(|e| {
+            tracing::error!("Failed to promote from waitlist: {}", e);
+            internal_error()
+        })?
+    } else {
+        None
+    };
+
+    audit::record(
+        &mut *tx,
+        "registration.cancel",
+        "participant",
+        &id.to_string(),
+        "participant",
+        &json!({ "from_status": from }),
+        now,
     )
     .await
-    {
-        tracing::error!("Failed to insert participant notification: {}", e);
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    let counts = count_participants(&mut *tx, cancelled.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit cancellation: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(
+        &state,
+        DomainEvent::ParticipantStatusChanged { participant: cancelled.clone(), previous: from, counts },
+    )
+    .await;
+    if let Some(promoted) = promoted {
+        domain_events::publish(&state, DomainEvent::ParticipantPromoted { participant: promoted, counts }).await;
     }
 
-    Ok(Json(participant))
+    Ok(Json(cancelled))
 }
 
-/// Delete a participant
+/// Delete a participant; a freed place goes to the longest-waiting
+/// participant
 pub async fn delete_participant(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
-    // Get event_id before deletion for notification
+    IsAdmin(is_admin): IsAdmin,
+) -> Result<StatusCode, ApiError> {
+    // Check the freeze before deleting; the event of an unknown id is unknown
     let event_id = sqlx::query_scalar::<_, Uuid>(
         "SELECT event_id FROM participants WHERE id = ?"
     )
@@ -294,42 +1186,62 @@ pub async fn delete_participant(
         )
     })?;
 
-    let result = sqlx::query("DELETE FROM participants WHERE id = ?")
-        .bind(id)
-        .execute(&state.db_pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to delete participant: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "Internal server error" })),
-            )
-        })?;
-
-    if result.rows_affected() == 0 {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Participant not found" })),
-        ));
+    if let Some(event_id) = event_id {
+        ensure_not_frozen(&state.db_pool, event_id, is_admin).await?;
+        ensure_not_completed(&state.db_pool, event_id, is_admin).await?;
     }
 
-    // Invalidate cache and notify other instances
-    state.cache.invalidate_participants().await;
-    let notification_payload = json!({
-        "operation": "DELETE",
-        "table": "participants",
-        "id": id,
-        "event_id": event_id,
-        "timestamp": chrono::Utc::now()
-    }).to_string();
-    if let Err(e) = db::insert_notification(
-        &state.db_pool,
-        "participant_changes",
-        &notification_payload,
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    // The removed row is returned so subscribers can notify the participant
+    let participant = sqlx::query_as::<_, Participant>(
+        "DELETE FROM participants WHERE id = ?
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
     )
+    .bind(id)
+    .fetch_optional(&mut *tx)
     .await
-    {
-        tracing::error!("Failed to insert participant notification: {}", e);
+    .map_err(|e| {
+        tracing::error!("Failed to delete participant: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Internal server error" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Participant not found" })),
+        )
+    })?;
+
+    let promoted = if participant.status.holds_place() {
+        promote_from_waitlist(&mut tx, participant.event_id, Some(participant.id), state.clock.now())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to promote from waitlist: {}", e);
+                internal_error()
+            })?
+    } else {
+        None
+    };
+
+    let counts = count_participants(&mut *tx, participant.event_id).await.map_err(|e| {
+        tracing::error!("Failed to count participants: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit participant removal: {}", e);
+        internal_error()
+    })?;
+
+    domain_events::publish(&state, DomainEvent::ParticipantRemoved { participant, counts }).await;
+    if let Some(promoted) = promoted {
+        domain_events::publish(&state, DomainEvent::ParticipantPromoted { participant: promoted, counts }).await;
     }
 
     Ok(StatusCode::NO_CONTENT)
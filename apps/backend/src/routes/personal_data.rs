@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::RequireAdmin;
+use crate::domain_events::{self, DomainEvent};
+use crate::email;
+use crate::error::{internal_error, ApiError};
+use crate::personal_data::{self, ErasureReport, PersonalData};
+use crate::routes::participants::count_participants;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Everything held about the person with `email`, across all events; an
+/// address nothing is held about gets an empty export
+pub async fn export_personal_data(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    _admin: RequireAdmin,
+) -> Result<Json<PersonalData>, ApiError> {
+    let address = email::normalize(&address);
+    let now = state.clock.now();
+
+    let data = personal_data::collect(&state.db_pool, &address, state.config.pii_key.as_ref(), now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to collect personal data: {}", e);
+            internal_error()
+        })?;
+
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "personal_data.export",
+        "data_subject",
+        &personal_data::subject_id(&address),
+        audit::actor_label(true),
+        &json!({ "registrations": data.registrations.len() }),
+        now,
+    )
+    .await
+    {
+        tracing::error!("Failed to record audit entry: {}", e);
+    }
+
+    Ok(Json(data))
+}
+
+/// Erase the person with `email` from all events, promoting waitlisted
+/// participants into the places freed
+pub async fn erase_personal_data(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    _admin: RequireAdmin,
+) -> Result<Json<ErasureReport>, ApiError> {
+    let address = email::normalize(&address);
+    let now = state.clock.now();
+
+    let mut tx = state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        internal_error()
+    })?;
+
+    let erased = personal_data::erase(&mut tx, &address, now).await.map_err(|e| {
+        tracing::error!("Failed to erase personal data: {}", e);
+        internal_error()
+    })?;
+
+    let events: HashSet<Uuid> = erased.removed.iter().map(|p| p.event_id).collect();
+    let mut counts = HashMap::new();
+    for event_id in events {
+        let event_counts = count_participants(&mut *tx, event_id).await.map_err(|e| {
+            tracing::error!("Failed to count participants: {}", e);
+            internal_error()
+        })?;
+        counts.insert(event_id, event_counts);
+    }
+
+    // Recorded with the erasure, so neither happens without the other
+    audit::record(
+        &mut *tx,
+        "personal_data.erase",
+        "data_subject",
+        &personal_data::subject_id(&address),
+        audit::actor_label(true),
+        &json!(erased.report),
+        now,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record audit entry: {}", e);
+        internal_error()
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit erasure: {}", e);
+        internal_error()
+    })?;
+    tracing::info!("Erased {} registrations of a data subject", erased.removed.len());
+
+    for participant in erased.removed {
+        let counts = counts[&participant.event_id];
+        domain_events::publish(&state, DomainEvent::ParticipantRemoved { participant, counts }).await;
+    }
+    for participant in erased.promoted {
+        let counts = counts[&participant.event_id];
+        domain_events::publish(&state, DomainEvent::ParticipantPromoted { participant, counts }).await;
+    }
+
+    Ok(Json(erased.report))
+}
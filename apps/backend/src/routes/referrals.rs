@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::error::{api_error, internal_error, ApiError};
+use crate::organizers::EventAuthor;
+use crate::referrals::{self, ReferralSummary};
+use crate::routes::events::fetch_event;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReferralQuery {
+    /// Inviters to list
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = referrals::MAX_TOP_REFERRERS, code = "out_of_range", message = "must be between 1 and 100"))]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    10
+}
+
+/// Top inviters of an event's bring-a-friend campaign; organizers only
+pub async fn get_referrals(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ReferralQuery>,
+    author: EventAuthor,
+) -> Result<Json<ReferralSummary>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    validation::validate(&query)?;
+
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+
+    let summary = referrals::summary(&state.db_pool, id, query.limit).await.map_err(|e| {
+        tracing::error!("Failed to summarize referrals: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(summary))
+}
@@ -0,0 +1,81 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::organizers::EventAuthor;
+use crate::registration_form::{self, RegistrationForm, RegistrationFormInput};
+use crate::routes::events::{ensure_not_frozen, fetch_event};
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+async fn require_event(state: &AppState, id: Uuid) -> Result<(), ApiError> {
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+    Ok(())
+}
+
+/// The custom fields registrants answer for an event
+pub async fn get_registration_form(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RegistrationForm>, ApiError> {
+    require_event(&state, id).await?;
+
+    let fields = registration_form::load(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch registration form: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(RegistrationForm { event_id: id, fields }))
+}
+
+/// Replace an event's custom fields; organizers only
+pub async fn update_registration_form(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<RegistrationFormInput>,
+) -> Result<Json<RegistrationForm>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    validation::validate(&payload)?;
+    require_event(&state, id).await?;
+    ensure_not_frozen(&state.db_pool, id, author.is_admin()).await?;
+
+    let fields = registration_form::replace(&state.db_pool, &state.ids, id, &payload.fields, state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save registration form: {}", e);
+            internal_error()
+        })?;
+
+    let keys: Vec<&str> = fields.iter().map(|f| f.key.as_str()).collect();
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        "registration_form.update",
+        "event",
+        &id.to_string(),
+        &author.actor(),
+        &json!({ "fields": keys }),
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to audit registration form change: {}", e);
+    }
+
+    Ok(Json(RegistrationForm { event_id: id, fields }))
+}
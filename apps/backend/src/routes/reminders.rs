@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::reminders::{self, LeadTime, ReminderPreferences};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePreferences {
+    /// Empty for no reminders
+    pub lead_times: Vec<LeadTime>,
+}
+
+/// Reminder preferences of the holder of the token handed out at
+/// registration
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<ReminderPreferences>, ApiError> {
+    reminders::find(&state.db_pool, &token)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch reminder preferences: {}", e);
+            internal_error()
+        })?
+        .map(Json)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Reminder preferences not found"))
+}
+
+/// Change the reminder lead times using the token handed out at
+/// registration
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    JsonBody(payload): JsonBody<UpdatePreferences>,
+) -> Result<Json<ReminderPreferences>, ApiError> {
+    reminders::update(&state.db_pool, &token, &payload.lead_times, state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update reminder preferences: {}", e);
+            internal_error()
+        })?
+        .map(Json)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Reminder preferences not found"))
+}
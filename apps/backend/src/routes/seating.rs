@@ -0,0 +1,237 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::models::ParticipantStatus;
+use crate::organizers::EventAuthor;
+use crate::routes::events::{ensure_not_completed, ensure_not_frozen, fetch_event};
+use crate::seating::{self, Occupancy, SeatAssignment, SeatMap, SeatMapInput, SeatRequest};
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+async fn require_event(state: &AppState, id: Uuid) -> Result<(), ApiError> {
+    fetch_event(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch event: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Event not found"))?;
+    Ok(())
+}
+
+async fn load_map(state: &AppState, event_id: Uuid) -> Result<SeatMap, ApiError> {
+    seating::load(&state.db_pool, event_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch seat map: {}", e);
+        internal_error()
+    })
+}
+
+async fn audit_seating(state: &AppState, action: &str, entity: &str, id: Uuid, actor: &str, details: serde_json::Value) {
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        action,
+        entity,
+        &id.to_string(),
+        actor,
+        &details,
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to audit seating change: {}", e);
+    }
+}
+
+/// The tables and rows of an event's seat map
+pub async fn get_seat_map(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SeatMap>, ApiError> {
+    require_event(&state, id).await?;
+    Ok(Json(load_map(&state, id).await?))
+}
+
+/// Replace an event's seat map; organizers only. Assignments to seats the
+/// new map lacks are dropped.
+pub async fn update_seat_map(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<SeatMapInput>,
+) -> Result<Json<SeatMap>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    validation::validate(&payload)?;
+    require_event(&state, id).await?;
+    ensure_not_frozen(&state.db_pool, id, author.is_admin()).await?;
+
+    let (map, dropped) = seating::replace(&state.db_pool, id, &payload.sections, state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save seat map: {}", e);
+            internal_error()
+        })?;
+
+    let sections: Vec<&str> = map.sections.iter().map(|s| s.name.as_str()).collect();
+    audit_seating(
+        &state,
+        "seat_map.update",
+        "event",
+        id,
+        &author.actor(),
+        json!({ "sections": sections, "dropped_assignments": dropped }),
+    )
+    .await;
+
+    Ok(Json(map))
+}
+
+/// Give a participant holding a place a seat of the event's map, moving
+/// them if they already have one
+pub async fn assign_seat(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<SeatRequest>,
+) -> Result<Json<SeatAssignment>, ApiError> {
+    validation::validate(&payload)?;
+
+    let participant = sqlx::query_as::<_, (Uuid, ParticipantStatus)>("SELECT event_id, status FROM participants WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?;
+    let Some((event_id, status)) = participant else {
+        return Err(api_error(StatusCode::NOT_FOUND, "Participant not found"));
+    };
+    author.ensure_owns(&state.db_pool, event_id).await?;
+    if !status.holds_place() {
+        return Err(api_error(StatusCode::CONFLICT, "Participant holds no place at this event"));
+    }
+    ensure_not_frozen(&state.db_pool, event_id, author.is_admin()).await?;
+    ensure_not_completed(&state.db_pool, event_id, author.is_admin()).await?;
+
+    let map = load_map(&state, event_id).await?;
+    if map.sections.is_empty() {
+        return Err(api_error(StatusCode::CONFLICT, "Event has no seat map"));
+    }
+    if !map.has_seat(&payload.section, payload.seat) {
+        return Err(validation::field_error(
+            "seat",
+            "unknown_seat",
+            format!("seat {} of '{}' is not on the seat map", payload.seat, payload.section),
+        ));
+    }
+
+    let assignment = match seating::assign(
+        &state.db_pool,
+        event_id,
+        id,
+        &payload.section,
+        payload.seat,
+        state.clock.now(),
+    )
+    .await
+    {
+        Ok(assignment) => assignment,
+        Err(e) if e.as_database_error().is_some_and(|db_error| db_error.message().contains("UNIQUE constraint failed")) => {
+            let occupant = seating::occupant(&state.db_pool, event_id, &payload.section, payload.seat, id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to fetch seat occupant: {}", e);
+                    internal_error()
+                })?;
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Seat is already taken",
+                    "section": payload.section,
+                    "seat": payload.seat,
+                    "participant_id": occupant,
+                })),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to assign seat: {}", e);
+            return Err(internal_error());
+        }
+    };
+
+    audit_seating(
+        &state,
+        "participant.seat",
+        "participant",
+        id,
+        &author.actor(),
+        json!({ "section": assignment.section, "seat": assignment.seat }),
+    )
+    .await;
+
+    Ok(Json(assignment))
+}
+
+/// Take a participant's seat away
+pub async fn release_seat(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    let assignment = seating::find(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to fetch seat assignment: {}", e);
+        internal_error()
+    })?;
+    let Some(assignment) = assignment else {
+        return Err(api_error(StatusCode::NOT_FOUND, "Participant has no seat"));
+    };
+    author.ensure_owns(&state.db_pool, assignment.event_id).await?;
+    ensure_not_frozen(&state.db_pool, assignment.event_id, author.is_admin()).await?;
+    ensure_not_completed(&state.db_pool, assignment.event_id, author.is_admin()).await?;
+
+    seating::release(&state.db_pool, id).await.map_err(|e| {
+        tracing::error!("Failed to release seat: {}", e);
+        internal_error()
+    })?;
+
+    audit_seating(
+        &state,
+        "participant.unseat",
+        "participant",
+        id,
+        &author.actor(),
+        json!({ "section": assignment.section, "seat": assignment.seat }),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Who sits where at an event, section by section, for table plans and
+/// the door; organizers only
+pub async fn get_occupancy(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<Json<Occupancy>, ApiError> {
+    author.ensure_owns(&state.db_pool, id).await?;
+    require_event(&state, id).await?;
+
+    let map = load_map(&state, id).await?;
+    let occupancy = seating::occupancy(&state.db_pool, &map).await.map_err(|e| {
+        tracing::error!("Failed to compute seat occupancy: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(occupancy))
+}
@@ -0,0 +1,507 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::domain_events::{self, DomainEvent};
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::models::{CreateEvent, Event, EVENT_COLUMNS};
+use crate::nearby;
+use crate::organizers::EventAuthor;
+use crate::recurrence::{self, CreateSeries, EventSeries, Occurrence, SeriesSnapshot};
+use crate::routes::events::{self, ensure_not_frozen};
+use crate::routes::participants;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Which occurrences an edit or delete applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Only the given occurrence
+    #[default]
+    This,
+    /// The given occurrence and all after it
+    Future,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ScopeQuery {
+    #[serde(default)]
+    pub scope: Scope,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OccurrencesQuery {
+    /// Earliest scheduled start; the first occurrence when omitted
+    pub from: Option<DateTime<Utc>>,
+    /// Scheduled starts before this; all occurrences when omitted
+    pub to: Option<DateTime<Utc>>,
+}
+
+fn db_error(action: &str) -> impl FnOnce(sqlx::Error) -> ApiError + '_ {
+    move |e| {
+        tracing::error!("Failed to {}: {}", action, e);
+        internal_error()
+    }
+}
+
+/// A series, its exclusions and occurrence events, through the cache
+async fn load_snapshot(state: &AppState, id: Uuid) -> Result<SeriesSnapshot, ApiError> {
+    let id_str = id.to_string();
+    if let Some(snapshot) = state.cache.series.get(&id_str).await {
+        return Ok(snapshot);
+    }
+
+    let snapshot = recurrence::snapshot(&state.db_pool, id)
+        .await
+        .map_err(db_error("fetch series"))?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Series not found"))?;
+    state.cache.series.insert(id_str, snapshot.clone()).await;
+
+    Ok(snapshot)
+}
+
+/// A series straight from the database, for changes to it
+async fn fresh_snapshot(state: &AppState, id: Uuid) -> Result<SeriesSnapshot, ApiError> {
+    recurrence::snapshot(&state.db_pool, id)
+        .await
+        .map_err(db_error("fetch series"))?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Series not found"))
+}
+
+/// Position of a scheduled, not deleted occurrence
+fn require_occurrence(snapshot: &SeriesSnapshot, start: DateTime<Utc>) -> Result<usize, ApiError> {
+    snapshot
+        .series
+        .occurrence_index(start)
+        .filter(|_| !snapshot.exclusions.contains(&start))
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Occurrence not found"))
+}
+
+fn check_recurrence(event: &CreateEvent, recurrence: &recurrence::Recurrence) -> Result<(), ApiError> {
+    validation::validate(recurrence)?;
+    if !event.tags.is_empty() {
+        return Err(validation::field_error(
+            "tags",
+            "unsupported",
+            "series are not tagged; tag the events of their occurrences instead".to_string(),
+        ));
+    }
+    if recurrence.until.is_some_and(|until| until < event.start_time) {
+        return Err(validation::field_error(
+            "until",
+            "out_of_range",
+            "must not be before the first occurrence".to_string(),
+        ));
+    }
+    if recurrence.exceeds_limit(event.start_time) {
+        return Err(validation::field_error(
+            "until",
+            "too_many_occurrences",
+            format!("must end within {} occurrences", recurrence::MAX_OCCURRENCES),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a recurring series, owned by the organizer creating it
+pub async fn create_series(
+    State(state): State<AppState>,
+    author: EventAuthor,
+    JsonBody(mut payload): JsonBody<CreateSeries>,
+) -> Result<(StatusCode, Json<EventSeries>), ApiError> {
+    let now = state.clock.now();
+    validation::normalize_event_times(&mut payload.event, &state.config.date_bounds, now, None);
+    validation::validate_event(&payload.event, &state.config, now, author.is_admin(), None)?;
+    check_recurrence(&payload.event, &payload.recurrence)?;
+
+    let series = recurrence::insert(
+        &state.db_pool,
+        state.ids.generate(now),
+        &payload.event,
+        &payload.recurrence,
+        author.organizer_id(),
+        now,
+    )
+    .await
+        .map_err(db_error("create series"))?;
+
+    domain_events::publish(&state, DomainEvent::SeriesChanged { series_id: series.id }).await;
+
+    Ok((StatusCode::CREATED, Json(series)))
+}
+
+pub async fn get_series(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EventSeries>, ApiError> {
+    Ok(Json(load_snapshot(&state, id).await?.series))
+}
+
+/// Occurrences of a series, expanded from its rule
+pub async fn list_occurrences(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<OccurrencesQuery>,
+) -> Result<Json<Vec<Occurrence>>, ApiError> {
+    let snapshot = load_snapshot(&state, id).await?;
+    Ok(Json(snapshot.expand(query.from, query.to)))
+}
+
+/// Delete a series. Occurrences that have their own event keep it, as a
+/// standalone event.
+pub async fn delete_series(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    let mut tx = state.db_pool.begin().await.map_err(db_error("start transaction"))?;
+
+    author.ensure_owns_series(&mut *tx, id).await?;
+    let event_ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM events WHERE series_id = ?")
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error("fetch occurrences"))?;
+    for event_id in event_ids {
+        author.ensure_owns(&mut *tx, event_id).await?;
+    }
+
+    sqlx::query("UPDATE events SET series_id = NULL, occurrence_start = NULL, updated_at = ? WHERE series_id = ?")
+        .bind(state.clock.now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("detach occurrences"))?;
+    let deleted = sqlx::query("DELETE FROM event_series WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("delete series"))?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(api_error(StatusCode::NOT_FOUND, "Series not found"));
+    }
+
+    tx.commit().await.map_err(db_error("commit transaction"))?;
+
+    state.cache.invalidate_events().await;
+    domain_events::publish(&state, DomainEvent::SeriesChanged { series_id: id }).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The occurrence's own event, created from the series if needed
+async fn occurrence_event(state: &AppState, snapshot: &SeriesSnapshot, start: DateTime<Utc>) -> Result<(Event, bool), ApiError> {
+    if let Some(event) = snapshot.event_at(start) {
+        return Ok((event.clone(), false));
+    }
+
+    let now = state.clock.now();
+    let mut tx = state.db_pool.begin().await.map_err(db_error("start transaction"))?;
+    let event = recurrence::materialize(&mut tx, &snapshot.series, state.ids.generate(now), start, now)
+        .await
+        .map_err(|e| {
+            if let Some(db_error) = e.as_database_error() {
+                if db_error.message().contains("UNIQUE constraint failed") {
+                    return api_error(StatusCode::CONFLICT, "Occurrence was opened concurrently; retry");
+                }
+            }
+            tracing::error!("Failed to materialize occurrence: {}", e);
+            internal_error()
+        })?;
+    tx.commit().await.map_err(db_error("commit transaction"))?;
+
+    domain_events::publish(state, DomainEvent::EventCreated { event: event.clone() }).await;
+
+    Ok((event, true))
+}
+
+/// The event of one occurrence, created on first use, e.g. to register
+pub async fn open_occurrence(
+    State(state): State<AppState>,
+    Path((id, start)): Path<(Uuid, DateTime<Utc>)>,
+) -> Result<(StatusCode, Json<Event>), ApiError> {
+    let snapshot = fresh_snapshot(&state, id).await?;
+    require_occurrence(&snapshot, start)?;
+
+    let (event, created) = occurrence_event(&state, &snapshot, start).await?;
+    let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+
+    Ok((status, Json(event)))
+}
+
+/// Edit one occurrence (`?scope=this`, answered with its event) or it and
+/// all later ones (`?scope=future`, answered with the series covering
+/// them). Later occurrences move by as much as this one does.
+pub async fn update_occurrence(
+    State(state): State<AppState>,
+    Path((id, start)): Path<(Uuid, DateTime<Utc>)>,
+    Query(query): Query<ScopeQuery>,
+    author: EventAuthor,
+    JsonBody(payload): JsonBody<CreateEvent>,
+) -> Result<Response, ApiError> {
+    let snapshot = fresh_snapshot(&state, id).await?;
+    let index = require_occurrence(&snapshot, start)?;
+    author.ensure_owns_series(&state.db_pool, id).await?;
+
+    match query.scope {
+        Scope::This => {
+            let (event, _) = occurrence_event(&state, &snapshot, start).await?;
+            author.ensure_owns(&state.db_pool, event.id).await?;
+            let event = events::apply_update(&state, event.id, author.is_admin(), payload).await?;
+            Ok(Json(event).into_response())
+        }
+        Scope::Future => {
+            let series = update_future(&state, &snapshot, index, start, &author, payload).await?;
+            Ok(Json(series).into_response())
+        }
+    }
+}
+
+async fn update_future(
+    state: &AppState,
+    snapshot: &SeriesSnapshot,
+    index: usize,
+    start: DateTime<Utc>,
+    author: &EventAuthor,
+    mut payload: CreateEvent,
+) -> Result<EventSeries, ApiError> {
+    let now = state.clock.now();
+    let is_admin = author.is_admin();
+    let series = &snapshot.series;
+    validation::normalize_event_times(&mut payload, &state.config.date_bounds, now, Some(start));
+    validation::validate_event(&payload, &state.config, now, is_admin, Some(start))?;
+
+    let shift = payload.start_time - start;
+    let duration = payload.end_time - payload.start_time;
+    let continued = recurrence::continued_from(series, index, shift);
+    check_recurrence(&payload, &continued)?;
+
+    let mut tx = state.db_pool.begin().await.map_err(db_error("start transaction"))?;
+
+    let moved_events: Vec<&Event> = snapshot
+        .events
+        .iter()
+        .filter(|e| e.occurrence_start.is_some_and(|t| t >= start))
+        .collect();
+    for event in &moved_events {
+        author.ensure_owns(&mut *tx, event.id).await?;
+        ensure_not_frozen(&mut *tx, event.id, is_admin).await?;
+    }
+
+    // The first occurrence changes the whole series; later ones end it and
+    // continue as a new series
+    let (target_id, continued_series) = if index == 0 {
+        let replaced = recurrence::replace(&mut *tx, series.id, &payload, &continued, now)
+            .await
+            .map_err(db_error("update series"))?;
+        (series.id, replaced)
+    } else {
+        let ended = recurrence::ended_before(series, index, start);
+        sqlx::query("UPDATE event_series SET until = ?, count = ?, updated_at = ? WHERE id = ?")
+            .bind(ended.until)
+            .bind(ended.count)
+            .bind(now)
+            .bind(series.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error("end series"))?;
+        let owner = recurrence::owner(&mut *tx, series.id)
+            .await
+            .map_err(db_error("fetch series owner"))?
+            .flatten();
+        let inserted = recurrence::insert(&mut *tx, state.ids.generate(now), &payload, &continued, owner, now)
+            .await
+            .map_err(db_error("continue series"))?;
+        (inserted.id, inserted)
+    };
+
+    sqlx::query("DELETE FROM event_series_exclusions WHERE series_id = ? AND occurrence_start >= ?")
+        .bind(series.id)
+        .bind(start)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("move exclusions"))?;
+    for excluded in snapshot.exclusions.iter().filter(|t| **t >= start) {
+        recurrence::exclude(&mut *tx, target_id, *excluded + shift)
+            .await
+            .map_err(db_error("move exclusions"))?;
+    }
+
+    let trig = nearby::trig_columns(payload.latitude, payload.longitude);
+    let mut updated = Vec::with_capacity(moved_events.len());
+    for event in moved_events {
+        let occurrence_start = event.occurrence_start.unwrap_or(start) + shift;
+        let event = sqlx::query_as::<_, Event>(&format!(
+            "UPDATE events
+             SET title = ?, description = ?, start_time = ?, end_time = ?, location = ?, max_participants = ?, email_policy = ?, participant_visibility = ?,
+                 latitude = ?, longitude = ?, geo_sin_lat = ?, geo_cos_lat = ?, geo_sin_lng = ?, geo_cos_lng = ?, min_age = ?,
+                 series_id = ?, occurrence_start = ?, updated_at = ?
+             WHERE id = ?
+             RETURNING {}",
+            EVENT_COLUMNS
+        ))
+        .bind(&payload.title)
+        .bind(&payload.description)
+        .bind(occurrence_start)
+        .bind(occurrence_start + duration)
+        .bind(&payload.location)
+        .bind(payload.max_participants)
+        .bind(payload.email_policy)
+        .bind(payload.participant_visibility)
+        .bind(payload.latitude)
+        .bind(payload.longitude)
+        .bind(trig.map(|t| t.sin_lat))
+        .bind(trig.map(|t| t.cos_lat))
+        .bind(trig.map(|t| t.sin_lng))
+        .bind(trig.map(|t| t.cos_lng))
+        .bind(payload.min_age)
+        .bind(target_id)
+        .bind(occurrence_start)
+        .bind(now)
+        .bind(event.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error("update occurrence"))?;
+
+        participants::rekey_participants(&mut tx, event.id, event.email_policy)
+            .await
+            .map_err(|e| {
+                if let Some(db_error) = e.as_database_error() {
+                    if db_error.message().contains("UNIQUE constraint failed") {
+                        return api_error(
+                            StatusCode::CONFLICT,
+                            "Existing registrations share an email; they conflict with this email_policy",
+                        );
+                    }
+                }
+                tracing::error!("Failed to apply email policy: {}", e);
+                internal_error()
+            })?;
+        updated.push(event);
+    }
+
+    tx.commit().await.map_err(db_error("commit transaction"))?;
+
+    domain_events::publish(state, DomainEvent::SeriesChanged { series_id: series.id }).await;
+    if target_id != series.id {
+        domain_events::publish(state, DomainEvent::SeriesChanged { series_id: target_id }).await;
+    }
+    for event in updated {
+        domain_events::publish(state, DomainEvent::EventUpdated { event }).await;
+    }
+
+    Ok(continued_series)
+}
+
+/// Delete one occurrence (`?scope=this`) or it and all later ones
+/// (`?scope=future`). Occurrences with registrations are not deleted; their
+/// events have to be deleted first.
+pub async fn delete_occurrence(
+    State(state): State<AppState>,
+    Path((id, start)): Path<(Uuid, DateTime<Utc>)>,
+    Query(query): Query<ScopeQuery>,
+    author: EventAuthor,
+) -> Result<StatusCode, ApiError> {
+    let snapshot = fresh_snapshot(&state, id).await?;
+    let index = require_occurrence(&snapshot, start)?;
+    author.ensure_owns_series(&state.db_pool, id).await?;
+    let series = &snapshot.series;
+    let now = state.clock.now();
+
+    let doomed: Vec<&Event> = snapshot
+        .events
+        .iter()
+        .filter(|e| match query.scope {
+            Scope::This => e.occurrence_start == Some(start),
+            Scope::Future => e.occurrence_start.is_some_and(|t| t >= start),
+        })
+        .collect();
+
+    let mut tx = state.db_pool.begin().await.map_err(db_error("start transaction"))?;
+
+    for event in &doomed {
+        author.ensure_owns(&mut *tx, event.id).await?;
+        ensure_not_frozen(&mut *tx, event.id, author.is_admin()).await?;
+        let registered = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM participants WHERE event_id = ?")
+            .bind(event.id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error("count participants"))?;
+        if registered > 0 {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Occurrence has registered participants; delete its event first",
+                    "event_id": event.id,
+                    "participant_count": registered
+                })),
+            ));
+        }
+        sqlx::query("DELETE FROM events WHERE id = ?")
+            .bind(event.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error("delete occurrence event"))?;
+    }
+
+    match query.scope {
+        Scope::This => {
+            recurrence::exclude(&mut *tx, series.id, start).await.map_err(db_error("exclude occurrence"))?;
+            sqlx::query("UPDATE event_series SET updated_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(series.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error("update series"))?;
+        }
+        Scope::Future if index == 0 => {
+            sqlx::query("DELETE FROM event_series WHERE id = ?")
+                .bind(series.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error("delete series"))?;
+        }
+        Scope::Future => {
+            let ended = recurrence::ended_before(series, index, start);
+            sqlx::query("DELETE FROM event_series_exclusions WHERE series_id = ? AND occurrence_start >= ?")
+                .bind(series.id)
+                .bind(start)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error("delete exclusions"))?;
+            sqlx::query("UPDATE event_series SET until = ?, count = ?, updated_at = ? WHERE id = ?")
+                .bind(ended.until)
+                .bind(ended.count)
+                .bind(now)
+                .bind(series.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error("end series"))?;
+        }
+    }
+
+    tx.commit().await.map_err(db_error("commit transaction"))?;
+
+    for event in doomed {
+        domain_events::publish(
+            &state,
+            DomainEvent::EventDeleted { event: event.clone(), participants: Vec::new() },
+        )
+        .await;
+    }
+    domain_events::publish(&state, DomainEvent::SeriesChanged { series_id: series.id }).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
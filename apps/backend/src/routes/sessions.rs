@@ -0,0 +1,83 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::auth;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::session::{self, AdminSession};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct Login {
+    pub token: String,
+}
+
+/// Exchange the admin token for a session cookie; the response carries
+/// the CSRF token to send with state-changing requests
+pub async fn create_session(
+    State(state): State<AppState>,
+    JsonBody(payload): JsonBody<Login>,
+) -> Result<Response, ApiError> {
+    let Some(expected) = state.config.admin_token.as_deref() else {
+        return Err(api_error(StatusCode::FORBIDDEN, "Admin access is disabled"));
+    };
+    if !auth::constant_time_eq(payload.token.as_bytes(), expected.as_bytes()) {
+        return Err(api_error(StatusCode::UNAUTHORIZED, "Invalid admin token"));
+    }
+
+    let now = state.clock.now();
+    let ttl = state.config.admin_session_ttl;
+    let session = AdminSession {
+        id: Uuid::new_v4().simple().to_string(),
+        csrf_token: Uuid::new_v4().simple().to_string(),
+        expires_at: now + ttl,
+    };
+
+    if let Err(e) = session::prune(&state.db_pool, now).await {
+        tracing::warn!("Failed to prune admin sessions: {}", e);
+    }
+
+    session::create(&state.db_pool, &session, now).await.map_err(|e| {
+        tracing::error!("Failed to create admin session: {}", e);
+        internal_error()
+    })?;
+    tracing::info!("Admin session started, expires {}", session.expires_at);
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::SET_COOKIE, session::session_cookie(&session.id, ttl))],
+        Json(session),
+    )
+        .into_response())
+}
+
+/// The current session, so a reloaded panel can recover its CSRF token
+pub async fn get_session(session: Option<Extension<AdminSession>>) -> Result<Json<AdminSession>, ApiError> {
+    match session {
+        Some(Extension(session)) => Ok(Json(session)),
+        None => Err(api_error(StatusCode::UNAUTHORIZED, "No active session")),
+    }
+}
+
+/// End the current session and clear the cookie
+pub async fn delete_session(
+    State(state): State<AppState>,
+    session: Option<Extension<AdminSession>>,
+) -> Result<Response, ApiError> {
+    if let Some(Extension(session)) = session {
+        session::delete(&state.db_pool, &session.id).await.map_err(|e| {
+            tracing::error!("Failed to delete admin session: {}", e);
+            internal_error()
+        })?;
+    }
+
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, session::clear_cookie())]).into_response())
+}
@@ -1,40 +1,155 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
+    http::HeaderMap,
     response::{sse::Event, Sse},
 };
-use futures::stream::Stream;
+use futures::stream::{self, BoxStream, StreamExt as _};
+use serde::Deserialize;
+use serde_json::json;
 use std::convert::Infallible;
 use std::time::Duration;
-use tokio_stream::StreamExt as _;
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
+
+use crate::broadcaster::{ServerEvent, Topic};
+use crate::db::{self, DbPool};
+use crate::reconnect::ReconnectReason;
 
 // Type alias for our app state
 type AppState = crate::AppState;
 
-/// SSE endpoint that streams events to clients
+/// Route pattern of the stream, as fault injection rules name it
+const STREAM_ROUTE: &str = "/api/events/stream";
+
+/// SSE event type telling a resuming client that notifications it missed
+/// can no longer be replayed, so it must reload what it shows
+pub const RESYNC_EVENT: &str = "resync";
+
+/// Most notifications replayed to a resuming client; one that missed more
+/// is told to resync instead
+pub const MAX_REPLAY: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ResumeQuery {
+    /// Resume point for clients that cannot send `Last-Event-ID`, such as a
+    /// newly opened EventSource; the header wins when both are given
+    pub last_event_id: Option<i64>,
+}
+
+/// The notifications missed since `last_id`, or None when they cannot all
+/// be replayed: deleted by retention, too many, or `last_id` is not from
+/// this database
+async fn missed_since(pool: &DbPool, last_id: i64) -> Result<Option<Vec<ServerEvent>>, sqlx::Error> {
+    let (oldest, newest) = db::notification_history(pool).await?;
+    if last_id > newest || last_id + 1 < oldest {
+        return Ok(None);
+    }
+
+    let missed = db::notifications_after(pool, last_id, MAX_REPLAY + 1).await?;
+    if missed.len() as i64 > MAX_REPLAY {
+        return Ok(None);
+    }
+    Ok(Some(missed))
+}
+
+/// SSE endpoint that streams events to clients, limited to those matching
+/// the `channel` and `event_id` query parameters when given. Every event
+/// carries its notification id; a client reconnecting with `Last-Event-ID`
+/// first gets what it missed from `change_notifications`, or a `resync`
+/// event when that is gone. The stream opens with the reconnect delay;
+/// while shedding load, or once the instance drains, it ends with a
+/// `reconnect` event instead (see `reconnect`).
 pub async fn event_stream(
     State(state): State<AppState>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    debug!("New SSE client connected");
+    Query(topic): Query<Topic>,
+    Query(resume): Query<ResumeQuery>,
+    headers: HeaderMap,
+) -> Sse<BoxStream<'static, Result<Event, Infallible>>> {
+    let reconnect = state.config.sse_reconnect.clone();
 
+    if state.load_shedder.is_overloaded() {
+        warn!("Refusing SSE client: {} requests in flight", state.load_shedder.in_flight());
+        let advice = reconnect.reconnect_event(ReconnectReason::Overloaded, state.clock.now());
+        return Sse::new(stream::once(async move { Ok(advice) }).boxed());
+    }
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .or(resume.last_event_id);
+    debug!("New SSE client connected for {:?}, resuming after {:?}", topic, last_event_id);
+
+    // Subscribed before reading the history, so a notification committed
+    // in between arrives live rather than not at all
     let receiver = state.broadcaster.subscribe();
-    let stream = BroadcastStream::new(receiver);
 
-    let event_stream = stream
-        .filter_map(|result| match result {
-            Ok(event) => {
-                debug!("Sending event to SSE client: {:?}", event);
-                Some(Ok(Event::default()
-                    .event(&event.channel)
-                    .data(event.payload)))
+    let mut opening = vec![Ok(reconnect.retry_event())];
+    let mut missed = Vec::new();
+    let mut replayed_to = None;
+    if let Some(last_id) = last_event_id {
+        match missed_since(&state.db_pool, last_id).await {
+            Ok(Some(events)) => {
+                replayed_to = Some(events.last().map_or(last_id, |event| event.id));
+                missed = events;
+            }
+            result => {
+                if let Err(e) = result {
+                    error!("Failed to read missed notifications: {}", e);
+                }
+                let resync = json!({
+                    "type": RESYNC_EVENT,
+                    "last_event_id": last_id,
+                    "timestamp": state.clock.now()
+                });
+                opening.push(Ok(Event::default().event(RESYNC_EVENT).data(resync.to_string())));
             }
+        }
+    }
+
+    let live = BroadcastStream::new(receiver).filter_map(move |result| {
+        let event = match result {
+            // Already sent from the history
+            Ok(event) if replayed_to.is_some_and(|id| event.id <= id) => None,
+            Ok(event) => Some(event),
             Err(e) => {
                 error!("Broadcast stream error: {}", e);
                 None
             }
+        };
+        async move { event }
+    });
+
+    let chaos = state.config.chaos.clone();
+    let events = stream::iter(missed)
+        .chain(live)
+        .filter_map(move |event| {
+            let event = if !topic.matches(&event) {
+                None
+            } else if chaos.drop_message(STREAM_ROUTE) {
+                debug!("Dropping event for SSE client: {:?}", event);
+                None
+            } else {
+                debug!("Sending event to SSE client: {:?}", event);
+                Some(Ok(Event::default()
+                    .id(event.id.to_string())
+                    .event(event.channel.as_str())
+                    .data(event.payload)))
+            };
+            async move { event }
         });
 
+    // Close the stream on shutdown so graceful shutdown is not held up by
+    // it, telling the client when to reconnect to the next instance
+    let instance = state.instance.clone();
+    let clock = state.clock.clone();
+    let event_stream = stream::iter(opening)
+        .chain(events.take_until(async move { instance.drained().await }))
+        .chain(stream::once(async move {
+            Ok(reconnect.reconnect_event(ReconnectReason::ShuttingDown, clock.now()))
+        }))
+        .boxed();
+
     Sse::new(event_stream).keep_alive(
         axum::response::sse::KeepAlive::new()
             .interval(Duration::from_secs(15))
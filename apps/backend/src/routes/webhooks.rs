@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::error::{api_error, internal_error, ApiError};
+use crate::json::JsonBody;
+use crate::organizers::{CurrentOrganizer, Organizer};
+use crate::routes::participants::fetch_participant;
+use crate::validation;
+use crate::webhooks::{self, CreatedWebhook, Delivery, Webhook, WebhookInput};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+fn actor(organizer: &Organizer) -> String {
+    format!("organizer:{}", organizer.id)
+}
+
+async fn audit_change(state: &AppState, action: &str, organizer: &Organizer, id: Uuid, details: serde_json::Value) {
+    if let Err(e) = audit::record(
+        &state.db_pool,
+        action,
+        "webhook",
+        &id.to_string(),
+        &actor(organizer),
+        &details,
+        state.clock.now(),
+    )
+    .await
+    {
+        tracing::error!("Failed to audit webhook change: {}", e);
+    }
+}
+
+/// The signed-in organizer's CRM webhooks
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    CurrentOrganizer(organizer): CurrentOrganizer,
+) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let webhooks = webhooks::list(&state.db_pool, organizer.id).await.map_err(|e| {
+        tracing::error!("Failed to list webhooks: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(webhooks))
+}
+
+/// Add a CRM webhook; the signing secret in the response is not shown again
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    CurrentOrganizer(organizer): CurrentOrganizer,
+    JsonBody(payload): JsonBody<WebhookInput>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), ApiError> {
+    validation::validate(&payload)?;
+
+    let secret = webhooks::new_secret();
+    let now = state.clock.now();
+    let webhook = webhooks::create(&state.db_pool, state.ids.generate(now), organizer.id, &payload, &secret, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create webhook: {}", e);
+            internal_error()
+        })?;
+    audit_change(&state, "webhook.create", &organizer, webhook.id, json!({ "name": webhook.name, "url": webhook.url })).await;
+
+    Ok((StatusCode::CREATED, Json(CreatedWebhook { webhook, secret })))
+}
+
+/// Replace a webhook's settings; its secret stays
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    CurrentOrganizer(organizer): CurrentOrganizer,
+    JsonBody(payload): JsonBody<WebhookInput>,
+) -> Result<Json<Webhook>, ApiError> {
+    validation::validate(&payload)?;
+
+    let webhook = webhooks::update(&state.db_pool, organizer.id, id, &payload, state.clock.now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update webhook: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Webhook not found"))?;
+    audit_change(
+        &state,
+        "webhook.update",
+        &organizer,
+        webhook.id,
+        json!({ "name": webhook.name, "url": webhook.url, "active": webhook.active }),
+    )
+    .await;
+
+    Ok(Json(webhook))
+}
+
+/// Delete a webhook along with its queued and past deliveries
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    CurrentOrganizer(organizer): CurrentOrganizer,
+) -> Result<StatusCode, ApiError> {
+    let deleted = webhooks::delete(&state.db_pool, organizer.id, id).await.map_err(|e| {
+        tracing::error!("Failed to delete webhook: {}", e);
+        internal_error()
+    })?;
+    if !deleted {
+        return Err(api_error(StatusCode::NOT_FOUND, "Webhook not found"));
+    }
+    audit_change(&state, "webhook.delete", &organizer, id, json!({})).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// What the signed-in organizer's webhooks sent about a participant, newest
+/// first
+pub async fn list_participant_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    CurrentOrganizer(organizer): CurrentOrganizer,
+) -> Result<Json<Vec<Delivery>>, ApiError> {
+    fetch_participant(&state.db_pool, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch participant: {}", e);
+            internal_error()
+        })?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Participant not found"))?;
+
+    let deliveries = webhooks::deliveries_for(&state.db_pool, organizer.id, id).await.map_err(|e| {
+        tracing::error!("Failed to list webhook deliveries: {}", e);
+        internal_error()
+    })?;
+
+    Ok(Json(deliveries))
+}
@@ -0,0 +1,384 @@
+//! Seat maps and seat assignments.
+//!
+//! Events such as gala dinners seat their guests. An organizer describes
+//! the room as the event's seat map: sections, each a table or a row with
+//! a number of seats, stored as JSON in `event_seat_maps`. Participants
+//! holding a place are then given a seat with
+//! `POST /api/participants/:id/seat`; a seat is named by its section and
+//! its number within the section, counted from 1.
+//!
+//! A unique index on (event_id, section, seat) keeps two participants off
+//! the same seat. A participant who gives up their place keeps their
+//! assignment until the seat is given to someone else, but no longer
+//! counts as sitting there. Replacing the seat map drops the assignments
+//! to seats it no longer has.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::db::DbPool;
+use crate::encryption::PiiText;
+
+/// Most sections one seat map may have
+pub const MAX_SECTIONS: usize = 200;
+/// Most seats one section may have
+pub const MAX_SEATS_PER_SECTION: u32 = 500;
+/// Longest section name
+pub const MAX_SECTION_NAME_LEN: u64 = 100;
+
+/// Statuses whose participants occupy the seat they were given; the same
+/// as `ParticipantStatus::holds_place`
+const SEATED_STATUSES: &str = "('registered', 'confirmed', 'checked_in')";
+
+/// How a section's seats are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    /// Seats around a table
+    Table,
+    /// Seats side by side, as in a theatre
+    Row,
+}
+
+/// A table or row of the seat map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Section {
+    /// Unique within the map, e.g. "Table 4" or "Row B"
+    pub name: String,
+    pub kind: SectionKind,
+    /// Seats are numbered 1 to `seats`
+    pub seats: u32,
+}
+
+/// The seat map of one event, sections in display order
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatMap {
+    pub event_id: Uuid,
+    pub sections: Vec<Section>,
+    /// None while the event has no seat map
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SeatMap {
+    /// Whether the map has seat `seat` in section `section`
+    pub fn has_seat(&self, section: &str, seat: u32) -> bool {
+        self.sections
+            .iter()
+            .any(|s| s.name == section && (1..=s.seats).contains(&seat))
+    }
+
+    /// Seats across all sections
+    pub fn capacity(&self) -> i64 {
+        self.sections.iter().map(|s| i64::from(s.seats)).sum()
+    }
+}
+
+/// Replacement for an event's seat map
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SeatMapInput {
+    #[validate(custom(function = "sections"))]
+    pub sections: Vec<Section>,
+}
+
+/// The seat to give a participant
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SeatRequest {
+    #[validate(length(min = 1, max = MAX_SECTION_NAME_LEN, code = "out_of_range"))]
+    pub section: String,
+    #[validate(range(min = 1, code = "out_of_range", message = "must be greater than 0"))]
+    pub seat: u32,
+}
+
+/// The seat a participant was given
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SeatAssignment {
+    pub participant_id: Uuid,
+    pub event_id: Uuid,
+    pub section: String,
+    pub seat: i64,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// A participant sitting in a section
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SeatedGuest {
+    pub seat: i64,
+    pub participant_id: Uuid,
+    pub name: String,
+}
+
+/// One section and who sits there
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionOccupancy {
+    pub name: String,
+    pub kind: SectionKind,
+    pub seats: u32,
+    pub occupied: i64,
+    /// By seat number
+    pub guests: Vec<SeatedGuest>,
+}
+
+/// How full an event's seat map is
+#[derive(Debug, Clone, Serialize)]
+pub struct Occupancy {
+    pub event_id: Uuid,
+    /// Seats across all sections
+    pub capacity: i64,
+    pub occupied: i64,
+    /// Participants holding a place without a seat
+    pub unseated: i64,
+    pub sections: Vec<SectionOccupancy>,
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// Check section definitions: unique names and at least one seat each
+fn sections(value: &[Section]) -> Result<(), ValidationError> {
+    if value.len() > MAX_SECTIONS {
+        return Err(error("too_many_sections", format!("must have at most {} sections", MAX_SECTIONS)));
+    }
+
+    let mut names = HashSet::new();
+    for section in value {
+        let name = section.name.trim();
+        if name.is_empty() || name.chars().count() as u64 > MAX_SECTION_NAME_LEN {
+            return Err(error(
+                "invalid_name",
+                format!("section names must be 1 to {} characters", MAX_SECTION_NAME_LEN),
+            ));
+        }
+        if name != section.name {
+            return Err(error("invalid_name", "section names must not start or end with whitespace"));
+        }
+        if !names.insert(name) {
+            return Err(error("duplicate_name", format!("section '{}' is listed twice", name)));
+        }
+        if section.seats == 0 || section.seats > MAX_SEATS_PER_SECTION {
+            return Err(error(
+                "invalid_seats",
+                format!("sections need 1 to {} seats", MAX_SEATS_PER_SECTION),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The seat map of an event; empty when it has none
+pub async fn load<'e, E>(executor: E, event_id: Uuid) -> Result<SeatMap, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query_as::<_, (sqlx::types::Json<Vec<Section>>, DateTime<Utc>)>(
+        "SELECT sections, updated_at FROM event_seat_maps WHERE event_id = ?"
+    )
+    .bind(event_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(match row {
+        Some((sections, updated_at)) => SeatMap { event_id, sections: sections.0, updated_at: Some(updated_at) },
+        None => SeatMap { event_id, sections: Vec::new(), updated_at: None },
+    })
+}
+
+/// Replace an event's seat map, dropping assignments to seats it no longer
+/// has; an empty map removes it. Returns the map and the number of dropped
+/// assignments.
+pub async fn replace(
+    pool: &DbPool,
+    event_id: Uuid,
+    sections: &[Section],
+    now: DateTime<Utc>,
+) -> Result<(SeatMap, u64), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if sections.is_empty() {
+        sqlx::query("DELETE FROM event_seat_maps WHERE event_id = ?")
+            .bind(event_id)
+            .execute(&mut *tx)
+            .await?;
+    } else {
+        sqlx::query(
+            "INSERT INTO event_seat_maps (event_id, sections, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT (event_id) DO UPDATE SET sections = excluded.sections, updated_at = excluded.updated_at"
+        )
+        .bind(event_id)
+        .bind(sqlx::types::Json(sections))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let dropped = sqlx::query(
+        "DELETE FROM seat_assignments
+         WHERE event_id = ?1
+           AND NOT EXISTS (
+               SELECT 1 FROM json_each(?2) s
+               WHERE json_extract(s.value, '$.name') = seat_assignments.section
+                 AND seat_assignments.seat <= json_extract(s.value, '$.seats')
+           )"
+    )
+    .bind(event_id)
+    .bind(sqlx::types::Json(sections))
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let map = load(&mut *tx, event_id).await?;
+    tx.commit().await?;
+    Ok((map, dropped))
+}
+
+/// The seat of a participant, if they were given one
+pub async fn find<'e, E>(executor: E, participant_id: Uuid) -> Result<Option<SeatAssignment>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, SeatAssignment>(
+        "SELECT participant_id, event_id, section, seat, assigned_at FROM seat_assignments WHERE participant_id = ?"
+    )
+    .bind(participant_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Who occupies a seat, other than `participant_id`
+pub async fn occupant(
+    pool: &DbPool,
+    event_id: Uuid,
+    section: &str,
+    seat: u32,
+    participant_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(&format!(
+        "SELECT a.participant_id FROM seat_assignments a
+         JOIN participants p ON p.id = a.participant_id
+         WHERE a.event_id = ? AND a.section = ? AND a.seat = ? AND a.participant_id != ?
+           AND p.status IN {}",
+        SEATED_STATUSES
+    ))
+    .bind(event_id)
+    .bind(section)
+    .bind(seat)
+    .bind(participant_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Give `participant_id` a seat, moving them if they already have one. A
+/// seat left behind by a participant without a place is taken over; one
+/// occupied by anyone else fails with a unique constraint violation.
+pub async fn assign(
+    pool: &DbPool,
+    event_id: Uuid,
+    participant_id: Uuid,
+    section: &str,
+    seat: u32,
+    now: DateTime<Utc>,
+) -> Result<SeatAssignment, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(&format!(
+        "DELETE FROM seat_assignments
+         WHERE event_id = ? AND section = ? AND seat = ?
+           AND participant_id IN (SELECT id FROM participants WHERE status NOT IN {})",
+        SEATED_STATUSES
+    ))
+    .bind(event_id)
+    .bind(section)
+    .bind(seat)
+    .execute(&mut *tx)
+    .await?;
+
+    let assignment = sqlx::query_as::<_, SeatAssignment>(
+        "INSERT INTO seat_assignments (participant_id, event_id, section, seat, assigned_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT (participant_id) DO UPDATE SET
+             section = excluded.section,
+             seat = excluded.seat,
+             assigned_at = excluded.assigned_at
+         RETURNING participant_id, event_id, section, seat, assigned_at"
+    )
+    .bind(participant_id)
+    .bind(event_id)
+    .bind(section)
+    .bind(seat)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(assignment)
+}
+
+/// Take a participant's seat away; returns whether they had one
+pub async fn release(pool: &DbPool, participant_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM seat_assignments WHERE participant_id = ?")
+        .bind(participant_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Who sits where under `map`
+pub async fn occupancy(pool: &DbPool, map: &SeatMap) -> Result<Occupancy, sqlx::Error> {
+    let guests = sqlx::query_as::<_, (String, i64, Uuid, PiiText)>(&format!(
+        "SELECT a.section, a.seat, a.participant_id, p.name
+         FROM seat_assignments a
+         JOIN participants p ON p.id = a.participant_id
+         WHERE a.event_id = ? AND p.status IN {}
+         ORDER BY a.section, a.seat",
+        SEATED_STATUSES
+    ))
+    .bind(map.event_id)
+    .fetch_all(pool)
+    .await?;
+
+    let unseated = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT count(*) FROM participants p
+         WHERE p.event_id = ? AND p.status IN {}
+           AND NOT EXISTS (SELECT 1 FROM seat_assignments a WHERE a.participant_id = p.id)",
+        SEATED_STATUSES
+    ))
+    .bind(map.event_id)
+    .fetch_one(pool)
+    .await?;
+
+    let sections: Vec<SectionOccupancy> = map
+        .sections
+        .iter()
+        .map(|section| {
+            let guests: Vec<SeatedGuest> = guests
+                .iter()
+                .filter(|(name, ..)| *name == section.name)
+                .map(|(_, seat, participant_id, name)| SeatedGuest {
+                    seat: *seat,
+                    participant_id: *participant_id,
+                    name: name.0.clone(),
+                })
+                .collect();
+            SectionOccupancy {
+                name: section.name.clone(),
+                kind: section.kind,
+                seats: section.seats,
+                occupied: guests.len() as i64,
+                guests,
+            }
+        })
+        .collect();
+
+    Ok(Occupancy {
+        event_id: map.event_id,
+        capacity: map.capacity(),
+        occupied: sections.iter().map(|s| s.occupied).sum(),
+        unseated,
+        sections,
+    })
+}
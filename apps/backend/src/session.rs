@@ -0,0 +1,165 @@
+//! Cookie-backed sessions for the embedded admin panel.
+//!
+//! Page scripts cannot hold the admin token without exposing it, so the
+//! panel exchanges it once at `POST /api/admin/session` for an HttpOnly,
+//! SameSite=Strict cookie. Sessions live in SQLite so any instance can
+//! serve them. A request carrying a valid session cookie acts as admin;
+//! if it changes state it must also echo the session's CSRF token in
+//! `X-CSRF-Token`, or it is rejected.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::auth;
+use crate::db::DbPool;
+use crate::error::{api_error, internal_error};
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Cookie carrying the session id
+pub const SESSION_COOKIE: &str = "admin_session";
+
+/// Header echoing the session's CSRF token on state-changing requests
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Login and logout endpoint
+pub const SESSION_PATH: &str = "/api/admin/session";
+
+/// An active admin session; inserted as a request extension by `load_session`
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AdminSession {
+    #[serde(skip)]
+    pub id: String,
+    pub csrf_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Store a new session valid until `expires_at`
+pub async fn create(
+    pool: &DbPool,
+    session: &AdminSession,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO admin_sessions (id, csrf_token, created_at, expires_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(&session.id)
+    .bind(&session.csrf_token)
+    .bind(now)
+    .bind(session.expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// The session with this id, unless unknown or expired
+pub async fn find(pool: &DbPool, id: &str, now: DateTime<Utc>) -> Result<Option<AdminSession>, sqlx::Error> {
+    sqlx::query_as::<_, AdminSession>(
+        "SELECT id, csrf_token, expires_at FROM admin_sessions WHERE id = ? AND expires_at > ?"
+    )
+    .bind(id)
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// End a session
+pub async fn delete(pool: &DbPool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM admin_sessions WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete expired sessions
+pub async fn prune(pool: &DbPool, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM admin_sessions WHERE expires_at <= ?")
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The session id from the request's cookies
+pub fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// `Set-Cookie` value handing out a session for `max_age`
+pub fn session_cookie(id: &str, max_age: chrono::Duration) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/; HttpOnly; Secure; SameSite=Strict; Max-Age={}",
+        SESSION_COOKIE,
+        id,
+        max_age.num_seconds()
+    ))
+    .expect("session ids are header-safe")
+}
+
+/// `Set-Cookie` value removing the session cookie
+pub fn clear_cookie() -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "{}=; Path=/; HttpOnly; Secure; SameSite=Strict; Max-Age=0",
+        SESSION_COOKIE
+    ))
+    .expect("static cookie is header-safe")
+}
+
+/// Whether a request changes state and so needs the CSRF token. Logging
+/// in is exempt: it is authenticated by the admin token itself.
+fn needs_csrf(method: &Method, path: &str) -> bool {
+    let safe = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let login = method == Method::POST && path == SESSION_PATH;
+    !(safe || login)
+}
+
+/// Middleware resolving the session cookie into an `AdminSession`.
+/// Unknown or expired sessions leave the request unauthenticated.
+pub async fn load_session(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(id) = session_id(request.headers()).map(str::to_string) else {
+        return next.run(request).await;
+    };
+
+    let session = match find(&state.db_pool, &id, state.clock.now()).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return next.run(request).await,
+        Err(e) => {
+            tracing::error!("Failed to load admin session: {}", e);
+            return internal_error().into_response();
+        }
+    };
+
+    if needs_csrf(request.method(), request.uri().path()) {
+        let valid = request
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|provided| auth::constant_time_eq(provided.as_bytes(), session.csrf_token.as_bytes()));
+        if !valid {
+            return api_error(StatusCode::FORBIDDEN, "CSRF token missing or invalid").into_response();
+        }
+    }
+
+    request.extensions_mut().insert(session);
+    next.run(request).await
+}
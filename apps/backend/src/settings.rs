@@ -0,0 +1,130 @@
+//! Runtime settings that admins can change without a redeploy.
+//!
+//! Each setting group is stored as a JSON document in the `settings` table
+//! under a fixed key. Background tasks re-read their group on every run,
+//! so a change made through the admin API (on any instance) takes effect
+//! on the next cycle.
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::Sqlite;
+use validator::Validate;
+
+use crate::db::DbPool;
+use crate::models::EventScope;
+
+/// Key of the retention policy document
+pub const RETENTION_KEY: &str = "retention";
+
+/// Key of the event list settings document
+pub const EVENT_LIST_KEY: &str = "event_list";
+
+/// How long housekeeping keeps old rows around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Age after which change notifications are deleted (1 minute to 7 days)
+    #[validate(range(min = 60, max = 604800, code = "out_of_range"))]
+    pub notification_retention_secs: i64,
+    /// Age after which audit log entries are deleted (1 day to 10 years)
+    #[validate(range(min = 1, max = 3650, code = "out_of_range"))]
+    pub audit_retention_days: i64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            notification_retention_secs: 3600,
+            audit_retention_days: 365,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn notification_retention(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.notification_retention_secs)
+    }
+
+    pub fn audit_retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.audit_retention_days)
+    }
+}
+
+/// How the public event list behaves when the request does not say
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventListSettings {
+    /// Scope of `GET /api/events` without `?scope=`
+    pub default_scope: EventScope,
+}
+
+/// Load a setting group, falling back to its default when unset or unreadable
+pub async fn get<T>(pool: &DbPool, key: &str) -> Result<T, sqlx::Error>
+where
+    T: DeserializeOwned + Default,
+{
+    let value = sqlx::query_scalar::<_, String>("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(match value {
+        Some(value) => serde_json::from_str(&value).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable setting {}: {}", key, e);
+            T::default()
+        }),
+        None => T::default(),
+    })
+}
+
+/// Store a setting group, replacing any previous value
+pub async fn put<'e, E, T>(executor: E, key: &str, value: &T, now: DateTime<Utc>) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+    T: Serialize,
+{
+    let value = serde_json::to_string(value).expect("settings serialize to JSON");
+
+    sqlx::query(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+    )
+    .bind(key)
+    .bind(value)
+    .bind(now)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// The current retention policy
+pub async fn retention(pool: &DbPool) -> Result<RetentionPolicy, sqlx::Error> {
+    get(pool, RETENTION_KEY).await
+}
+
+/// The current event list settings
+pub async fn event_list(pool: &DbPool) -> Result<EventListSettings, sqlx::Error> {
+    get(pool, EVENT_LIST_KEY).await
+}
+
+/// A random secret kept under `key`, created by whichever instance needs
+/// it first so every instance signs with the same one
+pub async fn shared_secret(pool: &DbPool, key: &str, now: DateTime<Utc>) -> Result<String, sqlx::Error> {
+    let generated = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    sqlx::query(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(key) DO NOTHING"
+    )
+    .bind(key)
+    .bind(serde_json::to_string(&generated).expect("secret serializes"))
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    let stored = sqlx::query_scalar::<_, String>("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_one(pool)
+        .await?;
+    serde_json::from_str(&stored).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
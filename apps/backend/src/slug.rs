@@ -0,0 +1,85 @@
+//! URL-friendly event slugs, such as `summer-meetup-2026`.
+//!
+//! An event gets its slug from the title when it is created and keeps it
+//! when the title changes, so published links stay valid. Slugs are unique;
+//! a title whose slug is taken gets the first free `-2`, `-3`, ... suffix.
+
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Longest slug generated from a title, before any suffix
+pub const MAX_SLUG_LEN: usize = 80;
+
+/// Lowercase ASCII letters and digits of `title`, with German umlauts and
+/// ß spelled out and every other run of characters turned into one `-`
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars().flat_map(char::to_lowercase) {
+        let spelled = match c {
+            'ä' => "ae",
+            'ö' => "oe",
+            'ü' => "ue",
+            'ß' => "ss",
+            c if c.is_ascii_alphanumeric() => {
+                slug.push(c);
+                continue;
+            }
+            _ => "-",
+        };
+        if spelled == "-" {
+            if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        } else {
+            slug.push_str(spelled);
+        }
+    }
+
+    let mut slug: String = slug.chars().take(MAX_SLUG_LEN).collect();
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("event");
+    }
+    slug
+}
+
+/// A slug for `title` no event has yet. Run it in the transaction creating
+/// the event; the unique index rejects a concurrent event that picked the
+/// same slug.
+pub async fn unique_slug(conn: &mut SqliteConnection, title: &str) -> Result<String, sqlx::Error> {
+    let base = slugify(title);
+    let taken = sqlx::query_scalar::<_, String>("SELECT slug FROM events WHERE slug = ?1 OR slug LIKE ?1 || '-%'")
+        .bind(&base)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if !taken.contains(&base) {
+        return Ok(base);
+    }
+    let slug = (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("a free suffix exists");
+    Ok(slug)
+}
+
+/// Give events from before slugs existed one, such as those restored from
+/// an older export. Returns how many were missing.
+pub async fn backfill(conn: &mut SqliteConnection) -> Result<usize, sqlx::Error> {
+    let missing = sqlx::query_as::<_, (Uuid, String)>("SELECT id, title FROM events WHERE slug IS NULL ORDER BY created_at")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (id, title) in &missing {
+        let slug = unique_slug(&mut *conn, title).await?;
+        sqlx::query("UPDATE events SET slug = ? WHERE id = ?")
+            .bind(slug)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(missing.len())
+}
@@ -0,0 +1,95 @@
+//! Disk usage of the data directory.
+//!
+//! Everything the server keeps lives under DATA_DIR: the SQLite database
+//! with its WAL, backups in `backups/` and export files in `exports/`. On
+//! Railway that directory is a volume of fixed size, which the server
+//! cannot see; VOLUME_SIZE_MB tells it, so `GET /api/admin/storage` can
+//! report how full the volume is before writes start failing.
+//!
+//! Usage is measured by walking the directory on request, so it is always
+//! current and needs no bookkeeping when files come and go.
+
+use serde::Serialize;
+use std::path::Path;
+
+/// Share of the volume above which usage is flagged
+pub const WARN_RATIO: f64 = 0.9;
+
+/// Bytes used under DATA_DIR, by what uses them
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageUsage {
+    pub data_dir: String,
+    /// The database file with its WAL and shared-memory files
+    pub database_bytes: u64,
+    pub backups_bytes: u64,
+    pub exports_bytes: u64,
+    /// Anything else found in the directory
+    pub other_bytes: u64,
+    pub total_bytes: u64,
+    /// Configured size of the volume (VOLUME_SIZE_MB), if known
+    pub volume_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+    /// `total_bytes` as a share of the volume, rounded to 0.1%
+    pub used_percent: Option<f64>,
+    /// Usage is above `WARN_RATIO` of the volume
+    pub near_capacity: bool,
+}
+
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+fn measure(data_dir: &str) -> std::io::Result<StorageUsage> {
+    let mut usage = StorageUsage {
+        data_dir: data_dir.to_string(),
+        ..StorageUsage::default()
+    };
+
+    let entries = match std::fs::read_dir(data_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(usage),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let size = if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        let bucket = match name.as_str() {
+            "data.db" | "data.db-wal" | "data.db-shm" => &mut usage.database_bytes,
+            "backups" => &mut usage.backups_bytes,
+            "exports" => &mut usage.exports_bytes,
+            _ => &mut usage.other_bytes,
+        };
+        *bucket += size;
+        usage.total_bytes += size;
+    }
+
+    Ok(usage)
+}
+
+/// Measure `data_dir` against a volume of `volume_bytes`
+pub async fn usage(data_dir: &str, volume_bytes: Option<u64>) -> std::io::Result<StorageUsage> {
+    let dir = data_dir.to_string();
+    let mut usage = tokio::task::spawn_blocking(move || measure(&dir))
+        .await
+        .map_err(std::io::Error::other)??;
+
+    if let Some(volume) = volume_bytes.filter(|v| *v > 0) {
+        let ratio = usage.total_bytes as f64 / volume as f64;
+        usage.volume_bytes = Some(volume);
+        usage.available_bytes = Some(volume.saturating_sub(usage.total_bytes));
+        usage.used_percent = Some((ratio * 1000.0).round() / 10.0);
+        usage.near_capacity = ratio >= WARN_RATIO;
+    }
+
+    Ok(usage)
+}
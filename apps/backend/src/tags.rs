@@ -0,0 +1,53 @@
+//! Tags categorizing events, such as "workshop" or "meetup".
+//!
+//! Tag names live once in `tags`; `event_tags` links them to events. Events
+//! carry their tags as a sorted list of names, read with a subquery in the
+//! same statement as the event itself. Tags no event uses any more are kept,
+//! so their ids stay stable across edits.
+
+use chrono::{DateTime, Utc};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Replace the tags of an event with `tags`, which must be normalized
+/// (`validation::normalize_tags`). Run it in the transaction writing the
+/// event.
+pub async fn set_event_tags(
+    conn: &mut SqliteConnection,
+    event_id: Uuid,
+    tags: &[String],
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM event_tags WHERE event_id = ?")
+        .bind(event_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for tag in tags {
+        sqlx::query("INSERT INTO tags (name, created_at) VALUES (?, ?) ON CONFLICT (name) DO NOTHING")
+            .bind(tag)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("INSERT INTO event_tags (event_id, tag_id) SELECT ?, id FROM tags WHERE name = ?")
+            .bind(event_id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Tags of one event, sorted by name
+pub async fn event_tags<'e, E>(executor: E, event_id: Uuid) -> Result<Vec<String>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query_scalar::<_, String>(
+        "SELECT t.name FROM event_tags et JOIN tags t ON t.id = et.tag_id WHERE et.event_id = ? ORDER BY t.name"
+    )
+    .bind(event_id)
+    .fetch_all(executor)
+    .await
+}
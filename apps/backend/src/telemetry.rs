@@ -0,0 +1,143 @@
+//! Opt-in anonymous usage reporting.
+//!
+//! When `TELEMETRY_ENDPOINT` is set, a background task periodically POSTs
+//! aggregate counts for this deployment. Reports carry no names, emails or
+//! event content; deployments are told apart by a random id generated on
+//! first use and kept in the settings table.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::clock::SharedClock;
+use crate::db::DbPool;
+use crate::settings;
+
+/// Settings key of the anonymous deployment id
+const DEPLOYMENT_ID_KEY: &str = "deployment_id";
+
+/// Where and how often to report; disabled unless an endpoint is set
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryConfig {
+    /// Collector URL (TELEMETRY_ENDPOINT); reporting is off when unset
+    pub endpoint: Option<String>,
+    /// Time between reports (TELEMETRY_INTERVAL_HOURS, default 24)
+    pub interval: Duration,
+}
+
+impl TelemetryConfig {
+    /// Read settings from TELEMETRY_* environment variables
+    pub fn from_env() -> Self {
+        let endpoint = std::env::var("TELEMETRY_ENDPOINT").ok().filter(|e| !e.is_empty());
+        let interval_hours: u64 = std::env::var("TELEMETRY_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+
+        Self {
+            endpoint,
+            interval: Duration::from_secs(interval_hours * 3600),
+        }
+    }
+}
+
+/// Aggregate usage of one deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub deployment_id: Uuid,
+    pub version: String,
+    pub event_count: i64,
+    pub participant_count: i64,
+    /// Registrations since `period_start`
+    pub registrations_in_period: i64,
+    pub period_start: DateTime<Utc>,
+    pub reported_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DeploymentId(Option<Uuid>);
+
+/// The random id of this deployment, created on first use
+pub async fn deployment_id(pool: &DbPool, now: DateTime<Utc>) -> Result<Uuid, sqlx::Error> {
+    if let DeploymentId(Some(id)) = settings::get(pool, DEPLOYMENT_ID_KEY).await? {
+        return Ok(id);
+    }
+
+    // Several instances may race on first start; the first write wins
+    let id = Uuid::new_v4();
+    sqlx::query("INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES (?, ?, ?)")
+        .bind(DEPLOYMENT_ID_KEY)
+        .bind(serde_json::to_string(&DeploymentId(Some(id))).expect("uuid serializes"))
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    let DeploymentId(stored) = settings::get(pool, DEPLOYMENT_ID_KEY).await?;
+    Ok(stored.unwrap_or(id))
+}
+
+/// Gather the aggregates for a report covering `period_start..now`
+pub async fn collect(
+    pool: &DbPool,
+    period_start: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<UsageReport, sqlx::Error> {
+    let event_count = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM events")
+        .fetch_one(pool)
+        .await?;
+    let participant_count = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM participants")
+        .fetch_one(pool)
+        .await?;
+    let registrations_in_period = sqlx::query_scalar::<_, i64>(
+        "SELECT count(*) FROM participants WHERE registered_at >= ?"
+    )
+    .bind(period_start)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(UsageReport {
+        deployment_id: deployment_id(pool, now).await?,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        event_count,
+        participant_count,
+        registrations_in_period,
+        period_start,
+        reported_at: now,
+    })
+}
+
+/// Periodically send usage reports; returns immediately when disabled
+pub async fn start_reporter(pool: DbPool, clock: SharedClock, config: TelemetryConfig) {
+    let Some(endpoint) = config.endpoint else {
+        return;
+    };
+    info!("Anonymous usage reporting enabled, sending to {}", endpoint);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build HTTP client");
+    let mut period_start = clock.now();
+
+    loop {
+        tokio::time::sleep(config.interval).await;
+
+        let now = clock.now();
+        let report = match collect(&pool, period_start, now).await {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("Failed to collect usage report: {}", e);
+                continue;
+            }
+        };
+
+        match client.post(&endpoint).json(&report).send().await {
+            Ok(response) if response.status().is_success() => period_start = now,
+            Ok(response) => warn!("Usage report rejected with status {}", response.status()),
+            Err(e) => warn!("Failed to send usage report: {}", e),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Shared helpers for integration tests and benchmarks.
+//!
+//! Enabled with the `testing` feature so downstream tests build state and
+//! routers the same way instead of copy-pasting them.
+
+use axum::{body::Body, Router};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+use crate::broadcaster::{Broadcaster, Channel, ServerEvent};
+use crate::cache::AppCache;
+use crate::clock::SystemClock;
+use crate::config::Config;
+use crate::ids::IdGenerator;
+use crate::instance::Instance;
+use crate::concurrency::ConcurrencyLimits;
+use crate::load_shed::LoadShedder;
+use crate::mailer::{LogMailer, MailError, Mailer, OutgoingMail};
+use crate::reputation::NoReputationCheck;
+use crate::watchdog::TaskHealth;
+use crate::encryption::{self, PiiText};
+use crate::models::{EmailPolicy, Event, EVENT_COLUMNS, Participant};
+use crate::organizers;
+use crate::{db, AppState};
+use crate::domain_events::DomainEventBus;
+use crate::fixtures::{self, Fixture};
+
+/// Key sealing participant names and emails in tests, installed by
+/// `create_test_state` so every test stores them as production would
+pub const PII_KEY: &str = "0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0";
+
+/// Create an app state backed by a temporary SQLite database.
+///
+/// The returned `TempDir` must be kept alive for the duration of the test.
+pub async fn create_test_state() -> (AppState, tempfile::TempDir) {
+    encryption::install(&PII_KEY.parse().unwrap()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let db_path_str = db_path.to_str().unwrap();
+
+    let db_pool = db::create_pool(db_path_str).await.unwrap();
+    db::initialize_tables(&db_pool).await.unwrap();
+
+    let state = AppState {
+        db_pool,
+        broadcaster: Broadcaster::new(),
+        domain_events: DomainEventBus::new(),
+        cache: AppCache::new(60),
+        load_shedder: LoadShedder::default(),
+        concurrency: ConcurrencyLimits::default(),
+        clock: Arc::new(SystemClock),
+        ids: IdGenerator::default(),
+        config: Arc::new(Config::default()),
+        poller_health: TaskHealth::new(),
+        poller_metrics: db::PollerMetrics::new(),
+        geo: None,
+        email_reputation: Arc::new(NoReputationCheck),
+        mailer: Arc::new(LogMailer),
+        mail_outcomes: Default::default(),
+        instance: Instance::new("test", chrono::Utc::now()),
+        log_level: Default::default(),
+        views: Default::default(),
+    };
+    // Tests have no startup catch-up; readiness only tracks the poller
+    state.instance.mark_ready();
+
+    (state, dir)
+}
+
+/// Create a second instance beside `state`, as another replica of the same
+/// deployment: its own pool on the database file in `dir`, and its own
+/// broadcaster, caches, poller bookkeeping and identity. Only the database
+/// connects the two, so changes reach it through its notification poller.
+pub async fn create_peer_state(state: &AppState, dir: &tempfile::TempDir) -> AppState {
+    let db_pool = db::create_pool(dir.path().join("test.db").to_str().unwrap())
+        .await
+        .unwrap();
+
+    let peer = AppState {
+        db_pool,
+        broadcaster: Broadcaster::new(),
+        domain_events: DomainEventBus::new(),
+        cache: AppCache::new(60),
+        load_shedder: LoadShedder::default(),
+        concurrency: ConcurrencyLimits::default(),
+        poller_health: TaskHealth::new(),
+        poller_metrics: db::PollerMetrics::new(),
+        mail_outcomes: Default::default(),
+        instance: Instance::new("peer", chrono::Utc::now()),
+        log_level: Default::default(),
+        views: Default::default(),
+        ..state.clone()
+    };
+    peer.instance.mark_ready();
+
+    peer
+}
+
+/// Build the application router with the default configuration
+pub fn build_app(state: AppState) -> Router {
+    crate::build_router(state, &Config::default())
+}
+
+/// Extract the JSON body from a response
+pub async fn body_json(response: axum::http::Response<Body>) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Obtain a confirmation token for a destructive request through the API
+pub async fn prepare_confirmation(app: &Router, method: &str, target: &str) -> String {
+    use tower::ServiceExt;
+
+    let body = serde_json::json!({ "method": method, "target": target });
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri("/api/confirmations")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+
+    body_json(response).await["token"].as_str().unwrap().to_string()
+}
+
+/// Publish an event created as a draft through the API, signed in with the
+/// `Authorization` header value `auth`
+pub async fn publish_event(app: &Router, auth: &str, event_id: &str) {
+    use tower::ServiceExt;
+
+    let response = app
+        .clone()
+        .oneshot(
+            axum::http::Request::builder()
+                .method("POST")
+                .uri(format!("/api/events/{}/publish", event_id))
+                .header("Authorization", auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), axum::http::StatusCode::OK);
+}
+
+/// Create an organizer account directly in the database and return the
+/// `Authorization` header value that signs requests in as it. The account
+/// has no usable password, which skips the deliberately slow hashing.
+pub async fn organizer_auth(state: &AppState) -> String {
+    seed_organizer(state).await.1
+}
+
+/// Like `organizer_auth`, also returning the account
+pub async fn seed_organizer(state: &AppState) -> (organizers::Organizer, String) {
+    let now = state.clock.now();
+    let id = state.ids.generate(now);
+    let organizer = organizers::create(
+        &state.db_pool,
+        id,
+        "Test Organizer",
+        &format!("organizer-{}@example.com", id.simple()),
+        "unusable",
+        now,
+    )
+    .await
+    .unwrap();
+
+    let key = organizers::signing_key(&state.db_pool, state.config.jwt_secret.as_deref(), now)
+        .await
+        .unwrap();
+    let token = organizers::issue_token(&key, id, now, now + state.config.organizer_token_ttl);
+    (organizer, format!("Bearer {}", token))
+}
+
+/// Insert an event directly into the database, bypassing the API
+pub async fn seed_event(state: &AppState, title: &str, max_participants: Option<i32>) -> Event {
+    let now = state.clock.now();
+    let start = now + chrono::Duration::days(7);
+
+    sqlx::query_as::<_, Event>(&format!(
+        "INSERT INTO events (id, title, description, start_time, end_time, location, max_participants, created_at, updated_at)
+         VALUES (?, ?, NULL, ?, ?, NULL, ?, ?, ?)
+         RETURNING {}",
+        EVENT_COLUMNS
+    ))
+    .bind(state.ids.generate(now))
+    .bind(title)
+    .bind(start)
+    .bind(start + chrono::Duration::hours(2))
+    .bind(max_participants)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap()
+}
+
+/// Make `organizer_id` the owner of an event, as if they had created it
+pub async fn assign_owner(state: &AppState, event_id: Uuid, organizer_id: Uuid) {
+    sqlx::query("UPDATE events SET organizer_id = ? WHERE id = ?")
+        .bind(organizer_id)
+        .bind(event_id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+}
+
+/// Insert a registered participant directly into the database, bypassing
+/// the API; the email is normalized and keyed as under the default strict
+/// email policy
+pub async fn seed_participant(state: &AppState, event_id: Uuid, name: &str, email: &str) -> Participant {
+    let now = state.clock.now();
+    let email = crate::email::normalize(email);
+
+    sqlx::query_as::<_, Participant>(
+        "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at, dedupe_key, email_index)
+         VALUES (?1, ?2, ?3, ?4, 'registered', ?5, ?5, ?6, ?7)
+         RETURNING id, event_id, name, email, status, registered_at, updated_at, checked_in_at, metadata"
+    )
+    .bind(state.ids.generate(now))
+    .bind(event_id)
+    .bind(PiiText(name.to_string()))
+    .bind(PiiText(email.clone()))
+    .bind(now)
+    .bind(EmailPolicy::Strict.dedupe_key(&email, name))
+    .bind(encryption::blind_index(&email))
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap()
+}
+
+/// Start the notification poller for this state so writes reach the broadcaster
+pub async fn spawn_poller(state: &AppState) -> tokio::task::JoinHandle<()> {
+    let last_id = Arc::new(Mutex::new(db::get_max_notification_id(&state.db_pool).await));
+    tokio::spawn(db::start_notification_poller(
+        state.db_pool.clone(),
+        state.broadcaster.clone(),
+        state.cache.clone(),
+        state.clock.clone(),
+        last_id,
+        state.poller_health.clone(),
+        state.poller_metrics.clone(),
+    ))
+}
+
+/// Wait for the next broadcast on `channel`, skipping other channels, and
+/// return its parsed JSON payload. Panics if nothing arrives within `timeout`.
+pub async fn expect_broadcast(
+    receiver: &mut broadcast::Receiver<ServerEvent>,
+    channel: Channel,
+    timeout: Duration,
+) -> Value {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        let event = tokio::time::timeout_at(deadline, receiver.recv())
+            .await
+            .unwrap_or_else(|_| panic!("No broadcast on '{}' within {:?}", channel, timeout))
+            .expect("Broadcast channel closed");
+
+        if event.channel == channel {
+            return serde_json::from_str(&event.payload).expect("SSE payload is not valid JSON");
+        }
+    }
+}
+
+/// Directory of the recorded response fixtures (see `fixtures`)
+pub fn fixtures_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// Compare a response with the recorded fixture `name`, after
+/// canonicalizing it. With `UPDATE_FIXTURES=1` the fixture is recorded
+/// instead.
+pub fn assert_fixture(name: &str, mut fixture: Fixture) {
+    fixtures::canonicalize(&mut fixture.body);
+    let path = fixtures_dir().join(format!("{}.json", name));
+
+    if std::env::var("UPDATE_FIXTURES").is_ok_and(|v| v == "1") {
+        std::fs::create_dir_all(fixtures_dir()).expect("Failed to create fixtures directory");
+        std::fs::write(&path, fixture.to_file()).expect("Failed to write fixture");
+        return;
+    }
+
+    let recorded = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No fixture {}; record it with UPDATE_FIXTURES=1", path.display()));
+    let recorded: Fixture = serde_json::from_str(&recorded).expect("Fixture is not valid JSON");
+    assert_eq!(
+        recorded, fixture,
+        "Response drifted from {}; re-record with UPDATE_FIXTURES=1 if the change is intended",
+        path.display()
+    );
+}
+
+/// Mailer that keeps sent mails in memory for assertions
+#[derive(Default)]
+pub struct RecordingMailer {
+    sent: std::sync::Mutex<Vec<OutgoingMail>>,
+    failing: std::sync::atomic::AtomicBool,
+}
+
+impl RecordingMailer {
+    pub fn sent(&self) -> Vec<OutgoingMail> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Fail every send, as during an SMTP outage, until reset
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[axum::async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, mail: &OutgoingMail) -> Result<(), MailError> {
+        if self.failing.load(std::sync::atomic::Ordering::SeqCst) {
+            return Err(MailError("SMTP connect to relay failed: connection refused".to_string()));
+        }
+        self.sent.lock().unwrap().push(mail.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Optional TLS termination for deployments not behind Railway's proxy.
+//!
+//! Enabled when both TLS_CERT_PATH and TLS_KEY_PATH are set. ALPN offers
+//! HTTP/2 first unless HTTP2_ENABLED=false. With TLS_REDIRECT_PORT a second
+//! plain-HTTP listener answers every request with a redirect to HTTPS.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+
+/// Certificate locations and listener options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first
+    pub cert_path: PathBuf,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: PathBuf,
+    /// Advertise h2 via ALPN
+    pub http2: bool,
+    /// Plain-HTTP port redirecting to HTTPS; disabled when None
+    pub redirect_port: Option<u16>,
+}
+
+impl TlsConfig {
+    /// Read TLS_* settings; None unless both cert and key paths are set
+    pub fn from_env() -> Option<Self> {
+        let cert_path = std::env::var("TLS_CERT_PATH").ok().filter(|p| !p.is_empty())?;
+        let key_path = std::env::var("TLS_KEY_PATH").ok().filter(|p| !p.is_empty())?;
+
+        let http2 = std::env::var("HTTP2_ENABLED")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+        let redirect_port = std::env::var("TLS_REDIRECT_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok());
+
+        Some(Self {
+            cert_path: PathBuf::from(cert_path),
+            key_path: PathBuf::from(key_path),
+            http2,
+            redirect_port,
+        })
+    }
+
+    /// ALPN protocols in preference order
+    pub fn alpn_protocols(&self) -> Vec<Vec<u8>> {
+        if self.http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        }
+    }
+}
+
+/// Load the certificate and key into a rustls server configuration
+pub fn server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, String> {
+    let certs = CertificateDer::pem_file_iter(&config.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("failed to read {}: {}", config.cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("no certificates in {}", config.cert_path.display()));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(&config.key_path)
+        .map_err(|e| format!("failed to read {}: {}", config.key_path.display(), e))?;
+
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("invalid certificate or key: {}", e))?;
+    server_config.alpn_protocols = config.alpn_protocols();
+
+    Ok(Arc::new(server_config))
+}
+
+/// Router for the plain-HTTP redirect listener
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(redirect_to_https).with_state(https_port)
+}
+
+/// Permanent redirect to the same host and path over HTTPS
+async fn redirect_to_https(State(https_port): State<u16>, request: Request) -> Response {
+    let Some(host) = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse::<axum::http::uri::Authority>().ok())
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let authority = match https_port {
+        443 => host.host().to_string(),
+        port => format!("{}:{}", host.host(), port),
+    };
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+
+    match format!("https://{}{}", authority, path_and_query).parse::<Uri>() {
+        Ok(uri) => Redirect::permanent(&uri.to_string()).into_response(),
+        Err(_) => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
@@ -0,0 +1,505 @@
+//! Declarative request validation.
+//!
+//! Payload structs derive `validator::Validate`; the custom rules used by
+//! those derives live here, along with the conversion of validation failures
+//! into field-keyed 422 responses.
+
+use axum::{http::StatusCode, Json};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, SubsecRound, Utc};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use validator::{Validate, ValidateArgs, ValidationError, ValidationErrors};
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::models::CreateEvent;
+
+/// Earliest year accepted for event times
+pub const MIN_YEAR: i32 = 2000;
+/// Latest year accepted for event times
+pub const MAX_YEAR: i32 = 2100;
+
+/// Configurable maximum lengths (in characters) for free-text fields
+#[derive(Debug, Clone)]
+pub struct FieldLimits {
+    pub title: usize,
+    pub description: usize,
+    pub location: usize,
+    pub name: usize,
+    pub email: usize,
+}
+
+impl FieldLimits {
+    /// Hard caps mirrored as length checks in the database; configured
+    /// limits may be lower but never higher
+    pub const HARD_CAPS: FieldLimits = FieldLimits {
+        title: 1000,
+        description: 20_000,
+        location: 1000,
+        name: 1000,
+        email: 320,
+    };
+
+    /// Read limits from MAX_*_LEN environment variables, clamped to the hard caps
+    pub fn from_env() -> Self {
+        fn read(var: &str, default: usize, cap: usize) -> usize {
+            let value = std::env::var(var)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default);
+            if value > cap {
+                tracing::warn!("{}={} exceeds the database limit, using {}", var, value, cap);
+            }
+            value.min(cap)
+        }
+
+        let defaults = Self::default();
+        let caps = Self::HARD_CAPS;
+        Self {
+            title: read("MAX_TITLE_LEN", defaults.title, caps.title),
+            description: read("MAX_DESCRIPTION_LEN", defaults.description, caps.description),
+            location: read("MAX_LOCATION_LEN", defaults.location, caps.location),
+            name: read("MAX_NAME_LEN", defaults.name, caps.name),
+            email: read("MAX_EMAIL_LEN", defaults.email, caps.email),
+        }
+    }
+}
+
+impl Default for FieldLimits {
+    fn default() -> Self {
+        Self {
+            title: 200,
+            description: 5000,
+            location: 200,
+            name: 200,
+            email: 254,
+        }
+    }
+}
+
+/// Sanity bounds on event start times, relative to the deployment
+#[derive(Debug, Clone)]
+pub struct DateBounds {
+    /// Events may not start before this instant (e.g. the deployment date)
+    pub epoch: Option<DateTime<Utc>>,
+    /// Events may not start more than this many years from now
+    pub max_years_ahead: u32,
+    /// Reject events starting in the past
+    pub reject_past: bool,
+    /// How far in the past a start time may lie and still count as "now",
+    /// absorbing client clock skew
+    pub clock_skew: Duration,
+}
+
+impl DateBounds {
+    /// Read bounds from EVENT_EPOCH (RFC 3339), EVENT_MAX_YEARS_AHEAD,
+    /// EVENT_REJECT_PAST_START and EVENT_START_SKEW_SECS
+    pub fn from_env() -> Self {
+        let epoch = std::env::var("EVENT_EPOCH").ok().map(|v| {
+            DateTime::parse_from_rfc3339(&v)
+                .expect("Invalid EVENT_EPOCH")
+                .with_timezone(&Utc)
+        });
+        let max_years_ahead = std::env::var("EVENT_MAX_YEARS_AHEAD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let reject_past = std::env::var("EVENT_REJECT_PAST_START").is_ok_and(|v| v == "true" || v == "1");
+        let clock_skew = std::env::var("EVENT_START_SKEW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::seconds)
+            .unwrap_or(Self::default().clock_skew);
+
+        Self {
+            epoch,
+            max_years_ahead,
+            reject_past,
+            clock_skew,
+        }
+    }
+}
+
+impl Default for DateBounds {
+    fn default() -> Self {
+        Self {
+            epoch: None,
+            max_years_ahead: 5,
+            reject_past: false,
+            clock_skew: Duration::seconds(60),
+        }
+    }
+}
+
+/// Allowed range for the time between start_time and end_time
+#[derive(Debug, Clone)]
+pub struct DurationLimits {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl DurationLimits {
+    /// Read limits from EVENT_MIN_DURATION_MINUTES and EVENT_MAX_DURATION_DAYS
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let min = std::env::var("EVENT_MIN_DURATION_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::minutes)
+            .unwrap_or(defaults.min);
+        let max = std::env::var("EVENT_MAX_DURATION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::days)
+            .unwrap_or(defaults.max);
+
+        Self { min, max }
+    }
+}
+
+impl Default for DurationLimits {
+    fn default() -> Self {
+        Self {
+            min: Duration::minutes(5),
+            max: Duration::days(30),
+        }
+    }
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// Reject empty or whitespace-only strings
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("blank", "must not be blank"));
+    }
+    Ok(())
+}
+
+fn max_len(value: &str, max: usize) -> Result<(), ValidationError> {
+    if value.chars().count() > max {
+        let mut err = error("too_long", format!("must be at most {} characters", max));
+        err.add_param(Cow::Borrowed("max"), &max);
+        return Err(err);
+    }
+    Ok(())
+}
+
+pub fn title_len(value: &str, limits: &FieldLimits) -> Result<(), ValidationError> {
+    max_len(value, limits.title)
+}
+
+pub fn description_len(value: &str, limits: &FieldLimits) -> Result<(), ValidationError> {
+    max_len(value, limits.description)
+}
+
+pub fn location_len(value: &str, limits: &FieldLimits) -> Result<(), ValidationError> {
+    max_len(value, limits.location)
+}
+
+pub fn name_len(value: &str, limits: &FieldLimits) -> Result<(), ValidationError> {
+    max_len(value, limits.name)
+}
+
+pub fn email_len(value: &str, limits: &FieldLimits) -> Result<(), ValidationError> {
+    max_len(value, limits.email)
+}
+
+/// Longest accepted privacy policy version identifier
+pub const MAX_POLICY_VERSION_LEN: usize = 64;
+
+pub fn policy_version_len(value: &str) -> Result<(), ValidationError> {
+    max_len(value, MAX_POLICY_VERSION_LEN)
+}
+
+/// Longest accepted referrer code or campaign name
+pub const MAX_TRACKING_CODE_LEN: usize = 64;
+
+/// Referrer codes and campaign names: letters, digits, `-`, `_` and `.`
+pub fn tracking_code(value: &str) -> Result<(), ValidationError> {
+    max_len(value, MAX_TRACKING_CODE_LEN)?;
+    if !value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+        return Err(error("invalid_code", "may only contain letters, digits, '-', '_' and '.'"));
+    }
+    Ok(())
+}
+
+/// Most tags one event may carry
+pub const MAX_TAGS: usize = 20;
+/// Longest accepted tag name
+pub const MAX_TAG_LEN: usize = 40;
+
+/// Tags as stored: trimmed, lowercase, sorted and without duplicates
+pub fn normalize_tags(tags: &mut Vec<String>) {
+    for tag in tags.iter_mut() {
+        *tag = tag.trim().to_lowercase();
+    }
+    tags.sort_unstable();
+    tags.dedup();
+}
+
+/// Normalized tags: up to MAX_TAGS of letters, digits, `-` and `_`
+pub fn tags(value: &[String]) -> Result<(), ValidationError> {
+    if value.len() > MAX_TAGS {
+        return Err(error("too_many_tags", format!("must have at most {} tags", MAX_TAGS)));
+    }
+    for tag in value {
+        not_blank(tag)?;
+        max_len(tag, MAX_TAG_LEN)?;
+        if !tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_')) {
+            return Err(error("invalid_tag", "tags may only contain letters, digits, '-' and '_'"));
+        }
+    }
+    Ok(())
+}
+
+/// Reject timestamps outside the sane year range
+pub fn sane_date(value: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if !(MIN_YEAR..=MAX_YEAR).contains(&value.year()) {
+        return Err(error(
+            "out_of_range",
+            format!("year must be between {} and {}", MIN_YEAR, MAX_YEAR),
+        ));
+    }
+    Ok(())
+}
+
+/// Oldest accepted age, to catch mistyped years of birth
+pub const MAX_AGE: i32 = 130;
+
+/// Full years of someone born on `date_of_birth` as of `day`
+pub fn age_on(date_of_birth: NaiveDate, day: NaiveDate) -> i32 {
+    let age = day.year() - date_of_birth.year();
+    if (day.month(), day.day()) < (date_of_birth.month(), date_of_birth.day()) {
+        age - 1
+    } else {
+        age
+    }
+}
+
+/// Check a registrant's date of birth against an event's minimum age,
+/// which must be reached by the day the event starts
+pub fn check_age(
+    date_of_birth: Option<NaiveDate>,
+    min_age: Option<i32>,
+    event_day: NaiveDate,
+    today: NaiveDate,
+) -> Result<(), ApiError> {
+    if let Some(date_of_birth) = date_of_birth {
+        if date_of_birth > today || age_on(date_of_birth, today) > MAX_AGE {
+            return Err(field_error("date_of_birth", "out_of_range", "must be a past date of birth".to_string()));
+        }
+    }
+
+    let Some(min_age) = min_age else {
+        return Ok(());
+    };
+    let Some(date_of_birth) = date_of_birth else {
+        return Err(field_error(
+            "date_of_birth",
+            "required",
+            format!("required for events with a minimum age of {}", min_age),
+        ));
+    };
+    if age_on(date_of_birth, event_day) < min_age {
+        return Err(field_error(
+            "date_of_birth",
+            "under_age",
+            format!("must be at least {} years old on the day of the event", min_age),
+        ));
+    }
+    Ok(())
+}
+
+/// Cross-field rule: the event must end after it starts.
+///
+/// Struct-level errors have no field of their own; the `field` param tells
+/// the renderer which field to report them under.
+pub fn event_time_range(event: &CreateEvent) -> Result<(), ValidationError> {
+    if event.end_time <= event.start_time {
+        let mut err = error("end_before_start", "end_time must be after start_time");
+        err.add_param(Cow::Borrowed("field"), &"end_time");
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Normalize client-supplied event times: drop sub-second precision, and
+/// when past starts are rejected, move a new start within the clock skew
+/// tolerance before `now` up to `now`, so "starts now" survives a client
+/// clock running slightly behind ours
+pub fn normalize_event_times(
+    event: &mut CreateEvent,
+    bounds: &DateBounds,
+    now: DateTime<Utc>,
+    previous_start: Option<DateTime<Utc>>,
+) {
+    event.start_time = event.start_time.trunc_subsecs(0);
+    event.end_time = event.end_time.trunc_subsecs(0);
+
+    let now = now.trunc_subsecs(0);
+    if bounds.reject_past
+        && previous_start != Some(event.start_time)
+        && event.start_time < now
+        && event.start_time >= now - bounds.clock_skew
+    {
+        event.start_time = now;
+    }
+}
+
+/// Check an event's start time against the configured bounds.
+///
+/// `previous_start` is the stored start of an event being updated; an
+/// unchanged start is not checked against `now`, so events that already
+/// began stay editable.
+pub fn event_date_bounds(
+    event: &CreateEvent,
+    bounds: &DateBounds,
+    now: DateTime<Utc>,
+    previous_start: Option<DateTime<Utc>>,
+) -> Result<(), ValidationError> {
+    if bounds.reject_past
+        && previous_start != Some(event.start_time)
+        && event.start_time < now - bounds.clock_skew
+    {
+        return Err(error("in_past", "start_time must not be in the past"));
+    }
+
+    if let Some(epoch) = bounds.epoch {
+        if event.start_time < epoch {
+            return Err(error(
+                "before_epoch",
+                format!("start_time must not be before {}", epoch.to_rfc3339()),
+            ));
+        }
+    }
+
+    let latest = now
+        .checked_add_months(Months::new(bounds.max_years_ahead.saturating_mul(12)))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    if event.start_time > latest {
+        return Err(error(
+            "too_far_ahead",
+            format!("start_time must be within {} years from now", bounds.max_years_ahead),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check the event duration against the configured limits. Runs only when
+/// end_time is after start_time; the time-range rule reports that case.
+pub fn event_duration(event: &CreateEvent, limits: &DurationLimits) -> Result<(), ValidationError> {
+    let duration = event.end_time - event.start_time;
+    if duration <= Duration::zero() {
+        return Ok(());
+    }
+
+    if duration < limits.min {
+        return Err(error(
+            "duration_too_short",
+            format!("event must last at least {} minutes", limits.min.num_minutes()),
+        ));
+    }
+    if duration > limits.max {
+        return Err(error(
+            "duration_too_long",
+            format!("event must not last longer than {} days", limits.max.num_days()),
+        ));
+    }
+
+    Ok(())
+}
+
+fn render_error(error: &ValidationError) -> Value {
+    let message = error
+        .message
+        .as_ref()
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| error.code.to_string());
+    json!({ "code": error.code, "message": message })
+}
+
+/// Convert validation failures into a 422 response keyed by field name
+pub fn validation_error(errors: ValidationErrors) -> ApiError {
+    let mut fields: Map<String, Value> = Map::new();
+
+    for (field, errs) in errors.field_errors() {
+        for err in errs {
+            let key = match (field.as_ref(), err.params.get("field")) {
+                ("__all__", Some(Value::String(target))) => target.clone(),
+                _ => field.to_string(),
+            };
+            let entry = fields.entry(key).or_insert_with(|| Value::Array(Vec::new()));
+            if let Value::Array(list) = entry {
+                list.push(render_error(err));
+            }
+        }
+    }
+
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": "Validation failed", "fields": fields })),
+    )
+}
+
+/// A 422 response for a single failed check on `field`, for rules that
+/// run outside the derive (e.g. async lookups)
+pub fn field_error(field: &'static str, code: &'static str, message: String) -> ApiError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new(code).with_message(message.into()));
+    validation_error(errors)
+}
+
+/// Validate a payload, converting failures into a 422 response
+pub fn validate<T: Validate>(payload: &T) -> Result<(), ApiError> {
+    payload.validate().map_err(validation_error)
+}
+
+/// Validate an event payload: field rules, duration limits and the date
+/// bounds, which admins may bypass. `previous_start` is the stored start
+/// time when updating an event.
+pub fn validate_event(
+    event: &CreateEvent,
+    config: &Config,
+    now: DateTime<Utc>,
+    is_admin: bool,
+    previous_start: Option<DateTime<Utc>>,
+) -> Result<(), ApiError> {
+    let mut errors = match event.validate_with_args(&config.field_limits) {
+        Ok(()) => ValidationErrors::new(),
+        Err(errors) => errors,
+    };
+
+    if let Err(err) = event_duration(event, &config.duration_limits) {
+        errors.add("end_time", err);
+    }
+
+    match (event.latitude, event.longitude) {
+        (Some(_), None) => errors.add("longitude", error("required", "required when latitude is given")),
+        (None, Some(_)) => errors.add("latitude", error("required", "required when longitude is given")),
+        _ => {}
+    }
+
+    if !is_admin {
+        if let Err(err) = event_date_bounds(event, &config.date_bounds, now, previous_start) {
+            errors.add("start_time", err);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validation_error(errors))
+    }
+}
+
+/// Validate a payload whose rules depend on the configured field limits
+pub fn validate_with_limits<'a, T>(payload: &'a T, limits: &'a FieldLimits) -> Result<(), ApiError>
+where
+    T: ValidateArgs<'a, Args = &'a FieldLimits>,
+{
+    payload.validate_with_args(limits).map_err(validation_error)
+}
@@ -0,0 +1,106 @@
+//! Who may see an event's participant list, and which fields.
+//!
+//! Each event's `participant_visibility` decides who may list its
+//! participants. The viewer is an organizer when acting as admin or as the
+//! event's owner, impersonated or not, a participant when sending the
+//! access token from their registration in `X-Participant-Token`, and the
+//! public otherwise.
+//! Handlers render participants through `ParticipantView`, which drops the
+//! email for everyone but organizers following `MaskPolicy::for_viewer`.
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::masking::{MaskPolicy, Pii};
+use crate::metadata::Metadata;
+use crate::models::{Participant, ParticipantStatus, ParticipantVisibility};
+
+/// Header carrying a participant's access token
+pub const PARTICIPANT_TOKEN_HEADER: &str = "x-participant-token";
+
+/// Who is looking at a participant list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewer {
+    Organizer,
+    /// A registered, not cancelled participant of the event
+    Participant,
+    Public,
+}
+
+impl Viewer {
+    /// Whether this viewer may list participants under `visibility`
+    pub fn can_list(self, visibility: ParticipantVisibility) -> bool {
+        match visibility {
+            ParticipantVisibility::Public => true,
+            ParticipantVisibility::Participants => self != Viewer::Public,
+            ParticipantVisibility::Organizers => self == Viewer::Organizer,
+        }
+    }
+}
+
+/// A fresh access token for a new registration
+pub fn new_access_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Determine the viewer of `event_id`'s participants
+pub async fn viewer(
+    pool: &DbPool,
+    headers: &HeaderMap,
+    event_id: Uuid,
+    is_organizer: bool,
+) -> Result<Viewer, sqlx::Error> {
+    if is_organizer {
+        return Ok(Viewer::Organizer);
+    }
+
+    let Some(token) = headers.get(PARTICIPANT_TOKEN_HEADER).and_then(|v| v.to_str().ok()) else {
+        return Ok(Viewer::Public);
+    };
+
+    let registered = sqlx::query_scalar::<_, i64>(
+        "SELECT count(*) FROM participants
+         WHERE access_token = ? AND event_id = ? AND status != 'cancelled'"
+    )
+    .bind(token)
+    .bind(event_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(if registered > 0 { Viewer::Participant } else { Viewer::Public })
+}
+
+/// A participant as shown to a particular viewer
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipantView {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub name: String,
+    /// Organizers only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub status: ParticipantStatus,
+    pub registered_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Organizers only, as it may hold personal details
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+impl ParticipantView {
+    pub fn new(participant: Participant, viewer: Viewer) -> Self {
+        Self {
+            id: participant.id,
+            event_id: participant.event_id,
+            name: participant.name,
+            email: MaskPolicy::for_viewer(viewer).keep(Pii::Email, participant.email),
+            status: participant.status,
+            registered_at: participant.registered_at,
+            updated_at: participant.updated_at,
+            metadata: (viewer == Viewer::Organizer).then_some(participant.metadata),
+        }
+    }
+}
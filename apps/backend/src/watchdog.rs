@@ -0,0 +1,116 @@
+//! Supervision for long-running background tasks.
+//!
+//! `supervise` restarts a task with exponential backoff whenever it panics
+//! or returns, and `TaskHealth` records its last successful iteration so
+//! readiness checks and the stall watchdog can tell a live task from a
+//! dead or wedged one.
+
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::clock::SharedClock;
+
+/// Delay before the first restart; doubles up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A task that ran at least this long before failing restarts without backoff
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/// Liveness of a supervised task, shared with readiness checks
+#[derive(Debug, Clone)]
+pub struct TaskHealth {
+    /// Milliseconds since the epoch of the last success; 0 if never
+    last_success_ms: Arc<AtomicI64>,
+    restarts: Arc<AtomicU64>,
+}
+
+impl TaskHealth {
+    pub fn new() -> Self {
+        Self {
+            last_success_ms: Arc::new(AtomicI64::new(0)),
+            restarts: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Mark an iteration of the task as successful
+    pub fn record_success(&self, now: DateTime<Utc>) {
+        self.last_success_ms.store(now.timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn last_success(&self) -> Option<DateTime<Utc>> {
+        match self.last_success_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => DateTime::from_timestamp_millis(ms),
+        }
+    }
+
+    /// Number of times the supervisor has restarted the task
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// Whether the task has gone longer than `threshold` without a success
+    pub fn is_stalled(&self, now: DateTime<Utc>, threshold: chrono::Duration) -> bool {
+        self.last_success().is_none_or(|last| now - last > threshold)
+    }
+}
+
+impl Default for TaskHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run the task produced by `make` forever, restarting it with backoff
+/// after a panic or an early return
+pub async fn supervise<F, Fut>(name: &'static str, health: TaskHealth, mut make: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let started = Instant::now();
+        match AssertUnwindSafe(make()).catch_unwind().await {
+            Ok(()) => warn!("{} exited; restarting", name),
+            Err(_) => error!("{} panicked; restarting", name),
+        }
+
+        if started.elapsed() >= HEALTHY_RUN {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        health.restarts.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Periodically log an error while `health` reports the task as stalled
+pub async fn watch(name: &'static str, health: TaskHealth, clock: SharedClock, threshold: chrono::Duration) {
+    let interval = threshold.to_std().unwrap_or(Duration::from_secs(30)) / 2;
+    let started = clock.now();
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let now = clock.now();
+        // Give the task one threshold to report its first success
+        if now - started > threshold && health.is_stalled(now, threshold) {
+            error!(
+                "{} stalled: last success {:?}, {} restarts",
+                name,
+                health.last_success(),
+                health.restarts()
+            );
+        }
+    }
+}
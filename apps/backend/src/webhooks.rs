@@ -0,0 +1,625 @@
+//! Participant status webhooks for external CRMs.
+//!
+//! An organizer configures webhooks on their account at
+//! `/api/organizers/me/webhooks`. Each names the status transitions it
+//! fires on, such as `{"from": "waitlisted", "to": "confirmed"}` or any
+//! move to `cancelled`, and a field mapping: the JSON object to send, with
+//! template placeholders in its values, e.g.
+//! `{"member_email": "{{email}}", "state": "{{status}}"}`. Webhooks fire for
+//! participants of the events the organizer owns.
+//!
+//! A status change queues one delivery per matching webhook, with the
+//! payload rendered right away. The dispatcher posts due deliveries signed
+//! with the webhook's secret (`X-Webhook-Signature: sha256=<hex HMAC of
+//! the body>`) and retries failures with growing pauses, MAX_ATTEMPTS
+//! times in all. `GET /api/participants/:id/webhook-deliveries` lists what
+//! was sent about a participant and how it went.
+//!
+//! Registrations have no previous status and match only transitions
+//! without `from`. Deleting a participant is not a status change and sends
+//! nothing; its queued deliveries are deleted with it.
+
+use chrono::{DateTime, Duration, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, warn};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::db::DbPool;
+use crate::domain_events::DomainEvent;
+use crate::email_templates::{self, Values};
+use crate::encryption::PiiText;
+use crate::export::encode_hex;
+use crate::models::{Event, Participant, ParticipantStatus};
+use crate::routes::events::fetch_event;
+use crate::validation;
+
+// Type alias for our app state
+type AppState = crate::AppState;
+
+/// Header carrying the HMAC-SHA256 of the body
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+/// Header naming the webhook
+pub const WEBHOOK_HEADER: &str = "x-webhook-id";
+/// Header naming the delivery; the same on every attempt
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+/// Attempts before a delivery is given up
+pub const MAX_ATTEMPTS: i64 = 6;
+/// Pause after the first failed attempt; doubles with every further one
+const RETRY_BASE_SECS: i64 = 30;
+/// How long a CRM may take to answer
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// How long a claimed delivery stays claimed; one claimed by an instance
+/// that stopped is attempted again after this
+const CLAIM_SECS: i64 = 60;
+/// How long the dispatcher waits before looking at an empty queue again
+const IDLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Longest webhook name
+pub const MAX_NAME_LEN: u64 = 100;
+/// Longest webhook URL
+pub const MAX_URL_LEN: u64 = 2000;
+/// Most transitions one webhook fires on
+pub const MAX_TRANSITIONS: u64 = 20;
+/// Most fields one mapping sends
+pub const MAX_FIELDS: usize = 50;
+/// Longest field name
+pub const MAX_FIELD_NAME_LEN: usize = 100;
+/// Longest field template
+pub const MAX_FIELD_TEMPLATE_LEN: usize = 1000;
+
+/// Placeholders a field template may use besides the email template ones
+/// and `metadata.<key>`
+pub const PLACEHOLDERS: &[&str] = &[
+    "participant.id",
+    "event.id",
+    "status",
+    "previous_status",
+    "registered_at",
+];
+
+/// A status change a webhook fires on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    /// Status before the change; any when absent
+    #[serde(default)]
+    pub from: Option<ParticipantStatus>,
+    pub to: ParticipantStatus,
+}
+
+impl Transition {
+    fn matches(&self, from: Option<&ParticipantStatus>, to: &ParticipantStatus) -> bool {
+        self.to == *to && self.from.as_ref().is_none_or(|wanted| Some(wanted) == from)
+    }
+}
+
+/// A CRM endpoint of an organizer
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub organizer_id: Uuid,
+    pub name: String,
+    pub url: String,
+    /// Signs the deliveries; shown once, when the webhook is created
+    #[serde(skip)]
+    pub secret: String,
+    #[sqlx(json)]
+    pub transitions: Vec<Transition>,
+    /// Field name to template of its value
+    #[sqlx(json)]
+    pub fields: BTreeMap<String, String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A newly created webhook and its signing secret, which is not shown again
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+fn yes() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct WebhookInput {
+    #[validate(
+        custom(function = "validation::not_blank"),
+        length(max = MAX_NAME_LEN, code = "too_long", message = "must be at most 100 characters")
+    )]
+    pub name: String,
+    #[validate(
+        length(max = MAX_URL_LEN, code = "too_long", message = "must be at most 2000 characters"),
+        custom(function = "http_url")
+    )]
+    pub url: String,
+    #[validate(length(min = 1, max = MAX_TRANSITIONS, code = "out_of_range", message = "must list 1 to 20 transitions"))]
+    pub transitions: Vec<Transition>,
+    #[validate(custom(function = "field_mapping"))]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default = "yes")]
+    pub active: bool,
+}
+
+/// Accept absolute http and https URLs only
+fn http_url(url: &str) -> Result<(), ValidationError> {
+    let host = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or_default());
+    match host {
+        Some(host) if !host.is_empty() && !host.contains(char::is_whitespace) => Ok(()),
+        _ => Err(ValidationError::new("invalid_url").with_message(Cow::Borrowed("must be an http or https URL"))),
+    }
+}
+
+fn known_placeholder(name: &str) -> bool {
+    PLACEHOLDERS.contains(&name)
+        || email_templates::PLACEHOLDERS.contains(&name)
+        || name.strip_prefix("metadata.").is_some_and(|key| !key.is_empty())
+}
+
+/// Check a field mapping: 1 to MAX_FIELDS named fields whose templates use
+/// known placeholders
+fn field_mapping(fields: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    if fields.is_empty() || fields.len() > MAX_FIELDS {
+        return Err(ValidationError::new("out_of_range").with_message(Cow::Borrowed("must map 1 to 50 fields")));
+    }
+    let hint = format!("{}, {}, metadata.<key>", email_templates::PLACEHOLDERS.join(", "), PLACEHOLDERS.join(", "));
+    for (name, template) in fields {
+        if name.trim().is_empty() || name.chars().count() > MAX_FIELD_NAME_LEN {
+            return Err(ValidationError::new("invalid_field")
+                .with_message(Cow::Borrowed("field names must be 1 to 100 characters")));
+        }
+        if template.chars().count() > MAX_FIELD_TEMPLATE_LEN {
+            return Err(ValidationError::new("too_long")
+                .with_message(Cow::Borrowed("field templates must be at most 1000 characters")));
+        }
+        email_templates::placeholders_allowed(template, known_placeholder, &hint)?;
+    }
+    Ok(())
+}
+
+/// Where a delivery is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum DeliveryState {
+    /// Waiting for its next attempt
+    Queued,
+    Sending,
+    Delivered,
+    /// Given up after MAX_ATTEMPTS
+    Failed,
+}
+
+/// One status change sent, or to be sent, to one webhook
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Delivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub participant_id: Uuid,
+    pub event_id: Uuid,
+    pub from_status: Option<ParticipantStatus>,
+    pub to_status: ParticipantStatus,
+    /// The body sent
+    #[sqlx(try_from = "PiiText")]
+    pub payload: Value,
+    pub state: DeliveryState,
+    pub attempts: i64,
+    /// When it is attempted next, while queued
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the latest answer
+    pub response_status: Option<i64>,
+    /// Error of the latest failed attempt
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A fresh signing secret
+pub fn new_secret() -> String {
+    format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Signature header value for `body`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    format!("sha256={}", encode_hex(hmac::sign(&key, body).as_ref()))
+}
+
+/// Render `fields` for a participant moving from `from` to their status
+pub fn render(
+    fields: &BTreeMap<String, String>,
+    event: &Event,
+    participant: &Participant,
+    from: Option<&ParticipantStatus>,
+) -> Value {
+    let values = Values::new(event, participant);
+    let status = |status: &ParticipantStatus| {
+        serde_json::to_value(status).ok().and_then(|v| v.as_str().map(str::to_string))
+    };
+    let lookup = |name: &str| match name {
+        "participant.id" => Some(participant.id.to_string()),
+        "event.id" => Some(event.id.to_string()),
+        "status" => status(&participant.status),
+        "previous_status" => Some(from.and_then(status).unwrap_or_default()),
+        "registered_at" => Some(participant.registered_at.to_rfc3339()),
+        _ => match name.strip_prefix("metadata.") {
+            Some(key) => Some(match participant.metadata.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            }),
+            None => values.get(name),
+        },
+    };
+
+    Value::Object(
+        fields
+            .iter()
+            .map(|(name, template)| (name.clone(), Value::String(email_templates::render_with(template, lookup))))
+            .collect(),
+    )
+}
+
+const COLUMNS: &str = "id, organizer_id, name, url, secret, transitions, fields, active, created_at, updated_at";
+
+/// An organizer's webhooks, oldest first
+pub async fn list(pool: &DbPool, organizer_id: Uuid) -> Result<Vec<Webhook>, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {} FROM webhooks WHERE organizer_id = ? ORDER BY created_at, rowid",
+        COLUMNS
+    ))
+    .bind(organizer_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find(pool: &DbPool, id: Uuid) -> Result<Option<Webhook>, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(&format!("SELECT {} FROM webhooks WHERE id = ?", COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn create(
+    pool: &DbPool,
+    id: Uuid,
+    organizer_id: Uuid,
+    input: &WebhookInput,
+    secret: &str,
+    now: DateTime<Utc>,
+) -> Result<Webhook, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(&format!(
+        "INSERT INTO webhooks (id, organizer_id, name, url, secret, transitions, fields, active, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(id)
+    .bind(organizer_id)
+    .bind(input.name.trim())
+    .bind(input.url.trim())
+    .bind(secret)
+    .bind(serde_json::to_string(&input.transitions).expect("transitions serialize"))
+    .bind(serde_json::to_string(&input.fields).expect("fields serialize"))
+    .bind(input.active)
+    .bind(now)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+}
+
+/// Replace an organizer's webhook; None when they have none with `id`
+pub async fn update(
+    pool: &DbPool,
+    organizer_id: Uuid,
+    id: Uuid,
+    input: &WebhookInput,
+    now: DateTime<Utc>,
+) -> Result<Option<Webhook>, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(&format!(
+        "UPDATE webhooks
+         SET name = ?, url = ?, transitions = ?, fields = ?, active = ?, updated_at = ?
+         WHERE id = ? AND organizer_id = ?
+         RETURNING {}",
+        COLUMNS
+    ))
+    .bind(input.name.trim())
+    .bind(input.url.trim())
+    .bind(serde_json::to_string(&input.transitions).expect("transitions serialize"))
+    .bind(serde_json::to_string(&input.fields).expect("fields serialize"))
+    .bind(input.active)
+    .bind(now)
+    .bind(id)
+    .bind(organizer_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Delete an organizer's webhook and its deliveries; returns whether it
+/// existed
+pub async fn delete(pool: &DbPool, organizer_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND organizer_id = ?")
+        .bind(id)
+        .bind(organizer_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Payloads name the participant, so they are stored sealed
+impl TryFrom<PiiText> for Value {
+    type Error = serde_json::Error;
+
+    fn try_from(text: PiiText) -> Result<Self, Self::Error> {
+        serde_json::from_str(&text.0)
+    }
+}
+
+const DELIVERY_COLUMNS: &str = "d.id, d.webhook_id, d.participant_id, d.event_id, d.from_status, d.to_status,
+     d.payload, d.state, d.attempts, d.next_attempt_at, d.response_status, d.last_error, d.created_at, d.delivered_at";
+
+/// Deliveries about a participant through an organizer's webhooks, newest
+/// first
+pub async fn deliveries_for(
+    pool: &DbPool,
+    organizer_id: Uuid,
+    participant_id: Uuid,
+) -> Result<Vec<Delivery>, sqlx::Error> {
+    sqlx::query_as::<_, Delivery>(&format!(
+        "SELECT {}
+         FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.participant_id = ? AND w.organizer_id = ?
+         ORDER BY d.created_at DESC, d.rowid DESC",
+        DELIVERY_COLUMNS
+    ))
+    .bind(participant_id)
+    .bind(organizer_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// The participant and previous status of a status change; None for
+/// domain events that are not one
+async fn transition_of<'a>(
+    pool: &DbPool,
+    domain_event: &'a DomainEvent,
+) -> Result<Option<(&'a Participant, Option<ParticipantStatus>)>, sqlx::Error> {
+    Ok(match domain_event {
+        DomainEvent::ParticipantRegistered { participant, .. } => Some((participant, None)),
+        DomainEvent::ParticipantStatusChanged { participant, previous, .. } => {
+            Some((participant, Some(previous.clone())))
+        }
+        DomainEvent::ParticipantPromoted { participant, .. } => Some((participant, Some(ParticipantStatus::Waitlisted))),
+        DomainEvent::ParticipantCheckedIn { participant, .. } => {
+            // Check-ins do not report where they came from; the status
+            // history trigger recorded it
+            let from = sqlx::query_scalar::<_, ParticipantStatus>(
+                "SELECT from_status FROM participant_status_history
+                 WHERE participant_id = ? AND to_status = 'checked_in'
+                 ORDER BY id DESC LIMIT 1"
+            )
+            .bind(participant.id)
+            .fetch_optional(pool)
+            .await?;
+            Some((participant, from))
+        }
+        _ => None,
+    })
+}
+
+/// Queue a delivery to every active webhook of the event's owner that fires
+/// on the change; returns how many were queued
+pub async fn enqueue(state: &AppState, domain_event: &DomainEvent) -> Result<usize, sqlx::Error> {
+    let pool = &state.db_pool;
+    let Some((participant, from)) = transition_of(pool, domain_event).await? else {
+        return Ok(0);
+    };
+
+    let webhooks = sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {} FROM webhooks
+         WHERE active AND organizer_id = (SELECT organizer_id FROM events WHERE id = ?)
+         ORDER BY created_at, rowid",
+        COLUMNS
+    ))
+    .bind(participant.event_id)
+    .fetch_all(pool)
+    .await?;
+    let webhooks: Vec<_> = webhooks
+        .into_iter()
+        .filter(|webhook| webhook.transitions.iter().any(|t| t.matches(from.as_ref(), &participant.status)))
+        .collect();
+    if webhooks.is_empty() {
+        return Ok(0);
+    }
+    let Some(event) = fetch_event(pool, participant.event_id).await? else {
+        return Ok(0);
+    };
+
+    let now = state.clock.now();
+    for webhook in &webhooks {
+        sqlx::query(
+            "INSERT INTO webhook_deliveries
+                 (id, webhook_id, participant_id, event_id, from_status, to_status, payload, next_attempt_at, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(state.ids.generate(now))
+        .bind(webhook.id)
+        .bind(participant.id)
+        .bind(participant.event_id)
+        .bind(from.as_ref())
+        .bind(&participant.status)
+        .bind(PiiText(render(&webhook.fields, &event, participant, from.as_ref()).to_string()))
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(webhooks.len())
+}
+
+/// Queue deliveries for the status changes handled by this instance
+pub async fn start_enqueuer(state: AppState) {
+    let mut events = state.domain_events.subscribe();
+
+    loop {
+        let domain_event = match events.recv().await {
+            Ok(domain_event) => domain_event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Webhook enqueuer lagged; {} domain events not delivered", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if let Err(e) = enqueue(&state, &domain_event).await {
+            error!("Failed to queue webhook deliveries: {}", e);
+        }
+    }
+}
+
+/// HTTP client for deliveries
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+#[derive(FromRow)]
+struct Claimed {
+    id: Uuid,
+    webhook_id: Uuid,
+    #[sqlx(try_from = "PiiText")]
+    payload: String,
+    attempts: i64,
+}
+
+/// Claim the delivery due longest; None when none is due or another
+/// instance got there first
+async fn claim(pool: &DbPool, now: DateTime<Utc>) -> Result<Option<Claimed>, sqlx::Error> {
+    sqlx::query_as::<_, Claimed>(
+        "UPDATE webhook_deliveries
+         SET state = 'sending', attempts = attempts + 1, next_attempt_at = ?1
+         WHERE id = (SELECT id FROM webhook_deliveries
+                     WHERE state IN ('queued', 'sending') AND next_attempt_at <= ?2
+                     ORDER BY next_attempt_at, rowid LIMIT 1)
+           AND state IN ('queued', 'sending') AND next_attempt_at <= ?2
+         RETURNING id, webhook_id, payload, attempts"
+    )
+    .bind(now + Duration::seconds(CLAIM_SECS))
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Pause before the attempt after `attempts` failed ones
+fn backoff(attempts: i64) -> Duration {
+    Duration::seconds(RETRY_BASE_SECS << (attempts - 1).clamp(0, 16))
+}
+
+/// Post a claimed delivery; returns the HTTP status and the error, if any
+async fn post(client: &reqwest::Client, webhook: &Webhook, claimed: &Claimed) -> (Option<i64>, Option<String>) {
+    let result = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_HEADER, webhook.id.to_string())
+        .header(DELIVERY_HEADER, claimed.id.to_string())
+        .header(SIGNATURE_HEADER, sign(&webhook.secret, claimed.payload.as_bytes()))
+        .body(claimed.payload.clone())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16().into()), None),
+        Ok(response) => (
+            Some(response.status().as_u16().into()),
+            Some(format!("HTTP {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Record the outcome of an attempt, queueing the next one after a failure
+/// until MAX_ATTEMPTS
+async fn finish(
+    pool: &DbPool,
+    claimed: &Claimed,
+    response_status: Option<i64>,
+    error: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let (state, delivered_at, next_attempt_at) = match error {
+        None => (DeliveryState::Delivered, Some(now), now),
+        Some(_) if claimed.attempts >= MAX_ATTEMPTS => (DeliveryState::Failed, None, now),
+        Some(_) => (DeliveryState::Queued, None, now + backoff(claimed.attempts)),
+    };
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET state = ?, response_status = ?, last_error = ?, delivered_at = ?, next_attempt_at = ?
+         WHERE id = ?"
+    )
+    .bind(state)
+    .bind(response_status)
+    .bind(error)
+    .bind(delivered_at)
+    .bind(next_attempt_at)
+    .bind(claimed.id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Attempt up to `limit` due deliveries; returns how many were attempted
+pub async fn dispatch_due(state: &AppState, client: &reqwest::Client, limit: usize) -> Result<usize, sqlx::Error> {
+    let mut attempted = 0;
+
+    while attempted < limit {
+        let Some(claimed) = claim(&state.db_pool, state.clock.now()).await? else {
+            break;
+        };
+        let (response_status, error) = match find(&state.db_pool, claimed.webhook_id).await? {
+            Some(webhook) => post(client, &webhook, &claimed).await,
+            // Deleted while sending; its deliveries went with it
+            None => continue,
+        };
+        if let Some(e) = &error {
+            warn!("Webhook delivery {} failed (attempt {}): {}", claimed.id, claimed.attempts, e);
+        }
+        finish(&state.db_pool, &claimed, response_status, error, state.clock.now()).await?;
+        attempted += 1;
+    }
+
+    Ok(attempted)
+}
+
+/// Deliver due webhook calls; runs on instances with background jobs (see
+/// `config::Role`)
+pub async fn start_dispatcher(state: AppState) {
+    let client = client();
+
+    loop {
+        match dispatch_due(&state, &client, 1).await {
+            Ok(0) => tokio::time::sleep(IDLE_INTERVAL).await,
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to dispatch webhooks: {}", e);
+                tokio::time::sleep(IDLE_INTERVAL).await;
+            }
+        }
+    }
+}
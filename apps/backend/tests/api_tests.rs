@@ -1,70 +1,379 @@
 use axum::{
     body::Body,
     http::{Request, StatusCode, Method},
-    routing::{get, post},
-    Router,
 };
 use serde_json::{json, Value};
+use std::time::Duration;
 use tower::ServiceExt;
 
 // Import from the backend crate
-use backend::{AppState, cache::AppCache, db, broadcaster::Broadcaster};
+use backend::{build_router, bulk_mail, completion, config::Config, db, ics, registration_mail, reminders, telemetry, webhooks};
+use backend::broadcaster::Channel;
+use backend::mailer::OutgoingMail;
+use backend::clock::{Clock, MockClock};
+use backend::domain_events::DomainEvent;
+use backend::geo::{CountryLookup, GeoGate};
+use backend::ids::{IdGenerator, IdVersion};
+use backend::instance::{self, Instance};
+use backend::validation::{DateBounds, FieldLimits};
+use std::sync::Arc;
+use backend::concurrency::{ConcurrencyConfig, ConcurrencyLimits};
+use backend::cors::CorsConfig;
+use backend::load_shed::{LoadShedConfig, LoadShedder};
+use backend::reputation::DisposableDomains;
+use backend::watchdog::{self, TaskHealth};
+use backend::testing::{
+    assign_owner, body_json, build_app, create_test_state, expect_broadcast, prepare_confirmation, publish_event, seed_event,
+    organizer_auth, seed_organizer, seed_participant, spawn_poller, RecordingMailer,
+};
 
-/// Helper to create a test app state with a temporary SQLite database
-async fn create_test_state() -> (AppState, tempfile::TempDir) {
-    let dir = tempfile::tempdir().unwrap();
-    let db_path = dir.path().join("test.db");
-    let db_path_str = db_path.to_str().unwrap();
+// =====================
+// Health Check Tests
+// =====================
+
+#[tokio::test]
+async fn test_health_check() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["status"], "healthy");
+    assert!(body["timestamp"].is_string());
+}
+
+#[tokio::test]
+async fn test_readiness_tracks_notification_poller() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state.clone());
+    let ready = || Request::builder().uri("/ready").body(Body::empty()).unwrap();
+
+    // No poller has succeeded yet
+    let response = app.clone().oneshot(ready()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body_json(response).await["status"], "not_ready");
+
+    let _poller = spawn_poller(&state).await;
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    let response = app.oneshot(ready()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["status"], "ready");
+    assert!(body["poller_last_success"].is_string());
+}
+
+#[tokio::test]
+async fn test_instance_ready_after_catch_up_and_drains() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    state.instance = Instance::new("replica-b", state.clock.now());
+    let app = build_app(state.clone());
+    let ready = || Request::builder().uri("/ready").body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(ready()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = body_json(response).await;
+    assert_eq!(body["phase"], "starting");
+    assert_eq!(body["instance_id"], "replica-b");
+
+    let _poller = spawn_poller(&state).await;
+    let _heartbeat = tokio::spawn(instance::start_heartbeat(
+        state.db_pool.clone(),
+        state.instance.clone(),
+        state.poller_health.clone(),
+        state.clock.clone(),
+        Duration::from_secs(10),
+    ));
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    let response = app.clone().oneshot(ready()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["phase"], "ready");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/admin/instances")
+                .header("X-Admin-Token", "secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let instances = body_json(response).await;
+    assert_eq!(instances[0]["id"], "replica-b");
+    assert_eq!(instances[0]["phase"], "ready");
+
+    state.instance.mark_draining();
+    let response = app.oneshot(ready()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body_json(response).await["phase"], "draining");
+}
+
+#[tokio::test]
+async fn test_maintenance_message_is_announced() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let app = build_app(state);
+
+    let set_message = |message: serde_json::Value| {
+        Request::builder()
+            .method(Method::PUT)
+            .uri("/api/admin/settings/maintenance")
+            .header("Content-Type", "application/json")
+            .header("X-Admin-Token", "secret")
+            .body(Body::from(json!({ "message": message }).to_string()))
+            .unwrap()
+    };
+    let health = || Request::builder().uri("/health").body(Body::empty()).unwrap();
 
-    let db_pool = db::create_pool(db_path_str).await.unwrap();
-    db::initialize_tables(&db_pool).await.unwrap();
+    let response = app.clone().oneshot(set_message(json!("Paused until 18:00 (Grüße)"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let payload = expect_broadcast(&mut receiver, Channel::System, Duration::from_secs(3)).await;
+    assert_eq!(payload["type"], "maintenance");
+    assert_eq!(payload["message"], "Paused until 18:00 (Grüße)");
+
+    let response = app.clone().oneshot(health()).await.unwrap();
+    assert_eq!(
+        response.headers()["x-maintenance-message"],
+        "Paused until 18:00 (Gr%C3%BC%C3%9Fe)"
+    );
+    assert_eq!(body_json(response).await["maintenance_message"], "Paused until 18:00 (Grüße)");
+
+    // Clearing the notice removes the header and the health field
+    let response = app.clone().oneshot(set_message(serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(health()).await.unwrap();
+    assert!(response.headers().get("x-maintenance-message").is_none());
+    assert!(body_json(response).await.get("maintenance_message").is_none());
+}
 
-    let cache = AppCache::new(60);
-    let broadcaster = Broadcaster::new();
+#[tokio::test]
+async fn test_log_level_change_expires() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state);
 
-    let state = AppState {
-        db_pool,
-        broadcaster,
-        cache,
+    let request = |method: Method, body: Option<serde_json::Value>| {
+        Request::builder()
+            .method(method)
+            .uri("/api/admin/log-level")
+            .header("Content-Type", "application/json")
+            .header("X-Admin-Token", "secret")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap()
     };
 
-    (state, dir)
+    let response = app.clone().oneshot(request(Method::GET, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    let default_filter = body["default_filter"].clone();
+    assert_eq!(body["filter"], default_filter);
+    assert!(body["expires_at"].is_null());
+    assert_eq!(body["instance"], "test");
+
+    // Invalid directives are rejected and leave the filter alone
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, Some(json!({ "filter": "backend=loudest" }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["filter"][0]["code"], "invalid_filter");
+
+    // Ten minutes unless asked otherwise
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, Some(json!({ "filter": "backend=trace" }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["filter"], "backend=trace");
+    let expires_at: chrono::DateTime<chrono::Utc> = serde_json::from_value(body["expires_at"].clone()).unwrap();
+    let ttl = expires_at - chrono::Utc::now();
+    assert!(ttl > chrono::Duration::minutes(9) && ttl <= chrono::Duration::minutes(10));
+
+    // A later change replaces the earlier one and its expiry
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, Some(json!({ "filter": "backend=debug,sqlx=debug", "ttl_secs": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.clone().oneshot(request(Method::GET, None)).await.unwrap();
+    assert_eq!(body_json(response).await["filter"], "backend=debug,sqlx=debug");
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let response = app.clone().oneshot(request(Method::GET, None)).await.unwrap();
+    let body = body_json(response).await;
+    assert_eq!(body["filter"], default_filter);
+    assert!(body["expires_at"].is_null());
+
+    // Resetting early goes straight back to the default
+    app.clone()
+        .oneshot(request(Method::PUT, Some(json!({ "filter": "trace" }))))
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(request(Method::DELETE, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["filter"], default_filter);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri("/api/admin/log-level")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "filter": "trace" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
-/// Helper to build the test router (same as main.rs)
-fn build_app(state: AppState) -> Router {
-    use backend::routes;
+#[tokio::test]
+async fn test_supervisor_restarts_panicked_task() {
+    let health = TaskHealth::new();
+    let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+    let counter = runs.clone();
+    let supervisor = tokio::spawn(watchdog::supervise("flaky task", health.clone(), move || {
+        let run = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        async move {
+            if run == 0 {
+                panic!("first run fails");
+            }
+            std::future::pending::<()>().await;
+        }
+    }));
 
-    Router::new()
-        .route("/health", get(backend::health_check))
-        .route("/api/events/stream", get(routes::sse::event_stream))
-        .route("/api/events", get(routes::events::list_events).post(routes::events::create_event))
-        .route("/api/events/:id", get(routes::events::get_event).put(routes::events::update_event).delete(routes::events::delete_event))
-        .route("/api/events/:id/participants", get(routes::participants::list_participants))
-        .route("/api/participants", post(routes::participants::create_participant))
-        .route("/api/participants/:id", get(routes::participants::get_participant).put(routes::participants::update_participant_status).delete(routes::participants::delete_participant))
-        .with_state(state)
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+    assert_eq!(health.restarts(), 1);
+    supervisor.abort();
 }
 
-/// Helper to extract JSON body from response
-async fn body_json(response: axum::http::Response<Body>) -> Value {
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    serde_json::from_slice(&body).unwrap()
+#[test]
+fn test_bind_addr_parsing() {
+    use backend::config::BindTarget;
+
+    assert_eq!(
+        BindTarget::parse("127.0.0.1", 8080).unwrap(),
+        BindTarget::Tcp("127.0.0.1:8080".parse().unwrap())
+    );
+    assert_eq!(
+        BindTarget::parse("[::1]:9000", 8080).unwrap(),
+        BindTarget::Tcp("[::1]:9000".parse().unwrap())
+    );
+    assert_eq!(
+        BindTarget::parse("unix:/run/backend.sock", 8080).unwrap(),
+        BindTarget::Unix("/run/backend.sock".into())
+    );
+    assert!(BindTarget::parse("unix:", 8080).is_err());
+    assert!(BindTarget::parse("localhost", 8080).is_err());
 }
 
-// =====================
-// Health Check Tests
-// =====================
+#[test]
+fn test_role_from_command_line() {
+    use backend::config::Role;
+
+    let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    assert_eq!(Role::from_args(args(&[])).unwrap(), None);
+    assert_eq!(Role::from_args(args(&["--role", "worker"])).unwrap(), Some(Role::Worker));
+    assert_eq!(Role::from_args(args(&["--role=Web"])).unwrap(), Some(Role::Web));
+    assert!(Role::from_args(args(&["--role"])).is_err());
+    assert!(Role::from_args(args(&["--role", "cron"])).is_err());
+
+    assert!(Role::All.serves_http() && Role::All.runs_jobs());
+    assert!(Role::Web.serves_http() && !Role::Web.runs_jobs());
+    assert!(!Role::Worker.serves_http() && Role::Worker.runs_jobs());
+}
 
 #[tokio::test]
-async fn test_health_check() {
+async fn test_tls_redirect_listener_and_alpn() {
+    use backend::tls::{self, TlsConfig};
+
+    let app = tls::redirect_router(8443);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/events?limit=5")
+                .header("Host", "events.example.com:8080")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+        response.headers()["location"],
+        "https://events.example.com:8443/api/events?limit=5"
+    );
+
+    let response = app
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let mut config = TlsConfig {
+        cert_path: "/nonexistent/cert.pem".into(),
+        key_path: "/nonexistent/key.pem".into(),
+        http2: true,
+        redirect_port: None,
+    };
+    assert_eq!(config.alpn_protocols(), vec![b"h2".to_vec(), b"http/1.1".to_vec()]);
+    config.http2 = false;
+    assert_eq!(config.alpn_protocols(), vec![b"http/1.1".to_vec()]);
+    assert!(tls::server_config(&config).is_err());
+}
+
+#[tokio::test]
+async fn test_router_applies_cors_layer() {
     let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state);
+    let config = Config {
+        cors: CorsConfig {
+            origin: "https://app.example.com".to_string(),
+            ..CorsConfig::default()
+        },
+        ..Config::default()
+    };
+    let app = build_router(state, &config);
 
     let response = app
         .oneshot(
             Request::builder()
                 .uri("/health")
+                .header("Origin", "https://app.example.com")
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -72,9 +381,152 @@ async fn test_health_check() {
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+}
+
+#[tokio::test]
+async fn test_cors_policies_differ_for_public_and_admin_routes() {
+    let (state, _temp_dir) = create_test_state().await;
+    let config = Config {
+        cors: CorsConfig {
+            origin: "*".to_string(),
+            admin_origin: "https://admin.example.com".to_string(),
+            allow_credentials: true,
+            max_age: Duration::from_secs(900),
+        },
+        ..Config::default()
+    };
+    let app = build_router(state, &config);
+
+    let preflight = |uri: &str, origin: &str| {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri(uri)
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .header("Access-Control-Request-Headers", "content-type, x-admin-token")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Public endpoints admit any origin, without credentials
+    let response = app.clone().oneshot(preflight("/api/participants", "https://embed.example.org")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["access-control-allow-origin"], "*");
+    assert_eq!(response.headers()["access-control-max-age"], "900");
+    assert!(response.headers().get("access-control-allow-credentials").is_none());
+    let allowed = response.headers()["access-control-allow-headers"].to_str().unwrap();
+    assert!(allowed.contains("x-admin-token"));
+
+    // Admin endpoints only admit the admin origin, with credentials
+    let response = app.clone().oneshot(preflight("/api/admin/session", "https://admin.example.com")).await.unwrap();
+    assert_eq!(response.headers()["access-control-allow-origin"], "https://admin.example.com");
+    assert_eq!(response.headers()["access-control-allow-credentials"], "true");
+    assert_eq!(response.headers()["access-control-max-age"], "900");
+
+    // Other origins are told the admin origin, which browsers then refuse
+    for uri in ["/api/admin/session", "/admin"] {
+        let response = app.clone().oneshot(preflight(uri, "https://embed.example.org")).await.unwrap();
+        assert_eq!(response.headers()["access-control-allow-origin"], "https://admin.example.com");
+    }
+
+    // Paths merely starting with the prefix stay public
+    let response = app.oneshot(preflight("/administrators", "https://embed.example.org")).await.unwrap();
+    assert_eq!(response.headers()["access-control-allow-origin"], "*");
+}
+
+#[tokio::test]
+async fn test_cors_accepts_origin_lists_and_subdomain_patterns() {
+    let (state, _temp_dir) = create_test_state().await;
+    let config = Config {
+        cors: CorsConfig {
+            origin: "https://app.example.com, https://staging.example.com,https://*.preview.example.com".to_string(),
+            allow_credentials: true,
+            ..CorsConfig::default()
+        },
+        ..Config::default()
+    };
+    let app = build_router(state, &config);
+
+    let allowed = |uri: &'static str, origin: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(Method::OPTIONS)
+                        .uri(uri)
+                        .header("Origin", origin)
+                        .header("Access-Control-Request-Method", "GET")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .map(|v| v.to_str().unwrap().to_string())
+        }
+    };
+
+    for origin in ["https://app.example.com", "https://staging.example.com", "https://pr-12.preview.example.com"] {
+        assert_eq!(allowed("/api/events", origin).await.as_deref(), Some(origin));
+    }
+    for origin in ["https://preview.example.com", "http://pr-12.preview.example.com", "https://evil.example.org"] {
+        assert_eq!(allowed("/api/events", origin).await, None);
+    }
+
+    // The admin policy falls back to the same list
+    assert_eq!(
+        allowed("/api/admin/session", "https://staging.example.com").await.as_deref(),
+        Some("https://staging.example.com")
+    );
+    assert_eq!(allowed("/api/admin/session", "https://evil.example.org").await, None);
+}
+
+#[tokio::test]
+async fn test_unknown_route_wrong_method_and_trailing_slash() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+
+    // Unknown route gets a JSON 404
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/nope").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
     let body = body_json(response).await;
-    assert_eq!(body["status"], "healthy");
-    assert!(body["timestamp"].is_string());
+    assert_eq!(body["error"], "Route not found");
+
+    // Wrong method gets a JSON 405 with Allow
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri("/api/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    let allow = response.headers()["allow"].to_str().unwrap().to_string();
+    assert!(allow.contains("GET") && allow.contains("POST"));
+    let body = body_json(response).await;
+    assert_eq!(body["error"], "Method not allowed");
+
+    // Trailing slash is normalized
+    let response = app
+        .oneshot(Request::builder().uri("/api/events/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 // =====================
@@ -84,6 +536,7 @@ async fn test_health_check() {
 #[tokio::test]
 async fn test_create_and_get_event() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
     // Create event
@@ -102,6 +555,7 @@ async fn test_create_and_get_event() {
             Request::builder()
                 .method(Method::POST)
                 .uri("/api/events")
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
                 .body(Body::from(create_body.to_string()))
                 .unwrap(),
@@ -137,6 +591,7 @@ async fn test_create_and_get_event() {
 #[tokio::test]
 async fn test_list_events() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
     // Create two events
@@ -147,24 +602,27 @@ async fn test_list_events() {
             "end_time": "2026-03-01T12:00:00Z"
         });
 
-        app.clone()
+        let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method(Method::POST)
                     .uri("/api/events")
+                    .header("Authorization", &auth)
                     .header("Content-Type", "application/json")
                     .body(Body::from(body.to_string()))
                     .unwrap(),
             )
             .await
             .unwrap();
+        publish_event(&app, &auth, body_json(response).await["id"].as_str().unwrap()).await;
     }
 
     // List events
     let response = app
         .oneshot(
             Request::builder()
-                .uri("/api/events")
+                .uri("/api/events?scope=all")
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -177,202 +635,567 @@ async fn test_list_events() {
 }
 
 #[tokio::test]
-async fn test_update_event() {
+async fn test_event_tags_filter_and_invalidate_tagged_lists() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
-    // Create event
-    let create_body = json!({
-        "title": "Original",
-        "start_time": "2026-03-01T10:00:00Z",
-        "end_time": "2026-03-01T12:00:00Z"
-    });
+    let event_body = |tags: Value| {
+        json!({
+            "title": "Rust Basics",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z",
+            "tags": tags
+        })
+    };
+    let send = |method: Method, uri: String, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let list = |query: &str| {
+        Request::builder()
+            .uri(format!("/api/events?scope=all{}", query))
+            .body(Body::empty())
+            .unwrap()
+    };
 
+    // Tags are trimmed, lowercased, deduplicated and sorted
     let response = app
         .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(create_body.to_string()))
-                .unwrap(),
-        )
+        .oneshot(send(Method::POST, "/api/events".to_string(), event_body(json!([" Workshop", "beginner", "workshop"]))))
         .await
         .unwrap();
-
+    assert_eq!(response.status(), StatusCode::CREATED);
     let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
+    assert_eq!(event["tags"], json!(["beginner", "workshop"]));
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
 
-    // Update event
-    let update_body = json!({
-        "title": "Updated Title",
-        "start_time": "2026-03-01T10:00:00Z",
-        "end_time": "2026-03-01T14:00:00Z",
-        "location": "New Room"
-    });
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/events".to_string(), event_body(json!(["meetup"]))))
+        .await
+        .unwrap();
+    publish_event(&app, &auth, body_json(response).await["id"].as_str().unwrap()).await;
+
+    let events = body_json(app.clone().oneshot(list("&tag=Workshop")).await.unwrap()).await;
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["id"], event_id.as_str());
+    assert_eq!(events[0]["tags"], json!(["beginner", "workshop"]));
+    let events = body_json(app.clone().oneshot(list("&tag=meetup")).await.unwrap()).await;
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    let events = body_json(app.clone().oneshot(list("")).await.unwrap()).await;
+    assert_eq!(events.as_array().unwrap().len(), 2);
+
+    // Dropping a tag removes the event from that tag's cached list
+    let response = app
+        .clone()
+        .oneshot(send(Method::PUT, format!("/api/events/{}", event_id), event_body(json!(["beginner"]))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["tags"], json!(["beginner"]));
+
+    let events = body_json(app.clone().oneshot(list("&tag=workshop")).await.unwrap()).await;
+    assert!(events.as_array().unwrap().is_empty());
+    let events = body_json(app.clone().oneshot(list("&tag=beginner")).await.unwrap()).await;
+    assert_eq!(events[0]["id"], event_id.as_str());
+
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/events".to_string(), event_body(json!(["no spaces"]))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["tags"][0]["code"], "invalid_tag");
 
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
-                .method(Method::PUT)
+                .method(Method::DELETE)
                 .uri(format!("/api/events/{}", event_id))
-                .header("Content-Type", "application/json")
-                .body(Body::from(update_body.to_string()))
+                .header("Authorization", &auth)
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-
-    assert_eq!(response.status(), StatusCode::OK);
-    let updated = body_json(response).await;
-    assert_eq!(updated["title"], "Updated Title");
-    assert_eq!(updated["location"], "New Room");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let events = body_json(app.oneshot(list("&tag=beginner")).await.unwrap()).await;
+    assert!(events.as_array().unwrap().is_empty());
 }
 
 #[tokio::test]
-async fn test_delete_event() {
+async fn test_batch_get_events_reports_each_id() {
     let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state);
+    let first = seed_event(&state, "First", None).await;
+    let second = seed_event(&state, "Second", None).await;
+    let missing = uuid::Uuid::new_v4();
+    let app = build_app(state.clone());
 
-    // Create event
-    let create_body = json!({
-        "title": "To Delete",
-        "start_time": "2026-03-01T10:00:00Z",
-        "end_time": "2026-03-01T12:00:00Z"
-    });
+    let batch = |ids: serde_json::Value| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/events/batch")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({ "ids": ids }).to_string()))
+            .unwrap()
+    };
 
+    // Warm the cache for one of them
     let response = app
         .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(create_body.to_string()))
-                .unwrap(),
-        )
+        .oneshot(Request::builder().uri(format!("/api/events/{}", first.id)).body(Body::empty()).unwrap())
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
-
-    // Delete event
     let response = app
         .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::DELETE)
-                .uri(format!("/api/events/{}", event_id))
-                .body(Body::empty())
-                .unwrap(),
-        )
+        .oneshot(batch(json!([second.id, missing, first.id, second.id])))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3, "duplicates are answered once");
 
-    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(results[0]["id"], second.id.to_string());
+    assert_eq!(results[0]["found"], true);
+    assert_eq!(results[0]["event"]["title"], "Second");
+    assert_eq!(results[1]["id"], missing.to_string());
+    assert_eq!(results[1]["found"], false);
+    assert!(results[1].get("event").is_none());
+    assert_eq!(results[2]["event"]["title"], "First");
 
-    // Verify deleted
-    let response = app
-        .oneshot(
-            Request::builder()
-                .uri(format!("/api/events/{}", event_id))
-                .body(Body::empty())
-                .unwrap(),
-        )
+    // Events loaded for the batch are cached like single GETs
+    assert!(state.cache.event.get(&second.id.to_string()).await.is_some());
+
+    let response = app.clone().oneshot(batch(json!([]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let too_many: Vec<uuid::Uuid> = (0..101).map(|_| uuid::Uuid::new_v4()).collect();
+    let response = app.oneshot(batch(json!(too_many))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["ids"][0]["code"], "out_of_range");
+}
+
+#[tokio::test]
+async fn test_trending_events_rank_flushed_views_and_registrations() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    let viewed = seed_event(&state, "Viewed", None).await;
+    let booked = seed_event(&state, "Booked", None).await;
+    let draft = seed_event(&state, "Draft", None).await;
+    sqlx::query("UPDATE events SET status = 'draft' WHERE id = ?")
+        .bind(draft.id)
+        .execute(&state.db_pool)
         .await
         .unwrap();
+    let app = build_app(state.clone());
 
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
-}
+    let trending = |query: &str| {
+        Request::builder()
+            .uri(format!("/api/events/trending{}", query))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let titles = |body: &Value| -> Vec<String> {
+        body.as_array().unwrap().iter().map(|e| e["title"].as_str().unwrap().to_string()).collect()
+    };
 
-// =====================
-// Event Validation Tests
-// =====================
+    for id in [viewed.id, viewed.id, viewed.id, booked.id, draft.id] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(format!("/api/events/{}", id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Views are only buffered until the flush
+    assert_eq!(state.views.pending(), 5);
+    let body = body_json(app.clone().oneshot(trending("")).await.unwrap()).await;
+    assert!(titles(&body).is_empty());
+
+    assert_eq!(state.views.flush(&state.db_pool, clock.now()).await.unwrap(), 5);
+    assert_eq!(state.views.pending(), 0);
+    let body = body_json(app.clone().oneshot(trending("")).await.unwrap()).await;
+    assert_eq!(titles(&body), ["Viewed", "Booked"]);
+    assert_eq!(body[0]["views"], 3);
+
+    // A registration outweighs a handful of views
+    seed_participant(&state, booked.id, "Alice", "alice@test.com").await;
+    let body = body_json(app.clone().oneshot(trending("?window_hours=4")).await.unwrap()).await;
+    assert_eq!(titles(&body), ["Booked", "Viewed"]);
+    assert_eq!(body[0]["registrations"], 1);
+    assert_eq!(body[0]["registration_velocity"], 0.25);
+    assert_eq!(body[0]["score"], 21);
+
+    // Activity outside the window no longer counts
+    clock.advance(chrono::Duration::hours(25));
+    let body = body_json(app.clone().oneshot(trending("")).await.unwrap()).await;
+    assert!(titles(&body).is_empty());
+
+    let response = app.oneshot(trending("?limit=0")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
 
 #[tokio::test]
-async fn test_create_event_invalid_time_range() {
+async fn test_event_slugs_are_unique_and_resolve() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
-    let body = json!({
-        "title": "Bad Event",
-        "start_time": "2026-03-01T14:00:00Z",
-        "end_time": "2026-03-01T10:00:00Z"
-    });
+    let create = |title: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                json!({
+                    "title": title,
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+    let by_slug = |slug: &str| {
+        Request::builder()
+            .uri(format!("/api/events/by-slug/{}", slug))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let first = body_json(app.clone().oneshot(create("Grüße aus Köln: Rust & Tea!")).await.unwrap()).await;
+    assert_eq!(first["slug"], "gruesse-aus-koeln-rust-tea");
+    let second = body_json(app.clone().oneshot(create("Grüße aus Köln – Rust/Tea")).await.unwrap()).await;
+    assert_eq!(second["slug"], "gruesse-aus-koeln-rust-tea-2");
+    let untitled = body_json(app.clone().oneshot(create("???")).await.unwrap()).await;
+    assert_eq!(untitled["slug"], "event");
+
+    let response = app.clone().oneshot(by_slug("gruesse-aus-koeln-rust-tea-2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["id"], second["id"]);
 
+    // Renaming keeps the slug, so shared links stay valid
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
+                .method(Method::PUT)
+                .uri(format!("/api/events/{}", first["id"].as_str().unwrap()))
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(body.to_string()))
+                .body(Body::from(
+                    json!({
+                        "title": "Renamed",
+                        "start_time": "2026-03-01T10:00:00Z",
+                        "end_time": "2026-03-01T12:00:00Z"
+                    })
+                    .to_string(),
+                ))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["slug"], "gruesse-aus-koeln-rust-tea");
+    let event = body_json(app.clone().oneshot(by_slug("gruesse-aus-koeln-rust-tea")).await.unwrap()).await;
+    assert_eq!(event["title"], "Renamed");
 
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.oneshot(by_slug("no-such-event")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
-async fn test_create_event_empty_title() {
-    let (state, _temp_dir) = create_test_state().await;
+async fn test_list_events_scope_and_configured_default() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
-    let body = json!({
-        "title": "  ",
-        "start_time": "2026-03-01T10:00:00Z",
-        "end_time": "2026-03-01T12:00:00Z"
+    for (title, start, end) in [
+        ("Later", "2026-08-01T10:00:00Z", "2026-08-01T12:00:00Z"),
+        ("Past", "2026-05-01T10:00:00Z", "2026-05-01T12:00:00Z"),
+        ("Ongoing", "2026-06-01T10:00:00Z", "2026-06-01T14:00:00Z"),
+        ("Soon", "2026-06-02T10:00:00Z", "2026-06-02T12:00:00Z"),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/events")
+                    .header("Authorization", &auth)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({ "title": title, "start_time": start, "end_time": end }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        publish_event(&app, &auth, body_json(response).await["id"].as_str().unwrap()).await;
+    }
+
+    let titles = |app: &axum::Router, uri: &'static str| {
+        let app = app.clone();
+        async move {
+            let events = body_json(app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap()).await;
+            events
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["title"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Events still running count as upcoming
+    assert_eq!(titles(&app, "/api/events").await, ["Ongoing", "Soon", "Later"]);
+    assert_eq!(titles(&app, "/api/events?scope=past").await, ["Past"]);
+    assert_eq!(titles(&app, "/api/events?scope=all").await, ["Later", "Soon", "Ongoing", "Past"]);
+
+    let settings = |method: Method, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri("/api/admin/settings/event-list")
+            .header("X-Admin-Token", "secret")
+            .header("Content-Type", "application/json")
+            .body(body)
+            .unwrap()
+    };
+    let response = app.clone().oneshot(settings(Method::GET, Body::empty())).await.unwrap();
+    assert_eq!(body_json(response).await["default_scope"], "upcoming");
+
+    let response = app
+        .clone()
+        .oneshot(settings(Method::PUT, Body::from(json!({ "default_scope": "all" }).to_string())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(titles(&app, "/api/events").await, ["Later", "Soon", "Ongoing", "Past"]);
+    assert_eq!(titles(&app, "/api/events?scope=upcoming").await, ["Ongoing", "Soon", "Later"]);
+}
+
+#[tokio::test]
+async fn test_draft_events_hidden_and_closed_until_published() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
     });
+    let pool = state.db_pool.clone();
+    let auth = organizer_auth(&state).await;
+    let stranger_auth = organizer_auth(&state).await;
+    let app = build_app(state);
 
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method(Method::POST)
                 .uri("/api/events")
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(body.to_string()))
+                .body(Body::from(json!({
+                    "title": "Launch Party",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["status"], "draft");
+    let event_id = event["id"].as_str().unwrap().to_string();
+
+    let list = |admin: bool| {
+        let mut builder = Request::builder().uri("/api/events?scope=all");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let register = || {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event_id,
+                "name": "Early Bird",
+                "email": "early@example.com",
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+    let publish_as = |auth: Option<&str>| {
+        let mut builder = Request::builder().method(Method::POST).uri(format!("/api/events/{}/publish", event_id));
+        if let Some(auth) = auth {
+            builder = builder.header("Authorization", auth);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let publish = || publish_as(Some(&auth));
+
+    // Only organizers see the draft
+    let events = body_json(app.clone().oneshot(list(false)).await.unwrap()).await;
+    assert!(events.as_array().unwrap().is_empty());
+    let events = body_json(app.clone().oneshot(list(true)).await.unwrap()).await;
+    assert_eq!(events[0]["id"], event_id);
+
+    let response = app.clone().oneshot(register()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await["error"], "Event is not published yet; registration is closed");
+
+    // Only the owner publishes
+    let response = app.clone().oneshot(publish_as(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(publish_as(Some(&stranger_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(publish()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["status"], "published");
+
+    // Publishing again is a no-op
+    let response = app.clone().oneshot(publish()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let actions: Vec<String> = sqlx::query_scalar("SELECT action FROM audit_log WHERE entity_id = ?")
+        .bind(&event_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(actions, vec!["event.publish"]);
 
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let events = body_json(app.clone().oneshot(list(false)).await.unwrap()).await;
+    assert_eq!(events[0]["id"], event_id);
+    let response = app.clone().oneshot(register()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    sqlx::query("UPDATE events SET status = 'cancelled' WHERE id = ?")
+        .bind(event_id.parse::<uuid::Uuid>().unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(publish()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
 }
 
 #[tokio::test]
-async fn test_get_nonexistent_event() {
+async fn test_list_events_near_point() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
+    let create = |title: &str, coordinates: Option<(f64, f64)>| {
+        let mut body = json!({
+            "title": title,
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z"
+        });
+        if let Some((lat, lng)) = coordinates {
+            body["latitude"] = json!(lat);
+            body["longitude"] = json!(lng);
+        }
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    for (title, coordinates) in [
+        ("Berlin", Some((52.52, 13.405))),
+        ("Potsdam", Some((52.3906, 13.0645))),
+        ("Munich", Some((48.1374, 11.5755))),
+        ("Online", None),
+    ] {
+        let response = app.clone().oneshot(create(title, coordinates)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        publish_event(&app, &auth, body_json(response).await["id"].as_str().unwrap()).await;
+    }
+
+    // Default radius of 25 km
+    let response = app.clone().oneshot(get("/api/events?near=52.52,13.405")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let events = body_json(response).await;
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["title"], "Berlin");
+    assert_eq!(events[0]["distance_km"], 0.0);
+
+    let response = app.clone().oneshot(get("/api/events?near=52.52,13.405&radius_km=30")).await.unwrap();
+    let events = body_json(response).await;
+    let titles: Vec<&str> = events.as_array().unwrap().iter().map(|e| e["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["Berlin", "Potsdam"]);
+    let distance = events[1]["distance_km"].as_f64().unwrap();
+    assert!((25.0..28.0).contains(&distance), "Berlin to Potsdam is ~26.6 km, got {}", distance);
+    assert_eq!(events[1]["latitude"], 52.3906);
+
+    // Without a filter every event is listed, without distances
+    let response = app.clone().oneshot(get("/api/events?scope=all")).await.unwrap();
+    let events = body_json(response).await;
+    assert_eq!(events.as_array().unwrap().len(), 4);
+    assert!(events[0].get("distance_km").is_none());
+
+    for uri in [
+        "/api/events?near=52.52",
+        "/api/events?near=91,13",
+        "/api/events?near=52.52,13.405&radius_km=0",
+        "/api/events?near=52.52,13.405&radius_km=501",
+    ] {
+        let response = app.clone().oneshot(get(uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", uri);
+    }
 
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
-                .uri("/api/events/00000000-0000-0000-0000-000000000000")
-                .body(Body::empty())
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Half a place",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z",
+                    "latitude": 52.52
+                }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
-
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["longitude"][0]["code"], "required");
 }
 
-// =====================
-// Participant Tests
-// =====================
-
 #[tokio::test]
-async fn test_create_and_list_participants() {
+async fn test_update_event() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
     // Create event
-    let event_body = json!({
-        "title": "Event",
+    let create_body = json!({
+        "title": "Original",
         "start_time": "2026-03-01T10:00:00Z",
         "end_time": "2026-03-01T12:00:00Z"
     });
@@ -383,8 +1206,9 @@ async fn test_create_and_list_participants() {
             Request::builder()
                 .method(Method::POST)
                 .uri("/api/events")
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(event_body.to_string()))
+                .body(Body::from(create_body.to_string()))
                 .unwrap(),
         )
         .await
@@ -393,65 +1217,55 @@ async fn test_create_and_list_participants() {
     let event = body_json(response).await;
     let event_id = event["id"].as_str().unwrap();
 
-    // Create participant
-    let part_body = json!({
-        "event_id": event_id,
-        "name": "John Doe",
-        "email": "john@example.com"
+    // Update event
+    let update_body = json!({
+        "title": "Updated Title",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T14:00:00Z",
+        "location": "New Room"
     });
 
     let response = app
-        .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/participants")
+                .method(Method::PUT)
+                .uri(format!("/api/events/{}", event_id))
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(part_body.to_string()))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-
-    assert_eq!(response.status(), StatusCode::CREATED);
-    let participant = body_json(response).await;
-    assert_eq!(participant["name"], "John Doe");
-    assert_eq!(participant["status"], "registered");
-
-    // List participants
-    let response = app
-        .oneshot(
-            Request::builder()
-                .uri(format!("/api/events/{}/participants", event_id))
-                .body(Body::empty())
+                .body(Body::from(update_body.to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::OK);
-    let participants = body_json(response).await;
-    assert_eq!(participants.as_array().unwrap().len(), 1);
+    let updated = body_json(response).await;
+    assert_eq!(updated["title"], "Updated Title");
+    assert_eq!(updated["location"], "New Room");
 }
 
 #[tokio::test]
-async fn test_update_participant_status() {
+async fn test_delete_event() {
     let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
     let app = build_app(state);
 
     // Create event
+    let create_body = json!({
+        "title": "To Delete",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    });
+
     let response = app
         .clone()
         .oneshot(
             Request::builder()
                 .method(Method::POST)
                 .uri("/api/events")
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Event",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
-                }).to_string()))
+                .body(Body::from(create_body.to_string()))
                 .unwrap(),
         )
         .await
@@ -460,403 +1274,7001 @@ async fn test_update_participant_status() {
     let event = body_json(response).await;
     let event_id = event["id"].as_str().unwrap();
 
-    // Create participant
+    // Delete event
     let response = app
         .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/participants")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "event_id": event_id,
-                    "name": "Jane",
-                    "email": "jane@test.com"
-                }).to_string()))
+                .method(Method::DELETE)
+                .uri(format!("/api/events/{}", event_id))
+                .header("Authorization", &auth)
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
 
-    let participant = body_json(response).await;
-    let part_id = participant["id"].as_str().unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Verify deleted
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/events/{}", event_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_recurring_series_expands_and_edits_occurrences() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let stranger_auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+    let send_as = |auth: Option<&str>, method: Method, uri: String, body: serde_json::Value| {
+        let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+        if let Some(auth) = auth {
+            builder = builder.header("Authorization", auth);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+    let send = |method: Method, uri: String, body: serde_json::Value| send_as(Some(&auth), method, uri, body);
+    let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let stamp = |t: chrono::DateTime<chrono::Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let first = chrono::Utc::now().date_naive().and_hms_opt(18, 0, 0).unwrap().and_utc() + chrono::Duration::days(7);
+    let week = chrono::Duration::weeks(1);
+    let event = json!({
+        "title": "Weekly Meetup",
+        "start_time": stamp(first),
+        "end_time": stamp(first + chrono::Duration::hours(2))
+    });
+
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/series".into(), json!({ "event": event, "recurrence": { "freq": "weekly" } })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["until"][0]["code"], "invalid_end");
+
+    let response = app
+        .clone()
+        .oneshot(send(
+            Method::POST,
+            "/api/series".into(),
+            json!({ "event": event, "recurrence": { "freq": "weekly", "count": 4 } }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let series_id = body_json(response).await["id"].as_str().unwrap().to_string();
+    let occurrences = |series_id: &str| get(format!("/api/series/{}/occurrences", series_id));
+    let occurrence = |series_id: &str, start| format!("/api/series/{}/occurrences/{}", series_id, stamp(start));
+
+    let response = app.clone().oneshot(occurrences(&series_id)).await.unwrap();
+    let listed = body_json(response).await;
+    assert_eq!(listed.as_array().unwrap().len(), 4);
+    assert!(listed[3]["event_id"].is_null());
+
+    // Registrations go to the occurrence's own event, created on first use
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, format!("{}/event", occurrence(&series_id, first + week)), json!({})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let opened = body_json(response).await;
+    assert_eq!(opened["series_id"], series_id.as_str());
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, format!("{}/event", occurrence(&series_id, first + week)), json!({})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["id"], opened["id"]);
+
+    // Only the series' owner edits or deletes occurrences
+    let mut renamed = event.clone();
+    renamed["title"] = json!("Meetup: Lightning Talks");
+    for (auth, status) in [(None, StatusCode::UNAUTHORIZED), (Some(stranger_auth.as_str()), StatusCode::FORBIDDEN)] {
+        let response = app
+            .clone()
+            .oneshot(send_as(auth, Method::PUT, occurrence(&series_id, first), renamed.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+        let response = app
+            .clone()
+            .oneshot(send_as(auth, Method::DELETE, occurrence(&series_id, first + week), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+        let response = app
+            .clone()
+            .oneshot(send_as(auth, Method::DELETE, format!("/api/series/{}", series_id), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+    }
+
+    // This occurrence only
+    let response = app.clone().oneshot(send(Method::PUT, occurrence(&series_id, first), renamed)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(occurrences(&series_id)).await.unwrap();
+    let listed = body_json(response).await;
+    assert_eq!(listed[0]["title"], "Meetup: Lightning Talks");
+    assert!(listed[0]["event_id"].is_string());
+    assert_eq!(listed[1]["title"], "Weekly Meetup");
+
+    // The third and all later occurrences move an hour later
+    let third = first + week * 2;
+    let response = app
+        .clone()
+        .oneshot(send(
+            Method::PUT,
+            format!("{}?scope=future", occurrence(&series_id, third)),
+            json!({
+                "title": "Weekly Meetup (new room)",
+                "start_time": stamp(third + chrono::Duration::hours(1)),
+                "end_time": stamp(third + chrono::Duration::hours(3))
+            }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let continued = body_json(response).await;
+    assert_eq!(continued["recurrence"]["count"], 2);
+    let continued_id = continued["id"].as_str().unwrap().to_string();
+    assert_ne!(continued_id, series_id);
+
+    let response = app.clone().oneshot(occurrences(&series_id)).await.unwrap();
+    assert_eq!(body_json(response).await.as_array().unwrap().len(), 2);
+    let response = app.clone().oneshot(occurrences(&continued_id)).await.unwrap();
+    let listed = body_json(response).await;
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    assert_eq!(listed[1]["title"], "Weekly Meetup (new room)");
+    let expected = (third + week + chrono::Duration::hours(1)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    assert_eq!(listed[1]["start_time"], expected.as_str());
+
+    // Deleting an occurrence's event leaves a gap rather than the rule's default
+    let event_id = opened["id"].as_str().unwrap();
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method(Method::DELETE).uri(format!("/api/events/{}", event_id)).header("Authorization", &auth).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method(Method::DELETE).uri(occurrence(&series_id, first)).header("Authorization", &auth).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.oneshot(occurrences(&series_id)).await.unwrap();
+    assert_eq!(body_json(response).await, json!([]));
+}
+
+// =====================
+// Event Validation Tests
+// =====================
+
+#[tokio::test]
+async fn test_organizer_accounts_own_the_events_they_create() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state);
+
+    let post = |uri: &str, body: Value| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let event_request = |method: Method, uri: String, auth: Option<&str>| {
+        let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+        if let Some(auth) = auth {
+            builder = builder.header("Authorization", auth);
+        }
+        builder
+            .body(Body::from(json!({
+                "title": "Owned",
+                "start_time": "2026-03-01T10:00:00Z",
+                "end_time": "2026-03-01T12:00:00Z"
+            }).to_string()))
+            .unwrap()
+    };
+
+    // Signing up normalizes the email and answers with a token
+    let signup = json!({ "name": "Ada", "email": " Ada@Example.com", "password": "correct horse battery" });
+    let response = app.clone().oneshot(post("/api/organizers/signup", signup.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada = body_json(response).await;
+    assert_eq!(ada["token_type"], "Bearer");
+    assert_eq!(ada["organizer"]["email"], "ada@example.com");
+    assert!(ada["organizer"].get("password_hash").is_none());
+
+    let response = app.clone().oneshot(post("/api/organizers/signup", signup)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(post("/api/organizers/signup", json!({ "name": "Bo", "email": "bo@example.com", "password": "short" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["password"][0]["code"], "out_of_range");
+
+    // Logging in checks the password
+    let login = |password: &str| post("/api/organizers/login", json!({ "email": "ADA@example.com", "password": password }));
+    let response = app.clone().oneshot(login("wrong password")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(login("correct horse battery")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let ada_auth = format!("Bearer {}", body_json(response).await["token"].as_str().unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/organizers/me").header("Authorization", &ada_auth).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["name"], "Ada");
+
+    // Creating events needs a token; a forged one is rejected outright
+    let response = app.clone().oneshot(event_request(Method::POST, "/api/events".into(), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let forged = format!("{}x", ada_auth);
+    let response = app.clone().oneshot(event_request(Method::POST, "/api/events".into(), Some(&forged))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let response = app.clone().oneshot(event_request(Method::POST, "/api/events".into(), Some(&ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let uri = format!("/api/events/{}", body_json(response).await["id"].as_str().unwrap());
+
+    // Reads stay public
+    let response = app.clone().oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only the owner, or an admin, may change the event
+    let response = app
+        .clone()
+        .oneshot(post("/api/organizers/signup", json!({ "name": "Bo", "email": "bo@example.com", "password": "another long one" })))
+        .await
+        .unwrap();
+    let bo_auth = format!("Bearer {}", body_json(response).await["token"].as_str().unwrap());
+    let response = app.clone().oneshot(event_request(Method::PUT, uri.clone(), Some(&bo_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(event_request(Method::DELETE, uri.clone(), Some(&bo_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(event_request(Method::PUT, uri.clone(), Some(&ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let admin_delete = Request::builder()
+        .method(Method::DELETE)
+        .uri(&uri)
+        .header("X-Admin-Token", "secret")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(admin_delete).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Events without an owner are left to admins
+    let mut admin_create = event_request(Method::POST, "/api/events".into(), None);
+    admin_create.headers_mut().insert("X-Admin-Token", "secret".parse().unwrap());
+    let response = app.clone().oneshot(admin_create).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let uri = format!("/api/events/{}", body_json(response).await["id"].as_str().unwrap());
+    let response = app.clone().oneshot(event_request(Method::PUT, uri.clone(), Some(&ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let mut admin_update = event_request(Method::PUT, uri, None);
+    admin_update.headers_mut().insert("X-Admin-Token", "secret".parse().unwrap());
+    let response = app.clone().oneshot(admin_update).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_organizer_availability_merges_owned_events_into_blocks() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let ada_auth = organizer_auth(&state).await;
+    let bo_auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+
+    let get = |uri: String, auth: (&str, &str)| Request::builder().uri(uri).header(auth.0, auth.1).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(get("/api/organizers/me".into(), ("Authorization", &ada_auth))).await.unwrap();
+    let ada_id = body_json(response).await["id"].as_str().unwrap().to_string();
+
+    let mut ids = Vec::new();
+    for (auth, start, end) in [
+        (&ada_auth, "10:00", "12:00"),
+        // Overlapping and back-to-back events make one busy block
+        (&ada_auth, "11:00", "13:00"),
+        (&ada_auth, "13:00", "14:00"),
+        (&ada_auth, "16:00", "17:00"),
+        (&ada_auth, "19:00", "21:00"),
+        (&ada_auth, "15:00", "16:00"),
+        (&bo_auth, "14:00", "15:00"),
+    ] {
+        let body = json!({
+            "title": "Booked",
+            "start_time": format!("2026-03-01T{}:00Z", start),
+            "end_time": format!("2026-03-01T{}:00Z", end)
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        ids.push(body_json(response).await["id"].as_str().unwrap().to_string());
+    }
+    // A cancelled event frees its time
+    sqlx::query("UPDATE events SET status = 'cancelled' WHERE id = ?")
+        .bind(ids[5].parse::<uuid::Uuid>().unwrap())
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let uri = format!("/api/organizers/{}/availability?from=2026-03-01T09:00:00Z&to=2026-03-01T20:00:00Z", ada_id);
+    let response = app.clone().oneshot(get(uri.clone(), ("Authorization", &ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let calendar = body_json(response).await;
+    assert_eq!(calendar["organizer_id"], ada_id);
+    let blocks: Vec<(String, String, String, Value)> = calendar["blocks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            let time = |field: &str| b[field].as_str().unwrap()[11..16].to_string();
+            (b["kind"].as_str().unwrap().to_string(), time("start"), time("end"), b["event_ids"].clone())
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![
+            ("free".into(), "09:00".into(), "10:00".into(), json!([])),
+            ("busy".into(), "10:00".into(), "14:00".into(), json!([ids[0], ids[1], ids[2]])),
+            ("free".into(), "14:00".into(), "16:00".into(), json!([])),
+            ("busy".into(), "16:00".into(), "17:00".into(), json!([ids[3]])),
+            ("free".into(), "17:00".into(), "19:00".into(), json!([])),
+            // Clipped to the window
+            ("busy".into(), "19:00".into(), "20:00".into(), json!([ids[4]])),
+        ]
+    );
+
+    // Admins may look; other organizers may not
+    let response = app.clone().oneshot(get(uri.clone(), ("X-Admin-Token", "secret"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(get(uri.clone(), ("Authorization", &bo_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Impersonating admins see only the calendar of the organizer they act as
+    let response = app.clone().oneshot(get("/api/organizers/me".into(), ("Authorization", &bo_auth))).await.unwrap();
+    let bo_id = body_json(response).await["id"].as_str().unwrap().to_string();
+    for (org, expected) in [(&bo_id, StatusCode::FORBIDDEN), (&ada_id, StatusCode::OK)] {
+        let mut request = get(uri.clone(), ("X-Admin-Token", "secret"));
+        request.headers_mut().insert("X-Impersonate-Org", org.parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    let uri = format!("/api/organizers/{}/availability?from=2026-03-01T09:00:00Z&to=2026-03-01T09:00:00Z", ada_id);
+    let response = app.clone().oneshot(get(uri, ("Authorization", &ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let uri = format!("/api/organizers/{}/availability?from=2026-01-01T00:00:00Z&to=2027-06-01T00:00:00Z", ada_id);
+    let response = app.clone().oneshot(get(uri, ("Authorization", &ada_auth))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let uri = format!("/api/organizers/{}/availability", uuid::Uuid::new_v4());
+    let response = app.clone().oneshot(get(uri, ("X-Admin-Token", "secret"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_delete_event_with_participants_requires_force() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Guarded", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let (organizer, auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, organizer.id).await;
+    let app = build_app(state.clone());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/events/{}", event.id))
+                .header("Authorization", &auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = body_json(response).await;
+    assert_eq!(body["participant_count"], 2);
+
+    let target = format!("/api/events/{}?force=true", event.id);
+    let token = prepare_confirmation(&app, "DELETE", &target).await;
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(&target)
+                .header("Authorization", &auth)
+                .header("x-confirmation-token", token)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // The cascade is recorded in the audit log
+    let (action, details): (String, String) = sqlx::query_as(
+        "SELECT action, details FROM audit_log WHERE entity_id = ?"
+    )
+    .bind(event.id.to_string())
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(action, "event.force_delete");
+    let details: serde_json::Value = serde_json::from_str(&details).unwrap();
+    assert_eq!(details["participant_count"], 2);
+}
+
+#[tokio::test]
+async fn test_force_delete_requires_single_use_confirmation() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T00:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    let event = seed_event(&state, "Guarded", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let (organizer, auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, organizer.id).await;
+    let app = build_app(state.clone());
+    let target = format!("/api/events/{}?force=true", event.id);
+
+    let delete = |token: Option<String>| {
+        let mut builder = Request::builder().method(Method::DELETE).uri(&target).header("Authorization", &auth);
+        if let Some(token) = token {
+            builder = builder.header("x-confirmation-token", token);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // No token
+    let response = app.clone().oneshot(delete(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    // Still required when the query is spelled differently
+    for query in ["force=%74rue", "force=tru%65&", "%66orce=true"] {
+        let encoded = Request::builder()
+            .method(Method::DELETE)
+            .uri(format!("/api/events/{}?{}", event.id, query))
+            .header("Authorization", &auth)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(encoded).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED, "{}", query);
+    }
+
+    // Token bound to a different request
+    let other = prepare_confirmation(&app, "DELETE", "/api/events/other?force=true").await;
+    let response = app.clone().oneshot(delete(Some(other))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    // Expired token
+    let stale = prepare_confirmation(&app, "DELETE", &target).await;
+    clock.advance(chrono::Duration::minutes(5));
+    let response = app.clone().oneshot(delete(Some(stale))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    // Valid token works once; a replay is rejected
+    let token = prepare_confirmation(&app, "DELETE", &target).await;
+    let response = app.clone().oneshot(delete(Some(token.clone()))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.oneshot(delete(Some(token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+}
+
+#[tokio::test]
+async fn test_participant_diff_lists_changes_in_window() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    let event = seed_event(&state, "Synced", None).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    clock.advance(chrono::Duration::hours(1));
+    let bob = seed_participant(&state, event.id, "Bob", "bob@example.com").await;
+    let app = build_app(state);
+
+    let set_status = |id: uuid::Uuid, status: &str| {
+        Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/participants/{}", id))
+            .header("Content-Type", "application/json")
+            .header("X-Admin-Token", "secret")
+            .body(Body::from(json!({ "status": status }).to_string()))
+            .unwrap()
+    };
+    let diff = |query: &str, admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/participants/diff?{}", event.id, query));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(set_status(ada.id, "confirmed")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    clock.advance(chrono::Duration::hours(1));
+    let response = app.clone().oneshot(set_status(bob.id, "cancelled")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Ada registered before the window; her confirmation falls inside it
+    let response = app.clone().oneshot(diff("from=2026-06-01T12:30:00Z", true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["to"], "2026-06-01T14:00:00Z");
+    let changes: Vec<(String, String, String)> = body["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["kind"].as_str().unwrap().to_string(), c["name"].as_str().unwrap().to_string(), c["to_status"].as_str().unwrap().to_string()))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("registered".to_string(), "Bob".to_string(), "registered".to_string()),
+            ("status_changed".to_string(), "Ada".to_string(), "confirmed".to_string()),
+        ]
+    );
+    assert_eq!(body["changes"][1]["from_status"], "registered");
+    assert_eq!(body["changes"][1]["email"], "ada@example.com");
+
+    // The end is exclusive, the start inclusive
+    let response = app
+        .clone()
+        .oneshot(diff("from=2026-06-01T14:00:00Z&to=2026-06-01T15:00:00Z", true))
+        .await
+        .unwrap();
+    let body = body_json(response).await;
+    assert_eq!(body["changes"].as_array().unwrap().len(), 1);
+    assert_eq!(body["changes"][0]["kind"], "cancelled");
+    assert_eq!(body["changes"][0]["participant_id"], bob.id.to_string());
+
+    let response = app
+        .clone()
+        .oneshot(diff("from=2026-06-01T12:00:00Z&to=2026-06-01T13:00:00Z", true))
+        .await
+        .unwrap();
+    let body = body_json(response).await;
+    assert_eq!(body["changes"].as_array().unwrap().len(), 1);
+    assert_eq!(body["changes"][0]["kind"], "registered");
+    assert_eq!(body["changes"][0]["name"], "Ada");
+
+    let response = app
+        .clone()
+        .oneshot(diff("from=2026-06-01T13:00:00Z&to=2026-06-01T13:00:00Z", true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.oneshot(diff("from=2026-06-01T12:00:00Z", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_create_event_invalid_time_range() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let body = json!({
+        "title": "Bad Event",
+        "start_time": "2026-03-01T14:00:00Z",
+        "end_time": "2026-03-01T10:00:00Z"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["end_time"][0]["code"], "end_before_start");
+}
+
+#[tokio::test]
+async fn test_create_event_empty_title() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let body = json!({
+        "title": "  ",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["title"][0]["code"], "blank");
+}
+
+#[tokio::test]
+async fn test_create_event_rejects_absurd_year_and_long_title() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let body = json!({
+        "title": "x".repeat(201),
+        "start_time": "9999-03-01T10:00:00Z",
+        "end_time": "9999-03-01T12:00:00Z"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["title"][0]["code"], "too_long");
+    assert_eq!(body["fields"]["start_time"][0]["code"], "out_of_range");
+    assert_eq!(body["fields"]["end_time"][0]["code"], "out_of_range");
+}
+
+#[tokio::test]
+async fn test_oversized_bodies_are_rejected_with_413() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let body = json!({
+        "title": "Huge",
+        "description": "x".repeat(2 * 1024 * 1024),
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    })
+    .to_string();
+
+    // Refused from the declared length, before the body is read
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .header("Content-Length", body.len())
+                .body(Body::from(body.clone()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let error = body_json(response).await;
+    assert_eq!(error["error"], "Request body too large");
+    assert_eq!(error["limit_bytes"], 1024 * 1024);
+
+    // Without a declared length the body is cut off at the limit
+    for naming in ["snake_case", "camelCase"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/events")
+                    .header("Authorization", &auth)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", format!("application/json; profile={}", naming))
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body_json(response).await["limit_bytes"], 1024 * 1024);
+    }
+}
+
+#[tokio::test]
+async fn test_create_participant_invalid_email() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Email Check", None).await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Nobody",
+                    "email": "not-an-email",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["email"][0]["code"], "invalid_email");
+}
+
+#[tokio::test]
+async fn test_event_date_bounds_with_admin_override() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        date_bounds: DateBounds {
+            epoch: Some("2026-01-01T00:00:00Z".parse().unwrap()),
+            max_years_ahead: 5,
+            ..DateBounds::default()
+        },
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    state.clock = Arc::new(MockClock::new("2026-06-01T00:00:00Z".parse().unwrap()));
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let request = |body: serde_json::Value, admin: bool| {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let before_epoch = json!({
+        "title": "Typo'd Year",
+        "start_time": "2025-06-01T10:00:00Z",
+        "end_time": "2025-06-01T12:00:00Z"
+    });
+    let too_far = json!({
+        "title": "Far Future",
+        "start_time": "2040-06-01T10:00:00Z",
+        "end_time": "2040-06-01T12:00:00Z"
+    });
+
+    let response = app.clone().oneshot(request(before_epoch.clone(), false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["start_time"][0]["code"], "before_epoch");
+
+    let response = app.clone().oneshot(request(too_far, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["start_time"][0]["code"], "too_far_ahead");
+
+    // Admins may override the bounds
+    let response = app.oneshot(request(before_epoch, true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_past_start_rejected_with_clock_skew_tolerance() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        date_bounds: DateBounds {
+            reject_past: true,
+            clock_skew: chrono::Duration::seconds(60),
+            ..DateBounds::default()
+        },
+        ..Config::default()
+    });
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let send = |method: Method, uri: String, start: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "title": "Starts now",
+                "start_time": start,
+                "end_time": "2026-06-01T14:00:00Z"
+            }).to_string()))
+            .unwrap()
+    };
+
+    // A client clock 30 seconds behind: the start is moved up to now
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/events".into(), "2026-06-01T11:59:30.250Z"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["start_time"], "2026-06-01T12:00:00Z");
+
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/events".into(), "2026-06-01T11:58:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["start_time"][0]["code"], "in_past");
+
+    // Once the event has begun it stays editable, but cannot be moved back
+    clock.advance(chrono::Duration::minutes(30));
+    let uri = format!("/api/events/{}", event["id"].as_str().unwrap());
+    let response = app.clone().oneshot(send(Method::PUT, uri.clone(), "2026-06-01T12:00:00Z")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["start_time"], "2026-06-01T12:00:00Z");
+
+    let response = app.oneshot(send(Method::PUT, uri, "2026-06-01T12:10:00Z")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_event_duration_limits() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let cases = [
+        ("2026-03-01T10:00:00Z", "2026-03-01T10:00:30Z", "duration_too_short"),
+        ("2026-03-01T10:00:00Z", "2027-04-05T10:00:00Z", "duration_too_long"),
+    ];
+
+    for (start, end, code) in cases {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/events")
+                    .header("Authorization", &auth)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "title": "Odd Duration",
+                        "start_time": start,
+                        "end_time": end
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body_json(response).await;
+        assert_eq!(body["fields"]["end_time"][0]["code"], code);
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_fields_rejected_unless_allowed() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let request = || {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "title": "Typo",
+                "start_time": "2026-03-01T10:00:00Z",
+                "end_time": "2026-03-01T12:00:00Z",
+                "max_participents": 10,
+                "locaton": "Berlin"
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = build_app(state.clone()).oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["unknown_fields"], json!(["locaton", "max_participents"]));
+
+    // Compatibility mode drops them as before
+    state.config = Arc::new(Config {
+        allow_unknown_fields: true,
+        ..Config::default()
+    });
+    let response = build_app(state).oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(body_json(response).await["max_participants"].is_null());
+}
+
+#[tokio::test]
+async fn test_malformed_json_errors_name_the_field() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+    let request = |body: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/events")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(&json!({
+            "title": "Wrong type",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z",
+            "max_participants": "ten"
+        }).to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(response).await;
+    assert_eq!(body["error"], "Invalid request body");
+    assert_eq!(body["field"], "max_participants");
+    assert_eq!(body["expected"], "i32");
+
+    let response = app
+        .clone()
+        .oneshot(request(r#"{"start_time": "2026-03-01T10:00:00Z", "end_time": "2026-03-01T12:00:00Z"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(response).await;
+    assert_eq!(body["field"], "title");
+    assert_eq!(body["message"], "missing field `title`");
+
+    let response = app.clone().oneshot(request("not json")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = body_json(response).await;
+    assert_eq!(body["error"], "Malformed JSON");
+    assert!(body.get("field").is_none());
+    assert_eq!(body["line"], 1);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(body_json(response).await["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_camel_case_negotiated_per_request() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+    const CAMEL: &str = "application/json; profile=\"camelCase\"";
+
+    // camelCase in, camelCase out
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .header("Accept", CAMEL)
+                .body(Body::from(json!({
+                    "title": "Camel Talk",
+                    "startTime": "2026-03-01T10:00:00Z",
+                    "endTime": "2026-03-01T12:00:00Z",
+                    "maxParticipants": 30
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["vary"], "accept");
+    let event = body_json(response).await;
+    assert_eq!(event["maxParticipants"], 30);
+    assert_eq!(event["participantVisibility"], "public");
+    assert!(event.get("max_participants").is_none());
+    let uri = format!("/api/events/{}", event["id"].as_str().unwrap());
+
+    // Other clients keep snake_case
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let event = body_json(response).await;
+    assert_eq!(event["max_participants"], 30);
+    assert!(event["start_time"].is_string());
+
+    // Metadata keys are the client's own and keep their spelling both ways
+    publish_event(&app, &auth, event["id"].as_str().unwrap()).await;
+    let metadata = json!({ "tshirt_size": "M", "dietNotes": "vegan" });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .header("Accept", CAMEL)
+                .body(Body::from(json!({
+                    "eventId": event["id"],
+                    "name": "Ada",
+                    "email": "ada@example.com",
+                    "privacyPolicyVersion": "2024-01",
+                    "metadata": metadata
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let participant = body_json(response).await;
+    assert_eq!(participant["metadata"], metadata);
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/participants/{}", participant["id"].as_str().unwrap())).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["metadata"], metadata);
+
+    // A metadata patch is a map of the client's keys from its root
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PATCH)
+                .uri(format!("/api/participants/{}/metadata", participant["id"].as_str().unwrap()))
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .header("Accept", CAMEL)
+                .body(Body::from(json!({ "shoeSize": 42 }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let patched = body_json(response).await;
+    assert_eq!(patched["metadata"]["shoeSize"], 42);
+    assert!(patched["eventId"].is_string());
+
+    // Each representation has a tag of its own
+    let list = |accept: Option<&str>, if_none_match: Option<&str>| {
+        let mut builder = Request::builder().uri("/api/events");
+        if let Some(accept) = accept {
+            builder = builder.header("Accept", accept);
+        }
+        if let Some(tag) = if_none_match {
+            builder = builder.header("If-None-Match", tag);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let response = app.clone().oneshot(list(None, None)).await.unwrap();
+    let snake_tag = response.headers()["etag"].to_str().unwrap().to_string();
+    let response = app.clone().oneshot(list(Some(CAMEL), None)).await.unwrap();
+    assert_eq!(response.headers()["vary"], "accept");
+    let camel_tag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_ne!(camel_tag, snake_tag);
+    assert!(camel_tag.ends_with("-camel\""));
+
+    let response = app.clone().oneshot(list(Some(CAMEL), Some(&camel_tag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["etag"], camel_tag.as_str());
+    let response = app.clone().oneshot(list(Some(CAMEL), Some(&snake_tag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_json(response).await.is_array());
+    let response = app.clone().oneshot(list(None, Some(&camel_tag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(list(None, Some(&snake_tag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // A camelCase default still serves clients asking for snake_case
+    let mut state = state;
+    state.config = Arc::new(Config {
+        json_field_naming: "camelCase".parse().unwrap(),
+        ..Config::default()
+    });
+    let app = build_app(state);
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(body_json(response).await["createdAt"].is_string());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(&uri)
+                .header("Accept", "application/json; profile=snake_case")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(body_json(response).await["created_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_get_nonexistent_event() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/events/00000000-0000-0000-0000-000000000000")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// =====================
+// Participant Tests
+// =====================
+
+#[tokio::test]
+async fn test_create_and_list_participants() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Create event
+    let event_body = json!({
+        "title": "Event",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(event_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+    publish_event(&app, &auth, event_id).await;
+
+    // Create participant
+    let part_body = json!({
+        "event_id": event_id,
+        "name": "John Doe",
+        "email": "john@example.com",
+        "privacy_policy_version": "2024-01"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(part_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let participant = body_json(response).await;
+    assert_eq!(participant["name"], "John Doe");
+    assert_eq!(participant["status"], "registered");
+
+    // List participants
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/events/{}/participants", event_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let participants = body_json(response).await;
+    assert_eq!(participants.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_list_participants_pages_filters_and_searches() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Paged", None).await;
+    let mut ids = Vec::new();
+    for (name, email) in [
+        ("Ada Lovelace", "ada@math.org"),
+        ("Grace Hopper", "grace@navy.mil"),
+        ("Alan Turing", "alan@math.org"),
+        ("Edsger Dijkstra", "edsger@tue.nl"),
+    ] {
+        ids.push(seed_participant(&state, event.id, name, email).await.id);
+        clock.advance(chrono::Duration::minutes(1));
+    }
+    sqlx::query("UPDATE participants SET status = 'confirmed' WHERE id IN (?, ?)")
+        .bind(ids[1])
+        .bind(ids[3])
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let app = build_app(state.clone());
+
+    let list = |query: &str, admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/participants{}", event.id, query));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let names = |body: &Value| -> Vec<String> {
+        body.as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap().to_string()).collect()
+    };
+    let fetch = |query: &'static str, admin: bool| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(list(query, admin)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", query);
+            let total = response.headers()["x-total-count"].to_str().unwrap().to_string();
+            (total, body_json(response).await)
+        }
+    };
+
+    // Warm the cache with the full list; filtered pages must not reuse it
+    let (total, body) = fetch("", false).await;
+    assert_eq!(total, "4");
+    assert_eq!(names(&body).len(), 4);
+
+    let (total, body) = fetch("?limit=2&offset=1", false).await;
+    assert_eq!(total, "4");
+    assert_eq!(names(&body), ["Grace Hopper", "Alan Turing"]);
+
+    let (total, body) = fetch("?status=confirmed", false).await;
+    assert_eq!(total, "2");
+    assert_eq!(names(&body), ["Grace Hopper", "Edsger Dijkstra"]);
+
+    let (total, body) = fetch("?search=RA&limit=1", false).await;
+    assert_eq!(total, "2");
+    assert_eq!(names(&body), ["Grace Hopper"]);
+
+    // Only organizers' searches match emails
+    let (total, _) = fetch("?search=math.org", false).await;
+    assert_eq!(total, "0");
+    let (total, body) = fetch("?search=math.org", true).await;
+    assert_eq!(total, "2");
+    assert_eq!(names(&body), ["Ada Lovelace", "Alan Turing"]);
+
+    // Cached pages are dropped with the full list on changes
+    sqlx::query("UPDATE participants SET status = 'confirmed' WHERE id = ?")
+        .bind(ids[0])
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    state.cache.invalidate_participants().await;
+    let (total, _) = fetch("?status=confirmed", false).await;
+    assert_eq!(total, "3");
+
+    for query in ["?limit=0", "?limit=201", "?fuzzy=ada&search=ada"] {
+        let response = app.clone().oneshot(list(query, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn test_participant_visibility_and_email_redaction() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Members Only",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z",
+                    "participant_visibility": "participants"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["participant_visibility"], "participants");
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event_id,
+                    "name": "Ada",
+                    "email": "ada@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let access_token = body_json(response).await["access_token"].as_str().unwrap().to_string();
+
+    let list = |header: Option<(&str, &str)>| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/participants", event_id));
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(list(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(list(Some(("X-Participant-Token", "bogus")))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Participants see each other, but not the emails
+    let response = app
+        .clone()
+        .oneshot(list(Some(("X-Participant-Token", &access_token))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let participants = body_json(response).await;
+    assert_eq!(participants[0]["name"], "Ada");
+    assert!(participants[0].get("email").is_none());
+
+    let response = app.oneshot(list(Some(("X-Admin-Token", "secret")))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await[0]["email"], "ada@example.com");
+}
+
+#[tokio::test]
+async fn test_fuzzy_participant_search() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Door Check", None).await;
+    seed_participant(&state, event.id, "Ada Lovelace", "ada@example.com").await;
+    seed_participant(&state, event.id, "John Smith", "js@example.com").await;
+    seed_participant(&state, event.id, "Grace Hopper", "amazing.grace@example.com").await;
+    let app = build_app(state);
+
+    let search = |query: &str, admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/participants?fuzzy={}", event.id, query));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let names = |body: serde_json::Value| {
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let response = app.clone().oneshot(search("jonh", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(names(body_json(response).await), ["John Smith"]);
+
+    let response = app.clone().oneshot(search("lovelase", false)).await.unwrap();
+    assert_eq!(names(body_json(response).await), ["Ada Lovelace"]);
+
+    // Emails are searched for organizers only
+    let response = app.clone().oneshot(search("amazng", false)).await.unwrap();
+    assert!(names(body_json(response).await).is_empty());
+    let response = app.clone().oneshot(search("amazng", true)).await.unwrap();
+    assert_eq!(names(body_json(response).await), ["Grace Hopper"]);
+
+    let response = app.oneshot(search(&"x".repeat(101), false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_update_participant_status() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Create event
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Event",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+    publish_event(&app, &auth, event_id).await;
+
+    // Create participant
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event_id,
+                    "name": "Jane",
+                    "email": "jane@test.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let participant = body_json(response).await;
+    let part_id = participant["id"].as_str().unwrap();
 
     // Update status
     let response = app
         .oneshot(
             Request::builder()
-                .method(Method::PUT)
-                .uri(format!("/api/participants/{}", part_id))
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({"status": "confirmed"}).to_string()))
+                .method(Method::PUT)
+                .uri(format!("/api/participants/{}", part_id))
+                .header("Content-Type", "application/json")
+                .header("X-Admin-Token", "secret")
+                .body(Body::from(json!({"status": "confirmed"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated = body_json(response).await;
+    assert_eq!(updated["status"], "confirmed");
+}
+
+#[tokio::test]
+async fn test_maybe_and_declined_free_places() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Small Room", Some(2)).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let grace = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let app = build_app(state);
+
+    let set_status = |id: uuid::Uuid, status: &str, admin: bool| {
+        let mut builder = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/participants/{}", id))
+            .header("Content-Type", "application/json");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::from(json!({ "status": status }).to_string())).unwrap()
+    };
+    let availability = || {
+        Request::builder()
+            .uri(format!("/api/events/{}/availability", event.id))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // A maybe does not hold a place, so someone else can register
+    let response = app.clone().oneshot(set_status(ada.id, "maybe", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(app.clone().oneshot(availability()).await.unwrap()).await;
+    assert_eq!(body["taken"], 1);
+    assert_eq!(body["remaining"], 1);
+    assert_eq!(body["counts"]["maybe"], 1);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Linus",
+                    "email": "linus@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Taking the place back fails while the event is full
+    let response = app.clone().oneshot(set_status(ada.id, "registered", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app.clone().oneshot(set_status(grace.id, "declined", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(set_status(ada.id, "registered", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Confirming and waitlisting are up to the organizer
+    let response = app.clone().oneshot(set_status(ada.id, "confirmed", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["status"][0]["code"], "organizer_only");
+    let response = app.clone().oneshot(set_status(ada.id, "confirmed", true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = body_json(app.clone().oneshot(availability()).await.unwrap()).await;
+    assert_eq!(body["max_participants"], 2);
+    assert_eq!(body["taken"], 2);
+    assert_eq!(body["remaining"], 0);
+    assert_eq!(
+        body["counts"],
+        json!({ "registered": 1, "confirmed": 1, "maybe": 0, "declined": 1, "waitlisted": 0, "cancelled": 0, "checked_in": 0 })
+    );
+}
+
+#[tokio::test]
+async fn test_duplicate_participant_rejected() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Create event
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Event",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+    publish_event(&app, &auth, event_id).await;
+
+    let part_body = json!({
+        "event_id": event_id,
+        "name": "John",
+        "email": "john@test.com",
+        "privacy_policy_version": "2024-01"
+    });
+
+    // First registration
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(part_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Duplicate registration
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(part_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_participant_emails_are_normalized_and_checked() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Normalized", None).await;
+    let app = build_app(state.clone());
+
+    let register = |email: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "John",
+                "email": email,
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(register("  John@Example.COM ")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = body_json(response).await;
+    assert_eq!(body["email"], "john@example.com");
+
+    // Differently cased, the same address is still a duplicate
+    let response = app.clone().oneshot(register("john@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    for email in [
+        "john",
+        "john@localhost",
+        "john..doe@example.com",
+        ".john@example.com",
+        "john doe@example.com",
+        "john@-example.com",
+        "john@example..com",
+        "john@192.168.0.1",
+        "\"john\"@example.com",
+    ] {
+        let response = app.clone().oneshot(register(email)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", email);
+        let body = body_json(response).await;
+        assert_eq!(body["fields"]["email"][0]["code"], "invalid_email", "{}", email);
+    }
+
+    for email in ["o'neil+events@mail.example.co.uk", "jürgen@bücher.de"] {
+        let response = app.clone().oneshot(register(email)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED, "{}", email);
+    }
+
+    // The migration normalizes stored addresses unless that would collide
+    let now = chrono::Utc::now();
+    for (email, name) in [("Ada@Example.com", "Ada"), ("Grace@Example.com", "Grace"), ("grace@example.com", "Grace")] {
+        sqlx::query(
+            "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at, dedupe_key)
+             VALUES (?1, ?2, ?3, ?4, 'registered', ?5, ?5, ?4)"
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(event.id)
+        .bind(name)
+        .bind(email)
+        .bind(now)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    }
+    let migration = backend::migrations::MIGRATIONS
+        .iter()
+        .find(|m| m.name == "normalize_participant_emails")
+        .unwrap();
+    for statement in migration.statements {
+        sqlx::query(statement).execute(&state.db_pool).await.unwrap();
+    }
+    let stored: Vec<(String, String)> = sqlx::query_as(
+        "SELECT email, dedupe_key FROM participants WHERE name IN ('Ada', 'Grace') ORDER BY email"
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        stored,
+        [
+            ("Grace@Example.com".to_string(), "Grace@Example.com".to_string()),
+            ("ada@example.com".to_string(), "ada@example.com".to_string()),
+            ("grace@example.com".to_string(), "grace@example.com".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_email_policy_controls_duplicate_registrations() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+    let event_body = |policy: &str| {
+        json!({
+            "title": "Family Day",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z",
+            "email_policy": policy
+        })
+    };
+    let send = |method: Method, uri: String, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(send(Method::POST, "/api/events".into(), event_body("allow_different_name"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["email_policy"], "allow_different_name");
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+    let event_uri = format!("/api/events/{}", event_id);
+
+    let register = |name: &str| {
+        send(
+            Method::POST,
+            "/api/participants".into(),
+            json!({
+                "event_id": event_id,
+                "name": name,
+                "email": "parent@example.com",
+                "privacy_policy_version": "2024-01"
+            }),
+        )
+    };
+
+    // Same email, different people
+    assert_eq!(app.clone().oneshot(register("Ada")).await.unwrap().status(), StatusCode::CREATED);
+    assert_eq!(app.clone().oneshot(register("Bob")).await.unwrap().status(), StatusCode::CREATED);
+    // Same person again, regardless of case and padding
+    assert_eq!(app.clone().oneshot(register(" ada ")).await.unwrap().status(), StatusCode::CONFLICT);
+
+    // Tightening to strict conflicts with the existing registrations
+    let response = app.clone().oneshot(send(Method::PUT, event_uri.clone(), event_body("strict"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app.clone().oneshot(send(Method::PUT, event_uri.clone(), event_body("unlimited"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["email_policy"], "unlimited");
+    assert_eq!(app.clone().oneshot(register("Ada")).await.unwrap().status(), StatusCode::CREATED);
+
+    let response = app
+        .clone()
+        .oneshot(send(Method::POST, "/api/events".into(), event_body("sometimes")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["field"], "email_policy");
+}
+
+#[tokio::test]
+async fn test_cancellation_token_cancels_registration() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Cancellable",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z",
+                    "max_participants": 1
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let event_id = body_json(response).await["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+
+    let mut registrations = Vec::new();
+    for name in ["Alice", "Bob"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/participants")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "event_id": event_id,
+                        "name": name,
+                        "email": format!("{}@test.com", name.to_lowercase()),
+                        "privacy_policy_version": "2024-01"
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        registrations.push(body_json(response).await);
+    }
+    let token = registrations[0]["cancellation_token"].as_str().unwrap().to_string();
+    assert_ne!(token, registrations[1]["cancellation_token"].as_str().unwrap());
+
+    let cancel = |token: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/registrations/cancel?token={}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Forged, tampered and unknown tokens are all rejected
+    let (nonce, signature) = token.split_once('.').unwrap();
+    let flipped = if signature.ends_with('0') { '1' } else { '0' };
+    let tampered_signature = format!("{}.{}{}", nonce, &signature[..signature.len() - 1], flipped);
+    let other_nonce = format!("{}.{}", "0".repeat(32), signature);
+    for bad in ["garbage", nonce, tampered_signature.as_str(), other_nonce.as_str()] {
+        let response = app.clone().oneshot(cancel(bad)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "token {}", bad);
+    }
+
+    let response = app.clone().oneshot(cancel(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["id"], registrations[0]["id"]);
+    assert_eq!(body["status"], "cancelled");
+
+    // The freed place goes to the waitlist
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/participants/{}", registrations[1]["id"].as_str().unwrap()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["status"], "registered");
+
+    // Cancelling again is harmless
+    let response = app.clone().oneshot(cancel(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["status"], "cancelled");
+
+    // Tokens signed with another key stop working
+    let mut rotated = state.clone();
+    rotated.config = Arc::new(Config {
+        cancellation_signing_key: Some("rotated".to_string()),
+        ..Config::default()
+    });
+    let response = build_app(rotated)
+        .oneshot(cancel(registrations[1]["cancellation_token"].as_str().unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_registration_form_answers_are_checked_and_stored() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Workshop", None).await;
+    let app = build_app(state.clone());
+
+    let form = json!({ "fields": [
+        { "key": "diet", "label": "Diet", "kind": "select", "required": true, "options": ["vegan", "omnivore"] },
+        { "key": "notes", "label": "Anything else?", "kind": "text" },
+        { "key": "photos_ok", "label": "Photos are fine", "kind": "checkbox" }
+    ] });
+    let put_form = |token: Option<&str>, form: &Value| {
+        let mut builder = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/events/{}/registration-form", event.id))
+            .header("Content-Type", "application/json");
+        if let Some(token) = token {
+            builder = builder.header("X-Admin-Token", token);
+        }
+        builder.body(Body::from(form.to_string())).unwrap()
+    };
+
+    let response = app.clone().oneshot(put_form(None, &form)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let invalid = json!({ "fields": [{ "key": "diet", "label": "Diet", "kind": "select" }] });
+    let response = app.clone().oneshot(put_form(Some("secret"), &invalid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["fields"][0]["code"], "invalid_options");
+
+    let response = app.clone().oneshot(put_form(Some("secret"), &form)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Anyone may read the form to render it
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/events/{}/registration-form", event.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    let keys: Vec<&str> = body["fields"].as_array().unwrap().iter().map(|f| f["key"].as_str().unwrap()).collect();
+    assert_eq!(keys, ["diet", "notes", "photos_ok"]);
+    assert_eq!(body["fields"][0]["options"], json!(["vegan", "omnivore"]));
+
+    let register = |email: &str, answers: Value| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "Alice",
+                "email": email,
+                "privacy_policy_version": "2024-01",
+                "answers": answers
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(register("alice@test.com", json!({ "diet": "carnivore", "notes": 3, "shoe_size": "42" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fields = body_json(response).await["fields"].clone();
+    assert_eq!(fields["answers.diet"][0]["code"], "invalid_option");
+    assert_eq!(fields["answers.notes"][0]["code"], "invalid_type");
+    assert_eq!(fields["answers.shoe_size"][0]["code"], "unknown_field");
+
+    let response = app.clone().oneshot(register("alice@test.com", json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["answers.diet"][0]["code"], "required");
+
+    let response = app
+        .clone()
+        .oneshot(register("alice@test.com", json!({ "diet": "vegan", "photos_ok": true })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let participant_id = body_json(response).await["id"].as_str().unwrap().to_string();
+
+    let stored: Vec<(String, String)> = sqlx::query_as(
+        "SELECT f.key, a.value FROM participant_answers a JOIN event_form_fields f ON f.id = a.field_id
+         WHERE a.participant_id = ? ORDER BY f.position",
+    )
+    .bind(uuid::Uuid::parse_str(&participant_id).unwrap())
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, [("diet".to_string(), "\"vegan\"".to_string()), ("photos_ok".to_string(), "true".to_string())]);
+
+    // Relabelling keeps answers; dropping a field drops them
+    let relabelled = json!({ "fields": [
+        { "key": "diet", "label": "Dietary needs", "kind": "select", "required": true, "options": ["vegan", "omnivore"] }
+    ] });
+    let response = app.clone().oneshot(put_form(Some("secret"), &relabelled)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let remaining: i64 = sqlx::query_scalar("SELECT count(*) FROM participant_answers")
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 1);
+}
+
+#[tokio::test]
+async fn test_ended_events_are_completed_and_closed_to_changes() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        event_completion_grace: chrono::Duration::minutes(30),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Meetup", None).await;
+    let alice = seed_participant(&state, event.id, "Alice", "alice@test.com").await;
+    let bob = seed_participant(&state, event.id, "Bob", "bob@test.com").await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let app = build_app(state.clone());
+
+    // Ended, but still within the grace period
+    clock.set(event.end_time + chrono::Duration::minutes(10));
+    let upcoming = seed_event(&state, "Upcoming", None).await;
+    assert!(completion::complete_ended(&state, clock.now()).await.unwrap().is_empty());
+
+    clock.set(event.end_time + chrono::Duration::minutes(30));
+    let completed = completion::complete_ended(&state, clock.now()).await.unwrap();
+    assert_eq!(completed.iter().map(|e| e.id).collect::<Vec<_>>(), [event.id]);
+    assert!(completion::complete_ended(&state, clock.now()).await.unwrap().is_empty());
+
+    let payload = expect_broadcast(&mut receiver, Channel::EventChanges, Duration::from_secs(3)).await;
+    assert_eq!(payload["operation"], "COMPLETE");
+    assert_eq!(payload["id"], event.id.to_string());
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/events/{}", event.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["status"], "completed");
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/events/{}", upcoming.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["status"], "published");
+
+    let register = |admin: bool| {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "Carol",
+                "email": "carol@test.com",
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+    let response = app.clone().oneshot(register(false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await["error"], "Event has ended; registration is closed");
+
+    let set_status = |id: uuid::Uuid, admin: bool| {
+        let mut builder = Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/participants/{}", id))
+            .header("Content-Type", "application/json");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::from(json!({ "status": "cancelled" }).to_string())).unwrap()
+    };
+    let response = app.clone().oneshot(set_status(alice.id, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method(Method::DELETE).uri(format!("/api/participants/{}", bob.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Admins may still correct the record
+    let response = app.clone().oneshot(set_status(alice.id, true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/participants/{}/checkin", bob.id))
+                .header("X-Admin-Token", "secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.oneshot(register(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_event_full_waitlists_and_promotes_on_cancel() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Create event with max_participants = 1
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Small Event",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z",
+                    "max_participants": 1
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+    publish_event(&app, &auth, event_id).await;
+
+    // First participant
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event_id,
+                    "name": "Alice",
+                    "email": "alice@test.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let alice_id = body_json(response).await["id"].as_str().unwrap().to_string();
+
+    // Later registrations join the waitlist in order
+    let mut waiting = Vec::new();
+    for name in ["Bob", "Carol"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/participants")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "event_id": event_id,
+                        "name": name,
+                        "email": format!("{}@test.com", name.to_lowercase()),
+                        "privacy_policy_version": "2024-01"
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let registration = body_json(response).await;
+        assert_eq!(registration["status"], "waitlisted");
+        waiting.push(registration["id"].as_str().unwrap().to_string());
+    }
+
+    let status_of = |id: String| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(Request::builder().uri(format!("/api/participants/{}", id)).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            body_json(response).await["status"].as_str().unwrap().to_string()
+        }
+    };
+
+    // Cancelling frees the place for the longest-waiting participant
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/participants/{}", alice_id))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "status": "cancelled" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(status_of(waiting[0].clone()).await, "registered");
+    assert_eq!(status_of(waiting[1].clone()).await, "waitlisted");
+
+    // So does deleting a participant holding a place
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/participants/{}", waiting[0]))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(status_of(waiting[1].clone()).await, "registered");
+}
+
+#[tokio::test]
+async fn test_capacity_holds_reserve_places_and_release_to_waitlist() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Gala", Some(4)).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let unlimited = seed_event(&state, "Open Air", None).await;
+    let (owner, owner_auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let (stranger, stranger_auth) = seed_organizer(&state).await;
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    let organizer = |method: Method, uri: String, body: Option<Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .header("Content-Type", "application/json");
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let holds_uri = format!("/api/events/{}/holds", event.id);
+    let register = |name: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": name,
+                "email": format!("{}@example.com", name.to_lowercase()),
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(&holds_uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "label": "Sponsors", "seats": 2 }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Holds belong to the event's owner
+    let stranger_org = stranger.id.to_string();
+    for (headers, expected) in [
+        (vec![("Authorization", owner_auth.as_str())], StatusCode::OK),
+        (vec![("Authorization", stranger_auth.as_str())], StatusCode::FORBIDDEN),
+        (vec![("X-Admin-Token", "secret"), ("X-Impersonate-Org", stranger_org.as_str())], StatusCode::FORBIDDEN),
+    ] {
+        let mut builder = Request::builder().uri(&holds_uri);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let response = app.clone().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, holds_uri.clone(), Some(json!({ "label": "Sponsors", "seats": 2 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let hold = body_json(response).await;
+    assert_eq!(hold["seats"], 2);
+    assert_eq!(hold["created_by"], "admin");
+
+    // Only free places can be held
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, holds_uri.clone(), Some(json!({ "label": "Press", "seats": 2 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await["available"], 1);
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::POST,
+            format!("/api/events/{}/holds", unlimited.id),
+            Some(json!({ "label": "Press", "seats": 1 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, holds_uri.clone(), Some(json!({ "label": " ", "seats": 0 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/events/{}/availability", event.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let availability = body_json(response).await;
+    assert_eq!(availability["taken"], 1);
+    assert_eq!(availability["held"], 2);
+    assert_eq!(availability["remaining"], 1);
+
+    // Held places are not open to registration
+    let response = app.clone().oneshot(register("Grace")).await.unwrap();
+    assert_eq!(body_json(response).await["status"], "registered");
+    let response = app.clone().oneshot(register("Linus")).await.unwrap();
+    let linus = body_json(response).await;
+    assert_eq!(linus["status"], "waitlisted");
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::PUT,
+            format!("/api/participants/{}", linus["id"].as_str().unwrap()),
+            Some(json!({ "status": "confirmed" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app.clone().oneshot(organizer(Method::GET, holds_uri.clone(), None)).await.unwrap();
+    assert_eq!(body_json(response).await.as_array().unwrap().len(), 1);
+
+    // Releasing gives the places to the waitlist
+    let hold_uri = format!("{}/{}", holds_uri, hold["id"].as_str().unwrap());
+    let response = app.clone().oneshot(organizer(Method::DELETE, hold_uri.clone(), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/participants/{}", linus["id"].as_str().unwrap())).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["status"], "registered");
+    let response = app.clone().oneshot(register("Barbara")).await.unwrap();
+    assert_eq!(body_json(response).await["status"], "registered");
+
+    let response = app.clone().oneshot(organizer(Method::DELETE, hold_uri, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let actions: Vec<(String, String)> = sqlx::query_as("SELECT action, details FROM audit_log WHERE entity_id = ? ORDER BY id")
+        .bind(event.id.to_string())
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].0, "capacity_hold.create");
+    assert_eq!(actions[1].0, "capacity_hold.release");
+    let details: Value = serde_json::from_str(&actions[1].1).unwrap();
+    assert_eq!(details["promoted"], 1);
+}
+
+#[tokio::test]
+async fn test_kiosk_devices_check_in_at_their_event_and_report_heartbeats() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Door", Some(10)).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let other = seed_event(&state, "Elsewhere", Some(10)).await;
+    let grace = seed_participant(&state, other.id, "Grace", "grace@example.com").await;
+    let (owner, owner_auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let (stranger, stranger_auth) = seed_organizer(&state).await;
+    let app = build_app(state);
+
+    let request = |method: Method, uri: String, header: Option<(&str, &str)>, body: Option<Value>| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let registration = json!({ "name": "Entrance A", "event_id": event.id });
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices".into(), None, Some(registration.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Only the event's owner registers its devices
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices".into(), Some(("Authorization", &stranger_auth)), Some(registration.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let mut impersonated = request(Method::POST, "/api/devices".into(), Some(("X-Admin-Token", "secret")), Some(registration.clone()));
+    impersonated.headers_mut().insert("X-Impersonate-Org", stranger.id.to_string().parse().unwrap());
+    let response = app.clone().oneshot(impersonated).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices".into(), Some(("Authorization", &owner_auth)), Some(json!({ "name": "Entrance B", "event_id": event.id }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices".into(), Some(("X-Admin-Token", "secret")), Some(registration)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let device = body_json(response).await;
+    let token = device["token"].as_str().unwrap().to_string();
+    let device_id = device["id"].as_str().unwrap().to_string();
+    assert!(device.get("token_hash").is_none());
+    let kiosk = Some(("X-Device-Token", token.as_str()));
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices/heartbeat".into(), None, Some(json!({ "pending_sync": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Before its first heartbeat a device is not online
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, "/api/admin/devices".into(), Some(("X-Admin-Token", "secret")), None))
+        .await
+        .unwrap();
+    let listed = body_json(response).await;
+    assert_eq!(listed[0]["online"], false);
+    assert!(listed[0]["last_seen_at"].is_null());
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::POST,
+            "/api/devices/heartbeat".into(),
+            kiosk,
+            Some(json!({ "pending_sync": 3, "app_version": "1.4.0" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["pending_sync"], 3);
+
+    // The token checks in at the device's own event only
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, format!("/api/participants/{}/checkin", ada.id), kiosk, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["status"], "checked_in");
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, format!("/api/participants/{}/checkin", grace.id), kiosk, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, format!("/api/events/{}/checkins", event.id), kiosk, None))
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["checked_in"], 1);
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, format!("/api/events/{}/checkins", other.id), kiosk, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::POST,
+            format!("/api/events/{}/holds", event.id),
+            kiosk,
+            Some(json!({ "label": "Friends", "seats": 1 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, "/api/admin/devices".into(), None, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, "/api/admin/devices".into(), Some(("X-Admin-Token", "secret")), None))
+        .await
+        .unwrap();
+    let listed = body_json(response).await;
+    let entrance = listed.as_array().unwrap().iter().find(|d| d["id"] == device_id.as_str()).unwrap();
+    assert_eq!(entrance["name"], "Entrance A");
+    assert_eq!(entrance["pending_sync"], 3);
+    assert_eq!(entrance["app_version"], "1.4.0");
+    assert_eq!(entrance["online"], true);
+
+    // A revoked token is refused
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::DELETE,
+            format!("/api/admin/devices/{}", device_id),
+            Some(("X-Admin-Token", "secret")),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/devices/heartbeat".into(), kiosk, Some(json!({}))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_offline_checkins_sync_with_earliest_time_winning() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Door", Some(10)).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let grace = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let linus = seed_participant(&state, event.id, "Linus", "linus@example.com").await;
+    let other = seed_event(&state, "Elsewhere", Some(10)).await;
+    let barbara = seed_participant(&state, other.id, "Barbara", "barbara@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'cancelled' WHERE id = ?")
+        .bind(linus.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let mut domain_events = state.domain_events.subscribe();
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    let request = |method: Method, uri: String, header: Option<(&str, &str)>, body: Option<Value>| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::POST,
+            "/api/devices".into(),
+            Some(("X-Admin-Token", "secret")),
+            Some(json!({ "name": "Entrance B", "event_id": event.id })),
+        ))
+        .await
+        .unwrap();
+    let device = body_json(response).await;
+    let token = device["token"].as_str().unwrap().to_string();
+    let kiosk = Some(("X-Device-Token", token.as_str()));
+
+    // Grace was checked in online, later than the kiosk saw her
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, format!("/api/participants/{}/checkin", grace.id), kiosk, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let now = chrono::Utc::now();
+    let ada_at = now - chrono::Duration::minutes(30);
+    let grace_at = now - chrono::Duration::hours(1);
+    let batch = json!({
+        "device_id": device["id"],
+        "checkins": [
+            { "participant_id": ada.id, "checked_in_at": ada_at },
+            { "participant_id": grace.id, "checked_in_at": grace_at },
+            { "participant_id": ada.id, "checked_in_at": now - chrono::Duration::minutes(10) },
+            { "participant_id": linus.id, "checked_in_at": now },
+            { "participant_id": barbara.id, "checked_in_at": now },
+            { "participant_id": uuid::Uuid::new_v4(), "checked_in_at": now },
+            { "participant_id": ada.id, "checked_in_at": now + chrono::Duration::hours(1) }
+        ]
+    });
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/checkins/batch".into(), None, Some(batch.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/checkins/batch".into(), kiosk, Some(batch.clone())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = body_json(response).await;
+    assert_eq!(report["device_id"], device["id"]);
+    let outcomes: Vec<&str> = report["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["outcome"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        outcomes,
+        ["checked_in", "corrected", "duplicate", "no_place", "wrong_event", "not_found", "future_timestamp"]
+    );
+    assert_eq!(report["results"][2]["accepted"], true);
+    assert_eq!(report["results"][3]["accepted"], false);
+
+    // Ada's check-in and the correction of Grace's time reach subscribers
+    let mut published = Vec::new();
+    while let Ok(event) = domain_events.try_recv() {
+        match event {
+            DomainEvent::ParticipantCheckedIn { participant, .. } => published.push(("checkin", participant)),
+            DomainEvent::ParticipantDetailsChanged { participant, .. } => published.push(("update", participant)),
+            _ => {}
+        }
+    }
+    assert_eq!(published.len(), 3);
+    assert_eq!((published[1].0, published[1].1.id), ("checkin", ada.id));
+    assert_eq!((published[2].0, published[2].1.id), ("update", grace.id));
+    assert_eq!(published[2].1.checked_in_at, Some(grace_at));
+
+    // Resubmitting changes nothing
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/checkins/batch".into(), kiosk, Some(batch)))
+        .await
+        .unwrap();
+    let report = body_json(response).await;
+    assert_eq!(report["results"][0]["outcome"], "duplicate");
+    assert_eq!(report["results"][1]["outcome"], "duplicate");
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, format!("/api/events/{}/checkins", event.id), kiosk, None))
+        .await
+        .unwrap();
+    let attendance = body_json(response).await;
+    assert_eq!(attendance["checked_in"], 2);
+    let recorded: Vec<(String, chrono::DateTime<chrono::Utc>)> = attendance["checkins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["name"].as_str().unwrap().to_string(), c["checked_in_at"].as_str().unwrap().parse().unwrap()))
+        .collect();
+    assert_eq!(recorded, [("Ada".to_string(), ada_at), ("Grace".to_string(), grace_at)]);
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::POST,
+            "/api/checkins/batch".into(),
+            kiosk,
+            Some(json!({ "device_id": uuid::Uuid::new_v4(), "checkins": [{ "participant_id": ada.id, "checked_in_at": now }] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/checkins/batch".into(), kiosk, Some(json!({ "checkins": [] }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // A frozen event rejects each record, leaving the check-in to be retried
+    sqlx::query("UPDATE events SET frozen = 1 WHERE id = ?")
+        .bind(event.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE participants SET status = 'registered' WHERE id = ?")
+        .bind(linus.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::POST,
+            "/api/checkins/batch".into(),
+            kiosk,
+            Some(json!({ "checkins": [
+                { "participant_id": linus.id, "checked_in_at": now },
+                { "participant_id": grace.id, "checked_in_at": grace_at - chrono::Duration::minutes(5) }
+            ] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = body_json(response).await;
+    for result in report["results"].as_array().unwrap() {
+        assert_eq!(result["outcome"], "event_frozen");
+        assert_eq!(result["accepted"], false);
+    }
+    let status: String = sqlx::query_scalar("SELECT status FROM participants WHERE id = ?")
+        .bind(linus.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "registered");
+}
+
+#[tokio::test]
+async fn test_checkin_tracks_attendance() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Door", None).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let grace = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let linus = seed_participant(&state, event.id, "Linus", "linus@example.com").await;
+    let (owner, owner_auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    let organizer = |method: Method, uri: String, body: Option<Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .header("Content-Type", "application/json");
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let checkin_uri = |id: uuid::Uuid| format!("/api/participants/{}/checkin", id);
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().method(Method::POST).uri(checkin_uri(ada.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(organizer(Method::POST, checkin_uri(ada.id), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let participant = body_json(response).await;
+    assert_eq!(participant["status"], "checked_in");
+    assert!(participant["checked_in_at"].is_string());
+
+    // A second scan of the same ticket is flagged
+    let response = app.clone().oneshot(organizer(Method::POST, checkin_uri(ada.id), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Only participants holding a place get in, and only through check-in
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::PUT, format!("/api/participants/{}", linus.id), Some(json!({ "status": "cancelled" }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(organizer(Method::POST, checkin_uri(linus.id), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::PUT, format!("/api/participants/{}", grace.id), Some(json!({ "status": "checked_in" }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = app.clone().oneshot(organizer(Method::POST, checkin_uri(uuid::Uuid::new_v4()), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::GET, format!("/api/events/{}/checkins", event.id), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let attendance = body_json(response).await;
+    assert_eq!(attendance["expected"], 2);
+    assert_eq!(attendance["checked_in"], 1);
+    assert_eq!(attendance["checkins"][0]["participant_id"], ada.id.to_string());
+    assert_eq!(attendance["checkins"][0]["name"], "Ada");
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::GET, format!("/api/events/{}/availability", event.id), None))
+        .await
+        .unwrap();
+    let availability = body_json(response).await;
+    assert_eq!(availability["taken"], 2);
+    assert_eq!(availability["counts"]["checked_in"], 1);
+
+    // A frozen event only lets admins record arrivals
+    sqlx::query("UPDATE events SET frozen = 1 WHERE id = ?")
+        .bind(event.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(checkin_uri(grace.id))
+                .header("Authorization", owner_auth.as_str())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::LOCKED);
+    let response = app.oneshot(organizer(Method::POST, checkin_uri(grace.id), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_seat_assignment_detects_conflicts_and_reports_occupancy() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Gala", None).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let grace = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let linus = seed_participant(&state, event.id, "Linus", "linus@example.com").await;
+    let (owner, owner_auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let (stranger, stranger_auth) = seed_organizer(&state).await;
+    let app = build_app(state);
+
+    let organizer = |method: Method, uri: String, body: Option<Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .header("Content-Type", "application/json");
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let seat_uri = |id: uuid::Uuid| format!("/api/participants/{}/seat", id);
+    let map_uri = format!("/api/events/{}/seat-map", event.id);
+    let occupancy_uri = format!("/api/events/{}/seats", event.id);
+
+    // Seating belongs to the event's owner
+    let stranger_org = stranger.id.to_string();
+    for (headers, expected) in [
+        (vec![("Authorization", owner_auth.as_str())], StatusCode::OK),
+        (vec![("Authorization", stranger_auth.as_str())], StatusCode::FORBIDDEN),
+        (vec![("X-Admin-Token", "secret"), ("X-Impersonate-Org", stranger_org.as_str())], StatusCode::FORBIDDEN),
+    ] {
+        let mut builder = Request::builder().uri(&occupancy_uri);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let response = app.clone().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // Seats can only be given once the event has a map
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(ada.id), Some(json!({ "section": "Table 1", "seat": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::PUT,
+            map_uri.clone(),
+            Some(json!({ "sections": [
+                { "name": "Table 1", "kind": "table", "seats": 2 },
+                { "name": "Table 1", "kind": "table", "seats": 4 }
+            ] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(&map_uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "sections": [] }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::PUT,
+            map_uri.clone(),
+            Some(json!({ "sections": [
+                { "name": "Table 1", "kind": "table", "seats": 2 },
+                { "name": "Row A", "kind": "row", "seats": 10 }
+            ] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let map = body_json(response).await;
+    assert_eq!(map["sections"][1]["kind"], "row");
+
+    // The map itself is public
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(&map_uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["sections"].as_array().unwrap().len(), 2);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(ada.id), Some(json!({ "section": "Table 1", "seat": 3 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body_json(response).await["fields"]["seat"].is_array());
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(ada.id), Some(json!({ "section": "Table 1", "seat": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let assignment = body_json(response).await;
+    assert_eq!(assignment["section"], "Table 1");
+    assert_eq!(assignment["seat"], 1);
+
+    // The seat is taken
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(grace.id), Some(json!({ "section": "Table 1", "seat": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await["participant_id"], ada.id.to_string());
+
+    // Moving frees the old seat
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(ada.id), Some(json!({ "section": "Table 1", "seat": 2 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(grace.id), Some(json!({ "section": "Table 1", "seat": 1 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A participant who gives up their place no longer occupies their seat
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(linus.id), Some(json!({ "section": "Row A", "seat": 5 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::PUT, format!("/api/participants/{}", linus.id), Some(json!({ "status": "cancelled" }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(linus.id), Some(json!({ "section": "Row A", "seat": 6 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, seat_uri(grace.id), Some(json!({ "section": "Row A", "seat": 5 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::GET, occupancy_uri.clone(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let occupancy = body_json(response).await;
+    assert_eq!(occupancy["capacity"], 12);
+    assert_eq!(occupancy["occupied"], 2);
+    assert_eq!(occupancy["unseated"], 0);
+    assert_eq!(occupancy["sections"][0]["guests"][0]["name"], "Ada");
+    assert_eq!(occupancy["sections"][0]["guests"][0]["seat"], 2);
+    assert_eq!(occupancy["sections"][1]["guests"][0]["participant_id"], grace.id.to_string());
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::GET, format!("/api/participants/ndjson?event_id={}", event.id), None))
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let rows: Vec<Value> = body
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    let seat_of = |id: uuid::Uuid| rows.iter().find(|r| r["id"] == id.to_string()).unwrap()["seat"].clone();
+    assert_eq!(seat_of(ada.id), json!({ "section": "Table 1", "seat": 2 }));
+    assert_eq!(seat_of(linus.id), Value::Null);
+
+    // Seats the new map lacks are given up
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::PUT,
+            map_uri.clone(),
+            Some(json!({ "sections": [{ "name": "Table 1", "kind": "table", "seats": 1 }] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::GET, occupancy_uri.clone(), None))
+        .await
+        .unwrap();
+    let occupancy = body_json(response).await;
+    assert_eq!(occupancy["occupied"], 0);
+    assert_eq!(occupancy["unseated"], 2);
+
+    let response = app.clone().oneshot(organizer(Method::DELETE, seat_uri(ada.id), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_concurrent_registrations_respect_capacity() {
+    let (state, temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "One Seat", Some(1)).await;
+
+    // A second "instance" with its own pool on the same database file
+    let mut other = state.clone();
+    other.db_pool = db::create_pool(temp_dir.path().join("test.db").to_str().unwrap())
+        .await
+        .unwrap();
+    let apps = [build_app(state.clone()), build_app(other)];
+
+    let mut handles = Vec::new();
+    for i in 0..100 {
+        let app = apps[i % 2].clone();
+        let body = json!({
+            "event_id": event.id,
+            "name": format!("Racer {}", i),
+            "email": format!("racer{}@example.com", i),
+            "privacy_policy_version": "2024-01"
+        });
+        handles.push(tokio::spawn(async move {
+            app.oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/participants")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+        }));
+    }
+
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), StatusCode::CREATED);
+    }
+
+    // Everyone beyond the one seat is waitlisted
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM participants WHERE event_id = ? AND status = 'registered'")
+        .bind(event.id)
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_registration_records_consent_and_withdrawal() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        privacy_policy_version: Some("2024-01".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Consent", None).await;
+    let app = build_app(state);
+    let register = |email: &str, version: Option<&str>| {
+        let mut body = json!({ "event_id": event.id, "name": "Ada", "email": email, "marketing_opt_in": true });
+        if let Some(version) = version {
+            body["privacy_policy_version"] = json!(version);
+        }
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // The policy version is required and must be the current one
+    let response = app.clone().oneshot(register("ada@example.com", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.clone().oneshot(register("ada@example.com", Some("2023-06"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["privacy_policy_version"][0]["code"], "outdated_policy");
+
+    let response = app.clone().oneshot(register("ada@example.com", Some("2024-01"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = body_json(response).await;
+    assert_eq!(body["name"], "Ada");
+    assert_eq!(body["consent"]["privacy_policy_version"], "2024-01");
+    assert_eq!(body["consent"]["marketing_opt_in"], true);
+    let token = body["consent"]["withdrawal_token"].as_str().unwrap().to_string();
+
+    let withdraw = |token: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/consents/{}/withdraw", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.clone().oneshot(withdraw(&token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let withdrawn = body_json(response).await;
+    assert_eq!(withdrawn["marketing_opt_in"], false);
+    assert!(withdrawn.get("withdrawal_token").is_none());
+
+    // Withdrawing again keeps the original timestamp
+    let response = app.clone().oneshot(withdraw(&token)).await.unwrap();
+    assert_eq!(body_json(response).await["marketing_updated_at"], withdrawn["marketing_updated_at"]);
+
+    let response = app.clone().oneshot(withdraw("unknown")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Exports carry consent but never the token
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/participants/ndjson")
+                .header("X-Admin-Token", "secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let line: serde_json::Value = serde_json::from_slice(body.split(|b| *b == b'\n').next().unwrap()).unwrap();
+    assert_eq!(line["email"], "ada@example.com");
+    assert_eq!(line["consent"]["privacy_policy_version"], "2024-01");
+    assert_eq!(line["consent"]["marketing_opt_in"], false);
+    assert!(line["consent"].get("withdrawal_token").is_none());
+}
+
+#[tokio::test]
+async fn test_reminders_follow_participant_preferences() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    let mailer = Arc::new(RecordingMailer::default());
+    state.mailer = mailer.clone();
+    // Starts on 2026-06-08 at 12:00
+    let event = seed_event(&state, "Reminded", None).await;
+    let app = build_app(state.clone());
+
+    let register = |email: &str, reminders: Option<serde_json::Value>| {
+        let mut body = json!({
+            "event_id": event.id,
+            "name": email.split('@').next().unwrap(),
+            "email": email,
+            "privacy_policy_version": "2024-01"
+        });
+        if let Some(reminders) = reminders {
+            body["reminders"] = reminders;
+        }
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(register("daily@example.com", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(body_json(response).await["reminders"]["lead_times"], json!(["24h"]));
+    let response = app.clone().oneshot(register("both@example.com", Some(json!(["1h", "24h"])))).await.unwrap();
+    let token = body_json(response).await["reminders"]["token"].as_str().unwrap().to_string();
+    let response = app.clone().oneshot(register("quiet@example.com", Some(json!([])))).await.unwrap();
+    assert_eq!(body_json(response).await["reminders"]["lead_times"], json!([]));
+    let response = app.clone().oneshot(register("odd@example.com", Some(json!(["2h"])))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Nothing due a week ahead
+    assert_eq!(reminders::send_due(&state).await.unwrap(), 0);
+
+    // A day ahead, for those who want it, once
+    clock.advance(chrono::Duration::days(6) + chrono::Duration::hours(1));
+    assert_eq!(reminders::send_due(&state).await.unwrap(), 2);
+    assert_eq!(reminders::send_due(&state).await.unwrap(), 0);
+    let mut recipients: Vec<String> = mailer.sent().iter().map(|m| m.to_address.clone()).collect();
+    recipients.sort();
+    assert_eq!(recipients, vec!["both@example.com", "daily@example.com"]);
+    assert!(mailer.sent()[0].text.contains("starts tomorrow"));
+
+    // Preferences change through the token
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/reminders/{}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "lead_times": ["1h"] }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let preferences = body_json(response).await;
+    assert_eq!(preferences["lead_times"], json!(["1h"]));
+    assert!(preferences.get("token").is_none());
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/reminders/unknown").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // An hour ahead
+    clock.advance(chrono::Duration::hours(22) + chrono::Duration::minutes(30));
+    assert_eq!(reminders::send_due(&state).await.unwrap(), 1);
+    let last = mailer.sent().pop().unwrap();
+    assert_eq!(last.to_address, "both@example.com");
+    assert!(last.text.contains("starts in an hour"));
+    assert!(last.text.contains(&token));
+}
+
+// =====================
+// Event Freeze Tests
+// =====================
+
+#[tokio::test]
+async fn test_frozen_event_blocks_non_admin_mutations() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Reconciling", None).await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let (organizer, auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, organizer.id).await;
+    let app = build_app(state);
+
+    let request = |method: Method, uri: String, body: Option<serde_json::Value>, admin: bool| {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .header("Authorization", &auth);
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        let body = body.map(|b| Body::from(b.to_string())).unwrap_or_else(Body::empty);
+        builder.body(body).unwrap()
+    };
+    let registration = |email: &str| {
+        Some(json!({ "event_id": event.id, "name": "Ada", "email": email, "privacy_policy_version": "2024-01" }))
+    };
+    let freeze_uri = format!("/api/events/{}/freeze", event.id);
+
+    // Only admins may freeze
+    let response = app.clone().oneshot(request(Method::POST, freeze_uri.clone(), None, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(request(Method::POST, freeze_uri, None, true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["frozen"], true);
+
+    let payload = expect_broadcast(&mut receiver, Channel::EventChanges, Duration::from_secs(3)).await;
+    assert_eq!(payload["operation"], "FREEZE");
+    assert_eq!(payload["id"], event.id.to_string());
+
+    // Non-admin registrations and edits are locked out
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/participants".into(), registration("ada@example.com"), false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::LOCKED);
+
+    let update = json!({
+        "title": "Renamed",
+        "start_time": event.start_time,
+        "end_time": event.end_time
+    });
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, format!("/api/events/{}", event.id), Some(update), false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::LOCKED);
+
+    // Admins can still register participants
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/api/participants".into(), registration("grace@example.com"), true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Unfreezing reopens registration
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, format!("/api/events/{}/unfreeze", event.id), None, true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(request(Method::POST, "/api/participants".into(), registration("ada@example.com"), false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_admin_impersonation_acts_as_organizer_and_is_audited() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Support case", None).await;
+    let (organizer, _) = seed_organizer(&state).await;
+    let org = organizer.id.to_string();
+    let org = org.as_str();
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    let request = |method: Method, uri: String, admin: bool, org: Option<&str>| {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        if let Some(org) = org {
+            builder = builder.header("X-Impersonate-Org", org);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let freeze_uri = format!("/api/events/{}/freeze", event.id);
+
+    // Impersonation needs the admin token
+    let response = app.clone().oneshot(request(Method::GET, "/api/events".into(), false, Some(org))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Only existing organizers can be impersonated
+    let unknown = uuid::Uuid::new_v4().to_string();
+    let response = app.clone().oneshot(request(Method::GET, "/api/events".into(), true, Some(&unknown))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.clone().oneshot(request(Method::GET, "/api/events".into(), true, Some("acme"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Impersonated requests act with organizer privileges
+    let response = app.clone().oneshot(request(Method::GET, "/api/events".into(), true, Some(org))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-impersonating"], org);
+
+    let response = app.clone().oneshot(request(Method::POST, freeze_uri.clone(), true, Some(org))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(request(Method::GET, "/api/admin/cache".into(), true, Some(org))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Without the header the same token is a plain admin
+    let response = app.clone().oneshot(request(Method::POST, freeze_uri, true, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("x-impersonating").is_none());
+
+    let audited: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT entity_id, actor, details FROM audit_log WHERE action = 'impersonation.request' ORDER BY id"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited.len(), 3);
+    assert!(audited.iter().all(|(entity, actor, _)| entity == org && actor == "admin"));
+    let details: serde_json::Value = serde_json::from_str(&audited[1].2).unwrap();
+    assert_eq!(details["method"], "POST");
+    assert_eq!(details["status"], 403);
+}
+
+#[tokio::test]
+async fn test_impersonation_checks_ownership_as_the_organizer() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let (organizer, _) = seed_organizer(&state).await;
+    let (other, _) = seed_organizer(&state).await;
+    let own = seed_event(&state, "Own", None).await;
+    let foreign = seed_event(&state, "Foreign", None).await;
+    assign_owner(&state, own.id, organizer.id).await;
+    assign_owner(&state, foreign.id, other.id).await;
+    let app = build_app(state);
+    let org = organizer.id.to_string();
+
+    let impersonated = |method: Method, uri: String, body: Option<Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .header("X-Impersonate-Org", &org)
+            .header("Content-Type", "application/json");
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let update = |event: &backend::models::Event| {
+        json!({ "title": "Renamed", "start_time": event.start_time, "end_time": event.end_time })
+    };
+
+    let response = app
+        .clone()
+        .oneshot(impersonated(Method::PUT, format!("/api/events/{}", own.id), Some(update(&own))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(impersonated(Method::PUT, format!("/api/events/{}", foreign.id), Some(update(&foreign))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Endpoints for the signed-in organizer serve the impersonated one
+    let response = app.clone().oneshot(impersonated(Method::GET, "/api/organizers/me".into(), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["id"], org);
+    let response = app
+        .oneshot(impersonated(Method::GET, "/api/organizers/me/webhooks".into(), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_publishing_and_series_changes_check_the_owner() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let (ada, ada_auth) = seed_organizer(&state).await;
+    let (bo, bo_auth) = seed_organizer(&state).await;
+    let owned = seed_event(&state, "Owned", None).await;
+    assign_owner(&state, owned.id, ada.id).await;
+    let ownerless = seed_event(&state, "Ownerless", None).await;
+    sqlx::query("UPDATE events SET status = 'draft'")
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let app = build_app(state);
+    let (ada_org, bo_org) = (ada.id.to_string(), bo.id.to_string());
+
+    let request = |headers: &[(&str, &str)], method: Method, uri: &str, body: Option<Value>| {
+        let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).unwrap()
+    };
+    let as_ada = [("Authorization", ada_auth.as_str())];
+    let as_bo = [("Authorization", bo_auth.as_str())];
+    let impersonating_ada = [("X-Admin-Token", "secret"), ("X-Impersonate-Org", ada_org.as_str())];
+    let impersonating_bo = [("X-Admin-Token", "secret"), ("X-Impersonate-Org", bo_org.as_str())];
+    let admin = [("X-Admin-Token", "secret")];
+
+    // Only the owner publishes an event
+    let publish = format!("/api/events/{}/publish", owned.id);
+    for (headers, expected) in [
+        (&as_bo[..], StatusCode::FORBIDDEN),
+        (&impersonating_bo[..], StatusCode::FORBIDDEN),
+        (&as_ada[..], StatusCode::OK),
+    ] {
+        let response = app.clone().oneshot(request(headers, Method::POST, &publish, None)).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // Events without an owner are published by admins only
+    let publish = format!("/api/events/{}/publish", ownerless.id);
+    for (headers, expected) in [
+        (&as_ada[..], StatusCode::FORBIDDEN),
+        (&impersonating_ada[..], StatusCode::FORBIDDEN),
+        (&admin[..], StatusCode::OK),
+    ] {
+        let response = app.clone().oneshot(request(headers, Method::POST, &publish, None)).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // Series belong to the organizer who created them; those an admin
+    // created have no owner
+    let first = chrono::Utc::now().date_naive().and_hms_opt(18, 0, 0).unwrap().and_utc() + chrono::Duration::days(7);
+    let stamp = |t: chrono::DateTime<chrono::Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let series = json!({
+        "event": {
+            "title": "Weekly Meetup",
+            "start_time": stamp(first),
+            "end_time": stamp(first + chrono::Duration::hours(2))
+        },
+        "recurrence": { "freq": "weekly", "count": 4 }
+    });
+    let mut created = Vec::new();
+    for headers in [&as_ada[..], &admin[..]] {
+        let response = app
+            .clone()
+            .oneshot(request(headers, Method::POST, "/api/series", Some(series.clone())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        created.push(body_json(response).await["id"].as_str().unwrap().to_string());
+    }
+    let occurrence = |series_id: &str| format!("/api/series/{}/occurrences/{}", series_id, stamp(first));
+
+    for (series_id, attempts) in [
+        (
+            &created[0],
+            [
+                (&as_bo[..], StatusCode::FORBIDDEN),
+                (&impersonating_bo[..], StatusCode::FORBIDDEN),
+                (&as_ada[..], StatusCode::NO_CONTENT),
+            ],
+        ),
+        (
+            &created[1],
+            [
+                (&as_ada[..], StatusCode::FORBIDDEN),
+                (&impersonating_ada[..], StatusCode::FORBIDDEN),
+                (&admin[..], StatusCode::NO_CONTENT),
+            ],
+        ),
+    ] {
+        for (headers, expected) in attempts {
+            let response = app
+                .clone()
+                .oneshot(request(headers, Method::DELETE, &occurrence(series_id), None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_organizer_views_are_limited_to_owned_events() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let (ada, ada_auth) = seed_organizer(&state).await;
+    let (bo, bo_auth) = seed_organizer(&state).await;
+    let event = seed_event(&state, "Owned", None).await;
+    assign_owner(&state, event.id, ada.id).await;
+    sqlx::query("UPDATE events SET participant_visibility = 'organizers' WHERE id = ?")
+        .bind(event.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let participant = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    for (title, owner) in [("Ada draft", ada.id), ("Bo draft", bo.id)] {
+        let draft = seed_event(&state, title, None).await;
+        assign_owner(&state, draft.id, owner).await;
+        sqlx::query("UPDATE events SET status = 'draft' WHERE id = ?")
+            .bind(draft.id)
+            .execute(&state.db_pool)
+            .await
+            .unwrap();
+    }
+    let app = build_app(state);
+
+    let ada_token = ("Authorization", ada_auth.as_str());
+    let bo_token = ("Authorization", bo_auth.as_str());
+    let admin = ("X-Admin-Token", "secret");
+    let request = |method: Method, uri: String, headers: &[(&str, &str)]| {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // Drafts are listed to their owner and admins
+    for (headers, expected) in [
+        (vec![], vec!["Owned"]),
+        (vec![ada_token], vec!["Ada draft", "Owned"]),
+        (vec![bo_token], vec!["Bo draft", "Owned"]),
+        (vec![admin], vec!["Ada draft", "Bo draft", "Owned"]),
+    ] {
+        let response = app.clone().oneshot(request(Method::GET, "/api/events?scope=all".into(), &headers)).await.unwrap();
+        let mut titles: Vec<String> = body_json(response)
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["title"].as_str().unwrap().to_string())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, expected);
+    }
+
+    // The owner's token lists participants as organizer; others are turned away
+    let participants = format!("/api/events/{}/participants", event.id);
+    let response = app.clone().oneshot(request(Method::GET, participants.clone(), &[ada_token])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await[0]["email"], "grace@example.com");
+    let (ada_org, bo_org) = (ada.id.to_string(), bo.id.to_string());
+    let as_ada = ("X-Impersonate-Org", ada_org.as_str());
+    let as_bo = ("X-Impersonate-Org", bo_org.as_str());
+    for (headers, expected) in [
+        (vec![bo_token], StatusCode::FORBIDDEN),
+        (vec![admin, as_bo], StatusCode::FORBIDDEN),
+        (vec![admin, as_ada], StatusCode::OK),
+    ] {
+        let response = app.clone().oneshot(request(Method::GET, participants.clone(), &headers)).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // Only the owner checks in at the door
+    let checkin = format!("/api/participants/{}/checkin", participant.id);
+    for (headers, expected) in [
+        (vec![bo_token], StatusCode::FORBIDDEN),
+        (vec![admin, as_bo], StatusCode::FORBIDDEN),
+        (vec![ada_token], StatusCode::OK),
+    ] {
+        let response = app.clone().oneshot(request(Method::POST, checkin.clone(), &headers)).await.unwrap();
+        assert_eq!(response.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_event_audit_masks_personal_data_for_non_admins() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Audited", None).await;
+    let (organizer, _) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, organizer.id).await;
+    let org = organizer.id.to_string();
+    let (stranger, _) = seed_organizer(&state).await;
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    backend::audit::record(
+        &pool,
+        "participant.remove",
+        "participant",
+        "p-1",
+        "admin",
+        &json!({
+            "event_id": event.id.to_string(),
+            "participant": { "name": "Ada Lovelace", "email": "ada@example.com", "status": "registered" },
+            "mails": [{ "to_address": "ada@example.com", "subject": "Removed" }]
+        }),
+        chrono::Utc::now(),
+    )
+    .await
+    .unwrap();
+
+    let audit = |org: Option<&str>, admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/audit", event.id));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        if let Some(org) = org {
+            builder = builder.header("X-Impersonate-Org", org);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(audit(None, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Admins see the snapshot as recorded
+    let response = app.clone().oneshot(audit(None, true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let entries = body_json(response).await;
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["details"]["participant"]["email"], "ada@example.com");
+    assert_eq!(entries[0]["details"]["participant"]["name"], "Ada Lovelace");
+
+    // Organizers get names and emails left out, everything else intact
+    let response = app.clone().oneshot(audit(Some(&org), true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let entries = body_json(response).await;
+    let entry = &entries[0];
+    assert_eq!(entry["action"], "participant.remove");
+    assert_eq!(entry["details"]["participant"], json!({ "status": "registered" }));
+    assert_eq!(entry["details"]["mails"], json!([{ "subject": "Removed" }]));
+    assert!(!entry.to_string().contains("ada@example.com"));
+
+    // Other organizers' events stay closed, impersonated or not
+    let response = app.clone().oneshot(audit(Some(&stranger.id.to_string()), true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+// =====================
+// Digest Tests
+// =====================
+
+#[tokio::test]
+async fn test_event_digest_summarizes_last_day() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Digest Night", None).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let grace = seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    seed_participant(&state, event.id, "Linus", "linus@example.com").await;
+    let owner_auth = organizer_auth(&state).await;
+    let stranger_auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/organizers/me").header("Authorization", &owner_auth).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let owner_id: uuid::Uuid = body_json(response).await["id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE events SET organizer_id = ? WHERE id = ?")
+        .bind(owner_id)
+        .bind(event.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let set_status = |id: uuid::Uuid, status: &str| {
+        Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/participants/{}", id))
+            .header("Content-Type", "application/json")
+            .header("X-Admin-Token", "secret")
+            .body(Body::from(json!({ "status": status }).to_string()))
+            .unwrap()
+    };
+    for (id, status) in [(ada.id, "cancelled"), (grace.id, "waitlisted"), (grace.id, "registered")] {
+        let response = app.clone().oneshot(set_status(id, status)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let digest = |format: &str, auth: Option<(&str, &str)>| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/digest{}", event.id, format));
+        if let Some((name, value)) = auth {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // It names everyone who moved, so only the owner and admins may read it
+    let response = app.clone().oneshot(digest("", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(digest("", Some(("Authorization", &stranger_auth)))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(digest("", Some(("Authorization", &owner_auth)))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["new_registrations"].as_array().unwrap().len(), 3);
+    assert_eq!(body["cancellations"][0]["name"], "Ada");
+    assert_eq!(body["waitlist_joined"][0]["name"], "Grace");
+    assert_eq!(body["waitlist_promoted"][0]["name"], "Grace");
+    assert_eq!(body["totals"]["registered"], 2);
+    assert_eq!(body["totals"]["cancelled"], 1);
+
+    let response = app.oneshot(digest("?format=text", Some(("X-Admin-Token", "secret")))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("Cancellations (1)"));
+    assert!(text.contains("Ada <ada@example.com>"));
+}
+
+// =====================
+// Geo Gating Tests
+// =====================
+
+struct FixedCountries(Vec<(&'static str, &'static str)>);
+
+impl CountryLookup for FixedCountries {
+    fn country(&self, ip: std::net::IpAddr) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(addr, _)| addr.parse::<std::net::IpAddr>().ok() == Some(ip))
+            .map(|(_, country)| country.to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_geo_gate_blocks_denied_countries() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let lookup = FixedCountries(vec![("203.0.113.5", "RU"), ("198.51.100.7", "DE")]);
+    state.geo = Some(GeoGate::new(Arc::new(lookup), &[], &["ru".to_string()]));
+    let event = seed_event(&state, "Sponsored", None).await;
+    let app = build_app(state.clone());
+
+    let register = |ip: &str, email: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .header("X-Forwarded-For", format!("10.0.0.1, {}", ip))
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "Ada",
+                "email": email,
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(register("203.0.113.5", "ada@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(body_json(response).await["code"], "geo_blocked");
+
+    let response = app.oneshot(register("198.51.100.7", "ada@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let decisions: Vec<(String, String)> = sqlx::query_as(
+        "SELECT action, entity_id FROM audit_log WHERE entity_type = 'client_ip' ORDER BY id"
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        decisions,
+        vec![
+            ("registration.geo_denied".to_string(), "203.0.113.5".to_string()),
+            ("registration.geo_allowed".to_string(), "198.51.100.7".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_referrals_link_registrations_and_rank_inviters() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Bring a Friend", None).await;
+    let other = seed_event(&state, "Elsewhere", None).await;
+    let outsider = seed_participant(&state, other.id, "Outsider", "outsider@example.com").await;
+    let app = build_app(state.clone());
+
+    let register = |name: &str, invited_by: Option<String>| {
+        let app = app.clone();
+        let body = json!({
+            "event_id": event.id,
+            "name": name,
+            "email": format!("{}@example.com", name.to_lowercase()),
+            "privacy_policy_version": "2024-01",
+            "invited_by": invited_by,
+        });
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/participants")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = register("Ada", None).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada = body_json(response).await;
+    let ada_code = ada["invite_code"].as_str().unwrap().to_string();
+    assert_eq!(ada_code.len(), 12);
+
+    // By invite code, in any case, or by participant id
+    let response = register("Grace", Some(ada_code.to_uppercase())).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let grace = body_json(response).await;
+    let response = register("Alan", Some(grace["id"].as_str().unwrap().to_string())).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = register("Edsger", Some(ada_code.clone())).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    for invited_by in ["nosuchcode", &outsider.id.to_string()] {
+        let response = register("Barbara", Some(invited_by.to_string())).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", invited_by);
+        let body = body_json(response).await;
+        assert_eq!(body["fields"]["invited_by"][0]["code"], "unknown_referrer");
+    }
+
+    sqlx::query("UPDATE participants SET status = 'confirmed' WHERE id = ?")
+        .bind(grace["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap())
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let get = |query: &str, admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/referrals{}", event.id, query));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(get("", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get("?limit=0", true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.clone().oneshot(get("", true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let summary = body_json(response).await;
+    assert_eq!(summary["referred"], 3);
+    let top = summary["top_referrers"].as_array().unwrap();
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0]["name"], "Ada");
+    assert_eq!(top[0]["referrals"], 2);
+    assert_eq!(top[0]["confirmed"], 1);
+    assert_eq!(top[0]["indirect"], 1);
+    assert_eq!(top[1]["name"], "Grace");
+    assert_eq!(top[1]["referrals"], 1);
+    assert_eq!(top[1]["indirect"], 0);
+
+    // Removing an inviter keeps the registrations they referred
+    sqlx::query("DELETE FROM participants WHERE id = ?")
+        .bind(ada["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap())
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let response = app.oneshot(get("?limit=5", true)).await.unwrap();
+    let summary = body_json(response).await;
+    assert_eq!(summary["referred"], 3);
+    assert_eq!(summary["top_referrers"].as_array().unwrap().len(), 1);
+    assert_eq!(summary["top_referrers"][0]["name"], "Grace");
+}
+
+#[tokio::test]
+async fn test_participant_metadata_is_stored_and_merge_patched() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Conference", None).await;
+    let app = build_app(state);
+
+    let register = |email: &str, metadata: Value| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "Ada",
+                "email": email,
+                "privacy_policy_version": "2024-01",
+                "metadata": metadata
+            }).to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(register("ada@example.com", json!({ "company": "Analytical Engines", "tshirt_size": "M", "vegetarian": true })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let registration = body_json(response).await;
+    assert_eq!(registration["metadata"]["tshirt_size"], "M");
+    let id = registration["id"].as_str().unwrap().to_string();
+    let access_token = registration["access_token"].as_str().unwrap().to_string();
+
+    for metadata in [json!({ "nested": { "a": 1 } }), json!({ "bad key": "x" }), json!({ "note": "x".repeat(501) })] {
+        let response = app.clone().oneshot(register("grace@example.com", metadata.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", metadata);
+    }
+
+    let patch = |body: Value, header: Option<(&str, &str)>| {
+        let mut builder = Request::builder()
+            .method(Method::PATCH)
+            .uri(format!("/api/participants/{}/metadata", id))
+            .header("Content-Type", "application/json");
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+
+    // Neither the public nor another token may change it
+    let response = app.clone().oneshot(patch(json!({ "tshirt_size": "L" }), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(patch(json!({ "tshirt_size": "L" }), Some(("X-Participant-Token", "wrong"))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Listed keys are set, null removes, the rest stays
+    let response = app
+        .clone()
+        .oneshot(patch(
+            json!({ "tshirt_size": "L", "vegetarian": null, "arrival": "friday" }),
+            Some(("X-Participant-Token", &access_token)),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let participant = body_json(response).await;
+    assert_eq!(
+        participant["metadata"],
+        json!({ "arrival": "friday", "company": "Analytical Engines", "tshirt_size": "L" })
+    );
+
+    let many: serde_json::Map<String, Value> = (0..30).map(|i| (format!("key_{}", i), json!(i))).collect();
+    let response = app
+        .clone()
+        .oneshot(patch(Value::Object(many), Some(("X-Admin-Token", "secret"))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["metadata"][0]["code"], "too_many_keys");
+
+    // Lists show metadata to organizers only
+    let list = |admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/events/{}/participants", event.id));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let body = body_json(app.clone().oneshot(list(false)).await.unwrap()).await;
+    assert!(body[0].get("metadata").is_none());
+    let body = body_json(app.oneshot(list(true)).await.unwrap()).await;
+    assert_eq!(body[0]["metadata"]["arrival"], "friday");
+}
+
+#[tokio::test]
+async fn test_disposable_email_domains_are_rejected() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.email_reputation = Arc::new(DisposableDomains::new(["mailinator.com", "# comment", ""]));
+    let event = seed_event(&state, "Limited Seats", Some(10)).await;
+    let app = build_app(state);
+
+    let register = |email: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": "Hoarder",
+                "email": email,
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+
+    for email in ["seat@mailinator.com", "seat@eu.Mailinator.com"] {
+        let response = app.clone().oneshot(register(email)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body_json(response).await;
+        assert_eq!(body["fields"]["email"][0]["code"], "disposable_email");
+    }
+
+    let response = app.oneshot(register("seat@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+// =====================
+// Admin Panel Tests
+// =====================
+
+#[tokio::test]
+async fn test_admin_panel_assets_are_embedded() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(get("/admin")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+
+    let response = app.clone().oneshot(get("/admin/app.js")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().contains("javascript"));
+
+    let response = app.oneshot(get("/admin/missing.js")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_backups_and_cache_stats() {
+    let (mut state, temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        data_dir: temp_dir.path().to_str().unwrap().to_string(),
+        volume_size_bytes: Some(1 << 30),
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    seed_event(&state, "Backed Up", None).await;
+    let app = build_app(state);
+    let admin = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(admin(Method::POST, "/api/admin/backups")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let backup = body_json(response).await;
+    let file_name = backup["file_name"].as_str().unwrap().to_string();
+
+    // The backup is a usable copy of the database
+    let copy = db::create_pool(temp_dir.path().join("backups").join(&file_name).to_str().unwrap())
+        .await
+        .unwrap();
+    let events: i64 = sqlx::query_scalar("SELECT count(*) FROM events")
+        .fetch_one(&copy)
+        .await
+        .unwrap();
+    assert_eq!(events, 1);
+
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/backups")).await.unwrap();
+    let backups = body_json(response).await;
+    assert_eq!(backups[0]["file_name"], file_name);
+
+    // Storage usage counts the backup against the volume
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/storage")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let usage = body_json(response).await;
+    // Opening the copy above left its WAL files next to it
+    assert!(usage["backups_bytes"].as_u64().unwrap() >= backup["size_bytes"].as_u64().unwrap());
+    assert_eq!(usage["exports_bytes"], 0);
+    let total = usage["total_bytes"].as_u64().unwrap();
+    assert!(total > usage["backups_bytes"].as_u64().unwrap());
+    assert_eq!(usage["volume_bytes"], 1u64 << 30);
+    assert_eq!(usage["available_bytes"], (1u64 << 30) - total);
+    assert_eq!(usage["near_capacity"], false);
+
+    let response = app.oneshot(admin(Method::GET, "/api/admin/cache")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_json(response).await["events_list"].is_number());
+}
+
+#[tokio::test]
+async fn test_admin_participant_search_purge_and_system_state() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let gala = seed_event(&state, "Gala", None).await;
+    let meetup = seed_event(&state, "Meetup", None).await;
+    seed_participant(&state, gala.id, "Ada Lovelace", "ada@example.com").await;
+    seed_participant(&state, meetup.id, "Ada Byron", "byron@example.com").await;
+    let grace = seed_participant(&state, meetup.id, "Grace Hopper", "grace@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'cancelled' WHERE id = ?")
+        .bind(grace.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let expired = chrono::Utc::now() - chrono::Duration::hours(1);
+    sqlx::query("INSERT INTO confirmation_tokens (token, method, target, expires_at) VALUES ('stale', 'DELETE', '/api/events/x', ?)")
+        .bind(expired)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO admin_sessions (id, csrf_token, created_at, expires_at) VALUES ('old', 'csrf', ?1, ?1)")
+        .bind(expired)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let app = build_app(state);
+    let admin = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/admin/participants").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Search spans events and matches emails too
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/participants?search=ADA%20")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-total-count"], "2");
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/participants?search=ada%40")).await.unwrap();
+    let found = body_json(response).await;
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["event_title"], "Gala");
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/participants?search=a&limit=2")).await.unwrap();
+    assert_eq!(response.headers()["x-total-count"], "3");
+    assert_eq!(body_json(response).await.as_array().unwrap().len(), 2);
+    let response = app
+        .clone()
+        .oneshot(admin(Method::GET, &format!("/api/admin/participants?event_id={}&status=registered", meetup.id)))
+        .await
+        .unwrap();
+    let found = body_json(response).await;
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["name"], "Ada Byron");
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/participants?limit=500")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/system")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let system = body_json(response).await;
+    assert_eq!(system["schema_version"], system["latest_schema_version"]);
+    assert_eq!(system["tables"]["events"], 2);
+    assert_eq!(system["tables"]["confirmation_tokens"], 1);
+    assert_eq!(system["participants_by_status"]["registered"], 2);
+    assert_eq!(system["participants_by_status"]["cancelled"], 1);
+
+    let response = app.clone().oneshot(admin(Method::POST, "/api/admin/purge")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let purged = body_json(response).await;
+    assert_eq!(purged["confirmation_tokens"], 1);
+    assert_eq!(purged["admin_sessions"], 1);
+    assert_eq!(purged["export_jobs"], 0);
+
+    let response = app.oneshot(admin(Method::GET, "/api/admin/system")).await.unwrap();
+    let system = body_json(response).await;
+    assert_eq!(system["tables"]["confirmation_tokens"], 0);
+    assert_eq!(system["tables"]["admin_sessions"], 0);
+}
+
+#[tokio::test]
+async fn test_admin_session_cookie_with_csrf() {
+    let (mut state, temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        data_dir: temp_dir.path().to_str().unwrap().to_string(),
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state);
+    let login = |token: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/admin/session")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({ "token": token }).to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(login("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.clone().oneshot(login("secret")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let set_cookie = response.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(set_cookie.contains("HttpOnly") && set_cookie.contains("SameSite=Strict"));
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+    let csrf = body_json(response).await["csrf_token"].as_str().unwrap().to_string();
+
+    let with_cookie = |method: Method, uri: &str, csrf: Option<&str>| {
+        let mut builder = Request::builder().method(method).uri(uri).header("Cookie", &cookie);
+        if let Some(csrf) = csrf {
+            builder = builder.header("X-CSRF-Token", csrf);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // Reads need only the cookie
+    let response = app.clone().oneshot(with_cookie(Method::GET, "/api/admin/cache", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(with_cookie(Method::GET, "/api/admin/session", None)).await.unwrap();
+    assert_eq!(body_json(response).await["csrf_token"], csrf.as_str());
+
+    // Writes also need the CSRF token
+    let response = app
+        .clone()
+        .oneshot(with_cookie(Method::POST, "/api/admin/backups", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(with_cookie(Method::POST, "/api/admin/backups", Some("forged")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(with_cookie(Method::POST, "/api/admin/backups", Some(&csrf)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = app
+        .clone()
+        .oneshot(with_cookie(Method::DELETE, "/api/admin/session", Some(&csrf)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(response.headers()["set-cookie"].to_str().unwrap().contains("Max-Age=0"));
+
+    let response = app.oneshot(with_cookie(Method::GET, "/api/admin/cache", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+// =====================
+// Cache Tests
+// =====================
+
+#[tokio::test]
+async fn test_cache_is_populated_on_read() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+
+    // Create event
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Cached Event",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+
+    // First GET (populates cache)
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/events/{}", event_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Verify cache is populated
+    assert!(state.cache.event.get(&event_id.to_string()).await.is_some());
+}
+
+#[tokio::test]
+async fn test_cached_event_list_is_served_with_etag() {
+    let (state, _temp_dir) = create_test_state().await;
+    seed_event(&state, "Tagged", None).await;
+    let app = build_app(state.clone());
+
+    let list = |etag: Option<&str>| {
+        let mut builder = Request::builder().uri("/api/events");
+        if let Some(etag) = etag {
+            builder = builder.header("If-None-Match", etag);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(list(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/\""));
+    let first = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let cached = state.cache.events_list.get("all:upcoming").await.unwrap();
+    assert_eq!(cached.body, first);
+
+    // Hits serve the stored bytes unchanged
+    let response = app.clone().oneshot(list(None)).await.unwrap();
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let second = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(second, first);
+
+    let response = app.clone().oneshot(list(Some(&etag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let response = app.clone().oneshot(list(Some("W/\"0000000000000000\""))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A change yields a new tag
+    seed_event(&state, "Another", None).await;
+    state.cache.invalidate_events().await;
+    let response = app.clone().oneshot(list(Some(&etag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()["etag"], etag.as_str());
+    assert_eq!(body_json(response).await.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_cache_invalidated_on_write() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+
+    // Create and list events (populates list cache)
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Event 1",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // List to populate cache
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(state.cache.events_list.get("all:upcoming").await.is_some());
+
+    // Create another event (should invalidate list cache)
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Event 2",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Cache should be invalidated
+    assert!(state.cache.events_list.get("all:upcoming").await.is_none());
+}
+
+#[tokio::test]
+async fn test_reconciliation_invalidates_changes_missed_by_notifications() {
+    use backend::reconcile::Reconciler;
+
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Before", None).await;
+    let app = build_app(state.clone());
+    let mut reconciler = Reconciler::new();
+    assert!(reconciler.run_once(&state.db_pool, &state.cache).await.unwrap().is_empty());
+
+    let list = || Request::builder().uri("/api/events").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(list()).await.unwrap();
+    assert_eq!(body_json(response).await[0]["title"], "Before");
+
+    // A write whose notification was lost stays hidden behind the cache
+    let rename = |title: &'static str, seconds: i64| {
+        sqlx::query("UPDATE events SET title = ?, updated_at = ? WHERE id = ?")
+            .bind(title)
+            .bind(chrono::Utc::now() + chrono::Duration::seconds(seconds))
+            .bind(event.id)
+            .execute(&state.db_pool)
+    };
+    rename("After", 1).await.unwrap();
+    let response = app.clone().oneshot(list()).await.unwrap();
+    assert_eq!(body_json(response).await[0]["title"], "Before");
+
+    let missed = reconciler.run_once(&state.db_pool, &state.cache).await.unwrap();
+    assert_eq!(missed, vec![Channel::EventChanges]);
+    let response = app.clone().oneshot(list()).await.unwrap();
+    assert_eq!(body_json(response).await[0]["title"], "After");
+
+    // Notified changes are not counted as missed
+    rename("Notified", 2).await.unwrap();
+    state.cache.invalidate_for_channel(Channel::EventChanges).await;
+    assert!(reconciler.run_once(&state.db_pool, &state.cache).await.unwrap().is_empty());
+    assert_eq!(state.cache.forced_invalidations(), 1);
+
+    let response = app.oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap()).await.unwrap();
+    let body = String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("cache_reconcile_forced_invalidations_total 1"));
+}
+
+#[tokio::test]
+async fn test_chaos_rules_inject_faults_per_route() {
+    use backend::chaos::ChaosConfig;
+
+    assert!(ChaosConfig::parse("/api/events=error:2").is_err());
+    assert!(ChaosConfig::parse("/api/events=latency:0.5").is_err());
+    assert!(ChaosConfig::parse("/api/events").is_err());
+    let chaos = ChaosConfig::parse("/api/events=error:1 ; *=latency:1:20;/api/events/stream=drop:0.5").unwrap();
+    assert_eq!(chaos.rules.len(), 3);
+    assert_eq!(chaos.rule("/api/events/stream").unwrap().drop_rate, 0.5);
+    assert_eq!(chaos.rule("/api/events/:id").unwrap().route, "*");
+    assert!(chaos.rule("/health").is_none());
+
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config { chaos, ..Config::default() });
+    let event = seed_event(&state, "Flaky", None).await;
+    let app = build_app(state);
+    let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(get("/api/events".into())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()["x-chaos"], "error");
+
+    let started = std::time::Instant::now();
+    let response = app.clone().oneshot(get(format!("/api/events/{}", event.id))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-chaos"], "latency");
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    let response = app.oneshot(get("/health".into())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("x-chaos").is_none());
+}
+
+#[tokio::test]
+async fn test_cache_audit_passes_responses_through() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        cache_audit: true,
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Audited", None).await;
+    let app = build_app(state.clone());
+    let get = || {
+        Request::builder()
+            .uri(format!("/api/events/{}", event.id))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["title"], "Audited");
+
+    // Change the row behind the cache's back; the audit logs the divergence
+    // but still serves the cached body unchanged
+    sqlx::query("UPDATE events SET title = 'Changed' WHERE id = ?")
+        .bind(event.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let response = app.oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["title"], "Audited");
+}
+
+// =====================
+// Database Tests
+// =====================
+
+#[tokio::test]
+async fn test_db_initialization() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("init_test.db");
+    let db_path_str = db_path.to_str().unwrap();
+
+    let pool = db::create_pool(db_path_str).await.unwrap();
+    db::initialize_tables(&pool).await.unwrap();
+
+    // Tables should exist
+    let count: (i64,) = sqlx::query_as("SELECT count(*) FROM sqlite_master WHERE type='table' AND name IN ('events', 'participants', 'change_notifications')")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count.0, 3);
+}
+
+#[tokio::test]
+async fn test_migrations_enforce_length_caps() {
+    let (state, _temp_dir) = create_test_state().await;
+
+    // All migrations applied on initialization
+    assert!(backend::migrations::pending(&state.db_pool).await.unwrap().is_empty());
+
+    // Database rejects values beyond the hard caps even when bypassing validation
+    let now = chrono::Utc::now();
+    let result = sqlx::query(
+        "INSERT INTO events (id, title, start_time, end_time, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind("x".repeat(1001))
+    .bind(now)
+    .bind(now + chrono::Duration::hours(1))
+    .bind(now)
+    .bind(now)
+    .execute(&state.db_pool)
+    .await;
+
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("CHECK constraint failed"));
+}
+
+#[tokio::test]
+async fn test_migration_plan_previews_without_applying() {
+    let (mut state, temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+
+    // A database whose migrations were held back
+    let pool = db::create_pool(temp_dir.path().join("held_back.db").to_str().unwrap()).await.unwrap();
+    db::initialize_schema(&pool, false).await.unwrap();
+    state.db_pool = pool.clone();
+    let app = build_app(state);
+
+    let plan = |query: &str, admin: bool| {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/admin/migrations/plan{}", query));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(plan("", false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(plan("", true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["dialect"], "sqlite");
+    assert_eq!(body["current_version"], 0);
+    assert_eq!(body["target_version"], backend::migrations::MIGRATIONS.last().unwrap().version);
+    assert_eq!(body["rehearsed"], false);
+    let pending = body["pending"].as_array().unwrap();
+    assert_eq!(pending.len(), backend::migrations::MIGRATIONS.len());
+    assert_eq!(pending[0]["version"], 1);
+    let first = pending[0]["statements"][0]["sql"].as_str().unwrap();
+    assert!(first.starts_with("CREATE TRIGGER"), "{}", first);
+    assert!(!first.contains("\n            "), "statements are dedented: {}", first);
+    assert!(pending[0]["statements"][0].get("rows_affected").is_none());
+
+    let response = app.clone().oneshot(plan("?rehearse=true", true)).await.unwrap();
+    let body = body_json(response).await;
+    assert_eq!(body["rehearsed"], true);
+    assert_eq!(body["rehearsal_succeeded"], true);
+    assert_eq!(body["pending"][0]["statements"][0]["rows_affected"], 0);
+
+    // Neither preview applied anything
+    assert_eq!(backend::migrations::current_version(&pool).await.unwrap(), 0);
+    let referrals: i64 = sqlx::query_scalar("SELECT count(*) FROM sqlite_master WHERE name = 'referrals'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(referrals, 0);
+
+    // Once applied there is nothing left to plan
+    backend::migrations::run(&pool).await.unwrap();
+    let response = app.oneshot(plan("", true)).await.unwrap();
+    let body = body_json(response).await;
+    assert_eq!(body["current_version"], body["target_version"]);
+    assert!(body["pending"].as_array().unwrap().is_empty());
+}
+#[tokio::test]
+async fn test_configured_field_limits_apply() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        field_limits: FieldLimits {
+            name: 5,
+            ..FieldLimits::default()
+        },
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Limits", None).await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Bartholomew",
+                    "email": "bart@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["name"][0]["code"], "too_long");
+}
+
+#[tokio::test]
+async fn test_notification_insert_and_poll() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("notify_test.db");
+    let db_path_str = db_path.to_str().unwrap();
+
+    let pool = db::create_pool(db_path_str).await.unwrap();
+    db::initialize_tables(&pool).await.unwrap();
+
+    // Insert notification
+    db::insert_notification(&pool, Channel::EventChanges, "{\"test\": true}", chrono::Utc::now())
+        .await
+        .unwrap();
+
+    // Check it was stored
+    let max_id = db::get_max_notification_id(&pool).await;
+    assert!(max_id > 0);
+
+    // Poll and verify
+    let notifications: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT id, channel, payload FROM change_notifications WHERE id > 0"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].1, "event_changes");
+}
+
+#[tokio::test]
+async fn test_notification_cleanup_uses_clock() {
+    let (state, _temp_dir) = create_test_state().await;
+    let start = chrono::DateTime::parse_from_rfc3339("2026-03-01T10:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let clock = MockClock::new(start);
+
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", clock.now())
+        .await
+        .unwrap();
+
+    // Still within retention
+    clock.advance(chrono::Duration::minutes(30));
+    let cutoff = clock.now() - chrono::Duration::hours(1);
+    assert_eq!(db::cleanup_notifications(&state.db_pool, cutoff).await.unwrap(), 0);
+
+    // Past retention
+    clock.advance(chrono::Duration::hours(1));
+    let cutoff = clock.now() - chrono::Duration::hours(1);
+    assert_eq!(db::cleanup_notifications(&state.db_pool, cutoff).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_poller_skips_unknown_channels() {
+    let (state, _temp_dir) = create_test_state().await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+
+    // A row from an instance running a newer build
+    sqlx::query("INSERT INTO change_notifications (channel, payload, created_at) VALUES ('event_chagnes', '{}', ?)")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    db::insert_notification(&state.db_pool, Channel::System, "{\"n\": 2}", chrono::Utc::now())
+        .await
+        .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(event.channel, Channel::System);
+    assert_eq!(event.payload, "{\"n\": 2}");
+    assert_eq!(state.cache.invalidations(Channel::EventChanges), 0);
+
+    assert_eq!(serde_json::to_value(Channel::ParticipantChanges).unwrap(), "participant_changes");
+    assert_eq!("event_changes".parse::<Channel>(), Ok(Channel::EventChanges));
+    assert!("event_chagnes".parse::<Channel>().is_err());
+}
+
+#[tokio::test]
+async fn test_usage_report_aggregates() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Counted", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+
+    let now = chrono::Utc::now();
+    let report = telemetry::collect(&state.db_pool, now - chrono::Duration::days(1), now)
+        .await
+        .unwrap();
+    assert_eq!(report.event_count, 1);
+    assert_eq!(report.participant_count, 2);
+    assert_eq!(report.registrations_in_period, 2);
+
+    // The anonymous deployment id is stable across reports
+    let again = telemetry::collect(&state.db_pool, now, now).await.unwrap();
+    assert_eq!(again.deployment_id, report.deployment_id);
+    assert_eq!(again.registrations_in_period, 0);
+}
+
+#[tokio::test]
+async fn test_retention_policy_is_configurable_and_applied() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state.clone());
+
+    let request = |method: Method, body: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri("/api/admin/settings/retention")
+            .header("Content-Type", "application/json")
+            .header("X-Admin-Token", "secret");
+        let body = body.map(|b| Body::from(b.to_string())).unwrap_or_else(Body::empty);
+        builder.body(body).unwrap()
+    };
+
+    let response = app.clone().oneshot(request(Method::GET, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["notification_retention_secs"], 3600);
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, Some(json!({ "notification_retention_secs": 5 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["notification_retention_secs"][0]["code"], "out_of_range");
+
+    let response = app
+        .oneshot(request(Method::PUT, Some(json!({ "notification_retention_secs": 600 }))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A 20-minute-old notification falls outside the new 10-minute window
+    let now = chrono::Utc::now();
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", now - chrono::Duration::minutes(20))
+        .await
+        .unwrap();
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", now)
+        .await
+        .unwrap();
+    db::run_retention(&state.db_pool, now).await;
+
+    let remaining: i64 = sqlx::query_scalar("SELECT count(*) FROM change_notifications")
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 1);
+}
+
+#[tokio::test]
+async fn test_handlers_use_injected_clock() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let fixed = chrono::DateTime::parse_from_rfc3339("2026-01-15T08:30:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    state.clock = Arc::new(MockClock::new(fixed));
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Clocked",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["created_at"], "2026-01-15T08:30:00Z");
+    assert_eq!(event["updated_at"], "2026-01-15T08:30:00Z");
+}
+
+#[tokio::test]
+async fn test_v7_ids_follow_creation_order() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.ids = IdGenerator::new(IdVersion::V7);
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let mut ids = Vec::new();
+    for title in ["First", "Second", "Third"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/events")
+                    .header("Authorization", &auth)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "title": title,
+                        "start_time": "2026-03-01T10:00:00Z",
+                        "end_time": "2026-03-01T12:00:00Z"
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let event = body_json(response).await;
+        let id = uuid::Uuid::parse_str(event["id"].as_str().unwrap()).unwrap();
+        assert_eq!(id.get_version_num(), 7);
+        ids.push(id);
+    }
+
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+}
+
+// =====================
+// Delete Participant Tests
+// =====================
+
+#[tokio::test]
+async fn test_delete_participant() {
+    let (state, _temp_dir) = create_test_state().await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Create event
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": "Event",
+                    "start_time": "2026-03-01T10:00:00Z",
+                    "end_time": "2026-03-01T12:00:00Z"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let event = body_json(response).await;
+    let event_id = event["id"].as_str().unwrap();
+    publish_event(&app, &auth, event_id).await;
+
+    // Create participant
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event_id,
+                    "name": "Jane",
+                    "email": "jane@test.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let participant = body_json(response).await;
+    let part_id = participant["id"].as_str().unwrap();
+
+    // Delete participant
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/participants/{}", part_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Verify deleted
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/participants/{}", part_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_personal_data_export_and_erasure() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state.clone());
+
+    // Ada holds the only place of one event, Bob waits for it
+    let full = seed_event(&state, "Full Workshop", Some(1)).await;
+    let ada_full = seed_participant(&state, full.id, "Ada", "ada@example.com").await;
+    let bob = seed_participant(&state, full.id, "Bob", "bob@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'waitlisted' WHERE id = ?")
+        .bind(bob.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let talk = seed_event(&state, "Evening Talk", None).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": talk.id,
+                    "name": "Ada Lovelace",
+                    "email": "ADA@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    sqlx::query(
+        "INSERT INTO mail_failures (to_address, to_address_index, subject, mail, error, first_failed_at, last_failed_at)
+         VALUES ('ada@example.com', ?1, 'Your registration', '{}', 'relay down', ?2, ?2)"
+    )
+    .bind(backend::encryption::blind_index("ada@example.com"))
+    .bind(state.clock.now())
+    .execute(&state.db_pool)
+    .await
+    .unwrap();
+
+    let export = |admin: bool| {
+        let mut builder = Request::builder().uri("/api/participants/by-email/Ada@Example.com/export");
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let response = app.clone().oneshot(export(false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(export(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let data = body_json(response).await;
+    assert_eq!(data["email"], "ada@example.com");
+    let registrations = data["registrations"].as_array().unwrap();
+    assert_eq!(registrations.len(), 2);
+    assert_eq!(registrations[0]["event_title"], "Full Workshop");
+    assert_eq!(registrations[0]["participant"]["id"], ada_full.id.to_string());
+    assert!(registrations[0]["participant"].get("dedupe_key").is_none());
+    assert_eq!(registrations[1]["participant"]["name"], "Ada Lovelace");
+    assert_eq!(registrations[1]["participant"]["metadata"], json!({}));
+    let related = &registrations[1]["related"];
+    assert_eq!(related["participant_consents"][0]["privacy_policy_version"], "2024-01");
+    assert!(related["participant_consents"][0].get("withdrawal_token").is_none());
+    assert!(related["reminder_preferences"][0].get("token").is_none());
+    assert_eq!(data["mail_failures"][0]["subject"], "Your registration");
+
+    // Erasure must be confirmed
+    let target = "/api/participants/by-email/ada@example.com";
+    let erase = |token: Option<String>| {
+        let mut builder = Request::builder()
+            .method(Method::DELETE)
+            .uri(target)
+            .header("X-Admin-Token", "secret");
+        if let Some(token) = token {
+            builder = builder.header("x-confirmation-token", token);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let response = app.clone().oneshot(erase(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    let token = prepare_confirmation(&app, "DELETE", target).await;
+    let response = app.clone().oneshot(erase(Some(token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = body_json(response).await;
+    assert_eq!(report["registrations"].as_array().unwrap().len(), 2);
+    assert_eq!(report["mail_failures"], 1);
+
+    // Bob took the freed place
+    let status: String = sqlx::query_scalar("SELECT status FROM participants WHERE id = ?")
+        .bind(bob.id)
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "registered");
+    let history: i64 = sqlx::query_scalar("SELECT count(*) FROM participant_status_history WHERE participant_id = ?")
+        .bind(ada_full.id)
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(history, 0);
+
+    // The audit log names the person by hash only
+    let (entity_id, details): (String, String) = sqlx::query_as(
+        "SELECT entity_id, details FROM audit_log WHERE action = 'personal_data.erase'"
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(entity_id, backend::personal_data::subject_id("ada@example.com"));
+    assert!(!details.contains("example.com"));
+
+    let data = body_json(app.oneshot(export(true)).await.unwrap()).await;
+    assert_eq!(data["registrations"], json!([]));
+    assert_eq!(data["mail_failures"], json!([]));
+}
+
+// =====================
+// Load Shedding Tests
+// =====================
+
+#[tokio::test]
+async fn test_concurrency_limits_and_metrics() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    // A running export takes the only export slot
+    state.concurrency = ConcurrencyLimits::new(ConcurrencyConfig {
+        max_exports: 1,
+        ..ConcurrencyConfig::default()
+    });
+    let app = build_app(state.clone());
+    let permit = state.concurrency.try_export_permit().unwrap();
+
+    let response = app.clone().oneshot(get("/api/events/ndjson")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key("retry-after"));
+
+    drop(permit);
+    let response = app.clone().oneshot(get("/api/events/ndjson")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // With no queue capacity every limited request is shed, probes are not
+    state.concurrency = ConcurrencyLimits::new(ConcurrencyConfig {
+        max_queued: 0,
+        ..ConcurrencyConfig::default()
+    });
+    let app = build_app(state.clone());
+
+    let response = app.clone().oneshot(get("/api/events")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = app.clone().oneshot(get("/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(get("/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("http_requests_queued 0\n"));
+    assert!(text.contains("http_requests_shed_total{reason=\"queue_full\"} 1\n"));
+    assert!(text.contains("# TYPE http_requests_shed_total counter"));
+}
+
+#[tokio::test]
+async fn test_autoscale_metrics() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    // Before any request there is no latency to report
+    let response = app.clone().oneshot(get("/metrics/autoscale")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("application/openmetrics-text"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("http_request_duration_p95_seconds 0\n"));
+    assert!(text.contains("http_requests_queued 0\n"));
+    assert!(text.ends_with("# EOF\n"));
+    assert!(!text.contains("http_requests_shed_total"));
+
+    let response = app.clone().oneshot(get("/api/events")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(get("/metrics/autoscale")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(!text.contains("http_request_duration_p95_seconds 0\n"));
+}
+
+#[tokio::test]
+async fn test_notification_poller_metrics() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state.clone());
+    let now = chrono::Utc::now();
+    // Older than the poller, so only the retention job sees it
+    let stale = now - chrono::Duration::days(30);
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", stale).await.unwrap();
+
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", now).await.unwrap();
+    db::insert_notification(&state.db_pool, Channel::EventChanges, "{}", now).await.unwrap();
+    expect_broadcast(&mut receiver, Channel::EventChanges, Duration::from_secs(3)).await;
+    expect_broadcast(&mut receiver, Channel::EventChanges, Duration::from_secs(3)).await;
+
+    // Deletions by the retention job are counted with the poller's figures
+    state.poller_metrics.record_cleanup(db::run_retention(&state.db_pool, now).await);
+
+    let response = app
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("notification_poller_rows_fetched_total 2\n"));
+    assert!(text.contains("notification_cleanup_deleted_total 1\n"));
+    assert!(text.contains("notification_poller_overruns_total 0\n"));
+    assert!(text.contains("# TYPE notification_poller_poll_seconds_total counter"));
+    assert!(state.poller_metrics.iterations() >= 1);
+    assert!(state.poller_metrics.poll_time() > Duration::ZERO);
+}
+
+
+#[tokio::test]
+async fn test_load_shedding_spares_writes_and_health() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.load_shedder = LoadShedder::new(LoadShedConfig {
+        max_in_flight: 0,
+        ..LoadShedConfig::default()
+    });
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    // Low-priority list endpoint is shed
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/events")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "5");
+
+    // Writes still go through
+    let create_body = json!({
+        "title": "Still Accepted",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/events")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(create_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Health check is never shed
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_load_shedding_under_pool_pressure() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.load_shedder = LoadShedder::new(LoadShedConfig {
+        acquire_threshold: std::time::Duration::from_millis(50),
+        ..LoadShedConfig::default()
+    });
+    let pool = state.db_pool.clone();
+    let app = build_app(state);
+
+    let list_events = || {
+        Request::builder()
+            .uri("/api/events")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // An exhausted pool sheds low-priority reads
+    let mut held = Vec::new();
+    for _ in 0..pool.options().get_max_connections() {
+        held.push(pool.acquire().await.unwrap());
+    }
+
+    let response = app.clone().oneshot(list_events()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "5");
+
+    drop(held);
+    let response = app.clone().oneshot(list_events()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A failing acquire counts as saturated too
+    pool.close().await;
+    let response = app.oneshot(list_events()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "5");
+}
+
+// =====================
+// Broadcast Tests
+// =====================
+
+#[tokio::test]
+async fn test_participant_write_is_broadcast() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Broadcast Event", None).await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Ada",
+                    "email": "ada@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let payload = expect_broadcast(&mut receiver, Channel::ParticipantChanges, Duration::from_secs(3)).await;
+    assert_eq!(payload["operation"], "INSERT");
+    assert_eq!(payload["event_id"], event.id.to_string());
+}
+
+#[tokio::test]
+async fn test_mutations_publish_domain_events() {
+    let (state, _temp_dir) = create_test_state().await;
+    let event = seed_event(&state, "Domain Event", None).await;
+    let mut receiver = state.domain_events.subscribe();
+    let app = build_app(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Ada",
+                    "email": "ada@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    match receiver.try_recv().unwrap() {
+        DomainEvent::ParticipantRegistered { participant, .. } => {
+            assert_eq!(participant.event_id, event.id);
+            assert_eq!(participant.email, "ada@example.com");
+        }
+        other => panic!("unexpected domain event {:?}", other),
+    }
+}
+
+// =====================
+// Confirmation Mail Tests
+// =====================
+
+#[test]
+fn test_ics_escapes_and_folds_lines() {
+    assert_eq!(ics::escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+    let line = format!("SUMMARY:{}", "ü".repeat(60));
+    let folded = ics::fold(&line);
+    for part in folded.split("\r\n") {
+        assert!(part.len() <= 75, "line of {} octets", part.len());
+    }
+    assert_eq!(folded.replace("\r\n ", ""), line);
+}
+
+/// Wait until the mailer has sent `count` mails
+async fn wait_for_mails(mailer: &RecordingMailer, count: usize) -> Vec<OutgoingMail> {
+    for _ in 0..50 {
+        let sent = mailer.sent();
+        if sent.len() >= count {
+            return sent;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("expected {} mails, got {:?}", count, mailer.sent());
+}
+
+/// The attached invite with folded lines joined again
+fn invite_of(mail: &OutgoingMail) -> String {
+    String::from_utf8(mail.attachments[0].content.clone())
+        .unwrap()
+        .replace("\r\n ", "")
+}
+
+fn sequence_of(invite: &str) -> i64 {
+    invite
+        .lines()
+        .find_map(|line| line.strip_prefix("SEQUENCE:"))
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_registration_mails_carry_calendar_invites() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let mailer = Arc::new(RecordingMailer::default());
+    state.mailer = mailer.clone();
+    let event = seed_event(&state, "Workshop, Part 1", None).await;
+    let (organizer, auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, organizer.id).await;
+    let app = build_app(state.clone());
+    let _sender = tokio::spawn(registration_mail::start_sender(state.clone()));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/participants")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Ada Lovelace",
+                    "email": "ada@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let participant_id = body_json(response).await["id"].as_str().unwrap().to_string();
+
+    let sent = wait_for_mails(&mailer, 1).await;
+    assert_eq!(sent[0].to_address, "ada@example.com");
+    let attachment = &sent[0].attachments[0];
+    assert_eq!(attachment.content_type, "text/calendar");
+    assert!(attachment.parameters.contains(&("method".to_string(), "REQUEST".to_string())));
+    let invite = invite_of(&sent[0]);
+    assert!(invite.contains("METHOD:REQUEST\r\n"));
+    assert!(invite.contains(&format!("UID:registration-{}@localhost", participant_id)));
+    assert!(invite.contains("SUMMARY:Workshop\\, Part 1"));
+    assert!(invite.contains("ATTENDEE;CN=Ada Lovelace;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED;RSVP=FALSE:mailto:ada@example.com"));
+    let first_sequence = sequence_of(&invite);
+
+    // Rescheduling re-sends the invite for the same UID with a higher sequence
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/events/{}", event.id))
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "title": event.title,
+                    "start_time": event.start_time,
+                    "end_time": event.end_time,
+                    "location": "Room 2"
+                }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let sent = wait_for_mails(&mailer, 2).await;
+    let update = invite_of(&sent[1]);
+    assert!(update.contains("LOCATION:Room 2"));
+    assert!(sequence_of(&update) > first_sequence);
+
+    // Removing the registration cancels the calendar entry
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/participants/{}", participant_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let sent = wait_for_mails(&mailer, 3).await;
+    let cancel = invite_of(&sent[2]);
+    assert!(cancel.contains("METHOD:CANCEL\r\n"));
+    assert!(cancel.contains("STATUS:CANCELLED"));
+    assert!(cancel.contains(&format!("UID:registration-{}@localhost", participant_id)));
+    assert!(sequence_of(&cancel) >= sequence_of(&update));
+}
+
+#[tokio::test]
+async fn test_failed_mails_are_surfaced_and_retried() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let mut config = Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    };
+    config.mail.failure_window = 5;
+    config.mail.failure_alert_percent = 60;
+    state.config = Arc::new(config);
+    let mailer = Arc::new(RecordingMailer::default());
+    mailer.set_failing(true);
+    state.mailer = mailer.clone();
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let event = seed_event(&state, "Relay Down", None).await;
+    let app = build_app(state.clone());
+    let _sender = tokio::spawn(registration_mail::start_sender(state.clone()));
+
+    let admin = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    for i in 0..5 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/participants")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "event_id": event.id,
+                        "name": format!("Guest {}", i),
+                        "email": format!("guest{}@example.com", i),
+                        "privacy_policy_version": "2024-01"
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // Every registration mail failed, so the rate crossed the threshold
+    let alert = expect_broadcast(&mut receiver, Channel::System, Duration::from_secs(5)).await;
+    assert_eq!(alert["type"], "mail_failures");
+    assert_eq!(alert["state"], "failing");
+    assert_eq!(alert["failure_rate"], 1.0);
+
+    let response = app.clone().oneshot(admin(Method::GET, "/api/admin/mail/failures")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let failures = body_json(response).await;
+    assert_eq!(failures.as_array().unwrap().len(), 5);
+    assert_eq!(failures[0]["attempts"], 1);
+    assert!(failures[0]["error"].as_str().unwrap().contains("connection refused"));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/admin/mail/failures")
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
+    // Once the relay is back, a retry delivers and clears them
+    mailer.set_failing(false);
+    let response = app.clone().oneshot(admin(Method::POST, "/api/admin/mail/failures/retry")).await.unwrap();
     assert_eq!(response.status(), StatusCode::OK);
-    let updated = body_json(response).await;
-    assert_eq!(updated["status"], "confirmed");
+    assert_eq!(body_json(response).await, json!({ "retried": 5, "sent": 5, "failed": 0 }));
+    assert_eq!(mailer.sent().len(), 5);
+
+    let recovered = expect_broadcast(&mut receiver, Channel::System, Duration::from_secs(5)).await;
+    assert_eq!(recovered["state"], "recovered");
+
+    let response = app.oneshot(admin(Method::GET, "/api/admin/mail/failures")).await.unwrap();
+    assert_eq!(body_json(response).await, json!([]));
 }
 
 #[tokio::test]
-async fn test_duplicate_participant_rejected() {
-    let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state);
+async fn test_email_templates_validate_preview_and_word_mails() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let mailer = Arc::new(RecordingMailer::default());
+    state.mailer = mailer.clone();
+    let event = seed_event(&state, "Rust Meetup", None).await;
+    let app = build_app(state.clone());
+    let _sender = tokio::spawn(registration_mail::start_sender(state.clone()));
 
-    // Create event
+    let organizer = |method: Method, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("X-Admin-Token", "secret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/email-templates").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Placeholders outside the whitelist are rejected on save
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, "/api/email-templates", json!({
+            "event_id": event.id,
+            "kind": "confirmation",
+            "name": "Welcome",
+            "subject": "See you at {{event.title}}",
+            "body": "Hi {{ first_name }}"
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["fields"]["body"][0]["code"], "unknown_placeholder");
+
+    let template = json!({
+        "event_id": event.id,
+        "kind": "confirmation",
+        "name": "Welcome",
+        "subject": "See you at {{event.title}}",
+        "body": "Hi {{ name }}, you are in for {{event.title}} at {{event.location}}."
+    });
+    let response = app.clone().oneshot(organizer(Method::POST, "/api/email-templates", template.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = app.clone().oneshot(organizer(Method::POST, "/api/email-templates", template)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, "/api/email-templates/preview", json!({
+            "event_id": event.id,
+            "subject": "{{event.title}}",
+            "body": "Dear {{name}}"
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await, json!({ "subject": "Rust Meetup", "body": "Dear Jane Doe" }));
+
+    // Registration mails use the event's template and keep their invite
     let response = app
         .clone()
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri("/api/events")
+                .uri("/api/participants")
                 .header("Content-Type", "application/json")
                 .body(Body::from(json!({
-                    "title": "Event",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
+                    "event_id": event.id,
+                    "name": "Ada Lovelace",
+                    "email": "ada@example.com",
+                    "privacy_policy_version": "2024-01"
                 }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let sent = wait_for_mails(&mailer, 1).await;
+    assert_eq!(sent[0].subject, "See you at Rust Meetup");
+    assert!(sent[0].text.starts_with("Hi Ada Lovelace, you are in for Rust Meetup at TBA."));
+    assert_eq!(sent[0].attachments[0].content_type, "text/calendar");
 
-    let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
+    let response = app
+        .clone()
+        .oneshot(organizer(Method::POST, "/api/email-templates", json!({
+            "kind": "announcement",
+            "name": "Room change",
+            "subject": "{{event.title}} has moved",
+            "body": "Hello {{name}}, we meet in room 2 instead."
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let announcement_id = body_json(response).await["id"].as_str().unwrap().to_string();
 
-    let part_body = json!({
-        "event_id": event_id,
-        "name": "John",
-        "email": "john@test.com"
+    let response = app
+        .clone()
+        .oneshot(organizer(
+            Method::POST,
+            &format!("/api/events/{}/announcements", event.id),
+            json!({ "template_id": announcement_id }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(body_json(response).await["recipients"], 1);
+    let sent = wait_for_mails(&mailer, 2).await;
+    assert_eq!(sent[1].subject, "Rust Meetup has moved");
+    assert_eq!(sent[1].text, "Hello Ada Lovelace, we meet in room 2 instead.");
+}
+
+#[tokio::test]
+async fn test_bulk_mail_filters_by_status_and_reports_each_send() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
     });
+    let mailer = Arc::new(RecordingMailer::default());
+    state.mailer = mailer.clone();
+    let event = seed_event(&state, "Rust Meetup", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let waitlisted = seed_participant(&state, event.id, "Linus", "linus@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'waitlisted' WHERE id = ?")
+        .bind(waitlisted.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let app = build_app(state.clone());
 
-    // First registration
+    let send = |body: Value, token: Option<&str>| {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/events/{}/participants/email", event.id))
+            .header("Content-Type", "application/json");
+        if let Some(token) = token {
+            request = request.header("X-Admin-Token", token);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+    let mail = json!({ "subject": "{{event.title}}: room change", "body": "Hello {{name}}, we meet in room 2." });
+
+    let response = app.clone().oneshot(send(mail.clone(), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(send(json!({ "subject": "Hi", "body": "Dear {{first_name}}" }), Some("secret")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["body"][0]["code"], "unknown_placeholder");
+
+    // Those holding a place by default; nothing is sent before the sender runs
+    let response = app.clone().oneshot(send(mail, Some("secret"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let report = body_json(response).await;
+    assert_eq!(report["statuses"], json!(["registered", "confirmed", "checked_in"]));
+    assert_eq!(report["queued"], 2);
+    assert!(report["completed_at"].is_null());
+    assert!(mailer.sent().is_empty());
+    let report_uri = format!("/api/events/{}/participants/email/{}", event.id, report["id"].as_str().unwrap());
+
+    mailer.set_failing(true);
+    assert_eq!(bulk_mail::send_queued(&state, 1).await.unwrap(), 1);
+    mailer.set_failing(false);
+    assert_eq!(bulk_mail::send_queued(&state, usize::MAX).await.unwrap(), 1);
+    assert_eq!(bulk_mail::send_queued(&state, usize::MAX).await.unwrap(), 0);
+
+    let sent = mailer.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to_address, "grace@example.com");
+    assert_eq!(sent[0].subject, "Rust Meetup: room change");
+    assert_eq!(sent[0].text, "Hello Grace, we meet in room 2.");
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(&report_uri).header("X-Admin-Token", "secret").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = body_json(response).await;
+    assert_eq!((report["queued"].clone(), report["sent"].clone(), report["failed"].clone()), (json!(0), json!(1), json!(1)));
+    assert!(report["completed_at"].is_string());
+    assert_eq!(report["recipients"][0]["to_address"], "ada@example.com");
+    assert_eq!(report["recipients"][0]["state"], "failed");
+    assert!(report["recipients"][0]["error"].is_string());
+    assert_eq!(report["recipients"][1]["state"], "sent");
+
+    let response = app
+        .clone()
+        .oneshot(send(json!({ "subject": "Spot open?", "body": "Hi {{name}}", "statuses": ["waitlisted"] }), Some("secret")))
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["recipients"][0]["to_address"], "linus@example.com");
+
+    let response = app
+        .oneshot(send(json!({ "subject": "Hi", "body": "Hi", "statuses": [] }), Some("secret")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// =====================
+// CRM Webhook Tests
+// =====================
+
+/// Requests received by a stand-in CRM, as headers and body
+type Received = Arc<std::sync::Mutex<Vec<(axum::http::HeaderMap, String)>>>;
+
+/// Serve a stand-in CRM on a free port that fails its first request
+async fn spawn_crm() -> (String, Received) {
+    let received: Received = Default::default();
+    let app = axum::Router::new()
+        .route(
+            "/hook",
+            axum::routing::post(
+                |axum::extract::State(received): axum::extract::State<Received>,
+                 headers: axum::http::HeaderMap,
+                 body: String| async move {
+                    let mut received = received.lock().unwrap();
+                    received.push((headers, body));
+                    if received.len() == 1 {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, received)
+}
+
+#[tokio::test]
+async fn test_status_webhooks_map_fields_and_retry() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock.clone();
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let auth = organizer_auth(&state).await;
+    let other_auth = organizer_auth(&state).await;
+    let app = build_app(state.clone());
+    let (url, received) = spawn_crm().await;
+
+    let me = body_json(
+        app.clone()
+            .oneshot(Request::builder().uri("/api/organizers/me").header("Authorization", &auth).body(Body::empty()).unwrap())
+            .await
+            .unwrap(),
+    )
+    .await;
+    let event = seed_event(&state, "Members Evening", None).await;
+    sqlx::query("UPDATE events SET organizer_id = ? WHERE id = ?")
+        .bind(me["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap())
+        .bind(event.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let participant = seed_participant(&state, event.id, "Ada Lovelace", "ada@example.com").await;
+    sqlx::query("UPDATE participants SET metadata = '{\"member_no\":\"M-42\"}' WHERE id = ?")
+        .bind(participant.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let create = |body: Value| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/organizers/me/webhooks")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Unknown placeholders and non-HTTP URLs are rejected
+    let response = app
+        .clone()
+        .oneshot(create(json!({
+            "name": "CRM",
+            "url": url,
+            "transitions": [{ "to": "confirmed" }],
+            "fields": { "email": "{{mail}}" }
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = app
+        .clone()
+        .oneshot(create(json!({
+            "name": "CRM",
+            "url": "ftp://crm.example.com",
+            "transitions": [{ "to": "confirmed" }],
+            "fields": { "email": "{{email}}" }
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .clone()
+        .oneshot(create(json!({
+            "name": "Membership CRM",
+            "url": url,
+            "transitions": [{ "from": "registered", "to": "confirmed" }, { "to": "cancelled" }],
+            "fields": {
+                "member_email": "{{email}}",
+                "member_no": "{{ metadata.member_no }}",
+                "state": "{{previous_status}} -> {{status}}",
+                "note": "Attends {{event.title}}"
+            }
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let webhook = body_json(response).await;
+    let webhook_id = webhook["id"].as_str().unwrap().to_string();
+    let secret = webhook["secret"].as_str().unwrap().to_string();
+
+    // The secret is only shown on creation
+    let listed = body_json(
+        app.clone()
+            .oneshot(Request::builder().uri("/api/organizers/me/webhooks").header("Authorization", &auth).body(Body::empty()).unwrap())
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert!(listed[0].get("secret").is_none());
+
+    // Confirming queues a delivery with the mapped fields
+    let mut domain_events = state.domain_events.subscribe();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/participants/{}", participant.id))
+                .header("Content-Type", "application/json")
+                .header("X-Admin-Token", "secret")
+                .body(Body::from(json!({ "status": "confirmed" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let domain_event = domain_events.recv().await.unwrap();
+    assert_eq!(webhooks::enqueue(&state, &domain_event).await.unwrap(), 1);
+
+    let history = |auth: &str| {
+        Request::builder()
+            .uri(format!("/api/participants/{}/webhook-deliveries", participant.id))
+            .header("Authorization", auth)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let deliveries = body_json(app.clone().oneshot(history(&auth)).await.unwrap()).await;
+    assert_eq!(deliveries[0]["state"], "queued");
+    assert_eq!(deliveries[0]["from_status"], "registered");
+    assert_eq!(deliveries[0]["to_status"], "confirmed");
+    let expected = json!({
+        "member_email": "ada@example.com",
+        "member_no": "M-42",
+        "state": "registered -> confirmed",
+        "note": "Attends Members Evening"
+    });
+    assert_eq!(deliveries[0]["payload"], expected);
+
+    // The CRM fails the first attempt; the retry waits for its backoff
+    let client = webhooks::client();
+    assert_eq!(webhooks::dispatch_due(&state, &client, usize::MAX).await.unwrap(), 1);
+    let deliveries = body_json(app.clone().oneshot(history(&auth)).await.unwrap()).await;
+    assert_eq!(deliveries[0]["state"], "queued");
+    assert_eq!(deliveries[0]["attempts"], 1);
+    assert_eq!(deliveries[0]["response_status"], 500);
+    assert_eq!(webhooks::dispatch_due(&state, &client, usize::MAX).await.unwrap(), 0);
+
+    clock.advance(chrono::Duration::seconds(30));
+    assert_eq!(webhooks::dispatch_due(&state, &client, usize::MAX).await.unwrap(), 1);
+    let deliveries = body_json(app.clone().oneshot(history(&auth)).await.unwrap()).await;
+    assert_eq!(deliveries[0]["state"], "delivered");
+    assert_eq!(deliveries[0]["attempts"], 2);
+    assert!(deliveries[0]["last_error"].is_null());
+
+    // Both attempts carry the same delivery id and a valid signature
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2);
+    for (headers, body) in &received {
+        assert_eq!(headers["x-webhook-id"], webhook_id.as_str());
+        assert_eq!(headers["x-webhook-delivery"], deliveries[0]["id"].as_str().unwrap());
+        assert_eq!(headers["x-webhook-signature"], webhooks::sign(&secret, body.as_bytes()).as_str());
+        assert_eq!(serde_json::from_str::<Value>(body).unwrap(), expected);
+    }
+
+    // Another organizer sees none of it
+    let deliveries = body_json(app.clone().oneshot(history(&other_auth)).await.unwrap()).await;
+    assert_eq!(deliveries, json!([]));
     let response = app
         .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/api/organizers/me/webhooks/{}", webhook_id))
+                .header("Authorization", &other_auth)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Registrations match no transition with a `from`
+    let response = app
         .oneshot(
             Request::builder()
                 .method(Method::POST)
                 .uri("/api/participants")
                 .header("Content-Type", "application/json")
-                .body(Body::from(part_body.to_string()))
+                .body(Body::from(json!({
+                    "event_id": event.id,
+                    "name": "Grace Hopper",
+                    "email": "grace@example.com",
+                    "privacy_policy_version": "2024-01"
+                }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
-
     assert_eq!(response.status(), StatusCode::CREATED);
+    let domain_event = domain_events.recv().await.unwrap();
+    assert_eq!(webhooks::enqueue(&state, &domain_event).await.unwrap(), 0);
+}
+
+// =====================
+// NDJSON Streaming Tests
+// =====================
+
+#[tokio::test]
+async fn test_ndjson_streams() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let first = seed_event(&state, "First", None).await;
+    let second = seed_event(&state, "Second", None).await;
+    seed_participant(&state, first.id, "Ada", "ada@example.com").await;
+    seed_participant(&state, second.id, "Grace", "grace@example.com").await;
+    let (organizer, auth) = seed_organizer(&state).await;
+    assign_owner(&state, second.id, organizer.id).await;
+    let app = build_app(state.clone());
+
+    let read_lines = |response: axum::http::Response<Body>| async move {
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/api/events/ndjson").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let events = read_lines(response).await;
+    assert_eq!(events.len(), 2);
+
+    let participants_of = |admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/participants/ndjson?event_id={}", first.id));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // Every row carries the email, so anonymous callers get none of them
+    sqlx::query("UPDATE events SET participant_visibility = 'organizers' WHERE id = ?")
+        .bind(first.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(participants_of(false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(!String::from_utf8_lossy(&body).contains("ada@example.com"));
+
+    // Organizers only stream the participants of their own events
+    let as_organizer = |uri: String| {
+        Request::builder().uri(uri).header("Authorization", auth.as_str()).body(Body::empty()).unwrap()
+    };
+    let response = app
+        .clone()
+        .oneshot(as_organizer(format!("/api/participants/ndjson?event_id={}", first.id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(as_organizer("/api/participants/ndjson".into())).await.unwrap();
+    let participants = read_lines(response).await;
+    assert_eq!(participants.len(), 1);
+    assert_eq!(participants[0]["name"], "Grace");
+
+    let response = app.oneshot(participants_of(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let participants = read_lines(response).await;
+    assert_eq!(participants.len(), 1);
+    assert_eq!(participants[0]["name"], "Ada");
+    // Seeded directly, so no consent was recorded
+    assert!(participants[0]["consent"].is_null());
+}
+
+#[tokio::test]
+async fn test_minimum_age_checks_encrypted_date_of_birth() {
+    let key: backend::encryption::PiiKey = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff".parse().unwrap();
+    let (mut state, _temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.clock = clock;
+    state.config = Arc::new(Config {
+        pii_key: Some(key.clone()),
+        ..Config::default()
+    });
+    let pool = state.db_pool.clone();
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
 
-    // Duplicate registration
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri("/api/participants")
+                .uri("/api/events")
+                .header("Authorization", &auth)
                 .header("Content-Type", "application/json")
-                .body(Body::from(part_body.to_string()))
+                .body(Body::from(json!({
+                    "title": "Evening Lounge",
+                    "start_time": "2026-07-01T20:00:00Z",
+                    "end_time": "2026-07-01T23:00:00Z",
+                    "min_age": 18
+                }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let event = body_json(response).await;
+    assert_eq!(event["min_age"], 18);
+    publish_event(&app, &auth, event["id"].as_str().unwrap()).await;
 
-    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let register = |email: &str, date_of_birth: Option<&str>| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event["id"],
+                "name": "Someone",
+                "email": email,
+                "privacy_policy_version": "2024-01",
+                "date_of_birth": date_of_birth
+            }).to_string()))
+            .unwrap()
+    };
+    let response = app.clone().oneshot(register("a@example.com", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["date_of_birth"][0]["code"], "required");
+
+    // Turns 18 the day after the event
+    let response = app.clone().oneshot(register("b@example.com", Some("2008-07-02"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["date_of_birth"][0]["code"], "under_age");
+
+    let response = app.clone().oneshot(register("c@example.com", Some("2027-01-01"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["date_of_birth"][0]["code"], "out_of_range");
+
+    // 18 on the day of the event, though not yet when registering
+    let response = app.clone().oneshot(register("d@example.com", Some("2008-07-01"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let participant = body_json(response).await;
+    assert!(participant.get("date_of_birth").is_none());
+
+    let stored: Vec<u8> = sqlx::query_scalar("SELECT date_of_birth FROM participants WHERE id = ?")
+        .bind(participant["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&stored).contains("2008"));
+    assert_eq!(key.decrypt(&stored).unwrap(), b"2008-07-01");
 }
 
 #[tokio::test]
-async fn test_event_full_rejected() {
-    let (state, _temp_dir) = create_test_state().await;
+async fn test_participant_names_and_emails_are_sealed_at_rest() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Sealed", None).await;
+    let pool = state.db_pool.clone();
     let app = build_app(state);
 
-    // Create event with max_participants = 1
+    let register = |name: &str, email: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({
+                "event_id": event.id,
+                "name": name,
+                "email": email,
+                "privacy_policy_version": "2024-01"
+            }).to_string()))
+            .unwrap()
+    };
+    let response = app.clone().oneshot(register("Ada Lovelace", "ada@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada = body_json(response).await;
+    assert_eq!(ada["email"], "ada@example.com");
+    let ada_id = ada["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap();
+
+    let (name, email): (Vec<u8>, Vec<u8>) = sqlx::query_as("SELECT name, email FROM participants WHERE id = ?")
+        .bind(ada_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&name).contains("Ada"));
+    assert!(!String::from_utf8_lossy(&email).contains("ada@"));
+
+    // Duplicates are still found, by the blind index
+    let response = app.clone().oneshot(register("Ada Lovelace", "ADA@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // A row from before encryption reads as it is, and is sealed on the next start
+    let legacy = uuid::Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO participants (id, event_id, name, email, status, registered_at, updated_at, dedupe_key, email_index)
+         VALUES (?1, ?2, 'Grace Hopper', 'grace@example.com', 'registered', ?3, ?3, 'grace@example.com', 'grace@example.com')"
+    )
+    .bind(legacy)
+    .bind(event.id)
+    .bind(chrono::Utc::now())
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO mail_failures (to_address, to_address_index, subject, mail, error, first_failed_at, last_failed_at)
+         VALUES ('grace@example.com', 'grace@example.com', 'Welcome', '{\"to_address\":\"grace@example.com\"}', 'relay down', ?1, ?1)"
+    )
+    .bind(chrono::Utc::now())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/participants/{}", legacy)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["name"], "Grace Hopper");
+
+    db::initialize_tables(&pool).await.unwrap();
+    let kinds: (String, String) = sqlx::query_as("SELECT typeof(name), typeof(email) FROM participants WHERE id = ?")
+        .bind(legacy)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kinds, ("blob".to_string(), "blob".to_string()));
+    let kinds: (String, String) = sqlx::query_as("SELECT typeof(to_address), typeof(mail) FROM mail_failures")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kinds, ("blob".to_string(), "blob".to_string()));
+    let response = app.clone().oneshot(register("Grace Hopper", "grace@example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Searches and lookups by email see through the sealing
     let response = app
         .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Small Event",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z",
-                    "max_participants": 1
-                }).to_string()))
+                .uri(format!("/api/events/{}/participants?search=hopper", event.id))
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.headers()["x-total-count"], "1");
+    assert_eq!(body_json(response).await[0]["name"], "Grace Hopper");
 
-    let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
-
-    // First participant
-    let response = app.clone()
+    let response = app
+        .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/participants")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "event_id": event_id,
-                    "name": "Alice",
-                    "email": "alice@test.com"
-                }).to_string()))
+                .uri("/api/participants/by-email/grace@example.com/export")
+                .header("X-Admin-Token", "secret")
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    let export = body_json(response).await;
+    assert_eq!(export["registrations"].as_array().unwrap().len(), 1);
+    assert_eq!(export["registrations"][0]["participant"]["name"], "Grace Hopper");
+    assert!(export["registrations"][0]["participant"].get("email_index").is_none());
+    // Failed mails are found by their address index and opened
+    assert_eq!(export["mail_failures"][0]["to_address"], "grace@example.com");
+    assert_eq!(export["mail_failures"][0]["mail"]["to_address"], "grace@example.com");
+    assert!(export["mail_failures"][0].get("to_address_index").is_none());
 
-    assert_eq!(response.status(), StatusCode::CREATED);
-
-    // Second participant should fail (event full)
     let response = app
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/participants")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "event_id": event_id,
-                    "name": "Bob",
-                    "email": "bob@test.com"
-                }).to_string()))
+                .uri("/api/admin/mail/failures")
+                .header("X-Admin-Token", "secret")
+                .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-
-    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body_json(response).await[0]["to_address"], "grace@example.com");
 }
 
-// =====================
-// Cache Tests
-// =====================
-
 #[tokio::test]
-async fn test_cache_is_populated_on_read() {
-    let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state.clone());
+async fn test_registration_client_context_is_recorded_and_aggregated() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Tracked", None).await;
+    let app = build_app(state);
 
-    // Create event
+    let register = |email: &str, context: serde_json::Value| {
+        let mut body = json!({
+            "event_id": event.id,
+            "name": "Someone",
+            "email": email,
+            "privacy_policy_version": "2024-01"
+        });
+        body.as_object_mut().unwrap().extend(context.as_object().unwrap().clone());
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/participants")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(register("bad@example.com", json!({ "source": "web", "utm_campaign": "spring sale" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body_json(response).await["fields"]["utm_campaign"][0]["code"], "invalid_code");
+
+    let mut kiosk_ids = Vec::new();
+    for (email, context) in [
+        ("a@example.com", json!({ "source": "kiosk", "referrer_code": "door-1" })),
+        ("b@example.com", json!({ "source": "kiosk" })),
+        ("c@example.com", json!({ "source": "web", "utm_campaign": "spring_2026" })),
+        ("d@example.com", json!({})),
+    ] {
+        let response = app.clone().oneshot(register(email, context.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        if context["source"] == "kiosk" {
+            kiosk_ids.push(body_json(response).await["id"].as_str().unwrap().to_string());
+        }
+    }
     let response = app
         .clone()
         .oneshot(
             Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
+                .method(Method::PUT)
+                .uri(format!("/api/participants/{}", kiosk_ids[0]))
                 .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Cached Event",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
-                }).to_string()))
+                .header("X-Admin-Token", "secret")
+                .body(Body::from(json!({ "status": "confirmed" }).to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
+    let analytics = |admin: bool| {
+        let mut builder = Request::builder().uri(format!("/api/analytics/sources?event_id={}", event.id));
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let response = app.clone().oneshot(analytics(false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(analytics(true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let sources = body_json(response).await;
+    assert_eq!(sources[0], json!({
+        "source": "kiosk", "registrations": 2, "confirmed": 1, "cancelled": 0, "conversion_rate": 0.5
+    }));
+    assert_eq!(sources.as_array().unwrap().len(), 3);
+    assert!(sources.as_array().unwrap().iter().any(|s| s["source"].is_null() && s["registrations"] == 1));
 
-    // First GET (populates cache)
-    app.clone()
+    let response = app
         .oneshot(
             Request::builder()
-                .uri(format!("/api/events/{}", event_id))
+                .uri("/api/participants/ndjson?source=kiosk")
+                .header("X-Admin-Token", "secret")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
-
-    // Verify cache is populated
-    assert!(state.cache.event.get(&event_id.to_string()).await.is_some());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let exported: Vec<serde_json::Value> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(exported.len(), 2);
+    assert!(exported.iter().all(|p| p["source"] == "kiosk"));
+    assert_eq!(exported[0]["referrer_code"], "door-1");
+    assert!(exported[1]["referrer_code"].is_null());
 }
 
 #[tokio::test]
-async fn test_cache_invalidated_on_write() {
-    let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state.clone());
+async fn test_export_job_serves_file_through_signed_url() {
+    let (mut state, temp_dir) = create_test_state().await;
+    let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+    state.clock = clock.clone();
+    state.config = Arc::new(Config {
+        data_dir: temp_dir.path().to_str().unwrap().to_string(),
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let event = seed_event(&state, "Exported", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    seed_participant(&state, event.id, "Grace", "grace@example.com").await;
+    let (owner, owner_auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let (stranger, stranger_auth) = seed_organizer(&state).await;
+    let app = build_app(state);
 
-    // Create and list events (populates list cache)
-    app.clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Event 1",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
-                }).to_string()))
-                .unwrap(),
-        )
+    let request = |method: Method, uri: &str, admin: bool| {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if admin {
+            builder = builder.header("X-Admin-Token", "secret");
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let with_headers = |method: Method, uri: &str, headers: &[(&str, &str)]| {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let start_uri = format!("/api/events/{}/export/start", event.id);
+
+    let response = app.clone().oneshot(request(Method::POST, &start_uri, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Exports belong to the event's owner
+    let stranger_org = stranger.id.to_string();
+    let strangers = [
+        vec![("Authorization", stranger_auth.as_str())],
+        vec![("X-Admin-Token", "secret"), ("X-Impersonate-Org", stranger_org.as_str())],
+    ];
+    for headers in &strangers {
+        let response = app.clone().oneshot(with_headers(Method::POST, &start_uri, headers)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(with_headers(Method::POST, &start_uri, &[("Authorization", owner_auth.as_str())]))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let job = body_json(response).await;
+    let status_url = job["status_url"].as_str().unwrap().to_string();
+    for headers in &strangers {
+        let response = app.clone().oneshot(with_headers(Method::GET, &status_url, headers)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
 
-    // List to populate cache
-    app.clone()
+    let mut job = job;
+    for _ in 0..50 {
+        if job["status"] == "ready" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        job = body_json(app.clone().oneshot(request(Method::GET, &status_url, true)).await.unwrap()).await;
+    }
+    assert_eq!(job["status"], "ready");
+    let download_url = job["download_url"].as_str().unwrap().to_string();
+
+    // The signed URL is the only credential needed
+    let response = app.clone().oneshot(request(Method::GET, &download_url, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["email"], "ada@example.com");
+    assert_eq!(job["size_bytes"], body.len());
+
+    let tampered = download_url.replace("expires=", "expires=1");
+    let response = app.clone().oneshot(request(Method::GET, &tampered, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    clock.advance(chrono::Duration::hours(2));
+    let response = app.clone().oneshot(request(Method::GET, &download_url, false)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::GONE);
+    let response = app.oneshot(request(Method::GET, &status_url, true)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_full_export_restores_into_empty_database() {
+    let admin_config = || {
+        Arc::new(Config {
+            admin_token: Some("secret".to_string()),
+            ..Config::default()
+        })
+    };
+    let (mut source, _source_dir) = create_test_state().await;
+    source.config = admin_config();
+    let event = seed_event(&source, "Restore Me", Some(10)).await;
+    let participant = seed_participant(&source, event.id, "Ada", "ada@example.com").await;
+    let pdf = vec![0x25, 0x50, 0x44, 0x46, 0x00, 0xff];
+    sqlx::query("INSERT INTO certificates (id, event_id, participant_id, issued_at, pdf) VALUES (?, ?, ?, ?, ?)")
+        .bind(uuid::Uuid::new_v4())
+        .bind(event.id)
+        .bind(participant.id)
+        .bind(chrono::Utc::now())
+        .bind(&pdf)
+        .execute(&source.db_pool)
+        .await
+        .unwrap();
+
+    let response = build_app(source.clone())
         .oneshot(
             Request::builder()
-                .uri("/api/events")
+                .uri("/api/admin/export/full")
+                .header("X-Admin-Token", "secret")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let export = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(export.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0]["kind"], "header");
+    let schema_version = backend::migrations::current_version(&source.db_pool).await.unwrap();
+    assert_eq!(lines[0]["schema_version"], schema_version);
+    assert_eq!(lines[1]["table"], "events");
+    assert_eq!(lines[1]["row"]["title"], "Restore Me");
 
-    assert!(state.cache.events_list.get("all").await.is_some());
-
-    // Create another event (should invalidate list cache)
-    app.clone()
-        .oneshot(
+    let import = |app: axum::Router, body: Vec<u8>| async move {
+        app.oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Event 2",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
-                }).to_string()))
+                .uri("/api/admin/import/full")
+                .header("Content-Type", "application/x-ndjson")
+                .header("X-Admin-Token", "secret")
+                .body(Body::from(body))
                 .unwrap(),
         )
         .await
-        .unwrap();
-
-    // Cache should be invalidated
-    assert!(state.cache.events_list.get("all").await.is_none());
-}
-
-// =====================
-// Database Tests
-// =====================
-
-#[tokio::test]
-async fn test_db_initialization() {
-    let dir = tempfile::tempdir().unwrap();
-    let db_path = dir.path().join("init_test.db");
-    let db_path_str = db_path.to_str().unwrap();
+        .unwrap()
+    };
 
-    let pool = db::create_pool(db_path_str).await.unwrap();
-    db::initialize_tables(&pool).await.unwrap();
+    let (mut target, _target_dir) = create_test_state().await;
+    target.config = admin_config();
+    let app = build_app(target.clone());
+    let response = import(app.clone(), export.to_vec()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = body_json(response).await;
+    assert_eq!(report["rows"]["events"], 1);
+    assert_eq!(report["rows"]["participants"], 1);
+    assert_eq!(report["rows"]["certificates"], 1);
 
-    // Tables should exist
-    let count: (i64,) = sqlx::query_as("SELECT count(*) FROM sqlite_master WHERE type='table' AND name IN ('events', 'participants', 'change_notifications')")
-        .fetch_one(&pool)
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(format!("/api/events/{}", event.id)).body(Body::empty()).unwrap())
         .await
         .unwrap();
-    assert_eq!(count.0, 3);
-}
-
-#[tokio::test]
-async fn test_notification_insert_and_poll() {
-    let dir = tempfile::tempdir().unwrap();
-    let db_path = dir.path().join("notify_test.db");
-    let db_path_str = db_path.to_str().unwrap();
-
-    let pool = db::create_pool(db_path_str).await.unwrap();
-    db::initialize_tables(&pool).await.unwrap();
-
-    // Insert notification
-    db::insert_notification(&pool, "event_changes", "{\"test\": true}")
+    let restored = body_json(response).await;
+    assert_eq!(restored["title"], "Restore Me");
+    assert_eq!(restored["max_participants"], 10);
+    assert_eq!(restored["start_time"], serde_json::to_value(event.start_time).unwrap());
+    let restored_pdf: Vec<u8> = sqlx::query_scalar("SELECT pdf FROM certificates")
+        .fetch_one(&target.db_pool)
         .await
         .unwrap();
+    assert_eq!(restored_pdf, pdf);
 
-    // Check it was stored
-    let max_id = db::get_max_notification_id(&pool).await;
-    assert!(max_id > 0);
+    // Only into an empty database
+    let response = import(app, export.to_vec()).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
 
-    // Poll and verify
-    let notifications: Vec<(i64, String, String)> = sqlx::query_as(
-        "SELECT id, channel, payload FROM change_notifications WHERE id > 0"
-    )
-    .fetch_all(&pool)
-    .await
-    .unwrap();
+    // Nothing from a newer schema, and nothing the API would reject
+    let (mut fresh, _fresh_dir) = create_test_state().await;
+    fresh.config = admin_config();
+    let app = build_app(fresh.clone());
+    let newer = export
+        .to_vec()
+        .splitn(2, |b| *b == b'\n')
+        .last()
+        .map(|rows| {
+            let header = json!({
+                "kind": "header",
+                "format_version": 1,
+                "schema_version": 9999,
+                "exported_at": "2026-01-01T00:00:00Z",
+                "tables": []
+            });
+            [format!("{}\n", header).into_bytes(), rows.to_vec()].concat()
+        })
+        .unwrap();
+    let response = import(app.clone(), newer).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 
-    assert_eq!(notifications.len(), 1);
-    assert_eq!(notifications[0].1, "event_changes");
+    let blank_title = String::from_utf8(export.to_vec()).unwrap().replace("\"Restore Me\"", "\"  \"");
+    let response = import(app, blank_title.into_bytes()).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["line"], 2);
+    assert_eq!(body["fields"]["title"][0]["code"], "blank");
+    let events: i64 = sqlx::query_scalar("SELECT count(*) FROM events")
+        .fetch_one(&fresh.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(events, 0);
 }
 
 // =====================
-// Delete Participant Tests
+// Certificate Tests
 // =====================
 
 #[tokio::test]
-async fn test_delete_participant() {
-    let (state, _temp_dir) = create_test_state().await;
-    let app = build_app(state);
-
-    // Create event
-    let response = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::POST)
-                .uri("/api/events")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "title": "Event",
-                    "start_time": "2026-03-01T10:00:00Z",
-                    "end_time": "2026-03-01T12:00:00Z"
-                }).to_string()))
-                .unwrap(),
-        )
+async fn test_certificates_for_checked_in_participants() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let mailer = Arc::new(RecordingMailer::default());
+    state.mailer = mailer.clone();
+    let event = seed_event(&state, "Rust Workshop", None).await;
+    let attendee = seed_participant(&state, event.id, "Grace Hopper", "grace@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'checked_in', checked_in_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now())
+        .bind(attendee.id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    // Confirmed but never came to the door
+    let no_show = seed_participant(&state, event.id, "No Show", "noshow@example.com").await;
+    sqlx::query("UPDATE participants SET status = 'confirmed' WHERE id = ?")
+        .bind(no_show.id)
+        .execute(&state.db_pool)
         .await
         .unwrap();
+    let (owner, auth) = seed_organizer(&state).await;
+    assign_owner(&state, event.id, owner.id).await;
+    let (_, stranger_auth) = seed_organizer(&state).await;
+    let app = build_app(state);
 
-    let event = body_json(response).await;
-    let event_id = event["id"].as_str().unwrap();
+    let issue_as = |auth: Option<&str>, body: serde_json::Value| {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/events/{}/certificates", event.id))
+            .header("Content-Type", "application/json");
+        if let Some(auth) = auth {
+            builder = builder.header("Authorization", auth);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+    let issue = |body: serde_json::Value| issue_as(Some(auth.as_str()), body);
+    let get_as = |auth: &str, uri: &str| {
+        Request::builder().uri(uri).header("Authorization", auth).body(Body::empty()).unwrap()
+    };
+
+    // Certificates are issued by the event's owner
+    let response = app.clone().oneshot(issue_as(None, json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(issue_as(Some(stranger_auth.as_str()), json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-    // Create participant
     let response = app
         .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::POST)
-                .uri("/api/participants")
-                .header("Content-Type", "application/json")
-                .body(Body::from(json!({
-                    "event_id": event_id,
-                    "name": "Jane",
-                    "email": "jane@test.com"
-                }).to_string()))
-                .unwrap(),
-        )
+        .oneshot(issue(json!({
+            "template": { "title": "Certificate (Rust)", "body": "{name} completed {event}." },
+            "email": true
+        })))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let issued = body_json(response).await;
+    assert_eq!(issued["emailed"], 1);
+    let certificates = issued["certificates"].as_array().unwrap();
+    assert_eq!(certificates.len(), 1, "only checked-in participants attended");
+    assert_eq!(certificates[0]["participant_id"], json!(attendee.id));
+    let certificate_id = certificates[0]["id"].as_str().unwrap().to_string();
+    let download_url = certificates[0]["download_url"].as_str().unwrap().to_string();
 
-    let participant = body_json(response).await;
-    let part_id = participant["id"].as_str().unwrap();
-
-    // Delete participant
     let response = app
         .clone()
-        .oneshot(
-            Request::builder()
-                .method(Method::DELETE)
-                .uri(format!("/api/participants/{}", part_id))
-                .body(Body::empty())
-                .unwrap(),
-        )
+        .oneshot(Request::builder().uri(&download_url).body(Body::empty()).unwrap())
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get_as(&stranger_auth, &download_url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get_as(&auth, &download_url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/pdf");
+    let pdf = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.contains("(Certificate \\(Rust\\)) Tj"));
+    assert!(text.contains("(Grace Hopper completed Rust Workshop.) Tj"));
 
-    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let sent = wait_for_mails(&mailer, 1).await;
+    assert_eq!(sent[0].to_address, "grace@example.com");
+    assert_eq!(sent[0].attachments[0].content_type, "application/pdf");
+    assert_eq!(sent[0].attachments[0].content, pdf.to_vec());
+
+    // Issuing again keeps the certificate id and download link
+    let response = app.clone().oneshot(issue(json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let reissued = body_json(response).await;
+    assert_eq!(reissued["emailed"], 0);
+    assert_eq!(reissued["certificates"][0]["id"], certificate_id.as_str());
+
+    let list_uri = format!("/api/events/{}/certificates", event.id);
+    let response = app.clone().oneshot(get_as(&stranger_auth, &list_uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get_as(&auth, &list_uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let listed = body_json(response).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["participant_name"], "Grace Hopper");
 
-    // Verify deleted
     let response = app
-        .oneshot(
-            Request::builder()
-                .uri(format!("/api/participants/{}", part_id))
-                .body(Body::empty())
-                .unwrap(),
-        )
+        .oneshot(get_as(&auth, &format!("/api/certificates/{}", uuid::Uuid::new_v4())))
         .await
         .unwrap();
-
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
@@ -0,0 +1,238 @@
+//! Recorded response fixtures for every endpoint the frontend calls.
+//!
+//! Each response is compared with its file under `fixtures/`; the
+//! frontend's mock server serves the same files, so a response shape can't
+//! change on one side only. Re-record after an intended change with
+//! `UPDATE_FIXTURES=1 cargo test --test fixture_tests`.
+
+use axum::{
+    body::Body,
+    http::{Method, Request},
+    Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use backend::clock::MockClock;
+use backend::config::Config;
+use backend::fixtures::Fixture;
+use backend::testing::{assert_fixture, build_app, create_test_state};
+
+const ADMIN_TOKEN: &str = "secret";
+
+/// Send a request and capture its response as a fixture. `route` is the
+/// pattern recorded alongside, `uri` the concrete path with its query.
+async fn record(app: &Router, method: Method, route: &str, uri: &str, body: Option<Value>) -> Fixture {
+    capture(app, route, Request::builder().method(method).uri(uri), body).await
+}
+
+/// Like `record`, with the organizer's admin token
+async fn record_as_organizer(app: &Router, method: Method, route: &str, uri: &str, body: Option<Value>) -> Fixture {
+    let builder = Request::builder().method(method).uri(uri).header("X-Admin-Token", ADMIN_TOKEN);
+    capture(app, route, builder, body).await
+}
+
+async fn capture(app: &Router, route: &str, mut builder: axum::http::request::Builder, body: Option<Value>) -> Fixture {
+    let method = builder.method_ref().unwrap().to_string();
+    let uri = builder.uri_ref().unwrap().to_string();
+    if body.is_some() {
+        builder = builder.header("Content-Type", "application/json");
+    }
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+
+    let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status().as_u16();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    Fixture {
+        method,
+        route: route.to_string(),
+        query: uri.split_once('?').map(|(_, query)| query.to_string()),
+        status,
+        body: if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() },
+    }
+}
+
+#[tokio::test]
+async fn test_event_fixtures() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.config = Arc::new(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state);
+
+    let create = json!({
+        "title": "Summer Meetup",
+        "description": "Talks and drinks",
+        "start_time": "2026-07-01T18:00:00Z",
+        "end_time": "2026-07-01T21:00:00Z",
+        "location": "Main Hall",
+        "latitude": 52.52,
+        "longitude": 13.405,
+        "max_participants": 50,
+        "tags": ["meetup", "talks"]
+    });
+    let created = record_as_organizer(&app, Method::POST, "/api/events", "/api/events", Some(create.clone())).await;
+    let id = created.body["id"].as_str().unwrap().to_string();
+    let path = format!("/api/events/{}", id);
+    assert_fixture("create_event", created);
+
+    assert_fixture(
+        "publish_event",
+        record_as_organizer(&app, Method::POST, "/api/events/:id/publish", &format!("{}/publish", path), None).await,
+    );
+    assert_fixture("list_events", record(&app, Method::GET, "/api/events", "/api/events", None).await);
+    assert_fixture(
+        "list_events_near",
+        record(&app, Method::GET, "/api/events", "/api/events?near=52.52,13.40&radius_km=25", None).await,
+    );
+    assert_fixture("get_event", record(&app, Method::GET, "/api/events/:id", &path, None).await);
+
+    let mut update = create;
+    update["title"] = json!("Summer Meetup 2026");
+    assert_fixture("update_event", record_as_organizer(&app, Method::PUT, "/api/events/:id", &path, Some(update)).await);
+    assert_fixture(
+        "get_event_not_found",
+        record(&app, Method::GET, "/api/events/:id", "/api/events/00000000-0000-4000-8000-000000000000", None).await,
+    );
+    assert_fixture("delete_event", record_as_organizer(&app, Method::DELETE, "/api/events/:id", &path, None).await);
+}
+
+#[tokio::test]
+async fn test_participant_fixtures() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    state.clock = Arc::new(MockClock::new("2026-06-01T12:00:00Z".parse().unwrap()));
+    state.config = Arc::new(Config {
+        admin_token: Some(ADMIN_TOKEN.to_string()),
+        ..Config::default()
+    });
+    let app = build_app(state);
+
+    let event = record_as_organizer(
+        &app,
+        Method::POST,
+        "/api/events",
+        "/api/events",
+        Some(json!({
+            "title": "Workshop",
+            "start_time": "2026-07-01T09:00:00Z",
+            "end_time": "2026-07-01T17:00:00Z",
+            "max_participants": 1
+        })),
+    )
+    .await;
+    let event_id = event.body["id"].as_str().unwrap().to_string();
+    let event_path = format!("/api/events/{}", event_id);
+    record_as_organizer(&app, Method::POST, "/api/events/:id/publish", &format!("{}/publish", event_path), None).await;
+
+    let register = |name: &str, email: &str| {
+        json!({
+            "event_id": event_id,
+            "name": name,
+            "email": email,
+            "privacy_policy_version": "2024-01"
+        })
+    };
+    let registered = record(
+        &app,
+        Method::POST,
+        "/api/participants",
+        "/api/participants",
+        Some(register("Ada Lovelace", "ada@example.com")),
+    )
+    .await;
+    let id = registered.body["id"].as_str().unwrap().to_string();
+    let path = format!("/api/participants/{}", id);
+    assert_fixture("create_participant", registered);
+    assert_fixture(
+        "create_participant_waitlisted",
+        record(
+            &app,
+            Method::POST,
+            "/api/participants",
+            "/api/participants",
+            Some(register("Grace Hopper", "grace@example.com")),
+        )
+        .await,
+    );
+    assert_fixture(
+        "create_participant_invalid",
+        record(
+            &app,
+            Method::POST,
+            "/api/participants",
+            "/api/participants",
+            Some(register("Nobody", "not-an-email")),
+        )
+        .await,
+    );
+
+    assert_fixture(
+        "list_participants",
+        record_as_organizer(&app, Method::GET, "/api/events/:id/participants", &format!("{}/participants", event_path), None)
+            .await,
+    );
+    assert_fixture(
+        "list_participants_fuzzy",
+        record(
+            &app,
+            Method::GET,
+            "/api/events/:id/participants",
+            &format!("{}/participants?fuzzy=lovlace", event_path),
+            None,
+        )
+        .await,
+    );
+    assert_fixture(
+        "get_availability",
+        record(&app, Method::GET, "/api/events/:id/availability", &format!("{}/availability", event_path), None).await,
+    );
+    assert_fixture("get_participant", record(&app, Method::GET, "/api/participants/:id", &path, None).await);
+    assert_fixture(
+        "update_participant_status",
+        record_as_organizer(&app, Method::PUT, "/api/participants/:id", &path, Some(json!({ "status": "confirmed" }))).await,
+    );
+
+    // Certificates go to checked-in participants, by the organizer
+    record_as_organizer(&app, Method::POST, "/api/participants/:id/checkin", &format!("{}/checkin", path), None).await;
+    assert_fixture(
+        "issue_certificates",
+        record_as_organizer(
+            &app,
+            Method::POST,
+            "/api/events/:id/certificates",
+            &format!("{}/certificates", event_path),
+            Some(json!({})),
+        )
+        .await,
+    );
+    assert_fixture(
+        "list_certificates",
+        record_as_organizer(&app, Method::GET, "/api/events/:id/certificates", &format!("{}/certificates", event_path), None)
+            .await,
+    );
+
+    assert_fixture("delete_participant", record(&app, Method::DELETE, "/api/participants/:id", &path, None).await);
+}
+
+#[tokio::test]
+async fn test_fixtures_are_served_in_debug_builds() {
+    let (state, _temp_dir) = create_test_state().await;
+    let app = build_app(state);
+
+    let list = record(&app, Method::GET, "/api/debug/fixtures", "/api/debug/fixtures", None).await;
+    assert_eq!(list.status, 200);
+    let names = list.body.as_array().unwrap();
+    assert!(names.iter().any(|name| name == "get_event"), "missing fixtures: {}", list.body);
+
+    let fixture = record(&app, Method::GET, "/api/debug/fixtures/:name", "/api/debug/fixtures/get_event", None).await;
+    assert_eq!(fixture.status, 200);
+    let served: Fixture = serde_json::from_value(fixture.body).unwrap();
+    assert_eq!(served.route, "/api/events/:id");
+
+    let missing = record(&app, Method::GET, "/api/debug/fixtures/:name", "/api/debug/fixtures/nope", None).await;
+    assert_eq!(missing.status, 404);
+}
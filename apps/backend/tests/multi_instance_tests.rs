@@ -0,0 +1,196 @@
+//! Tests for keeping several instances on one database in sync.
+//!
+//! Each instance caches and broadcasts on its own; a write made through one
+//! reaches the others only as a row in `change_notifications`, picked up by
+//! their notification pollers. These tests run two instances on the same
+//! SQLite file, write through the first and watch the second.
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+use backend::broadcaster::Channel;
+use backend::config::Config;
+use backend::testing::{
+    body_json, build_app, create_peer_state, create_test_state, expect_broadcast, seed_event, seed_participant,
+    spawn_poller,
+};
+use backend::AppState;
+
+/// How long a change may take to reach the other instance: a poll
+/// interval, and slack for a slow test machine
+const SYNC_BOUND: Duration = Duration::from_secs(3);
+
+/// Two instances on one database, both accepting the admin token
+async fn create_instances() -> (AppState, AppState, tempfile::TempDir) {
+    let (mut state, temp_dir) = create_test_state().await;
+    state.config = Arc::new(Config {
+        admin_token: Some("secret".to_string()),
+        ..Config::default()
+    });
+    let peer = create_peer_state(&state, &temp_dir).await;
+    (state, peer, temp_dir)
+}
+
+async fn send(app: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("X-Admin-Token", "secret");
+    let body = match body {
+        Some(body) => {
+            builder = builder.header("Content-Type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, body_json(response).await)
+}
+
+#[tokio::test]
+async fn test_participant_change_reaches_other_instance() {
+    let (state, peer, _temp_dir) = create_instances().await;
+    let event = seed_event(&state, "Shared", None).await;
+    let ada = seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let app = build_app(state);
+    let peer_app = build_app(peer.clone());
+    let uri = format!("/api/participants/{}", ada.id);
+
+    // The other instance has the participant cached as registered
+    let (status, body) = send(&peer_app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "registered");
+    assert!(peer.cache.participant.get(&ada.id.to_string()).await.is_some());
+
+    let mut receiver = peer.broadcaster.subscribe();
+    let _poller = spawn_poller(&peer).await;
+
+    let (status, _) = send(&app, Method::PUT, &uri, Some(json!({ "status": "confirmed" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payload = expect_broadcast(&mut receiver, Channel::ParticipantChanges, SYNC_BOUND).await;
+    assert_eq!(payload["id"], ada.id.to_string());
+    assert_eq!(payload["event_id"], event.id.to_string());
+
+    // Invalidated before the broadcast, so the next read sees the change
+    assert!(peer.cache.participant.get(&ada.id.to_string()).await.is_none());
+    assert!(peer.cache.invalidations(Channel::ParticipantChanges) >= 1);
+    let (status, body) = send(&peer_app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "confirmed");
+}
+
+#[tokio::test]
+async fn test_event_update_reaches_other_instance() {
+    let (state, peer, _temp_dir) = create_instances().await;
+    let event = seed_event(&state, "Before", None).await;
+    let app = build_app(state);
+    let peer_app = build_app(peer.clone());
+    let uri = format!("/api/events/{}", event.id);
+
+    let (status, body) = send(&peer_app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["title"], "Before");
+    assert!(peer.cache.event.get(&event.id.to_string()).await.is_some());
+
+    let mut receiver = peer.broadcaster.subscribe();
+    let _poller = spawn_poller(&peer).await;
+
+    let update = json!({
+        "title": "After",
+        "start_time": event.start_time,
+        "end_time": event.end_time
+    });
+    let (status, _) = send(&app, Method::PUT, &uri, Some(update)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payload = expect_broadcast(&mut receiver, Channel::EventChanges, SYNC_BOUND).await;
+    assert_eq!(payload["operation"], "UPDATE");
+    assert_eq!(payload["id"], event.id.to_string());
+
+    assert!(peer.cache.event.get(&event.id.to_string()).await.is_none());
+    let (status, body) = send(&peer_app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["title"], "After");
+}
+
+#[tokio::test]
+async fn test_other_instance_broadcasts_changes_in_write_order() {
+    let (state, peer, _temp_dir) = create_instances().await;
+    let event = seed_event(&state, "Busy", None).await;
+    let app = build_app(state);
+
+    let mut receiver = peer.broadcaster.subscribe();
+    let _poller = spawn_poller(&peer).await;
+
+    let mut registered = Vec::new();
+    for i in 0..5 {
+        let body = json!({
+            "event_id": event.id,
+            "name": format!("Guest {}", i),
+            "email": format!("guest{}@example.com", i),
+            "privacy_policy_version": "2024-01"
+        });
+        let (status, participant) = send(&app, Method::POST, "/api/participants", Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        registered.push(participant["id"].as_str().unwrap().to_string());
+    }
+
+    let mut broadcast = Vec::new();
+    for _ in 0..registered.len() {
+        let payload = expect_broadcast(&mut receiver, Channel::ParticipantChanges, SYNC_BOUND).await;
+        assert_eq!(payload["operation"], "INSERT");
+        broadcast.push(payload["id"].as_str().unwrap().to_string());
+    }
+    assert_eq!(broadcast, registered);
+
+    // Every row went through the poller of the other instance
+    assert!(peer.poller_metrics.rows_fetched() >= registered.len() as u64);
+    assert!(peer.poller_metrics.iterations() >= 1);
+}
+
+#[tokio::test]
+async fn test_export_url_signed_by_one_instance_downloads_from_other() {
+    let (mut state, temp_dir) = create_test_state().await;
+    // Each instance reads its configuration on its own
+    let config = || {
+        Arc::new(Config {
+            data_dir: temp_dir.path().to_str().unwrap().to_string(),
+            admin_token: Some("secret".to_string()),
+            ..Config::default()
+        })
+    };
+    state.config = config();
+    let mut peer = create_peer_state(&state, &temp_dir).await;
+    peer.config = config();
+    let event = seed_event(&state, "Exported", None).await;
+    seed_participant(&state, event.id, "Ada", "ada@example.com").await;
+    let app = build_app(state);
+    let peer_app = build_app(peer);
+
+    let (status, mut job) = send(&app, Method::POST, &format!("/api/events/{}/export/start", event.id), None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let status_url = job["status_url"].as_str().unwrap().to_string();
+    for _ in 0..50 {
+        if job["status"] == "ready" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        job = send(&app, Method::GET, &status_url, None).await.1;
+    }
+    assert_eq!(job["status"], "ready");
+
+    // Without EXPORT_SIGNING_KEY both instances share the generated key
+    let download = Request::builder()
+        .uri(job["download_url"].as_str().unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let response = peer_app.oneshot(download).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
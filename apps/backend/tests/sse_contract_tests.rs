@@ -0,0 +1,640 @@
+//! Contract tests for the JSON payloads broadcast over SSE.
+//!
+//! The frontend parses these payloads directly, so any change to field names
+//! or types must be deliberate and show up here as a failing test.
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+
+use backend::broadcaster::{Channel, ServerEvent};
+use backend::db;
+use backend::load_shed::{LoadShedConfig, LoadShedder};
+use backend::reconnect::ReconnectConfig;
+use backend::testing::{
+    body_json, build_app, create_test_state, expect_broadcast, organizer_auth, publish_event, spawn_poller,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    String,
+    Uuid,
+    Timestamp,
+    /// Non-negative integer
+    Integer,
+    /// `{"registered": <int>, "waitlisted": <int>}`
+    Counts,
+}
+
+/// Assert that `payload` has exactly the given fields with the given types
+fn assert_schema(payload: &Value, schema: &[(&str, Kind)]) {
+    let object = payload.as_object().expect("payload must be a JSON object");
+
+    let mut actual: Vec<&str> = object.keys().map(String::as_str).collect();
+    let mut expected: Vec<&str> = schema.iter().map(|(name, _)| *name).collect();
+    actual.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(actual, expected, "payload fields changed: {}", payload);
+
+    for (name, kind) in schema {
+        let field = &object[*name];
+        let as_str = || {
+            field
+                .as_str()
+                .unwrap_or_else(|| panic!("field '{}' must be a string in {}", name, payload))
+        };
+        match kind {
+            Kind::String => {
+                as_str();
+            }
+            Kind::Uuid => {
+                let value = as_str();
+                uuid::Uuid::parse_str(value)
+                    .unwrap_or_else(|_| panic!("field '{}' must be a UUID, got {}", name, value));
+            }
+            Kind::Timestamp => {
+                let value = as_str();
+                chrono::DateTime::parse_from_rfc3339(value)
+                    .unwrap_or_else(|_| panic!("field '{}' must be RFC 3339, got {}", name, value));
+            }
+            Kind::Integer => {
+                assert!(field.is_u64(), "field '{}' must be a non-negative integer in {}", name, payload);
+            }
+            Kind::Counts => {
+                let counts = field
+                    .as_object()
+                    .unwrap_or_else(|| panic!("field '{}' must be an object in {}", name, payload));
+                let mut keys: Vec<&str> = counts.keys().map(String::as_str).collect();
+                keys.sort_unstable();
+                assert_eq!(keys, ["registered", "waitlisted"], "counts fields changed: {}", payload);
+                assert!(counts.values().all(Value::is_u64), "counts must be non-negative integers: {}", payload);
+            }
+        }
+    }
+}
+
+const EVENT_SCHEMA: &[(&str, Kind)] = &[
+    ("operation", Kind::String),
+    ("table", Kind::String),
+    ("id", Kind::Uuid),
+    ("timestamp", Kind::Timestamp),
+];
+
+const PARTICIPANT_SCHEMA: &[(&str, Kind)] = &[
+    ("operation", Kind::String),
+    ("table", Kind::String),
+    ("id", Kind::Uuid),
+    ("event_id", Kind::Uuid),
+    ("counts", Kind::Counts),
+    ("timestamp", Kind::Timestamp),
+];
+
+const RECONNECT_SCHEMA: &[(&str, Kind)] = &[
+    ("type", Kind::String),
+    ("reason", Kind::String),
+    ("status", Kind::Integer),
+    ("retry_after_ms", Kind::Integer),
+    ("timestamp", Kind::Timestamp),
+];
+
+async fn send(app: &Router, auth: &str, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("Authorization", auth);
+    let body = match body {
+        Some(body) => {
+            builder = builder.header("Content-Type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    if status == StatusCode::NO_CONTENT {
+        return (status, Value::Null);
+    }
+    (status, body_json(response).await)
+}
+
+/// Read the SSE stream at `uri` until the server closes it
+async fn read_stream(app: &Router, uri: &str) -> String {
+    read_stream_from(app, Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+}
+
+/// Read the SSE stream `request` opens until the server closes it
+async fn read_stream_from(app: &Router, request: Request<Body>) -> String {
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = tokio::time::timeout(TIMEOUT, axum::body::to_bytes(response.into_body(), usize::MAX))
+        .await
+        .expect("stream must close")
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// The type and data of the events in a read stream
+fn stream_events(text: &str) -> Vec<(String, Value)> {
+    text.split("\n\n")
+        .filter_map(|event| {
+            let kind = event.lines().find_map(|line| line.strip_prefix("event: "))?;
+            let data = event.lines().find_map(|line| line.strip_prefix("data: "))?;
+            Some((kind.to_string(), serde_json::from_str(data).unwrap()))
+        })
+        .collect()
+}
+
+/// The `id:` of each event in a read stream
+fn stream_ids(text: &str) -> Vec<i64> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("id: "))
+        .map(|id| id.parse().unwrap())
+        .collect()
+}
+
+/// Read an SSE stream until the server closes it; returns the `retry:`
+/// values and the data of the `reconnect` events it carried
+async fn read_closing_stream(app: &Router) -> (Vec<u64>, Vec<Value>) {
+    let text = read_stream(app, "/api/events/stream").await;
+
+    let retries = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("retry:"))
+        .map(|value| value.trim().parse().unwrap())
+        .collect();
+    let reconnects = stream_events(&text)
+        .into_iter()
+        .filter(|(kind, _)| kind == "reconnect")
+        .map(|(_, data)| data)
+        .collect();
+    (retries, reconnects)
+}
+
+async fn next_payload(receiver: &mut broadcast::Receiver<ServerEvent>, channel: Channel) -> Value {
+    expect_broadcast(receiver, channel, TIMEOUT).await
+}
+
+#[tokio::test]
+async fn test_event_changes_payload_schema() {
+    let (state, _temp_dir) = create_test_state().await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let event_body = json!({
+        "title": "Contract Event",
+        "start_time": "2026-03-01T10:00:00Z",
+        "end_time": "2026-03-01T12:00:00Z"
+    });
+
+    let (status, event) = send(&app, &auth, Method::POST, "/api/events", Some(event_body.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let event_id = event["id"].as_str().unwrap().to_string();
+
+    let payload = next_payload(&mut receiver, Channel::EventChanges).await;
+    assert_schema(&payload, EVENT_SCHEMA);
+    assert_eq!(payload["operation"], "INSERT");
+    assert_eq!(payload["table"], "events");
+    assert_eq!(payload["id"], event_id);
+
+    let uri = format!("/api/events/{}", event_id);
+    let (status, _) = send(&app, &auth, Method::POST, &format!("{}/publish", uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payload = next_payload(&mut receiver, Channel::EventChanges).await;
+    assert_schema(&payload, EVENT_SCHEMA);
+    assert_eq!(payload["operation"], "PUBLISH");
+
+    let (status, _) = send(&app, &auth, Method::PUT, &uri, Some(event_body)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payload = next_payload(&mut receiver, Channel::EventChanges).await;
+    assert_schema(&payload, EVENT_SCHEMA);
+    assert_eq!(payload["operation"], "UPDATE");
+
+    let (status, _) = send(&app, &auth, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let payload = next_payload(&mut receiver, Channel::EventChanges).await;
+    assert_schema(&payload, EVENT_SCHEMA);
+    assert_eq!(payload["operation"], "DELETE");
+    assert_eq!(payload["id"], event_id);
+}
+
+#[tokio::test]
+async fn test_participant_changes_payload_schema() {
+    let (state, _temp_dir) = create_test_state().await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let (_, event) = send(
+        &app,
+        &auth,
+        Method::POST,
+        "/api/events",
+        Some(json!({
+            "title": "Contract Event",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z"
+        })),
+    )
+    .await;
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+
+    let (status, participant) = send(
+        &app,
+        &auth,
+        Method::POST,
+        "/api/participants",
+        Some(json!({
+            "event_id": event_id,
+            "name": "Grace",
+            "email": "grace@example.com",
+            "privacy_policy_version": "2024-01"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let participant_id = participant["id"].as_str().unwrap().to_string();
+
+    let payload = next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    assert_schema(&payload, PARTICIPANT_SCHEMA);
+    assert_eq!(payload["operation"], "INSERT");
+    assert_eq!(payload["table"], "participants");
+    assert_eq!(payload["id"], participant_id);
+    assert_eq!(payload["event_id"], event_id);
+    assert_eq!(payload["counts"], json!({ "registered": 1, "waitlisted": 0 }));
+
+    let uri = format!("/api/participants/{}", participant_id);
+    let (status, _) = send(&app, &auth, Method::PUT, &uri, Some(json!({ "status": "maybe" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payload = next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    assert_schema(&payload, PARTICIPANT_SCHEMA);
+    assert_eq!(payload["operation"], "UPDATE");
+    assert_eq!(payload["counts"], json!({ "registered": 0, "waitlisted": 0 }));
+
+    let (status, _) = send(&app, &auth, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let payload = next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    assert_schema(&payload, PARTICIPANT_SCHEMA);
+    assert_eq!(payload["operation"], "DELETE");
+    assert_eq!(payload["event_id"], event_id);
+    assert_eq!(payload["counts"], json!({ "registered": 0, "waitlisted": 0 }));
+}
+
+#[tokio::test]
+async fn test_waitlist_promotion_payload_schema() {
+    let (state, _temp_dir) = create_test_state().await;
+    let mut receiver = state.broadcaster.subscribe();
+    let _poller = spawn_poller(&state).await;
+    let auth = organizer_auth(&state).await;
+    let app = build_app(state);
+
+    let (_, event) = send(
+        &app,
+        &auth,
+        Method::POST,
+        "/api/events",
+        Some(json!({
+            "title": "Contract Event",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z",
+            "max_participants": 1
+        })),
+    )
+    .await;
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+
+    let mut ids = Vec::new();
+    for email in ["grace@example.com", "ada@example.com"] {
+        let (status, participant) = send(
+            &app,
+            &auth,
+            Method::POST,
+            "/api/participants",
+            Some(json!({
+                "event_id": event_id,
+                "name": "Contract Participant",
+                "email": email,
+                "privacy_policy_version": "2024-01"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        ids.push(participant["id"].as_str().unwrap().to_string());
+        next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    }
+
+    let (status, _) = send(&app, &auth, Method::DELETE, &format!("/api/participants/{}", ids[0]), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let payload = next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    assert_eq!(payload["operation"], "DELETE");
+    let payload = next_payload(&mut receiver, Channel::ParticipantChanges).await;
+    assert_schema(&payload, PARTICIPANT_SCHEMA);
+    assert_eq!(payload["operation"], "PROMOTE");
+    assert_eq!(payload["id"], ids[1]);
+    assert_eq!(payload["event_id"], event_id);
+    assert_eq!(payload["counts"], json!({ "registered": 1, "waitlisted": 0 }));
+}
+
+#[tokio::test]
+async fn test_reconnect_advice_when_overloaded() {
+    let (mut state, _temp_dir) = create_test_state().await;
+    let mut config = (*state.config).clone();
+    config.sse_reconnect = ReconnectConfig {
+        delay: Duration::from_millis(2000),
+        jitter: Duration::from_millis(500),
+    };
+    state.config = std::sync::Arc::new(config);
+    // The stream request itself is in flight, so a limit of zero sheds it
+    state.load_shedder = LoadShedder::new(LoadShedConfig {
+        max_in_flight: 0,
+        ..LoadShedConfig::default()
+    });
+    let app = build_app(state);
+
+    let (retries, reconnects) = read_closing_stream(&app).await;
+    assert_eq!(reconnects.len(), 1);
+    let payload = &reconnects[0];
+    assert_schema(payload, RECONNECT_SCHEMA);
+    assert_eq!(payload["type"], "reconnect");
+    assert_eq!(payload["reason"], "overloaded");
+    assert_eq!(payload["status"], 503);
+
+    let delay = payload["retry_after_ms"].as_u64().unwrap();
+    assert!((2000..=2500).contains(&delay), "delay {} outside the jitter window", delay);
+    assert_eq!(retries, vec![delay]);
+}
+
+#[tokio::test]
+async fn test_reconnect_advice_on_shutdown() {
+    let (state, _temp_dir) = create_test_state().await;
+    let instance = state.instance.clone();
+    let app = build_app(state);
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        instance.mark_draining();
+    });
+
+    // The stream opens with a reconnect delay and closes with the advice
+    let (retries, reconnects) = read_closing_stream(&app).await;
+    assert_eq!(retries.len(), 2);
+    assert_eq!(reconnects.len(), 1);
+    assert_schema(&reconnects[0], RECONNECT_SCHEMA);
+    assert_eq!(reconnects[0]["reason"], "shutting_down");
+    assert_eq!(reconnects[0]["retry_after_ms"].as_u64().unwrap(), retries[1]);
+
+    // Clients reconnecting to the draining instance are closed right away
+    let (_, reconnects) = read_closing_stream(&app).await;
+    assert_eq!(reconnects[0]["reason"], "shutting_down");
+}
+
+#[tokio::test]
+async fn test_stream_sends_only_the_requested_topic() {
+    let (state, _temp_dir) = create_test_state().await;
+    let broadcaster = state.broadcaster.clone();
+    let instance = state.instance.clone();
+    let app = build_app(state);
+
+    let watched = uuid::Uuid::new_v4();
+    let other = uuid::Uuid::new_v4();
+    let event_change = |n: i64, id: uuid::Uuid| ServerEvent {
+        id: n,
+        channel: Channel::EventChanges,
+        payload: json!({ "operation": "UPDATE", "table": "events", "id": id }).to_string(),
+    };
+    let participant_change = |n: i64, event_id: uuid::Uuid| ServerEvent {
+        id: n,
+        channel: Channel::ParticipantChanges,
+        payload: json!({
+            "operation": "INSERT",
+            "table": "participants",
+            "id": uuid::Uuid::new_v4(),
+            "event_id": event_id
+        })
+        .to_string(),
+    };
+
+    tokio::spawn(async move {
+        // Once the streams below have subscribed
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        broadcaster.broadcast(event_change(1, other));
+        broadcaster.broadcast(participant_change(2, other));
+        broadcaster.broadcast(event_change(3, watched));
+        broadcaster.broadcast(participant_change(4, watched));
+        broadcaster.broadcast(ServerEvent {
+            id: 5,
+            channel: Channel::System,
+            payload: json!({ "type": "settings", "key": "event_list" }).to_string(),
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        instance.mark_draining();
+    });
+
+    let by_event_uri = format!("/api/events/stream?event_id={}", watched);
+    let by_channel_uri = format!("/api/events/stream?channel=participant_changes&event_id={}", watched);
+    let (all, by_event, by_channel) = tokio::join!(
+        read_stream(&app, "/api/events/stream"),
+        read_stream(&app, &by_event_uri),
+        read_stream(&app, &by_channel_uri),
+    );
+    let received = |text: &str| -> Vec<(String, Option<String>)> {
+        stream_events(text)
+            .into_iter()
+            .filter(|(kind, _)| kind != "reconnect")
+            .map(|(kind, data)| {
+                let subject = data.get("event_id").or(data.get("id")).and_then(Value::as_str).map(str::to_string);
+                (kind, subject)
+            })
+            .collect()
+    };
+
+    assert_eq!(received(&all).len(), 5);
+    let watched = Some(watched.to_string());
+    assert_eq!(
+        received(&by_event),
+        vec![
+            ("event_changes".to_string(), watched.clone()),
+            ("participant_changes".to_string(), watched.clone()),
+            // System notices concern every page
+            ("system".to_string(), None),
+        ]
+    );
+    assert_eq!(received(&by_channel), vec![("participant_changes".to_string(), watched)]);
+    assert_eq!(stream_ids(&by_channel), vec![4]);
+
+    // An unknown channel is refused rather than silently sending nothing
+    let request = Request::builder().uri("/api/events/stream?channel=everything").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_stream_carries_participant_counts() {
+    let (state, _temp_dir) = create_test_state().await;
+    let _poller = spawn_poller(&state).await;
+    let auth = organizer_auth(&state).await;
+    let instance = state.instance.clone();
+    let app = build_app(state);
+
+    let (_, event) = send(
+        &app,
+        &auth,
+        Method::POST,
+        "/api/events",
+        Some(json!({
+            "title": "Counted Event",
+            "start_time": "2026-03-01T10:00:00Z",
+            "end_time": "2026-03-01T12:00:00Z"
+        })),
+    )
+    .await;
+    let event_id = event["id"].as_str().unwrap().to_string();
+    publish_event(&app, &auth, &event_id).await;
+
+    let registering = {
+        let app = app.clone();
+        let event_id = event_id.clone();
+        tokio::spawn(async move {
+            // Once the stream below has subscribed
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let (status, participant) = send(
+                &app,
+                &auth,
+                Method::POST,
+                "/api/participants",
+                Some(json!({
+                    "event_id": event_id,
+                    "name": "Grace",
+                    "email": "grace@example.com",
+                    "privacy_policy_version": "2024-01"
+                })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+            tokio::time::sleep(db::POLL_INTERVAL + Duration::from_millis(500)).await;
+            instance.mark_draining();
+            participant["id"].as_str().unwrap().to_string()
+        })
+    };
+
+    let uri = format!("/api/events/stream?channel=participant_changes&event_id={}", event_id);
+    let text = read_stream(&app, &uri).await;
+    let participant_id = registering.await.unwrap();
+
+    let changes: Vec<Value> = stream_events(&text)
+        .into_iter()
+        .filter(|(kind, _)| kind == "participant_changes")
+        .map(|(_, data)| data)
+        .collect();
+    assert_eq!(changes.len(), 1, "stream: {}", text);
+    assert_schema(&changes[0], PARTICIPANT_SCHEMA);
+    assert_eq!(changes[0]["id"], participant_id);
+    assert_eq!(changes[0]["counts"], json!({ "registered": 1, "waitlisted": 0 }));
+}
+
+async fn insert_change(pool: &db::DbPool, created_at: chrono::DateTime<chrono::Utc>) -> i64 {
+    let payload = json!({ "operation": "UPDATE", "table": "events", "id": uuid::Uuid::new_v4(), "timestamp": created_at });
+    db::insert_notification(pool, Channel::EventChanges, &payload.to_string(), created_at).await.unwrap();
+    db::get_max_notification_id(pool).await
+}
+
+fn resuming(uri: &str, last_event_id: i64) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .header("Last-Event-ID", last_event_id.to_string())
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_stream_replays_notifications_missed_since_last_event_id() {
+    let (state, _temp_dir) = create_test_state().await;
+    let now = state.clock.now();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(insert_change(&state.db_pool, now).await);
+    }
+    let _poller = spawn_poller(&state).await;
+    let pool = state.db_pool.clone();
+    let instance = state.instance.clone();
+    let app = build_app(state);
+
+    let live = tokio::spawn(async move {
+        // Once the streams below have replayed and subscribed
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let id = insert_change(&pool, now).await;
+        tokio::time::sleep(db::POLL_INTERVAL + Duration::from_millis(500)).await;
+        instance.mark_draining();
+        id
+    });
+
+    let by_query = format!("/api/events/stream?last_event_id={}", ids[1]);
+    let (fresh, by_header, by_query) = tokio::join!(
+        read_stream(&app, "/api/events/stream"),
+        read_stream_from(&app, resuming("/api/events/stream", ids[0])),
+        read_stream(&app, &by_query),
+    );
+    let live = live.await.unwrap();
+
+    // Missed notifications first, then the live ones, each exactly once
+    assert_eq!(stream_ids(&fresh), vec![live]);
+    assert_eq!(stream_ids(&by_header), vec![ids[1], ids[2], live]);
+    assert_eq!(stream_ids(&by_query), vec![ids[2], live]);
+    let kinds: Vec<String> = stream_events(&by_header).into_iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, ["event_changes", "event_changes", "event_changes", "reconnect"]);
+}
+
+#[tokio::test]
+async fn test_stream_asks_for_resync_when_history_is_gone() {
+    let (state, _temp_dir) = create_test_state().await;
+    let now = state.clock.now();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(insert_change(&state.db_pool, now - chrono::Duration::days(10)).await);
+    }
+    assert_eq!(db::cleanup_notifications(&state.db_pool, now - chrono::Duration::days(1)).await.unwrap(), 3);
+    let kept = insert_change(&state.db_pool, now).await;
+    let instance = state.instance.clone();
+    let app = build_app(state);
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        instance.mark_draining();
+    });
+
+    let (expired, caught_up, unknown) = tokio::join!(
+        read_stream_from(&app, resuming("/api/events/stream", ids[0])),
+        read_stream_from(&app, resuming("/api/events/stream", ids[2])),
+        read_stream_from(&app, resuming("/api/events/stream", kept + 100)),
+    );
+
+    // The client missed notifications that were deleted
+    let events = stream_events(&expired);
+    assert_eq!(events[0].0, "resync");
+    assert_eq!(events[0].1["type"], "resync");
+    assert_eq!(events[0].1["last_event_id"], ids[0]);
+    assert!(stream_ids(&expired).is_empty());
+
+    // Nothing it missed was deleted
+    assert_eq!(stream_ids(&caught_up), vec![kept]);
+    assert!(stream_events(&caught_up).iter().all(|(kind, _)| kind != "resync"));
+
+    // An id this database never assigned
+    assert_eq!(stream_events(&unknown)[0].0, "resync");
+}
@@ -7,6 +7,9 @@
 
 const API_BASE_URL = import.meta.env.VITE_API_URL || 'http://localhost:3000/api'
 
+/** Privacy policy version registrants accept in the participant form */
+export const PRIVACY_POLICY_VERSION = import.meta.env.VITE_PRIVACY_POLICY_VERSION || '2024-01'
+
 export interface ApiError {
   message: string
   status: number
@@ -81,6 +84,18 @@ export class ApiClient {
     })
   }
 
+  async patch<T>(
+    endpoint: string,
+    data?: unknown,
+    options?: RequestInit
+  ): Promise<T> {
+    return this.request<T>(endpoint, {
+      ...options,
+      method: 'PATCH',
+      body: JSON.stringify(data),
+    })
+  }
+
   async delete<T>(endpoint: string, options?: RequestInit): Promise<T> {
     const url = `${this.baseURL}${endpoint}`
     
@@ -123,20 +138,232 @@ export class ApiClient {
 export const apiClient = new ApiClient()
 
 // Typed API methods
-import type { Event, CreateEvent, Participant, CreateParticipant, UpdateParticipantStatus } from './types'
+import type {
+  Event,
+  EventScope,
+  BatchEvents,
+  TrendingEvent,
+  CreateEvent,
+  NearbyEvent,
+  Certificate,
+  IssueCertificates,
+  IssuedCertificates,
+  Availability,
+  CapacityHold,
+  CreateCapacityHold,
+  Participant,
+  ParticipantListing,
+  ParticipantListOptions,
+  MetadataPatch,
+  ReferralSummary,
+  CreateParticipant,
+  Registration,
+  UpdateParticipantStatus,
+  SendBulkMail,
+  BulkMailReport,
+  Attendance,
+  ParticipantDiff,
+  RegistrationForm,
+  UpdateRegistrationForm,
+  SeatMap,
+  UpdateSeatMap,
+  AssignSeat,
+  SeatAssignment,
+  SeatOccupancy,
+  Organizer,
+  OrganizerSignup,
+  OrganizerLogin,
+  OrganizerToken,
+  OrganizerCalendar,
+  Webhook,
+  WebhookInput,
+  CreatedWebhook,
+  WebhookDelivery,
+  Device,
+  RegisterDevice,
+  RegisteredDevice,
+  DeviceHeartbeat,
+  CheckInBatch,
+  CheckInSyncReport,
+} from './types'
+
+/** Where the organizer's bearer token is kept */
+const ORGANIZER_TOKEN_KEY = 'organizerToken'
+
+export function rememberOrganizerToken(token: string | null) {
+  if (token) {
+    localStorage.setItem(ORGANIZER_TOKEN_KEY, token)
+  } else {
+    localStorage.removeItem(ORGANIZER_TOKEN_KEY)
+  }
+}
+
+function organizerAuth(): Record<string, string> {
+  const token = localStorage.getItem(ORGANIZER_TOKEN_KEY)
+  return token ? { Authorization: `Bearer ${token}` } : {}
+}
+
+export const organizersApi = {
+  /** Pass the returned token to `rememberOrganizerToken` */
+  signup: (data: OrganizerSignup) => apiClient.post<OrganizerToken>('/organizers/signup', data),
+  login: (data: OrganizerLogin) => apiClient.post<OrganizerToken>('/organizers/login', data),
+  me: () => apiClient.get<Organizer>('/organizers/me', { headers: organizerAuth() }),
+  /** The organizer's own calendar; 4 weeks from now unless `from`/`to` are given, at most 366 days */
+  availability: (organizerId: string, from?: string, to?: string) => {
+    const params = new URLSearchParams()
+    if (from) params.set('from', from)
+    if (to) params.set('to', to)
+    return apiClient.get<OrganizerCalendar>(`/organizers/${organizerId}/availability?${params}`, {
+      headers: organizerAuth(),
+    })
+  },
+  listWebhooks: () => apiClient.get<Webhook[]>('/organizers/me/webhooks', { headers: organizerAuth() }),
+  /** The returned secret is not shown again */
+  createWebhook: (data: WebhookInput) =>
+    apiClient.post<CreatedWebhook>('/organizers/me/webhooks', data, { headers: organizerAuth() }),
+  updateWebhook: (id: string, data: WebhookInput) =>
+    apiClient.put<Webhook>(`/organizers/me/webhooks/${id}`, data, { headers: organizerAuth() }),
+  deleteWebhook: (id: string) =>
+    apiClient.delete<void>(`/organizers/me/webhooks/${id}`, { headers: organizerAuth() }),
+  /** What the organizer's webhooks sent about a participant, newest first */
+  webhookDeliveries: (participantId: string) =>
+    apiClient.get<WebhookDelivery[]>(`/participants/${participantId}/webhook-deliveries`, { headers: organizerAuth() }),
+}
+
+/** Where a check-in tablet keeps its device token */
+const DEVICE_TOKEN_KEY = 'deviceToken'
+
+export function rememberDeviceToken(token: string | null) {
+  if (token) {
+    localStorage.setItem(DEVICE_TOKEN_KEY, token)
+  } else {
+    localStorage.removeItem(DEVICE_TOKEN_KEY)
+  }
+}
+
+function deviceAuth(): Record<string, string> {
+  const token = localStorage.getItem(DEVICE_TOKEN_KEY)
+  return token ? { 'X-Device-Token': token } : {}
+}
+
+export const devicesApi = {
+  /** Organizers only; pass the returned token to `rememberDeviceToken` on the tablet */
+  register: (data: RegisterDevice) =>
+    apiClient.post<RegisteredDevice>('/devices', data, { headers: organizerAuth() }),
+  heartbeat: (data: DeviceHeartbeat) =>
+    apiClient.post<Device>('/devices/heartbeat', data, { headers: deviceAuth() }),
+  /** Check-ins queued while offline; safe to resubmit */
+  syncCheckins: (data: CheckInBatch) =>
+    apiClient.post<CheckInSyncReport>('/checkins/batch', data, { headers: deviceAuth() }),
+}
 
 export const eventsApi = {
-  listEvents: () => apiClient.get<Event[]>('/events'),
+  /** With `tag`, only events carrying that tag; `scope` defaults to the server's setting */
+  listEvents: ({ tag, scope }: { tag?: string; scope?: EventScope } = {}) => {
+    const params = new URLSearchParams()
+    if (scope) params.set('scope', scope)
+    if (tag) params.set('tag', tag)
+    const query = params.toString()
+    return apiClient.get<Event[]>(query ? `/events?${query}` : '/events')
+  },
+  listEventsNear: (lat: number, lng: number, radiusKm?: number) =>
+    apiClient.get<NearbyEvent[]>(`/events?near=${lat},${lng}${radiusKm ? `&radius_km=${radiusKm}` : ''}`),
   getEvent: (id: string) => apiClient.get<Event>(`/events/${id}`),
-  createEvent: (data: CreateEvent) => apiClient.post<Event>('/events', data),
-  updateEvent: (id: string, data: CreateEvent) => apiClient.put<Event>(`/events/${id}`, data),
-  deleteEvent: (id: string) => apiClient.delete<void>(`/events/${id}`),
+  /** Up to 100 events in one request, one result per distinct id */
+  getEvents: (ids: string[]) => apiClient.post<BatchEvents>('/events/batch', { ids }),
+  /** Lags behind views by the server's flush interval */
+  getTrendingEvents: (limit = 10, windowHours = 24) =>
+    apiClient.get<TrendingEvent[]>(`/events/trending?limit=${limit}&window_hours=${windowHours}`),
+  getEventBySlug: (slug: string) => apiClient.get<Event>(`/events/by-slug/${encodeURIComponent(slug)}`),
+  /** Signed-in organizers only; they own the events they create */
+  createEvent: (data: CreateEvent) => apiClient.post<Event>('/events', data, { headers: organizerAuth() }),
+  updateEvent: (id: string, data: CreateEvent) =>
+    apiClient.put<Event>(`/events/${id}`, data, { headers: organizerAuth() }),
+  deleteEvent: (id: string) => apiClient.delete<void>(`/events/${id}`, { headers: organizerAuth() }),
+  publishEvent: (id: string) => apiClient.post<Event>(`/events/${id}/publish`),
+  listCertificates: (id: string) =>
+    apiClient.get<Certificate[]>(`/events/${id}/certificates`, { headers: organizerAuth() }),
+  issueCertificates: (id: string, data: IssueCertificates = {}) =>
+    apiClient.post<IssuedCertificates>(`/events/${id}/certificates`, data, { headers: organizerAuth() }),
+}
+
+/** Where the access token from registering for an event is kept */
+const participantTokenKey = (eventId: string) => `participantToken:${eventId}`
+
+export function rememberParticipantToken(eventId: string, token: string) {
+  localStorage.setItem(participantTokenKey(eventId), token)
 }
 
 export const participantsApi = {
-  listParticipants: (eventId: string) => apiClient.get<Participant[]>(`/events/${eventId}/participants`),
+  /**
+   * With `fuzzy`, only typo-tolerant matches, best first. `search` matches
+   * names (and emails, for organizers) by substring and cannot be combined
+   * with `fuzzy`; the server sends the unpaginated total as X-Total-Count.
+   */
+  listParticipants: (eventId: string, options: ParticipantListOptions = {}) => {
+    const token = localStorage.getItem(participantTokenKey(eventId))
+    const params = new URLSearchParams()
+    if (options.fuzzy) params.set('fuzzy', options.fuzzy)
+    if (options.status) params.set('status', options.status)
+    if (options.search) params.set('search', options.search)
+    if (options.limit !== undefined) params.set('limit', String(options.limit))
+    if (options.offset) params.set('offset', String(options.offset))
+    const query = params.toString()
+    return apiClient.get<ParticipantListing[]>(`/events/${eventId}/participants${query ? `?${query}` : ''}`, {
+      headers: token ? { 'X-Participant-Token': token } : {},
+    })
+  },
+  /** `to` defaults to now on the server */
+  getParticipantDiff: (eventId: string, from: string, to?: string) => {
+    const params = new URLSearchParams({ from })
+    if (to) params.set('to', to)
+    return apiClient.get<ParticipantDiff>(`/events/${eventId}/participants/diff?${params}`)
+  },
+  getAvailability: (eventId: string) => apiClient.get<Availability>(`/events/${eventId}/availability`),
+  /** Organizers only */
+  listCapacityHolds: (eventId: string) => apiClient.get<CapacityHold[]>(`/events/${eventId}/holds`),
+  /** Organizers only; 409 with `available` when fewer places are free */
+  createCapacityHold: (eventId: string, data: CreateCapacityHold) =>
+    apiClient.post<CapacityHold>(`/events/${eventId}/holds`, data),
+  /** Freed places go to waitlisted participants first */
+  releaseCapacityHold: (eventId: string, holdId: string) =>
+    apiClient.delete<void>(`/events/${eventId}/holds/${holdId}`),
+  getRegistrationForm: (eventId: string) => apiClient.get<RegistrationForm>(`/events/${eventId}/registration-form`),
+  /** Organizers only */
+  updateRegistrationForm: (eventId: string, data: UpdateRegistrationForm) =>
+    apiClient.put<RegistrationForm>(`/events/${eventId}/registration-form`, data),
+  getSeatMap: (eventId: string) => apiClient.get<SeatMap>(`/events/${eventId}/seat-map`),
+  /** Organizers only */
+  updateSeatMap: (eventId: string, data: UpdateSeatMap) => apiClient.put<SeatMap>(`/events/${eventId}/seat-map`, data),
+  /** Organizers only; 409 with the occupant's `participant_id` when the seat is taken */
+  assignSeat: (id: string, data: AssignSeat) => apiClient.post<SeatAssignment>(`/participants/${id}/seat`, data),
+  releaseSeat: (id: string) => apiClient.delete<void>(`/participants/${id}/seat`),
+  /** Organizers only */
+  getSeatOccupancy: (eventId: string) => apiClient.get<SeatOccupancy>(`/events/${eventId}/seats`),
   getParticipant: (id: string) => apiClient.get<Participant>(`/participants/${id}`),
-  createParticipant: (data: CreateParticipant) => apiClient.post<Participant>('/participants', data),
+  createParticipant: (data: CreateParticipant) => apiClient.post<Registration>('/participants', data),
   updateParticipantStatus: (id: string, data: UpdateParticipantStatus) => apiClient.put<Participant>(`/participants/${id}`, data),
+  cancelRegistration: (token: string) =>
+    apiClient.post<Participant>(`/registrations/cancel?token=${encodeURIComponent(token)}`),
   deleteParticipant: (id: string) => apiClient.delete<void>(`/participants/${id}`),
+  /** Queues the mails; poll `getBulkMail` for the outcome of each send */
+  sendBulkMail: (eventId: string, data: SendBulkMail) =>
+    apiClient.post<BulkMailReport>(`/events/${eventId}/participants/email`, data),
+  getBulkMail: (eventId: string, id: string) =>
+    apiClient.get<BulkMailReport>(`/events/${eventId}/participants/email/${id}`),
+  /** Merge patch: null removes a key. Organizers, or the participant with their access token */
+  updateParticipantMetadata: (id: string, eventId: string, patch: MetadataPatch) => {
+    const token = localStorage.getItem(participantTokenKey(eventId))
+    return apiClient.patch<Participant>(`/participants/${id}/metadata`, patch, {
+      headers: token ? { 'X-Participant-Token': token } : {},
+    })
+  },
+  /** Organizers, or a device registered for the participant's event */
+  checkIn: (id: string) =>
+    apiClient.post<Participant>(`/participants/${id}/checkin`, undefined, { headers: deviceAuth() }),
+  listCheckins: (eventId: string) =>
+    apiClient.get<Attendance>(`/events/${eventId}/checkins`, { headers: deviceAuth() }),
+  /** Organizers only */
+  getReferrals: (eventId: string, limit = 10) =>
+    apiClient.get<ReferralSummary>(`/events/${eventId}/referrals?limit=${limit}`),
 }
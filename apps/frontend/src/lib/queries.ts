@@ -6,8 +6,8 @@
  */
 
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
-import { eventsApi, participantsApi } from './api-client'
-import type { CreateEvent, CreateParticipant, UpdateParticipantStatus } from './types'
+import { eventsApi, participantsApi, rememberParticipantToken } from './api-client'
+import type { CreateEvent, CreateParticipant, ParticipantListOptions, UpdateParticipantStatus } from './types'
 
 // Query keys factory for type-safety and consistency
 export const queryKeys = {
@@ -17,6 +17,8 @@ export const queryKeys = {
     list: (filters?: unknown) => [...queryKeys.events.lists(), filters] as const,
     details: () => [...queryKeys.events.all, 'detail'] as const,
     detail: (id: string) => [...queryKeys.events.details(), id] as const,
+    batch: (ids: string[]) => [...queryKeys.events.all, 'batch', ids] as const,
+    trending: (limit: number, windowHours: number) => [...queryKeys.events.all, 'trending', limit, windowHours] as const,
   },
   participants: {
     all: ['participants'] as const,
@@ -44,6 +46,23 @@ export function useEvent(id: string) {
   })
 }
 
+/** Events for the homepage's trending list */
+export function useTrendingEvents(limit = 10, windowHours = 24) {
+  return useQuery({
+    queryKey: queryKeys.events.trending(limit, windowHours),
+    queryFn: () => eventsApi.getTrendingEvents(limit, windowHours),
+  })
+}
+
+/** Several events in one request instead of one `useEvent` each */
+export function useEventsByIds(ids: string[]) {
+  return useQuery({
+    queryKey: queryKeys.events.batch(ids),
+    queryFn: () => eventsApi.getEvents(ids),
+    enabled: ids.length > 0,
+  })
+}
+
 export function useCreateEvent() {
   const queryClient = useQueryClient()
 
@@ -68,6 +87,18 @@ export function useUpdateEvent() {
   })
 }
 
+export function usePublishEvent() {
+  const queryClient = useQueryClient()
+
+  return useMutation({
+    mutationFn: (id: string) => eventsApi.publishEvent(id),
+    onSuccess: (_, id) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.events.lists() })
+      queryClient.invalidateQueries({ queryKey: queryKeys.events.detail(id) })
+    },
+  })
+}
+
 export function useDeleteEvent() {
   const queryClient = useQueryClient()
 
@@ -81,10 +112,13 @@ export function useDeleteEvent() {
 
 // ===== PARTICIPANT QUERIES =====
 
-export function useParticipants(eventId: string) {
+export function useParticipants(eventId: string, options: ParticipantListOptions = {}) {
+  const filtered = Object.values(options).some((value) => value !== undefined && value !== '')
   return useQuery({
-    queryKey: queryKeys.participants.byEvent(eventId),
-    queryFn: () => participantsApi.listParticipants(eventId),
+    queryKey: filtered
+      ? [...queryKeys.participants.byEvent(eventId), options]
+      : queryKeys.participants.byEvent(eventId),
+    queryFn: () => participantsApi.listParticipants(eventId, options),
     enabled: !!eventId,
   })
 }
@@ -103,6 +137,7 @@ export function useCreateParticipant() {
   return useMutation({
     mutationFn: (data: CreateParticipant) => participantsApi.createParticipant(data),
     onSuccess: (data) => {
+      rememberParticipantToken(data.event_id, data.access_token)
       queryClient.invalidateQueries({ 
         queryKey: queryKeys.participants.byEvent(data.event_id) 
       })
@@ -14,6 +14,22 @@ export interface SSEEvent<T = unknown> {
   retry?: number
 }
 
+/** Final event of a stream the server closes, telling when to come back */
+export interface ReconnectAdvice {
+  type: 'reconnect'
+  reason: 'overloaded' | 'shutting_down'
+  status: number
+  retry_after_ms: number
+  timestamp: string
+}
+
+/** Sent to a resuming client whose missed events can no longer be replayed */
+export interface ResyncNotice {
+  type: 'resync'
+  last_event_id: number
+  timestamp: string
+}
+
 export interface SSEClientOptions {
   reconnect?: boolean
   reconnectInterval?: number
@@ -27,6 +43,9 @@ export class SSEClient {
   private retryCount = 0
   private listeners = new Map<string, Set<(event: SSEEvent) => void>>()
   private nativeHandlers = new Map<string, (event: MessageEvent) => void>()
+  private reconnectTimer: ReturnType<typeof setTimeout> | null = null
+  /** Id of the last event received, to resume after on reconnect */
+  private lastEventId: string | null = null
 
   constructor(endpoint: string, options: SSEClientOptions = {}) {
     this.url = endpoint ? `${SSE_BASE_URL}${endpoint}` : SSE_BASE_URL
@@ -44,7 +63,7 @@ export class SSEClient {
     }
 
     try {
-      this.eventSource = new EventSource(this.url)
+      this.eventSource = new EventSource(this.resumeUrl())
 
       this.eventSource.onopen = () => {
         console.log('[SSE] Connected to', this.url)
@@ -60,6 +79,12 @@ export class SSEClient {
         this.handleMessage(event)
       }
 
+      // The server closes streams when shedding load or shutting down and
+      // spreads clients over a jittered delay; that is not a failure
+      this.eventSource.addEventListener('reconnect', (event) => {
+        this.handleReconnectAdvice(event as MessageEvent)
+      })
+
       this.attachCustomListeners()
     } catch (error) {
       console.error('[SSE] Failed to connect:', error)
@@ -94,7 +119,22 @@ export class SSEClient {
     }
   }
 
+  /**
+   * The stream URL, resuming after the last event received. A new
+   * EventSource cannot send `Last-Event-ID`, so it goes in the query.
+   */
+  private resumeUrl(): string {
+    if (!this.lastEventId) {
+      return this.url
+    }
+    const separator = this.url.includes('?') ? '&' : '?'
+    return `${this.url}${separator}last_event_id=${encodeURIComponent(this.lastEventId)}`
+  }
+
   private handleMessage(event: MessageEvent): void {
+    if (event.lastEventId) {
+      this.lastEventId = event.lastEventId
+    }
     try {
       const data = JSON.parse(event.data)
       const sseEvent: SSEEvent = {
@@ -125,6 +165,25 @@ export class SSEClient {
     }
   }
 
+  private handleReconnectAdvice(event: MessageEvent): void {
+    let delay = this.options.reconnectInterval || 3000
+    try {
+      const advice: ReconnectAdvice = JSON.parse(event.data)
+      delay = advice.retry_after_ms
+      console.log(`[SSE] Server asked to reconnect in ${delay}ms (${advice.reason})`)
+    } catch (error) {
+      console.error('[SSE] Failed to parse reconnect advice:', error)
+    }
+
+    this.disconnect()
+    if (this.options.reconnect) {
+      this.reconnectTimer = setTimeout(() => {
+        this.reconnectTimer = null
+        this.connect()
+      }, delay)
+    }
+  }
+
   private handleError(): void {
     this.disconnect()
 
@@ -145,6 +204,10 @@ export class SSEClient {
   }
 
   disconnect(): void {
+    if (this.reconnectTimer) {
+      clearTimeout(this.reconnectTimer)
+      this.reconnectTimer = null
+    }
     if (this.eventSource) {
       this.eventSource.close()
       this.eventSource = null
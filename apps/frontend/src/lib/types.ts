@@ -5,15 +5,39 @@
 export interface Event {
   id: string
   title: string
+  slug: string | null
   description: string | null
   start_time: string
   end_time: string
   location: string | null
   max_participants: number | null
+  /** Drafts are listed to organizers only and closed for registration */
+  status: EventStatus
+  email_policy: EmailPolicy
+  participant_visibility: ParticipantVisibility
+  latitude: number | null
+  longitude: number | null
+  /** Youngest age allowed to register, on the day the event starts */
+  min_age: number | null
+  /** Recurring series this event is an occurrence of */
+  series_id: string | null
+  /** Start of the occurrence as scheduled by its series */
+  occurrence_start: string | null
   created_at: string
   updated_at: string
+  /** Lowercase tag names, sorted */
+  tags: string[]
 }
 
+export type EventStatus = 'draft' | 'published' | 'cancelled' | 'completed'
+
+/** Upcoming includes events still running; it is the usual default */
+export type EventScope = 'upcoming' | 'past' | 'all'
+
+export type EmailPolicy = 'strict' | 'allow_different_name' | 'unlimited'
+
+export type ParticipantVisibility = 'public' | 'participants' | 'organizers'
+
 export interface CreateEvent {
   title: string
   description?: string | null
@@ -21,9 +45,133 @@ export interface CreateEvent {
   end_time: string
   location?: string | null
   max_participants?: number | null
+  email_policy?: EmailPolicy
+  participant_visibility?: ParticipantVisibility
+  latitude?: number | null
+  longitude?: number | null
+  min_age?: number | null
+  tags?: string[]
+}
+
+/** Outcome for one id of a batch lookup; `event` is absent when not found */
+export interface BatchEventResult {
+  id: string
+  found: boolean
+  event?: Event
+}
+
+export interface BatchEvents {
+  results: BatchEventResult[]
+}
+
+export interface NearbyEvent extends Event {
+  distance_km: number
+}
+
+/** An upcoming event ranked by recent views and registrations */
+export interface TrendingEvent extends Event {
+  views: number
+  registrations: number
+  /** Registrations per hour over the window */
+  registration_velocity: number
+  score: number
+}
+
+export interface CertificateTemplate {
+  title?: string
+  body?: string
+}
+
+export interface IssueCertificates {
+  template?: CertificateTemplate
+  email?: boolean
+}
+
+export interface Certificate {
+  id: string
+  event_id: string
+  participant_id: string
+  participant_name: string
+  issued_at: string
+  download_url: string
+}
+
+export interface IssuedCertificates {
+  certificates: Certificate[]
+  emailed: number
+}
+
+export type ParticipantStatus = 'registered' | 'confirmed' | 'cancelled' | 'waitlisted' | 'maybe' | 'declined' | 'checked_in'
+
+export type StatusCounts = Record<ParticipantStatus, number>
+
+export interface ParticipantListOptions {
+  fuzzy?: string
+  status?: ParticipantStatus
+  search?: string
+  /** At most 200 */
+  limit?: number
+  offset?: number
+}
+
+/** Subject and body may use the email template placeholders, e.g. `{{name}}` */
+export interface SendBulkMail {
+  subject: string
+  body: string
+  /** Registered and confirmed participants when omitted */
+  statuses?: ParticipantStatus[]
+}
+
+export type BulkMailSendState = 'queued' | 'sending' | 'sent' | 'failed'
+
+export interface BulkMailRecipient {
+  participant_id: string
+  to_address: string
+  state: BulkMailSendState
+  error: string | null
+  attempted_at: string | null
+}
+
+export interface BulkMailReport {
+  id: string
+  event_id: string
+  subject: string
+  statuses: ParticipantStatus[]
+  requested_by: string
+  created_at: string
+  completed_at: string | null
+  queued: number
+  sent: number
+  failed: number
+  recipients: BulkMailRecipient[]
 }
 
-export type ParticipantStatus = 'registered' | 'confirmed' | 'cancelled' | 'waitlisted'
+/** Places taken and left; only registered and confirmed participants hold one */
+export interface Availability {
+  event_id: string
+  max_participants: number | null
+  taken: number
+  /** Places reserved by capacity holds */
+  held: number
+  /** Places left for registration */
+  remaining: number | null
+  counts: StatusCounts
+}
+
+/** Places reserved for invited guests; they are not open to registration */
+export interface CapacityHold {
+  id: string
+  event_id: string
+  label: string
+  seats: number
+  created_by: string
+  created_at: string
+}
+
+export interface CreateCapacityHold {
+  label: string
+  seats: number
+}
 
 export interface Participant {
   id: string
@@ -33,14 +181,368 @@ export interface Participant {
   status: ParticipantStatus
   registered_at: string
   updated_at: string
+  checked_in_at: string | null
+  /** Details such as dietary requirements or t-shirt size */
+  metadata: ParticipantMetadata
+}
+
+export type ParticipantMetadata = Record<string, string | number | boolean>
+
+/** Keys set to null are removed */
+export type MetadataPatch = Record<string, string | number | boolean | null>
+
+export interface CheckIn {
+  participant_id: string
+  name: string
+  checked_in_at: string
+}
+
+/** Live attendance; `expected` counts everyone holding a place */
+export interface Attendance {
+  event_id: string
+  expected: number
+  checked_in: number
+  checkins: CheckIn[]
+}
+
+/** A check-in tablet registered for one event */
+export interface Device {
+  id: string
+  name: string
+  event_id: string
+  /** Check-ins still waiting on the device to be synced */
+  pending_sync: number
+  app_version: string | null
+  /** Null before the first heartbeat */
+  last_seen_at: string | null
+  created_at: string
+}
+
+export interface RegisterDevice {
+  name: string
+  event_id: string
+}
+
+/** The token is only returned once, at registration */
+export interface RegisteredDevice extends Device {
+  token: string
+}
+
+export interface DeviceHeartbeat {
+  pending_sync: number
+  app_version?: string
+}
+
+/** A check-in recorded on a kiosk while offline */
+export interface OfflineCheckIn {
+  participant_id: string
+  /** Device time of the check-in */
+  checked_in_at: string
+}
+
+export interface CheckInBatch {
+  device_id?: string
+  checkins: OfflineCheckIn[]
+}
+
+export type CheckInSyncOutcome =
+  | 'checked_in'
+  | 'corrected'
+  | 'duplicate'
+  | 'not_found'
+  | 'wrong_event'
+  | 'no_place'
+  | 'event_completed'
+  | 'event_frozen'
+  | 'future_timestamp'
+
+/** `accepted` check-ins are on record and can be dropped from the queue */
+export interface CheckInSyncResult {
+  participant_id: string
+  outcome: CheckInSyncOutcome
+  accepted: boolean
+  checked_in_at: string | null
+}
+
+export interface CheckInSyncReport {
+  device_id: string
+  results: CheckInSyncResult[]
+}
+
+export type SeatSectionKind = 'table' | 'row'
+
+/** A table or row; seats are numbered from 1 */
+export interface SeatSection {
+  name: string
+  kind: SeatSectionKind
+  seats: number
+}
+
+/** `updated_at` is null while the event has no seat map */
+export interface SeatMap {
+  event_id: string
+  sections: SeatSection[]
+  updated_at: string | null
+}
+
+/** Replaces the map; assignments to seats it lacks are dropped, an empty list removes it */
+export interface UpdateSeatMap {
+  sections: SeatSection[]
+}
+
+export interface AssignSeat {
+  section: string
+  seat: number
+}
+
+export interface SeatAssignment {
+  participant_id: string
+  event_id: string
+  section: string
+  seat: number
+  assigned_at: string
+}
+
+export interface SeatedGuest {
+  seat: number
+  participant_id: string
+  name: string
+}
+
+export interface SectionOccupancy extends SeatSection {
+  occupied: number
+  guests: SeatedGuest[]
+}
+
+/** `unseated` counts participants holding a place without a seat */
+export interface SeatOccupancy {
+  event_id: string
+  capacity: number
+  occupied: number
+  unseated: number
+  sections: SectionOccupancy[]
+}
+
+export type ParticipantChangeKind = 'registered' | 'cancelled' | 'status_changed'
+
+export interface ParticipantChange {
+  kind: ParticipantChangeKind
+  participant_id: string
+  /** Null once the participant has been deleted */
+  name: string | null
+  email: string | null
+  /** Null for registrations */
+  from_status: ParticipantStatus | null
+  to_status: ParticipantStatus
+  at: string
+}
+
+/** Changes in `[from, to)`, oldest first; pass `to` as the next `from` */
+export interface ParticipantDiff {
+  event_id: string
+  from: string
+  to: string
+  changes: ParticipantChange[]
 }
 
 export interface CreateParticipant {
   event_id: string
   name: string
   email: string
+  privacy_policy_version: string
+  marketing_opt_in?: boolean
+  source?: RegistrationSource
+  referrer_code?: string
+  utm_campaign?: string
+  /** Invite code or id of the participant who invited the registrant */
+  invited_by?: string
+  metadata?: ParticipantMetadata
+  /** YYYY-MM-DD; required by events with a minimum age */
+  date_of_birth?: string
+  /** A day ahead when omitted; empty for no reminders */
+  reminders?: ReminderLeadTime[]
+  /** Answers to the event's registration form, keyed by field key */
+  answers?: Record<string, FormAnswer>
+}
+
+export type FormFieldKind = 'text' | 'select' | 'checkbox'
+
+/** A string for text and select fields, a boolean for checkboxes */
+export type FormAnswer = string | boolean
+
+export interface FormField {
+  id: string
+  event_id: string
+  key: string
+  label: string
+  kind: FormFieldKind
+  required: boolean
+  /** Choices of a select field */
+  options: string[]
+  position: number
+}
+
+export interface RegistrationForm {
+  event_id: string
+  fields: FormField[]
+}
+
+/** Replaces the form; answers of fields keeping their key and kind are kept */
+export interface UpdateRegistrationForm {
+  fields: Array<Pick<FormField, 'key' | 'label' | 'kind'> & Partial<Pick<FormField, 'required' | 'options'>>>
+}
+
+export type ReminderLeadTime = '24h' | '1h'
+
+export type RegistrationSource = 'web' | 'mobile' | 'kiosk'
+
+export interface ConsentReceipt {
+  privacy_policy_version: string
+  privacy_accepted_at: string
+  marketing_opt_in: boolean
+  withdrawal_token: string
+}
+
+export interface ReminderReceipt {
+  lead_times: ReminderLeadTime[]
+  /** Use with /reminders/:token to change the lead times later */
+  token: string
+}
+
+export interface Registration extends Participant {
+  consent: ConsentReceipt
+  reminders: ReminderReceipt
+  /** Sent as X-Participant-Token to see lists visible to participants */
+  access_token: string
+  /** Cancels the registration without any other credentials */
+  cancellation_token: string
+  /** Shared with friends, who register with it as `invited_by` */
+  invite_code: string
+}
+
+export interface Referrer {
+  participant_id: string
+  name: string
+  email: string
+  status: ParticipantStatus
+  referrals: number
+  /** Referrals that are confirmed or checked in */
+  confirmed: number
+  /** Registrations invited by this participant's invitees */
+  indirect: number
+}
+
+export interface ReferralSummary {
+  event_id: string
+  referred: number
+  top_referrers: Referrer[]
+}
+
+/** A participant as listed for an event; the email is shown to organizers only */
+export interface ParticipantListing extends Omit<Participant, 'email' | 'metadata'> {
+  /** Organizers only */
+  email?: string
+  /** Organizers only */
+  metadata?: ParticipantMetadata
 }
 
 export interface UpdateParticipantStatus {
   status: ParticipantStatus
 }
+
+export interface Organizer {
+  id: string
+  email: string
+  name: string
+  created_at: string
+  updated_at: string
+}
+
+export interface OrganizerSignup {
+  name: string
+  email: string
+  /** 10 to 128 characters */
+  password: string
+}
+
+export interface OrganizerLogin {
+  email: string
+  password: string
+}
+
+/** Send as `Authorization: Bearer <token>` until `expires_at` */
+export interface OrganizerToken {
+  token: string
+  token_type: 'Bearer'
+  expires_at: string
+  organizer: Organizer
+}
+
+/** A stretch of an organizer's calendar, clipped to the window asked for */
+export interface CalendarBlock {
+  kind: 'busy' | 'free'
+  start: string
+  end: string
+  /** Events taking up a busy block; empty for free ones */
+  event_ids: string[]
+}
+
+/** Busy and free blocks covering `from` to `to`; cancelled events are free */
+export interface OrganizerCalendar {
+  organizer_id: string
+  from: string
+  to: string
+  blocks: CalendarBlock[]
+}
+
+/** A status change a webhook fires on; any previous status when `from` is absent */
+export interface WebhookTransition {
+  from?: ParticipantStatus
+  to: ParticipantStatus
+}
+
+/** A CRM endpoint called when participants of the organizer's events change status */
+export interface Webhook {
+  id: string
+  organizer_id: string
+  name: string
+  url: string
+  transitions: WebhookTransition[]
+  /** Field name to template of its value, e.g. `{{email}}` or `{{metadata.member_no}}` */
+  fields: Record<string, string>
+  active: boolean
+  created_at: string
+  updated_at: string
+}
+
+export interface WebhookInput {
+  name: string
+  url: string
+  transitions: WebhookTransition[]
+  fields: Record<string, string>
+  active?: boolean
+}
+
+/** Only returned on creation; verifies the `X-Webhook-Signature` of deliveries */
+export interface CreatedWebhook extends Webhook {
+  secret: string
+}
+
+export type WebhookDeliveryState = 'queued' | 'sending' | 'delivered' | 'failed'
+
+export interface WebhookDelivery {
+  id: string
+  webhook_id: string
+  participant_id: string
+  event_id: string
+  from_status: ParticipantStatus | null
+  to_status: ParticipantStatus
+  payload: Record<string, string>
+  state: WebhookDeliveryState
+  attempts: number
+  next_attempt_at: string
+  response_status: number | null
+  last_error: string | null
+  created_at: string
+  delivered_at: string | null
+}
@@ -20,15 +20,21 @@ export function useEventSSE() {
       maxRetries: 5,
     })
 
+    // Invalidate all events and participants queries on any update
+    const invalidate = () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.events.all })
+      queryClient.invalidateQueries({ queryKey: queryKeys.participants.all })
+    }
+
     // Listen for all events and invalidate queries
     sseClient.on('message', (event) => {
       console.log('[SSE Hook] Received event:', event)
-
-      // Invalidate all events and participants queries on any update
-      queryClient.invalidateQueries({ queryKey: queryKeys.events.all })
-      queryClient.invalidateQueries({ queryKey: queryKeys.participants.all })
+      invalidate()
     })
 
+    // Changes missed while disconnected could not be replayed
+    sseClient.on('resync', invalidate)
+
     // Connect to stream
     sseClient.connect()
 
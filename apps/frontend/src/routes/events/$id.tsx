@@ -1,7 +1,14 @@
 import { createFileRoute, Link } from '@tanstack/react-router'
 import { useState } from 'react'
 import { useEvent, useParticipants, useCreateParticipant, useDeleteParticipant } from '../../lib/queries'
-import type { CreateParticipant, Participant } from '../../lib/types'
+import { PRIVACY_POLICY_VERSION } from '../../lib/api-client'
+import type { ParticipantListing } from '../../lib/types'
+
+/** The page's utm_campaign parameter, if it is a code the API accepts */
+function campaignFromUrl(): string | undefined {
+  const campaign = new URLSearchParams(window.location.search).get('utm_campaign')
+  return campaign && /^[A-Za-z0-9._-]{1,64}$/.test(campaign) ? campaign : undefined
+}
 
 export const Route = createFileRoute('/events/$id')({
   component: EventDetailComponent,
@@ -132,9 +139,11 @@ function EventDetailComponent() {
 
 function AddParticipantForm({ eventId, onSuccess }: { eventId: string; onSuccess: () => void }) {
   const createParticipant = useCreateParticipant()
-  const [formData, setFormData] = useState<Omit<CreateParticipant, 'event_id'>>({
+  const [formData, setFormData] = useState({
     name: '',
     email: '',
+    privacyAccepted: false,
+    marketingOptIn: false,
   })
 
   const handleSubmit = async (e: React.FormEvent) => {
@@ -145,8 +154,12 @@ function AddParticipantForm({ eventId, onSuccess }: { eventId: string; onSuccess
         event_id: eventId,
         name: formData.name,
         email: formData.email,
+        privacy_policy_version: PRIVACY_POLICY_VERSION,
+        marketing_opt_in: formData.marketingOptIn,
+        source: 'web',
+        utm_campaign: campaignFromUrl(),
       })
-      setFormData({ name: '', email: '' })
+      setFormData({ name: '', email: '', privacyAccepted: false, marketingOptIn: false })
       onSuccess()
     } catch (error) {
       console.error('Failed to add participant:', error)
@@ -207,6 +220,25 @@ function AddParticipantForm({ eventId, onSuccess }: { eventId: string; onSuccess
           />
         </div>
 
+        <label style={{ display: 'flex', gap: '0.5rem', alignItems: 'center' }}>
+          <input
+            type="checkbox"
+            required
+            checked={formData.privacyAccepted}
+            onChange={(e) => setFormData({ ...formData, privacyAccepted: e.target.checked })}
+          />
+          I accept the privacy policy (version {PRIVACY_POLICY_VERSION}) *
+        </label>
+
+        <label style={{ display: 'flex', gap: '0.5rem', alignItems: 'center' }}>
+          <input
+            type="checkbox"
+            checked={formData.marketingOptIn}
+            onChange={(e) => setFormData({ ...formData, marketingOptIn: e.target.checked })}
+          />
+          Send me news about upcoming events
+        </label>
+
         <div style={{ display: 'flex', gap: '1rem', marginTop: '0.5rem' }}>
           <button
             type="submit"
@@ -236,7 +268,7 @@ function AddParticipantForm({ eventId, onSuccess }: { eventId: string; onSuccess
   )
 }
 
-function ParticipantItem({ participant }: { participant: Participant }) {
+function ParticipantItem({ participant }: { participant: ParticipantListing }) {
   const deleteParticipant = useDeleteParticipant()
   const [showConfirm, setShowConfirm] = useState(false)
 
@@ -253,6 +285,9 @@ function ParticipantItem({ participant }: { participant: Participant }) {
     confirmed: '#10b981',
     cancelled: '#ef4444',
     waitlisted: '#f59e0b',
+    maybe: '#8b5cf6',
+    declined: '#9ca3af',
+    checked_in: '#047857',
   }
   
   const statusColor = statusColors[participant.status] || '#6b7280'
@@ -271,9 +306,11 @@ function ParticipantItem({ participant }: { participant: Participant }) {
         <div style={{ fontWeight: '500', marginBottom: '0.25rem' }}>
           {participant.name}
         </div>
-        <div style={{ fontSize: '0.875rem', color: '#666' }}>
-          {participant.email}
-        </div>
+        {participant.email && (
+          <div style={{ fontSize: '0.875rem', color: '#666' }}>
+            {participant.email}
+          </div>
+        )}
         <div style={{ 
           fontSize: '0.75rem', 
           marginTop: '0.25rem',
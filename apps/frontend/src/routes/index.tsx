@@ -1,7 +1,7 @@
 import { createFileRoute, Link } from '@tanstack/react-router'
 import { useState } from 'react'
-import { useEvents, useCreateEvent } from '../lib/queries'
-import type { CreateEvent } from '../lib/types'
+import { useEvents, useCreateEvent, usePublishEvent } from '../lib/queries'
+import type { CreateEvent, EmailPolicy, ParticipantVisibility } from '../lib/types'
 
 export const Route = createFileRoute('/')({
   component: IndexComponent,
@@ -113,6 +113,8 @@ function IndexComponent() {
 
 function CreateEventForm({ onSuccess }: { onSuccess: () => void }) {
   const createEvent = useCreateEvent()
+  const publishEvent = usePublishEvent()
+  const [publishNow, setPublishNow] = useState(true)
   const [formData, setFormData] = useState<CreateEvent>({
     title: '',
     description: '',
@@ -120,20 +122,28 @@ function CreateEventForm({ onSuccess }: { onSuccess: () => void }) {
     end_time: '',
     location: '',
     max_participants: undefined,
+    email_policy: 'strict',
+    participant_visibility: 'public',
   })
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault()
     
     try {
-      await createEvent.mutateAsync({
+      const event = await createEvent.mutateAsync({
         title: formData.title,
         description: formData.description || null,
         start_time: new Date(formData.start_time).toISOString(),
         end_time: new Date(formData.end_time).toISOString(),
         location: formData.location || null,
         max_participants: formData.max_participants || null,
+        email_policy: formData.email_policy,
+        participant_visibility: formData.participant_visibility,
       })
+      // New events start as drafts
+      if (publishNow) {
+        await publishEvent.mutateAsync(event.id)
+      }
       onSuccess()
     } catch (error) {
       console.error('Failed to create event:', error)
@@ -272,8 +282,57 @@ function CreateEventForm({ onSuccess }: { onSuccess: () => void }) {
               }}
             />
           </div>
+
+          <div>
+            <label style={{ display: 'block', marginBottom: '0.25rem', fontWeight: '500' }}>
+              Duplicate Emails
+            </label>
+            <select
+              value={formData.email_policy}
+              onChange={(e) => setFormData({ ...formData, email_policy: e.target.value as EmailPolicy })}
+              style={{
+                width: '100%',
+                padding: '0.5rem',
+                border: '1px solid #ddd',
+                borderRadius: '4px',
+                fontSize: '1rem',
+              }}
+            >
+              <option value="strict">One registration per email</option>
+              <option value="allow_different_name">Allow for different names</option>
+              <option value="unlimited">Unlimited</option>
+            </select>
+          </div>
+
+          <div>
+            <label style={{ display: 'block', marginBottom: '0.25rem', fontWeight: '500' }}>
+              Participant List
+            </label>
+            <select
+              value={formData.participant_visibility}
+              onChange={(e) =>
+                setFormData({ ...formData, participant_visibility: e.target.value as ParticipantVisibility })
+              }
+              style={{
+                width: '100%',
+                padding: '0.5rem',
+                border: '1px solid #ddd',
+                borderRadius: '4px',
+                fontSize: '1rem',
+              }}
+            >
+              <option value="public">Public</option>
+              <option value="participants">Participants only</option>
+              <option value="organizers">Organizers only</option>
+            </select>
+          </div>
         </div>
 
+        <label style={{ display: 'flex', gap: '0.5rem', alignItems: 'center' }}>
+          <input type="checkbox" checked={publishNow} onChange={(e) => setPublishNow(e.target.checked)} />
+          Publish now (otherwise the event stays a draft, hidden from the public list)
+        </label>
+
         <div style={{ display: 'flex', gap: '1rem', marginTop: '0.5rem' }}>
           <button
             type="submit"